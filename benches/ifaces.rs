@@ -0,0 +1,311 @@
+//! Enumeration benchmarks.
+//!
+//! `map_ifaddrs/50` maps a synthetic `getifaddrs` list of 50 interfaces, each
+//! with a link-layer, an IPv4 and an IPv6 entry, so it is stable across
+//! machines. `ifaces/live` enumerates the host it runs on.
+//!
+//! Before measuring, the allocations made by one pass over the fixture are
+//! printed. On the 50-interface fixture (150 entries, all returned):
+//!
+//! | revision                              | allocations | time    |
+//! |---------------------------------------|-------------|---------|
+//! | growing `Vec`, two copies of the name | 312         | ~209 µs |
+//! | sized `Vec`, one copy of the name     | 206         | ~193 µs |
+//! | per-interface `OsSpecific`            | 257         | ~164 µs |
+//!
+//! What is left is one `String` per returned entry (150), one per distinct
+//! name for the link cache (50), one shared `OsSpecific` per interface (50),
+//! the result `Vec` and the cache's table growing. Most of the time goes to
+//! `if_nametoindex` for the 50 names, which is now called once per name.
+//!
+//! `adapter_buffer` runs the Windows sizing loop against a stand-in for
+//! `GetAdaptersAddresses` that needs a list of 2, 14 or 256 KiB, and prints
+//! the allocations and calls of one pass for a buffer starting at the old
+//! 15 KiB floor, one starting empty and one kept from the call before.
+//! Starting empty always takes one allocation of the exact size and two
+//! calls; the floor saves the sizing call for lists below 15 KiB, pays for
+//! 15 KiB whatever the list needs, and takes two allocations and two calls
+//! above it. With the buffer kept per thread either way only matters for
+//! the first call, which is why there is no floor; a kept buffer takes one
+//! call and no allocation.
+//!
+//! `ifaces/live` also reports allocations of 4 KiB and up. On Windows the
+//! adapter buffer is kept per thread, so after the first call that count
+//! stays at zero. On Linux it does not: each netlink request allocates its
+//! receive buffer, and the sysfs reads for the SR-IOV and carrier fields
+//! add more.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LARGE_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+const LARGE: usize = 4 * 1024;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        if layout.size() >= LARGE {
+            LARGE_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        if new_size >= LARGE {
+            LARGE_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    black_box(f());
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn large_allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = LARGE_ALLOCATIONS.load(Ordering::Relaxed);
+    black_box(f());
+    LARGE_ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+#[cfg(unix)]
+mod fixture {
+    use std::ffi::CString;
+    use std::{mem, ptr};
+
+    use ifaces::ffi::ifaddrs;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    use ifaces::ffi::union_ifa_ifu;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const LINK: libc::c_int = libc::AF_PACKET;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    const LINK: libc::c_int = libc::AF_LINK;
+
+    /// An owned `getifaddrs`-shaped list.
+    ///
+    /// Entries are boxed so the pointers between them stay put while the
+    /// vectors grow.
+    #[allow(clippy::vec_box)]
+    pub struct Fixture {
+        _names: Vec<CString>,
+        _addrs: Vec<Box<libc::sockaddr_storage>>,
+        nodes: Vec<Box<ifaddrs>>,
+    }
+
+    impl Fixture {
+        pub fn new(count: usize) -> Fixture {
+            let mut fixture = Fixture {
+                _names: Vec::new(),
+                _addrs: Vec::new(),
+                nodes: Vec::new(),
+            };
+
+            // Linux lists every link-layer entry first, then IPv4, then IPv6.
+            for family in &[LINK, libc::AF_INET, libc::AF_INET6] {
+                for i in 0..count {
+                    let name = CString::new(format!("bench{}", i)).unwrap();
+                    let (addr, mask, broadcast) = match *family {
+                        libc::AF_INET => (
+                            v4([10, 0, i as u8, 1]),
+                            v4([255, 255, 255, 0]),
+                            v4([10, 0, i as u8, 255]),
+                        ),
+                        libc::AF_INET6 => {
+                            let mut addr = [0; 16];
+                            addr[0] = 0xfd;
+                            addr[15] = i as u8;
+                            (v6(addr), v6([0xff; 16]), empty())
+                        }
+                        _ => (link(), empty(), empty()),
+                    };
+                    // Zeroed for the fields only some platforms have.
+                    let mut node: Box<ifaddrs> = Box::new(unsafe { mem::zeroed() });
+                    node.ifa_name = name.as_ptr() as *mut _;
+                    node.ifa_flags = (libc::IFF_UP | libc::IFF_BROADCAST) as _;
+                    node.ifa_addr = fixture.keep(addr);
+                    node.ifa_netmask = fixture.keep(mask);
+                    let broadcast = fixture.keep(broadcast);
+                    #[cfg(any(target_os = "linux", target_os = "android"))]
+                    {
+                        node.ifa_ifu = union_ifa_ifu {
+                            data: broadcast as *mut _,
+                        };
+                    }
+                    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+                    {
+                        node.ifa_dstaddr = broadcast;
+                    }
+                    fixture._names.push(name);
+                    fixture.nodes.push(node);
+                }
+            }
+
+            for i in 1..fixture.nodes.len() {
+                let next = &mut *fixture.nodes[i] as *mut ifaddrs;
+                fixture.nodes[i - 1].ifa_next = next;
+            }
+            fixture
+        }
+
+        pub fn head(&self) -> *const ifaddrs {
+            &*self.nodes[0]
+        }
+
+        fn keep(&mut self, addr: Option<libc::sockaddr_storage>) -> *mut libc::sockaddr {
+            match addr {
+                Some(addr) => {
+                    let mut addr = Box::new(addr);
+                    let ptr = &mut *addr as *mut _ as *mut libc::sockaddr;
+                    self._addrs.push(addr);
+                    ptr
+                }
+                None => ptr::null_mut(),
+            }
+        }
+    }
+
+    fn empty() -> Option<libc::sockaddr_storage> {
+        None
+    }
+
+    fn link() -> Option<libc::sockaddr_storage> {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        storage.ss_family = LINK as _;
+        Some(storage)
+    }
+
+    fn v4(octets: [u8; 4]) -> Option<libc::sockaddr_storage> {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let sin = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in) };
+        sin.sin_family = libc::AF_INET as _;
+        sin.sin_addr.s_addr = u32::from_ne_bytes(octets);
+        Some(storage)
+    }
+
+    fn v6(octets: [u8; 16]) -> Option<libc::sockaddr_storage> {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let sin6 = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6) };
+        sin6.sin6_family = libc::AF_INET6 as _;
+        sin6.sin6_addr.s6_addr = octets;
+        Some(storage)
+    }
+}
+
+#[cfg(unix)]
+fn map_ifaddrs(c: &mut Criterion) {
+    let fixture = fixture::Fixture::new(50);
+    println!(
+        "map_ifaddrs/50: {} allocations per call",
+        allocations(|| unsafe { ifaces::ffi::map_ifaddrs(fixture.head()) })
+    );
+    c.bench_function("map_ifaddrs/50", |b| {
+        b.iter(|| unsafe { ifaces::ffi::map_ifaddrs(black_box(fixture.head())) })
+    });
+}
+
+#[cfg(not(unix))]
+fn map_ifaddrs(_: &mut Criterion) {}
+
+/// `GetAdaptersAddresses` for an adapter list of `needed` bytes, asking for
+/// a bigger buffer until it is handed one, and counting its calls.
+#[cfg(windows)]
+fn adapter_list(
+    needed: u32,
+    calls: &std::cell::Cell<usize>,
+) -> impl FnMut(*mut u8, &mut u32) -> u32 + '_ {
+    // ERROR_SUCCESS and ERROR_BUFFER_OVERFLOW.
+    move |_, length| {
+        calls.set(calls.get() + 1);
+        if *length < needed {
+            *length = needed;
+            111
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(windows)]
+fn adapter_buffer(c: &mut Criterion) {
+    use std::cell::Cell;
+
+    use ifaces::ffi::fill_adapter_buffer;
+
+    // The initial size the sizing call replaced.
+    const FLOOR: usize = 15 * 1024;
+
+    let calls = Cell::new(0);
+    let per_call = |start: &mut dyn FnMut() -> Vec<u64>, needed: u32| {
+        calls.set(0);
+        let allocations = allocations(|| {
+            let mut buffer = start();
+            fill_adapter_buffer(&mut buffer, adapter_list(needed, &calls)).unwrap();
+            buffer
+        });
+        (allocations, calls.get())
+    };
+    for kib in [2, 14, 256] {
+        let needed = kib * 1024;
+        let mut reused = vec![0; needed as usize / 8];
+        let floor = per_call(&mut || vec![0; FLOOR / 8], needed);
+        let sizing = per_call(&mut Vec::new, needed);
+        let reuse = per_call(&mut || std::mem::take(&mut reused), needed);
+        println!(
+            "adapter_buffer/{} KiB: (allocations, calls) with the floor {:?}, \
+             with the sizing call {:?}, reused {:?}",
+            kib, floor, sizing, reuse
+        );
+    }
+
+    c.bench_function("adapter_buffer/floor/2 KiB", |b| {
+        b.iter(|| {
+            let mut buffer = vec![0; FLOOR / 8];
+            fill_adapter_buffer(&mut buffer, adapter_list(2048, &calls)).unwrap();
+            buffer
+        })
+    });
+    c.bench_function("adapter_buffer/sizing/2 KiB", |b| {
+        b.iter(|| {
+            let mut buffer = Vec::new();
+            fill_adapter_buffer(&mut buffer, adapter_list(2048, &calls)).unwrap();
+            buffer
+        })
+    });
+}
+
+#[cfg(not(windows))]
+fn adapter_buffer(_: &mut Criterion) {}
+
+fn live(c: &mut Criterion) {
+    let mut first = 0;
+    let large = large_allocations(|| first = allocations(ifaces::ifaces));
+    println!(
+        "ifaces/live: {} allocations on the first call, {} of them large",
+        first, large
+    );
+    println!(
+        "ifaces/live: {} large allocations in steady state",
+        large_allocations(ifaces::ifaces)
+    );
+    c.bench_function("ifaces/live", |b| b.iter(ifaces::ifaces));
+}
+
+criterion_group!(benches, map_ifaddrs, adapter_buffer, live);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+//! Drive a `ChangeWatcher` from a `poll` loop, without a thread of its
+//! own, and print each change until interrupted.
+//!
+//! `cargo run --example poll_changes --features watch`
+//!
+//! Then add or remove an address, or bring a link up or down, in another
+//! terminal.
+
+#[cfg(all(unix, not(any(target_os = "redox", target_os = "espidf"))))]
+fn main() -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut watcher = ifaces::ChangeWatcher::new()?;
+    let mut pollfd = libc::pollfd {
+        fd: watcher.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    loop {
+        if unsafe { libc::poll(&mut pollfd, 1, -1) } < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err);
+        }
+        for event in watcher.drain_events()? {
+            println!("{:?}", event);
+        }
+    }
+}
+
+#[cfg(not(all(unix, not(any(target_os = "redox", target_os = "espidf")))))]
+fn main() {
+    eprintln!("poll_changes: this example uses poll(2)");
+}
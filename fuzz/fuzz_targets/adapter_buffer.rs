@@ -0,0 +1,26 @@
+//! The `GetAdaptersAddresses` buffer walker over any bytes, with the
+//! pointers in them read as offsets into the input. Windows only.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    #[cfg(windows)]
+    {
+        // Copied into u64s so the structures in it can be aligned.
+        let mut words = vec![0u64; data.len().div_ceil(8)];
+        let bytes =
+            unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, data.len()) };
+        bytes.copy_from_slice(data);
+        let (interfaces, warnings) = ifaces::ffi::map_adapter_buffer(bytes, 0);
+        for interface in &interfaces {
+            let _ = format!("{:?}", interface);
+        }
+        for warning in &warnings {
+            let _ = warning.to_string();
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = data;
+});
@@ -0,0 +1,11 @@
+//! `sockaddr_from_bytes`, which `getifaddrs` entries go through, over any
+//! bytes.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    #[cfg(not(windows))]
+    let _ = ifaces::ffi::sockaddr_from_bytes(data);
+});
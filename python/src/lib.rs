@@ -0,0 +1,212 @@
+//! The `interfaces` Python module.
+//!
+//! Enumerating and waiting release the GIL. `io::Error`s from the OS become
+//! `OSError`s with `errno` set, which Python turns into the matching
+//! subclass, see [`os_error`].
+
+use std::io;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use ifaces::{ChangeHint, ChangeWatcher, InterfaceFlags, Kind, NextHop};
+use pyo3::exceptions::{PyNotImplementedError, PyOSError, PyValueError};
+use pyo3::prelude::*;
+
+const FLAG_NAMES: &[(InterfaceFlags, &str)] = &[
+    (InterfaceFlags::UP, "up"),
+    (InterfaceFlags::BROADCAST, "broadcast"),
+    (InterfaceFlags::LOOPBACK, "loopback"),
+    (InterfaceFlags::POINT_TO_POINT, "point_to_point"),
+    (InterfaceFlags::RUNNING, "running"),
+    (InterfaceFlags::MULTICAST, "multicast"),
+    (InterfaceFlags::PROMISC, "promisc"),
+];
+
+/// One entry of `ifaces()`. Addresses are `ipaddress` objects.
+#[pyclass(name = "Interface", module = "interfaces", frozen, eq, get_all)]
+#[derive(PartialEq, Clone)]
+struct PyInterface {
+    name: String,
+    index: Option<u32>,
+    /// `"ipv4"`, `"ipv6"`, `"link"`, `"packet"`, `"can"`, `"bluetooth"` or
+    /// `"unknown"`.
+    kind: &'static str,
+    addr: Option<IpAddr>,
+    netmask: Option<IpAddr>,
+    prefix_len: Option<u8>,
+    broadcast: Option<IpAddr>,
+    destination: Option<IpAddr>,
+    /// The IPv6 scope id, 0 when there is none.
+    scope_id: u32,
+    flags: Vec<&'static str>,
+    mac: Option<String>,
+    mtu: Option<u32>,
+}
+
+impl From<ifaces::Interface> for PyInterface {
+    fn from(iface: ifaces::Interface) -> PyInterface {
+        let (broadcast, destination) = match iface.hop {
+            Some(NextHop::Broadcast(addr)) => (Some(addr.ip()), None),
+            Some(NextHop::Destination(addr)) => (None, Some(addr.ip())),
+            None => (None, None),
+        };
+        PyInterface {
+            prefix_len: iface.prefix_len(),
+            index: iface.index,
+            kind: match iface.kind {
+                Kind::Ipv4 => "ipv4",
+                Kind::Ipv6 => "ipv6",
+                Kind::Link => "link",
+                Kind::Packet => "packet",
+                Kind::Can => "can",
+                Kind::Bluetooth => "bluetooth",
+                Kind::Unknown => "unknown",
+            },
+            addr: iface.addr.map(|addr| addr.ip()),
+            netmask: iface.mask.map(|mask| mask.ip()),
+            broadcast,
+            destination,
+            scope_id: match iface.addr {
+                Some(std::net::SocketAddr::V6(addr)) => addr.scope_id(),
+                _ => 0,
+            },
+            flags: FLAG_NAMES
+                .iter()
+                .filter(|(flag, _)| iface.flags.contains(*flag))
+                .map(|(_, name)| *name)
+                .collect(),
+            mac: iface.mac.map(|mac| mac.to_string()),
+            mtu: iface.mtu,
+            name: iface.name,
+        }
+    }
+}
+
+#[pymethods]
+impl PyInterface {
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let fields = [
+            ("name", self.name.clone().into_pyobject(py)?.into_any()),
+            ("index", self.index.into_pyobject(py)?),
+            ("kind", self.kind.into_pyobject(py)?.into_any()),
+            ("addr", self.addr.into_pyobject(py)?),
+            ("prefix_len", self.prefix_len.into_pyobject(py)?),
+            ("flags", self.flags.clone().into_pyobject(py)?),
+            ("mac", self.mac.clone().into_pyobject(py)?),
+        ];
+        let mut repr = Vec::new();
+        for (name, value) in fields {
+            repr.push(format!("{}={}", name, value.repr()?));
+        }
+        Ok(format!("Interface({})", repr.join(", ")))
+    }
+}
+
+/// `OSError(errno, strerror)` for errors from the OS, so that e.g. `EACCES`
+/// comes out as `PermissionError`. Invalid arguments are `ValueError`, and
+/// what the platform cannot do is `NotImplementedError`.
+fn os_error(err: io::Error) -> PyErr {
+    match (err.raw_os_error(), err.kind()) {
+        (Some(errno), _) => PyOSError::new_err((errno, err.to_string())),
+        (None, io::ErrorKind::InvalidInput) => PyValueError::new_err(err.to_string()),
+        (None, io::ErrorKind::Unsupported) => PyNotImplementedError::new_err(err.to_string()),
+        (None, _) => err.into(),
+    }
+}
+
+/// A timeout in seconds.
+fn duration(seconds: f64) -> PyResult<Duration> {
+    Duration::try_from_secs_f64(seconds).map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+fn hint_name(hint: ChangeHint) -> &'static str {
+    match hint {
+        ChangeHint::Addresses => "addresses",
+        ChangeHint::Links => "links",
+        ChangeHint::Unknown => "unknown",
+    }
+}
+
+#[pyfunction(name = "ifaces")]
+fn all(py: Python<'_>) -> PyResult<Vec<PyInterface>> {
+    let ifaces = py.allow_threads(ifaces::ifaces).map_err(os_error)?;
+    Ok(ifaces.into_iter().map(PyInterface::from).collect())
+}
+
+/// The entry of the interface the default route goes over, preferring
+/// IPv4, or `None` without a default route. `NotImplementedError` where
+/// routes cannot be read.
+#[pyfunction]
+fn default_interface(py: Python<'_>) -> PyResult<Option<PyInterface>> {
+    let iface = py
+        .allow_threads(ifaces::default_interface)
+        .map_err(os_error)?;
+    Ok(iface.map(PyInterface::from))
+}
+
+/// Block until an interface called `name` exists, and return its first
+/// entry, or `None` once `timeout` seconds pass.
+#[pyfunction]
+fn wait_for_interface(py: Python<'_>, name: &str, timeout: f64) -> PyResult<Option<PyInterface>> {
+    let timeout = duration(timeout)?;
+    let iface = py
+        .allow_threads(|| ifaces::wait_for_interface(name, timeout))
+        .map_err(os_error)?;
+    Ok(iface.map(PyInterface::from))
+}
+
+/// A subscription to network configuration changes.
+///
+/// `wait()` blocks with the GIL released. On unix `fileno()` gives the
+/// socket, for `selectors` or an asyncio loop, with `drain()` called when
+/// it is readable. Hints are `"addresses"`, `"links"` or `"unknown"`; only
+/// hints, enumerate to find out what changed.
+#[pyclass(name = "Watcher", module = "interfaces")]
+struct PyWatcher {
+    watcher: ChangeWatcher,
+}
+
+#[pymethods]
+impl PyWatcher {
+    /// Subscribe. `NotImplementedError` on platforms without change
+    /// notifications.
+    #[new]
+    fn new() -> PyResult<PyWatcher> {
+        let watcher = ChangeWatcher::new().map_err(os_error)?;
+        Ok(PyWatcher { watcher })
+    }
+
+    /// Block until something changes and return the hint, or `None` once
+    /// `timeout` seconds pass.
+    #[pyo3(signature = (timeout=None))]
+    fn wait(&mut self, py: Python<'_>, timeout: Option<f64>) -> PyResult<Option<&'static str>> {
+        let timeout = timeout.map(duration).transpose()?;
+        let watcher = &mut self.watcher;
+        let hint = py
+            .allow_threads(|| watcher.wait(timeout))
+            .map_err(os_error)?;
+        Ok(hint.map(hint_name))
+    }
+
+    /// The hints queued since the last call, without blocking.
+    fn drain(&mut self) -> PyResult<Vec<&'static str>> {
+        let hints = self.watcher.drain_events().map_err(os_error)?;
+        Ok(hints.into_iter().map(hint_name).collect())
+    }
+
+    #[cfg(unix)]
+    fn fileno(&self) -> i32 {
+        use std::os::unix::io::AsRawFd;
+        self.watcher.as_raw_fd()
+    }
+}
+
+#[pymodule]
+fn interfaces(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyInterface>()?;
+    m.add_class::<PyWatcher>()?;
+    m.add_function(wrap_pyfunction!(all, m)?)?;
+    m.add_function(wrap_pyfunction!(default_interface, m)?)?;
+    m.add_function(wrap_pyfunction!(wait_for_interface, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,218 @@
+"""Tests of the `interfaces` module against the machine running them.
+
+Build the module first, `maturin develop`, then run `pytest tests`.
+"""
+
+import errno
+import ipaddress
+import os
+import shutil
+import subprocess
+import sys
+import threading
+import time
+
+import pytest
+
+import interfaces
+
+KINDS = {"ipv4", "ipv6", "link", "packet", "can", "bluetooth", "unknown"}
+FLAGS = {"up", "broadcast", "loopback", "point_to_point", "running", "multicast", "promisc"}
+HINTS = {"addresses", "links", "unknown"}
+
+
+@pytest.fixture(scope="module")
+def entries():
+    return interfaces.ifaces()
+
+
+@pytest.fixture
+def no_free_fds():
+    """Use up every file descriptor the process may open."""
+    resource = pytest.importorskip("resource")
+    soft, hard = resource.getrlimit(resource.RLIMIT_NOFILE)
+    resource.setrlimit(resource.RLIMIT_NOFILE, (64, hard))
+    fds = []
+    try:
+        while True:
+            fds.append(os.dup(0))
+    except OSError as err:
+        assert err.errno == errno.EMFILE
+    try:
+        yield
+    finally:
+        for fd in fds:
+            os.close(fd)
+        resource.setrlimit(resource.RLIMIT_NOFILE, (soft, hard))
+
+
+def loopback(entries):
+    return next(e for e in entries if "loopback" in e.flags and e.kind == "ipv4")
+
+
+def test_entries_are_interfaces(entries):
+    assert entries
+    for entry in entries:
+        assert isinstance(entry, interfaces.Interface)
+        assert isinstance(entry.name, str) and entry.name
+        assert entry.kind in KINDS
+        assert set(entry.flags) <= FLAGS
+
+
+def test_addresses_are_ipaddress_objects(entries):
+    for entry in entries:
+        for addr in (entry.addr, entry.netmask, entry.broadcast, entry.destination):
+            assert addr is None or isinstance(addr, (ipaddress.IPv4Address, ipaddress.IPv6Address))
+        if entry.kind == "ipv4":
+            assert isinstance(entry.addr, ipaddress.IPv4Address)
+        if entry.kind == "ipv6":
+            assert isinstance(entry.addr, ipaddress.IPv6Address)
+
+
+def test_loopback_is_listed(entries):
+    lo = loopback(entries)
+    assert lo.addr == ipaddress.IPv4Address("127.0.0.1")
+    assert lo.netmask == ipaddress.IPv4Address("255.0.0.0")
+    assert lo.prefix_len == 8
+    assert "up" in lo.flags
+
+
+def test_link_local_addresses_are_scoped(entries):
+    scoped = [e for e in entries if e.kind == "ipv6" and e.addr.is_link_local]
+    if not scoped:
+        pytest.skip("no IPv6 link-local addresses")
+    for entry in scoped:
+        assert entry.scope_id == entry.index
+
+
+def test_entries_are_frozen(entries):
+    with pytest.raises(AttributeError):
+        entries[0].name = "renamed0"
+
+
+def test_entries_compare_and_print(entries):
+    assert loopback(interfaces.ifaces()) == loopback(entries)
+    assert repr(entries[0]).startswith("Interface(name=")
+
+
+def test_mac_addresses_are_colon_separated(entries):
+    for entry in entries:
+        if entry.mac is not None:
+            assert len(entry.mac.split(":")) == 6
+
+
+def default_interface():
+    try:
+        return interfaces.default_interface()
+    except NotImplementedError:
+        pytest.skip("no routes on this platform")
+
+
+def test_default_interface_is_one_of_the_entries(entries):
+    found = default_interface()
+    if found is None:
+        pytest.skip("no default route")
+    assert found.index in {e.index for e in entries}
+    assert "loopback" not in found.flags
+
+
+def test_wait_for_an_existing_interface(entries):
+    lo = loopback(entries)
+    found = interfaces.wait_for_interface(lo.name, timeout=1.0)
+    assert found is not None and found.name == lo.name
+
+
+def test_wait_for_a_missing_interface_times_out():
+    assert interfaces.wait_for_interface("nosuchif0", timeout=0.2) is None
+
+
+def test_invalid_names_are_value_errors():
+    with pytest.raises(ValueError):
+        interfaces.wait_for_interface("", timeout=0.1)
+    with pytest.raises(ValueError):
+        interfaces.wait_for_interface("x" * 300, timeout=0.1)
+
+
+def test_negative_timeouts_are_value_errors():
+    with pytest.raises(ValueError):
+        interfaces.wait_for_interface("lo", timeout=-1.0)
+
+
+def blocks_without_the_gil(wait):
+    """Whether Python code runs while `wait` blocks for a second."""
+    thread = threading.Thread(target=wait)
+    start = time.monotonic()
+    thread.start()
+    time.sleep(0.05)
+    sum(range(100000))
+    elapsed = time.monotonic() - start
+    thread.join()
+    return elapsed < 0.9
+
+
+def test_waiting_releases_the_gil():
+    assert blocks_without_the_gil(lambda: interfaces.wait_for_interface("nosuchif0", timeout=1.0))
+
+
+def test_os_errors_carry_errno(no_free_fds):
+    with pytest.raises(OSError) as raised:
+        interfaces.ifaces()
+    assert raised.value.errno == errno.EMFILE
+    assert raised.value.strerror
+
+
+def make_watcher():
+    try:
+        return interfaces.Watcher()
+    except NotImplementedError:
+        pytest.skip("no change notifications on this platform")
+
+
+def test_watcher_times_out_or_hints():
+    watcher = make_watcher()
+    assert watcher.wait(0.1) in HINTS | {None}
+    assert set(watcher.drain()) <= HINTS
+
+
+def test_watcher_rejects_negative_timeouts():
+    watcher = make_watcher()
+    with pytest.raises(ValueError):
+        watcher.wait(-1.0)
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="no file descriptor on Windows")
+def test_watcher_has_a_file_descriptor():
+    watcher = make_watcher()
+    assert watcher.fileno() >= 0
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="no file descriptor limit on Windows")
+def test_watcher_errors_carry_errno(no_free_fds):
+    with pytest.raises(OSError) as raised:
+        interfaces.Watcher()
+    assert raised.value.errno == errno.EMFILE
+
+
+def test_watcher_wait_releases_the_gil():
+    watcher = make_watcher()
+    assert blocks_without_the_gil(lambda: watcher.wait(1.0))
+
+
+@pytest.mark.skipif(
+    not sys.platform.startswith("linux") or os.geteuid() != 0 or shutil.which("ip") is None,
+    reason="needs root and iproute2 on Linux",
+)
+def test_watcher_sees_new_links():
+    watcher = make_watcher()
+    watcher.drain()
+    for kind in ("dummy", "ifb"):
+        added = subprocess.run(["ip", "link", "add", "pytest0", "type", kind], capture_output=True)
+        if added.returncode == 0:
+            break
+    else:
+        pytest.skip("no dummy or ifb links: %s" % added.stderr.decode().strip())
+    try:
+        assert watcher.wait(5.0) in {"links", "unknown"}
+        assert interfaces.wait_for_interface("pytest0", timeout=1.0) is not None
+    finally:
+        subprocess.run(["ip", "link", "del", "pytest0"], check=True)
@@ -0,0 +1,139 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+
+use log::debug;
+
+use crate::{Interface, InterfaceFlags, NextHop};
+
+/// The directed broadcast address of every IPv4 network this machine is on,
+/// with `port`.
+///
+/// Only interfaces that are up and can broadcast are included, never
+/// loopback or point-to-point ones. The broadcast address the OS reports is
+/// used where there is one, otherwise it is computed from the address and
+/// mask. /31 and /32 networks have no broadcast address and are skipped.
+pub fn broadcast_targets(port: u16) -> io::Result<Vec<(Interface, SocketAddrV4)>> {
+    Ok(targets(crate::ifaces()?, port))
+}
+
+fn targets(ifaces: Vec<Interface>, port: u16) -> Vec<(Interface, SocketAddrV4)> {
+    let mut targets = Vec::new();
+    for iface in ifaces {
+        if !iface
+            .flags
+            .contains(InterfaceFlags::UP | InterfaceFlags::BROADCAST)
+            || iface
+                .flags
+                .intersects(InterfaceFlags::LOOPBACK | InterfaceFlags::POINT_TO_POINT)
+        {
+            continue;
+        }
+        let addr = match iface.addr.map(|addr| addr.ip()) {
+            Some(IpAddr::V4(addr)) => addr,
+            _ => continue,
+        };
+        match iface.prefix_len() {
+            Some(len) if len >= 31 => {
+                debug!("{}: no broadcast address on {}/{}", iface.name, addr, len);
+                continue;
+            }
+            _ => {}
+        }
+
+        let broadcast = match (&iface.hop, iface.mask.map(|mask| mask.ip())) {
+            (Some(NextHop::Broadcast(SocketAddr::V4(broadcast))), _) => *broadcast.ip(),
+            (_, Some(IpAddr::V4(mask))) => Ipv4Addr::from(u32::from(addr) | !u32::from(mask)),
+            _ => {
+                debug!("{}: no broadcast address or mask for {}", iface.name, addr);
+                continue;
+            }
+        };
+        targets.push((iface, SocketAddrV4::new(broadcast, port)));
+    }
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Kind;
+
+    fn v4(name: &str, addr: &str, mask: &str, flags: InterfaceFlags) -> Interface {
+        let mut iface = Interface::new(name, Kind::Ipv4);
+        iface.addr = Some(SocketAddr::new(addr.parse().unwrap(), 0));
+        iface.mask = Some(SocketAddr::new(mask.parse().unwrap(), 0));
+        iface.flags = flags;
+        iface
+    }
+
+    fn lan() -> InterfaceFlags {
+        InterfaceFlags::UP | InterfaceFlags::BROADCAST | InterfaceFlags::MULTICAST
+    }
+
+    fn names_and_targets(ifaces: Vec<Interface>) -> Vec<(String, String)> {
+        targets(ifaces, 5353)
+            .into_iter()
+            .map(|(iface, target)| (iface.name, target.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn the_reported_broadcast_wins() {
+        let mut eth0 = v4("eth0", "192.0.2.10", "255.255.255.0", lan());
+        // Some networks set an all-zeroes broadcast.
+        eth0.hop = Some(NextHop::Broadcast("192.0.2.0:0".parse().unwrap()));
+        assert_eq!(
+            names_and_targets(vec![eth0]),
+            [("eth0".to_owned(), "192.0.2.0:5353".to_owned())]
+        );
+    }
+
+    #[test]
+    fn missing_broadcasts_are_computed() {
+        let eth0 = v4("eth0", "10.1.2.3", "255.255.240.0", lan());
+        assert_eq!(
+            names_and_targets(vec![eth0]),
+            [("eth0".to_owned(), "10.1.15.255:5353".to_owned())]
+        );
+    }
+
+    #[test]
+    fn ineligible_entries_are_skipped() {
+        let mut no_mask = v4("eth1", "10.0.0.1", "255.0.0.0", lan());
+        no_mask.mask = None;
+        let mut v6 = Interface::new("eth0", Kind::Ipv6);
+        v6.addr = Some("[fd00::1]:0".parse().unwrap());
+        v6.flags = lan();
+        let ifaces = vec![
+            v4(
+                "lo",
+                "127.0.0.1",
+                "255.0.0.0",
+                InterfaceFlags::UP | InterfaceFlags::LOOPBACK,
+            ),
+            v4(
+                "tun0",
+                "10.8.0.2",
+                "255.255.255.0",
+                InterfaceFlags::UP | InterfaceFlags::BROADCAST | InterfaceFlags::POINT_TO_POINT,
+            ),
+            v4(
+                "eth2",
+                "192.0.2.1",
+                "255.255.255.0",
+                InterfaceFlags::BROADCAST,
+            ),
+            v4("eth3", "192.0.2.1", "255.255.255.0", InterfaceFlags::UP),
+            v4("eth4", "192.0.2.4", "255.255.255.254", lan()),
+            v4("eth5", "192.0.2.5", "255.255.255.255", lan()),
+            no_mask,
+            v6,
+            v4("eth0", "192.0.2.10", "255.255.255.0", lan()),
+        ];
+        assert_eq!(
+            names_and_targets(ifaces),
+            [("eth0".to_owned(), "192.0.2.255:5353".to_owned())]
+        );
+    }
+}
@@ -0,0 +1,409 @@
+//! A snapshot of the interfaces kept current by change notifications.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::debug;
+
+use crate::signature::signature;
+#[cfg(feature = "watch")]
+use crate::ChangeHint;
+use crate::Interface;
+
+/// How often a [`WatchedCache`] without change notifications enumerates
+/// again, unless [`with_ttl`](WatchedCache::with_ttl) says otherwise.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(5);
+
+/// How long the thread blocks on notifications before it checks whether
+/// the cache was dropped.
+#[cfg(feature = "watch")]
+const STOP_CHECK: Duration = Duration::from_millis(250);
+
+/// The latest interfaces, enumerated again on a background thread whenever
+/// the OS reports a change.
+///
+/// [`get`](WatchedCache::get) only clones an `Arc`, so it can be called on
+/// every packet. With the `watch` feature the snapshot is refreshed once
+/// per burst of change notifications and otherwise left alone. Without the
+/// feature, on platforms without notifications, or once the subscription
+/// fails, the cache falls back to enumerating every TTL;
+/// [`is_watching`](WatchedCache::is_watching) tells which mode it is in.
+/// Dropping the cache ends the subscription and waits for the thread.
+pub struct WatchedCache {
+    shared: Arc<Shared>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    latest: Mutex<Arc<Vec<Interface>>>,
+    generation: AtomicU64,
+    watching: AtomicBool,
+    enumerate: Enumerate,
+}
+
+/// Where the snapshots come from, `ifaces` but in the tests.
+type Enumerate = Box<dyn Fn() -> io::Result<Vec<Interface>> + Send + Sync>;
+
+/// A wait for change notifications, as `Watcher::wait` does it.
+#[cfg(feature = "watch")]
+type Wait = Box<dyn FnMut(Option<Duration>) -> io::Result<Option<ChangeHint>> + Send>;
+
+impl WatchedCache {
+    /// Enumerate, and keep the result current. Fails if the first
+    /// enumeration does.
+    pub fn new() -> io::Result<WatchedCache> {
+        WatchedCache::with_ttl(DEFAULT_TTL)
+    }
+
+    /// Like [`new`](WatchedCache::new), enumerating every `ttl` when there
+    /// are no change notifications.
+    pub fn with_ttl(ttl: Duration) -> io::Result<WatchedCache> {
+        // Subscribed before the first enumeration, so no change slips
+        // through between the two.
+        #[cfg(feature = "watch")]
+        let wait: Option<Wait> = match crate::ffi::Watcher::new() {
+            Ok(mut watcher) => Some(Box::new(move |timeout| watcher.wait(timeout))),
+            Err(err) => {
+                debug!(
+                    "change notifications unavailable, refreshing every {:?}: {}",
+                    ttl, err
+                );
+                None
+            }
+        };
+        WatchedCache::start(
+            Box::new(crate::ifaces),
+            #[cfg(feature = "watch")]
+            wait,
+            ttl,
+        )
+    }
+
+    /// The cache over `enumerate`, refreshed on the notifications `wait`
+    /// waits for, or every `ttl` without them.
+    fn start(
+        enumerate: Enumerate,
+        #[cfg(feature = "watch")] wait: Option<Wait>,
+        ttl: Duration,
+    ) -> io::Result<WatchedCache> {
+        #[cfg(feature = "watch")]
+        let watching = wait.is_some();
+        #[cfg(not(feature = "watch"))]
+        let watching = false;
+
+        let shared = Arc::new(Shared {
+            latest: Mutex::new(Arc::new(enumerate()?)),
+            generation: AtomicU64::new(0),
+            watching: AtomicBool::new(watching),
+            enumerate,
+        });
+        let (stop, stopped) = mpsc::channel();
+        let refreshed = shared.clone();
+        let thread = thread::spawn(move || {
+            #[cfg(feature = "watch")]
+            watch(&refreshed, wait, &stopped, ttl);
+            #[cfg(not(feature = "watch"))]
+            poll(&refreshed, &stopped, ttl);
+        });
+
+        Ok(WatchedCache {
+            shared,
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+
+    /// The interfaces as of the last refresh.
+    pub fn get(&self) -> Arc<Vec<Interface>> {
+        self.shared.latest.lock().unwrap().clone()
+    }
+
+    /// How many times the snapshot changed since the cache was made.
+    ///
+    /// Compare it with an earlier value to tell whether anything changed
+    /// since. Every notification counts, even one for something the
+    /// snapshot does not show; in TTL mode only refreshes that change the
+    /// [`network_signature`](crate::network_signature) do.
+    pub fn generation(&self) -> u64 {
+        self.shared.generation.load(Ordering::Acquire)
+    }
+
+    /// Whether the snapshot follows change notifications, rather than a
+    /// TTL.
+    pub fn is_watching(&self) -> bool {
+        self.shared.watching.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for WatchedCache {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Shared {
+    /// Enumerate again. The generation goes up if `changed`, or else if
+    /// the signature differs from the snapshot's.
+    fn refresh(&self, changed: bool) {
+        let ifaces = match (self.enumerate)() {
+            Ok(ifaces) => ifaces,
+            Err(err) => {
+                debug!("cache refresh failed: {}", err);
+                return;
+            }
+        };
+        let mut latest = self.latest.lock().unwrap();
+        let changed = changed || signature(&latest) != signature(&ifaces);
+        *latest = Arc::new(ifaces);
+        if changed {
+            self.generation.fetch_add(1, Ordering::Release);
+        }
+    }
+}
+
+/// Refresh on every notification `wait` returns, until the cache is
+/// dropped or the wait fails, and then go on with [`poll`].
+#[cfg(feature = "watch")]
+fn watch(shared: &Shared, wait: Option<Wait>, stopped: &Receiver<()>, ttl: Duration) {
+    if let Some(mut wait) = wait {
+        loop {
+            match wait(Some(STOP_CHECK)) {
+                Ok(Some(_)) => shared.refresh(true),
+                Ok(None) => {}
+                Err(err) => {
+                    debug!(
+                        "change notifications failed, refreshing every {:?}: {}",
+                        ttl, err
+                    );
+                    shared.watching.store(false, Ordering::Relaxed);
+                    // Changes may have gone unreported before the failure.
+                    shared.refresh(false);
+                    break;
+                }
+            }
+            match stopped.try_recv() {
+                Err(mpsc::TryRecvError::Empty) => {}
+                _ => return,
+            }
+        }
+    }
+    poll(shared, stopped, ttl);
+}
+
+/// Refresh every `ttl` until the cache is dropped.
+fn poll(shared: &Shared, stopped: &Receiver<()>, ttl: Duration) {
+    loop {
+        match stopped.recv_timeout(ttl) {
+            Err(RecvTimeoutError::Timeout) => shared.refresh(false),
+            _ => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Kind;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Instant;
+
+    /// Counts the enumerations, each returning `snapshot` of the count.
+    fn counting(
+        snapshot: fn(usize) -> io::Result<Vec<Interface>>,
+    ) -> (Arc<AtomicUsize>, Enumerate) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let counted = count.clone();
+        let enumerate = Box::new(move || snapshot(counted.fetch_add(1, Ordering::SeqCst) + 1));
+        (count, enumerate)
+    }
+
+    fn unchanged(_: usize) -> io::Result<Vec<Interface>> {
+        Ok(vec![Interface::new("lo", Kind::Ipv4)])
+    }
+
+    /// A new interface on every enumeration.
+    fn growing(count: usize) -> io::Result<Vec<Interface>> {
+        Ok((0..count)
+            .map(|i| Interface::new(&format!("eth{}", i), Kind::Ipv4))
+            .collect())
+    }
+
+    fn eventually(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// Waits on the events sent to the returned sender, as a watcher
+    /// waits on the OS.
+    #[cfg(feature = "watch")]
+    fn injected() -> (Sender<io::Result<ChangeHint>>, Wait) {
+        let (events, received) = mpsc::channel::<io::Result<ChangeHint>>();
+        let wait: Wait = Box::new(move |timeout: Option<Duration>| {
+            match received.recv_timeout(timeout.unwrap()) {
+                Ok(event) => event.map(Some),
+                Err(RecvTimeoutError::Timeout) => Ok(None),
+                Err(RecvTimeoutError::Disconnected) => {
+                    thread::sleep(timeout.unwrap());
+                    Ok(None)
+                }
+            }
+        });
+        (events, wait)
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn each_event_refreshes_once() {
+        let (count, enumerate) = counting(unchanged);
+        let (events, wait) = injected();
+        let cache = WatchedCache::start(enumerate, Some(wait), Duration::from_millis(10)).unwrap();
+        assert!(cache.is_watching());
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // Watching, the TTL is not used.
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.generation(), 0);
+
+        for hint in [
+            ChangeHint::Addresses,
+            ChangeHint::Links,
+            ChangeHint::Unknown,
+        ]
+        .iter()
+        {
+            events.send(Ok(*hint)).unwrap();
+        }
+        eventually(|| count.load(Ordering::SeqCst) == 4);
+        // Even with the snapshot unchanged.
+        eventually(|| cache.generation() == 3);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(count.load(Ordering::SeqCst), 4);
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn refreshes_replace_the_snapshot() {
+        let (_, enumerate) = counting(growing);
+        let (events, wait) = injected();
+        let cache = WatchedCache::start(enumerate, Some(wait), DEFAULT_TTL).unwrap();
+        let first = cache.get();
+        assert_eq!(first.len(), 1);
+
+        events.send(Ok(ChangeHint::Links)).unwrap();
+        eventually(|| cache.generation() == 1);
+        assert_eq!(cache.get().len(), 2);
+        // Earlier snapshots are left alone.
+        assert_eq!(first.len(), 1);
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn a_failed_wait_falls_back_to_the_ttl() {
+        let (count, enumerate) = counting(unchanged);
+        let (events, wait) = injected();
+        let cache = WatchedCache::start(enumerate, Some(wait), Duration::from_millis(10)).unwrap();
+        events.send(Err(io::Error::other("overrun"))).unwrap();
+        eventually(|| !cache.is_watching());
+        // The refresh after the failure, then every TTL.
+        eventually(|| count.load(Ordering::SeqCst) >= 5);
+        // None of which changed the signature.
+        assert_eq!(cache.generation(), 0);
+        // Nothing waits for events any more.
+        thread::sleep(Duration::from_millis(300));
+        assert!(events.send(Ok(ChangeHint::Links)).is_err());
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn dropping_stops_the_refreshes() {
+        let (count, enumerate) = counting(unchanged);
+        let (events, wait) = injected();
+        let cache = WatchedCache::start(enumerate, Some(wait), DEFAULT_TTL).unwrap();
+        events.send(Ok(ChangeHint::Addresses)).unwrap();
+        eventually(|| count.load(Ordering::SeqCst) == 2);
+
+        drop(cache);
+        // The thread is joined, so the wait is gone.
+        assert!(events.send(Ok(ChangeHint::Addresses)).is_err());
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn without_notifications_the_ttl_refreshes() {
+        let (count, enumerate) = counting(growing);
+        let cache = WatchedCache::start(
+            enumerate,
+            #[cfg(feature = "watch")]
+            None,
+            Duration::from_millis(10),
+        )
+        .unwrap();
+        assert!(!cache.is_watching());
+        eventually(|| count.load(Ordering::SeqCst) >= 4);
+        // Every refresh changed the signature.
+        assert!(cache.generation() >= 3);
+
+        drop(cache);
+        let stopped = count.load(Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(count.load(Ordering::SeqCst), stopped);
+    }
+
+    #[test]
+    fn failed_refreshes_keep_the_snapshot() {
+        fn failing(count: usize) -> io::Result<Vec<Interface>> {
+            match count {
+                1 => unchanged(count),
+                _ => Err(io::Error::other("gone")),
+            }
+        }
+        let (count, enumerate) = counting(failing);
+        let cache = WatchedCache::start(
+            enumerate,
+            #[cfg(feature = "watch")]
+            None,
+            Duration::from_millis(10),
+        )
+        .unwrap();
+        eventually(|| count.load(Ordering::SeqCst) >= 3);
+        assert_eq!(cache.get().len(), 1);
+        assert_eq!(cache.generation(), 0);
+    }
+
+    #[test]
+    fn the_first_enumeration_must_succeed() {
+        let (_, enumerate) = counting(|_| Err(io::Error::other("gone")));
+        let err = WatchedCache::start(
+            enumerate,
+            #[cfg(feature = "watch")]
+            None,
+            DEFAULT_TTL,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.to_string(), "gone");
+    }
+
+    #[test]
+    fn this_machine() {
+        let cache = WatchedCache::new().unwrap();
+        assert!(cache
+            .get()
+            .iter()
+            .any(|i| i.flags.contains(crate::InterfaceFlags::LOOPBACK)));
+        #[cfg(not(feature = "watch"))]
+        assert!(!cache.is_watching());
+    }
+}
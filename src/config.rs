@@ -0,0 +1,409 @@
+//! Changing interface configuration.
+//!
+//! Everything in here needs elevated privileges: root or `CAP_NET_ADMIN` on
+//! unix, an administrator token on Windows.
+
+pub use crate::ffi::config::*;
+
+use std::io;
+use std::net::IpAddr;
+
+use crate::{Kind, MacAddr, ValidName};
+
+/// What [`flush_addresses`] did with each address.
+#[derive(Debug)]
+pub struct FlushReport {
+    /// One entry per address tried, with its prefix length, in the order
+    /// they were removed.
+    pub results: Vec<(IpAddr, u8, io::Result<()>)>,
+}
+
+impl FlushReport {
+    pub fn removed(&self) -> impl Iterator<Item = (IpAddr, u8)> + '_ {
+        self.results
+            .iter()
+            .filter(|(_, _, result)| result.is_ok())
+            .map(|(addr, len, _)| (*addr, *len))
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = (IpAddr, u8, &io::Error)> {
+        self.results
+            .iter()
+            .filter_map(|(addr, len, result)| result.as_ref().err().map(|err| (*addr, *len, err)))
+    }
+
+    pub fn all_removed(&self) -> bool {
+        self.failed().next().is_none()
+    }
+}
+
+/// Removes every address of `family`, `Ipv4` or `Ipv6`, or of both for
+/// `None`, from interface `name`.
+///
+/// The addresses are collected first and then removed one by one with
+/// [`remove_address`], last listed first, so that Linux secondary IPv4
+/// addresses go before the primary one that would take them with it.
+/// Failures are collected in the report instead of stopping the loop; only
+/// failing to enumerate the interfaces is an error. With
+/// `keep_link_local`, IPv6 link-local addresses are left alone, since the
+/// kernel adds them back as long as the interface is up.
+pub fn flush_addresses(
+    name: &str,
+    family: Option<Kind>,
+    keep_link_local: bool,
+) -> io::Result<FlushReport> {
+    if matches!(
+        family,
+        Some(Kind::Packet | Kind::Link | Kind::Can | Kind::Bluetooth | Kind::Unknown)
+    ) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the family must be Ipv4 or Ipv6",
+        ));
+    }
+    let name = crate::validate_name(name)?;
+    Ok(flush_with(
+        &name,
+        family,
+        keep_link_local,
+        crate::ifaces()?,
+        |addr, len| remove_address(name.as_str(), addr, len),
+    ))
+}
+
+/// The collecting and removing of [`flush_addresses`], over `ifaces` and
+/// with the removal passed in.
+fn flush_with(
+    name: &ValidName,
+    family: Option<Kind>,
+    keep_link_local: bool,
+    ifaces: Vec<crate::Interface>,
+    mut remove: impl FnMut(IpAddr, u8) -> io::Result<()>,
+) -> FlushReport {
+    let mut addrs: Vec<(IpAddr, u8)> = Vec::new();
+    for iface in ifaces {
+        if !name.matches(&iface.name) || (family.is_some() && family.as_ref() != Some(&iface.kind))
+        {
+            continue;
+        }
+        let addr = match iface.addr {
+            Some(addr) => addr.ip(),
+            None => continue,
+        };
+        let link_local = matches!(addr, IpAddr::V6(v6) if v6.segments()[0] & 0xffc0 == 0xfe80);
+        if keep_link_local && link_local {
+            continue;
+        }
+        let width = if addr.is_ipv4() { 32 } else { 128 };
+        let entry = (addr, iface.prefix_len().unwrap_or(width));
+        if !addrs.contains(&entry) {
+            addrs.push(entry);
+        }
+    }
+
+    let results = addrs
+        .into_iter()
+        .rev()
+        .map(|(addr, len)| (addr, len, remove(addr, len)))
+        .collect();
+    FlushReport { results }
+}
+
+/// Installs a static neighbor entry: `ip` is at `mac` on interface `name`,
+/// with an ARP entry for IPv4 and an NDP one for IPv6.
+///
+/// The entry is permanent, so the OS neither probes nor expires it. `ip`
+/// has to be on-link, i.e. in the network of one of the interface's
+/// addresses, see [`Interface::contains`](crate::Interface::contains).
+/// Errors:
+///
+/// - `ErrorKind::NotFound` if there is no interface `name`;
+/// - `ErrorKind::InvalidInput` if `ip` is not on-link for it;
+/// - `ErrorKind::AlreadyExists` if there is a static entry for `ip` already;
+/// - `ErrorKind::PermissionDenied` without the privileges to change it.
+pub fn add_neighbor(name: &str, ip: IpAddr, mac: MacAddr) -> io::Result<()> {
+    let name = crate::validate_name(name)?;
+    let index = on_link_index(crate::ifaces()?, &name, ip)?;
+    crate::ffi::config::install_neighbor(index, ip, mac)
+}
+
+/// The index of interface `name` among `ifaces`, if `ip` is on-link for
+/// one of its addresses.
+fn on_link_index(ifaces: Vec<crate::Interface>, name: &ValidName, ip: IpAddr) -> io::Result<u32> {
+    let ifaces: Vec<_> = ifaces
+        .into_iter()
+        .filter(|iface| name.matches(&iface.name))
+        .collect();
+    if ifaces.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no such interface"));
+    }
+    ifaces
+        .iter()
+        .find(|iface| iface.contains(ip))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the address is not on-link for the interface",
+            )
+        })?
+        .index
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the interface has no index"))
+}
+
+/// Removes the neighbor entry of `ip` on interface `name`.
+///
+/// Fails with `ErrorKind::NotFound` if the interface or the entry does not
+/// exist.
+pub fn remove_neighbor(name: &str, ip: IpAddr) -> io::Result<()> {
+    let index = index_of(&crate::validate_name(name)?)?;
+    crate::ffi::config::delete_neighbor(index, ip)
+}
+
+/// Creates VLAN `vlan_id` on interface `parent` and returns the index of
+/// the new interface.
+///
+/// The new interface is called `name`, or `<parent>.<vlan_id>` without one.
+/// It starts out down. Only Linux creates VLANs so far, the other platforms
+/// fail with `ErrorKind::Unsupported`, as does a kernel without 802.1Q
+/// support. Errors:
+///
+/// - `ErrorKind::InvalidInput` if `vlan_id` is not in `1..=4094` or the
+///   name, given or made up, is not a valid interface name;
+/// - `ErrorKind::NotFound` if there is no interface `parent`;
+/// - `ErrorKind::AlreadyExists` if there is an interface called `name`
+///   already, or a VLAN with that id on `parent`.
+pub fn create_vlan(parent: &str, vlan_id: u16, name: Option<&str>) -> io::Result<u32> {
+    if !(1..=4094).contains(&vlan_id) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "VLAN ids are 1 to 4094",
+        ));
+    }
+    let parent = crate::validate_name(parent)?;
+    let name = match name {
+        Some(name) => crate::validate_name(name)?,
+        None => crate::validate_name(&format!("{}.{}", parent, vlan_id))?,
+    };
+    let index = index_of(&parent)?;
+    crate::ffi::config::add_vlan(index, vlan_id, &name)
+}
+
+/// Deletes the VLAN interface `name`, one [`create_vlan`] made or any
+/// other. Fails with `ErrorKind::NotFound` if there is no such interface and
+/// `ErrorKind::InvalidInput` if it is not a VLAN.
+pub fn delete_vlan(name: &str) -> io::Result<()> {
+    let index = index_of(&crate::validate_name(name)?)?;
+    crate::ffi::config::delete_vlan(index)
+}
+
+/// Creates a bridge called `name` and returns its index.
+///
+/// The bridge starts out down and without ports, see [`bridge_add_port`].
+/// Only Linux creates bridges so far, the other platforms fail with
+/// `ErrorKind::Unsupported`. Fails with `ErrorKind::AlreadyExists` if there
+/// is an interface called `name` already.
+pub fn create_bridge(name: &str) -> io::Result<u32> {
+    crate::ffi::config::add_bridge(&crate::validate_name(name)?)
+}
+
+/// Deletes the bridge `name`, releasing its ports. Fails with
+/// `ErrorKind::NotFound` if there is no such interface and
+/// `ErrorKind::InvalidInput` if it is not a bridge.
+pub fn delete_bridge(name: &str) -> io::Result<()> {
+    let index = index_of(&crate::validate_name(name)?)?;
+    crate::ffi::config::delete_bridge(index)
+}
+
+/// Makes interface `iface` a port of bridge `bridge`. Errors:
+///
+/// - `ErrorKind::NotFound` if either interface does not exist;
+/// - `ErrorKind::InvalidInput` if `bridge` is not a bridge;
+/// - `ErrorKind::ResourceBusy` if `iface` is a port of another bridge or
+///   bond, which it has to be released from first.
+///
+/// Adding a port the bridge already has does nothing.
+pub fn bridge_add_port(bridge: &str, iface: &str) -> io::Result<()> {
+    let bridge = index_of(&crate::validate_name(bridge)?)?;
+    let port = index_of(&crate::validate_name(iface)?)?;
+    crate::ffi::config::add_bridge_port(bridge, port)
+}
+
+/// Releases interface `iface` from bridge `bridge`. Fails with
+/// `ErrorKind::NotFound` if `iface` is not one of its ports.
+pub fn bridge_remove_port(bridge: &str, iface: &str) -> io::Result<()> {
+    let bridge = index_of(&crate::validate_name(bridge)?)?;
+    let port = index_of(&crate::validate_name(iface)?)?;
+    crate::ffi::config::remove_bridge_port(bridge, port)
+}
+
+/// The index of interface `name`, looked up in [`crate::ifaces`].
+fn index_of(name: &ValidName) -> io::Result<u32> {
+    crate::ifaces()?
+        .into_iter()
+        .find(|iface| name.matches(&iface.name))
+        .and_then(|iface| iface.index)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such interface"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Interface;
+
+    fn entry(name: &str, addr: &str, mask: &str) -> Interface {
+        let addr: IpAddr = addr.parse().unwrap();
+        let kind = if addr.is_ipv4() {
+            Kind::Ipv4
+        } else {
+            Kind::Ipv6
+        };
+        let mut iface = Interface::new(name, kind);
+        iface.addr = Some((addr, 0).into());
+        iface.mask = Some((mask.parse::<IpAddr>().unwrap(), 0).into());
+        iface
+    }
+
+    fn fixture() -> Vec<Interface> {
+        vec![
+            entry("eth0", "192.0.2.2", "255.255.255.0"),
+            entry("eth0", "192.0.2.3", "255.255.255.0"),
+            entry("eth0", "2001:db8::2", "ffff:ffff:ffff:ffff::"),
+            entry("eth0", "fe80::2", "ffff:ffff:ffff:ffff::"),
+            // Listed again under another entry, say the link layer one's.
+            entry("eth0", "192.0.2.2", "255.255.255.0"),
+            entry("eth1", "198.51.100.2", "255.255.255.0"),
+            Interface::new("eth0", Kind::Packet),
+        ]
+    }
+
+    fn flush(family: Option<Kind>, keep_link_local: bool) -> Vec<(IpAddr, u8)> {
+        let name = crate::validate_name("eth0").unwrap();
+        let report = flush_with(&name, family, keep_link_local, fixture(), |_, _| Ok(()));
+        assert!(report.all_removed());
+        report.removed().collect()
+    }
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    #[test]
+    fn addresses_are_removed_last_listed_first() {
+        assert_eq!(
+            flush(None, false),
+            [
+                (ip("fe80::2"), 64),
+                (ip("2001:db8::2"), 64),
+                (ip("192.0.2.3"), 24),
+                (ip("192.0.2.2"), 24),
+            ]
+        );
+    }
+
+    #[test]
+    fn families_and_link_local_addresses_are_left_alone_when_asked() {
+        assert_eq!(
+            flush(Some(Kind::Ipv4), false),
+            [(ip("192.0.2.3"), 24), (ip("192.0.2.2"), 24)]
+        );
+        assert_eq!(flush(Some(Kind::Ipv6), true), [(ip("2001:db8::2"), 64)]);
+    }
+
+    #[test]
+    fn failures_do_not_stop_the_flush() {
+        let name = crate::validate_name("eth0").unwrap();
+        let mut tried = 0;
+        let report = flush_with(&name, Some(Kind::Ipv4), false, fixture(), |addr, _| {
+            tried += 1;
+            if addr == ip("192.0.2.3") {
+                Err(io::Error::from(io::ErrorKind::PermissionDenied))
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(tried, 2);
+        assert!(!report.all_removed());
+        assert_eq!(
+            report.removed().collect::<Vec<_>>(),
+            [(ip("192.0.2.2"), 24)]
+        );
+        let failed: Vec<_> = report
+            .failed()
+            .map(|(addr, len, err)| (addr, len, err.kind()))
+            .collect();
+        assert_eq!(
+            failed,
+            [(ip("192.0.2.3"), 24, io::ErrorKind::PermissionDenied)]
+        );
+    }
+
+    #[test]
+    fn interfaces_without_addresses_flush_nothing() {
+        let name = crate::validate_name("eth2").unwrap();
+        let report = flush_with(&name, None, false, fixture(), |_, _| unreachable!());
+        assert!(report.results.is_empty());
+        assert!(report.all_removed());
+    }
+
+    #[test]
+    fn only_address_families_can_be_flushed() {
+        let err = flush_addresses("eth0", Some(Kind::Packet), false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn neighbors_have_to_be_on_link() {
+        let mut ifaces = fixture();
+        for iface in &mut ifaces {
+            iface.index = Some(if iface.name == "eth0" { 4 } else { 5 });
+        }
+        let index = |name: &str, addr: &str| {
+            let name = crate::validate_name(name).unwrap();
+            on_link_index(ifaces.clone(), &name, ip(addr)).map_err(|err| err.kind())
+        };
+        assert_eq!(index("eth0", "192.0.2.9"), Ok(4));
+        assert_eq!(index("eth0", "2001:db8::9"), Ok(4));
+        assert_eq!(index("eth1", "198.51.100.9"), Ok(5));
+        // On-link, but for the other interface.
+        assert_eq!(
+            index("eth0", "198.51.100.9"),
+            Err(io::ErrorKind::InvalidInput)
+        );
+        assert_eq!(
+            index("eth0", "203.0.113.9"),
+            Err(io::ErrorKind::InvalidInput)
+        );
+        assert_eq!(index("eth2", "192.0.2.9"), Err(io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn vlan_ids_and_names_are_checked_first() {
+        let kind = |parent: &str, id, name| create_vlan(parent, id, name).unwrap_err().kind();
+        assert_eq!(kind("eth0", 0, None), io::ErrorKind::InvalidInput);
+        assert_eq!(kind("eth0", 4095, None), io::ErrorKind::InvalidInput);
+        assert_eq!(kind("eth0", u16::MAX, None), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            create_vlan("eth0", 0, None).unwrap_err().to_string(),
+            "VLAN ids are 1 to 4094"
+        );
+        // Even if the parent is there, the made-up name is too long.
+        assert_eq!(
+            kind("abcdefghijklmn", 4094, None),
+            io::ErrorKind::InvalidInput
+        );
+        assert_eq!(kind("eth0", 7, Some("")), io::ErrorKind::InvalidInput);
+        assert_eq!(kind("", 7, Some("vlan7")), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn vlans_need_a_parent() {
+        assert_eq!(
+            create_vlan("ifaces-none0", 7, None).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            delete_vlan("ifaces-none0").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! What kind of network an IPv4 address is on, for the ranges std has no
+//! stable check for.
+
+use core::net::Ipv4Addr;
+
+/// Whether `addr` is in `network/len`.
+fn in_network(addr: Ipv4Addr, network: [u8; 4], len: u32) -> bool {
+    let mask = u32::MAX.checked_shl(32 - len).unwrap_or(0);
+    u32::from(addr) & mask == u32::from(Ipv4Addr::from(network))
+}
+
+/// The private ranges of RFC 1918: `10.0.0.0/8`, `172.16.0.0/12` and
+/// `192.168.0.0/16`.
+pub fn is_rfc1918(addr: Ipv4Addr) -> bool {
+    in_network(addr, [10, 0, 0, 0], 8)
+        || in_network(addr, [172, 16, 0, 0], 12)
+        || in_network(addr, [192, 168, 0, 0], 16)
+}
+
+/// `169.254.0.0/16`, where hosts put themselves when DHCP gave them
+/// nothing (APIPA, RFC 3927). An interface with only these has no usable
+/// network beyond its link.
+pub fn is_apipa(addr: Ipv4Addr) -> bool {
+    in_network(addr, [169, 254, 0, 0], 16)
+}
+
+/// `100.64.0.0/10`, the shared address space of RFC 6598 that carriers
+/// number their NAT customers from.
+pub fn is_cgnat(addr: Ipv4Addr) -> bool {
+    in_network(addr, [100, 64, 0, 0], 10)
+}
+
+/// `198.18.0.0/15`, kept for network benchmarks by RFC 2544.
+pub fn is_benchmarking(addr: Ipv4Addr) -> bool {
+    in_network(addr, [198, 18, 0, 0], 15)
+}
+
+/// The documentation networks of RFC 5737: `192.0.2.0/24`,
+/// `198.51.100.0/24` and `203.0.113.0/24`.
+pub fn is_documentation(addr: Ipv4Addr) -> bool {
+    in_network(addr, [192, 0, 2, 0], 24)
+        || in_network(addr, [198, 51, 100, 0], 24)
+        || in_network(addr, [203, 0, 113, 0], 24)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each check, with the addresses just outside and inside its ranges.
+    #[allow(clippy::type_complexity)]
+    const BOUNDARIES: [(&str, fn(Ipv4Addr) -> bool, &[(&str, bool)]); 5] = [
+        (
+            "rfc1918",
+            is_rfc1918,
+            &[
+                ("9.255.255.255", false),
+                ("10.0.0.0", true),
+                ("10.255.255.255", true),
+                ("11.0.0.0", false),
+                ("172.15.255.255", false),
+                ("172.16.0.0", true),
+                ("172.31.255.255", true),
+                ("172.32.0.0", false),
+                ("192.167.255.255", false),
+                ("192.168.0.0", true),
+                ("192.168.255.255", true),
+                ("192.169.0.0", false),
+            ],
+        ),
+        (
+            "apipa",
+            is_apipa,
+            &[
+                ("169.253.255.255", false),
+                ("169.254.0.0", true),
+                ("169.254.255.255", true),
+                ("169.255.0.0", false),
+            ],
+        ),
+        (
+            "cgnat",
+            is_cgnat,
+            &[
+                ("100.63.255.255", false),
+                ("100.64.0.0", true),
+                ("100.127.255.255", true),
+                ("100.128.0.0", false),
+            ],
+        ),
+        (
+            "benchmarking",
+            is_benchmarking,
+            &[
+                ("198.17.255.255", false),
+                ("198.18.0.0", true),
+                ("198.19.255.255", true),
+                ("198.20.0.0", false),
+            ],
+        ),
+        (
+            "documentation",
+            is_documentation,
+            &[
+                ("192.0.1.255", false),
+                ("192.0.2.0", true),
+                ("192.0.2.255", true),
+                ("192.0.3.0", false),
+                ("198.51.99.255", false),
+                ("198.51.100.0", true),
+                ("198.51.100.255", true),
+                ("198.51.101.0", false),
+                ("203.0.112.255", false),
+                ("203.0.113.0", true),
+                ("203.0.113.255", true),
+                ("203.0.114.0", false),
+            ],
+        ),
+    ];
+
+    #[test]
+    fn ranges_end_where_they_should() {
+        for (name, check, cases) in BOUNDARIES {
+            for &(addr, expected) in cases {
+                assert_eq!(check(addr.parse().unwrap()), expected, "{} {}", name, addr);
+            }
+        }
+    }
+
+    #[test]
+    fn the_ranges_do_not_overlap() {
+        for (name, _, cases) in BOUNDARIES {
+            for &(addr, _) in cases.iter().filter(|(_, inside)| *inside) {
+                let addr = addr.parse().unwrap();
+                let matching: usize = BOUNDARIES
+                    .iter()
+                    .filter(|(_, check, _)| check(addr))
+                    .count();
+                assert_eq!(matching, 1, "{} {}", name, addr);
+            }
+        }
+    }
+
+    #[test]
+    fn public_and_special_addresses_are_none_of_them() {
+        for addr in [
+            "0.0.0.0",
+            "1.1.1.1",
+            "8.8.8.8",
+            "127.0.0.1",
+            "224.0.0.1",
+            "255.255.255.255",
+        ] {
+            let addr = addr.parse().unwrap();
+            for (name, check, _) in BOUNDARIES {
+                assert!(!check(addr), "{} {}", name, addr);
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use bitflags::bitflags;
+
+bitflags! {
+    /// The state of an interface, in the same terms on every platform.
+    ///
+    /// On Unix these are the `IFF_*` flags of `getifaddrs`. On Windows `UP`
+    /// and `RUNNING` both follow the operational status, `LOOPBACK`,
+    /// `POINT_TO_POINT` and `BROADCAST` the interface type, and `MULTICAST`
+    /// is set unless the adapter has multicast switched off.
+    #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    pub struct InterfaceFlags: u32 {
+        const UP = 0x1;
+        const BROADCAST = 0x2;
+        const LOOPBACK = 0x4;
+        const POINT_TO_POINT = 0x8;
+        const RUNNING = 0x10;
+        const MULTICAST = 0x20;
+        const PROMISC = 0x40;
+    }
+}
+
+/// What kind of hardware, or software, is behind an interface.
+///
+/// Linux reports Wi-Fi devices as Ethernet at this level.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum HardwareKind {
+    Ethernet,
+    Wireless,
+    /// Mobile broadband, LTE and 5G modems.
+    Cellular,
+    /// CAN bus controllers, `can0` and `slcan0` on Linux.
+    Can,
+    /// Bluetooth network devices, e.g. the `bnep` interfaces of PAN.
+    Bluetooth,
+    Loopback,
+    Ppp,
+    Tunnel,
+    /// Bridges, veth pairs, VLANs and other devices without hardware of
+    /// their own.
+    Virtual,
+    Other,
+}
+
+impl HardwareKind {
+    /// Map an IANA `ifType`, which both the BSDs (`IFT_*`) and Windows
+    /// (`IF_TYPE_*`) use.
+    #[allow(dead_code)]
+    pub(crate) fn from_iana(if_type: u32) -> HardwareKind {
+        match if_type {
+            6 => HardwareKind::Ethernet,
+            71 => HardwareKind::Wireless,
+            // wwanPP and wwanPP2, the Mobile Broadband adapters of Windows.
+            243 | 244 => HardwareKind::Cellular,
+            24 => HardwareKind::Loopback,
+            23 => HardwareKind::Ppp,
+            55 | 131 => HardwareKind::Tunnel,
+            53 | 135 | 209 => HardwareKind::Virtual,
+            _ => HardwareKind::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iana_types_map_to_kinds() {
+        let cases = [
+            (6, HardwareKind::Ethernet),
+            (71, HardwareKind::Wireless),
+            (243, HardwareKind::Cellular),
+            (244, HardwareKind::Cellular),
+            (24, HardwareKind::Loopback),
+            (23, HardwareKind::Ppp),
+            (131, HardwareKind::Tunnel),
+            (53, HardwareKind::Virtual),
+            // IFT_CELLULAR of Apple's is not an IANA type.
+            (0xff, HardwareKind::Other),
+            (0, HardwareKind::Other),
+        ];
+        for (if_type, kind) in cases {
+            assert_eq!(HardwareKind::from_iana(if_type), kind, "{}", if_type);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::Interface;
+
+/// How often the carrier of a link was lost and came back, see
+/// [`Interface::carrier_changes`].
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct CarrierChanges {
+    /// Transitions either way since the device was created. Bringing the
+    /// link down and up administratively counts too.
+    pub changes: u64,
+    /// The transitions to carrier up and to carrier down, on kernels from
+    /// 4.16 on.
+    pub up: Option<u64>,
+    pub down: Option<u64>,
+}
+
+/// The interfaces in `next` whose carrier came or went since `prev` was
+/// taken, by name, in the order of `next` and without repeats.
+///
+/// Interfaces are matched by name and index. Ones missing from either
+/// list, and ones without carrier counters, on platforms other than Linux
+/// among them, are left out. A count that went down, as for a device
+/// that was recreated under the same name, is not a flap.
+pub fn detect_flaps(prev: &[Interface], next: &[Interface]) -> Vec<String> {
+    let mut flapped: Vec<String> = Vec::new();
+    for iface in next {
+        let now = match iface.carrier_changes() {
+            Some(now) => now.changes,
+            None => continue,
+        };
+        let before = prev
+            .iter()
+            .find(|old| old.name == iface.name && old.index == iface.index)
+            .and_then(Interface::carrier_changes)
+            .map(|before| before.changes);
+        if before.is_some_and(|before| now > before) && !flapped.contains(&iface.name) {
+            flapped.push(iface.name.clone());
+        }
+    }
+    flapped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Kind;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn link(name: &str, index: u32, changes: Option<u64>) -> Interface {
+        let mut iface = Interface::new(name, Kind::Link).with_os_specific(crate::OsSpecific {
+            carrier_changes: changes.map(|changes| CarrierChanges {
+                changes,
+                up: None,
+                down: None,
+            }),
+            ..crate::OsSpecific::default()
+        });
+        iface.index = Some(index);
+        iface
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn links_whose_count_went_up_flapped() {
+        let prev = [
+            link("eth0", 2, Some(4)),
+            link("eth1", 3, Some(2)),
+            link("wlan0", 4, Some(10)),
+            link("lo", 1, Some(0)),
+            link("br0", 6, None),
+            link("veth0", 7, Some(5)),
+        ];
+        let cases: [(Interface, bool); 8] = [
+            // Up by two: the carrier went and came back.
+            (link("eth0", 2, Some(6)), true),
+            (link("eth1", 3, Some(2)), false),
+            // An odd step is a flap too.
+            (link("wlan0", 4, Some(11)), true),
+            (link("lo", 1, Some(0)), false),
+            // No counters, before or after.
+            (link("br0", 6, Some(3)), false),
+            (link("tun0", 8, None), false),
+            // Recreated under the same name, with a new index.
+            (link("veth0", 9, Some(6)), false),
+            // Not there before.
+            (link("eth2", 5, Some(9)), false),
+        ];
+        for (iface, flapped) in &cases {
+            let expected: &[&str] = match flapped {
+                true => &[iface.name.as_str()],
+                false => &[],
+            };
+            assert_eq!(
+                detect_flaps(&prev, core::slice::from_ref(iface)),
+                expected,
+                "{}",
+                iface.name
+            );
+        }
+        let next: Vec<Interface> = cases.iter().map(|(iface, _)| iface.clone()).collect();
+        assert_eq!(detect_flaps(&prev, &next), ["eth0", "wlan0"]);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn flaps_are_named_once_and_counts_that_went_down_are_not() {
+        // An entry per address, all with the counters of their link.
+        let prev = [link("eth0", 2, Some(4)), link("eth0", 2, Some(4))];
+        let next = [link("eth0", 2, Some(5)), link("eth0", 2, Some(5))];
+        assert_eq!(detect_flaps(&prev, &next), ["eth0"]);
+        assert!(detect_flaps(&next, &prev).is_empty());
+        assert!(detect_flaps(&[], &next).is_empty());
+        assert!(detect_flaps(&prev, &[]).is_empty());
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    #[test]
+    fn nothing_flaps_without_counters() {
+        let iface = Interface::new("en0", Kind::Link);
+        assert_eq!(iface.carrier_changes(), None);
+        assert!(
+            detect_flaps(core::slice::from_ref(&iface), core::slice::from_ref(&iface)).is_empty()
+        );
+    }
+}
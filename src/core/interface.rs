@@ -0,0 +1,473 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+#[cfg(any(target_os = "linux", target_os = "android", target_family = "windows"))]
+use alloc::vec::Vec;
+
+use crate::{
+    is_eui64_derived, AddressOrigin, CarrierChanges, InterfaceFlags, InterfaceStats, MacAddr,
+    TunnelEndpoints,
+};
+// These come from the Windows backend, which needs `std`.
+#[cfg(all(target_family = "windows", feature = "std"))]
+use crate::{AdapterFlags, AdapterStrings, Guid};
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum NextHop {
+    Broadcast(core::net::SocketAddr),
+    Destination(core::net::SocketAddr),
+}
+
+/// The address family of an entry.
+///
+/// `Can` and `Bluetooth` were added in 0.2 for Linux entries of the
+/// `AF_CAN` and `AF_BLUETOOTH` families, which carry no IP address; code
+/// that matches on `Kind` exhaustively needs arms for them.
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Kind {
+    Packet,
+    Link,
+    Ipv4,
+    Ipv6,
+    Can,
+    Bluetooth,
+    Unknown,
+}
+
+/// Platform-specific details of an interface, see [`Interface::os_specific`].
+///
+/// Which fields exist depends on the target, so code reading them needs the
+/// same `cfg` as the field. Fields may be added in any release, which is why
+/// the struct is `non_exhaustive`; the fields every platform has live on
+/// [`Interface`] and never move in here.
+#[non_exhaustive]
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct OsSpecific {
+    /// The `ARPHRD_*` hardware type of the link.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub hardware_type: Option<u16>,
+    /// The netlink link kind, e.g. `bridge`, `veth` or `wireguard`. `None`
+    /// for physical devices, and when netlink is not available.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub link_kind: Option<String>,
+    /// The transmit queue length (`txqueuelen`), in packets.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub tx_queue_len: Option<u32>,
+    /// The number of transmit queues; software devices usually have one.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub num_tx_queues: Option<u32>,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub num_rx_queues: Option<u32>,
+    /// Alternative names given with `ip link property add altname`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub alt_names: Vec<String>,
+    /// The free-text alias set with `ip link set alias`, see
+    /// [`config::alias`](crate::config::alias).
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub alias: Option<String>,
+    /// The endpoints of `gre`, `gretap`, `ip6gre`, `ip6gretap`, `ipip`,
+    /// `sit` and `vxlan` links, see [`Interface::tunnel_endpoints`].
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub tunnel: Option<TunnelEndpoints>,
+    /// The other end of a `veth` link.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub veth_peer: Option<VethPeer>,
+    /// The index of the bridge or bond the link is a port of.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub master: Option<u32>,
+    /// The link group set with `ip link set group`, see
+    /// [`Interface::group`].
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub group: Option<u32>,
+    /// How many times promiscuous mode is asked for: once for `IFF_PROMISC`
+    /// and once per packet socket in `PACKET_MR_PROMISC`, as tcpdump's
+    /// are. See [`Interface::promiscuity`].
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub promiscuity: Option<u32>,
+    /// Whether the device is an SR-IOV physical or virtual function, from
+    /// sysfs. `None` for devices without SR-IOV.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub sriov: Option<Sriov>,
+    /// The `DEVTYPE` of the device in its sysfs `uevent`, e.g. `wlan`,
+    /// `wwan` or `bridge`. `None` for plain Ethernet devices, which have
+    /// none.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub devtype: Option<String>,
+    /// The carrier counters from sysfs, see [`Interface::carrier_changes`].
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub carrier_changes: Option<CarrierChanges>,
+    /// The `IFT_*` type of the link-layer address.
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ))]
+    pub link_type: Option<u8>,
+    /// The routing domain (`rdomain`) the interface is in, see
+    /// [`ifaces_in_rdomain`](crate::ifaces_in_rdomain).
+    #[cfg(target_os = "openbsd")]
+    pub rdomain: Option<u32>,
+    /// The locally unique identifier (`NET_LUID`) of the adapter.
+    #[cfg(target_family = "windows")]
+    pub luid: u64,
+    /// The adapter name, which is the adapter GUID in registry form.
+    #[cfg(target_family = "windows")]
+    pub adapter_name: String,
+    /// The `IF_TYPE_*` interface type.
+    #[cfg(target_family = "windows")]
+    pub if_type: u32,
+    /// The `TUNNEL_TYPE` of tunnel adapters, 0 for everything else.
+    #[cfg(target_family = "windows")]
+    pub tunnel_type: u32,
+    /// The GUID of the network the adapter is connected to; adapters on
+    /// the same LAN share it. `None` when it is not connected to one, see
+    /// [`group_by_network`](crate::group_by_network).
+    #[cfg(all(target_family = "windows", feature = "std"))]
+    pub network_guid: Option<Guid>,
+    /// What the adapter is set up for, e.g. whether it takes multicast.
+    #[cfg(all(target_family = "windows", feature = "std"))]
+    pub adapter_flags: AdapterFlags,
+    /// The on-link prefixes of the adapter, without duplicates. Besides the
+    /// networks themselves Windows lists host routes (/32 and /128) for the
+    /// adapter's own and broadcast addresses, and multicast prefixes.
+    /// `mask` on each entry is taken from the longest network in here that
+    /// holds its address.
+    #[cfg(target_family = "windows")]
+    pub prefixes: Vec<(core::net::IpAddr, u8)>,
+    #[cfg(all(target_family = "windows", feature = "std"))]
+    pub strings: AdapterStrings,
+}
+
+/// The other end of a `veth` link, see [`OsSpecific::veth_peer`].
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct VethPeer {
+    /// The index of the peer, in the namespace it is in.
+    pub peer_index: u32,
+    /// The id this namespace gave the namespace of the peer, as `ip netns
+    /// list-id` shows it. `None` when the peer is in this namespace.
+    pub netnsid: Option<i32>,
+}
+
+/// The SR-IOV side of a device, see [`OsSpecific::sriov`].
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub enum Sriov {
+    /// A physical function (PF).
+    Physical {
+        /// How many virtual functions are enabled, `sriov_numvfs`.
+        num_vfs: u32,
+        /// The enabled VFs by VF index, with their interface name. `None`
+        /// for a VF without an interface here, e.g. one passed through to
+        /// a guest.
+        vfs: Vec<(u32, Option<String>)>,
+    },
+    /// A virtual function (VF).
+    Virtual {
+        /// The index of the VF on its PF, as `ip link show <pf>` numbers
+        /// them.
+        vf_index: Option<u32>,
+        /// The interface of the PF. `None` if it has none in this network
+        /// namespace.
+        pf_name: Option<String>,
+        pf_index: Option<u32>,
+    },
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Interface {
+    pub name: String,
+    pub index: Option<u32>,
+    pub kind: Kind,
+    pub addr: Option<core::net::SocketAddr>,
+    pub mask: Option<core::net::SocketAddr>,
+    pub hop: Option<NextHop>,
+    /// How the address was configured, `Unknown` for entries without one.
+    pub origin: AddressOrigin,
+    pub flags: InterfaceFlags,
+    /// The MAC address of the interface, on every entry of it.
+    pub mac: Option<MacAddr>,
+    /// The traffic counters of the interface when it was enumerated, on
+    /// every entry of it. Linux, macOS and the BSDs fill these in.
+    pub stats: Option<InterfaceStats>,
+    pub mtu: Option<u32>,
+    #[cfg_attr(any(feature = "serde", feature = "schemars"), serde(skip))]
+    pub(crate) os: Arc<OsSpecific>,
+}
+
+impl Interface {
+    /// An entry of `kind` for the interface called `name`, with nothing
+    /// else filled in, for building entries by hand, e.g. as fixtures. Set
+    /// the public fields afterwards; the platform details are empty until
+    /// [`with_os_specific`](Interface::with_os_specific).
+    pub fn new(name: &str, kind: Kind) -> Interface {
+        Interface {
+            name: name.into(),
+            index: None,
+            kind,
+            addr: None,
+            mask: None,
+            hop: None,
+            origin: AddressOrigin::default(),
+            flags: InterfaceFlags::empty(),
+            mac: None,
+            stats: None,
+            mtu: None,
+            os: Arc::new(OsSpecific::default()),
+        }
+    }
+
+    /// This entry with `os` as its platform details. Start from
+    /// `OsSpecific::default()` and set the fields needed.
+    pub fn with_os_specific(mut self, os: OsSpecific) -> Interface {
+        self.os = Arc::new(os);
+        self
+    }
+
+    /// The prefix length encoded by `mask`, if the mask is contiguous.
+    pub fn prefix_len(&self) -> Option<u8> {
+        let bits = match self.mask?.ip() {
+            core::net::IpAddr::V4(mask) => u32::from(mask) as u128,
+            core::net::IpAddr::V6(mask) => u128::from(mask),
+        };
+        let width = match self.mask?.ip() {
+            core::net::IpAddr::V4(_) => 32,
+            core::net::IpAddr::V6(_) => 128,
+        };
+        let len = (bits << (128 - width)).leading_ones();
+        match bits.count_ones() == len {
+            true => Some(len as u8),
+            false => None,
+        }
+    }
+
+    /// Whether `ip` is on the network of this entry, as `addr` and `mask`
+    /// give it, or is the peer of a point-to-point entry. Entries without
+    /// an address or mask contain nothing.
+    pub fn contains(&self, ip: core::net::IpAddr) -> bool {
+        use core::net::IpAddr;
+
+        if let Some(NextHop::Destination(peer)) = &self.hop {
+            if peer.ip() == ip {
+                return true;
+            }
+        }
+        let bits = |addr: IpAddr| match addr {
+            IpAddr::V4(addr) => (u32::from(addr) as u128, true),
+            IpAddr::V6(addr) => (u128::from(addr), false),
+        };
+        match (self.addr, self.mask) {
+            (Some(addr), Some(mask)) => {
+                let (addr, v4) = bits(addr.ip());
+                let (mask, mask_v4) = bits(mask.ip());
+                let (ip, ip_v4) = bits(ip);
+                v4 == mask_v4 && v4 == ip_v4 && addr & mask == ip & mask
+            }
+            _ => false,
+        }
+    }
+
+    /// The address of this entry with `port`, ready to `bind()` to.
+    ///
+    /// An IPv6 link-local address is scoped to this interface, since it is
+    /// only unique on it. Any other address has its scope id and flow info
+    /// cleared. `None` if the entry has no address, or a link-local one
+    /// without an interface index to scope it with. The same address works
+    /// for connecting to this interface from the machine itself.
+    pub fn to_bind_addr(&self, port: u16) -> Option<core::net::SocketAddr> {
+        use core::net::{SocketAddr, SocketAddrV6};
+
+        match self.addr? {
+            SocketAddr::V4(addr) => Some(SocketAddr::new((*addr.ip()).into(), port)),
+            SocketAddr::V6(addr) => {
+                let link_local = addr.ip().segments()[0] & 0xffc0 == 0xfe80;
+                let scope_id = match link_local {
+                    true => match (self.index, addr.scope_id()) {
+                        (Some(index), _) => index,
+                        (None, 0) => return None,
+                        (None, scope_id) => scope_id,
+                    },
+                    false => 0,
+                };
+                Some(SocketAddr::V6(SocketAddrV6::new(
+                    *addr.ip(),
+                    port,
+                    0,
+                    scope_id,
+                )))
+            }
+        }
+    }
+
+    /// Whether this is an IPv6 entry whose address is made from the MAC of
+    /// the interface, see [`is_eui64_derived`]. `false` for every other
+    /// entry, those of interfaces without a MAC included.
+    pub fn is_eui64_derived(&self) -> bool {
+        match (self.addr, &self.mac) {
+            (Some(core::net::SocketAddr::V6(addr)), Some(mac)) => is_eui64_derived(addr.ip(), mac),
+            _ => false,
+        }
+    }
+
+    /// The endpoints of a tunnel interface.
+    ///
+    /// Linux reads them from the link info of GRE, IP-in-IP, SIT and VXLAN
+    /// links. `None` for every other link, and on the other platforms.
+    pub fn tunnel_endpoints(&self) -> Option<TunnelEndpoints> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        return self.os.tunnel;
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        return None;
+    }
+
+    /// The link group of the interface, as `ip link set group` sets it.
+    ///
+    /// Interfaces nobody put in a group are in the default group,
+    /// `Some(0)`. `None` when netlink is not available, and on platforms
+    /// other than Linux.
+    pub fn group(&self) -> Option<u32> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        return self.os.group;
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        return None;
+    }
+
+    /// How many holders keep the device in promiscuous mode, from netlink.
+    ///
+    /// Unlike [`InterfaceFlags::PROMISC`], which is only the flag
+    /// [`config::set_promiscuous`](crate::config::set_promiscuous) sets,
+    /// this counts capture tools too; non-zero means the device receives
+    /// all traffic. `None` when netlink is not available, and on platforms
+    /// other than Linux.
+    pub fn promiscuity(&self) -> Option<u32> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        return self.os.promiscuity;
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        return None;
+    }
+
+    /// How often the carrier of the link was lost and came back, to find
+    /// flapping links with [`detect_flaps`](crate::detect_flaps).
+    ///
+    /// Linux reads `carrier_changes`, `carrier_up_count` and
+    /// `carrier_down_count` from sysfs. `None` on the other platforms.
+    pub fn carrier_changes(&self) -> Option<CarrierChanges> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        return self.os.carrier_changes;
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        return None;
+    }
+
+    /// Platform-specific details, shared by all entries of the interface.
+    pub fn os_specific(&self) -> &OsSpecific {
+        &self.os
+    }
+
+    /// The adapter's strings with their original UTF-16 contents.
+    ///
+    /// `name` is the lossy conversion of the friendly name in here.
+    #[cfg(all(target_family = "windows", feature = "std"))]
+    pub fn adapter_strings(&self) -> &AdapterStrings {
+        &self.os.strings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{SocketAddr, SocketAddrV6};
+
+    use super::*;
+
+    fn entry(addr: &str, index: Option<u32>) -> Interface {
+        let mut iface = Interface::new("eth0", Kind::Ipv6);
+        iface.addr = Some(addr.parse().unwrap());
+        iface.index = index;
+        iface
+    }
+
+    fn v6(addr: &str, port: u16, scope_id: u32) -> Option<SocketAddr> {
+        Some(SocketAddr::V6(SocketAddrV6::new(
+            addr.parse().unwrap(),
+            port,
+            0,
+            scope_id,
+        )))
+    }
+
+    #[test]
+    fn link_local_addresses_are_scoped_to_the_interface() {
+        assert_eq!(
+            entry("[fe80::1]:0", Some(4)).to_bind_addr(80),
+            v6("fe80::1", 80, 4)
+        );
+        // The index wins over a stale scope id.
+        assert_eq!(
+            entry("[fe80::1%9]:0", Some(4)).to_bind_addr(80),
+            v6("fe80::1", 80, 4)
+        );
+        // Without an index the scope id is all there is.
+        assert_eq!(
+            entry("[fe80::1%9]:0", None).to_bind_addr(80),
+            v6("fe80::1", 80, 9)
+        );
+        assert_eq!(entry("[fe80::1]:0", None).to_bind_addr(80), None);
+        // All of fe80::/10.
+        assert_eq!(
+            entry("[febf::1]:0", Some(4)).to_bind_addr(0),
+            v6("febf::1", 0, 4)
+        );
+    }
+
+    #[test]
+    fn other_addresses_lose_their_scope_and_flow_info() {
+        let mut global = entry("[2001:db8::1]:0", Some(4));
+        global.addr = Some(SocketAddr::V6(SocketAddrV6::new(
+            "2001:db8::1".parse().unwrap(),
+            1234,
+            7,
+            4,
+        )));
+        assert_eq!(global.to_bind_addr(53), v6("2001:db8::1", 53, 0));
+        assert_eq!(entry("[::1]:0", Some(1)).to_bind_addr(53), v6("::1", 53, 0));
+        assert_eq!(
+            entry("[fec0::1%4]:0", Some(4)).to_bind_addr(53),
+            v6("fec0::1", 53, 0)
+        );
+    }
+
+    #[test]
+    fn ipv4_addresses_take_the_port() {
+        assert_eq!(
+            entry("192.0.2.1:9", Some(4)).to_bind_addr(53),
+            Some("192.0.2.1:53".parse().unwrap())
+        );
+        assert_eq!(Interface::new("eth0", Kind::Packet).to_bind_addr(53), None);
+    }
+
+    #[test]
+    fn only_v6_entries_with_a_mac_are_eui64_derived() {
+        let mac = Some(MacAddr::new([0x00, 0x16, 0x3e, 0x12, 0x34, 0x56]));
+        let mut slaac = entry("[2001:db8::216:3eff:fe12:3456]:0", Some(4));
+        assert!(!slaac.is_eui64_derived());
+        slaac.mac = mac;
+        assert!(slaac.is_eui64_derived());
+        let mut random = entry("[2001:db8::9c4e:71d2:a0b3:5f18]:0", Some(4));
+        random.mac = mac;
+        assert!(!random.is_eui64_derived());
+        let mut v4 = entry("192.0.2.1:0", Some(4));
+        v4.mac = mac;
+        assert!(!v4.is_eui64_derived());
+        let mut link = Interface::new("eth0", Kind::Packet);
+        link.mac = mac;
+        assert!(!link.is_eui64_derived());
+    }
+}
@@ -0,0 +1,215 @@
+use core::convert::TryInto;
+use core::fmt;
+use core::net::Ipv6Addr;
+use core::str::FromStr;
+
+/// A 48-bit MAC address.
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MacAddr([u8; 6]);
+
+impl MacAddr {
+    pub const fn new(octets: [u8; 6]) -> MacAddr {
+        MacAddr(octets)
+    }
+
+    /// Build from a hardware address, if it is six bytes long and not all
+    /// zeroes. Loopback and tunnel devices report the latter.
+    pub fn from_hardware_addr(bytes: &[u8]) -> Option<MacAddr> {
+        let octets: [u8; 6] = bytes.try_into().ok()?;
+        match octets {
+            [0, 0, 0, 0, 0, 0] => None,
+            _ => Some(MacAddr(octets)),
+        }
+    }
+
+    pub const fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+
+    /// The modified EUI-64 interface identifier SLAAC makes from this
+    /// address: `ff:fe` in the middle and the universal/local bit flipped.
+    pub const fn eui64_interface_id(&self) -> [u8; 8] {
+        let [a, b, c, d, e, f] = self.0;
+        [a ^ 0x02, b, c, 0xff, 0xfe, d, e, f]
+    }
+}
+
+/// Whether the low 64 bits of `addr` are the modified EUI-64 identifier of
+/// `mac`, see [`MacAddr::eui64_interface_id`], so that the address gives
+/// the MAC away. The prefix is not looked at.
+pub fn is_eui64_derived(addr: &Ipv6Addr, mac: &MacAddr) -> bool {
+    addr.octets()[8..] == mac.eui64_interface_id()
+}
+
+impl From<[u8; 6]> for MacAddr {
+    fn from(octets: [u8; 6]) -> MacAddr {
+        MacAddr(octets)
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            a, b, c, d, e, g
+        )
+    }
+}
+
+/// Six hex pairs separated by `:` or `-`, as [`MacAddr`]'s `Display`
+/// and Windows write them.
+impl FromStr for MacAddr {
+    type Err = MacAddrParseError;
+
+    fn from_str(s: &str) -> Result<MacAddr, MacAddrParseError> {
+        let separator = match s.as_bytes().get(2) {
+            Some(b'-') => '-',
+            _ => ':',
+        };
+        let mut octets = [0; 6];
+        let mut parts = s.split(separator);
+        for octet in &mut octets {
+            let part = parts.next().ok_or(MacAddrParseError(()))?;
+            if part.len() != 2 || !part.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(MacAddrParseError(()));
+            }
+            *octet = u8::from_str_radix(part, 16).map_err(|_| MacAddrParseError(()))?;
+        }
+        match parts.next() {
+            Some(_) => Err(MacAddrParseError(())),
+            None => Ok(MacAddr(octets)),
+        }
+    }
+}
+
+/// Why a string is not a [`MacAddr`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MacAddrParseError(());
+
+impl fmt::Display for MacAddrParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid MAC address syntax")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MacAddrParseError {}
+
+impl fmt::Debug for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_both_separators() {
+        let mac = MacAddr::new([0x02, 0x42, 0xac, 0x11, 0x00, 0x0f]);
+        assert_eq!("02:42:ac:11:00:0f".parse(), Ok(mac));
+        assert_eq!("02-42-AC-11-00-0F".parse(), Ok(mac));
+    }
+
+    #[test]
+    fn rejects_malformed_addresses() {
+        for s in [
+            "",
+            "02:42:ac:11:00",
+            "02:42:ac:11:00:0f:01",
+            "02:42:ac:11:00:f",
+            "02:42-ac:11:00:0f",
+            "02:42:ac:11:00:+f",
+            "0242.ac11.000f",
+        ] {
+            assert_eq!(s.parse::<MacAddr>(), Err(MacAddrParseError(())), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn eui64_flips_the_local_bit() {
+        let mac = MacAddr::new([0x00, 0x16, 0x3e, 0x12, 0x34, 0x56]);
+        assert_eq!(
+            mac.eui64_interface_id(),
+            [0x02, 0x16, 0x3e, 0xff, 0xfe, 0x12, 0x34, 0x56]
+        );
+        let addr: Ipv6Addr = "fe80::216:3eff:fe12:3456".parse().unwrap();
+        assert!(is_eui64_derived(&addr, &mac));
+        assert!(!is_eui64_derived(&"fe80::1".parse().unwrap(), &mac));
+    }
+
+    /// MACs and the identifiers SLAAC makes from them, from RFC 4291
+    /// appendix A and captured `ip addr` output.
+    const PAIRS: [(&str, &str); 5] = [
+        ("00:16:3e:12:34:56", "fe80::216:3eff:fe12:3456"),
+        ("3c:fd:fe:a1:b2:c3", "2001:db8:1:2:3efd:feff:fea1:b2c3"),
+        // The universal bit of a local address flips back to 0.
+        ("02:42:ac:11:00:02", "fe80::42:acff:fe11:2"),
+        ("00:00:00:00:00:00", "fe80::200:ff:fe00:0"),
+        ("ff:ff:ff:ff:ff:ff", "fe80::fdff:ffff:feff:ffff"),
+    ];
+
+    #[test]
+    fn known_pairs_are_derived() {
+        for (mac, addr) in PAIRS {
+            let mac: MacAddr = mac.parse().unwrap();
+            let addr: Ipv6Addr = addr.parse().unwrap();
+            assert!(is_eui64_derived(&addr, &mac), "{} {}", mac, addr);
+        }
+    }
+
+    #[test]
+    fn the_prefix_does_not_matter() {
+        let mac: MacAddr = "00:16:3e:12:34:56".parse().unwrap();
+        for addr in [
+            "fe80::216:3eff:fe12:3456",
+            "2001:db8::216:3eff:fe12:3456",
+            "fd00:1:2:3:216:3eff:fe12:3456",
+            "::216:3eff:fe12:3456",
+        ] {
+            assert!(is_eui64_derived(&addr.parse().unwrap(), &mac), "{}", addr);
+        }
+    }
+
+    #[test]
+    fn near_misses_are_not_derived() {
+        let mac: MacAddr = "00:16:3e:12:34:56".parse().unwrap();
+        for addr in [
+            // The u/l bit left as it is in the MAC.
+            "fe80::16:3eff:fe12:3456",
+            // Both of the low bits flipped.
+            "fe80::316:3eff:fe12:3456",
+            // Without the ff:fe, or with it the wrong way round.
+            "fe80::216:3e00:12:3456",
+            "fe80::216:3efe:ff12:3456",
+            // The MAC of a neighbor.
+            "fe80::216:3eff:fe12:3457",
+            // The identifier in the prefix half.
+            "216:3eff:fe12:3456::1",
+            // Random, as privacy extensions and stable-privacy make them.
+            "2001:db8::9c4e:71d2:a0b3:5f18",
+        ] {
+            assert!(!is_eui64_derived(&addr.parse().unwrap(), &mac), "{}", addr);
+        }
+    }
+
+    #[test]
+    fn the_local_bit_flips_both_ways() {
+        let universal = MacAddr::new([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        let local = MacAddr::new([0x02, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(universal.eui64_interface_id()[0], 0x02);
+        assert_eq!(local.eui64_interface_id()[0], 0x00);
+        let addr: Ipv6Addr = "fe80::211:22ff:fe33:4455".parse().unwrap();
+        assert!(is_eui64_derived(&addr, &universal));
+        assert!(!is_eui64_derived(&addr, &local));
+        // The multicast bit is kept.
+        let group = MacAddr::new([0x01, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(group.eui64_interface_id()[0], 0x03);
+    }
+}
@@ -0,0 +1,29 @@
+//! The types of the interface list, and the helpers on them that need no
+//! OS: address classification, prefix math and counter deltas.
+//!
+//! This is all that builds without the `std` feature, with `alloc` only,
+//! so that firmware can share the types with host tools. Everything in
+//! here is re-exported at the crate root.
+
+pub mod classify;
+mod flags;
+mod flap;
+mod interface;
+mod mac;
+mod origin;
+#[cfg(feature = "smoltcp")]
+mod smoltcp;
+mod stats;
+mod tunnel;
+
+#[cfg(feature = "smoltcp")]
+pub use self::smoltcp::from_smoltcp;
+pub use flags::{HardwareKind, InterfaceFlags};
+pub use flap::{detect_flaps, CarrierChanges};
+pub use interface::{Interface, Kind, NextHop, OsSpecific};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use interface::{Sriov, VethPeer};
+pub use mac::{is_eui64_derived, MacAddr, MacAddrParseError};
+pub use origin::AddressOrigin;
+pub use stats::{CounterWidth, InterfaceStats, StatsDelta};
+pub use tunnel::TunnelEndpoints;
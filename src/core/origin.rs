@@ -0,0 +1,97 @@
+use core::net::IpAddr;
+
+/// Where an address came from, see [`Interface::origin`].
+///
+/// The platforms record this differently and not always, so this is only
+/// set where the platform says so, and is `Unknown` otherwise:
+///
+/// - Every platform: IPv6 `fe80::/10` and IPv4 `169.254.0.0/16` are
+///   `LinkLocal`, loopback addresses are `WellKnown`.
+/// - Linux, from the netlink address flags: `IFA_F_TEMPORARY` is
+///   `SlaacTemporary`. The `IFA_PROTO` of router advertisements (kernel
+///   5.18 and later) and `IFA_F_MANAGETEMPADDR` are `Slaac`. IPv4
+///   addresses with a lifetime are `Dhcp`, since only DHCP clients set
+///   one. Addresses without a lifetime (`IFA_F_PERMANENT`) are `Static`.
+///   IPv6 addresses with a lifetime and no protocol may be SLAAC or DHCPv6,
+///   and are `Unknown`.
+/// - macOS and FreeBSD, from `SIOCGIFAFLAG_IN6`: `IN6_IFF_TEMPORARY` is
+///   `SlaacTemporary`, `IN6_IFF_AUTOCONF` is `Slaac`, and on macOS
+///   `IN6_IFF_DYNAMIC` is `Dhcp`. IPv4 addresses carry no such flags.
+/// - Windows, from the prefix and suffix origins: a router advertisement
+///   prefix with a random suffix is `SlaacTemporary`, with any other
+///   suffix `Slaac`. A DHCP prefix or suffix is `Dhcp`, manual ones are
+///   `Static` and well-known ones `WellKnown`.
+///
+/// [`Interface::origin`]: crate::Interface::origin
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum AddressOrigin {
+    /// Configured by hand, or by a tool that gave it no lifetime.
+    Static,
+    /// Leased from DHCP or DHCPv6.
+    Dhcp,
+    /// Formed from a router advertisement, with a stable interface id.
+    Slaac,
+    /// A SLAAC privacy address (RFC 8981) that is replaced over time.
+    SlaacTemporary,
+    /// Only valid on the link, such as `fe80::/10` and `169.254.0.0/16`.
+    LinkLocal,
+    /// Given by the OS itself, such as the loopback addresses.
+    WellKnown,
+    /// The platform did not say.
+    #[default]
+    Unknown,
+}
+
+impl AddressOrigin {
+    /// What the address alone tells: link-local and loopback addresses.
+    #[allow(dead_code)]
+    pub(crate) fn of_address(ip: &IpAddr) -> Option<AddressOrigin> {
+        match ip {
+            ip if ip.is_loopback() => Some(AddressOrigin::WellKnown),
+            IpAddr::V6(v6) if v6.segments()[0] & 0xffc0 == 0xfe80 => Some(AddressOrigin::LinkLocal),
+            IpAddr::V4(v4) if v4.is_link_local() => Some(AddressOrigin::LinkLocal),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addresses_tell_link_local_and_loopback() {
+        let table = [
+            ("127.0.0.1", Some(AddressOrigin::WellKnown)),
+            ("127.255.255.254", Some(AddressOrigin::WellKnown)),
+            ("::1", Some(AddressOrigin::WellKnown)),
+            ("169.254.0.1", Some(AddressOrigin::LinkLocal)),
+            ("169.254.255.255", Some(AddressOrigin::LinkLocal)),
+            ("fe80::1", Some(AddressOrigin::LinkLocal)),
+            ("febf:ffff::1", Some(AddressOrigin::LinkLocal)),
+            // Just outside.
+            ("169.255.0.1", None),
+            ("fec0::1", None),
+            ("fe7f::1", None),
+            ("192.0.2.1", None),
+            ("2001:db8::1", None),
+            ("fd00::1", None),
+        ];
+        for (ip, origin) in table {
+            assert_eq!(
+                AddressOrigin::of_address(&ip.parse().unwrap()),
+                origin,
+                "{}",
+                ip
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_is_the_default() {
+        assert_eq!(AddressOrigin::default(), AddressOrigin::Unknown);
+    }
+}
@@ -0,0 +1,197 @@
+//! [`Interface`] entries from a smoltcp interface.
+
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use ::smoltcp::iface::Interface as SmolInterface;
+use ::smoltcp::phy::Medium;
+use ::smoltcp::wire::HardwareAddress;
+
+use crate::{AddressOrigin, Interface, InterfaceFlags, Kind, MacAddr, NextHop, OsSpecific};
+
+/// The entries of smoltcp's `iface`, laid out as [`ifaces`](crate::ifaces)
+/// lists them, so firmware can report its interface in the same terms as
+/// the host tools: a `Kind::Link` entry with the MAC for Ethernet links,
+/// then one entry per IP address, in smoltcp's order.
+///
+/// `medium` is that of the device, its `capabilities().medium`, which
+/// smoltcp does not tell. smoltcp has no interface names or indexes, so
+/// they are `name` and `index`. Every entry is `UP` and `RUNNING`; Ethernet ones are also
+/// `BROADCAST` and `MULTICAST`, and all are `LOOPBACK` if every address is
+/// a loopback one. IPv4 entries have their broadcast address as the next
+/// hop, but /31 and /32 ones. There are no counters, MTU or OS details.
+pub fn from_smoltcp(
+    iface: &SmolInterface,
+    medium: Medium,
+    name: &str,
+    index: Option<u32>,
+) -> Vec<Interface> {
+    let cidrs: Vec<(IpAddr, u8)> = iface
+        .ip_addrs()
+        .iter()
+        .map(|cidr| (IpAddr::from(cidr.address()), cidr.prefix_len()))
+        .collect();
+    // Asking an interface of another medium for its hardware address is
+    // a panic.
+    let ethernet = match medium {
+        Medium::Ethernet => match iface.hardware_addr() {
+            HardwareAddress::Ethernet(addr) => Some(addr),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        },
+        #[allow(unreachable_patterns)]
+        _ => None,
+    };
+    let mac = ethernet.and_then(|addr| MacAddr::from_hardware_addr(&addr.0));
+
+    let mut flags = InterfaceFlags::UP | InterfaceFlags::RUNNING;
+    if ethernet.is_some() {
+        flags |= InterfaceFlags::BROADCAST | InterfaceFlags::MULTICAST;
+    }
+    if !cidrs.is_empty() && cidrs.iter().all(|(ip, _)| ip.is_loopback()) {
+        flags |= InterfaceFlags::LOOPBACK;
+    }
+    let os = Arc::new(OsSpecific::default());
+    let entry = |kind, addr: Option<IpAddr>, mask: Option<IpAddr>, hop| Interface {
+        name: name.to_string(),
+        index,
+        kind,
+        addr: addr.map(|ip| SocketAddr::new(ip, 0)),
+        mask: mask.map(|ip| SocketAddr::new(ip, 0)),
+        hop,
+        origin: addr
+            .and_then(|ip| AddressOrigin::of_address(&ip))
+            .unwrap_or_default(),
+        flags,
+        mac,
+        stats: None,
+        mtu: None,
+        os: os.clone(),
+    };
+
+    let mut entries = Vec::with_capacity(cidrs.len() + 1);
+    if ethernet.is_some() {
+        entries.push(entry(Kind::Link, None, None, None));
+    }
+    for &(ip, len) in &cidrs {
+        let (kind, mask, hop) = match ip {
+            IpAddr::V4(v4) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(len)).unwrap_or(0);
+                let broadcast = Ipv4Addr::from(u32::from(v4) | !mask);
+                let hop = match len < 31 {
+                    true => Some(NextHop::Broadcast(SocketAddr::new(broadcast.into(), 0))),
+                    false => None,
+                };
+                (Kind::Ipv4, IpAddr::V4(Ipv4Addr::from(mask)), hop)
+            }
+            IpAddr::V6(_) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(len)).unwrap_or(0);
+                (Kind::Ipv6, IpAddr::V6(Ipv6Addr::from(mask)), None)
+            }
+        };
+        entries.push(entry(kind, Some(ip), Some(mask), hop));
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ::smoltcp::iface::Config;
+    use ::smoltcp::phy::Loopback;
+    use ::smoltcp::time::Instant;
+    use ::smoltcp::wire::{EthernetAddress, IpCidr};
+
+    /// An interface on a loopback device of `medium` with `cidrs`.
+    fn loopback(medium: Medium, cidrs: &[&str]) -> SmolInterface {
+        let mut device = Loopback::new(medium);
+        let hardware_addr = match medium {
+            Medium::Ethernet => EthernetAddress([0x02, 0, 0, 0, 0, 0x01]).into(),
+            _ => HardwareAddress::Ip,
+        };
+        let mut iface = SmolInterface::new(
+            Config::new(hardware_addr),
+            &mut device,
+            Instant::from_millis(0),
+        );
+        iface.update_ip_addrs(|addrs| {
+            for cidr in cidrs {
+                addrs.push(cidr.parse::<IpCidr>().unwrap()).unwrap();
+            }
+        });
+        iface
+    }
+
+    fn socket(s: &str) -> Option<SocketAddr> {
+        Some(SocketAddr::new(s.parse().unwrap(), 0))
+    }
+
+    #[test]
+    fn ethernet_links_come_first() {
+        let iface = loopback(Medium::Ethernet, &["192.168.1.10/24", "fd00::1/64"]);
+        let entries = from_smoltcp(&iface, Medium::Ethernet, "eth0", Some(1));
+        let kinds: Vec<Kind> = entries.iter().map(|entry| entry.kind.clone()).collect();
+        assert_eq!(kinds, [Kind::Link, Kind::Ipv4, Kind::Ipv6]);
+        let mac = MacAddr::new([0x02, 0, 0, 0, 0, 0x01]);
+        for entry in &entries {
+            assert_eq!(entry.name, "eth0");
+            assert_eq!(entry.index, Some(1));
+            assert_eq!(entry.mac, Some(mac));
+            assert_eq!(
+                entry.flags,
+                InterfaceFlags::UP
+                    | InterfaceFlags::RUNNING
+                    | InterfaceFlags::BROADCAST
+                    | InterfaceFlags::MULTICAST
+            );
+        }
+        assert_eq!(entries[0].addr, None);
+        assert_eq!(entries[1].addr, socket("192.168.1.10"));
+        assert_eq!(entries[1].mask, socket("255.255.255.0"));
+        assert_eq!(
+            entries[1].hop,
+            socket("192.168.1.255").map(NextHop::Broadcast)
+        );
+        assert_eq!(entries[2].addr, socket("fd00::1"));
+        assert_eq!(entries[2].prefix_len(), Some(64));
+        assert_eq!(entries[2].hop, None);
+    }
+
+    #[test]
+    fn ip_loopbacks_have_no_link_entry() {
+        let iface = loopback(Medium::Ip, &["127.0.0.1/8", "::1/128"]);
+        let entries = from_smoltcp(&iface, Medium::Ip, "lo", None);
+        let kinds: Vec<Kind> = entries.iter().map(|entry| entry.kind.clone()).collect();
+        assert_eq!(kinds, [Kind::Ipv4, Kind::Ipv6]);
+        for entry in &entries {
+            assert_eq!(entry.mac, None);
+            assert_eq!(
+                entry.flags,
+                InterfaceFlags::UP | InterfaceFlags::RUNNING | InterfaceFlags::LOOPBACK
+            );
+        }
+        assert_eq!(entries[0].prefix_len(), Some(8));
+        assert_eq!(entries[1].prefix_len(), Some(128));
+    }
+
+    #[test]
+    fn narrow_prefixes_have_no_broadcast() {
+        let iface = loopback(Medium::Ip, &["10.0.0.0/31", "10.0.1.1/32"]);
+        let entries = from_smoltcp(&iface, Medium::Ip, "tun0", Some(3));
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| entry.hop.is_none()));
+        assert!(!entries[0].flags.contains(InterfaceFlags::LOOPBACK));
+    }
+
+    #[test]
+    fn links_without_addresses_are_not_loopback() {
+        let iface = loopback(Medium::Ethernet, &[]);
+        let entries = from_smoltcp(&iface, Medium::Ethernet, "eth0", None);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind, Kind::Link);
+        assert!(!entries[0].flags.contains(InterfaceFlags::LOOPBACK));
+    }
+}
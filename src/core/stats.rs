@@ -0,0 +1,275 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+/// How wide the counters the platform handed out are.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum CounterWidth {
+    /// 32-bit counters, which wrap after 4 GiB. Older Linux kernels, and
+    /// `getifaddrs` when netlink is not available.
+    Bits32,
+    #[default]
+    Bits64,
+}
+
+/// Traffic counters of an interface, see [`Interface::stats`].
+///
+/// The counters count up from when the device was created and are only
+/// meaningful relative to an earlier sample, see [`StatsDelta`].
+///
+/// [`Interface::stats`]: crate::Interface::stats
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct InterfaceStats {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+    pub multicast: u64,
+    pub collisions: u64,
+    pub width: CounterWidth,
+}
+
+/// Per-second rates between two samples of [`InterfaceStats`].
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct StatsDelta {
+    pub rx_bytes: f64,
+    pub tx_bytes: f64,
+    pub rx_packets: f64,
+    pub tx_packets: f64,
+    pub rx_errors: f64,
+    pub tx_errors: f64,
+    pub rx_dropped: f64,
+    pub tx_dropped: f64,
+    pub multicast: f64,
+    pub collisions: f64,
+    /// The counters went backwards, most likely because the interface was
+    /// removed and created again between the samples.
+    pub reset: bool,
+}
+
+impl StatsDelta {
+    /// The rates from `prev` to `next`, taken `elapsed` apart.
+    ///
+    /// A 32-bit counter that went backwards is taken to have wrapped once if
+    /// that puts it less than half its range ahead. Anything else that goes
+    /// backwards, and a change of [`CounterWidth`], is a reset: `reset` is
+    /// set and the counters of `next` are taken as the traffic since the
+    /// reset. With no time elapsed all rates are zero.
+    pub fn between(prev: &InterfaceStats, next: &InterfaceStats, elapsed: Duration) -> StatsDelta {
+        let (before, after) = (counters(prev), counters(next));
+        let increases: Option<Vec<u64>> = match prev.width == next.width {
+            true => before
+                .iter()
+                .zip(after.iter())
+                .map(|(&a, &b)| increase(a, b, next.width))
+                .collect(),
+            false => None,
+        };
+        let reset = increases.is_none();
+        let deltas = increases.unwrap_or_else(|| after.to_vec());
+
+        let secs = elapsed.as_secs_f64();
+        let rate = |i: usize| match secs > 0.0 {
+            true => deltas[i] as f64 / secs,
+            false => 0.0,
+        };
+        StatsDelta {
+            rx_bytes: rate(0),
+            tx_bytes: rate(1),
+            rx_packets: rate(2),
+            tx_packets: rate(3),
+            rx_errors: rate(4),
+            tx_errors: rate(5),
+            rx_dropped: rate(6),
+            tx_dropped: rate(7),
+            multicast: rate(8),
+            collisions: rate(9),
+            reset,
+        }
+    }
+}
+
+const COUNTERS: usize = 10;
+
+fn counters(stats: &InterfaceStats) -> [u64; COUNTERS] {
+    [
+        stats.rx_bytes,
+        stats.tx_bytes,
+        stats.rx_packets,
+        stats.tx_packets,
+        stats.rx_errors,
+        stats.tx_errors,
+        stats.rx_dropped,
+        stats.tx_dropped,
+        stats.multicast,
+        stats.collisions,
+    ]
+}
+
+/// How far a counter moved forward, or `None` if it was reset.
+fn increase(prev: u64, next: u64, width: CounterWidth) -> Option<u64> {
+    if next >= prev {
+        return Some(next - prev);
+    }
+    match width {
+        CounterWidth::Bits32 => {
+            let wrapped = next.wrapping_add(1 << 32).wrapping_sub(prev);
+            match wrapped < 1 << 31 {
+                true => Some(wrapped),
+                false => None,
+            }
+        }
+        CounterWidth::Bits64 => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rx(rx_bytes: u64, width: CounterWidth) -> InterfaceStats {
+        InterfaceStats {
+            rx_bytes,
+            width,
+            ..InterfaceStats::default()
+        }
+    }
+
+    #[test]
+    fn increases() {
+        use CounterWidth::{Bits32, Bits64};
+        const MAX32: u64 = u32::MAX as u64;
+        let table: &[(u64, u64, CounterWidth, Option<u64>)] = &[
+            (0, 0, Bits64, Some(0)),
+            (100, 1100, Bits64, Some(1000)),
+            (100, 1100, Bits32, Some(1000)),
+            // A 32-bit counter wraps once.
+            (MAX32 - 99, 900, Bits32, Some(1000)),
+            (MAX32, 0, Bits32, Some(1)),
+            // Just under half the range ahead is still a wrap, half is not.
+            ((1 << 31) + 1, 0, Bits32, Some((1 << 31) - 1)),
+            (1 << 31, 0, Bits32, None),
+            (5000, 10, Bits32, None),
+            // 64-bit counters do not wrap in practice.
+            (u64::MAX - 99, 900, Bits64, None),
+            (5000, 10, Bits64, None),
+            (0, u64::MAX, Bits64, Some(u64::MAX)),
+        ];
+        for &(prev, next, width, expected) in table {
+            assert_eq!(
+                increase(prev, next, width),
+                expected,
+                "{} to {} ({:?})",
+                prev,
+                next,
+                width
+            );
+        }
+    }
+
+    #[test]
+    fn rates_are_per_second() {
+        let delta = StatsDelta::between(
+            &rx(1000, CounterWidth::Bits64),
+            &rx(3000, CounterWidth::Bits64),
+            Duration::from_millis(500),
+        );
+        assert_eq!(delta.rx_bytes, 4000.0);
+        assert_eq!(delta.tx_bytes, 0.0);
+        assert!(!delta.reset);
+    }
+
+    #[test]
+    fn every_counter_is_rated() {
+        let prev = InterfaceStats::default();
+        let next = InterfaceStats {
+            rx_bytes: 1,
+            tx_bytes: 2,
+            rx_packets: 3,
+            tx_packets: 4,
+            rx_errors: 5,
+            tx_errors: 6,
+            rx_dropped: 7,
+            tx_dropped: 8,
+            multicast: 9,
+            collisions: 10,
+            width: CounterWidth::Bits64,
+        };
+        let delta = StatsDelta::between(&prev, &next, Duration::from_secs(1));
+        let rates = [
+            delta.rx_bytes,
+            delta.tx_bytes,
+            delta.rx_packets,
+            delta.tx_packets,
+            delta.rx_errors,
+            delta.tx_errors,
+            delta.rx_dropped,
+            delta.tx_dropped,
+            delta.multicast,
+            delta.collisions,
+        ];
+        assert_eq!(rates, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0]);
+    }
+
+    #[test]
+    fn wraps_are_not_resets() {
+        let delta = StatsDelta::between(
+            &rx(u64::from(u32::MAX) - 999, CounterWidth::Bits32),
+            &rx(1000, CounterWidth::Bits32),
+            Duration::from_secs(2),
+        );
+        assert_eq!(delta.rx_bytes, 1000.0);
+        assert!(!delta.reset);
+    }
+
+    #[test]
+    fn resets_count_from_zero() {
+        let mut prev = rx(1_000_000, CounterWidth::Bits64);
+        prev.tx_bytes = 10;
+        let mut next = rx(500, CounterWidth::Bits64);
+        next.tx_bytes = 20;
+        let delta = StatsDelta::between(&prev, &next, Duration::from_secs(1));
+        assert!(delta.reset);
+        // Every counter starts over, not just the one that went back.
+        assert_eq!(delta.rx_bytes, 500.0);
+        assert_eq!(delta.tx_bytes, 20.0);
+    }
+
+    #[test]
+    fn width_changes_are_resets() {
+        let delta = StatsDelta::between(
+            &rx(100, CounterWidth::Bits32),
+            &rx(200, CounterWidth::Bits64),
+            Duration::from_secs(1),
+        );
+        assert!(delta.reset);
+        assert_eq!(delta.rx_bytes, 200.0);
+    }
+
+    #[test]
+    fn no_elapsed_time_is_no_rate() {
+        let delta = StatsDelta::between(
+            &rx(100, CounterWidth::Bits64),
+            &rx(200, CounterWidth::Bits64),
+            Duration::ZERO,
+        );
+        assert_eq!(delta.rx_bytes, 0.0);
+        assert!(!delta.reset);
+        let delta = StatsDelta::between(
+            &rx(200, CounterWidth::Bits64),
+            &rx(100, CounterWidth::Bits64),
+            Duration::ZERO,
+        );
+        assert_eq!(delta.rx_bytes, 0.0);
+        assert!(delta.reset);
+    }
+}
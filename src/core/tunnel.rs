@@ -0,0 +1,18 @@
+use core::net::IpAddr;
+
+/// The endpoints of a tunnel interface, see [`Interface::tunnel_endpoints`].
+///
+/// Each field is `None` where the tunnel does not fix it: a GRE tunnel
+/// without a remote (NBMA) sends to whatever the routes say, a VXLAN
+/// device without a group learns its peers, and tunnels bound to no local
+/// address pick one per packet.
+///
+/// [`Interface::tunnel_endpoints`]: crate::Interface::tunnel_endpoints
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Default)]
+pub struct TunnelEndpoints {
+    pub local: Option<IpAddr>,
+    /// The peer, or the multicast group of a VXLAN device.
+    pub remote: Option<IpAddr>,
+    /// The VXLAN network identifier.
+    pub vni: Option<u32>,
+}
@@ -0,0 +1,138 @@
+use std::io;
+use std::net::Ipv6Addr;
+use std::time::{Duration, Instant};
+
+use crate::wait::Changes;
+
+/// How duplicate address detection for an address ended, see
+/// [`wait_for_dad`].
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DadOutcome {
+    /// No other node has the address, and it can be bound to.
+    Preferred,
+    /// Another node on the link has the address. The OS keeps it off, and
+    /// it must not be used; remove it and pick another.
+    Duplicate,
+    /// Detection was still running when the timeout passed, or the address
+    /// never showed up.
+    TimedOut,
+}
+
+/// Where detection for one address stands. Only Linux and Windows tell.
+#[cfg_attr(
+    not(any(target_os = "linux", target_os = "android", target_family = "windows")),
+    allow(dead_code)
+)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub(crate) enum DadState {
+    Tentative,
+    Duplicate,
+    /// Done, or skipped (optimistic DAD, or DAD switched off).
+    Usable,
+}
+
+/// Block until duplicate address detection for `addr` on interface `name`
+/// finishes, or `timeout` passes.
+///
+/// A freshly added IPv6 address is tentative until detection is done, one
+/// second or so, and binding to it fails with `EADDRNOTAVAIL` until then.
+/// An address that is not there yet is waited for too. Linux reads the
+/// address flags over netlink and Windows the DAD state of the adapter
+/// address; other platforms fail with `ErrorKind::Unsupported`. An
+/// interface that does not exist gives `ErrorKind::NotFound`.
+pub fn wait_for_dad(name: &str, addr: Ipv6Addr, timeout: Duration) -> io::Result<DadOutcome> {
+    let mut changes = Changes::new();
+    dad_with(
+        || crate::ffi::dad_state(name, addr),
+        |remaining| changes.wait(remaining),
+        timeout,
+    )
+}
+
+/// [`wait_for_dad`] over any source of states and of change waits.
+fn dad_with<S, W>(mut state: S, mut wait: W, timeout: Duration) -> io::Result<DadOutcome>
+where
+    S: FnMut() -> io::Result<Option<DadState>>,
+    W: FnMut(Duration) -> io::Result<()>,
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        match state()? {
+            Some(DadState::Usable) => return Ok(DadOutcome::Preferred),
+            Some(DadState::Duplicate) => return Ok(DadOutcome::Duplicate),
+            Some(DadState::Tentative) | None => {}
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(DadOutcome::TimedOut);
+        }
+        wait(remaining)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    /// Waits for `states` in turn, the last one for good, with `waited`
+    /// counting the waits.
+    fn run(states: &[Option<DadState>], timeout: Duration) -> (io::Result<DadOutcome>, u32) {
+        let waited = Cell::new(0);
+        let outcome = dad_with(
+            || Ok(states[(waited.get() as usize).min(states.len() - 1)]),
+            |remaining| {
+                waited.set(waited.get() + 1);
+                std::thread::sleep(remaining.min(Duration::from_millis(1)));
+                Ok(())
+            },
+            timeout,
+        );
+        (outcome, waited.get())
+    }
+
+    #[test]
+    fn tentative_addresses_become_preferred() {
+        let long = Duration::from_secs(5);
+        let (outcome, waited) = run(&[Some(DadState::Usable)], long);
+        assert_eq!((outcome.unwrap(), waited), (DadOutcome::Preferred, 0));
+        let states = [None, Some(DadState::Tentative), Some(DadState::Usable)];
+        let (outcome, waited) = run(&states, long);
+        assert_eq!((outcome.unwrap(), waited), (DadOutcome::Preferred, 2));
+    }
+
+    #[test]
+    fn duplicates_are_told_apart() {
+        let states = [Some(DadState::Tentative), Some(DadState::Duplicate)];
+        let (outcome, waited) = run(&states, Duration::from_secs(5));
+        assert_eq!((outcome.unwrap(), waited), (DadOutcome::Duplicate, 1));
+    }
+
+    #[test]
+    fn detection_that_does_not_finish_times_out() {
+        let (outcome, _) = run(&[Some(DadState::Tentative)], Duration::from_millis(20));
+        assert_eq!(outcome.unwrap(), DadOutcome::TimedOut);
+        // An address that never shows up.
+        let (outcome, _) = run(&[None], Duration::from_millis(20));
+        assert_eq!(outcome.unwrap(), DadOutcome::TimedOut);
+    }
+
+    #[test]
+    fn errors_end_the_wait() {
+        let err = dad_with(
+            || Err(io::Error::from(io::ErrorKind::NotFound)),
+            |_| Ok(()),
+            Duration::from_secs(5),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = dad_with(
+            || Ok(Some(DadState::Tentative)),
+            |_| Err(io::Error::from(io::ErrorKind::BrokenPipe)),
+            Duration::from_secs(5),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
@@ -0,0 +1,357 @@
+//! ESP-IDF, through the `esp_netif` layer over lwIP.
+//!
+//! The functions are declared here rather than taken from `esp-idf-sys`;
+//! the application links ESP-IDF anyway. Interfaces are named by their
+//! `esp_netif` key, e.g. `WIFI_STA_DEF` or `ETH_DEF`, and the index is the
+//! lwIP netif number plus one, as lwIP's `if_nametoindex` counts.
+
+#![cfg_attr(not(target_os = "espidf"), allow(dead_code, unused_imports))]
+
+use std::ffi::CStr;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::sync::Arc;
+
+use crate::{
+    AddressOrigin, Interface, InterfaceFlags, Kind, MacAddr, NextHop, OsSpecific, Warning,
+};
+
+/// `CONFIG_LWIP_IPV6_NUM_ADDRESSES`, which ESP-IDF only lets be 3.
+const IPV6_NUM_ADDRESSES: usize = 3;
+
+const ESP_OK: c_int = 0;
+
+/// `esp_ip4_addr_t`, in network byte order.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EspIp4Addr {
+    pub addr: u32,
+}
+
+/// `esp_netif_ip_info_t`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EspNetifIpInfo {
+    pub ip: EspIp4Addr,
+    pub netmask: EspIp4Addr,
+    pub gw: EspIp4Addr,
+}
+
+/// `esp_ip6_addr_t`: the address as four words in network byte order, and
+/// the lwIP zone.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EspIp6Addr {
+    pub addr: [u32; 4],
+    pub zone: u8,
+}
+
+#[allow(non_camel_case_types)]
+type esp_netif_t = c_void;
+
+#[cfg(target_os = "espidf")]
+extern "C" {
+    fn esp_netif_next(netif: *mut esp_netif_t) -> *mut esp_netif_t;
+    fn esp_netif_get_ifkey(netif: *mut esp_netif_t) -> *const c_char;
+    fn esp_netif_is_netif_up(netif: *mut esp_netif_t) -> bool;
+    fn esp_netif_get_netif_impl_index(netif: *mut esp_netif_t) -> c_int;
+    fn esp_netif_get_mac(netif: *mut esp_netif_t, mac: *mut u8) -> c_int;
+    fn esp_netif_get_ip_info(netif: *mut esp_netif_t, info: *mut EspNetifIpInfo) -> c_int;
+    fn esp_netif_get_all_ip6(netif: *mut esp_netif_t, addrs: *mut EspIp6Addr) -> c_int;
+}
+
+/// What `esp_netif` says about one interface.
+#[derive(Clone, Debug, Default)]
+pub struct Netif {
+    pub key: String,
+    pub index: Option<u32>,
+    pub up: bool,
+    /// All zeros for interfaces without one, such as PPP.
+    pub mac: [u8; 6],
+    pub ip_info: EspNetifIpInfo,
+    pub ip6: Vec<EspIp6Addr>,
+}
+
+fn ip4(addr: EspIp4Addr) -> Ipv4Addr {
+    Ipv4Addr::from(addr.addr.to_ne_bytes())
+}
+
+fn ip6(addr: &EspIp6Addr) -> Ipv6Addr {
+    let mut octets = [0; 16];
+    for (chunk, word) in octets.chunks_mut(4).zip(addr.addr) {
+        chunk.copy_from_slice(&word.to_ne_bytes());
+    }
+    Ipv6Addr::from(octets)
+}
+
+/// The entries of one interface: its IPv4 address, if it has one yet, and
+/// its IPv6 addresses.
+///
+/// lwIP keeps no prefix length with IPv6 addresses, so their `mask` is
+/// `None`. Interfaces with a MAC are taken to be Ethernet-like, with
+/// broadcast and multicast.
+pub fn map_netif(netif: &Netif) -> Vec<Interface> {
+    let os = Arc::new(OsSpecific::default());
+    let mac = MacAddr::from_hardware_addr(&netif.mac);
+    let mut flags = InterfaceFlags::empty();
+    if netif.up {
+        flags |= InterfaceFlags::UP | InterfaceFlags::RUNNING;
+    }
+    if mac.is_some() {
+        flags |= InterfaceFlags::BROADCAST | InterfaceFlags::MULTICAST;
+    }
+    let entry = |kind, addr: IpAddr, mask: Option<IpAddr>, hop| Interface {
+        name: netif.key.clone(),
+        index: netif.index,
+        kind,
+        addr: Some(SocketAddr::new(addr, 0)),
+        mask: mask.map(|mask| SocketAddr::new(mask, 0)),
+        hop,
+        origin: AddressOrigin::of_address(&addr).unwrap_or_default(),
+        flags,
+        mac,
+        stats: None,
+        mtu: None,
+        os: os.clone(),
+    };
+
+    let mut ret = Vec::new();
+    let addr = ip4(netif.ip_info.ip);
+    if !addr.is_unspecified() {
+        let mask = ip4(netif.ip_info.netmask);
+        let broadcast = Ipv4Addr::from(u32::from(addr) | !u32::from(mask));
+        let hop = mac.map(|_| NextHop::Broadcast(SocketAddr::new(broadcast.into(), 0)));
+        ret.push(entry(Kind::Ipv4, addr.into(), Some(mask.into()), hop));
+    }
+    for addr in &netif.ip6 {
+        let addr = ip6(addr);
+        if !addr.is_unspecified() {
+            ret.push(entry(Kind::Ipv6, addr.into(), None, None));
+        }
+    }
+    ret
+}
+
+/// Read what `esp_netif` has on `netif`.
+///
+/// # Safety
+///
+/// `netif` must be a live handle from `esp_netif_next`.
+#[cfg(target_os = "espidf")]
+unsafe fn read_netif(netif: *mut esp_netif_t) -> Netif {
+    let key = esp_netif_get_ifkey(netif);
+    let mut ret = Netif {
+        key: match key.is_null() {
+            true => String::new(),
+            false => CStr::from_ptr(key).to_string_lossy().into_owned(),
+        },
+        index: match esp_netif_get_netif_impl_index(netif) {
+            index if index > 0 => Some(index as u32),
+            _ => None,
+        },
+        up: esp_netif_is_netif_up(netif),
+        ..Netif::default()
+    };
+    if esp_netif_get_mac(netif, ret.mac.as_mut_ptr()) != ESP_OK {
+        ret.mac = [0; 6];
+    }
+    if esp_netif_get_ip_info(netif, &mut ret.ip_info) != ESP_OK {
+        ret.ip_info = EspNetifIpInfo::default();
+    }
+    let mut ip6 = [EspIp6Addr::default(); IPV6_NUM_ADDRESSES];
+    let count = esp_netif_get_all_ip6(netif, ip6.as_mut_ptr());
+    ret.ip6 = ip6[..count.clamp(0, IPV6_NUM_ADDRESSES as c_int) as usize].to_vec();
+    ret
+}
+
+#[cfg(target_os = "espidf")]
+pub fn ifaces() -> io::Result<Vec<Interface>> {
+    ifaces_with_warnings().map(|(ifaces, _)| ifaces)
+}
+
+/// Walk the `esp_netif` list. Nothing on it can be skipped, so there are
+/// never warnings.
+#[cfg(target_os = "espidf")]
+pub fn ifaces_with_warnings() -> io::Result<(Vec<Interface>, Vec<Warning>)> {
+    let mut ret = Vec::new();
+    let mut netif = unsafe { esp_netif_next(ptr::null_mut()) };
+    while !netif.is_null() {
+        ret.extend(map_netif(&unsafe { read_netif(netif) }));
+        netif = unsafe { esp_netif_next(netif) };
+    }
+    Ok((ret, Vec::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+    use std::mem;
+
+    use super::*;
+
+    /// An `esp_ip4_addr_t` as `ESP_IP4TOADDR` makes it.
+    fn v4(octets: [u8; 4]) -> EspIp4Addr {
+        EspIp4Addr {
+            addr: u32::from_ne_bytes(octets),
+        }
+    }
+
+    /// An `esp_ip6_addr_t` of `addr`, as lwIP fills it in.
+    fn v6(addr: &str, zone: u8) -> EspIp6Addr {
+        let octets = addr.parse::<Ipv6Addr>().unwrap().octets();
+        let mut words = [0; 4];
+        for (word, chunk) in words.iter_mut().zip(octets.chunks(4)) {
+            *word = u32::from_ne_bytes(chunk.try_into().unwrap());
+        }
+        EspIp6Addr { addr: words, zone }
+    }
+
+    fn sta() -> Netif {
+        Netif {
+            key: "WIFI_STA_DEF".to_owned(),
+            index: Some(2),
+            up: true,
+            mac: [0x24, 0x0a, 0xc4, 0x12, 0x34, 0x56],
+            ip_info: EspNetifIpInfo {
+                ip: v4([192, 168, 4, 2]),
+                netmask: v4([255, 255, 255, 0]),
+                gw: v4([192, 168, 4, 1]),
+            },
+            ip6: vec![
+                v6("fe80::260a:c4ff:fe12:3456", 2),
+                v6("2001:db8::260a:c4ff:fe12:3456", 0),
+                EspIp6Addr::default(),
+            ],
+        }
+    }
+
+    #[test]
+    fn structs_have_the_c_layout() {
+        assert_eq!(mem::size_of::<EspIp4Addr>(), 4);
+        assert_eq!(mem::size_of::<EspNetifIpInfo>(), 12);
+        assert_eq!(mem::offset_of!(EspNetifIpInfo, netmask), 4);
+        assert_eq!(mem::offset_of!(EspNetifIpInfo, gw), 8);
+        assert_eq!(mem::offset_of!(EspIp6Addr, zone), 16);
+        assert_eq!(mem::size_of::<EspIp6Addr>(), 20);
+    }
+
+    #[test]
+    fn addresses_are_in_network_byte_order() {
+        assert_eq!(ip4(v4([192, 168, 4, 2])), Ipv4Addr::new(192, 168, 4, 2));
+        let addr: Ipv6Addr = "2001:db8::260a:c4ff:fe12:3456".parse().unwrap();
+        assert_eq!(ip6(&v6("2001:db8::260a:c4ff:fe12:3456", 0)), addr);
+    }
+
+    #[test]
+    fn stations_have_an_entry_per_address() {
+        let ifaces = map_netif(&sta());
+        assert_eq!(ifaces.len(), 3);
+        let v4 = &ifaces[0];
+        assert_eq!(v4.kind, Kind::Ipv4);
+        assert_eq!(v4.addr, Some("192.168.4.2:0".parse().unwrap()));
+        assert_eq!(v4.mask, Some("255.255.255.0:0".parse().unwrap()));
+        assert_eq!(v4.prefix_len(), Some(24));
+        assert_eq!(
+            v4.hop,
+            Some(NextHop::Broadcast("192.168.4.255:0".parse().unwrap()))
+        );
+        assert_eq!(v4.origin, AddressOrigin::Unknown);
+
+        let link_local = &ifaces[1];
+        assert_eq!(link_local.kind, Kind::Ipv6);
+        assert_eq!(
+            link_local.addr.unwrap().ip(),
+            "fe80::260a:c4ff:fe12:3456".parse::<IpAddr>().unwrap()
+        );
+        // lwIP keeps no prefix lengths.
+        assert_eq!(link_local.mask, None);
+        assert_eq!(link_local.hop, None);
+        assert_eq!(link_local.origin, AddressOrigin::LinkLocal);
+        assert_eq!(
+            ifaces[2].addr.unwrap().ip(),
+            "2001:db8::260a:c4ff:fe12:3456".parse::<IpAddr>().unwrap()
+        );
+
+        for iface in &ifaces {
+            assert_eq!(iface.name, "WIFI_STA_DEF");
+            assert_eq!(iface.index, Some(2));
+            assert_eq!(
+                iface.mac,
+                Some(MacAddr::from([0x24, 0x0a, 0xc4, 0x12, 0x34, 0x56]))
+            );
+            assert_eq!(
+                iface.flags,
+                InterfaceFlags::UP
+                    | InterfaceFlags::RUNNING
+                    | InterfaceFlags::BROADCAST
+                    | InterfaceFlags::MULTICAST
+            );
+            assert_eq!(iface.mtu, None);
+            assert_eq!(iface.stats, None);
+        }
+    }
+
+    #[test]
+    fn interfaces_without_addresses_have_no_entries() {
+        // A station before it got a lease.
+        let netif = Netif {
+            ip_info: EspNetifIpInfo::default(),
+            ip6: Vec::new(),
+            ..sta()
+        };
+        assert!(map_netif(&netif).is_empty());
+        let netif = Netif {
+            ip6: vec![EspIp6Addr::default(); IPV6_NUM_ADDRESSES],
+            ..netif
+        };
+        assert!(map_netif(&netif).is_empty());
+    }
+
+    #[test]
+    fn down_interfaces_are_not_up() {
+        let netif = Netif { up: false, ..sta() };
+        let ifaces = map_netif(&netif);
+        assert!(ifaces.iter().all(|iface| !iface
+            .flags
+            .intersects(InterfaceFlags::UP | InterfaceFlags::RUNNING)));
+        assert!(ifaces
+            .iter()
+            .all(|iface| iface.flags.contains(InterfaceFlags::BROADCAST)));
+    }
+
+    #[test]
+    fn interfaces_without_a_mac_are_point_to_point_like() {
+        // A PPP modem: no MAC, so no broadcast.
+        let netif = Netif {
+            key: "PPP_DEF".to_owned(),
+            mac: [0; 6],
+            ip_info: EspNetifIpInfo {
+                ip: v4([10, 64, 0, 7]),
+                netmask: v4([255, 255, 255, 255]),
+                gw: v4([10, 64, 0, 1]),
+            },
+            ip6: Vec::new(),
+            ..sta()
+        };
+        let ifaces = map_netif(&netif);
+        assert_eq!(ifaces.len(), 1);
+        assert_eq!(ifaces[0].mac, None);
+        assert_eq!(ifaces[0].hop, None);
+        assert_eq!(
+            ifaces[0].flags,
+            InterfaceFlags::UP | InterfaceFlags::RUNNING
+        );
+        assert_eq!(ifaces[0].prefix_len(), Some(32));
+    }
+
+    #[test]
+    fn netifs_without_an_index_have_none() {
+        let netif = Netif {
+            index: None,
+            ..sta()
+        };
+        assert!(map_netif(&netif).iter().all(|iface| iface.index.is_none()));
+    }
+}
@@ -1,9 +1,132 @@
 #[cfg(target_family = "windows")]
 mod windows;
+#[cfg(all(target_family = "windows", feature = "pktinfo"))]
+pub(crate) use windows::pktinfo::{enable_pktinfo, recv_pktinfo};
+#[cfg(all(target_family = "windows", feature = "resolve"))]
+pub(crate) use windows::resolve::ptr_name;
+#[cfg(all(target_family = "windows", feature = "sys"))]
+#[doc(hidden)]
+pub use windows::sys::{Adapter, AdapterList, Adapters};
+#[cfg(all(target_family = "windows", feature = "watch"))]
+pub(crate) use windows::watch::Watcher;
+#[cfg(all(target_family = "windows", feature = "sys"))]
+#[doc(hidden)]
+pub use windows::IpAdapterAddresses;
 #[cfg(target_family = "windows")]
-pub use windows::ifaces;
+pub use windows::{
+    bind_socket_to_interface, dhcp_lease, group_by_network, ifaces, ifaces_with_warnings,
+    interface_of_socket, ip_interface_settings, AdapterFlags, AdapterStrings, DhcpLease, Guid,
+    IpInterfaceSettings, LinkLocalBehavior, RouterDiscovery, WideString,
+};
+#[cfg(target_family = "windows")]
+pub(crate) use windows::{
+    config, dad_state, deprecated_or_temporary_v6, hardware_kind, if_index, index_name, is_metered,
+    is_wireless,
+};
+#[cfg(target_family = "windows")]
+#[doc(hidden)]
+pub use windows::{fill_adapter_buffer, map_adapter_buffer};
 
-#[cfg(target_family = "unix")]
+#[cfg(all(
+    target_family = "unix",
+    not(any(target_os = "redox", target_os = "espidf"))
+))]
 mod unix;
-#[cfg(target_family = "unix")]
-pub use unix::ifaces;
+#[cfg(target_os = "android")]
+pub use unix::android::{bind_socket_to_network, network_ifaces};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use unix::conf::{
+    ipv4_settings, ipv6_settings, AcceptRa, Ipv4Settings, Ipv6Settings, RpFilter,
+};
+#[cfg(target_os = "macos")]
+pub(crate) use unix::darwin::vpn_services;
+#[cfg(target_os = "macos")]
+pub use unix::darwin::{primary_interface, service_order};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use unix::ethtool::{
+    offloads, timestamping_info, Offload, OffloadInfo, RxFilters, TimestampingFlags,
+    TimestampingInfo, TxTypes,
+};
+#[cfg(target_os = "openbsd")]
+pub use unix::ifaces_in_rdomain;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use unix::ipv6::{ipv6_addr_gen, AddrGenMode, Ipv6AddrGen};
+#[cfg(all(target_os = "linux", feature = "networkmanager"))]
+pub(crate) use unix::networkmanager::nm_metered;
+#[cfg(all(target_os = "linux", feature = "networkmanager"))]
+pub use unix::networkmanager::{nm_connectivity, nm_device, Connectivity, NmDevice, NmDeviceState};
+#[cfg(all(
+    feature = "pktinfo",
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios"
+    )
+))]
+pub(crate) use unix::pktinfo::{enable_pktinfo, recv_pktinfo};
+#[cfg(all(
+    target_family = "unix",
+    not(any(target_os = "redox", target_os = "espidf")),
+    feature = "resolve"
+))]
+pub(crate) use unix::resolve::ptr_name;
+#[cfg(all(
+    target_family = "unix",
+    not(any(target_os = "redox", target_os = "espidf")),
+    feature = "sys"
+))]
+#[doc(hidden)]
+pub use unix::sys::{IfAddr, IfAddrs, IfAddrsIter};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use unix::topology::{nic_topology, IrqInfo, NicTopology};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[doc(hidden)]
+pub use unix::union_ifa_ifu;
+#[cfg(all(
+    target_family = "unix",
+    not(any(target_os = "redox", target_os = "espidf")),
+    feature = "watch"
+))]
+pub(crate) use unix::watch::Watcher;
+#[cfg(all(any(target_os = "linux", target_os = "android"), feature = "wireguard"))]
+pub(crate) use unix::wireguard::device as wg_device;
+#[cfg(all(
+    target_family = "unix",
+    not(any(target_os = "redox", target_os = "espidf"))
+))]
+pub use unix::{bind_socket_to_interface, ifaces, ifaces_with_warnings, interface_of_socket};
+#[cfg(all(
+    target_family = "unix",
+    not(any(target_os = "redox", target_os = "espidf"))
+))]
+pub(crate) use unix::{
+    config, dad_state, deprecated_or_temporary_v6, hardware_kind, if_index, index_name, is_wireless,
+};
+#[cfg(all(
+    target_family = "unix",
+    not(any(target_os = "redox", target_os = "espidf"))
+))]
+#[doc(hidden)]
+pub use unix::{ifaddrs, map_ifaddrs, map_ifaddrs_with_warnings, sockaddr_from_bytes};
+
+// The conversion from the C structs is tested everywhere.
+#[cfg(any(target_os = "espidf", test))]
+mod espidf;
+#[cfg(target_os = "espidf")]
+pub use espidf::{ifaces, ifaces_with_warnings};
+// The parsing of the scheme files is tested everywhere.
+#[cfg(any(target_os = "redox", test))]
+mod redox;
+#[cfg(target_os = "redox")]
+pub use redox::{ifaces, ifaces_with_warnings};
+#[cfg(any(target_os = "redox", target_os = "espidf"))]
+mod unsupported;
+#[cfg(all(any(target_os = "redox", target_os = "espidf"), feature = "watch"))]
+pub(crate) use unsupported::watch::Watcher;
+#[cfg(any(target_os = "redox", target_os = "espidf"))]
+pub use unsupported::{bind_socket_to_interface, interface_of_socket};
+#[cfg(any(target_os = "redox", target_os = "espidf"))]
+pub(crate) use unsupported::{
+    config, dad_state, deprecated_or_temporary_v6, hardware_kind, if_index, index_name, is_wireless,
+};
@@ -0,0 +1,318 @@
+//! Redox, whose network stack (`smolnetd`) has no `getifaddrs` and
+//! publishes its configuration as files under the `netcfg` scheme:
+//!
+//! ```text
+//! /scheme/netcfg/ifaces/eth0/mac        54-52-00-12-34-56
+//! /scheme/netcfg/ifaces/eth0/addr/list  10.0.2.15/24
+//!                                       fe80::5652:ff:fe12:3456/64
+//! ```
+//!
+//! That gives a name, a MAC address and addresses with their prefix
+//! lengths per interface, and nothing else: there are no interface
+//! indices, flags, MTUs or counters, so those fields stay empty.
+
+#![cfg_attr(not(target_os = "redox"), allow(dead_code))]
+
+use std::fs;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{
+    AddressOrigin, Interface, InterfaceFlags, Kind, MacAddr, OsSpecific, Warning, WarningAction,
+};
+
+const NETCFG_IFACES: &str = "/scheme/netcfg/ifaces";
+
+/// An address in an `addr/list` file, one `address/prefix` per line.
+pub fn parse_addr_line(line: &str) -> Option<(IpAddr, u8)> {
+    let (addr, prefix_len) = line.trim().split_once('/')?;
+    let addr: IpAddr = addr.parse().ok()?;
+    let prefix_len: u8 = prefix_len.parse().ok()?;
+    let width = if addr.is_ipv4() { 32 } else { 128 };
+    Some((addr, prefix_len)).filter(|_| prefix_len <= width)
+}
+
+/// The MAC address in a `mac` file, six hex bytes split by `-` or `:`.
+pub fn parse_mac(text: &str) -> Option<MacAddr> {
+    let mut bytes = [0u8; 6];
+    let mut parts = text.trim().split(['-', ':']);
+    for byte in bytes.iter_mut() {
+        let part = parts.next()?;
+        if part.len() != 2 {
+            return None;
+        }
+        *byte = u8::from_str_radix(part, 16).ok()?;
+    }
+    match parts.next() {
+        Some(_) => None,
+        None => Some(MacAddr::from(bytes)),
+    }
+}
+
+/// The netmask of a `prefix_len` network in the family of `addr`.
+fn mask_of(addr: &IpAddr, prefix_len: u8) -> SocketAddr {
+    let mask = match addr {
+        IpAddr::V4(_) => IpAddr::from(
+            u32::MAX
+                .checked_shl(32 - prefix_len as u32)
+                .unwrap_or(0)
+                .to_be_bytes(),
+        ),
+        IpAddr::V6(_) => IpAddr::from(
+            u128::MAX
+                .checked_shl(128 - prefix_len as u32)
+                .unwrap_or(0)
+                .to_be_bytes(),
+        ),
+    };
+    SocketAddr::new(mask, 0)
+}
+
+/// The entries of interface `name`, from the contents of its `mac` and
+/// `addr/list` files.
+pub fn map_netcfg(
+    name: &str,
+    mac: Option<&str>,
+    addr_list: &str,
+    warnings: &mut Vec<Warning>,
+) -> Vec<Interface> {
+    let os = Arc::new(OsSpecific::default());
+    let mac = mac.and_then(parse_mac);
+    let mut addrs = Vec::new();
+    for (number, line) in addr_list.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_addr_line(line) {
+            Some(entry) => addrs.push(entry),
+            None => warnings.push(Warning::new(
+                name,
+                number,
+                "addr",
+                WarningAction::Skipped,
+                format!("malformed address line {:?}", line),
+            )),
+        }
+    }
+    addrs
+        .into_iter()
+        .map(|(addr, prefix_len)| Interface {
+            name: name.to_owned(),
+            index: None,
+            kind: match addr {
+                IpAddr::V4(_) => Kind::Ipv4,
+                IpAddr::V6(_) => Kind::Ipv6,
+            },
+            addr: Some(SocketAddr::new(addr, 0)),
+            mask: Some(mask_of(&addr, prefix_len)),
+            hop: None,
+            origin: AddressOrigin::of_address(&addr).unwrap_or_default(),
+            flags: InterfaceFlags::empty(),
+            mac,
+            stats: None,
+            mtu: None,
+            os: os.clone(),
+        })
+        .collect()
+}
+
+/// The entries of every interface under `root`, the `netcfg` `ifaces`
+/// directory.
+fn read_netcfg(root: &Path) -> io::Result<(Vec<Interface>, Vec<Warning>)> {
+    let mut names: Vec<String> = fs::read_dir(root)?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    let mut ret = Vec::new();
+    let mut warnings = Vec::new();
+    for name in names {
+        let dir = root.join(&name);
+        let mac = fs::read_to_string(dir.join("mac")).ok();
+        let addr_list = match fs::read_to_string(dir.join("addr").join("list")) {
+            Ok(text) => text,
+            Err(err) => {
+                warnings.push(Warning::new(
+                    &name,
+                    0,
+                    "addr",
+                    WarningAction::Skipped,
+                    format!("cannot read the address list: {}", err),
+                ));
+                continue;
+            }
+        };
+        ret.extend(map_netcfg(&name, mac.as_deref(), &addr_list, &mut warnings));
+    }
+    Ok((ret, warnings))
+}
+
+pub fn ifaces() -> io::Result<Vec<Interface>> {
+    ifaces_with_warnings().map(|(ifaces, _)| ifaces)
+}
+
+pub fn ifaces_with_warnings() -> io::Result<(Vec<Interface>, Vec<Warning>)> {
+    read_netcfg(Path::new(NETCFG_IFACES))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/netcfg/ifaces")
+    }
+
+    fn ip(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn address_lines_are_parsed() {
+        let table = [
+            ("10.0.2.15/24", Some((ip("10.0.2.15"), 24))),
+            (
+                "fe80::5652:ff:fe12:3456/64",
+                Some((ip("fe80::5652:ff:fe12:3456"), 64)),
+            ),
+            ("  0.0.0.0/0 \r", Some((ip("0.0.0.0"), 0))),
+            ("::1/128", Some((ip("::1"), 128))),
+            ("10.0.0.1/32", Some((ip("10.0.0.1"), 32))),
+            ("10.0.0.1/33", None),
+            ("::1/129", None),
+            ("10.0.0.1", None),
+            ("10.0.0.1/", None),
+            ("10.0.0.1/-1", None),
+            ("10.0.0.1/24/8", None),
+            ("eth0/24", None),
+            ("", None),
+        ];
+        for (line, parsed) in table {
+            assert_eq!(parse_addr_line(line), parsed, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn macs_are_parsed() {
+        let mac = Some(MacAddr::from([0x54, 0x52, 0x00, 0x12, 0x34, 0x56]));
+        assert_eq!(parse_mac("54-52-00-12-34-56\n"), mac);
+        assert_eq!(parse_mac("54:52:00:12:34:56"), mac);
+        assert_eq!(parse_mac("54:52:00:12:34-56"), mac);
+        for text in [
+            "",
+            "54-52-00-12-34",
+            "54-52-00-12-34-56-78",
+            "54-52-00-12-34-5",
+            "54-52-00-12-34-056",
+            "54-52-00-12-34-zz",
+            "545200123456",
+        ] {
+            assert_eq!(parse_mac(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn masks_are_the_prefix_lengths() {
+        let mask = |addr: &str, len| mask_of(&ip(addr), len).ip();
+        assert_eq!(mask("10.0.2.15", 24), ip("255.255.255.0"));
+        assert_eq!(mask("10.0.2.15", 0), ip("0.0.0.0"));
+        assert_eq!(mask("10.0.2.15", 32), ip("255.255.255.255"));
+        assert_eq!(mask("fe80::1", 64), ip("ffff:ffff:ffff:ffff::"));
+        assert_eq!(mask("fe80::1", 0), ip("::"));
+        assert_eq!(
+            mask("fe80::1", 128),
+            ip("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff")
+        );
+    }
+
+    #[test]
+    fn interfaces_map_to_an_entry_per_address() {
+        let mut warnings = Vec::new();
+        let ifaces = map_netcfg(
+            "eth0",
+            Some("54-52-00-12-34-56"),
+            "10.0.2.15/24\n\nfe80::5652:ff:fe12:3456/64\n",
+            &mut warnings,
+        );
+        assert_eq!(warnings, []);
+        assert_eq!(ifaces.len(), 2);
+        let (v4, v6) = (&ifaces[0], &ifaces[1]);
+        assert_eq!(v4.kind, Kind::Ipv4);
+        assert_eq!(v4.addr, Some("10.0.2.15:0".parse().unwrap()));
+        assert_eq!(v4.prefix_len(), Some(24));
+        assert_eq!(v4.origin, AddressOrigin::Unknown);
+        assert_eq!(v6.kind, Kind::Ipv6);
+        assert_eq!(v6.prefix_len(), Some(64));
+        assert_eq!(v6.origin, AddressOrigin::LinkLocal);
+        for iface in &ifaces {
+            assert_eq!(iface.name, "eth0");
+            assert_eq!(iface.mac, parse_mac("54-52-00-12-34-56"));
+            // Not in the scheme.
+            assert_eq!(iface.index, None);
+            assert_eq!(iface.flags, InterfaceFlags::empty());
+            assert_eq!(iface.mtu, None);
+            assert_eq!(iface.stats, None);
+        }
+    }
+
+    #[test]
+    fn malformed_lines_are_warned_about() {
+        let mut warnings = Vec::new();
+        let ifaces = map_netcfg(
+            "eth0",
+            Some("garbage"),
+            "10.0.2.15\n::1/128\n",
+            &mut warnings,
+        );
+        assert_eq!(ifaces.len(), 1);
+        assert_eq!(ifaces[0].addr.unwrap().ip(), ip("::1"));
+        // A MAC address that does not parse is just not there.
+        assert_eq!(ifaces[0].mac, None);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].interface, "eth0");
+        assert_eq!(warnings[0].entry, 0);
+        assert_eq!(warnings[0].field, "addr");
+        assert_eq!(warnings[0].action, WarningAction::Skipped);
+        assert_eq!(warnings[0].reason, "malformed address line \"10.0.2.15\"");
+    }
+
+    #[test]
+    fn the_fixture_tree_is_read() {
+        let (ifaces, warnings) = read_netcfg(&fixture()).unwrap();
+        let entries: Vec<(&str, IpAddr, Option<u8>)> = ifaces
+            .iter()
+            .map(|iface| {
+                (
+                    iface.name.as_str(),
+                    iface.addr.unwrap().ip(),
+                    iface.prefix_len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("eth0", ip("10.0.2.15"), Some(24)),
+                ("eth0", ip("fe80::5652:ff:fe12:3456"), Some(64)),
+                ("lo", ip("127.0.0.1"), Some(8)),
+                ("lo", ip("::1"), Some(128)),
+            ]
+        );
+        assert!(ifaces[2..]
+            .iter()
+            .all(|iface| iface.mac.is_none() && iface.origin == AddressOrigin::WellKnown));
+        // eth1 has no address list.
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].interface, "eth1");
+        assert!(warnings[0]
+            .reason
+            .starts_with("cannot read the address list: "));
+    }
+
+    #[test]
+    fn a_missing_scheme_is_an_error() {
+        let err = read_netcfg(&fixture().join("none")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,229 @@
+//! Android `Network` handles, the `long` that `Network.getNetworkHandle()`
+//! returns, for picking a network rather than an interface.
+
+#![cfg_attr(not(target_os = "android"), allow(dead_code))]
+
+use std::ffi::CStr;
+use std::io;
+use std::os::raw::c_int;
+use std::os::unix::io::AsRawFd;
+use std::sync::OnceLock;
+
+use super::netlink::{self, Rule};
+use crate::Interface;
+
+/// The low half of every handle but 0; the high half is the `netId`.
+const HANDLE_MAGIC: u64 = 0xcafe_d00d;
+
+/// netd gives the routing table of each interface the number
+/// `1000 + ifindex`, and points the rules of a network at the tables of
+/// its interfaces.
+const TABLE_OFFSET_FROM_INDEX: u32 = 1000;
+
+/// The bits of the firewall mark that hold the `netId`.
+const NET_ID_MASK: u32 = 0xffff;
+
+/// The `netId` inside `network`, `None` for 0 and values that are not
+/// network handles.
+fn net_id(network: i64) -> Option<u32> {
+    let network = network as u64;
+    match network & 0xffff_ffff {
+        HANDLE_MAGIC => Some((network >> 32) as u32),
+        _ => None,
+    }
+}
+
+fn invalid_handle() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "not a network handle")
+}
+
+/// The interface indexes the rules send traffic of network `net_id`
+/// through: rules that match its `netId` exactly in the mark and look up
+/// an interface table.
+fn network_indexes(rules: &[Rule], net_id: u32) -> Vec<u32> {
+    let mut indexes: Vec<u32> = rules
+        .iter()
+        .filter(|rule| match (rule.fwmark, rule.fwmask) {
+            (Some(mark), Some(mask)) => {
+                mask & NET_ID_MASK == NET_ID_MASK && mark & NET_ID_MASK == net_id
+            }
+            _ => false,
+        })
+        .filter_map(|rule| rule.table.checked_sub(TABLE_OFFSET_FROM_INDEX))
+        .filter(|&index| index != 0)
+        .collect();
+    indexes.sort_unstable();
+    indexes.dedup();
+    indexes
+}
+
+/// The entries of `ifaces` on the interfaces of network `network`.
+///
+/// Android does not tell native code which interfaces a network has, so
+/// this reads the policy routing rules netd sets up for it and picks the
+/// interfaces whose tables they look up. A VPN network also leads to the
+/// interfaces it runs over when netd routes it that way. Reading rules
+/// can be blocked by SELinux for apps, which gives `PermissionDenied`.
+/// Fails with `InvalidInput` if `network` is not a network handle.
+pub fn network_ifaces(network: i64) -> io::Result<Vec<Interface>> {
+    let net_id = net_id(network).ok_or_else(invalid_handle)?;
+    let mut rules = netlink::rules(libc::AF_INET as u8)?;
+    rules.extend(netlink::rules(libc::AF_INET6 as u8)?);
+    let indexes = network_indexes(&rules, net_id);
+    Ok(crate::ifaces()?
+        .into_iter()
+        .filter(|iface| iface.index.is_some_and(|index| indexes.contains(&index)))
+        .collect())
+}
+
+type SetSockNetwork = unsafe extern "C" fn(network: u64, fd: c_int) -> c_int;
+
+/// `android_setsocknetwork` from `libandroid.so`, looked up once. It is
+/// there from API level 23 on.
+fn set_sock_network() -> Option<SetSockNetwork> {
+    static FUNCTION: OnceLock<Option<SetSockNetwork>> = OnceLock::new();
+    *FUNCTION.get_or_init(|| {
+        let library = CStr::from_bytes_with_nul(b"libandroid.so\0").ok()?;
+        let symbol = CStr::from_bytes_with_nul(b"android_setsocknetwork\0").ok()?;
+        unsafe {
+            let handle = libc::dlopen(library.as_ptr(), libc::RTLD_NOW);
+            if handle.is_null() {
+                return None;
+            }
+            let function = libc::dlsym(handle, symbol.as_ptr());
+            match function.is_null() {
+                true => None,
+                false => Some(std::mem::transmute::<*mut libc::c_void, SetSockNetwork>(
+                    function,
+                )),
+            }
+        }
+    })
+}
+
+/// Send the traffic of `socket` over network `network`, whatever the
+/// default network is, with `android_setsocknetwork`.
+///
+/// Unlike binding to an interface this follows the network when its
+/// interfaces change. The handle 0 undoes it. Call this before `connect`.
+/// Fails with `ErrorKind::Unsupported` before Android 6.0 (API level 23).
+pub fn bind_socket_to_network(socket: &impl AsRawFd, network: i64) -> io::Result<()> {
+    if network != 0 && net_id(network).is_none() {
+        return Err(invalid_handle());
+    }
+    let function = set_sock_network().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "android_setsocknetwork needs API level 23",
+        )
+    })?;
+    match unsafe { function(network as u64, socket.as_raw_fd()) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The handle `Network.getNetworkHandle()` returns for `net_id`.
+    fn handle(net_id: u32) -> i64 {
+        ((net_id as u64) << 32 | HANDLE_MAGIC) as i64
+    }
+
+    #[test]
+    fn handles_hold_the_net_id() {
+        assert_eq!(net_id(handle(100)), Some(100));
+        assert_eq!(net_id(handle(65535)), Some(65535));
+        assert_eq!(net_id(0), None);
+        assert_eq!(net_id(100), None);
+        assert_eq!(net_id(-1), None);
+    }
+
+    fn rule(table: u32, mark: Option<(u32, u32)>) -> Rule {
+        Rule {
+            table,
+            fwmark: mark.map(|(mark, _)| mark),
+            fwmask: mark.map(|(_, mask)| mask),
+        }
+    }
+
+    /// The rules netd sets up with Wi-Fi as the default network, netId
+    /// 100 on wlan0 (index 30), cellular as netId 101 on rmnet_data0
+    /// (index 12) and a VPN as netId 102 on tun0 (index 40), as
+    /// `ip rule` shows them.
+    fn netd_rules() -> Vec<Rule> {
+        vec![
+            // 0: from all lookup local
+            rule(255, None),
+            // 10000: from all fwmark 0xc0000/0xd0000 lookup legacy_system
+            rule(98, Some((0xc0000, 0xd0000))),
+            // 12000: from all fwmark 0x10066/0x1ffff lookup tun0
+            rule(1040, Some((0x10066, 0x1ffff))),
+            // 13000: from all fwmark 0x10063/0x1ffff lookup local_network
+            rule(97, Some((0x10063, 0x1ffff))),
+            // 13000: from all fwmark 0x10064/0x1ffff lookup wlan0
+            rule(1030, Some((0x10064, 0x1ffff))),
+            // 13000: from all fwmark 0x10065/0x1ffff lookup rmnet_data0
+            rule(1012, Some((0x10065, 0x1ffff))),
+            // 14000: from all oif wlan0 lookup wlan0, no mark.
+            rule(1030, None),
+            // 15000: from all fwmark 0x0/0x10000 lookup legacy_system
+            rule(98, Some((0x0, 0x10000))),
+            // 16000: from all fwmark 0x64/0x1ffff lookup wlan0
+            rule(1030, Some((0x64, 0x1ffff))),
+            // 17000: from all fwmark 0x66/0x1ffff lookup wlan0, the VPN
+            // over the network it runs on.
+            rule(1030, Some((0x66, 0x1ffff))),
+            // 22000: from all fwmark 0x0/0xffff lookup wlan0, the default
+            // network.
+            rule(1030, Some((0x0, 0xffff))),
+            // 32000: from all unreachable
+            rule(0, None),
+        ]
+    }
+
+    #[test]
+    fn networks_lead_to_the_tables_of_their_interfaces() {
+        let rules = netd_rules();
+        assert_eq!(network_indexes(&rules, 100), [30]);
+        assert_eq!(network_indexes(&rules, 101), [12]);
+        // The VPN and the interface it runs over, in order.
+        assert_eq!(network_indexes(&rules, 102), [30, 40]);
+        // local_network and others without an interface table.
+        assert!(network_indexes(&rules, 99).is_empty());
+        assert!(network_indexes(&rules, 103).is_empty());
+    }
+
+    #[test]
+    fn rules_without_the_whole_net_id_are_skipped() {
+        let rules = [
+            // A mask without all the netId bits.
+            rule(1030, Some((0x64, 0xff))),
+            // Marks without a mask, and masks without a mark.
+            Rule {
+                table: 1031,
+                fwmark: Some(0x64),
+                fwmask: None,
+            },
+            Rule {
+                table: 1032,
+                fwmark: None,
+                fwmask: Some(0xffff),
+            },
+            // Table 1000 would be index 0.
+            rule(1000, Some((0x64, 0xffff))),
+        ];
+        assert!(network_indexes(&rules, 100).is_empty());
+    }
+
+    #[test]
+    fn sockets_are_only_bound_to_network_handles() {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let err = bind_socket_to_network(&socket, 100).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = network_ifaces(-1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
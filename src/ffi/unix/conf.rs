@@ -0,0 +1,388 @@
+//! The per-family settings Linux keeps for each interface under
+//! `/proc/sys/net/{ipv4,ipv6}/conf/<name>/`.
+//!
+//! Every field is `None` when its file is missing, as on kernels older
+//! than the setting, or does not hold a number.
+
+use std::convert::TryFrom;
+use std::io;
+
+use super::sysfs::FsRoot;
+use crate::{validate_name, Ipv6Disabled, Ipv6DisabledEvidence, ValidName};
+
+/// `accept_ra`: whether router advertisements are taken.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum AcceptRa {
+    Never,
+    /// Only while `forwarding` is off, the default.
+    UnlessForwarding,
+    /// Even while forwarding.
+    Always,
+    Unknown(i64),
+}
+
+impl From<i64> for AcceptRa {
+    fn from(value: i64) -> AcceptRa {
+        match value {
+            0 => AcceptRa::Never,
+            1 => AcceptRa::UnlessForwarding,
+            2 => AcceptRa::Always,
+            other => AcceptRa::Unknown(other),
+        }
+    }
+}
+
+/// `rp_filter`: how the source address of incoming packets is checked.
+/// The kernel applies the higher of this and the `all` setting.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RpFilter {
+    Off,
+    /// The reverse route has to go out this interface (RFC 3704 strict).
+    Strict,
+    /// Some route back has to exist (RFC 3704 loose).
+    Loose,
+    Unknown(i64),
+}
+
+impl From<i64> for RpFilter {
+    fn from(value: i64) -> RpFilter {
+        match value {
+            0 => RpFilter::Off,
+            1 => RpFilter::Strict,
+            2 => RpFilter::Loose,
+            other => RpFilter::Unknown(other),
+        }
+    }
+}
+
+/// The IPv6 settings of an interface, see [`ipv6_settings`].
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Ipv6Settings {
+    pub disable_ipv6: Option<bool>,
+    pub accept_ra: Option<AcceptRa>,
+    /// Whether SLAAC addresses are made from the prefixes routers announce.
+    pub autoconf: Option<bool>,
+    /// Whether packets are forwarded, which turns `accept_ra` 1 off.
+    pub forwarding: Option<bool>,
+    /// The hop limit of outgoing packets.
+    pub hop_limit: Option<u8>,
+    /// The IPv6 MTU, which can be below the link MTU, e.g. after a router
+    /// advertised a smaller one.
+    pub mtu: Option<u32>,
+}
+
+/// The IPv4 settings of an interface, see [`ipv4_settings`].
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Ipv4Settings {
+    pub forwarding: Option<bool>,
+    pub rp_filter: Option<RpFilter>,
+    /// `arp_ignore`, from 0 (answer for any local address) to 8 (never
+    /// answer); see the kernel's `ip-sysctl` documentation.
+    pub arp_ignore: Option<u8>,
+}
+
+/// The IPv6 settings of the interface called `name`.
+///
+/// `Ok(None)` when the interface has no IPv6 configuration, i.e. IPv6 is
+/// disabled for the whole host. An interface that does not exist gives
+/// `NotFound`.
+pub fn ipv6_settings(name: &str) -> io::Result<Option<Ipv6Settings>> {
+    ipv6_settings_from(&FsRoot::system(), &validate_name(name)?)
+}
+
+/// The IPv4 settings of the interface called `name`. An interface that
+/// does not exist gives `NotFound`.
+pub fn ipv4_settings(name: &str) -> io::Result<Ipv4Settings> {
+    ipv4_settings_from(&FsRoot::system(), &validate_name(name)?)
+}
+
+/// The `conf` directory of `family` for the interface, `None` if there is
+/// none.
+fn conf_dir(root: &FsRoot, family: &str, name: &ValidName) -> io::Result<Option<String>> {
+    if !root.exists(format!("sys/class/net/{}", name)) {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no such interface"));
+    }
+    let conf = format!("proc/sys/net/{}/conf/{}", family, name);
+    Ok(Some(conf).filter(|conf| root.exists(conf)))
+}
+
+fn ipv6_settings_from(root: &FsRoot, name: &ValidName) -> io::Result<Option<Ipv6Settings>> {
+    let conf = match conf_dir(root, "ipv6", name)? {
+        Some(conf) => conf,
+        None => return Ok(None),
+    };
+    let int = |setting: &str| root.read_int(format!("{}/{}", conf, setting)).ok();
+    Ok(Some(Ipv6Settings {
+        disable_ipv6: int("disable_ipv6").map(|value| value != 0),
+        accept_ra: int("accept_ra").map(AcceptRa::from),
+        autoconf: int("autoconf").map(|value| value != 0),
+        forwarding: int("forwarding").map(|value| value != 0),
+        hop_limit: int("hop_limit").and_then(|value| u8::try_from(value).ok()),
+        mtu: int("mtu").and_then(|value| u32::try_from(value).ok()),
+    }))
+}
+
+fn ipv4_settings_from(root: &FsRoot, name: &ValidName) -> io::Result<Ipv4Settings> {
+    let conf = match conf_dir(root, "ipv4", name)? {
+        Some(conf) => conf,
+        None => return Ok(Ipv4Settings::default()),
+    };
+    let int = |setting: &str| root.read_int(format!("{}/{}", conf, setting)).ok();
+    Ok(Ipv4Settings {
+        forwarding: int("forwarding").map(|value| value != 0),
+        rp_filter: int("rp_filter").map(RpFilter::from),
+        arp_ignore: int("arp_ignore").and_then(|value| u8::try_from(value).ok()),
+    })
+}
+
+/// Whether IPv6 is off for the interface called `name`, see
+/// [`ipv6_disabled_evidence`](crate::ipv6_disabled_evidence).
+pub(crate) fn ipv6_disabled(name: &ValidName) -> io::Result<Option<Ipv6Disabled>> {
+    ipv6_disabled_from(&FsRoot::system(), name)
+}
+
+fn ipv6_disabled_from(root: &FsRoot, name: &ValidName) -> io::Result<Option<Ipv6Disabled>> {
+    if conf_dir(root, "ipv6", name)?.is_none() && !root.exists("proc/sys/net/ipv6") {
+        return Ok(Some(Ipv6Disabled {
+            disabled: true,
+            evidence: vec![Ipv6DisabledEvidence::NoIpv6Stack],
+        }));
+    }
+    let disabled = |conf: &str| {
+        root.read_int(format!("proc/sys/net/ipv6/conf/{}/disable_ipv6", conf))
+            .ok()
+            .map(|value| value != 0)
+    };
+    let iface = disabled(name.as_str());
+    let all = disabled("all");
+    let evidence: Vec<_> = iface
+        .map(Ipv6DisabledEvidence::DisableIpv6)
+        .into_iter()
+        .chain(all.map(Ipv6DisabledEvidence::DisableIpv6All))
+        .collect();
+    if evidence.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Ipv6Disabled {
+        disabled: iface == Some(true) || all == Some(true),
+        evidence,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ffi::unix::sysfs::fake::FakeTree;
+
+    fn eth0() -> ValidName {
+        validate_name("eth0").unwrap()
+    }
+
+    /// A tree with `eth0` and the settings of a host that takes router
+    /// advertisements.
+    fn tree() -> FakeTree {
+        let tree = FakeTree::new();
+        tree.dir("sys/class/net/eth0");
+        for (setting, value) in [
+            ("disable_ipv6", "0"),
+            ("accept_ra", "1"),
+            ("autoconf", "1"),
+            ("forwarding", "0"),
+            ("hop_limit", "64"),
+            ("mtu", "1480"),
+        ] {
+            tree.file(
+                &format!("proc/sys/net/ipv6/conf/eth0/{}", setting),
+                &format!("{}\n", value),
+            );
+        }
+        for (setting, value) in [("forwarding", "1"), ("rp_filter", "2"), ("arp_ignore", "1")] {
+            tree.file(
+                &format!("proc/sys/net/ipv4/conf/eth0/{}", setting),
+                &format!("{}\n", value),
+            );
+        }
+        tree
+    }
+
+    #[test]
+    fn settings_are_read_into_their_types() {
+        let tree = tree();
+        assert_eq!(
+            ipv6_settings_from(&tree.root(), &eth0()).unwrap(),
+            Some(Ipv6Settings {
+                disable_ipv6: Some(false),
+                accept_ra: Some(AcceptRa::UnlessForwarding),
+                autoconf: Some(true),
+                forwarding: Some(false),
+                hop_limit: Some(64),
+                mtu: Some(1480),
+            })
+        );
+        assert_eq!(
+            ipv4_settings_from(&tree.root(), &eth0()).unwrap(),
+            Ipv4Settings {
+                forwarding: Some(true),
+                rp_filter: Some(RpFilter::Loose),
+                arp_ignore: Some(1),
+            }
+        );
+    }
+
+    #[test]
+    fn missing_and_unreadable_settings_are_none() {
+        let tree = FakeTree::new();
+        tree.dir("sys/class/net/eth0")
+            // An older kernel, without most of the files.
+            .file("proc/sys/net/ipv6/conf/eth0/accept_ra", "2\n")
+            .file("proc/sys/net/ipv6/conf/eth0/hop_limit", "300\n")
+            .file("proc/sys/net/ipv6/conf/eth0/mtu", "-1\n")
+            .file("proc/sys/net/ipv4/conf/eth0/rp_filter", "strict\n");
+        assert_eq!(
+            ipv6_settings_from(&tree.root(), &eth0()).unwrap(),
+            Some(Ipv6Settings {
+                accept_ra: Some(AcceptRa::Always),
+                ..Ipv6Settings::default()
+            })
+        );
+        assert_eq!(
+            ipv4_settings_from(&tree.root(), &eth0()).unwrap(),
+            Ipv4Settings::default()
+        );
+    }
+
+    #[test]
+    fn hosts_without_ipv6_have_no_ipv6_settings() {
+        let tree = FakeTree::new();
+        tree.dir("sys/class/net/eth0");
+        assert_eq!(ipv6_settings_from(&tree.root(), &eth0()).unwrap(), None);
+        assert_eq!(
+            ipv4_settings_from(&tree.root(), &eth0()).unwrap(),
+            Ipv4Settings::default()
+        );
+    }
+
+    #[test]
+    fn missing_interfaces_are_not_found() {
+        let tree = tree();
+        let name = validate_name("eth1").unwrap();
+        let err = ipv6_settings_from(&tree.root(), &name).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = ipv4_settings_from(&tree.root(), &name).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn knobs_keep_values_they_do_not_know() {
+        assert_eq!(AcceptRa::from(0), AcceptRa::Never);
+        assert_eq!(AcceptRa::from(3), AcceptRa::Unknown(3));
+        assert_eq!(RpFilter::from(0), RpFilter::Off);
+        assert_eq!(RpFilter::from(1), RpFilter::Strict);
+        assert_eq!(RpFilter::from(-1), RpFilter::Unknown(-1));
+    }
+
+    #[test]
+    fn loopback_settings_are_read() {
+        let lo = ipv4_settings("lo").unwrap();
+        assert!(lo.rp_filter.is_some());
+        if let Some(lo) = ipv6_settings("lo").unwrap() {
+            assert!(lo.disable_ipv6.is_some());
+            assert!(lo.mtu.is_some());
+        }
+        let err = ipv4_settings("nosuchif0").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    fn disabled(tree: &FakeTree) -> Option<Ipv6Disabled> {
+        ipv6_disabled_from(&tree.root(), &eth0()).unwrap()
+    }
+
+    #[test]
+    fn disable_ipv6_is_read_for_the_interface_and_all() {
+        let tree = tree();
+        tree.file("proc/sys/net/ipv6/conf/all/disable_ipv6", "0\n");
+        assert_eq!(
+            disabled(&tree),
+            Some(Ipv6Disabled {
+                disabled: false,
+                evidence: vec![
+                    Ipv6DisabledEvidence::DisableIpv6(false),
+                    Ipv6DisabledEvidence::DisableIpv6All(false),
+                ],
+            })
+        );
+
+        tree.file("proc/sys/net/ipv6/conf/eth0/disable_ipv6", "1\n");
+        assert_eq!(
+            disabled(&tree),
+            Some(Ipv6Disabled {
+                disabled: true,
+                evidence: vec![
+                    Ipv6DisabledEvidence::DisableIpv6(true),
+                    Ipv6DisabledEvidence::DisableIpv6All(false),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn the_host_level_setting_disables_every_interface() {
+        let tree = tree();
+        tree.file("proc/sys/net/ipv6/conf/all/disable_ipv6", "1\n");
+        let status = disabled(&tree).unwrap();
+        assert!(status.disabled);
+        assert_eq!(
+            status.evidence[1],
+            Ipv6DisabledEvidence::DisableIpv6All(true)
+        );
+    }
+
+    #[test]
+    fn kernels_without_ipv6_are_disabled() {
+        let tree = FakeTree::new();
+        tree.dir("sys/class/net/eth0")
+            .file("proc/sys/net/ipv4/conf/eth0/forwarding", "0\n");
+        assert_eq!(
+            disabled(&tree),
+            Some(Ipv6Disabled {
+                disabled: true,
+                evidence: vec![Ipv6DisabledEvidence::NoIpv6Stack],
+            })
+        );
+    }
+
+    #[test]
+    fn unreadable_settings_give_no_answer() {
+        let tree = FakeTree::new();
+        tree.dir("sys/class/net/eth0")
+            .file("proc/sys/net/ipv6/conf/eth0/disable_ipv6", "maybe\n");
+        assert_eq!(disabled(&tree), None);
+        // Only the host-level one.
+        tree.file("proc/sys/net/ipv6/conf/all/disable_ipv6", "0\n");
+        assert_eq!(
+            disabled(&tree).unwrap().evidence,
+            [Ipv6DisabledEvidence::DisableIpv6All(false)]
+        );
+    }
+
+    #[test]
+    fn ipv6_disabled_needs_the_interface() {
+        let tree = tree();
+        let name = validate_name("eth1").unwrap();
+        let err = ipv6_disabled_from(&tree.root(), &name).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn loopback_ipv6_is_read() {
+        let lo = ipv6_disabled(&validate_name("lo").unwrap())
+            .unwrap()
+            .unwrap();
+        let stack = std::path::Path::new("/proc/sys/net/ipv6").exists();
+        assert_eq!(
+            lo.evidence.contains(&Ipv6DisabledEvidence::NoIpv6Stack),
+            !stack
+        );
+    }
+}
@@ -0,0 +1,818 @@
+use std::io;
+use std::net::{IpAddr, Ipv6Addr};
+use std::os::raw::c_short;
+
+use super::ioctl::{self, ifreq};
+use crate::{
+    validate_name, Ipv6Disabled, Kind, MacAddr, Route, TempAddrs, ValidName, WolInfo, WolModes,
+};
+
+// FreeBSD ignores IFF_PROMISC from userland and wants IFF_PPROMISC, which
+// lives in the high half of the flags.
+#[cfg(target_os = "freebsd")]
+const PROMISC: (usize, c_short) = (1, (libc::IFF_PPROMISC >> 16) as c_short);
+#[cfg(not(target_os = "freebsd"))]
+const PROMISC: (usize, c_short) = (0, super::SIOCGIFFLAGS::IFF_PROMISC as c_short);
+
+fn flags(name: &str) -> io::Result<(std::os::unix::io::OwnedFd, ifreq)> {
+    let socket = ioctl::socket()?;
+    let mut req = ifreq::new(&validate_name(name)?);
+    ioctl::ioctl(&socket, ioctl::SIOCGIFFLAGS, &mut req)?;
+    Ok((socket, req))
+}
+
+fn is_promiscuous(req: &ifreq) -> bool {
+    let (word, bit) = PROMISC;
+    unsafe { req.ifr_ifru.ifru_flags[word] & bit == bit }
+}
+
+/// Returns whether promiscuous mode is switched on for the interface.
+///
+/// This reads back the flag [`set_promiscuous`] toggles. On Linux, packet
+/// sockets that joined `PACKET_MR_PROMISC` (tcpdump and friends) bump a
+/// separate promiscuity counter which is not reflected here; read it with
+/// [`Interface::promiscuity`](crate::Interface::promiscuity).
+pub fn promiscuous(name: &str) -> io::Result<bool> {
+    let (_, req) = flags(name)?;
+    Ok(is_promiscuous(&req))
+}
+
+/// Switches promiscuous mode on or off and returns the previous state.
+///
+/// The flag is set with `SIOCSIFFLAGS`, so the change outlives the calling
+/// process and shows up in `ip link`/`ifconfig`. A capture tool that wants
+/// promiscuity tied to its own lifetime should join `PACKET_MR_PROMISC` on
+/// its packet socket instead.
+pub fn set_promiscuous(name: &str, enabled: bool) -> io::Result<bool> {
+    let (socket, mut req) = flags(name)?;
+    let previous = is_promiscuous(&req);
+    if previous == enabled {
+        return Ok(previous);
+    }
+
+    let (word, bit) = PROMISC;
+    unsafe {
+        if enabled {
+            req.ifr_ifru.ifru_flags[word] |= bit;
+        } else {
+            req.ifr_ifru.ifru_flags[word] &= !bit;
+        }
+    }
+    ioctl::ioctl(&socket, ioctl::SIOCSIFFLAGS, &mut req)?;
+    Ok(previous)
+}
+
+/// Installs `route`.
+///
+/// Routes without a gateway are on-link routes out of `route.ifindex`.
+/// Fails with `ErrorKind::AlreadyExists` if the same route is already
+/// there.
+pub fn add_route(route: &Route) -> io::Result<()> {
+    route.validate()?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::netlink::change_route(super::netlink::RTM_NEWROUTE, route);
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ))]
+    return super::route_socket::change_route(super::route_socket::RTM_ADD, route);
+    #[allow(unreachable_code)]
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "changing routes is not supported on this platform",
+    ))
+}
+
+/// Removes `route`.
+///
+/// The route is looked up by destination, prefix length and table, and by
+/// gateway, interface and metric where they are set. Fails with
+/// `ErrorKind::NotFound` if there is no such route.
+pub fn remove_route(route: &Route) -> io::Result<()> {
+    route.validate()?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::netlink::change_route(super::netlink::RTM_DELROUTE, route);
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ))]
+    return super::route_socket::change_route(super::route_socket::RTM_DELETE, route);
+    #[allow(unreachable_code)]
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "changing routes is not supported on this platform",
+    ))
+}
+
+/// Removes the address `addr/prefix_len` from interface `name`.
+///
+/// Fails with `ErrorKind::NotFound` if the interface does not have it. On
+/// macOS and the BSDs only IPv4 addresses can be removed so far; IPv6 ones
+/// fail with `ErrorKind::Unsupported`.
+pub fn remove_address(name: &str, addr: IpAddr, prefix_len: u8) -> io::Result<()> {
+    let name = &validate_name(name)?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::netlink::delete_addr(super::if_index(name)?, &addr, prefix_len);
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        let _ = prefix_len;
+        let addr = match addr {
+            IpAddr::V4(addr) => addr,
+            IpAddr::V6(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "removing IPv6 addresses is not supported on this platform",
+                ))
+            }
+        };
+        let mut req = ifreq::new(name);
+        let mut sin: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+        sin.sin_len = std::mem::size_of::<libc::sockaddr_in>() as u8;
+        sin.sin_family = libc::AF_INET as libc::sa_family_t;
+        sin.sin_addr.s_addr = u32::from_ne_bytes(addr.octets());
+        req.ifr_ifru.ifru_addr =
+            unsafe { std::mem::transmute::<libc::sockaddr_in, libc::sockaddr>(sin) };
+        let socket = ioctl::socket()?;
+        match ioctl::ioctl(&socket, ioctl::SIOCDIFADDR, &mut req) {
+            Err(err) if err.raw_os_error() == Some(libc::EADDRNOTAVAIL) => {
+                Err(io::Error::new(io::ErrorKind::NotFound, err))
+            }
+            result => result,
+        }
+    }
+}
+
+/// Returns the Wake-on-LAN triggers interface `name` supports and has on.
+///
+/// Linux asks the driver with `ETHTOOL_GWOL`. Drivers without Wake-on-LAN
+/// support, virtual devices among them, fail with `ErrorKind::Unsupported`,
+/// as do other platforms.
+pub fn wol(name: &str) -> io::Result<WolInfo> {
+    let name = &validate_name(name)?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::ethtool::wol(name);
+    #[allow(unreachable_code)]
+    {
+        let _ = name;
+        Err(wol_unsupported())
+    }
+}
+
+/// Switches on exactly the Wake-on-LAN triggers in `modes`, see [`wol`].
+/// An empty set switches Wake-on-LAN off.
+pub fn set_wol(name: &str, modes: WolModes) -> io::Result<()> {
+    let name = &validate_name(name)?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::ethtool::set_wol(name, modes);
+    #[allow(unreachable_code)]
+    {
+        let _ = (name, modes);
+        Err(wol_unsupported())
+    }
+}
+
+#[allow(dead_code)]
+fn wol_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Wake-on-LAN settings are not supported on this platform",
+    )
+}
+
+/// Longest alias Linux takes: `IFALIASZ` less the terminating NUL.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const MAX_ALIAS_LEN: usize = 255;
+
+/// Returns the alias of interface `name`, the free-text label operators
+/// set with `ip link set alias`. `None` if it has none.
+///
+/// Only Linux has aliases; other platforms fail with
+/// `ErrorKind::Unsupported`.
+pub fn alias(name: &str) -> io::Result<Option<String>> {
+    let name = &validate_name(name)?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        let index = super::if_index(name)?;
+        let link = super::netlink::link(index)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such interface"))?;
+        return Ok(link.alias.filter(|alias| !alias.is_empty()));
+    }
+    #[allow(unreachable_code)]
+    {
+        let _ = name;
+        Err(alias_unsupported())
+    }
+}
+
+/// Sets the alias of interface `name`; an empty `text` clears it.
+///
+/// Linux takes up to 255 bytes, longer ones fail with
+/// `ErrorKind::InvalidInput` before anything is sent.
+pub fn set_alias(name: &str, text: &str) -> io::Result<()> {
+    let name = &validate_name(name)?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        if text.len() > MAX_ALIAS_LEN || text.contains('\0') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "aliases are at most 255 bytes, without NULs",
+            ));
+        }
+        return super::netlink::set_alias(super::if_index(name)?, text);
+    }
+    #[allow(unreachable_code)]
+    {
+        let _ = (name, text);
+        Err(alias_unsupported())
+    }
+}
+
+#[allow(dead_code)]
+fn alias_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "interface aliases are not supported on this platform",
+    )
+}
+
+/// Installs a permanent neighbor entry mapping `ip` to `mac` on link
+/// `index`, see [`crate::config::add_neighbor`].
+pub(crate) fn install_neighbor(index: u32, ip: IpAddr, mac: MacAddr) -> io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::netlink::change_neigh(super::netlink::RTM_NEWNEIGH, index, &ip, Some(mac));
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ))]
+    return super::route_socket::change_neighbor(
+        super::route_socket::RTM_ADD,
+        index,
+        &ip,
+        Some(mac),
+    );
+    #[allow(unreachable_code)]
+    {
+        let _ = (index, ip, mac);
+        Err(neighbors_unsupported())
+    }
+}
+
+/// Deletes the neighbor entry of `ip` on link `index`.
+pub(crate) fn delete_neighbor(index: u32, ip: IpAddr) -> io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::netlink::change_neigh(super::netlink::RTM_DELNEIGH, index, &ip, None);
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ))]
+    return super::route_socket::change_neighbor(super::route_socket::RTM_DELETE, index, &ip, None);
+    #[allow(unreachable_code)]
+    {
+        let _ = (index, ip);
+        Err(neighbors_unsupported())
+    }
+}
+
+/// The gateway and outgoing link of the default route of `family`, see
+/// [`crate::gateway_mac`].
+pub(crate) fn default_gateway(family: Kind) -> io::Result<Option<(IpAddr, u32)>> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::netlink::default_gateway(match family {
+        Kind::Ipv6 => libc::AF_INET6 as u8,
+        _ => libc::AF_INET as u8,
+    });
+    #[allow(unreachable_code)]
+    {
+        let _ = family;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "reading the default route is not supported on this platform",
+        ))
+    }
+}
+
+/// The MAC address of the resolved neighbor entry of `ip` on link `index`.
+pub(crate) fn neighbor_mac(index: u32, ip: IpAddr) -> io::Result<Option<MacAddr>> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::netlink::neighbor_mac(index, &ip);
+    #[allow(unreachable_code)]
+    {
+        let _ = (index, ip);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "reading neighbor entries is not supported on this platform",
+        ))
+    }
+}
+
+#[allow(dead_code)]
+fn neighbors_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "changing neighbor entries is not supported on this platform",
+    )
+}
+
+/// Makes VLAN `vlan_id` on link `parent`, called `name`, and returns its
+/// index. See [`crate::config::create_vlan`].
+pub(crate) fn add_vlan(parent: u32, vlan_id: u16, name: &ValidName) -> io::Result<u32> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        super::netlink::create_vlan(parent, vlan_id, name)?;
+        return super::if_index(name);
+    }
+    #[allow(unreachable_code)]
+    {
+        let _ = (parent, vlan_id, name);
+        Err(vlans_unsupported())
+    }
+}
+
+/// Deletes link `index`, which has to be a VLAN.
+pub(crate) fn delete_vlan(index: u32) -> io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        link_of_kind(index, "vlan", "the interface is not a VLAN")?;
+        return super::netlink::delete_link(index);
+    }
+    #[allow(unreachable_code)]
+    {
+        let _ = index;
+        Err(vlans_unsupported())
+    }
+}
+
+#[allow(dead_code)]
+fn vlans_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "creating VLANs is not supported on this platform",
+    )
+}
+
+/// Link `index`, failing with `ErrorKind::InvalidInput` and `message` if
+/// it is not a `kind` link.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn link_of_kind(index: u32, kind: &str, message: &str) -> io::Result<super::netlink::Link> {
+    let link = super::netlink::link(index)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such interface"))?;
+    match link.kind.as_deref() == Some(kind) {
+        true => Ok(link),
+        false => Err(io::Error::new(io::ErrorKind::InvalidInput, message)),
+    }
+}
+
+/// Makes a bridge called `name` and returns its index. See
+/// [`crate::config::create_bridge`].
+pub(crate) fn add_bridge(name: &ValidName) -> io::Result<u32> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        super::netlink::create_bridge(name)?;
+        return super::if_index(name);
+    }
+    #[allow(unreachable_code)]
+    {
+        let _ = name;
+        Err(bridges_unsupported())
+    }
+}
+
+/// Deletes link `index`, which has to be a bridge. Its ports are released.
+pub(crate) fn delete_bridge(index: u32) -> io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        link_of_kind(index, "bridge", "the interface is not a bridge")?;
+        return super::netlink::delete_link(index);
+    }
+    #[allow(unreachable_code)]
+    {
+        let _ = index;
+        Err(bridges_unsupported())
+    }
+}
+
+/// Makes link `port` a port of bridge `bridge`, see
+/// [`crate::config::bridge_add_port`].
+pub(crate) fn add_bridge_port(bridge: u32, port: u32) -> io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        link_of_kind(bridge, "bridge", "the master is not a bridge")?;
+        let link = super::netlink::link(port)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such interface"))?;
+        // The kernel would move the port over from its old master without
+        // a word.
+        match link.master {
+            Some(master) if master == bridge => return Ok(()),
+            Some(master) => {
+                let name = super::index_name(master).unwrap_or_else(|| master.to_string());
+                return Err(io::Error::new(
+                    io::ErrorKind::ResourceBusy,
+                    format!("the interface is already a port of {}", name),
+                ));
+            }
+            None => {}
+        }
+        return super::netlink::set_master(port, bridge);
+    }
+    #[allow(unreachable_code)]
+    {
+        let _ = (bridge, port);
+        Err(bridges_unsupported())
+    }
+}
+
+/// Releases link `port` from bridge `bridge`.
+pub(crate) fn remove_bridge_port(bridge: u32, port: u32) -> io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        link_of_kind(bridge, "bridge", "the master is not a bridge")?;
+        let link = super::netlink::link(port)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such interface"))?;
+        if link.master != Some(bridge) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "the interface is not a port of the bridge",
+            ));
+        }
+        return super::netlink::set_master(port, 0);
+    }
+    #[allow(unreachable_code)]
+    {
+        let _ = (bridge, port);
+        Err(bridges_unsupported())
+    }
+}
+
+#[allow(dead_code)]
+fn bridges_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "creating bridges is not supported on this platform",
+    )
+}
+
+/// Sets the metric of the default routes out of interface `name` for
+/// `family`, `Ipv4` or `Ipv6`.
+///
+/// Linux has no metric on the interface itself, the kernel picks between
+/// default routes by theirs. Each default route in the main table is
+/// replaced by the same route with the new metric, so whatever set it up
+/// (DHCP, router advertisements) will put its own metric back when it next
+/// renews. Fails with `ErrorKind::NotFound` if the interface has no default
+/// route, and with `ErrorKind::Unsupported` on other platforms, where
+/// routes have no metric to pick by.
+pub fn set_metric(name: &str, family: Kind, metric: u32) -> io::Result<()> {
+    let name = &validate_name(name)?;
+    let family = match family {
+        Kind::Ipv4 => libc::AF_INET,
+        Kind::Ipv6 => libc::AF_INET6,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the family must be Ipv4 or Ipv6",
+            ))
+        }
+    };
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        let index = super::if_index(name)?;
+        return match super::netlink::set_default_metric(family as u8, index, metric)? {
+            0 => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no default route out of the interface",
+            )),
+            _ => Ok(()),
+        };
+    }
+    #[allow(unreachable_code)]
+    {
+        let _ = (name, family, metric);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "interface metrics are not supported on this platform",
+        ))
+    }
+}
+
+/// Returns whether interface `name` makes temporary IPv6 addresses.
+///
+/// On Linux this is the interface's `use_tempaddr` sysctl. macOS and
+/// FreeBSD only have the host-wide `net.inet6.ip6.use_tempaddr` and
+/// `prefer_tempaddr`, so there `name` is only checked to exist and every
+/// interface gives the same answer. Fails with `ErrorKind::NotFound` if
+/// IPv6 is disabled on the interface, and with `ErrorKind::Unsupported` on
+/// other platforms.
+pub fn temp_addrs(name: &str) -> io::Result<TempAddrs> {
+    let name = &validate_name(name)?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::ipv6::temp_addrs(name);
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    {
+        super::if_index(name)?;
+        return Ok(temp_addrs_of(
+            sysctl_int(USE_TEMPADDR)?,
+            sysctl_int(PREFER_TEMPADDR)?,
+        ));
+    }
+    #[allow(unreachable_code)]
+    {
+        let _ = name;
+        Err(temp_addrs_unsupported())
+    }
+}
+
+/// Sets whether interface `name` makes temporary IPv6 addresses.
+///
+/// New settings apply to addresses made from the next router advertisement
+/// on. On macOS and FreeBSD this changes the host-wide sysctls, and so
+/// every interface, see [`temp_addrs`].
+pub fn set_temp_addrs(name: &str, mode: TempAddrs) -> io::Result<()> {
+    let name = &validate_name(name)?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::ipv6::set_temp_addrs(name, mode);
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    {
+        super::if_index(name)?;
+        set_sysctl_int(USE_TEMPADDR, (mode != TempAddrs::Disabled) as libc::c_int)?;
+        return set_sysctl_int(
+            PREFER_TEMPADDR,
+            (mode == TempAddrs::Preferred) as libc::c_int,
+        );
+    }
+    #[allow(unreachable_code)]
+    {
+        let _ = (name, mode);
+        Err(temp_addrs_unsupported())
+    }
+}
+
+/// The IPv6 token of interface `name`, the interface identifier SLAAC puts
+/// after the advertised prefixes instead of one of its own making. `None`
+/// if no token is set.
+///
+/// Linux only, as `ip token` sets it. Fails with `ErrorKind::Unsupported`
+/// on other platforms.
+pub fn ipv6_token(name: &str) -> io::Result<Option<Ipv6Addr>> {
+    let name = &validate_name(name)?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::ipv6::token(name);
+    #[allow(unreachable_code)]
+    {
+        let _ = name;
+        Err(tokens_unsupported())
+    }
+}
+
+/// Sets the IPv6 token of interface `name`, see [`ipv6_token`]; `::`
+/// clears it.
+///
+/// The token must have its upper 64 bits zero. The kernel takes it only
+/// on interfaces that do SLAAC, and an interface that does not is
+/// reported as `ErrorKind::InvalidInput` with the setting in the way.
+/// Addresses made from the next router advertisement on use the token.
+pub fn set_ipv6_token(name: &str, token: Ipv6Addr) -> io::Result<()> {
+    let name = &validate_name(name)?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::ipv6::set_token(name, token);
+    #[allow(unreachable_code)]
+    {
+        let _ = (name, token);
+        Err(tokens_unsupported())
+    }
+}
+
+/// Whether IPv6 is off on interface `name`, see
+/// [`ipv6_disabled_evidence`](crate::ipv6_disabled_evidence).
+pub(crate) fn ipv6_disabled(name: &str) -> io::Result<Option<Ipv6Disabled>> {
+    let name = &validate_name(name)?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return super::conf::ipv6_disabled(name);
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    return nd6_disabled(name);
+    #[allow(unreachable_code)]
+    {
+        let _ = name;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "telling whether IPv6 is disabled is not supported on this platform",
+        ))
+    }
+}
+
+/// Disabled if the interface has `ND6_IFF_IFDISABLED`, or no IPv6 address;
+/// macOS attaches none to interfaces whose IPv6 is configured off.
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+fn nd6_disabled(name: &ValidName) -> io::Result<Option<Ipv6Disabled>> {
+    let ifaces = crate::ifaces()?;
+    let mut ifaces = ifaces.iter().filter(|iface| name.matches(&iface.name));
+    if ifaces.clone().next().is_none() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no such interface"));
+    }
+    let has_inet6 = ifaces.any(|iface| iface.kind == Kind::Ipv6);
+    let mut status = Ipv6Disabled {
+        disabled: !has_inet6,
+        evidence: vec![crate::Ipv6DisabledEvidence::HasInet6Address(has_inet6)],
+    };
+    match ioctl::socket6().and_then(|socket| ioctl::nd6_flags(&socket, name)) {
+        Ok(flags) => {
+            status.disabled |= flags & ioctl::ND6_IFF_IFDISABLED != 0;
+            status
+                .evidence
+                .push(crate::Ipv6DisabledEvidence::Nd6Flags(flags));
+        }
+        Err(err) => log::debug!("SIOCGIFINFO_IN6 failed on {}: {}", name, err),
+    }
+    Ok(Some(status))
+}
+
+#[allow(dead_code)]
+fn tokens_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "IPv6 tokens are not supported on this platform",
+    )
+}
+
+#[allow(dead_code)]
+fn temp_addrs_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "temporary address settings are not supported on this platform",
+    )
+}
+
+/// The mode the host-wide `use_tempaddr` and `prefer_tempaddr` make.
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+fn temp_addrs_of(use_tempaddr: libc::c_int, prefer_tempaddr: libc::c_int) -> TempAddrs {
+    match (use_tempaddr, prefer_tempaddr) {
+        (0, _) => TempAddrs::Disabled,
+        (_, 0) => TempAddrs::Enabled,
+        _ => TempAddrs::Preferred,
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+const USE_TEMPADDR: &[u8] = b"net.inet6.ip6.use_tempaddr\0";
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+const PREFER_TEMPADDR: &[u8] = b"net.inet6.ip6.prefer_tempaddr\0";
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+fn sysctl_int(name: &[u8]) -> io::Result<libc::c_int> {
+    let mut value: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>();
+    let ret = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr() as *const libc::c_char,
+            &mut value as *mut libc::c_int as *mut libc::c_void,
+            &mut len,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    match ret {
+        0 => Ok(value),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+fn set_sysctl_int(name: &[u8], mut value: libc::c_int) -> io::Result<()> {
+    let ret = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr() as *const libc::c_char,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut value as *mut libc::c_int as *mut libc::c_void,
+            std::mem::size_of::<libc::c_int>(),
+        )
+    };
+    match ret {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_promiscuous_bit_is_read_from_its_word() {
+        let mut req = ifreq::new(&validate_name("eth0").unwrap());
+        assert!(!is_promiscuous(&req));
+        let (word, bit) = PROMISC;
+        unsafe { req.ifr_ifru.ifru_flags[word] = bit | libc::IFF_UP as c_short };
+        assert!(is_promiscuous(&req));
+        unsafe { req.ifr_ifru.ifru_flags[word] = !bit };
+        assert!(!is_promiscuous(&req));
+    }
+
+    #[test]
+    fn promiscuous_mode_is_read_without_privileges() {
+        let lo = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(crate::InterfaceFlags::LOOPBACK))
+            .unwrap();
+        promiscuous(&lo.name).unwrap();
+    }
+
+    #[test]
+    fn bad_names_fail_cleanly() {
+        let err = promiscuous("").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        // Before any change is attempted.
+        let err = set_promiscuous("nosuchif0", true).unwrap_err();
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(err.raw_os_error(), Some(libc::ENODEV));
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        assert_eq!(err.raw_os_error(), Some(libc::ENXIO));
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    #[test]
+    fn host_wide_sysctls_map_to_modes() {
+        assert_eq!(temp_addrs_of(0, 1), TempAddrs::Disabled);
+        assert_eq!(temp_addrs_of(1, 0), TempAddrs::Enabled);
+        assert_eq!(temp_addrs_of(1, 1), TempAddrs::Preferred);
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd"
+    ))]
+    #[test]
+    fn temp_addrs_are_read_without_privileges() {
+        let lo = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(crate::InterfaceFlags::LOOPBACK))
+            .unwrap();
+        let mode = temp_addrs(&lo.name);
+        // Linux loopback reports -1, or has no IPv6 settings at all where
+        // IPv6 is off; the BSDs give the host-wide setting.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        match mode {
+            Ok(mode) => assert_eq!(mode, TempAddrs::Disabled),
+            Err(err) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        mode.unwrap();
+        let err = temp_addrs("nosuchif0").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn long_aliases_are_refused_before_sending() {
+        // No privileges needed: nothing reaches the kernel.
+        let err = set_alias("lo", &"x".repeat(MAX_ALIAS_LEN + 1)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = set_alias("lo", "uplink\0to rack 4").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn aliases_of_missing_interfaces_are_not_found() {
+        let err = alias("nosuchif0").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(alias("").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "freebsd"
+    ))]
+    #[test]
+    fn ipv6_disabled_is_read_without_privileges() {
+        let lo = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(crate::InterfaceFlags::LOOPBACK))
+            .unwrap();
+        let status = ipv6_disabled(&lo.name).unwrap().unwrap();
+        assert!(!status.evidence.is_empty());
+        let err = ipv6_disabled("nosuchif0").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = ipv6_disabled("").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
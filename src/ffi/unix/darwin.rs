@@ -0,0 +1,249 @@
+//! What SystemConfiguration thinks of the interfaces on macOS.
+
+use std::io;
+
+use core_foundation::array::CFArray;
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::dictionary::CFDictionary;
+use core_foundation::string::CFString;
+use core_foundation::ConcreteCFType;
+use system_configuration::dynamic_store::{SCDynamicStore, SCDynamicStoreBuilder};
+
+use crate::Kind;
+
+fn store() -> SCDynamicStore {
+    SCDynamicStoreBuilder::new("ifaces").build()
+}
+
+/// The dictionary stored under `key`, if there is one.
+fn dictionary(store: &SCDynamicStore, key: &str) -> Option<CFDictionary> {
+    store.get(key)?.downcast_into::<CFDictionary>()
+}
+
+/// The value under `key` in `dict`, if it is a `T`.
+fn value<T: ConcreteCFType>(dict: &CFDictionary, key: &str) -> Option<T> {
+    let key = CFString::new(key);
+    let value = dict.find(key.as_concrete_TypeRef() as *const _)?;
+    unsafe { CFType::wrap_under_get_rule(*value as _) }.downcast::<T>()
+}
+
+/// The BSD names of the network services, highest priority first.
+///
+/// This is the Service Order of the Network preferences, which decides the
+/// primary interface when several have a route. Services without an
+/// interface, and ones whose setup cannot be read, are left out.
+pub fn service_order() -> io::Result<Vec<String>> {
+    let store = store();
+    Ok(dictionary(&store, "Setup:/Network/Global/IPv4")
+        .and_then(|global| value::<CFArray>(&global, "ServiceOrder"))
+        .map(|order| service_order_from(&order, |key| dictionary(&store, key)))
+        .unwrap_or_default())
+}
+
+/// The interface names of the services in `order`, the `ServiceOrder` of
+/// the global IPv4 setup, with their setup from `lookup`.
+fn service_order_from(
+    order: &CFArray,
+    lookup: impl Fn(&str) -> Option<CFDictionary>,
+) -> Vec<String> {
+    let mut names = Vec::new();
+    for service in order.iter() {
+        let service = unsafe { CFType::wrap_under_get_rule(*service as _) };
+        let service = match service.downcast::<CFString>() {
+            Some(service) => service.to_string(),
+            None => continue,
+        };
+        let key = format!("Setup:/Network/Service/{}/Interface", service);
+        if let Some(name) = lookup(&key).and_then(|iface| value::<CFString>(&iface, "DeviceName")) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// The BSD name of the interface the system uses for `family` by default,
+/// `Kind::Ipv4` or `Kind::Ipv6`. `None` while there is no primary service,
+/// e.g. when offline.
+pub fn primary_interface(family: Kind) -> io::Result<Option<String>> {
+    let key = match family {
+        Kind::Ipv4 => "State:/Network/Global/IPv4",
+        Kind::Ipv6 => "State:/Network/Global/IPv6",
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "family must be Ipv4 or Ipv6",
+            ))
+        }
+    };
+    Ok(dictionary(&store(), key).and_then(|global| primary_from(&global)))
+}
+
+/// The primary interface in the global IPv4 or IPv6 state.
+fn primary_from(global: &CFDictionary) -> Option<String> {
+    value::<CFString>(global, "PrimaryInterface").map(|name| name.to_string())
+}
+
+/// The interface names and ids of the VPN services that are connected:
+/// services set up as `PPP`, `IPSec` or `VPN` that have IPv4 or IPv6
+/// state naming an interface.
+pub(crate) fn vpn_services() -> Vec<(String, String)> {
+    let store = store();
+    let mut keys = Vec::new();
+    for &pattern in &[
+        "State:/Network/Service/[^/]+/IPv4",
+        "State:/Network/Service/[^/]+/IPv6",
+    ] {
+        if let Some(found) = store.get_keys(pattern) {
+            keys.extend(found.iter().map(|key| key.to_string()));
+        }
+    }
+    vpn_services_from(&keys, |key| dictionary(&store, key))
+}
+
+/// [`vpn_services`] among the service state `keys`, with the dictionaries
+/// from `lookup`.
+fn vpn_services_from(
+    keys: &[String],
+    lookup: impl Fn(&str) -> Option<CFDictionary>,
+) -> Vec<(String, String)> {
+    let mut services = Vec::new();
+    for key in keys {
+        let service = match key.split('/').nth(3) {
+            Some(service) => service.to_owned(),
+            None => continue,
+        };
+        let setup = format!("Setup:/Network/Service/{}/Interface", service);
+        let is_vpn = lookup(&setup)
+            .and_then(|iface| value::<CFString>(&iface, "Type"))
+            .is_some_and(|ty| matches!(ty.to_string().as_str(), "PPP" | "IPSec" | "VPN"));
+        let name = lookup(key)
+            .and_then(|state| value::<CFString>(&state, "InterfaceName"))
+            .map(|name| name.to_string());
+        if let (true, Some(name)) = (is_vpn, name) {
+            if !services.contains(&(name.clone(), service.clone())) {
+                services.push((name, service));
+            }
+        }
+    }
+    services
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use core_foundation::number::CFNumber;
+
+    /// A dictionary of strings, as the store has them.
+    fn strings(pairs: &[(&str, &str)]) -> CFDictionary {
+        let pairs: Vec<(CFString, CFType)> = pairs
+            .iter()
+            .map(|&(key, value)| (CFString::new(key), CFString::new(value).as_CFType()))
+            .collect();
+        CFDictionary::from_CFType_pairs(&pairs).to_untyped()
+    }
+
+    /// A store holding `entries`.
+    fn store_of(entries: Vec<(String, CFDictionary)>) -> impl Fn(&str) -> Option<CFDictionary> {
+        let entries: HashMap<String, CFDictionary> = entries.into_iter().collect();
+        move |key| entries.get(key).cloned()
+    }
+
+    fn interface(service: &str, pairs: &[(&str, &str)]) -> (String, CFDictionary) {
+        (
+            format!("Setup:/Network/Service/{}/Interface", service),
+            strings(pairs),
+        )
+    }
+
+    #[test]
+    fn services_are_in_the_service_order() {
+        let order = CFArray::from_CFTypes(&[
+            CFString::new("B2").as_CFType(),
+            CFString::new("A1").as_CFType(),
+        ])
+        .into_untyped();
+        let lookup = store_of(vec![
+            interface("A1", &[("DeviceName", "en0"), ("Type", "Ethernet")]),
+            interface("B2", &[("DeviceName", "en1"), ("Type", "IEEE80211")]),
+        ]);
+        assert_eq!(service_order_from(&order, lookup), ["en1", "en0"]);
+    }
+
+    #[test]
+    fn odd_entries_of_the_service_order_are_skipped() {
+        let order = CFArray::from_CFTypes(&[
+            CFNumber::from(7).as_CFType(),
+            CFString::new("A1").as_CFType(),
+            // A Bluetooth PAN, say, with no device yet.
+            CFString::new("C3").as_CFType(),
+            // Set up nowhere.
+            CFString::new("D4").as_CFType(),
+        ])
+        .into_untyped();
+        let lookup = store_of(vec![
+            interface("A1", &[("DeviceName", "en0")]),
+            interface("C3", &[("Type", "Ethernet")]),
+        ]);
+        assert_eq!(service_order_from(&order, lookup), ["en0"]);
+    }
+
+    #[test]
+    fn device_names_that_are_no_strings_are_skipped() {
+        let order = CFArray::from_CFTypes(&[CFString::new("A1").as_CFType()]).into_untyped();
+        let setup = CFDictionary::from_CFType_pairs(&[(
+            CFString::new("DeviceName"),
+            CFNumber::from(0).as_CFType(),
+        )])
+        .to_untyped();
+        let lookup = store_of(vec![(
+            "Setup:/Network/Service/A1/Interface".to_owned(),
+            setup,
+        )]);
+        assert!(service_order_from(&order, lookup).is_empty());
+    }
+
+    #[test]
+    fn the_primary_interface_is_read_from_the_global_state() {
+        let global = strings(&[("PrimaryInterface", "en0"), ("PrimaryService", "A1")]);
+        assert_eq!(primary_from(&global).as_deref(), Some("en0"));
+        assert_eq!(primary_from(&strings(&[("PrimaryService", "A1")])), None);
+    }
+
+    #[test]
+    fn connected_vpn_services_are_found() {
+        let keys: Vec<String> = [
+            "State:/Network/Service/V1/IPv4",
+            "State:/Network/Service/V1/IPv6",
+            "State:/Network/Service/A1/IPv4",
+            "State:/Network/Service/V2/IPv4",
+            "State:/Network/Odd",
+        ]
+        .iter()
+        .map(|&key| key.to_owned())
+        .collect();
+        let lookup = store_of(vec![
+            interface("V1", &[("Type", "VPN")]),
+            interface("A1", &[("Type", "Ethernet"), ("DeviceName", "en0")]),
+            interface("V2", &[("Type", "PPP")]),
+            (keys[0].clone(), strings(&[("InterfaceName", "utun3")])),
+            (keys[1].clone(), strings(&[("InterfaceName", "utun3")])),
+            (keys[2].clone(), strings(&[("InterfaceName", "en0")])),
+            // Set up, but not connected.
+            (keys[3].clone(), strings(&[("Router", "10.0.0.1")])),
+        ]);
+        assert_eq!(
+            vpn_services_from(&keys, lookup),
+            [("utun3".to_owned(), "V1".to_owned())]
+        );
+    }
+
+    #[test]
+    fn this_machine() {
+        service_order().unwrap();
+        primary_interface(Kind::Ipv4).unwrap();
+        vpn_services();
+    }
+}
@@ -0,0 +1,712 @@
+//! The `SIOCETHTOOL` ioctl: driver settings that have no netlink attribute
+//! in the link messages.
+
+use std::convert::TryFrom;
+use std::io;
+use std::os::raw::{c_ulong, c_void};
+use std::path::PathBuf;
+
+use bitflags::bitflags;
+
+use super::ioctl::{self, ifreq};
+use crate::{validate_name, ValidName, WolInfo, WolModes};
+
+const SIOCETHTOOL: c_ulong = 0x8946;
+
+const ETHTOOL_GWOL: u32 = 0x5;
+const ETHTOOL_SWOL: u32 = 0x6;
+const ETHTOOL_GSTRINGS: u32 = 0x1b;
+const ETHTOOL_GSSET_INFO: u32 = 0x37;
+const ETHTOOL_GFEATURES: u32 = 0x3a;
+const ETHTOOL_GET_TS_INFO: u32 = 0x41;
+
+/// The string set that names the feature bits.
+const ETH_SS_FEATURES: u32 = 4;
+const ETH_GSTRING_LEN: usize = 32;
+
+const SOPASS_MAX: usize = 6;
+
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct ethtool_wolinfo {
+    cmd: u32,
+    supported: u32,
+    wolopts: u32,
+    sopass: [u8; SOPASS_MAX],
+}
+
+/// Run ethtool command `data`, which starts with its `cmd`, on `name`.
+///
+/// Drivers without the command fail with `EOPNOTSUPP`, which comes back as
+/// `ErrorKind::Unsupported`.
+fn ethtool<T>(name: &ValidName, data: &mut T) -> io::Result<()> {
+    ethtool_raw(name, data as *mut T as *mut c_void)
+}
+
+/// [`ethtool`] for commands with a variable-length tail, which `data` has
+/// to have room for.
+fn ethtool_raw(name: &ValidName, data: *mut c_void) -> io::Result<()> {
+    let mut req = ifreq::new(name);
+    req.ifr_ifru.ifru_data = data;
+    match ioctl::ioctl(&ioctl::socket()?, SIOCETHTOOL, &mut req) {
+        Err(err) if err.raw_os_error() == Some(libc::EOPNOTSUPP) => {
+            Err(io::Error::new(io::ErrorKind::Unsupported, err))
+        }
+        Err(err) if err.raw_os_error() == Some(libc::ENODEV) => {
+            Err(io::Error::new(io::ErrorKind::NotFound, err))
+        }
+        result => result,
+    }
+}
+
+pub fn wol(name: &ValidName) -> io::Result<WolInfo> {
+    let mut info = ethtool_wolinfo {
+        cmd: ETHTOOL_GWOL,
+        ..ethtool_wolinfo::default()
+    };
+    ethtool(name, &mut info)?;
+    Ok(wol_from(&info))
+}
+
+fn wol_from(info: &ethtool_wolinfo) -> WolInfo {
+    WolInfo {
+        supported: WolModes::from_bits_truncate(info.supported),
+        enabled: WolModes::from_bits_truncate(info.wolopts),
+    }
+}
+
+/// Switch on exactly `modes`. The SecureOn password is kept as it was.
+pub fn set_wol(name: &ValidName, modes: WolModes) -> io::Result<()> {
+    let mut info = ethtool_wolinfo {
+        cmd: ETHTOOL_GWOL,
+        ..ethtool_wolinfo::default()
+    };
+    ethtool(name, &mut info)?;
+    info.cmd = ETHTOOL_SWOL;
+    info.wolopts = modes.bits();
+    ethtool(name, &mut info)
+}
+
+/// The state of one offload.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Offload {
+    On,
+    /// Supported, but switched off.
+    Off,
+    /// The driver cannot do it; `ethtool -k` says `off [fixed]`.
+    Unsupported,
+}
+
+/// The offloads of an interface, see [`offloads`].
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct OffloadInfo {
+    pub rx_checksum: Offload,
+    /// Transmit checksumming of any kind: IPv4, IPv6 or generic.
+    pub tx_checksum: Offload,
+    pub scatter_gather: Offload,
+    /// TCP segmentation offload, for IPv4 or IPv6.
+    pub tso: Offload,
+    /// Generic segmentation offload, done in the kernel.
+    pub gso: Offload,
+    /// Generic receive offload, done in the kernel.
+    pub gro: Offload,
+    /// Large receive offload, done by the NIC.
+    pub lro: Offload,
+}
+
+/// The feature names each field of [`OffloadInfo`] is made of.
+const OFFLOAD_FEATURES: [&[&str]; 7] = [
+    &["rx-checksum"],
+    &[
+        "tx-checksum-ipv4",
+        "tx-checksum-ip-generic",
+        "tx-checksum-ipv6",
+    ],
+    &["tx-scatter-gather"],
+    &["tx-tcp-segmentation", "tx-tcp6-segmentation"],
+    &["tx-generic-segmentation"],
+    &["rx-gro"],
+    &["rx-lro"],
+];
+
+/// The offloads of interface `name`, as `ethtool -k` shows them.
+///
+/// The kernel numbers feature bits differently between versions, so they
+/// are looked up by name in its string table first. Fails with
+/// `ErrorKind::Unsupported` if the driver does not report features.
+pub fn offloads(name: &str) -> io::Result<OffloadInfo> {
+    let name = &validate_name(name)?;
+    let names = feature_names(name)?;
+    let blocks = names.len().div_ceil(32);
+    // cmd and size, then available, requested, active and never_changed
+    // for each block of 32 features.
+    let mut buf = vec![0u32; 2 + 4 * blocks];
+    buf[0] = ETHTOOL_GFEATURES;
+    buf[1] = blocks as u32;
+    ethtool_raw(name, buf.as_mut_ptr() as *mut c_void)?;
+    let blocks: Vec<[u32; 4]> = buf[2..]
+        .chunks_exact(4)
+        .map(|block| [block[0], block[1], block[2], block[3]])
+        .collect();
+    Ok(offloads_from(&names, &blocks))
+}
+
+/// The names of the feature bits, in bit order.
+fn feature_names(name: &ValidName) -> io::Result<Vec<String>> {
+    // struct ethtool_sset_info with room for one length.
+    #[repr(C)]
+    struct SsetInfo {
+        cmd: u32,
+        reserved: u32,
+        sset_mask: u64,
+        len: u32,
+    }
+    let mut info = SsetInfo {
+        cmd: ETHTOOL_GSSET_INFO,
+        reserved: 0,
+        sset_mask: 1 << ETH_SS_FEATURES,
+        len: 0,
+    };
+    ethtool(name, &mut info)?;
+    if info.sset_mask & 1 << ETH_SS_FEATURES == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the driver does not name its features",
+        ));
+    }
+    let count = info.len as usize;
+
+    // struct ethtool_gstrings: cmd, string_set and len, then the strings.
+    let mut buf = vec![0u32; 3 + count * ETH_GSTRING_LEN / 4];
+    buf[0] = ETHTOOL_GSTRINGS;
+    buf[1] = ETH_SS_FEATURES;
+    buf[2] = count as u32;
+    ethtool_raw(name, buf.as_mut_ptr() as *mut c_void)?;
+    let bytes: Vec<u8> = buf[3..]
+        .iter()
+        .flat_map(|word| word.to_ne_bytes())
+        .collect();
+    Ok(parse_strings(&bytes))
+}
+
+/// Split a string table into its fixed-width, NUL-padded entries.
+fn parse_strings(table: &[u8]) -> Vec<String> {
+    table
+        .chunks_exact(ETH_GSTRING_LEN)
+        .map(|entry| {
+            let end = entry.iter().position(|&b| b == 0).unwrap_or(entry.len());
+            String::from_utf8_lossy(&entry[..end]).into_owned()
+        })
+        .collect()
+}
+
+/// Read the offloads out of the `available`, `requested`, `active` and
+/// `never_changed` words of each block, with `names` naming the bits.
+fn offloads_from(names: &[String], blocks: &[[u32; 4]]) -> OffloadInfo {
+    let state = |feature: &str| {
+        let bit = names.iter().position(|name| name == feature)?;
+        let [available, _, active, _] = *blocks.get(bit / 32)?;
+        let mask = 1 << (bit % 32);
+        Some(match (available & mask != 0, active & mask != 0) {
+            (_, true) => Offload::On,
+            (true, false) => Offload::Off,
+            (false, false) => Offload::Unsupported,
+        })
+    };
+    // The best state of any of the features that make up one offload.
+    let combined = |features: &[&str]| {
+        let states: Vec<Offload> = features.iter().filter_map(|f| state(f)).collect();
+        if states.contains(&Offload::On) {
+            Offload::On
+        } else if states.contains(&Offload::Off) {
+            Offload::Off
+        } else {
+            Offload::Unsupported
+        }
+    };
+    let [rx_checksum, tx_checksum, scatter_gather, tso, gso, gro, lro] = OFFLOAD_FEATURES;
+    OffloadInfo {
+        rx_checksum: combined(rx_checksum),
+        tx_checksum: combined(tx_checksum),
+        scatter_gather: combined(scatter_gather),
+        tso: combined(tso),
+        gso: combined(gso),
+        gro: combined(gro),
+        lro: combined(lro),
+    }
+}
+
+bitflags! {
+    /// The `SOF_TIMESTAMPING_*` flags: what timestamps a socket on the
+    /// interface can ask for, and how they are reported.
+    #[derive(Default)]
+    pub struct TimestampingFlags: u32 {
+        const TX_HARDWARE = 1 << 0;
+        const TX_SOFTWARE = 1 << 1;
+        const RX_HARDWARE = 1 << 2;
+        const RX_SOFTWARE = 1 << 3;
+        /// Report software timestamps.
+        const SOFTWARE = 1 << 4;
+        /// Deprecated hardware timestamps translated to system time.
+        const SYS_HARDWARE = 1 << 5;
+        /// Report hardware timestamps, in the time of the PHC.
+        const RAW_HARDWARE = 1 << 6;
+        const OPT_ID = 1 << 7;
+        const TX_SCHED = 1 << 8;
+        const TX_ACK = 1 << 9;
+        const OPT_CMSG = 1 << 10;
+        const OPT_TSONLY = 1 << 11;
+        const OPT_STATS = 1 << 12;
+        const OPT_PKTINFO = 1 << 13;
+        const OPT_TX_SWHW = 1 << 14;
+        const BIND_PHC = 1 << 15;
+        const OPT_ID_TCP = 1 << 16;
+        const OPT_RX_FILTER = 1 << 17;
+    }
+}
+
+bitflags! {
+    /// The `HWTSTAMP_TX_*` modes a NIC has, bit `n` for mode `n`.
+    #[derive(Default)]
+    pub struct TxTypes: u32 {
+        const OFF = 1 << 0;
+        const ON = 1 << 1;
+        /// One-step timestamps inserted into Sync messages.
+        const ONESTEP_SYNC = 1 << 2;
+        /// One-step timestamps in Sync and peer delay messages.
+        const ONESTEP_P2P = 1 << 3;
+    }
+}
+
+bitflags! {
+    /// The `HWTSTAMP_FILTER_*` receive filters a NIC has, bit `n` for
+    /// filter `n`.
+    #[derive(Default)]
+    pub struct RxFilters: u32 {
+        const NONE = 1 << 0;
+        const ALL = 1 << 1;
+        const SOME = 1 << 2;
+        const PTP_V1_L4_EVENT = 1 << 3;
+        const PTP_V1_L4_SYNC = 1 << 4;
+        const PTP_V1_L4_DELAY_REQ = 1 << 5;
+        const PTP_V2_L4_EVENT = 1 << 6;
+        const PTP_V2_L4_SYNC = 1 << 7;
+        const PTP_V2_L4_DELAY_REQ = 1 << 8;
+        const PTP_V2_L2_EVENT = 1 << 9;
+        const PTP_V2_L2_SYNC = 1 << 10;
+        const PTP_V2_L2_DELAY_REQ = 1 << 11;
+        const PTP_V2_EVENT = 1 << 12;
+        const PTP_V2_SYNC = 1 << 13;
+        const PTP_V2_DELAY_REQ = 1 << 14;
+        const NTP_ALL = 1 << 15;
+    }
+}
+
+/// The timestamping an interface has, as `ethtool -T` shows it, see
+/// [`timestamping_info`].
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct TimestampingInfo {
+    pub capabilities: TimestampingFlags,
+    /// The PTP hardware clock the NIC timestamps with, `None` without one.
+    pub phc_index: Option<u32>,
+    pub tx_types: TxTypes,
+    pub rx_filters: RxFilters,
+}
+
+impl TimestampingInfo {
+    /// What the kernel does for every interface: software timestamps on
+    /// receive, without a PHC.
+    const SOFTWARE_ONLY: TimestampingInfo = TimestampingInfo {
+        capabilities: TimestampingFlags::from_bits_truncate(
+            TimestampingFlags::RX_SOFTWARE.bits() | TimestampingFlags::SOFTWARE.bits(),
+        ),
+        phc_index: None,
+        tx_types: TxTypes::empty(),
+        rx_filters: RxFilters::empty(),
+    };
+
+    /// Whether the NIC takes timestamps itself, in either direction.
+    pub fn has_hardware(&self) -> bool {
+        self.capabilities
+            .intersects(TimestampingFlags::TX_HARDWARE | TimestampingFlags::RX_HARDWARE)
+    }
+
+    /// The device of the PHC, `/dev/ptp<phc_index>`.
+    pub fn phc_device(&self) -> Option<PathBuf> {
+        self.phc_index
+            .map(|index| PathBuf::from(format!("/dev/ptp{}", index)))
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct ethtool_ts_info {
+    cmd: u32,
+    so_timestamping: u32,
+    phc_index: i32,
+    tx_types: u32,
+    tx_reserved: [u32; 3],
+    rx_filters: u32,
+    rx_reserved: [u32; 3],
+}
+
+/// The timestamping capabilities of interface `name`, from
+/// `ETHTOOL_GET_TS_INFO`.
+///
+/// NICs without hardware timestamping report software-only capabilities,
+/// which [`TimestampingInfo::has_hardware`] tells apart. So do drivers
+/// that do not answer, on kernels that pass the command on to them; only a
+/// missing interface is an error.
+pub fn timestamping_info(name: &str) -> io::Result<TimestampingInfo> {
+    let name = &validate_name(name)?;
+    let mut info = ethtool_ts_info {
+        cmd: ETHTOOL_GET_TS_INFO,
+        ..ethtool_ts_info::default()
+    };
+    match ethtool(name, &mut info) {
+        Ok(()) => Ok(timestamping_from(&info)),
+        Err(err) if err.kind() == io::ErrorKind::Unsupported => Ok(TimestampingInfo::SOFTWARE_ONLY),
+        Err(err) => Err(err),
+    }
+}
+
+fn timestamping_from(info: &ethtool_ts_info) -> TimestampingInfo {
+    TimestampingInfo {
+        capabilities: TimestampingFlags::from_bits_truncate(info.so_timestamping),
+        phc_index: u32::try_from(info.phc_index).ok(),
+        tx_types: TxTypes::from_bits_truncate(info.tx_types),
+        rx_filters: RxFilters::from_bits_truncate(info.rx_filters),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::mem;
+
+    #[test]
+    fn wolinfo_has_the_kernel_layout() {
+        assert_eq!(mem::size_of::<ethtool_wolinfo>(), 20);
+        assert_eq!(mem::offset_of!(ethtool_wolinfo, supported), 4);
+        assert_eq!(mem::offset_of!(ethtool_wolinfo, wolopts), 8);
+        assert_eq!(mem::offset_of!(ethtool_wolinfo, sopass), 12);
+    }
+
+    #[test]
+    fn wol_modes_have_the_wake_bits() {
+        let bits = [
+            (WolModes::PHY, 0x01),
+            (WolModes::UNICAST, 0x02),
+            (WolModes::MULTICAST, 0x04),
+            (WolModes::BROADCAST, 0x08),
+            (WolModes::ARP, 0x10),
+            (WolModes::MAGIC, 0x20),
+            (WolModes::MAGIC_SECURE, 0x40),
+            (WolModes::FILTER, 0x80),
+        ];
+        for (mode, bit) in bits {
+            assert_eq!(mode.bits(), bit, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn wol_answers_are_decoded() {
+        // A typical Intel NIC: `Supports Wake-on: pumbg`, `Wake-on: g`.
+        let info = ethtool_wolinfo {
+            cmd: ETHTOOL_GWOL,
+            supported: 0x2f,
+            wolopts: 0x20,
+            sopass: [0; SOPASS_MAX],
+        };
+        let wol = wol_from(&info);
+        assert_eq!(
+            wol.supported,
+            WolModes::PHY
+                | WolModes::UNICAST
+                | WolModes::MULTICAST
+                | WolModes::BROADCAST
+                | WolModes::MAGIC
+        );
+        assert_eq!(wol.enabled, WolModes::MAGIC);
+        // Bits newer kernels may add are dropped.
+        let info = ethtool_wolinfo {
+            supported: 0x120,
+            ..info
+        };
+        assert_eq!(wol_from(&info).supported, WolModes::MAGIC);
+    }
+
+    #[test]
+    fn drivers_without_wol_are_unsupported() {
+        // Loopback has no get_wol, and no privileges are needed to ask.
+        let err = wol(&validate_name("lo").unwrap()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        let err = wol(&validate_name("nosuchif0").unwrap()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    /// A string table as `ETHTOOL_GSTRINGS` fills it in.
+    fn table(names: &[&str]) -> Vec<u8> {
+        let mut table = vec![0; names.len() * ETH_GSTRING_LEN];
+        for (entry, name) in table.chunks_exact_mut(ETH_GSTRING_LEN).zip(names) {
+            entry[..name.len()].copy_from_slice(name.as_bytes());
+        }
+        table
+    }
+
+    /// The start of the feature table of a 6.1 kernel, with a few of the
+    /// later bits.
+    const FEATURES: [&str; 36] = [
+        "tx-scatter-gather",
+        "tx-checksum-ipv4",
+        "tx-checksum-ip-generic",
+        "tx-checksum-ipv6",
+        "highdma",
+        "tx-scatter-gather-fraglist",
+        "tx-vlan-hw-insert",
+        "rx-vlan-hw-parse",
+        "rx-vlan-filter",
+        "vlan-challenged",
+        "tx-generic-segmentation",
+        "tx-lockless",
+        "netns-local",
+        "rx-gro",
+        "rx-lro",
+        "tx-tcp-segmentation",
+        "tx-gso-robust",
+        "tx-tcp-ecn-segmentation",
+        "tx-tcp-mangleid-segmentation",
+        "tx-tcp6-segmentation",
+        "tx-fcoe-segmentation",
+        "tx-gre-segmentation",
+        "tx-gre-csum-segmentation",
+        "tx-ipxip4-segmentation",
+        "tx-ipxip6-segmentation",
+        "tx-udp_tnl-segmentation",
+        "tx-udp_tnl-csum-segmentation",
+        "tx-gso-partial",
+        "tx-tunnel-remcsum-segmentation",
+        "tx-sctp-segmentation",
+        "tx-esp-segmentation",
+        "tx-udp-segmentation",
+        "tx-gso-list",
+        "tx-checksum-fcoe-crc",
+        "tx-checksum-sctp",
+        "rx-checksum",
+    ];
+
+    fn names() -> Vec<String> {
+        parse_strings(&table(&FEATURES))
+    }
+
+    /// A block per 32 features, with `available` and `active` set for the
+    /// bits of the features named.
+    fn blocks(available: &[&str], active: &[&str]) -> Vec<[u32; 4]> {
+        let mut blocks = vec![[0; 4]; FEATURES.len().div_ceil(32)];
+        for (word, features) in [(0, available), (2, active)] {
+            for feature in features {
+                let bit = FEATURES.iter().position(|f| f == feature).unwrap();
+                blocks[bit / 32][word] |= 1 << (bit % 32);
+            }
+        }
+        blocks
+    }
+
+    #[test]
+    fn string_tables_are_split_into_names() {
+        let names = names();
+        assert_eq!(names.len(), FEATURES.len());
+        assert_eq!(names[0], "tx-scatter-gather");
+        assert_eq!(names[35], "rx-checksum");
+        // A name that fills its entry has no NUL.
+        let long = "x".repeat(ETH_GSTRING_LEN);
+        assert_eq!(
+            parse_strings(&table(&[&long, "rx-gro"])),
+            [long.as_str(), "rx-gro"]
+        );
+        // A short tail is left out.
+        assert!(parse_strings(&table(&["rx-gro"])[..ETH_GSTRING_LEN - 1]).is_empty());
+    }
+
+    #[test]
+    fn offloads_are_on_off_or_unsupported() {
+        let on = ["tx-scatter-gather", "rx-gro", "tx-generic-segmentation"];
+        let available = [&on[..], &["rx-lro", "rx-checksum"]].concat();
+        let offloads = offloads_from(&names(), &blocks(&available, &on));
+        assert_eq!(offloads.scatter_gather, Offload::On);
+        assert_eq!(offloads.gro, Offload::On);
+        assert_eq!(offloads.gso, Offload::On);
+        assert_eq!(offloads.lro, Offload::Off);
+        // Bit 35, in the second block.
+        assert_eq!(offloads.rx_checksum, Offload::Off);
+        assert_eq!(offloads.tx_checksum, Offload::Unsupported);
+        assert_eq!(offloads.tso, Offload::Unsupported);
+    }
+
+    #[test]
+    fn offloads_take_the_best_of_their_features() {
+        let available = [
+            "tx-checksum-ipv4",
+            "tx-checksum-ipv6",
+            "tx-tcp6-segmentation",
+        ];
+        let active = ["tx-checksum-ipv6", "tx-tcp6-segmentation"];
+        let offloads = offloads_from(&names(), &blocks(&available, &active));
+        assert_eq!(offloads.tx_checksum, Offload::On);
+        assert_eq!(offloads.tso, Offload::On);
+        // On but not changeable, `on [fixed]`, is still on.
+        let offloads = offloads_from(&names(), &blocks(&[], &["rx-checksum"]));
+        assert_eq!(offloads.rx_checksum, Offload::On);
+    }
+
+    #[test]
+    fn features_the_kernel_does_not_name_are_unsupported() {
+        let names: Vec<String> = names().into_iter().take(32).collect();
+        let offloads = offloads_from(&names, &blocks(&["rx-checksum"], &["rx-checksum"]));
+        assert_eq!(offloads.rx_checksum, Offload::Unsupported);
+        // Nor do bits past the blocks count.
+        let offloads = offloads_from(&self::names(), &blocks(&[], &[])[..1]);
+        assert_eq!(offloads.rx_checksum, Offload::Unsupported);
+    }
+
+    #[test]
+    fn loopback_offloads_are_read() {
+        let lo = offloads("lo").unwrap();
+        // `on [fixed]` and `off [fixed]` in `ethtool -k lo`.
+        assert_eq!(lo.rx_checksum, Offload::On);
+        assert_eq!(lo.lro, Offload::Unsupported);
+        let err = offloads("nosuchif0").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn ts_info_has_the_kernel_layout() {
+        assert_eq!(mem::size_of::<ethtool_ts_info>(), 44);
+        assert_eq!(mem::offset_of!(ethtool_ts_info, so_timestamping), 4);
+        assert_eq!(mem::offset_of!(ethtool_ts_info, phc_index), 8);
+        assert_eq!(mem::offset_of!(ethtool_ts_info, tx_types), 12);
+        assert_eq!(mem::offset_of!(ethtool_ts_info, rx_filters), 28);
+    }
+
+    #[test]
+    fn timestamping_flags_have_the_sof_bits() {
+        let bits = [
+            (TimestampingFlags::TX_HARDWARE, 0x1),
+            (TimestampingFlags::TX_SOFTWARE, 0x2),
+            (TimestampingFlags::RX_HARDWARE, 0x4),
+            (TimestampingFlags::RX_SOFTWARE, 0x8),
+            (TimestampingFlags::SOFTWARE, 0x10),
+            (TimestampingFlags::SYS_HARDWARE, 0x20),
+            (TimestampingFlags::RAW_HARDWARE, 0x40),
+            (TimestampingFlags::OPT_ID, 0x80),
+            (TimestampingFlags::TX_SCHED, 0x100),
+            (TimestampingFlags::TX_ACK, 0x200),
+            (TimestampingFlags::OPT_TSONLY, 0x800),
+            (TimestampingFlags::BIND_PHC, 0x8000),
+            (TimestampingFlags::OPT_RX_FILTER, 0x20000),
+        ];
+        for (flag, bit) in bits {
+            assert_eq!(flag.bits(), bit, "{:?}", flag);
+        }
+        assert_eq!(TimestampingFlags::all().bits(), 0x3ffff);
+    }
+
+    #[test]
+    fn tx_types_and_rx_filters_are_bits_of_their_modes() {
+        // HWTSTAMP_TX_ONESTEP_P2P is 3, HWTSTAMP_FILTER_NTP_ALL 15.
+        assert_eq!(TxTypes::ONESTEP_P2P.bits(), 1 << 3);
+        assert_eq!(TxTypes::all().bits(), 0xf);
+        assert_eq!(RxFilters::PTP_V2_L2_EVENT.bits(), 1 << 9);
+        assert_eq!(RxFilters::PTP_V2_EVENT.bits(), 1 << 12);
+        assert_eq!(RxFilters::NTP_ALL.bits(), 1 << 15);
+        assert_eq!(RxFilters::all().bits(), 0xffff);
+    }
+
+    fn ts_info(
+        so_timestamping: u32,
+        phc_index: i32,
+        tx_types: u32,
+        rx_filters: u32,
+    ) -> ethtool_ts_info {
+        ethtool_ts_info {
+            cmd: ETHTOOL_GET_TS_INFO,
+            so_timestamping,
+            phc_index,
+            tx_types,
+            rx_filters,
+            ..ethtool_ts_info::default()
+        }
+    }
+
+    #[test]
+    fn hardware_nics_are_decoded() {
+        let hardware = TimestampingFlags::TX_HARDWARE
+            | TimestampingFlags::TX_SOFTWARE
+            | TimestampingFlags::RX_HARDWARE
+            | TimestampingFlags::RX_SOFTWARE
+            | TimestampingFlags::SOFTWARE
+            | TimestampingFlags::RAW_HARDWARE;
+        // An Intel i210 (igb): filters `none` and `all`.
+        let i210 = timestamping_from(&ts_info(0x5f, 0, 0x3, 0x3));
+        assert_eq!(i210.capabilities, hardware);
+        assert_eq!(i210.phc_index, Some(0));
+        assert_eq!(i210.phc_device(), Some(PathBuf::from("/dev/ptp0")));
+        assert_eq!(i210.tx_types, TxTypes::OFF | TxTypes::ON);
+        assert_eq!(i210.rx_filters, RxFilters::NONE | RxFilters::ALL);
+        assert!(i210.has_hardware());
+        // An Intel 82599 (ixgbe), with PTP-only filters.
+        let x520 = timestamping_from(&ts_info(0x5f, 2, 0x3, 0x1031));
+        assert_eq!(x520.phc_device(), Some(PathBuf::from("/dev/ptp2")));
+        assert_eq!(
+            x520.rx_filters,
+            RxFilters::NONE
+                | RxFilters::PTP_V1_L4_SYNC
+                | RxFilters::PTP_V1_L4_DELAY_REQ
+                | RxFilters::PTP_V2_EVENT
+        );
+        // A Mellanox ConnectX-6 (mlx5) with one-step Sync.
+        let cx6 = timestamping_from(&ts_info(0x5f, 1, 0x7, 0x3));
+        assert_eq!(
+            cx6.tx_types,
+            TxTypes::OFF | TxTypes::ON | TxTypes::ONESTEP_SYNC
+        );
+    }
+
+    #[test]
+    fn software_only_nics_have_no_phc() {
+        // virtio-net: `software-transmit`, `software-receive`,
+        // `software-system-clock` and `PTP Hardware Clock: none`.
+        let virtio = timestamping_from(&ts_info(0x1a, -1, 0, 0));
+        assert_eq!(
+            virtio.capabilities,
+            TimestampingFlags::TX_SOFTWARE
+                | TimestampingFlags::RX_SOFTWARE
+                | TimestampingFlags::SOFTWARE
+        );
+        assert_eq!(virtio.phc_index, None);
+        assert_eq!(virtio.phc_device(), None);
+        assert!(!virtio.has_hardware());
+        assert!(!TimestampingInfo::SOFTWARE_ONLY.has_hardware());
+        assert_eq!(TimestampingInfo::SOFTWARE_ONLY.phc_index, None);
+        // Bits newer kernels may add are dropped.
+        let info = timestamping_from(&ts_info(0x1a | 1 << 30, -1, 1 << 20, 1 << 20));
+        assert_eq!(info.capabilities, virtio.capabilities);
+        assert!(info.tx_types.is_empty());
+        assert!(info.rx_filters.is_empty());
+    }
+
+    #[test]
+    fn loopback_timestamps_in_software() {
+        let lo = timestamping_info("lo").unwrap();
+        assert!(!lo.has_hardware());
+        assert_eq!(lo.phc_index, None);
+        assert!(lo
+            .capabilities
+            .contains(TimestampingFlags::RX_SOFTWARE | TimestampingFlags::SOFTWARE));
+        let err = timestamping_info("nosuchif0").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,270 @@
+//! The `struct if_data` that `ifa_data` of an `AF_LINK` entry points to.
+//!
+//! Only the leading fields are declared, the kernels append more over time.
+//! Darwin hands out the 32-bit `if_data` here, its `if_data64` is only
+//! available through `sysctl`, so byte counters wrap at 4 GiB there.
+
+use crate::{CounterWidth, InterfaceStats};
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct if_data {
+    pub ifi_type: u8,
+    pub ifi_typelen: u8,
+    pub ifi_physical: u8,
+    pub ifi_addrlen: u8,
+    pub ifi_hdrlen: u8,
+    pub ifi_recvquota: u8,
+    pub ifi_xmitquota: u8,
+    pub ifi_unused1: u8,
+    pub ifi_mtu: u32,
+    pub ifi_metric: u32,
+    pub ifi_baudrate: u32,
+    pub ifi_ipackets: u32,
+    pub ifi_ierrors: u32,
+    pub ifi_opackets: u32,
+    pub ifi_oerrors: u32,
+    pub ifi_collisions: u32,
+    pub ifi_ibytes: u32,
+    pub ifi_obytes: u32,
+    pub ifi_imcasts: u32,
+    pub ifi_omcasts: u32,
+    pub ifi_iqdrops: u32,
+}
+
+#[cfg(target_os = "freebsd")]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct if_data {
+    pub ifi_type: u8,
+    pub ifi_physical: u8,
+    pub ifi_addrlen: u8,
+    pub ifi_hdrlen: u8,
+    pub ifi_link_state: u8,
+    pub ifi_vhid: u8,
+    pub ifi_datalen: u16,
+    pub ifi_mtu: u32,
+    pub ifi_metric: u32,
+    pub ifi_baudrate: u64,
+    pub ifi_ipackets: u64,
+    pub ifi_ierrors: u64,
+    pub ifi_opackets: u64,
+    pub ifi_oerrors: u64,
+    pub ifi_collisions: u64,
+    pub ifi_ibytes: u64,
+    pub ifi_obytes: u64,
+    pub ifi_imcasts: u64,
+    pub ifi_omcasts: u64,
+    pub ifi_iqdrops: u64,
+    pub ifi_oqdrops: u64,
+}
+
+#[cfg(target_os = "openbsd")]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct if_data {
+    pub ifi_type: u8,
+    pub ifi_addrlen: u8,
+    pub ifi_hdrlen: u8,
+    pub ifi_link_state: u8,
+    pub ifi_mtu: u32,
+    pub ifi_metric: u32,
+    pub ifi_rdomain: u32,
+    pub ifi_baudrate: u64,
+    pub ifi_ipackets: u64,
+    pub ifi_ierrors: u64,
+    pub ifi_opackets: u64,
+    pub ifi_oerrors: u64,
+    pub ifi_collisions: u64,
+    pub ifi_ibytes: u64,
+    pub ifi_obytes: u64,
+    pub ifi_imcasts: u64,
+    pub ifi_omcasts: u64,
+    pub ifi_iqdrops: u64,
+    pub ifi_oqdrops: u64,
+}
+
+#[cfg(target_os = "netbsd")]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct if_data {
+    pub ifi_type: u8,
+    pub ifi_addrlen: u8,
+    pub ifi_hdrlen: u8,
+    pub ifi_link_state: std::os::raw::c_int,
+    pub ifi_mtu: u64,
+    pub ifi_metric: u64,
+    pub ifi_baudrate: u64,
+    pub ifi_ipackets: u64,
+    pub ifi_ierrors: u64,
+    pub ifi_opackets: u64,
+    pub ifi_oerrors: u64,
+    pub ifi_collisions: u64,
+    pub ifi_ibytes: u64,
+    pub ifi_obytes: u64,
+    pub ifi_imcasts: u64,
+    pub ifi_omcasts: u64,
+    pub ifi_iqdrops: u64,
+}
+
+// The casts are no-ops on some of the platforms.
+#[allow(clippy::unnecessary_cast)]
+impl if_data {
+    pub fn mtu(&self) -> u32 {
+        self.ifi_mtu as u32
+    }
+
+    pub fn stats(&self) -> InterfaceStats {
+        InterfaceStats {
+            rx_bytes: self.ifi_ibytes as u64,
+            tx_bytes: self.ifi_obytes as u64,
+            rx_packets: self.ifi_ipackets as u64,
+            tx_packets: self.ifi_opackets as u64,
+            rx_errors: self.ifi_ierrors as u64,
+            tx_errors: self.ifi_oerrors as u64,
+            rx_dropped: self.ifi_iqdrops as u64,
+            tx_dropped: self.tx_dropped(),
+            multicast: self.ifi_imcasts as u64,
+            collisions: self.ifi_collisions as u64,
+            width: match std::mem::size_of_val(&self.ifi_ibytes) {
+                4 => CounterWidth::Bits32,
+                _ => CounterWidth::Bits64,
+            },
+        }
+    }
+
+    #[cfg(any(target_os = "freebsd", target_os = "openbsd"))]
+    fn tx_dropped(&self) -> u64 {
+        self.ifi_oqdrops
+    }
+
+    /// Not counted separately here.
+    #[cfg(not(any(target_os = "freebsd", target_os = "openbsd")))]
+    fn tx_dropped(&self) -> u64 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(offset, width)` of mtu, ipackets, ierrors, opackets, oerrors,
+    /// collisions, ibytes, obytes, imcasts, iqdrops and oqdrops, from the
+    /// system headers. `oqdrops` is at offset 0 where there is none.
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    const LAYOUT: [(usize, usize); 11] = [
+        (8, 4),
+        (20, 4),
+        (24, 4),
+        (28, 4),
+        (32, 4),
+        (36, 4),
+        (40, 4),
+        (44, 4),
+        (48, 4),
+        (56, 4),
+        (0, 0),
+    ];
+    #[cfg(any(target_os = "freebsd", target_os = "openbsd"))]
+    const LAYOUT: [(usize, usize); 11] = [
+        (if cfg!(target_os = "freebsd") { 8 } else { 4 }, 4),
+        (24, 8),
+        (32, 8),
+        (40, 8),
+        (48, 8),
+        (56, 8),
+        (64, 8),
+        (72, 8),
+        (80, 8),
+        (96, 8),
+        (104, 8),
+    ];
+    #[cfg(target_os = "netbsd")]
+    const LAYOUT: [(usize, usize); 11] = [
+        (8, 8),
+        (32, 8),
+        (40, 8),
+        (48, 8),
+        (56, 8),
+        (64, 8),
+        (72, 8),
+        (80, 8),
+        (88, 8),
+        (104, 8),
+        (0, 0),
+    ];
+
+    /// What the kernel would write for the values given, in LAYOUT order.
+    fn canned(values: [u64; 11]) -> Vec<u8> {
+        let mut bytes = vec![0; 256];
+        for ((offset, width), value) in LAYOUT.iter().zip(values) {
+            let value = value.to_ne_bytes();
+            let value = match cfg!(target_endian = "little") {
+                true => &value[..*width],
+                false => &value[8 - width..],
+            };
+            bytes[*offset..offset + width].copy_from_slice(value);
+        }
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> if_data {
+        assert!(bytes.len() >= std::mem::size_of::<if_data>());
+        unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const if_data) }
+    }
+
+    #[test]
+    fn fields_are_where_the_headers_put_them() {
+        let data = decode(&canned([1500, 10, 1, 20, 2, 3, 1000, 2000, 4, 5, 6]));
+        assert_eq!(data.mtu(), 1500);
+        let has_oqdrops = LAYOUT[10].1 != 0;
+        assert_eq!(
+            data.stats(),
+            InterfaceStats {
+                rx_bytes: 1000,
+                tx_bytes: 2000,
+                rx_packets: 10,
+                tx_packets: 20,
+                rx_errors: 1,
+                tx_errors: 2,
+                rx_dropped: 5,
+                tx_dropped: if has_oqdrops { 6 } else { 0 },
+                multicast: 4,
+                collisions: 3,
+                width: match LAYOUT[6].1 {
+                    4 => CounterWidth::Bits32,
+                    _ => CounterWidth::Bits64,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn counters_use_their_full_width() {
+        let max = match LAYOUT[6].1 {
+            4 => u32::MAX as u64,
+            _ => u64::MAX,
+        };
+        let data = decode(&canned([9000, 0, 0, 0, 0, 0, max, max - 1, 0, 0, 0]));
+        assert_eq!(data.mtu(), 9000);
+        assert_eq!(data.stats().rx_bytes, max);
+        assert_eq!(data.stats().tx_bytes, max - 1);
+    }
+
+    #[test]
+    fn loopback_reports_its_counters() {
+        let lo = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.kind == crate::Kind::Link && iface.name.starts_with("lo"))
+            .unwrap();
+        assert!(lo.mtu.is_some());
+        assert!(lo.stats.is_some());
+    }
+}
@@ -0,0 +1,216 @@
+use std::io;
+use std::mem;
+use std::os::raw::{c_char, c_int, c_short, c_ulong, c_void};
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+
+use crate::ValidName;
+
+pub const IFNAMSIZ: usize = 16;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const IFRU_SIZE: usize = 24;
+#[cfg(target_os = "netbsd")]
+const IFRU_SIZE: usize = 128;
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "netbsd")))]
+const IFRU_SIZE: usize = 16;
+
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union ifreq_ifru {
+    /// `ifru_flags[1]` is `ifr_flagshigh` on FreeBSD, unused elsewhere.
+    pub ifru_flags: [c_short; 2],
+    pub ifru_ivalue: c_int,
+    pub ifru_addr: libc::sockaddr,
+    pub ifru_data: *mut c_void,
+    ifru_pad: [u8; IFRU_SIZE],
+}
+
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ifreq {
+    pub ifr_name: [c_char; IFNAMSIZ],
+    pub ifr_ifru: ifreq_ifru,
+}
+
+impl ifreq {
+    /// A request for interface `name`, with everything else zeroed.
+    pub fn new(name: &ValidName) -> ifreq {
+        let mut req: ifreq = unsafe { mem::zeroed() };
+        for (dst, src) in req.ifr_name.iter_mut().zip(name.as_str().as_bytes()) {
+            *dst = *src as c_char;
+        }
+        req
+    }
+}
+
+// BSD ioctl numbers encode direction, parameter length, group and number.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const fn iowr(inout: c_ulong, group: u8, num: u8, len: usize) -> c_ulong {
+    inout | ((len as c_ulong & 0x1fff) << 16) | ((group as c_ulong) << 8) | num as c_ulong
+}
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const IOC_IN: c_ulong = 0x8000_0000;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const IOC_INOUT: c_ulong = 0xc000_0000;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SIOCGIFFLAGS: c_ulong = 0x8913;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SIOCSIFFLAGS: c_ulong = 0x8914;
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub const SIOCGIFFLAGS: c_ulong = iowr(IOC_INOUT, b'i', 17, mem::size_of::<ifreq>());
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub const SIOCSIFFLAGS: c_ulong = iowr(IOC_IN, b'i', 16, mem::size_of::<ifreq>());
+/// The address to delete goes in `ifru_addr`.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub const SIOCDIFADDR: c_ulong = iowr(IOC_IN, b'i', 25, mem::size_of::<ifreq>());
+/// The routing domain goes in `ifru_ivalue` (`ifr_rdomainid`).
+#[cfg(target_os = "openbsd")]
+pub const SIOCGIFRDOMAIN: c_ulong = iowr(IOC_INOUT, b'i', 160, mem::size_of::<ifreq>());
+
+/// `struct ifmediareq`, for `SIOCGIFMEDIA`. Leaving `ifm_ulist` null asks
+/// for the current media word only.
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct ifmediareq {
+    pub ifm_name: [c_char; IFNAMSIZ],
+    pub ifm_current: c_int,
+    pub ifm_mask: c_int,
+    pub ifm_status: c_int,
+    pub ifm_active: c_int,
+    pub ifm_count: c_int,
+    pub ifm_ulist: *mut c_int,
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+pub const SIOCGIFMEDIA: c_ulong = iowr(IOC_INOUT, b'i', 56, mem::size_of::<ifmediareq>());
+/// The media type bits of a media word, and the type of 802.11 media.
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+pub const IFM_NMASK: c_int = 0xe0;
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+pub const IFM_IEEE80211: c_int = 0x80;
+
+/// `struct in6_ifreq`, with the union cut down to what is read here and
+/// padded to its largest member, `struct icmp6_ifstat`.
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct in6_ifreq {
+    pub ifr_name: [c_char; IFNAMSIZ],
+    pub ifr_ifru: in6_ifreq_ifru,
+}
+
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub union in6_ifreq_ifru {
+    pub ifru_addr: libc::sockaddr_in6,
+    pub ifru_flags6: c_int,
+    _icmp6stat: [u64; 34],
+}
+
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+pub const SIOCGIFAFLAG_IN6: c_ulong = iowr(IOC_INOUT, b'i', 73, mem::size_of::<in6_ifreq>());
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+pub const IN6_IFF_AUTOCONF: c_int = 0x40;
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+pub const IN6_IFF_TEMPORARY: c_int = 0x80;
+/// Set by `configd` on addresses it got from DHCPv6.
+#[cfg(target_os = "macos")]
+pub const IN6_IFF_DYNAMIC: c_int = 0x100;
+
+/// `struct in6_ndireq`, for `SIOCGIFINFO_IN6`.
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct in6_ndireq {
+    pub ifname: [c_char; IFNAMSIZ],
+    pub ndi: nd_ifinfo,
+}
+
+/// `struct nd_ifinfo`, with the fields after `flags` left opaque.
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct nd_ifinfo {
+    pub linkmtu: u32,
+    pub maxmtu: u32,
+    pub basereachable: u32,
+    pub reachable: u32,
+    pub retrans: u32,
+    pub flags: u32,
+    recalctm: c_int,
+    chlim: u8,
+    receivedra: u8,
+    randomseeds: [u8; 24],
+}
+
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+pub const SIOCGIFINFO_IN6: c_ulong = iowr(IOC_INOUT, b'i', 108, mem::size_of::<in6_ndireq>());
+/// IPv6 is turned off on the interface, e.g. with `ifconfig -inet6` or
+/// after duplicate address detection failed for its link-local address.
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+pub const ND6_IFF_IFDISABLED: u32 = 0x8;
+
+/// A throwaway datagram socket to issue interface ioctls on.
+pub fn socket() -> io::Result<OwnedFd> {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// A throwaway IPv6 datagram socket, for the `_IN6` ioctls.
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+pub fn socket6() -> io::Result<OwnedFd> {
+    let fd = unsafe { libc::socket(libc::AF_INET6, libc::SOCK_DGRAM, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// The `IN6_IFF_*` flags of `addr` on interface `name`.
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+pub fn in6_flags(
+    socket: &OwnedFd,
+    name: &ValidName,
+    addr: &std::net::SocketAddrV6,
+) -> io::Result<c_int> {
+    let mut req: in6_ifreq = unsafe { mem::zeroed() };
+    for (dst, src) in req.ifr_name.iter_mut().zip(name.as_str().as_bytes()) {
+        *dst = *src as c_char;
+    }
+    let sa = unsafe { &mut req.ifr_ifru.ifru_addr };
+    sa.sin6_len = mem::size_of::<libc::sockaddr_in6>() as u8;
+    sa.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+    sa.sin6_addr.s6_addr = addr.ip().octets();
+    sa.sin6_scope_id = addr.scope_id();
+    ioctl(socket, SIOCGIFAFLAG_IN6, &mut req)?;
+    Ok(unsafe { req.ifr_ifru.ifru_flags6 })
+}
+
+/// The `ND6_IFF_*` flags of interface `name`.
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+pub fn nd6_flags(socket: &OwnedFd, name: &ValidName) -> io::Result<u32> {
+    let mut req: in6_ndireq = unsafe { mem::zeroed() };
+    for (dst, src) in req.ifname.iter_mut().zip(name.as_str().as_bytes()) {
+        *dst = *src as c_char;
+    }
+    ioctl(socket, SIOCGIFINFO_IN6, &mut req)?;
+    Ok(req.ndi.flags)
+}
+
+/// Issue `request` with `req`, an `ifreq` or another request struct that
+/// starts with the interface name.
+pub fn ioctl<T>(socket: &OwnedFd, request: c_ulong, req: &mut T) -> io::Result<()> {
+    match unsafe { libc::ioctl(socket.as_raw_fd(), request as _, req as *mut T) } {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
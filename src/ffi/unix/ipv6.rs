@@ -0,0 +1,400 @@
+//! How Linux forms IPv6 addresses on an interface.
+
+use std::io;
+use std::net::Ipv6Addr;
+
+use super::netlink;
+use super::sysfs::FsRoot;
+use crate::{validate_name, TempAddrs, ValidName};
+
+/// `addr_gen_mode`: how the interface identifier of SLAAC addresses is made.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum AddrGenMode {
+    /// From the MAC address, the classic EUI-64 form.
+    Eui64,
+    /// No link-local address is generated at all.
+    None,
+    /// RFC 7217 stable-privacy addresses, derived from `stable_secret`.
+    StablePrivacy,
+    /// Stable-privacy addresses with a random secret.
+    Random,
+    Unknown(i64),
+}
+
+impl From<i64> for AddrGenMode {
+    fn from(value: i64) -> AddrGenMode {
+        match value {
+            0 => AddrGenMode::Eui64,
+            1 => AddrGenMode::None,
+            2 => AddrGenMode::StablePrivacy,
+            3 => AddrGenMode::Random,
+            other => AddrGenMode::Unknown(other),
+        }
+    }
+}
+
+/// The IPv6 address generation settings of an interface, see
+/// [`ipv6_addr_gen`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Ipv6AddrGen {
+    pub mode: AddrGenMode,
+    pub use_tempaddr: TempAddrs,
+    /// Whether a `stable_secret` is set. `None` when it cannot be read,
+    /// which needs root.
+    pub stable_secret: Option<bool>,
+    /// Addresses the kernel flags `IFA_F_STABLE_PRIVACY`. Empty if netlink
+    /// is unavailable.
+    pub stable_privacy_addrs: Vec<Ipv6Addr>,
+}
+
+/// The IPv6 address generation settings of the interface called `name`.
+///
+/// `Ok(None)` when the interface has no IPv6 configuration, i.e. IPv6 is
+/// disabled for it or for the whole host. An interface that does not exist
+/// gives `NotFound`.
+pub fn ipv6_addr_gen(name: &str) -> io::Result<Option<Ipv6AddrGen>> {
+    let name = &validate_name(name)?;
+    let mut gen = match addr_gen_from(&FsRoot::system(), name)? {
+        Some(gen) => gen,
+        None => return Ok(None),
+    };
+    let index = super::if_index(name)?;
+    if let Ok(addrs) = netlink::addrs(libc::AF_INET6) {
+        gen.stable_privacy_addrs = addrs
+            .iter()
+            .filter(|addr| addr.index == index && addr.flags & netlink::IFA_F_STABLE_PRIVACY != 0)
+            .filter_map(|addr| match addr.address {
+                Some(std::net::IpAddr::V6(v6)) => Some(v6),
+                _ => None,
+            })
+            .collect();
+    }
+    Ok(Some(gen))
+}
+
+/// The `conf` directory of the interface, `None` if IPv6 is disabled for
+/// it.
+fn conf_of(root: &FsRoot, name: &ValidName) -> io::Result<Option<String>> {
+    if !root.exists(format!("sys/class/net/{}", name)) {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no such interface"));
+    }
+    let conf = format!("proc/sys/net/ipv6/conf/{}", name);
+    Ok(Some(conf).filter(|conf| root.exists(conf)))
+}
+
+fn addr_gen_from(root: &FsRoot, name: &ValidName) -> io::Result<Option<Ipv6AddrGen>> {
+    let conf = match conf_of(root, name)? {
+        Some(conf) => conf,
+        None => return Ok(None),
+    };
+
+    let stable_secret = match root.read(format!("{}/stable_secret", conf)) {
+        Ok(_) => Some(true),
+        // The kernel answers EIO while no secret is set.
+        Err(err) if err.raw_os_error() == Some(libc::EIO) => Some(false),
+        Err(_) => None,
+    };
+    Ok(Some(Ipv6AddrGen {
+        mode: root.read_int(format!("{}/addr_gen_mode", conf))?.into(),
+        use_tempaddr: root.read_int(format!("{}/use_tempaddr", conf))?.into(),
+        stable_secret,
+        stable_privacy_addrs: Vec::new(),
+    }))
+}
+
+/// The `use_tempaddr` setting of the interface called `name`.
+pub fn temp_addrs(name: &ValidName) -> io::Result<TempAddrs> {
+    temp_addrs_from(&FsRoot::system(), name)
+}
+
+/// Write `use_tempaddr` for the interface called `name`. The kernel applies
+/// it to addresses made from the next router advertisement on.
+pub fn set_temp_addrs(name: &ValidName, mode: TempAddrs) -> io::Result<()> {
+    set_temp_addrs_in(&FsRoot::system(), name, mode)
+}
+
+fn temp_addrs_from(root: &FsRoot, name: &ValidName) -> io::Result<TempAddrs> {
+    let conf = ipv6_conf_of(root, name)?;
+    Ok(root.read_int(format!("{}/use_tempaddr", conf))?.into())
+}
+
+fn set_temp_addrs_in(root: &FsRoot, name: &ValidName, mode: TempAddrs) -> io::Result<()> {
+    let conf = ipv6_conf_of(root, name)?;
+    root.write(
+        format!("{}/use_tempaddr", conf),
+        &i64::from(mode).to_string(),
+    )
+}
+
+/// The IPv6 token of the interface called `name`, `None` if none is set
+/// or IPv6 is disabled on it.
+pub fn token(name: &ValidName) -> io::Result<Option<Ipv6Addr>> {
+    let link = netlink::link(super::if_index(name)?)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such interface"))?;
+    Ok(link.ipv6_token.filter(|token| !token.is_unspecified()))
+}
+
+/// Set the IPv6 token of the interface called `name`, `::` to clear it.
+pub fn set_token(name: &ValidName, token: Ipv6Addr) -> io::Result<()> {
+    if token.segments()[..4] != [0; 4] {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "a token must have the upper 64 bits zero",
+        ));
+    }
+    match netlink::set_ipv6_token(super::if_index(name)?, &token) {
+        Err(err) if err.raw_os_error() == Some(libc::EINVAL) => {
+            Err(token_rejected(&FsRoot::system(), name).unwrap_or(err))
+        }
+        result => result,
+    }
+}
+
+/// Why the kernel refused to set a token on the interface, as far as its
+/// settings tell. The kernel only takes tokens on interfaces that do
+/// SLAAC: not loopback or `NOARP` ones, and only while router
+/// advertisements are accepted and router solicitations sent.
+fn token_rejected(root: &FsRoot, name: &ValidName) -> Option<io::Error> {
+    let conf = ipv6_conf_of(root, name).ok()?;
+    let int = |setting: &str| root.read_int(format!("{}/{}", conf, setting)).ok();
+    let flags = root
+        .read(format!("sys/class/net/{}/flags", name))
+        .ok()
+        .and_then(|flags| i64::from_str_radix(flags.trim_start_matches("0x"), 16).ok())
+        .unwrap_or(0);
+    let reason = if flags & libc::IFF_LOOPBACK as i64 != 0 {
+        "tokens cannot be set on loopback interfaces"
+    } else if flags & libc::IFF_NOARP as i64 != 0 {
+        "tokens cannot be set on interfaces without neighbor discovery (NOARP)"
+    } else if int("accept_ra") == Some(0)
+        || (int("accept_ra") == Some(1) && int("forwarding") == Some(1))
+    {
+        "the interface does not accept router advertisements (accept_ra), which a token needs"
+    } else if int("router_solicitations") == Some(0) {
+        "the interface sends no router solicitations (router_solicitations), which a token needs"
+    } else {
+        return None;
+    };
+    Some(io::Error::new(io::ErrorKind::InvalidInput, reason))
+}
+
+/// [`conf_of`], failing with `NotFound` if IPv6 is disabled.
+fn ipv6_conf_of(root: &FsRoot, name: &ValidName) -> io::Result<String> {
+    conf_of(root, name)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "IPv6 is disabled on the interface"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ffi::unix::sysfs::fake::FakeTree;
+
+    fn conf(tree: &FakeTree, name: &str, mode: &str, tempaddr: &str) {
+        tree.dir(&format!("sys/class/net/{}", name));
+        let conf = format!("proc/sys/net/ipv6/conf/{}", name);
+        tree.file(&format!("{}/addr_gen_mode", conf), mode);
+        tree.file(&format!("{}/use_tempaddr", conf), tempaddr);
+    }
+
+    fn name(name: &str) -> ValidName {
+        validate_name(name).unwrap()
+    }
+
+    #[test]
+    fn addr_gen_modes_map_to_their_values() {
+        let modes = [
+            (0, AddrGenMode::Eui64),
+            (1, AddrGenMode::None),
+            (2, AddrGenMode::StablePrivacy),
+            (3, AddrGenMode::Random),
+            (4, AddrGenMode::Unknown(4)),
+            (-1, AddrGenMode::Unknown(-1)),
+        ];
+        for (value, mode) in modes {
+            assert_eq!(AddrGenMode::from(value), mode);
+        }
+    }
+
+    #[test]
+    fn settings_are_read_from_the_conf_directory() {
+        let tree = FakeTree::new();
+        conf(&tree, "eth0", "2\n", "2\n");
+        tree.file("proc/sys/net/ipv6/conf/eth0/stable_secret", "2001:db8::1\n");
+        conf(&tree, "lo", "0\n", "-1\n");
+        let root = tree.root();
+
+        let eth0 = addr_gen_from(&root, &name("eth0")).unwrap().unwrap();
+        assert_eq!(eth0.mode, AddrGenMode::StablePrivacy);
+        assert_eq!(eth0.use_tempaddr, TempAddrs::Preferred);
+        assert_eq!(eth0.stable_secret, Some(true));
+        assert!(eth0.stable_privacy_addrs.is_empty());
+
+        let lo = addr_gen_from(&root, &name("lo")).unwrap().unwrap();
+        assert_eq!(lo.mode, AddrGenMode::Eui64);
+        assert_eq!(lo.use_tempaddr, TempAddrs::Disabled);
+        // Without the file, whether one is set is unknown.
+        assert_eq!(lo.stable_secret, None);
+    }
+
+    #[test]
+    fn disabled_ipv6_is_none() {
+        let tree = FakeTree::new();
+        tree.dir("sys/class/net/can0");
+        let root = tree.root();
+        assert_eq!(addr_gen_from(&root, &name("can0")).unwrap(), None);
+        let err = temp_addrs_from(&root, &name("can0")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn missing_interfaces_are_not_found() {
+        let tree = FakeTree::new();
+        conf(&tree, "eth0", "0", "0");
+        let err = addr_gen_from(&tree.root(), &name("eth1")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn garbled_settings_are_invalid_data() {
+        let tree = FakeTree::new();
+        conf(&tree, "eth0", "eui64\n", "0\n");
+        let err = addr_gen_from(&tree.root(), &name("eth0")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn use_tempaddr_is_written_back() {
+        let tree = FakeTree::new();
+        conf(&tree, "eth0", "0\n", "0\n");
+        let root = tree.root();
+        set_temp_addrs_in(&root, &name("eth0"), TempAddrs::Enabled).unwrap();
+        assert_eq!(
+            temp_addrs_from(&root, &name("eth0")).unwrap(),
+            TempAddrs::Enabled
+        );
+    }
+
+    #[test]
+    fn every_mode_is_written_as_its_value() {
+        let tree = FakeTree::new();
+        conf(&tree, "eth0", "0\n", "-1\n");
+        let root = tree.root();
+        for (mode, value) in [
+            (TempAddrs::Preferred, "2"),
+            (TempAddrs::Enabled, "1"),
+            (TempAddrs::Disabled, "0"),
+        ] {
+            set_temp_addrs_in(&root, &name("eth0"), mode).unwrap();
+            let written = root.read("proc/sys/net/ipv6/conf/eth0/use_tempaddr");
+            assert_eq!(written.unwrap(), value);
+            assert_eq!(temp_addrs_from(&root, &name("eth0")).unwrap(), mode);
+        }
+    }
+
+    #[test]
+    fn use_tempaddr_is_not_written_where_ipv6_is_disabled() {
+        let tree = FakeTree::new();
+        tree.dir("sys/class/net/can0");
+        let root = tree.root();
+        let err = set_temp_addrs_in(&root, &name("can0"), TempAddrs::Enabled).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(!root.exists("proc/sys/net/ipv6/conf/can0/use_tempaddr"));
+    }
+
+    #[test]
+    fn token_refusals_are_explained() {
+        let tree = FakeTree::new();
+        conf(&tree, "lo", "0", "-1");
+        tree.file("sys/class/net/lo/flags", "0x9\n");
+        conf(&tree, "eth0", "0", "0");
+        tree.file("sys/class/net/eth0/flags", "0x1003\n");
+        tree.file("proc/sys/net/ipv6/conf/eth0/accept_ra", "0\n");
+        conf(&tree, "eth1", "0", "0");
+        tree.file("sys/class/net/eth1/flags", "0x1003\n");
+        tree.file("proc/sys/net/ipv6/conf/eth1/accept_ra", "1\n");
+        let root = tree.root();
+
+        let reason = |iface: &str| token_rejected(&root, &name(iface)).map(|err| err.to_string());
+        assert!(reason("lo").unwrap().contains("loopback"));
+        assert!(reason("eth0").unwrap().contains("accept_ra"));
+        assert_eq!(reason("eth1"), None);
+    }
+
+    /// An interface with `flags` and the IPv6 settings SLAAC depends on.
+    fn slaac(tree: &FakeTree, name: &str, flags: &str, settings: &[(&str, &str)]) {
+        tree.file(&format!("sys/class/net/{}/flags", name), flags);
+        for (setting, value) in settings {
+            tree.file(
+                &format!("proc/sys/net/ipv6/conf/{}/{}", name, setting),
+                value,
+            );
+        }
+    }
+
+    #[test]
+    fn refused_tokens_name_the_setting_in_the_way() {
+        let tree = FakeTree::new();
+        let defaults = [
+            ("accept_ra", "1\n"),
+            ("forwarding", "0\n"),
+            ("router_solicitations", "-1\n"),
+        ];
+        slaac(&tree, "lo", "0x9\n", &defaults);
+        slaac(&tree, "eth0", "0x1003\n", &defaults);
+        slaac(&tree, "wg0", "0x10c1\n", &defaults);
+        slaac(&tree, "eth1", "0x1003\n", &[("accept_ra", "0\n")]);
+        slaac(
+            &tree,
+            "br0",
+            "0x1003\n",
+            &[("accept_ra", "1\n"), ("forwarding", "1\n")],
+        );
+        slaac(
+            &tree,
+            "eth2",
+            "0x1003\n",
+            &[("accept_ra", "1\n"), ("router_solicitations", "0\n")],
+        );
+        // Routers that accept advertisements anyway can have a token.
+        slaac(
+            &tree,
+            "eth3",
+            "0x1003\n",
+            &[("accept_ra", "2\n"), ("forwarding", "1\n")],
+        );
+        tree.file("sys/class/net/eth4/flags", "0x1003\n");
+        let root = tree.root();
+        let reason = |iface: &str| {
+            token_rejected(&root, &name(iface)).map(|err| {
+                assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+                err.to_string()
+            })
+        };
+        assert!(reason("lo").unwrap().contains("loopback"));
+        assert!(reason("wg0").unwrap().contains("NOARP"));
+        assert!(reason("eth1").unwrap().contains("accept_ra"));
+        assert!(reason("br0").unwrap().contains("accept_ra"));
+        assert!(reason("eth2").unwrap().contains("router_solicitations"));
+        // Nothing in the way, or nothing known: the kernel's error stands.
+        assert_eq!(reason("eth0"), None);
+        assert_eq!(reason("eth3"), None);
+        assert_eq!(reason("eth4"), None);
+        assert_eq!(reason("eth5"), None);
+    }
+
+    #[test]
+    fn tokens_are_checked_before_they_are_sent() {
+        let err = set_token(&name("nosuchif0"), "2001:db8::1".parse().unwrap()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = set_token(&name("nosuchif0"), "::1:2".parse().unwrap()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn loopback_has_no_token() {
+        assert_eq!(token(&name("lo")).unwrap(), None);
+        let err = token(&name("nosuchif0")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
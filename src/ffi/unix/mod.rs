@@ -1,12 +1,83 @@
+use std::collections::HashMap;
 use std::ffi::CStr;
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 use std::net::IpAddr;
+use std::sync::Arc;
 use std::{net, ptr};
 
 use libc::{sockaddr_in, sockaddr_in6};
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "openbsd",
+    target_os = "macos",
+    target_os = "freebsd"
+))]
+use log::debug;
 use nix::sys::socket::AddressFamily;
 
-use crate::{Interface, Kind, NextHop};
+use crate::{
+    AddressOrigin, HardwareKind, Interface, InterfaceFlags, InterfaceStats, Kind, MacAddr, NextHop,
+    OsSpecific, Warning, WarningAction,
+};
+
+// The correlation of handles with rules is tested on Linux.
+#[cfg(any(target_os = "android", all(target_os = "linux", test)))]
+pub mod android;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod conf;
+pub mod config;
+#[cfg(target_os = "macos")]
+pub mod darwin;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod ethtool;
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+))]
+mod if_data;
+mod ioctl;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod ipv6;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod netlink;
+#[cfg(all(target_os = "linux", feature = "networkmanager"))]
+pub mod networkmanager;
+#[cfg(all(
+    feature = "pktinfo",
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios"
+    )
+))]
+pub mod pktinfo;
+#[cfg(feature = "resolve")]
+pub mod resolve;
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+))]
+mod route_socket;
+mod socket;
+pub mod sys;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod sysfs;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod topology;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(all(any(target_os = "linux", target_os = "android"), feature = "wireguard"))]
+pub mod wireguard;
+
+pub use socket::{bind_socket_to_interface, interface_of_socket};
 
 // https://github.com/Exa-Networks/exaproxy/blob/master/lib/exaproxy/util/interfaces.py
 
@@ -25,7 +96,7 @@ pub const AF_LINK: AddressFamily = nix::sys::socket::AddressFamily::Link;
 #[cfg(any(target_os = "linux", target_os = "android"))]
 pub const AF_PACKET: AddressFamily = nix::sys::socket::AddressFamily::Packet;
 
-#[allow(dead_code, non_camel_case_types)]
+#[allow(dead_code, non_camel_case_types, clippy::upper_case_acronyms)]
 #[repr(C)]
 pub enum SIOCGIFFLAGS {
     IFF_UP = 0x1,           /* Interface is up.  */
@@ -51,20 +122,85 @@ pub enum SIOCGIFFLAGS {
     IFF_DYNAMIC = 0x8000,   /* Dialup device with changing addresses.  */
 }
 
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+))]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct sockaddr_dl {
+    pub sdl_len: u8,
+    pub sdl_family: u8,
+    pub sdl_index: u16,
+    pub sdl_type: u8,
+    pub sdl_nlen: u8,
+    pub sdl_alen: u8,
+    pub sdl_slen: u8,
+    pub sdl_data: [u8; 12],
+}
+
+/// The leading counters of the `struct rtnl_link_stats` that `ifa_data` of
+/// an `AF_PACKET` entry points to.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct rtnl_link_stats {
+    pub rx_packets: u32,
+    pub tx_packets: u32,
+    pub rx_bytes: u32,
+    pub tx_bytes: u32,
+    pub rx_errors: u32,
+    pub tx_errors: u32,
+    pub rx_dropped: u32,
+    pub tx_dropped: u32,
+    pub multicast: u32,
+    pub collisions: u32,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl From<rtnl_link_stats> for InterfaceStats {
+    fn from(stats: rtnl_link_stats) -> InterfaceStats {
+        InterfaceStats {
+            rx_bytes: stats.rx_bytes as u64,
+            tx_bytes: stats.tx_bytes as u64,
+            rx_packets: stats.rx_packets as u64,
+            tx_packets: stats.tx_packets as u64,
+            rx_errors: stats.rx_errors as u64,
+            tx_errors: stats.tx_errors as u64,
+            rx_dropped: stats.rx_dropped as u64,
+            tx_dropped: stats.tx_dropped as u64,
+            multicast: stats.multicast as u64,
+            collisions: stats.collisions as u64,
+            width: crate::CounterWidth::Bits32,
+        }
+    }
+}
+
+/// The `ifa_ifu` union of Linux, which holds the broadcast address on
+/// broadcast interfaces and the destination address on point-to-point
+/// ones.
+#[cfg(any(target_os = "linux", target_os = "android"))]
 #[allow(non_camel_case_types)]
 #[repr(C)]
 pub struct union_ifa_ifu {
     pub data: *mut std::os::raw::c_void,
 }
+#[cfg(any(target_os = "linux", target_os = "android"))]
 impl union_ifa_ifu {
-    pub fn ifu_broadaddr(&mut self) -> *mut nix::sys::socket::sockaddr {
+    pub fn ifu_broadaddr(&self) -> *mut nix::sys::socket::sockaddr {
         self.data as *mut nix::sys::socket::sockaddr
     }
-    pub fn ifu_dstaddr(&mut self) -> *mut nix::sys::socket::sockaddr {
+    pub fn ifu_dstaddr(&self) -> *mut nix::sys::socket::sockaddr {
         self.data as *mut nix::sys::socket::sockaddr
     }
 }
 
+/// `struct ifaddrs` as glibc, musl and bionic declare it.
+#[cfg(any(target_os = "linux", target_os = "android"))]
 #[allow(non_camel_case_types)]
 #[repr(C)]
 pub struct ifaddrs {
@@ -77,10 +213,88 @@ pub struct ifaddrs {
     pub ifa_data: *mut std::os::raw::c_void,
 }
 
+/// `struct ifaddrs` as the BSDs, macOS, illumos and the rest declare it:
+/// `ifa_dstaddr` in place of the union, 64 bits of flags on illumos and
+/// Solaris, and `ifa_addrflags` at the end on NetBSD.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct ifaddrs {
+    pub ifa_next: *mut ifaddrs,
+    pub ifa_name: *mut std::os::raw::c_char,
+    #[cfg(not(any(target_os = "illumos", target_os = "solaris")))]
+    pub ifa_flags: std::os::raw::c_uint,
+    #[cfg(any(target_os = "illumos", target_os = "solaris"))]
+    pub ifa_flags: u64,
+    pub ifa_addr: *mut nix::sys::socket::sockaddr,
+    pub ifa_netmask: *mut nix::sys::socket::sockaddr,
+    /// The broadcast address on broadcast interfaces, the destination
+    /// address on point-to-point ones.
+    pub ifa_dstaddr: *mut nix::sys::socket::sockaddr,
+    pub ifa_data: *mut std::os::raw::c_void,
+    #[cfg(target_os = "netbsd")]
+    pub ifa_addrflags: std::os::raw::c_uint,
+}
+
+impl ifaddrs {
+    /// The `IFF_*` flags in the width every platform has; the ones only
+    /// illumos and Solaris have above 32 bits are cut off.
+    pub fn flags(&self) -> std::os::raw::c_uint {
+        #[cfg(any(target_os = "illumos", target_os = "solaris"))]
+        return self.ifa_flags as std::os::raw::c_uint;
+        #[cfg(not(any(target_os = "illumos", target_os = "solaris")))]
+        self.ifa_flags
+    }
+
+    /// The broadcast address, meaningful with `IFF_BROADCAST`.
+    pub fn broadaddr(&self) -> *mut nix::sys::socket::sockaddr {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        return self.ifa_ifu.ifu_broadaddr();
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        self.ifa_dstaddr
+    }
+
+    /// The destination address, meaningful with `IFF_POINTOPOINT`.
+    pub fn dstaddr(&self) -> *mut nix::sys::socket::sockaddr {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        return self.ifa_ifu.ifu_dstaddr();
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        self.ifa_dstaddr
+    }
+}
+
+// The declarations above against libc's, so a layout that does not match
+// the C library fails the build instead of misreading entries.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "haiku"
+))]
+const _: () = {
+    use std::mem::{offset_of, size_of};
+
+    assert!(size_of::<ifaddrs>() == size_of::<libc::ifaddrs>());
+    assert!(offset_of!(ifaddrs, ifa_name) == offset_of!(libc::ifaddrs, ifa_name));
+    assert!(offset_of!(ifaddrs, ifa_flags) == offset_of!(libc::ifaddrs, ifa_flags));
+    assert!(offset_of!(ifaddrs, ifa_addr) == offset_of!(libc::ifaddrs, ifa_addr));
+    assert!(offset_of!(ifaddrs, ifa_netmask) == offset_of!(libc::ifaddrs, ifa_netmask));
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    assert!(offset_of!(ifaddrs, ifa_ifu) == offset_of!(libc::ifaddrs, ifa_ifu));
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    assert!(offset_of!(ifaddrs, ifa_dstaddr) == offset_of!(libc::ifaddrs, ifa_dstaddr));
+    assert!(offset_of!(ifaddrs, ifa_data) == offset_of!(libc::ifaddrs, ifa_data));
+};
+
 extern "C" {
     pub fn getifaddrs(ifap: *mut *mut ifaddrs) -> std::os::raw::c_int;
     pub fn freeifaddrs(ifa: *mut ifaddrs) -> std::os::raw::c_void;
-    #[allow(dead_code)]
     pub fn if_nametoindex(ifname: *const std::os::raw::c_char) -> std::os::raw::c_uint;
 }
 
@@ -89,122 +303,1461 @@ pub fn nix_socketaddr_to_sockaddr(sa: *mut nix::sys::socket::sockaddr) -> Option
         return None;
     }
 
+    let len = match unsafe { *sa }.sa_family as i32 {
+        libc::AF_INET => std::mem::size_of::<sockaddr_in>(),
+        libc::AF_INET6 => std::mem::size_of::<sockaddr_in6>(),
+        _ => return None,
+    };
+    sockaddr_from_bytes(unsafe { std::slice::from_raw_parts(sa as *const u8, len) })
+}
+
+/// The address in the `sockaddr` at the start of `bytes`, `None` if it is
+/// not IPv4 or IPv6 or `bytes` is too short for its family.
+pub fn sockaddr_from_bytes(bytes: &[u8]) -> Option<net::SocketAddr> {
+    let family: libc::sa_family_t =
+        unsafe { read_at(bytes, std::mem::offset_of!(libc::sockaddr, sa_family))? };
+
     #[allow(clippy::identity_op)]
-    let (addr, port) = match AddressFamily::from_i32(unsafe { *sa }.sa_family as i32)? {
+    match AddressFamily::from_i32(family as i32)? {
         AF_INET => {
-            let sa = sa as *const sockaddr_in;
-            let sa = &unsafe { *sa };
+            let sa: sockaddr_in = unsafe { read_at(bytes, 0)? };
 
             let (addr, port) = (sa.sin_addr.s_addr, sa.sin_port);
-            (
-                IpAddr::V4(net::Ipv4Addr::new(
-                    ((addr & 0x000000FF) >> 0) as u8,
-                    ((addr & 0x0000FF00) >> 8) as u8,
-                    ((addr & 0x00FF0000) >> 16) as u8,
-                    ((addr & 0xFF000000) >> 24) as u8,
-                )),
-                port,
-            )
+            let addr = IpAddr::V4(net::Ipv4Addr::new(
+                ((addr & 0x000000FF) >> 0) as u8,
+                ((addr & 0x0000FF00) >> 8) as u8,
+                ((addr & 0x00FF0000) >> 16) as u8,
+                ((addr & 0xFF000000) >> 24) as u8,
+            ));
+            Some(net::SocketAddr::new(addr, port))
         }
         AF_INET6 => {
-            let sa = sa as *const sockaddr_in6;
-            let sa = &unsafe { *sa };
-            let (addr, port) = (sa.sin6_addr.s6_addr, sa.sin6_port);
-            (
-                IpAddr::V6(net::Ipv6Addr::new(
-                    addr[0] as u16,
-                    addr[1] as u16,
-                    addr[2] as u16,
-                    addr[3] as u16,
-                    addr[4] as u16,
-                    addr[5] as u16,
-                    addr[6] as u16,
-                    addr[7] as u16,
-                )),
-                port,
-            )
+            let sa: sockaddr_in6 = unsafe { read_at(bytes, 0)? };
+            // The bytes are in network order, as `Ipv6Addr` takes them. The
+            // scope id is what makes link-local addresses usable.
+            Some(net::SocketAddr::V6(net::SocketAddrV6::new(
+                net::Ipv6Addr::from(sa.sin6_addr.s6_addr),
+                sa.sin6_port,
+                sa.sin6_flowinfo,
+                sa.sin6_scope_id,
+            )))
         }
-        _ => return None,
-    };
-    Some(net::SocketAddr::new(addr, port))
+        _ => None,
+    }
+}
+
+/// The `T` at `offset` in `bytes`, which need not be aligned.
+///
+/// # Safety
+///
+/// Every bit pattern has to be a valid `T`, as for the C structures here.
+unsafe fn read_at<T: Copy>(bytes: &[u8], offset: usize) -> Option<T> {
+    let bytes = bytes.get(offset..offset.checked_add(std::mem::size_of::<T>())?)?;
+    Some(ptr::read_unaligned(bytes.as_ptr() as *const T))
 }
 
 /// Query the local system for all interface addresses.
 pub fn ifaces() -> Result<Vec<Interface>, Error> {
-    let mut ifaddrs_ptr: *mut ifaddrs = ptr::null_mut();
-    match unsafe { getifaddrs(&mut ifaddrs_ptr as *mut _) } {
+    Ok(ifaces_with_warnings()?.0)
+}
+
+/// [`ifaces`], with a [`Warning`] for each entry that was skipped or had a
+/// field left empty.
+pub fn ifaces_with_warnings() -> Result<(Vec<Interface>, Vec<Warning>), Error> {
+    let list = sys::IfAddrs::new()?;
+    let mut warnings = Vec::new();
+    #[allow(unused_mut)]
+    let mut ret = unsafe { map_ifaddrs_with_warnings(list.as_ptr(), &mut warnings) };
+    drop(list);
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    enrich(&mut ret);
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    netlink_origins(&mut ret);
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    in6_origins(&mut ret);
+    #[cfg(target_os = "openbsd")]
+    rdomains(&mut ret);
+    Ok((ret, warnings))
+}
+
+/// The interfaces in routing domain `rdomain`.
+///
+/// [`ifaces`] lists the interfaces of every routing domain, and the same
+/// network can be reached through several of them.
+#[cfg(target_os = "openbsd")]
+pub fn ifaces_in_rdomain(rdomain: u32) -> Result<Vec<Interface>, Error> {
+    let mut ret = ifaces()?;
+    ret.retain(|iface| iface.os.rdomain == Some(rdomain));
+    Ok(ret)
+}
+
+/// Fill in the routing domain of every interface.
+#[cfg(target_os = "openbsd")]
+fn rdomains(ifaces: &mut [Interface]) {
+    let socket = match ioctl::socket() {
+        Ok(socket) => socket,
+        Err(err) => {
+            debug!("no socket to ask for routing domains: {}", err);
+            return;
+        }
+    };
+
+    let mut looked_up: HashMap<String, Arc<OsSpecific>> = HashMap::new();
+    for iface in ifaces.iter_mut() {
+        let os = looked_up.entry(iface.name.clone()).or_insert_with(|| {
+            let mut os = (*iface.os).clone();
+            let rdomain = crate::validate_name(&iface.name)
+                .map_err(Error::from)
+                .and_then(|name| {
+                    let mut req = ioctl::ifreq::new(&name);
+                    ioctl::ioctl(&socket, ioctl::SIOCGIFRDOMAIN, &mut req)?;
+                    Ok(unsafe { req.ifr_ifru.ifru_ivalue })
+                });
+            match rdomain {
+                Ok(rdomain) => os.rdomain = Some(rdomain as u32),
+                Err(err) => debug!("SIOCGIFRDOMAIN failed on {}: {}", iface.name, err),
+            }
+            Arc::new(os)
+        });
+        iface.os = os.clone();
+    }
+}
+
+/// Fill in what only netlink knows about.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn enrich(ifaces: &mut [Interface]) {
+    let links = match netlink::links() {
+        Ok(links) => links,
+        Err(err) => {
+            debug!("netlink link dump failed: {}", err);
+            return;
+        }
+    };
+
+    let mut enriched: HashMap<u32, Link> = HashMap::new();
+    for iface in ifaces.iter_mut() {
+        let index = match iface.index {
+            Some(index) => index,
+            None => continue,
+        };
+        let link = enriched.entry(index).or_insert_with(|| {
+            let mut os = (*iface.os).clone();
+            let mut link = Link {
+                index: iface.index,
+                mac: iface.mac,
+                stats: iface.stats,
+                mtu: iface.mtu,
+                os: iface.os.clone(),
+            };
+            if let Some(info) = links.iter().find(|link| link.index == index) {
+                os.link_kind = info.kind.clone();
+                os.tx_queue_len = info.tx_queue_len;
+                os.num_tx_queues = info.num_tx_queues;
+                os.num_rx_queues = info.num_rx_queues;
+                os.alt_names = info.alt_names.clone();
+                os.alias = info.alias.clone();
+                os.tunnel = info.tunnel;
+                os.master = info.master;
+                os.group = info.group;
+                os.promiscuity = info.promiscuity;
+                os.veth_peer = veth_peer(info);
+                // The 64-bit counters, where the kernel has them.
+                link.stats = info.stats.or(link.stats);
+                link.mtu = info.mtu.or(link.mtu);
+            }
+            if let Ok(name) = crate::validate_name(&iface.name) {
+                let root = sysfs::FsRoot::system();
+                os.sriov = sysfs::sriov(&root, &name);
+                os.carrier_changes = sysfs::carrier_changes(&root, &name);
+                os.devtype = sysfs::devtype(&root, &name);
+            }
+            link.os = Arc::new(os);
+            link
+        });
+        iface.os = link.os.clone();
+        iface.stats = link.stats;
+        iface.mtu = link.mtu;
+    }
+}
+
+/// The other end of `link`, if it is a `veth`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn veth_peer(link: &netlink::Link) -> Option<crate::VethPeer> {
+    match (link.kind.as_deref(), link.link) {
+        (Some("veth"), Some(peer_index)) => Some(crate::VethPeer {
+            peer_index,
+            netnsid: link.link_netnsid,
+        }),
+        _ => None,
+    }
+}
+
+/// Fill in [`AddressOrigin`] from the netlink flags of each address.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn netlink_origins(ifaces: &mut [Interface]) {
+    let addrs = match netlink::addrs(libc::AF_UNSPEC) {
+        Ok(addrs) => addrs,
+        Err(err) => {
+            debug!("netlink address dump failed: {}", err);
+            return;
+        }
+    };
+    for iface in ifaces.iter_mut() {
+        let (ip, index) = match (iface.addr, iface.index) {
+            (Some(addr), Some(index)) if iface.origin == AddressOrigin::Unknown => {
+                (addr.ip(), index)
+            }
+            _ => continue,
+        };
+        if let Some(entry) = addrs
+            .iter()
+            .find(|entry| entry.index == index && entry.address == Some(ip))
+        {
+            iface.origin = netlink_origin(ip.is_ipv4(), entry.flags, entry.proto);
+        }
+    }
+}
+
+/// The origin of an address with `IFA_F_*` `flags` and `IFA_PROTO`
+/// `proto`, see [`AddressOrigin`] for the rules.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn netlink_origin(v4: bool, flags: u32, proto: Option<u8>) -> AddressOrigin {
+    if flags & netlink::IFA_F_TEMPORARY != 0 {
+        AddressOrigin::SlaacTemporary
+    } else if proto == Some(netlink::IFAPROT_KERNEL_RA)
+        || flags & netlink::IFA_F_MANAGETEMPADDR != 0
+    {
+        AddressOrigin::Slaac
+    } else if flags & netlink::IFA_F_PERMANENT != 0 {
+        AddressOrigin::Static
+    } else if v4 {
+        AddressOrigin::Dhcp
+    } else {
+        AddressOrigin::Unknown
+    }
+}
+
+/// Fill in [`AddressOrigin`] of IPv6 addresses from their
+/// `SIOCGIFAFLAG_IN6` flags.
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+fn in6_origins(ifaces: &mut [Interface]) {
+    let socket = match ioctl::socket6() {
+        Ok(socket) => socket,
+        Err(err) => {
+            debug!("no IPv6 socket for address flags: {}", err);
+            return;
+        }
+    };
+    for iface in ifaces.iter_mut() {
+        let addr = match iface.addr {
+            Some(std::net::SocketAddr::V6(addr)) if iface.origin == AddressOrigin::Unknown => addr,
+            _ => continue,
+        };
+        let name = match crate::validate_name(&iface.name) {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        match ioctl::in6_flags(&socket, &name, &addr) {
+            Ok(flags) => iface.origin = in6_origin(flags),
+            Err(err) => debug!("SIOCGIFAFLAG_IN6 failed on {}: {}", iface.name, err),
+        }
+    }
+}
+
+/// The origin of an IPv6 address with `IN6_IFF_*` `flags`.
+#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+fn in6_origin(flags: libc::c_int) -> AddressOrigin {
+    #[cfg(target_os = "macos")]
+    if flags & ioctl::IN6_IFF_DYNAMIC != 0 {
+        return AddressOrigin::Dhcp;
+    }
+    if flags & ioctl::IN6_IFF_TEMPORARY != 0 {
+        AddressOrigin::SlaacTemporary
+    } else if flags & ioctl::IN6_IFF_AUTOCONF != 0 {
+        AddressOrigin::Slaac
+    } else {
+        AddressOrigin::Unknown
+    }
+}
+
+/// What the link-layer entry of an interface tells us.
+struct Link {
+    index: Option<u32>,
+    mac: Option<MacAddr>,
+    stats: Option<InterfaceStats>,
+    mtu: Option<u32>,
+    os: Arc<OsSpecific>,
+}
+
+unsafe fn link_of(item: *const ifaddrs) -> Option<Link> {
+    let sa = (*item).ifa_addr;
+    if sa.is_null() {
+        return None;
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        if (*sa).sa_family as i32 != libc::AF_PACKET {
+            return None;
+        }
+        let sll = &*(sa as *const libc::sockaddr_ll);
+        Some(Link {
+            index: match sll.sll_ifindex {
+                0 => None,
+                index => Some(index as u32),
+            },
+            mac: MacAddr::from_hardware_addr(
+                sll.sll_addr.get(..sll.sll_halen as usize).unwrap_or(&[]),
+            ),
+            stats: match (*item).ifa_data.is_null() {
+                true => None,
+                false => {
+                    Some(ptr::read_unaligned((*item).ifa_data as *const rtnl_link_stats).into())
+                }
+            },
+            // Not in getifaddrs, netlink fills it in.
+            mtu: None,
+            os: Arc::new(OsSpecific {
+                hardware_type: Some(sll.sll_hatype),
+                ..OsSpecific::default()
+            }),
+        })
+    }
+
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ))]
+    {
+        if (*sa).sa_family as i32 != libc::AF_LINK {
+            return None;
+        }
+        let sdl = &*(sa as *const sockaddr_dl);
+        // The address follows the name and may run past the end of
+        // `sdl_data`, the real length is in `sdl_len`.
+        let offset = std::mem::offset_of!(sockaddr_dl, sdl_data) + sdl.sdl_nlen as usize;
+        let mac = match offset + sdl.sdl_alen as usize <= sdl.sdl_len as usize {
+            true => MacAddr::from_hardware_addr(std::slice::from_raw_parts(
+                (sa as *const u8).add(offset),
+                sdl.sdl_alen as usize,
+            )),
+            false => None,
+        };
+        let data = match (*item).ifa_data.is_null() {
+            true => None,
+            false => Some(ptr::read_unaligned(
+                (*item).ifa_data as *const if_data::if_data,
+            )),
+        };
+        Some(Link {
+            index: match sdl.sdl_index {
+                0 => None,
+                index => Some(index as u32),
+            },
+            mac,
+            stats: data.map(|data| data.stats()),
+            mtu: data.map(|data| data.mtu()),
+            os: Arc::new(OsSpecific {
+                link_type: Some(sdl.sdl_type),
+                ..OsSpecific::default()
+            }),
+        })
+    }
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    )))]
+    {
+        None
+    }
+}
+
+fn flags_of(ifa_flags: std::os::raw::c_uint) -> InterfaceFlags {
+    let mut flags = InterfaceFlags::empty();
+    for (flag, iff) in [
+        (InterfaceFlags::UP, libc::IFF_UP),
+        (InterfaceFlags::BROADCAST, libc::IFF_BROADCAST),
+        (InterfaceFlags::LOOPBACK, libc::IFF_LOOPBACK),
+        (InterfaceFlags::POINT_TO_POINT, libc::IFF_POINTOPOINT),
+        (InterfaceFlags::RUNNING, libc::IFF_RUNNING),
+        (InterfaceFlags::MULTICAST, libc::IFF_MULTICAST),
+        (InterfaceFlags::PROMISC, libc::IFF_PROMISC),
+    ]
+    .iter()
+    {
+        if ifa_flags & *iff as std::os::raw::c_uint != 0 {
+            flags |= *flag;
+        }
+    }
+    flags
+}
+
+/// Tell the hardware kind from the link type.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn hardware_kind(os: &OsSpecific) -> HardwareKind {
+    // Wi-Fi and WWAN devices are Ethernet or raw IP devices, only the
+    // device type tells them apart.
+    match os.devtype.as_deref() {
+        Some("wlan") => return HardwareKind::Wireless,
+        Some("wwan") => return HardwareKind::Cellular,
+        // BNEP devices are Ethernet devices too.
+        Some("bluetooth") => return HardwareKind::Bluetooth,
+        _ => {}
+    }
+    match os.hardware_type {
+        Some(libc::ARPHRD_ETHER) => match os.link_kind {
+            // Physical devices have no link kind.
+            Some(_) => HardwareKind::Virtual,
+            None => HardwareKind::Ethernet,
+        },
+        Some(libc::ARPHRD_IEEE80211..=libc::ARPHRD_IEEE80211_RADIOTAP) => HardwareKind::Wireless,
+        Some(libc::ARPHRD_LOOPBACK) => HardwareKind::Loopback,
+        Some(libc::ARPHRD_PPP) => HardwareKind::Ppp,
+        Some(libc::ARPHRD_CAN) => HardwareKind::Can,
+        Some(
+            libc::ARPHRD_TUNNEL
+            | libc::ARPHRD_TUNNEL6
+            | libc::ARPHRD_SIT
+            | libc::ARPHRD_IPGRE
+            | libc::ARPHRD_NONE,
+        ) => HardwareKind::Tunnel,
+        _ => HardwareKind::Other,
+    }
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+))]
+pub(crate) fn hardware_kind(os: &OsSpecific) -> HardwareKind {
+    match os.link_type {
+        // IFT_CELLULAR, the pdp_ip interfaces of the baseband.
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        Some(0xff) => HardwareKind::Cellular,
+        Some(link_type) => HardwareKind::from_iana(link_type as u32),
+        None => HardwareKind::Other,
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+)))]
+pub(crate) fn hardware_kind(_os: &OsSpecific) -> HardwareKind {
+    HardwareKind::Other
+}
+
+/// The IPv6 addresses the kernel flags deprecated or temporary. Only Linux
+/// says, through netlink; elsewhere, and if netlink fails, this is empty.
+pub(crate) fn deprecated_or_temporary_v6() -> Vec<std::net::Ipv6Addr> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    match netlink::addrs(libc::AF_INET6) {
+        Ok(addrs) => {
+            return addrs
+                .iter()
+                .filter(|addr| {
+                    addr.flags & (netlink::IFA_F_TEMPORARY | netlink::IFA_F_DEPRECATED) != 0
+                })
+                .filter_map(|addr| match addr.address {
+                    Some(IpAddr::V6(v6)) => Some(v6),
+                    _ => None,
+                })
+                .collect()
+        }
+        Err(err) => debug!("netlink address dump failed: {}", err),
+    }
+    Vec::new()
+}
+
+/// Where duplicate address detection for `addr` on `name` stands, `None`
+/// if the interface does not have the address.
+pub(crate) fn dad_state(
+    name: &str,
+    addr: std::net::Ipv6Addr,
+) -> std::io::Result<Option<crate::dad::DadState>> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        let index = if_index(&crate::validate_name(name)?)?;
+        let found = netlink::addrs(libc::AF_INET6)?
+            .into_iter()
+            .find(|entry| entry.index == index && entry.address == Some(IpAddr::V6(addr)));
+        return Ok(found.map(|entry| dad_state_of(entry.flags)));
+    }
+    #[allow(unreachable_code)]
+    {
+        let _ = (name, addr);
+        Err(Error::new(
+            std::io::ErrorKind::Unsupported,
+            "address DAD state is not available on this platform",
+        ))
+    }
+}
+
+/// The DAD state an address with the netlink `IFA_F_*` `flags` is in.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn dad_state_of(flags: u32) -> crate::dad::DadState {
+    use crate::dad::DadState;
+
+    if flags & netlink::IFA_F_DADFAILED != 0 {
+        DadState::Duplicate
+    } else if flags & netlink::IFA_F_TENTATIVE == 0 || flags & netlink::IFA_F_OPTIMISTIC != 0 {
+        DadState::Usable
+    } else {
+        DadState::Tentative
+    }
+}
+
+/// Whether the interface is Wi-Fi, which most drivers report as Ethernet.
+///
+/// Linux drivers on cfg80211 or the old wireless extensions show it in
+/// sysfs. macOS and FreeBSD give it in the media word, where 802.11
+/// interfaces have their own media type.
+pub(crate) fn is_wireless(name: &str, os: &OsSpecific) -> bool {
+    if hardware_kind(os) == HardwareKind::Wireless {
+        return true;
+    }
+    let name = match crate::validate_name(name) {
+        Ok(name) => name,
+        Err(_) => return false,
+    };
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return sysfs::is_wireless(&sysfs::FsRoot::system(), &name);
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    return media_is_wireless(&name).unwrap_or(false);
+    #[allow(unreachable_code)]
+    {
+        let _ = name;
+        false
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+fn media_is_wireless(name: &crate::ValidName) -> std::io::Result<bool> {
+    let mut req: ioctl::ifmediareq = unsafe { std::mem::zeroed() };
+    req.ifm_name = ioctl::ifreq::new(name).ifr_name;
+    ioctl::ioctl(&ioctl::socket()?, ioctl::SIOCGIFMEDIA, &mut req)?;
+    Ok(is_80211_media(req.ifm_current))
+}
+
+/// Whether media word `word` has the 802.11 media type.
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+fn is_80211_media(word: std::os::raw::c_int) -> bool {
+    word & ioctl::IFM_NMASK == ioctl::IFM_IEEE80211
+}
+
+/// The index of the interface called `name`.
+pub(crate) fn if_index(name: &crate::ValidName) -> std::io::Result<u32> {
+    // Valid names have no NUL.
+    let name = std::ffi::CString::new(name.as_str())
+        .map_err(|err| Error::new(std::io::ErrorKind::InvalidInput, err))?;
+    match unsafe { if_nametoindex(name.as_ptr()) } {
         0 => {
-            let mut ret = Vec::new();
-            let mut item: *mut ifaddrs = ifaddrs_ptr;
-            loop {
-                if item.is_null() {
-                    break;
+            let err = Error::last_os_error();
+            match err.raw_os_error() {
+                Some(libc::ENODEV) | Some(libc::ENXIO) => {
+                    Err(Error::new(std::io::ErrorKind::NotFound, err))
                 }
-                let name = String::from_utf8(
-                    unsafe { CStr::from_ptr((*item).ifa_name) }
-                        .to_bytes()
-                        .to_vec(),
+                _ => Err(err),
+            }
+        }
+        index => Ok(index),
+    }
+}
+
+/// The name of the interface with index `index`, with `if_indextoname`.
+pub(crate) fn index_name(index: u32) -> Option<String> {
+    let mut buf = [0 as std::os::raw::c_char; libc::IF_NAMESIZE];
+    let name = unsafe { libc::if_indextoname(index, buf.as_mut_ptr()) };
+    if name.is_null() {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(name) };
+    name.to_str().ok().map(str::to_owned)
+}
+
+unsafe fn name_to_index(item: *const ifaddrs) -> Option<u32> {
+    match if_nametoindex((*item).ifa_name) {
+        0 => None,
+        index => Some(index),
+    }
+}
+
+/// Map a `getifaddrs` list to interfaces.
+///
+/// Besides the returned `Vec`, which is sized up front, this allocates one
+/// `String` per returned entry, and a name and `OsSpecific` per interface.
+/// Entries that cannot be mapped are skipped.
+///
+/// # Safety
+///
+/// `list` must be null or point to a well-formed, null-terminated list.
+#[doc(hidden)]
+pub unsafe fn map_ifaddrs(list: *const ifaddrs) -> Vec<Interface> {
+    map_ifaddrs_with_warnings(list, &mut Vec::new())
+}
+
+/// [`map_ifaddrs`], pushing a [`Warning`] for each entry that is skipped or
+/// has a field left empty.
+///
+/// # Safety
+///
+/// As for [`map_ifaddrs`].
+#[doc(hidden)]
+pub unsafe fn map_ifaddrs_with_warnings(
+    list: *const ifaddrs,
+    warnings: &mut Vec<Warning>,
+) -> Vec<Interface> {
+    let mut len = 0;
+    let mut links: HashMap<String, Link> = HashMap::new();
+    let mut item = list;
+    while !item.is_null() {
+        len += 1;
+        if let (Ok(name), Some(mut link)) =
+            (CStr::from_ptr((*item).ifa_name).to_str(), link_of(item))
+        {
+            if link.index.is_none() {
+                link.index = name_to_index(item);
+            }
+            links.insert(name.to_owned(), link);
+        }
+        item = (*item).ifa_next;
+    }
+
+    let mut ret = Vec::with_capacity(len);
+    let default_os = Arc::new(OsSpecific::default());
+    let mut item = list;
+    let mut entry = 0;
+    while !item.is_null() {
+        let this = item;
+        item = (*item).ifa_next;
+        entry += 1;
+        let raw_name = CStr::from_ptr((*this).ifa_name);
+        let mut warn = |field, action, reason: String| {
+            warnings.push(Warning::new(
+                raw_name.to_string_lossy(),
+                entry - 1,
+                field,
+                action,
+                reason,
+            ))
+        };
+
+        let name = match raw_name.to_str() {
+            Ok(name) => name,
+            Err(_) => {
+                warn("name", WarningAction::Skipped, "not UTF-8".to_owned());
+                continue;
+            }
+        };
+        if (*this).ifa_addr.is_null() {
+            warn("addr", WarningAction::Skipped, "no address".to_owned());
+            continue;
+        }
+        let family = (*(*this).ifa_addr).sa_family as i32;
+        let kind = match AddressFamily::from_i32(family) {
+            Some(AF_INET) => Kind::Ipv4,
+            Some(AF_INET6) => Kind::Ipv6,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Some(AF_PACKET) => Kind::Packet,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            // Which nix does not map from the number.
+            _ if family == libc::AF_CAN => Kind::Can,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            _ if family == libc::AF_BLUETOOTH => Kind::Bluetooth,
+            #[cfg(any(
+                target_os = "macos",
+                target_os = "ios",
+                target_os = "freebsd",
+                target_os = "openbsd",
+                target_os = "netbsd"
+            ))]
+            Some(AF_LINK) => Kind::Link,
+            _ => {
+                warn(
+                    "addr",
+                    WarningAction::Skipped,
+                    format!("unsupported address family {}", family),
                 );
-                unsafe {
-                    if name.is_err() || (*item).ifa_addr.is_null() {
-                        break;
-                    }
-                }
+                continue;
+            }
+        };
 
-                let kind = AddressFamily::from_i32(unsafe { (*(*item).ifa_addr).sa_family } as i32);
-                if kind.is_none() {
-                    break;
-                }
-                let kind = match kind.unwrap() {
-                    AF_INET => Some(Kind::Ipv4),
-                    AF_INET6 => Some(Kind::Ipv6),
-                    #[cfg(any(target_os = "linux", target_os = "android"))]
-                    AF_PACKET => Some(Kind::Packet),
-                    #[cfg(any(
-                        target_os = "macos",
-                        target_os = "ios",
-                        target_os = "freebsd",
-                        target_os = "openbsd",
-                        target_os = "netbsd"
-                    ))]
-                    AF_LINK => Some(Kind::Link),
-                    _ => Some(Kind::Unknown),
-                };
-                if kind.is_none() {
-                    break;
+        let addr = nix_socketaddr_to_sockaddr((*this).ifa_addr);
+        let mask = nix_socketaddr_to_sockaddr((*this).ifa_netmask);
+        if matches!(kind, Kind::Ipv4 | Kind::Ipv6)
+            && mask.is_none()
+            && !(*this).ifa_netmask.is_null()
+        {
+            warn("mask", WarningAction::Defaulted, "unreadable".to_owned());
+        }
+        let hop = if (*this).flags() & SIOCGIFFLAGS::IFF_BROADCAST as std::os::raw::c_uint
+            == SIOCGIFFLAGS::IFF_BROADCAST as std::os::raw::c_uint
+        {
+            nix_socketaddr_to_sockaddr((*this).broadaddr()).map(NextHop::Broadcast)
+        } else {
+            nix_socketaddr_to_sockaddr((*this).dstaddr()).map(NextHop::Destination)
+        };
+
+        let link = match links.get(name) {
+            Some(link) => link,
+            None => links.entry(name.to_owned()).or_insert(Link {
+                index: name_to_index(this),
+                mac: None,
+                stats: None,
+                mtu: None,
+                os: default_os.clone(),
+            }),
+        };
+        if link.index.is_none() {
+            warn(
+                "index",
+                WarningAction::Defaulted,
+                "if_nametoindex failed".to_owned(),
+            );
+        }
+        ret.push(Interface {
+            name: name.to_owned(),
+            index: link.index,
+            kind,
+            addr,
+            mask,
+            hop,
+            origin: addr
+                .and_then(|addr| AddressOrigin::of_address(&addr.ip()))
+                .unwrap_or_default(),
+            flags: flags_of((*this).flags()),
+            mac: link.mac,
+            stats: link.stats,
+            mtu: link.mtu,
+            os: link.os.clone(),
+        });
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+
+    use super::*;
+
+    fn bytes_of<T>(sa: &T) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(sa as *const T as *const u8, mem::size_of::<T>()) }
+    }
+
+    fn sockaddr_in6_of(addr: SocketAddrV6) -> sockaddr_in6 {
+        let mut sa: sockaddr_in6 = unsafe { mem::zeroed() };
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            sa.sin6_len = mem::size_of::<sockaddr_in6>() as u8;
+        }
+        sa.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+        sa.sin6_addr.s6_addr = addr.ip().octets();
+        sa.sin6_port = addr.port();
+        sa.sin6_flowinfo = addr.flowinfo();
+        sa.sin6_scope_id = addr.scope_id();
+        sa
+    }
+
+    #[test]
+    fn sockaddr_in6_round_trips() {
+        let addrs = [
+            // Link-local, scoped to an interface.
+            SocketAddrV6::new("fe80::1".parse().unwrap(), 0, 0, 4),
+            SocketAddrV6::new("fe80::fc:ff:fe00:1".parse().unwrap(), 0, 7, 2),
+            // Global and loopback, without a scope.
+            SocketAddrV6::new("2001:db8:85a3::8a2e:370:7334".parse().unwrap(), 0, 0, 0),
+            SocketAddrV6::new(Ipv6Addr::LOCALHOST, 0, 0, 0),
+            SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, 0, 0, 0),
+        ];
+        for addr in addrs {
+            let sa = sockaddr_in6_of(addr);
+            assert_eq!(
+                sockaddr_from_bytes(bytes_of(&sa)),
+                Some(SocketAddr::V6(addr)),
+                "{}",
+                addr
+            );
+        }
+    }
+
+    #[test]
+    fn sockaddr_in_is_read() {
+        let mut sa: sockaddr_in = unsafe { mem::zeroed() };
+        sa.sin_family = libc::AF_INET as libc::sa_family_t;
+        sa.sin_addr.s_addr = u32::from_ne_bytes([192, 0, 2, 1]);
+        assert_eq!(
+            sockaddr_from_bytes(bytes_of(&sa)),
+            Some(SocketAddr::new(Ipv4Addr::new(192, 0, 2, 1).into(), 0))
+        );
+    }
+
+    #[test]
+    fn other_families_and_short_buffers_are_none() {
+        let mut sa: sockaddr_in6 = unsafe { mem::zeroed() };
+        sa.sin6_family = libc::AF_UNIX as libc::sa_family_t;
+        assert_eq!(sockaddr_from_bytes(bytes_of(&sa)), None);
+
+        let sa = sockaddr_in6_of(SocketAddrV6::new(Ipv6Addr::LOCALHOST, 0, 0, 0));
+        let bytes = bytes_of(&sa);
+        assert_eq!(sockaddr_from_bytes(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(sockaddr_from_bytes(&[]), None);
+    }
+
+    /// An owned `getifaddrs`-shaped list, boxed so the pointers between
+    /// its parts stay put.
+    #[allow(clippy::vec_box)]
+    #[derive(Default)]
+    struct Fixture {
+        names: Vec<std::ffi::CString>,
+        addrs: Vec<Box<libc::sockaddr_storage>>,
+        nodes: Vec<Box<ifaddrs>>,
+    }
+
+    impl Fixture {
+        fn push(
+            &mut self,
+            name: &[u8],
+            addr: Option<libc::sockaddr_storage>,
+            mask: Option<libc::sockaddr_storage>,
+        ) -> &mut Fixture {
+            let name = std::ffi::CString::new(name).unwrap();
+            let mut node: Box<ifaddrs> = Box::new(unsafe { mem::zeroed() });
+            node.ifa_name = name.as_ptr() as *mut _;
+            node.ifa_flags = libc::IFF_UP as _;
+            node.ifa_addr = self.keep(addr);
+            node.ifa_netmask = self.keep(mask);
+            self.names.push(name);
+            if let Some(last) = self.nodes.last_mut() {
+                last.ifa_next = &mut *node;
+            }
+            self.nodes.push(node);
+            self
+        }
+
+        fn keep(
+            &mut self,
+            addr: Option<libc::sockaddr_storage>,
+        ) -> *mut nix::sys::socket::sockaddr {
+            match addr {
+                Some(addr) => {
+                    let mut addr = Box::new(addr);
+                    let ptr = &mut *addr as *mut _ as *mut _;
+                    self.addrs.push(addr);
+                    ptr
                 }
+                None => ptr::null_mut(),
+            }
+        }
 
-                let addr = nix_socketaddr_to_sockaddr(unsafe { (*item).ifa_addr });
-                let mask = nix_socketaddr_to_sockaddr(unsafe { (*item).ifa_netmask });
-                let hop = unsafe {
-                    if (*item).ifa_flags & SIOCGIFFLAGS::IFF_BROADCAST as std::os::raw::c_uint
-                        == SIOCGIFFLAGS::IFF_BROADCAST as std::os::raw::c_uint
-                    {
-                        nix_socketaddr_to_sockaddr((*item).ifa_ifu.ifu_broadaddr())
-                            .map(NextHop::Broadcast)
-                    } else {
-                        nix_socketaddr_to_sockaddr((*item).ifa_ifu.ifu_dstaddr())
-                            .map(NextHop::Destination)
-                    }
-                };
-
-                if let Some(kind) = kind {
-                    if kind != Kind::Unknown {
-                        ret.push(Interface {
-                            name: name.unwrap(),
-                            kind,
-                            addr,
-                            mask,
-                            hop,
-                        });
-                    }
-                };
-
-                item = unsafe { (*item).ifa_next };
+        /// Set `flags` on the last entry, and `hop` in the field that holds
+        /// the broadcast or destination address on this platform.
+        fn hop(&mut self, flags: libc::c_int, hop: Option<libc::sockaddr_storage>) -> &mut Fixture {
+            let hop = self.keep(hop);
+            let node = self.nodes.last_mut().unwrap();
+            node.ifa_flags = (libc::IFF_UP | flags) as _;
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            {
+                node.ifa_ifu.data = hop as *mut _;
+            }
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            {
+                node.ifa_dstaddr = hop;
             }
-            unsafe { freeifaddrs(ifaddrs_ptr) };
-            Ok(ret)
+            self
+        }
+
+        fn head(&self) -> *const ifaddrs {
+            &*self.nodes[0]
+        }
+    }
+
+    fn storage_of<T>(sa: T) -> libc::sockaddr_storage {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        unsafe { ptr::write(&mut storage as *mut _ as *mut T, sa) };
+        storage
+    }
+
+    fn v4(octets: [u8; 4]) -> Option<libc::sockaddr_storage> {
+        let mut sa: sockaddr_in = unsafe { mem::zeroed() };
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            sa.sin_len = mem::size_of::<sockaddr_in>() as u8;
+        }
+        sa.sin_family = libc::AF_INET as libc::sa_family_t;
+        sa.sin_addr.s_addr = u32::from_ne_bytes(octets);
+        Some(storage_of(sa))
+    }
+
+    fn v6(addr: Ipv6Addr) -> Option<libc::sockaddr_storage> {
+        Some(storage_of(sockaddr_in6_of(SocketAddrV6::new(
+            addr, 0, 0, 0,
+        ))))
+    }
+
+    /// An `AF_UNIX` address, which no interface has.
+    fn unix() -> Option<libc::sockaddr_storage> {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        storage.ss_family = libc::AF_UNIX as libc::sa_family_t;
+        Some(storage)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const LO: &str = "lo";
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    const LO: &str = "lo0";
+
+    #[test]
+    fn malformed_entries_are_warned_about() {
+        let mut fixture = Fixture::default();
+        fixture
+            .push(LO.as_bytes(), v4([127, 0, 0, 1]), v4([255, 0, 0, 0]))
+            .push(b"lo\xff", v4([127, 0, 0, 2]), v4([255, 0, 0, 0]))
+            .push(LO.as_bytes(), None, None)
+            .push(LO.as_bytes(), unix(), None)
+            .push(LO.as_bytes(), v6(Ipv6Addr::LOCALHOST), unix())
+            .push(b"nosuchif0", v4([192, 0, 2, 2]), v4([255, 255, 255, 0]));
+        let mut warnings = Vec::new();
+        let ifaces = unsafe { map_ifaddrs_with_warnings(fixture.head(), &mut warnings) };
+
+        // Only the entries that were skipped are missing.
+        let kept: Vec<(&str, Option<IpAddr>)> = ifaces
+            .iter()
+            .map(|iface| (iface.name.as_str(), iface.addr.map(|addr| addr.ip())))
+            .collect();
+        assert_eq!(
+            kept,
+            [
+                (LO, Some([127, 0, 0, 1].into())),
+                (LO, Some(Ipv6Addr::LOCALHOST.into())),
+                ("nosuchif0", Some([192, 0, 2, 2].into())),
+            ]
+        );
+        assert_eq!(ifaces[1].mask, None);
+        assert_eq!(ifaces[2].index, None);
+
+        let warned: Vec<(&str, usize, &str, WarningAction)> = warnings
+            .iter()
+            .map(|w| (w.interface.as_str(), w.entry, w.field, w.action))
+            .collect();
+        assert_eq!(
+            warned,
+            [
+                ("lo\u{fffd}", 1, "name", WarningAction::Skipped),
+                (LO, 2, "addr", WarningAction::Skipped),
+                (LO, 3, "addr", WarningAction::Skipped),
+                (LO, 4, "mask", WarningAction::Defaulted),
+                ("nosuchif0", 5, "index", WarningAction::Defaulted),
+            ]
+        );
+        assert_eq!(
+            warnings[2].reason,
+            format!("unsupported address family {}", libc::AF_UNIX)
+        );
+    }
+
+    #[test]
+    fn well_formed_lists_have_no_warnings() {
+        let mut fixture = Fixture::default();
+        fixture
+            .push(LO.as_bytes(), v4([127, 0, 0, 1]), v4([255, 0, 0, 0]))
+            .push(
+                LO.as_bytes(),
+                v6(Ipv6Addr::LOCALHOST),
+                v6(Ipv6Addr::from(u128::MAX)),
+            );
+        let mut warnings = Vec::new();
+        let ifaces = unsafe { map_ifaddrs_with_warnings(fixture.head(), &mut warnings) };
+        assert_eq!(ifaces.len(), 2);
+        assert_eq!(warnings, []);
+        assert_eq!(unsafe { map_ifaddrs(fixture.head()) }.len(), 2);
+    }
+
+    #[test]
+    fn broadcast_and_destination_addresses_are_read_by_flag() {
+        let mut fixture = Fixture::default();
+        fixture
+            .push(b"eth0", v4([192, 0, 2, 1]), v4([255, 255, 255, 0]))
+            .hop(libc::IFF_BROADCAST, v4([192, 0, 2, 255]))
+            .push(b"tun0", v4([10, 0, 0, 1]), v4([255, 255, 255, 255]))
+            .hop(libc::IFF_POINTOPOINT, v4([10, 0, 0, 2]))
+            .push(b"dummy0", v4([198, 51, 100, 1]), v4([255, 255, 255, 0]))
+            .hop(0, None);
+        let ifaces = unsafe { map_ifaddrs(fixture.head()) };
+        let hop = |octets: [u8; 4]| SocketAddr::new(Ipv4Addr::from(octets).into(), 0);
+        assert_eq!(
+            ifaces[0].hop,
+            Some(NextHop::Broadcast(hop([192, 0, 2, 255])))
+        );
+        assert!(ifaces[0].flags.contains(InterfaceFlags::BROADCAST));
+        assert_eq!(
+            ifaces[1].hop,
+            Some(NextHop::Destination(hop([10, 0, 0, 2])))
+        );
+        assert!(ifaces[1].flags.contains(InterfaceFlags::POINT_TO_POINT));
+        assert_eq!(ifaces[2].hop, None);
+    }
+
+    #[test]
+    fn ifaddrs_has_the_layout_of_the_c_headers() {
+        // On top of the checks against libc, the offsets the headers give.
+        // NetBSD's trailing `ifa_addrflags` is padded to a pointer, and
+        // the 64 bits of flags of illumos fit where the others have 32
+        // and padding.
+        let (size, dstaddr, data) = match (cfg!(target_os = "netbsd"), mem::size_of::<usize>()) {
+            (false, 8) => (56, 40, 48),
+            (true, 8) => (64, 40, 48),
+            (false, _) => (28, 20, 24),
+            (true, _) => (32, 20, 24),
+        };
+        assert_eq!(mem::size_of::<ifaddrs>(), size);
+        assert_eq!(
+            mem::offset_of!(ifaddrs, ifa_flags),
+            2 * mem::size_of::<usize>()
+        );
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(mem::offset_of!(ifaddrs, ifa_ifu), dstaddr);
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        assert_eq!(mem::offset_of!(ifaddrs, ifa_dstaddr), dstaddr);
+        assert_eq!(mem::offset_of!(ifaddrs, ifa_data), data);
+    }
+
+    #[test]
+    fn this_machine_warns_about_nothing_it_lists() {
+        let (_, warnings) = ifaces_with_warnings().unwrap();
+        // An index is looked up for every entry that is kept.
+        for warning in &warnings {
+            assert_ne!(warning.field, "index", "{}", warning);
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn ifa_data_counters_are_32_bit() {
+        let stats = InterfaceStats::from(rtnl_link_stats {
+            rx_packets: 1,
+            tx_packets: 2,
+            rx_bytes: 3,
+            tx_bytes: u32::MAX,
+            rx_errors: 5,
+            tx_errors: 6,
+            rx_dropped: 7,
+            tx_dropped: 8,
+            multicast: 9,
+            collisions: 10,
+        });
+        assert_eq!(
+            stats,
+            InterfaceStats {
+                rx_bytes: 3,
+                tx_bytes: u32::MAX.into(),
+                rx_packets: 1,
+                tx_packets: 2,
+                rx_errors: 5,
+                tx_errors: 6,
+                rx_dropped: 7,
+                tx_dropped: 8,
+                multicast: 9,
+                collisions: 10,
+                width: crate::CounterWidth::Bits32,
+            }
+        );
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn the_extras_of_loopback_are_filled_in() {
+        let ifaces = ifaces().unwrap();
+        let lo = ifaces
+            .iter()
+            .find(|iface| {
+                iface.kind == Kind::Packet && iface.flags.contains(InterfaceFlags::LOOPBACK)
+            })
+            .unwrap();
+        let os = lo.os_specific();
+        assert_eq!(os.hardware_type, Some(libc::ARPHRD_LOOPBACK));
+        assert_eq!(os.link_kind, None);
+        assert!(os.tx_queue_len.is_some());
+        assert!(os.promiscuity.is_some());
+        // Shared by every entry of the interface.
+        for iface in ifaces.iter().filter(|iface| iface.index == lo.index) {
+            assert!(Arc::ptr_eq(&iface.os, &lo.os));
+        }
+    }
+
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ))]
+    #[test]
+    fn the_extras_of_loopback_are_filled_in() {
+        let lo = ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| {
+                iface.kind == Kind::Link && iface.flags.contains(InterfaceFlags::LOOPBACK)
+            })
+            .unwrap();
+        // IFT_LOOP.
+        assert_eq!(lo.os_specific().link_type, Some(0x18));
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    #[test]
+    fn only_802_11_media_words_are_wireless() {
+        // IFM_IEEE80211 | IFM_AUTO, and the same with the IBSS option.
+        assert!(is_80211_media(0x80));
+        assert!(is_80211_media(0x80 | 0x0400));
+        // IFM_ETHER | IFM_1000_T, and the type bits of neither.
+        assert!(!is_80211_media(0x20 | 16));
+        assert!(!is_80211_media(0xa0));
+        assert!(!is_80211_media(0));
+    }
+
+    #[test]
+    fn loopback_is_not_wireless() {
+        let lo = ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(InterfaceFlags::LOOPBACK))
+            .unwrap();
+        assert!(!lo.is_wireless());
+        assert!(!is_wireless("nosuchif0", &OsSpecific::default()));
+        assert!(!is_wireless("", &OsSpecific::default()));
+    }
+
+    #[cfg(target_os = "openbsd")]
+    #[test]
+    fn siocgifrdomain_is_the_openbsd_request() {
+        // _IOWR('i', 160, struct ifreq), a 32-byte ifreq.
+        assert_eq!(ioctl::SIOCGIFRDOMAIN, 0xc020_69a0);
+    }
+
+    #[cfg(target_os = "openbsd")]
+    #[test]
+    fn every_entry_has_its_rdomain() {
+        let ifaces = ifaces().unwrap();
+        assert!(ifaces
+            .iter()
+            .all(|iface| iface.os_specific().rdomain.is_some()));
+        // lo0 is the loopback of the default routing domain.
+        let default = ifaces_in_rdomain(0).unwrap();
+        assert!(default.iter().any(|iface| iface.name == "lo0"));
+        assert!(default
+            .iter()
+            .all(|iface| iface.os_specific().rdomain == Some(0)));
+        assert!(ifaces_in_rdomain(u32::MAX).unwrap().is_empty());
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn device_types_refine_ethernet() {
+        let kind = |devtype: Option<&str>, link_kind: Option<&str>| {
+            hardware_kind(&OsSpecific {
+                hardware_type: Some(libc::ARPHRD_ETHER),
+                devtype: devtype.map(str::to_owned),
+                link_kind: link_kind.map(str::to_owned),
+                ..OsSpecific::default()
+            })
+        };
+        assert_eq!(kind(None, None), HardwareKind::Ethernet);
+        assert_eq!(kind(Some("wlan"), None), HardwareKind::Wireless);
+        assert_eq!(kind(Some("wwan"), None), HardwareKind::Cellular);
+        assert_eq!(kind(Some("bluetooth"), None), HardwareKind::Bluetooth);
+        assert_eq!(kind(Some("bridge"), Some("bridge")), HardwareKind::Virtual);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn raw_ip_modems_are_cellular() {
+        // qmi_wwan and cdc_mbim devices in raw-IP mode have no link-layer
+        // header, which would make them tunnels.
+        let kind = |devtype: Option<&str>| {
+            hardware_kind(&OsSpecific {
+                hardware_type: Some(libc::ARPHRD_NONE),
+                devtype: devtype.map(str::to_owned),
+                ..OsSpecific::default()
+            })
+        };
+        assert_eq!(kind(Some("wwan")), HardwareKind::Cellular);
+        assert_eq!(kind(None), HardwareKind::Tunnel);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn can_and_bluetooth_entries_are_kept() {
+        let family = |family: libc::c_int| {
+            let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+            storage.ss_family = family as libc::sa_family_t;
+            Some(storage)
+        };
+        let mut fixture = Fixture::default();
+        fixture
+            .push(b"can0", family(libc::AF_CAN), None)
+            .hop(libc::IFF_RUNNING, None)
+            .push(b"hci0", family(libc::AF_BLUETOOTH), None)
+            .push(LO.as_bytes(), v4([127, 0, 0, 1]), v4([255, 0, 0, 0]));
+        let mut warnings = Vec::new();
+        let ifaces = unsafe { map_ifaddrs_with_warnings(fixture.head(), &mut warnings) };
+
+        // Named and flagged, without an address, and the walk goes on.
+        let kept: Vec<(&str, Kind, Option<SocketAddr>)> = ifaces
+            .iter()
+            .map(|iface| (iface.name.as_str(), iface.kind.clone(), iface.addr))
+            .collect();
+        assert_eq!(
+            kept,
+            [
+                ("can0", Kind::Can, None),
+                ("hci0", Kind::Bluetooth, None),
+                (LO, Kind::Ipv4, Some(SocketAddr::from(([127, 0, 0, 1], 0)))),
+            ]
+        );
+        assert_eq!(
+            ifaces[0].flags,
+            InterfaceFlags::UP | InterfaceFlags::RUNNING
+        );
+        assert_eq!(ifaces[1].flags, InterfaceFlags::UP);
+        assert!(ifaces[2].index.is_some());
+        // No such interfaces here, so only their indices are missing.
+        assert!(warnings.iter().all(|w| w.field == "index"));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn can_and_bluetooth_links_have_their_hardware_kind() {
+        let can = OsSpecific {
+            hardware_type: Some(libc::ARPHRD_CAN),
+            ..OsSpecific::default()
+        };
+        assert_eq!(hardware_kind(&can), HardwareKind::Can);
+        // bnep0 is an Ethernet device.
+        let bnep = OsSpecific {
+            hardware_type: Some(libc::ARPHRD_ETHER),
+            devtype: Some("bluetooth".to_owned()),
+            ..OsSpecific::default()
+        };
+        assert_eq!(hardware_kind(&bnep), HardwareKind::Bluetooth);
+        let eth = OsSpecific {
+            devtype: None,
+            ..bnep
+        };
+        assert_eq!(hardware_kind(&eth), HardwareKind::Ethernet);
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    #[test]
+    fn baseband_interfaces_are_cellular() {
+        let kind = |link_type: Option<u8>| {
+            hardware_kind(&OsSpecific {
+                link_type,
+                ..OsSpecific::default()
+            })
+        };
+        // pdp_ip0 and en0.
+        assert_eq!(kind(Some(0xff)), HardwareKind::Cellular);
+        assert_eq!(kind(Some(6)), HardwareKind::Ethernet);
+        assert_eq!(kind(None), HardwareKind::Other);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn dad_states_follow_the_address_flags() {
+        use crate::dad::DadState;
+        use netlink::{IFA_F_DADFAILED, IFA_F_OPTIMISTIC, IFA_F_TEMPORARY, IFA_F_TENTATIVE};
+
+        assert_eq!(dad_state_of(0), DadState::Usable);
+        assert_eq!(dad_state_of(IFA_F_TEMPORARY), DadState::Usable);
+        assert_eq!(dad_state_of(IFA_F_TENTATIVE), DadState::Tentative);
+        // Optimistic DAD lets the address be used while it runs.
+        assert_eq!(
+            dad_state_of(IFA_F_TENTATIVE | IFA_F_OPTIMISTIC),
+            DadState::Usable
+        );
+        // A failed address stays tentative.
+        assert_eq!(
+            dad_state_of(IFA_F_TENTATIVE | IFA_F_DADFAILED),
+            DadState::Duplicate
+        );
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn netlink_origins_follow_the_flags_and_protocol() {
+        use netlink::{
+            IFAPROT_KERNEL_RA, IFA_F_MANAGETEMPADDR, IFA_F_PERMANENT, IFA_F_STABLE_PRIVACY,
+            IFA_F_TEMPORARY,
+        };
+
+        let table = [
+            (false, IFA_F_TEMPORARY, None, AddressOrigin::SlaacTemporary),
+            (
+                false,
+                IFA_F_TEMPORARY,
+                Some(IFAPROT_KERNEL_RA),
+                AddressOrigin::SlaacTemporary,
+            ),
+            (false, 0, Some(IFAPROT_KERNEL_RA), AddressOrigin::Slaac),
+            (
+                false,
+                IFA_F_STABLE_PRIVACY,
+                Some(IFAPROT_KERNEL_RA),
+                AddressOrigin::Slaac,
+            ),
+            (false, IFA_F_MANAGETEMPADDR, None, AddressOrigin::Slaac),
+            (false, IFA_F_PERMANENT, None, AddressOrigin::Static),
+            // IFAPROT_UNSPEC, as `ip addr add` sets it.
+            (false, IFA_F_PERMANENT, Some(0), AddressOrigin::Static),
+            (true, IFA_F_PERMANENT, None, AddressOrigin::Static),
+            (true, 0, None, AddressOrigin::Dhcp),
+            // SLAAC on a kernel before 5.18, or DHCPv6: no telling.
+            (false, 0, None, AddressOrigin::Unknown),
+            (false, IFA_F_STABLE_PRIVACY, None, AddressOrigin::Unknown),
+        ];
+        for (v4, flags, proto, origin) in table {
+            assert_eq!(
+                netlink_origin(v4, flags, proto),
+                origin,
+                "{} {:#x} {:?}",
+                v4,
+                flags,
+                proto
+            );
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    #[test]
+    fn in6_origins_follow_the_flags() {
+        use ioctl::{IN6_IFF_AUTOCONF, IN6_IFF_TEMPORARY};
+
+        let table = [
+            (
+                IN6_IFF_AUTOCONF | IN6_IFF_TEMPORARY,
+                AddressOrigin::SlaacTemporary,
+            ),
+            (IN6_IFF_TEMPORARY, AddressOrigin::SlaacTemporary),
+            (IN6_IFF_AUTOCONF, AddressOrigin::Slaac),
+            (0, AddressOrigin::Unknown),
+            // IN6_IFF_TENTATIVE and IN6_IFF_DEPRECATED say nothing of it.
+            (0x02 | 0x10, AddressOrigin::Unknown),
+        ];
+        for (flags, origin) in table {
+            assert_eq!(in6_origin(flags), origin, "{:#x}", flags);
         }
-        _ => Err(Error::new(ErrorKind::Other, "Oh, no ...")), // Err(nix::errno::Errno::last());
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn dynamic_addresses_are_dhcp_on_macos() {
+        use ioctl::{IN6_IFF_AUTOCONF, IN6_IFF_DYNAMIC};
+
+        assert_eq!(in6_origin(IN6_IFF_DYNAMIC), AddressOrigin::Dhcp);
+        assert_eq!(
+            in6_origin(IN6_IFF_DYNAMIC | IN6_IFF_AUTOCONF),
+            AddressOrigin::Dhcp
+        );
+    }
+
+    #[test]
+    fn loopback_entries_are_well_known() {
+        let ifaces = ifaces().unwrap();
+        let lo: Vec<_> = ifaces
+            .iter()
+            .filter(|iface| iface.addr.is_some_and(|addr| addr.ip().is_loopback()))
+            .collect();
+        assert!(!lo.is_empty());
+        assert!(lo
+            .iter()
+            .all(|iface| iface.origin == AddressOrigin::WellKnown));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn veth_peers_are_read_from_captured_links() {
+        let peer = |payload: &[u8]| veth_peer(&netlink::parse_link(payload).unwrap());
+        // ifveth0, with its peer ifveth1 in this namespace.
+        assert_eq!(
+            peer(include_bytes!(
+                "../../../tests/fixtures/netlink/rtm-newlink-veth.bin"
+            )),
+            Some(crate::VethPeer {
+                peer_index: 177,
+                netnsid: None,
+            })
+        );
+        // ifveth2, with its peer in the namespace of nsid 0.
+        assert_eq!(
+            peer(include_bytes!(
+                "../../../tests/fixtures/netlink/rtm-newlink-veth-netns.bin"
+            )),
+            Some(crate::VethPeer {
+                peer_index: 2,
+                netnsid: Some(0),
+            })
+        );
+        // A macvlan has a lower link, not a peer.
+        assert_eq!(
+            peer(include_bytes!(
+                "../../../tests/fixtures/netlink/rtm-newlink-macvlan.bin"
+            )),
+            None
+        );
+        assert_eq!(
+            peer(include_bytes!(
+                "../../../tests/fixtures/netlink/rtm-newlink-eth0.bin"
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn metering_is_not_guessed() {
+        let lo = ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(InterfaceFlags::LOOPBACK))
+            .unwrap();
+        // NetworkManager leaves loopback alone, if it runs at all.
+        assert_eq!(lo.is_metered(), None);
+        assert_eq!(lo.is_metered_or_cellular(), None);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn cellular_links_count_as_metered_when_nothing_says() {
+        let modem = Interface::new("ifaces-wwan0", Kind::Ipv4).with_os_specific(OsSpecific {
+            hardware_type: Some(libc::ARPHRD_ETHER),
+            devtype: Some("wwan".to_owned()),
+            ..OsSpecific::default()
+        });
+        assert_eq!(modem.is_metered(), None);
+        assert_eq!(modem.is_metered_or_cellular(), Some(true));
+        let wired = Interface::new("ifaces-eth0", Kind::Ipv4).with_os_specific(OsSpecific {
+            hardware_type: Some(libc::ARPHRD_ETHER),
+            ..OsSpecific::default()
+        });
+        assert_eq!(wired.is_metered_or_cellular(), None);
     }
 }
@@ -0,0 +1,2140 @@
+//! A minimal rtnetlink client: enough to dump and change links, addresses
+//! and routes without pulling in a netlink crate, and to talk to the
+//! generic netlink families built on it.
+
+use std::io;
+use std::mem;
+use std::net::{IpAddr, Ipv6Addr};
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+use std::ptr;
+
+use crate::{CounterWidth, InterfaceStats, MacAddr, Route, TunnelEndpoints, ValidName};
+
+pub const NLMSG_ERROR: u16 = 2;
+pub const NLMSG_DONE: u16 = 3;
+
+pub const NLM_F_REQUEST: u16 = 0x1;
+pub const NLM_F_MULTI: u16 = 0x2;
+pub const NLM_F_ACK: u16 = 0x4;
+pub const NLM_F_DUMP: u16 = 0x300;
+pub const NLM_F_EXCL: u16 = 0x200;
+pub const NLM_F_CREATE: u16 = 0x400;
+
+pub const RTM_NEWLINK: u16 = 16;
+pub const RTM_DELLINK: u16 = 17;
+pub const RTM_GETLINK: u16 = 18;
+pub const RTM_SETLINK: u16 = 19;
+pub const RTM_DELADDR: u16 = 21;
+pub const RTM_GETADDR: u16 = 22;
+pub const RTM_NEWROUTE: u16 = 24;
+pub const RTM_DELROUTE: u16 = 25;
+pub const RTM_GETROUTE: u16 = 26;
+pub const RTM_NEWNEIGH: u16 = 28;
+pub const RTM_DELNEIGH: u16 = 29;
+pub const RTM_GETNEIGH: u16 = 30;
+#[cfg(any(target_os = "android", test))]
+pub const RTM_GETRULE: u16 = 34;
+
+// The multicast groups of the notifications, which libc lacks on Android.
+#[cfg(feature = "watch")]
+pub const RTMGRP_LINK: u32 = 0x1;
+#[cfg(feature = "watch")]
+pub const RTMGRP_IPV4_IFADDR: u32 = 0x10;
+#[cfg(feature = "watch")]
+pub const RTMGRP_IPV6_IFADDR: u32 = 0x100;
+
+pub const IFLA_IFNAME: u16 = 3;
+pub const IFLA_MTU: u16 = 4;
+pub const IFLA_LINK: u16 = 5;
+pub const IFLA_MASTER: u16 = 10;
+pub const IFLA_TXQLEN: u16 = 13;
+pub const IFLA_LINKINFO: u16 = 18;
+pub const IFLA_IFALIAS: u16 = 20;
+pub const IFLA_STATS64: u16 = 23;
+pub const IFLA_AF_SPEC: u16 = 26;
+pub const IFLA_GROUP: u16 = 27;
+pub const IFLA_PROMISCUITY: u16 = 30;
+pub const IFLA_NUM_TX_QUEUES: u16 = 31;
+pub const IFLA_LINK_NETNSID: u16 = 37;
+pub const IFLA_NUM_RX_QUEUES: u16 = 32;
+pub const IFLA_PROP_LIST: u16 = 52;
+pub const IFLA_ALT_IFNAME: u16 = 53;
+pub const IFLA_INET6_TOKEN: u16 = 7;
+pub const IFLA_INFO_KIND: u16 = 1;
+pub const IFLA_INFO_DATA: u16 = 2;
+
+// The IFLA_INFO_DATA attributes of tunnel links.
+const IFLA_GRE_LOCAL: u16 = 6;
+const IFLA_GRE_REMOTE: u16 = 7;
+const IFLA_IPTUN_LOCAL: u16 = 2;
+const IFLA_IPTUN_REMOTE: u16 = 3;
+const IFLA_VLAN_ID: u16 = 1;
+const IFLA_VXLAN_ID: u16 = 1;
+const IFLA_VXLAN_GROUP: u16 = 2;
+const IFLA_VXLAN_LOCAL: u16 = 4;
+const IFLA_VXLAN_GROUP6: u16 = 16;
+const IFLA_VXLAN_LOCAL6: u16 = 17;
+
+pub const IFA_ADDRESS: u16 = 1;
+pub const IFA_LOCAL: u16 = 2;
+pub const IFA_FLAGS: u16 = 8;
+pub const IFA_PROTO: u16 = 11;
+
+pub const IFA_F_TEMPORARY: u32 = 0x1;
+pub const IFA_F_OPTIMISTIC: u32 = 0x4;
+pub const IFA_F_DADFAILED: u32 = 0x8;
+pub const IFA_F_TENTATIVE: u32 = 0x40;
+pub const IFA_F_PERMANENT: u32 = 0x80;
+pub const IFA_F_MANAGETEMPADDR: u32 = 0x100;
+pub const IFA_F_DEPRECATED: u32 = 0x20;
+pub const IFA_F_STABLE_PRIVACY: u32 = 0x800;
+
+/// The `IFA_PROTO` of addresses the kernel made from a router
+/// advertisement.
+pub const IFAPROT_KERNEL_RA: u8 = 2;
+
+pub const NDA_DST: u16 = 1;
+pub const NDA_LLADDR: u16 = 2;
+
+pub const NUD_INCOMPLETE: u16 = 0x01;
+pub const NUD_FAILED: u16 = 0x20;
+pub const NUD_PERMANENT: u16 = 0x80;
+
+pub const RTA_DST: u16 = 1;
+pub const RTA_SRC: u16 = 2;
+pub const RTA_OIF: u16 = 4;
+pub const RTA_GATEWAY: u16 = 5;
+pub const RTA_PRIORITY: u16 = 6;
+pub const RTA_PREFSRC: u16 = 7;
+pub const RTA_METRICS: u16 = 8;
+pub const RTA_MULTIPATH: u16 = 9;
+pub const RTA_TABLE: u16 = 15;
+pub const RTA_PREF: u16 = 20;
+
+pub const RT_TABLE_MAIN: u8 = 254;
+pub const RTPROT_STATIC: u8 = 4;
+pub const RT_SCOPE_UNIVERSE: u8 = 0;
+pub const RT_SCOPE_LINK: u8 = 253;
+pub const RT_SCOPE_NOWHERE: u8 = 255;
+pub const RTN_UNICAST: u8 = 1;
+
+const NLA_F_NESTED: u16 = 0x8000;
+const NLA_TYPE_MASK: u16 = 0x3fff;
+const RECV_BUFFER_LEN: usize = 64 * 1024;
+
+pub const fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NlMsgHdr {
+    pub len: u32,
+    pub ty: u16,
+    pub flags: u16,
+    pub seq: u32,
+    pub pid: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IfInfoMsg {
+    pub family: u8,
+    pub pad: u8,
+    pub ty: u16,
+    pub index: i32,
+    pub flags: u32,
+    pub change: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RtMsg {
+    pub family: u8,
+    pub dst_len: u8,
+    pub src_len: u8,
+    pub tos: u8,
+    pub table: u8,
+    pub protocol: u8,
+    pub scope: u8,
+    pub ty: u8,
+    pub flags: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IfAddrMsg {
+    pub family: u8,
+    pub prefix_len: u8,
+    pub flags: u8,
+    pub scope: u8,
+    pub index: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NdMsg {
+    pub family: u8,
+    pub pad1: u8,
+    pub pad2: u16,
+    pub index: i32,
+    pub state: u16,
+    pub flags: u8,
+    pub ty: u8,
+}
+
+/// The header after `NlMsgHdr` in generic netlink messages.
+#[cfg(feature = "wireguard")]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GenlMsgHdr {
+    pub cmd: u8,
+    pub version: u8,
+    pub reserved: u16,
+}
+
+/// The message type of the generic netlink family `name`, looked up with
+/// the controller. A family that is not registered, because its module is
+/// not loaded, is `ErrorKind::NotFound`.
+#[cfg(feature = "wireguard")]
+pub fn family_id(socket: &mut Socket, name: &str) -> io::Result<u16> {
+    let replies = match socket.request(&mut family_message(name)) {
+        Err(err) if err.raw_os_error() == Some(libc::ENOENT) => {
+            let msg = format!("no generic netlink family {:?}", name);
+            return Err(io::Error::new(io::ErrorKind::NotFound, msg));
+        }
+        replies => replies?,
+    };
+    family_id_in(&replies)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no family id in reply"))
+}
+
+/// The `CTRL_CMD_GETFAMILY` request for `name`.
+#[cfg(feature = "wireguard")]
+fn family_message(name: &str) -> Message {
+    const GENL_ID_CTRL: u16 = 0x10;
+    const CTRL_CMD_GETFAMILY: u8 = 3;
+    const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+    let mut message = Message::new(GENL_ID_CTRL, 0);
+    message.push(&GenlMsgHdr {
+        cmd: CTRL_CMD_GETFAMILY,
+        version: 1,
+        ..GenlMsgHdr::default()
+    });
+    let mut family = name.as_bytes().to_vec();
+    family.push(0);
+    message.attr(CTRL_ATTR_FAMILY_NAME, &family);
+    message
+}
+
+/// The family id in the controller's replies to `family_message`.
+#[cfg(feature = "wireguard")]
+fn family_id_in(replies: &[Vec<u8>]) -> Option<u16> {
+    const CTRL_ATTR_FAMILY_ID: u16 = 1;
+
+    replies
+        .iter()
+        .filter_map(|payload| payload.get(align(mem::size_of::<GenlMsgHdr>())..))
+        .flat_map(Attrs::new)
+        .find(|&(ty, _)| ty == CTRL_ATTR_FAMILY_ID)
+        .and_then(|(_, value)| read::<u16>(value))
+}
+
+/// Read a `T` from the front of `bytes`, if it is long enough.
+pub fn read<T: Copy>(bytes: &[u8]) -> Option<T> {
+    if bytes.len() < mem::size_of::<T>() {
+        return None;
+    }
+    Some(unsafe { ptr::read_unaligned(bytes.as_ptr() as *const T) })
+}
+
+fn bytes_of<T: Copy>(value: &T) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) }
+}
+
+/// Iterator over the attributes packed in a message body.
+#[derive(Clone)]
+pub struct Attrs<'a>(&'a [u8]);
+
+impl<'a> Attrs<'a> {
+    pub fn new(bytes: &'a [u8]) -> Attrs<'a> {
+        Attrs(bytes)
+    }
+}
+
+impl<'a> Iterator for Attrs<'a> {
+    type Item = (u16, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = read::<u16>(self.0)? as usize;
+        let ty = read::<u16>(self.0.get(2..)?)?;
+        if len < 4 || len > self.0.len() {
+            self.0 = &[];
+            return None;
+        }
+        let value = &self.0[4..len];
+        self.0 = self.0.get(align(len)..).unwrap_or(&[]);
+        Some((ty & NLA_TYPE_MASK, value))
+    }
+}
+
+/// The value of a string attribute, without the terminating NUL.
+pub fn attr_str(value: &[u8]) -> Option<&str> {
+    let end = value.iter().position(|&b| b == 0).unwrap_or(value.len());
+    std::str::from_utf8(&value[..end]).ok()
+}
+
+/// A request under construction.
+pub struct Message {
+    buf: Vec<u8>,
+}
+
+impl Message {
+    pub fn new(ty: u16, flags: u16) -> Message {
+        let header = NlMsgHdr {
+            ty,
+            flags: flags | NLM_F_REQUEST,
+            ..NlMsgHdr::default()
+        };
+        let mut message = Message { buf: Vec::new() };
+        message.push(&header);
+        message
+    }
+
+    /// Append a fixed header such as `IfInfoMsg`.
+    pub fn push<T: Copy>(&mut self, value: &T) -> &mut Message {
+        self.buf.extend_from_slice(bytes_of(value));
+        self.buf.resize(align(self.buf.len()), 0);
+        self
+    }
+
+    /// Append an attribute.
+    pub fn attr(&mut self, ty: u16, value: &[u8]) -> &mut Message {
+        let len = (4 + value.len()) as u16;
+        self.buf.extend_from_slice(&len.to_ne_bytes());
+        self.buf.extend_from_slice(&ty.to_ne_bytes());
+        self.buf.extend_from_slice(value);
+        self.buf.resize(align(self.buf.len()), 0);
+        self
+    }
+
+    /// Append an attribute holding the attributes `fill` appends.
+    pub fn nested(&mut self, ty: u16, fill: impl FnOnce(&mut Message)) -> &mut Message {
+        let start = self.buf.len();
+        self.attr(ty | NLA_F_NESTED, &[]);
+        fill(self);
+        let len = (self.buf.len() - start) as u16;
+        self.buf[start..start + 2].copy_from_slice(&len.to_ne_bytes());
+        self
+    }
+
+    /// The request so far, before `finish` fills in its length.
+    #[cfg(all(test, feature = "wireguard"))]
+    pub fn bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    fn finish(&mut self, seq: u32) -> &[u8] {
+        let len = self.buf.len() as u32;
+        self.buf[0..4].copy_from_slice(&len.to_ne_bytes());
+        self.buf[8..12].copy_from_slice(&seq.to_ne_bytes());
+        &self.buf
+    }
+}
+
+pub struct Socket {
+    fd: OwnedFd,
+    seq: u32,
+}
+
+impl Socket {
+    pub fn route() -> io::Result<Socket> {
+        Socket::bind(libc::NETLINK_ROUTE, 0)
+    }
+
+    #[cfg(feature = "wireguard")]
+    pub fn generic() -> io::Result<Socket> {
+        Socket::bind(libc::NETLINK_GENERIC, 0)
+    }
+
+    /// A socket that receives the notifications of the `RTMGRP_*` `groups`.
+    #[cfg(feature = "watch")]
+    pub fn subscribe(groups: u32) -> io::Result<Socket> {
+        Socket::bind(libc::NETLINK_ROUTE, groups)
+    }
+
+    fn bind(protocol: i32, groups: u32) -> io::Result<Socket> {
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                protocol,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = groups;
+        let ret = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &addr as *const _ as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Socket { fd, seq: 0 })
+    }
+
+    #[cfg(feature = "watch")]
+    pub fn into_fd(self) -> OwnedFd {
+        self.fd
+    }
+
+    /// Send `message` and collect the payloads of the replies.
+    ///
+    /// Dumps are read until `NLMSG_DONE`, other requests until the first
+    /// reply or acknowledgement. Errors the kernel reports come back as the
+    /// matching `io::Error`.
+    pub fn request(&mut self, message: &mut Message) -> io::Result<Vec<Vec<u8>>> {
+        self.seq = self.seq.wrapping_add(1);
+        let seq = self.seq;
+        let bytes = message.finish(seq);
+        // NLM_F_DUMP is two bits, one of which doubles as NLM_F_EXCL.
+        let dump = read::<NlMsgHdr>(bytes).is_some_and(|h| h.flags & NLM_F_DUMP == NLM_F_DUMP);
+
+        let ret = unsafe {
+            libc::send(
+                self.fd.as_raw_fd(),
+                bytes.as_ptr() as *const libc::c_void,
+                bytes.len(),
+                0,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut responses = Vec::new();
+        let mut buf = vec![0u8; RECV_BUFFER_LEN];
+        loop {
+            let len = unsafe {
+                libc::recv(
+                    self.fd.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    0,
+                )
+            };
+            if len < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(err);
+            }
+
+            let mut rest = &buf[..len as usize];
+            while let Some(header) = read::<NlMsgHdr>(rest) {
+                let msg_len = header.len as usize;
+                if msg_len < mem::size_of::<NlMsgHdr>() || msg_len > rest.len() {
+                    break;
+                }
+                let payload = &rest[mem::size_of::<NlMsgHdr>()..msg_len];
+                rest = rest.get(align(msg_len)..).unwrap_or(&[]);
+
+                if header.seq != seq {
+                    continue;
+                }
+                match header.ty {
+                    NLMSG_DONE => return Ok(responses),
+                    NLMSG_ERROR => {
+                        let errno = read::<i32>(payload).unwrap_or(-libc::EIO);
+                        if errno != 0 {
+                            return Err(io::Error::from_raw_os_error(-errno));
+                        }
+                        if !dump {
+                            return Ok(responses);
+                        }
+                    }
+                    _ => {
+                        responses.push(payload.to_vec());
+                        if !dump && header.flags & NLM_F_MULTI == 0 {
+                            return Ok(responses);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The leading counters of `struct rtnl_link_stats64`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LinkStats64 {
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+    pub multicast: u64,
+    pub collisions: u64,
+}
+
+impl From<LinkStats64> for InterfaceStats {
+    fn from(stats: LinkStats64) -> InterfaceStats {
+        InterfaceStats {
+            rx_bytes: stats.rx_bytes,
+            tx_bytes: stats.tx_bytes,
+            rx_packets: stats.rx_packets,
+            tx_packets: stats.tx_packets,
+            rx_errors: stats.rx_errors,
+            tx_errors: stats.tx_errors,
+            rx_dropped: stats.rx_dropped,
+            tx_dropped: stats.tx_dropped,
+            multicast: stats.multicast,
+            collisions: stats.collisions,
+            width: CounterWidth::Bits64,
+        }
+    }
+}
+
+/// What the kernel says about one link.
+#[derive(Debug, Clone, Default)]
+pub struct Link {
+    pub index: u32,
+    pub name: Option<String>,
+    pub kind: Option<String>,
+    pub stats: Option<InterfaceStats>,
+    pub mtu: Option<u32>,
+    pub tx_queue_len: Option<u32>,
+    pub num_tx_queues: Option<u32>,
+    pub num_rx_queues: Option<u32>,
+    pub alt_names: Vec<String>,
+    /// The free-text alias, `ip link set alias`.
+    pub alias: Option<String>,
+    pub tunnel: Option<TunnelEndpoints>,
+    /// The index of the lower link, or of the peer of a `veth`.
+    pub link: Option<u32>,
+    /// The index of the bridge or bond the link is a port of.
+    pub master: Option<u32>,
+    /// The link group, `ip link set group`; 0 is the default group.
+    pub group: Option<u32>,
+    /// How many times promiscuous mode was asked for, see
+    /// [`OsSpecific::promiscuity`](crate::OsSpecific::promiscuity).
+    pub promiscuity: Option<u32>,
+    /// The IPv6 interface identifier set with `ip token`, `::` for none.
+    /// `None` if IPv6 is disabled on the link.
+    pub ipv6_token: Option<Ipv6Addr>,
+    /// The namespace `link` is in, if it is not this one.
+    pub link_netnsid: Option<i32>,
+}
+
+/// Decode an `RTM_NEWLINK` payload.
+pub fn parse_link(payload: &[u8]) -> Option<Link> {
+    let header = read::<IfInfoMsg>(payload)?;
+    let mut link = Link {
+        index: header.index as u32,
+        ..Link::default()
+    };
+    for (ty, value) in Attrs::new(payload.get(align(mem::size_of::<IfInfoMsg>())..)?) {
+        match ty {
+            IFLA_IFNAME => link.name = attr_str(value).map(str::to_owned),
+            IFLA_MTU => link.mtu = read::<u32>(value),
+            IFLA_LINK => link.link = read::<u32>(value),
+            IFLA_MASTER => link.master = read::<u32>(value).filter(|&master| master != 0),
+            IFLA_GROUP => link.group = read::<u32>(value),
+            IFLA_PROMISCUITY => link.promiscuity = read::<u32>(value),
+            IFLA_LINK_NETNSID => link.link_netnsid = read::<i32>(value),
+            IFLA_TXQLEN => link.tx_queue_len = read::<u32>(value),
+            IFLA_IFALIAS => link.alias = attr_str(value).map(str::to_owned),
+            IFLA_NUM_TX_QUEUES => link.num_tx_queues = read::<u32>(value),
+            IFLA_NUM_RX_QUEUES => link.num_rx_queues = read::<u32>(value),
+            IFLA_STATS64 => link.stats = read::<LinkStats64>(value).map(InterfaceStats::from),
+            IFLA_LINKINFO => {
+                let mut data = None;
+                for (ty, value) in Attrs::new(value) {
+                    match ty {
+                        IFLA_INFO_KIND => link.kind = attr_str(value).map(str::to_owned),
+                        IFLA_INFO_DATA => data = Some(value),
+                        _ => {}
+                    }
+                }
+                link.tunnel = link
+                    .kind
+                    .as_deref()
+                    .zip(data)
+                    .and_then(|(kind, data)| parse_tunnel(kind, data));
+            }
+            IFLA_AF_SPEC => {
+                for (family, value) in Attrs::new(value) {
+                    if family != libc::AF_INET6 as u16 {
+                        continue;
+                    }
+                    link.ipv6_token = Attrs::new(value)
+                        .find(|&(ty, _)| ty == IFLA_INET6_TOKEN)
+                        .and_then(|(_, token)| read::<[u8; 16]>(token))
+                        .map(Ipv6Addr::from);
+                }
+            }
+            IFLA_PROP_LIST => {
+                for (ty, value) in Attrs::new(value) {
+                    if ty == IFLA_ALT_IFNAME {
+                        link.alt_names.extend(attr_str(value).map(str::to_owned));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(link)
+}
+
+/// The endpoints in the `IFLA_INFO_DATA` of a `kind` link, `None` if it
+/// is not a tunnel.
+fn parse_tunnel(kind: &str, data: &[u8]) -> Option<TunnelEndpoints> {
+    let (local, remote) = match kind {
+        "gre" | "gretap" | "ip6gre" | "ip6gretap" => (IFLA_GRE_LOCAL, IFLA_GRE_REMOTE),
+        "ipip" | "sit" => (IFLA_IPTUN_LOCAL, IFLA_IPTUN_REMOTE),
+        "vxlan" => (IFLA_VXLAN_LOCAL, IFLA_VXLAN_GROUP),
+        _ => return None,
+    };
+    // The kernel sends the wildcard address for endpoints that are not set.
+    let endpoint = |value: &[u8]| {
+        match value.len() {
+            4 => read::<[u8; 4]>(value).map(IpAddr::from),
+            16 => read::<[u8; 16]>(value).map(IpAddr::from),
+            _ => None,
+        }
+        .filter(|ip| !ip.is_unspecified())
+    };
+    let mut tunnel = TunnelEndpoints::default();
+    for (ty, value) in Attrs::new(data) {
+        if ty == local || (kind == "vxlan" && ty == IFLA_VXLAN_LOCAL6) {
+            tunnel.local = tunnel.local.or(endpoint(value));
+        } else if ty == remote || (kind == "vxlan" && ty == IFLA_VXLAN_GROUP6) {
+            tunnel.remote = tunnel.remote.or(endpoint(value));
+        } else if kind == "vxlan" && ty == IFLA_VXLAN_ID {
+            tunnel.vni = read::<u32>(value);
+        }
+    }
+    Some(tunnel)
+}
+
+/// The link with index `index`.
+pub fn link(index: u32) -> io::Result<Option<Link>> {
+    let mut socket = Socket::route()?;
+    let mut message = Message::new(RTM_GETLINK, 0);
+    message.push(&IfInfoMsg {
+        index: index as i32,
+        ..IfInfoMsg::default()
+    });
+    Ok(socket
+        .request(&mut message)?
+        .iter()
+        .find_map(|payload| parse_link(payload)))
+}
+
+/// Set the alias of link `index`; an empty one clears it.
+pub fn set_alias(index: u32, alias: &str) -> io::Result<()> {
+    Socket::route()?
+        .request(&mut alias_message(index, alias))
+        .map(|_| ())
+}
+
+fn alias_message(index: u32, alias: &str) -> Message {
+    let mut message = Message::new(RTM_SETLINK, NLM_F_ACK);
+    message.push(&IfInfoMsg {
+        index: index as i32,
+        ..IfInfoMsg::default()
+    });
+    message.attr(IFLA_IFALIAS, alias.as_bytes());
+    message
+}
+
+/// The `RTM_SETLINK` request that sets the IPv6 token of link `index`.
+pub fn ipv6_token_message(index: u32, token: &Ipv6Addr) -> Message {
+    let mut message = Message::new(RTM_SETLINK, NLM_F_ACK);
+    message.push(&IfInfoMsg {
+        index: index as i32,
+        ..IfInfoMsg::default()
+    });
+    message.nested(IFLA_AF_SPEC, |spec| {
+        spec.nested(libc::AF_INET6 as u16, |inet6| {
+            inet6.attr(IFLA_INET6_TOKEN, &token.octets());
+        });
+    });
+    message
+}
+
+/// Set the IPv6 token of link `index`; `::` clears it.
+pub fn set_ipv6_token(index: u32, token: &Ipv6Addr) -> io::Result<()> {
+    Socket::route()?
+        .request(&mut ipv6_token_message(index, token))
+        .map(|_| ())
+}
+
+/// The start of an `RTM_NEWLINK` request that makes a link called `name`.
+fn new_link_message(name: &ValidName) -> Message {
+    let mut message = Message::new(RTM_NEWLINK, NLM_F_ACK | NLM_F_CREATE | NLM_F_EXCL);
+    message.push(&IfInfoMsg::default());
+    let mut ifname = name.as_str().as_bytes().to_vec();
+    ifname.push(0);
+    message.attr(IFLA_IFNAME, &ifname);
+    message
+}
+
+/// Send an `RTM_NEWLINK` request, with the errors of a kernel without
+/// support for the kind as `ErrorKind::Unsupported`.
+fn create_link(mut message: Message) -> io::Result<()> {
+    match Socket::route()?.request(&mut message) {
+        Ok(_) => Ok(()),
+        Err(err) if err.raw_os_error() == Some(libc::EOPNOTSUPP) => {
+            Err(io::Error::new(io::ErrorKind::Unsupported, err))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// The `RTM_NEWLINK` request that makes VLAN `vlan_id` on link `parent`,
+/// called `name`.
+pub fn vlan_message(parent: u32, vlan_id: u16, name: &ValidName) -> Message {
+    let mut message = new_link_message(name);
+    message.attr(IFLA_LINK, &parent.to_ne_bytes());
+    message.nested(IFLA_LINKINFO, |info| {
+        info.attr(IFLA_INFO_KIND, b"vlan");
+        info.nested(IFLA_INFO_DATA, |data| {
+            data.attr(IFLA_VLAN_ID, &vlan_id.to_ne_bytes());
+        });
+    });
+    message
+}
+
+/// Make VLAN `vlan_id` on link `parent`. A link called `name` already
+/// being there fails with `ErrorKind::AlreadyExists`, a kernel without
+/// 802.1Q support with `ErrorKind::Unsupported`.
+pub fn create_vlan(parent: u32, vlan_id: u16, name: &ValidName) -> io::Result<()> {
+    create_link(vlan_message(parent, vlan_id, name))
+}
+
+/// The `RTM_NEWLINK` request that makes a bridge called `name`.
+pub fn bridge_message(name: &ValidName) -> Message {
+    let mut message = new_link_message(name);
+    message.nested(IFLA_LINKINFO, |info| {
+        info.attr(IFLA_INFO_KIND, b"bridge");
+    });
+    message
+}
+
+/// Make a bridge called `name`, like [`create_vlan`].
+pub fn create_bridge(name: &ValidName) -> io::Result<()> {
+    create_link(bridge_message(name))
+}
+
+/// Make link `index` a port of link `master`, or of nothing for 0.
+pub fn set_master(index: u32, master: u32) -> io::Result<()> {
+    let mut message = Message::new(RTM_SETLINK, NLM_F_ACK);
+    message.push(&IfInfoMsg {
+        index: index as i32,
+        ..IfInfoMsg::default()
+    });
+    message.attr(IFLA_MASTER, &master.to_ne_bytes());
+    Socket::route()?.request(&mut message).map(|_| ())
+}
+
+/// Delete link `index`.
+pub fn delete_link(index: u32) -> io::Result<()> {
+    let mut message = Message::new(RTM_DELLINK, NLM_F_ACK);
+    message.push(&IfInfoMsg {
+        index: index as i32,
+        ..IfInfoMsg::default()
+    });
+    match Socket::route()?.request(&mut message) {
+        Ok(_) => Ok(()),
+        Err(err) if err.raw_os_error() == Some(libc::ENODEV) => {
+            Err(io::Error::new(io::ErrorKind::NotFound, err))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Dump every link.
+pub fn links() -> io::Result<Vec<Link>> {
+    let mut socket = Socket::route()?;
+    let mut message = Message::new(RTM_GETLINK, NLM_F_DUMP);
+    message.push(&IfInfoMsg::default());
+    Ok(socket
+        .request(&mut message)?
+        .iter()
+        .filter_map(|payload| parse_link(payload))
+        .collect())
+}
+
+/// What the kernel says about one address.
+#[derive(Debug, Clone)]
+pub struct Addr {
+    pub index: u32,
+    /// The `IFA_F_*` flags, from `IFA_FLAGS` where the kernel sends it.
+    pub flags: u32,
+    pub address: Option<IpAddr>,
+    /// Who added the address, `IFAPROT_*`; kernels before 5.18 do not say.
+    pub proto: Option<u8>,
+}
+
+fn ip_from(family: u8, value: &[u8]) -> Option<IpAddr> {
+    match family as i32 {
+        libc::AF_INET => Some(IpAddr::from(read::<[u8; 4]>(value)?)),
+        libc::AF_INET6 => Some(IpAddr::from(read::<[u8; 16]>(value)?)),
+        _ => None,
+    }
+}
+
+/// Decode an `RTM_NEWADDR` payload.
+pub fn parse_addr(payload: &[u8]) -> Option<Addr> {
+    let header = read::<IfAddrMsg>(payload)?;
+    let mut addr = Addr {
+        index: header.index,
+        flags: header.flags as u32,
+        address: None,
+        proto: None,
+    };
+    let mut local = None;
+    for (ty, value) in Attrs::new(payload.get(align(mem::size_of::<IfAddrMsg>())..)?) {
+        match ty {
+            IFA_ADDRESS => addr.address = ip_from(header.family, value),
+            IFA_LOCAL => local = ip_from(header.family, value),
+            IFA_FLAGS => addr.flags = read::<u32>(value).unwrap_or(addr.flags),
+            IFA_PROTO => addr.proto = read::<u8>(value),
+            _ => {}
+        }
+    }
+    // On point-to-point links IFA_ADDRESS is the peer, IFA_LOCAL our own.
+    addr.address = local.or(addr.address);
+    Some(addr)
+}
+
+/// Dump every address of `family`, or of all families for `AF_UNSPEC`.
+pub fn addrs(family: i32) -> io::Result<Vec<Addr>> {
+    let mut socket = Socket::route()?;
+    let mut message = Message::new(RTM_GETADDR, NLM_F_DUMP);
+    message.push(&IfAddrMsg {
+        family: family as u8,
+        ..IfAddrMsg::default()
+    });
+    Ok(socket
+        .request(&mut message)?
+        .iter()
+        .filter_map(|payload| parse_addr(payload))
+        .collect())
+}
+
+fn ip_bytes(addr: &IpAddr) -> Vec<u8> {
+    match addr {
+        IpAddr::V4(addr) => addr.octets().to_vec(),
+        IpAddr::V6(addr) => addr.octets().to_vec(),
+    }
+}
+
+/// Delete `addr` from link `index`. The kernel matches on the local
+/// address alone, so this works for point-to-point addresses too, and
+/// fails with `ErrorKind::NotFound` if the link does not have it.
+pub fn delete_addr(index: u32, addr: &IpAddr, prefix_len: u8) -> io::Result<()> {
+    let mut message = Message::new(RTM_DELADDR, NLM_F_ACK);
+    message.push(&IfAddrMsg {
+        family: match addr {
+            IpAddr::V4(_) => libc::AF_INET as u8,
+            IpAddr::V6(_) => libc::AF_INET6 as u8,
+        },
+        prefix_len,
+        index,
+        ..IfAddrMsg::default()
+    });
+    message.attr(IFA_LOCAL, &ip_bytes(addr));
+    match Socket::route()?.request(&mut message) {
+        Ok(_) => Ok(()),
+        Err(err) if err.raw_os_error() == Some(libc::EADDRNOTAVAIL) => {
+            Err(io::Error::new(io::ErrorKind::NotFound, err))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// The `RTM_NEWROUTE` or `RTM_DELROUTE` request for `route`.
+pub fn route_message(ty: u16, route: &Route) -> Message {
+    let flags = match ty {
+        RTM_NEWROUTE => NLM_F_ACK | NLM_F_CREATE | NLM_F_EXCL,
+        _ => NLM_F_ACK,
+    };
+    let table = route.table.unwrap_or(RT_TABLE_MAIN as u32);
+    let mut header = RtMsg {
+        family: match route.destination {
+            IpAddr::V4(_) => libc::AF_INET as u8,
+            IpAddr::V6(_) => libc::AF_INET6 as u8,
+        },
+        dst_len: route.prefix_len,
+        // Ids past 255 only fit in RTA_TABLE.
+        table: table.min(255) as u8,
+        ..RtMsg::default()
+    };
+    // Deleting matches on what is given, so leave the rest unspecified.
+    if ty == RTM_NEWROUTE {
+        header.protocol = RTPROT_STATIC;
+        header.ty = RTN_UNICAST;
+        header.scope = match route.gateway {
+            Some(_) => RT_SCOPE_UNIVERSE,
+            None => RT_SCOPE_LINK,
+        };
+    } else {
+        header.scope = RT_SCOPE_NOWHERE;
+    }
+
+    let mut message = Message::new(ty, flags);
+    message.push(&header);
+    message.attr(RTA_DST, &ip_bytes(&route.destination));
+    message.attr(RTA_TABLE, &table.to_ne_bytes());
+    if let Some(gateway) = &route.gateway {
+        message.attr(RTA_GATEWAY, &ip_bytes(gateway));
+    }
+    if let Some(ifindex) = route.ifindex {
+        message.attr(RTA_OIF, &ifindex.to_ne_bytes());
+    }
+    if let Some(metric) = route.metric {
+        message.attr(RTA_PRIORITY, &metric.to_ne_bytes());
+    }
+    message
+}
+
+/// Add or delete a route, as `ty` says. Deleting a route that is not there
+/// fails with `ErrorKind::NotFound`.
+pub fn change_route(ty: u16, route: &Route) -> io::Result<()> {
+    match Socket::route()?.request(&mut route_message(ty, route)) {
+        Ok(_) => Ok(()),
+        Err(err) if err.raw_os_error() == Some(libc::ESRCH) => {
+            Err(io::Error::new(io::ErrorKind::NotFound, err))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// The gateway and outgoing link of the default route of `family` in the
+/// main table, the one with the lowest metric if there are several.
+/// `None` without a default route through a gateway.
+pub fn default_gateway(family: u8) -> io::Result<Option<(IpAddr, u32)>> {
+    let mut dump = Message::new(RTM_GETROUTE, NLM_F_DUMP);
+    dump.push(&RtMsg {
+        family,
+        ..RtMsg::default()
+    });
+    Ok(default_gateway_in(&Socket::route()?.request(&mut dump)?))
+}
+
+/// The default gateway of the route dump `payloads`, see
+/// [`default_gateway`].
+fn default_gateway_in(payloads: &[Vec<u8>]) -> Option<(IpAddr, u32)> {
+    let mut best: Option<(u32, IpAddr, u32)> = None;
+    for payload in payloads {
+        let header = match read::<RtMsg>(payload) {
+            Some(header) if header.dst_len == 0 && header.ty == RTN_UNICAST => header,
+            _ => continue,
+        };
+        let attrs = match payload.get(align(mem::size_of::<RtMsg>())..) {
+            Some(attrs) => attrs,
+            None => continue,
+        };
+        let (mut table, mut gateway, mut oif, mut metric) = (header.table as u32, None, None, 0);
+        for (ty, value) in Attrs::new(attrs) {
+            match ty {
+                RTA_TABLE => table = read::<u32>(value).unwrap_or(table),
+                RTA_GATEWAY => gateway = ip_from_bytes(value),
+                RTA_OIF => oif = read::<u32>(value),
+                RTA_PRIORITY => metric = read::<u32>(value).unwrap_or(0),
+                _ => {}
+            }
+        }
+        if table != RT_TABLE_MAIN as u32 {
+            continue;
+        }
+        if let (Some(gateway), Some(oif)) = (gateway, oif) {
+            if best.is_none_or(|(best, _, _)| metric < best) {
+                best = Some((metric, gateway, oif));
+            }
+        }
+    }
+    best.map(|(_, gateway, oif)| (gateway, oif))
+}
+
+/// The link-layer address the neighbor table has for `ip` on link
+/// `index`. `None` if there is no entry, or resolution is still going on
+/// or failed.
+pub fn neighbor_mac(index: u32, ip: &IpAddr) -> io::Result<Option<MacAddr>> {
+    let mut dump = Message::new(RTM_GETNEIGH, NLM_F_DUMP);
+    dump.push(&NdMsg {
+        family: match ip {
+            IpAddr::V4(_) => libc::AF_INET as u8,
+            IpAddr::V6(_) => libc::AF_INET6 as u8,
+        },
+        ..NdMsg::default()
+    });
+    Ok(neighbor_mac_in(
+        &Socket::route()?.request(&mut dump)?,
+        index,
+        ip,
+    ))
+}
+
+/// The link-layer address of `ip` on link `index` in the neighbor dump
+/// `payloads`, see [`neighbor_mac`].
+fn neighbor_mac_in(payloads: &[Vec<u8>], index: u32, ip: &IpAddr) -> Option<MacAddr> {
+    for payload in payloads {
+        let header = match read::<NdMsg>(payload) {
+            Some(header) => header,
+            None => continue,
+        };
+        if header.index as u32 != index || header.state & (NUD_INCOMPLETE | NUD_FAILED) != 0 {
+            continue;
+        }
+        let attrs = match payload.get(align(mem::size_of::<NdMsg>())..) {
+            Some(attrs) => attrs,
+            None => continue,
+        };
+        let (mut dst, mut lladdr) = (None, None);
+        for (ty, value) in Attrs::new(attrs) {
+            match ty {
+                NDA_DST => dst = ip_from_bytes(value),
+                NDA_LLADDR => lladdr = MacAddr::from_hardware_addr(value),
+                _ => {}
+            }
+        }
+        if dst.as_ref() == Some(ip) && lladdr.is_some() {
+            return lladdr;
+        }
+    }
+    None
+}
+
+#[cfg(any(target_os = "android", test))]
+const FRA_FWMARK: u16 = 10;
+#[cfg(any(target_os = "android", test))]
+const FRA_TABLE: u16 = 15;
+#[cfg(any(target_os = "android", test))]
+const FRA_FWMASK: u16 = 16;
+
+/// A policy routing rule, as far as picking a table by firewall mark goes.
+#[cfg(any(target_os = "android", test))]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Rule {
+    pub table: u32,
+    pub fwmark: Option<u32>,
+    pub fwmask: Option<u32>,
+}
+
+/// The policy routing rules of `family`. Their header, `fib_rule_hdr`, is
+/// laid out like `rtmsg`.
+#[cfg(any(target_os = "android", test))]
+pub fn rules(family: u8) -> io::Result<Vec<Rule>> {
+    let mut dump = Message::new(RTM_GETRULE, NLM_F_DUMP);
+    dump.push(&RtMsg {
+        family,
+        ..RtMsg::default()
+    });
+    Ok(rules_in(&Socket::route()?.request(&mut dump)?))
+}
+
+/// The rules of the rule dump `payloads`, see [`rules`].
+#[cfg(any(target_os = "android", test))]
+fn rules_in(payloads: &[Vec<u8>]) -> Vec<Rule> {
+    let mut rules = Vec::new();
+    for payload in payloads {
+        let header = match read::<RtMsg>(payload) {
+            Some(header) => header,
+            None => continue,
+        };
+        let attrs = match payload.get(align(mem::size_of::<RtMsg>())..) {
+            Some(attrs) => attrs,
+            None => continue,
+        };
+        let mut rule = Rule {
+            table: header.table as u32,
+            ..Rule::default()
+        };
+        for (ty, value) in Attrs::new(attrs) {
+            match ty {
+                FRA_TABLE => rule.table = read::<u32>(value).unwrap_or(rule.table),
+                FRA_FWMARK => rule.fwmark = read::<u32>(value),
+                FRA_FWMASK => rule.fwmask = read::<u32>(value),
+                _ => {}
+            }
+        }
+        rules.push(rule);
+    }
+    rules
+}
+
+/// An address attribute, by its length.
+fn ip_from_bytes(value: &[u8]) -> Option<IpAddr> {
+    match value.len() {
+        4 => read::<[u8; 4]>(value).map(IpAddr::from),
+        16 => read::<[u8; 16]>(value).map(IpAddr::from),
+        _ => None,
+    }
+}
+
+/// The route attributes that describe a route, rather than its state, and
+/// so are kept when it is added again.
+const ROUTE_SETTINGS: &[u16] = &[
+    RTA_DST,
+    RTA_SRC,
+    RTA_OIF,
+    RTA_GATEWAY,
+    RTA_PREFSRC,
+    RTA_METRICS,
+    RTA_MULTIPATH,
+    RTA_TABLE,
+    RTA_PREF,
+];
+
+/// Give the default routes of `family` out of link `index` in the main
+/// table metric `metric`, and return how many there were.
+///
+/// A route's metric is part of what identifies it, so each one is added
+/// again with the new metric and everything else as it was, and then the
+/// old one is deleted.
+pub fn set_default_metric(family: u8, index: u32, metric: u32) -> io::Result<usize> {
+    let mut socket = Socket::route()?;
+    let mut dump = Message::new(RTM_GETROUTE, NLM_F_DUMP);
+    dump.push(&RtMsg {
+        family,
+        ..RtMsg::default()
+    });
+
+    let mut changed = 0;
+    for payload in socket.request(&mut dump)? {
+        if let Some((mut add, mut delete)) = metric_messages(&payload, index, metric) {
+            socket.request(&mut add)?;
+            socket.request(&mut delete)?;
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+/// The requests that add the route in `payload` again with metric `metric`
+/// and delete the old one, if it is a default route out of link `index` in
+/// the main table with another metric.
+fn metric_messages(payload: &[u8], index: u32, metric: u32) -> Option<(Message, Message)> {
+    let header = read::<RtMsg>(payload)?;
+    let attrs: Vec<(u16, &[u8])> =
+        Attrs::new(payload.get(align(mem::size_of::<RtMsg>())..)?).collect();
+    let find = |ty| {
+        attrs
+            .iter()
+            .find(|(t, _)| *t == ty)
+            .map(|(_, value)| *value)
+    };
+    let table = find(RTA_TABLE)
+        .and_then(read::<u32>)
+        .unwrap_or(header.table as u32);
+    if header.dst_len != 0
+        || header.ty != RTN_UNICAST
+        || table != RT_TABLE_MAIN as u32
+        || find(RTA_OIF).and_then(read::<u32>) != Some(index)
+        || find(RTA_PRIORITY).and_then(read::<u32>) == Some(metric)
+    {
+        return None;
+    }
+
+    let mut add = Message::new(RTM_NEWROUTE, NLM_F_ACK | NLM_F_CREATE | NLM_F_EXCL);
+    add.push(&header);
+    for (ty, value) in attrs.iter().filter(|(ty, _)| ROUTE_SETTINGS.contains(ty)) {
+        add.attr(*ty, value);
+    }
+    add.attr(RTA_PRIORITY, &metric.to_ne_bytes());
+
+    let mut delete = Message::new(RTM_DELROUTE, NLM_F_ACK);
+    delete.push(&header);
+    for (ty, value) in &attrs {
+        if ROUTE_SETTINGS.contains(ty) || *ty == RTA_PRIORITY {
+            delete.attr(*ty, value);
+        }
+    }
+    Some((add, delete))
+}
+
+/// The `RTM_NEWNEIGH` request for a permanent entry, or the `RTM_DELNEIGH`
+/// one, for `ip` on link `index`.
+pub fn neigh_message(ty: u16, index: u32, ip: &IpAddr, mac: Option<MacAddr>) -> Message {
+    let flags = match ty {
+        RTM_NEWNEIGH => NLM_F_ACK | NLM_F_CREATE | NLM_F_EXCL,
+        _ => NLM_F_ACK,
+    };
+    let mut message = Message::new(ty, flags);
+    message.push(&NdMsg {
+        family: match ip {
+            IpAddr::V4(_) => libc::AF_INET as u8,
+            IpAddr::V6(_) => libc::AF_INET6 as u8,
+        },
+        index: index as i32,
+        state: NUD_PERMANENT,
+        ..NdMsg::default()
+    });
+    message.attr(NDA_DST, &ip_bytes(ip));
+    if let Some(mac) = mac {
+        message.attr(NDA_LLADDR, &mac.octets());
+    }
+    message
+}
+
+/// Add or delete a neighbor entry, as `ty` says. Deleting one that is not
+/// there fails with `ErrorKind::NotFound`.
+pub fn change_neigh(ty: u16, index: u32, ip: &IpAddr, mac: Option<MacAddr>) -> io::Result<()> {
+    match Socket::route()?.request(&mut neigh_message(ty, index, ip, mac)) {
+        Ok(_) => Ok(()),
+        Err(err) if err.raw_os_error() == Some(libc::ENOENT) => {
+            Err(io::Error::new(io::ErrorKind::NotFound, err))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::TryFrom;
+
+    const LO: &[u8] = include_bytes!("../../../tests/fixtures/netlink/rtm-newlink-lo.bin");
+    const ETH0: &[u8] = include_bytes!("../../../tests/fixtures/netlink/rtm-newlink-eth0.bin");
+    const IFB0_PROMISC: &[u8] =
+        include_bytes!("../../../tests/fixtures/netlink/rtm-newlink-ifb0-promisc.bin");
+    const IFB0_PACKET_SOCKET: &[u8] =
+        include_bytes!("../../../tests/fixtures/netlink/rtm-newlink-ifb0-packet-socket.bin");
+    const VETH: &[u8] = include_bytes!("../../../tests/fixtures/netlink/rtm-newlink-veth.bin");
+    const VETH_NETNS: &[u8] =
+        include_bytes!("../../../tests/fixtures/netlink/rtm-newlink-veth-netns.bin");
+    const MACVLAN: &[u8] =
+        include_bytes!("../../../tests/fixtures/netlink/rtm-newlink-macvlan.bin");
+
+    fn ifi_flags(payload: &[u8]) -> u32 {
+        read::<IfInfoMsg>(payload).unwrap().flags
+    }
+
+    /// The header of a request, its fixed `T` header and its attributes.
+    fn decode<T: Copy>(message: &Message) -> (NlMsgHdr, T, Vec<(u16, Vec<u8>)>) {
+        let header = read::<NlMsgHdr>(&message.buf).unwrap();
+        let payload = &message.buf[mem::size_of::<NlMsgHdr>()..];
+        let fixed = read::<T>(payload).unwrap();
+        let attrs = Attrs::new(&payload[align(mem::size_of::<T>())..])
+            .map(|(ty, value)| (ty, value.to_vec()))
+            .collect();
+        (header, fixed, attrs)
+    }
+
+    fn route(destination: &str, prefix_len: u8) -> Route {
+        Route::new(destination.parse().unwrap(), prefix_len)
+    }
+
+    #[test]
+    fn routes_through_a_gateway_are_universe_scope() {
+        let route = route("10.0.0.0", 8)
+            .via("192.0.2.1".parse().unwrap())
+            .on(4)
+            .with_metric(100);
+        let (header, rtm, attrs) = decode::<RtMsg>(&route_message(RTM_NEWROUTE, &route));
+        assert_eq!(header.ty, RTM_NEWROUTE);
+        assert_eq!(
+            header.flags,
+            NLM_F_REQUEST | NLM_F_ACK | NLM_F_CREATE | NLM_F_EXCL
+        );
+        assert_eq!(
+            (rtm.family, rtm.dst_len, rtm.table),
+            (libc::AF_INET as u8, 8, RT_TABLE_MAIN)
+        );
+        assert_eq!(
+            (rtm.protocol, rtm.scope, rtm.ty),
+            (RTPROT_STATIC, RT_SCOPE_UNIVERSE, RTN_UNICAST)
+        );
+        assert_eq!(
+            attrs,
+            [
+                (RTA_DST, vec![10, 0, 0, 0]),
+                (RTA_TABLE, u32::from(RT_TABLE_MAIN).to_ne_bytes().to_vec()),
+                (RTA_GATEWAY, vec![192, 0, 2, 1]),
+                (RTA_OIF, 4u32.to_ne_bytes().to_vec()),
+                (RTA_PRIORITY, 100u32.to_ne_bytes().to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn on_link_host_routes_are_link_scope() {
+        let route = Route::host("2001:db8::7".parse().unwrap()).on(4);
+        let (_, rtm, attrs) = decode::<RtMsg>(&route_message(RTM_NEWROUTE, &route));
+        assert_eq!((rtm.family, rtm.dst_len), (libc::AF_INET6 as u8, 128));
+        assert_eq!(rtm.scope, RT_SCOPE_LINK);
+        let types: Vec<u16> = attrs.iter().map(|(ty, _)| *ty).collect();
+        assert_eq!(types, [RTA_DST, RTA_TABLE, RTA_OIF]);
+        assert_eq!(
+            attrs[0].1,
+            "2001:db8::7"
+                .parse::<std::net::Ipv6Addr>()
+                .unwrap()
+                .octets()
+        );
+    }
+
+    #[test]
+    fn deletes_match_on_what_is_given() {
+        let route = route("198.51.100.0", 24).on(4);
+        let (header, rtm, _) = decode::<RtMsg>(&route_message(RTM_DELROUTE, &route));
+        assert_eq!(header.ty, RTM_DELROUTE);
+        assert_eq!(header.flags, NLM_F_REQUEST | NLM_F_ACK);
+        assert_eq!((rtm.protocol, rtm.ty), (0, 0));
+        assert_eq!(rtm.scope, RT_SCOPE_NOWHERE);
+    }
+
+    #[test]
+    fn large_table_ids_go_in_the_attribute() {
+        let route = route("198.51.100.0", 24).on(4).in_table(1000);
+        let (_, rtm, attrs) = decode::<RtMsg>(&route_message(RTM_NEWROUTE, &route));
+        assert_eq!(rtm.table, 255);
+        assert!(attrs.contains(&(RTA_TABLE, 1000u32.to_ne_bytes().to_vec())));
+    }
+
+    /// An `RTM_NEWROUTE` dump entry for a default route via 192.0.2.1 out
+    /// of link `index`, with a cache attribute the kernel adds.
+    fn default_route(index: u32, metric: u32, table: u32) -> Vec<u8> {
+        let mut message = Message::new(RTM_NEWROUTE, 0);
+        message.push(&RtMsg {
+            family: libc::AF_INET as u8,
+            table: RT_TABLE_MAIN,
+            protocol: 16,
+            scope: RT_SCOPE_UNIVERSE,
+            ty: RTN_UNICAST,
+            ..RtMsg::default()
+        });
+        message.attr(RTA_TABLE, &table.to_ne_bytes());
+        message.attr(RTA_PRIORITY, &metric.to_ne_bytes());
+        message.attr(RTA_PREFSRC, &[192, 0, 2, 2]);
+        message.attr(RTA_GATEWAY, &[192, 0, 2, 1]);
+        message.attr(RTA_OIF, &index.to_ne_bytes());
+        // RTA_CACHEINFO, state rather than settings.
+        message.attr(12, &[0; 16]);
+        message.buf[mem::size_of::<NlMsgHdr>()..].to_vec()
+    }
+
+    #[test]
+    fn metric_changes_add_the_route_again_and_delete_the_old_one() {
+        let main = u32::from(RT_TABLE_MAIN);
+        let (add, delete) = metric_messages(&default_route(4, 600, main), 4, 50).unwrap();
+        let (header, rtm, attrs) = decode::<RtMsg>(&add);
+        assert_eq!(header.ty, RTM_NEWROUTE);
+        assert_eq!(
+            header.flags,
+            NLM_F_REQUEST | NLM_F_ACK | NLM_F_CREATE | NLM_F_EXCL
+        );
+        // The route is kept as the kernel described it.
+        assert_eq!(
+            (rtm.protocol, rtm.scope, rtm.ty),
+            (16, RT_SCOPE_UNIVERSE, RTN_UNICAST)
+        );
+        assert_eq!(
+            attrs,
+            [
+                (RTA_TABLE, main.to_ne_bytes().to_vec()),
+                (RTA_PREFSRC, vec![192, 0, 2, 2]),
+                (RTA_GATEWAY, vec![192, 0, 2, 1]),
+                (RTA_OIF, 4u32.to_ne_bytes().to_vec()),
+                (RTA_PRIORITY, 50u32.to_ne_bytes().to_vec()),
+            ]
+        );
+
+        let (header, _, attrs) = decode::<RtMsg>(&delete);
+        assert_eq!(header.ty, RTM_DELROUTE);
+        assert_eq!(header.flags, NLM_F_REQUEST | NLM_F_ACK);
+        assert!(attrs.contains(&(RTA_PRIORITY, 600u32.to_ne_bytes().to_vec())));
+        assert!(attrs.iter().all(|(ty, _)| *ty != 12));
+    }
+
+    #[test]
+    fn only_other_default_routes_of_the_link_change() {
+        let main = u32::from(RT_TABLE_MAIN);
+        // Another link, the metric it already has, another table.
+        assert!(metric_messages(&default_route(5, 600, main), 4, 50).is_none());
+        assert!(metric_messages(&default_route(4, 50, main), 4, 50).is_none());
+        assert!(metric_messages(&default_route(4, 600, 100), 4, 50).is_none());
+        // Not a default route.
+        let mut payload = default_route(4, 600, main);
+        payload[1] = 24;
+        assert!(metric_messages(&payload, 4, 50).is_none());
+        assert!(metric_messages(&payload[..4], 4, 50).is_none());
+    }
+
+    #[test]
+    fn neighbor_entries_are_permanent() {
+        let mac = MacAddr::new([0x02, 0, 0, 0, 0, 0x01]);
+        let message = neigh_message(RTM_NEWNEIGH, 4, &"192.0.2.9".parse().unwrap(), Some(mac));
+        let (header, ndm, attrs) = decode::<NdMsg>(&message);
+        assert_eq!(header.ty, RTM_NEWNEIGH);
+        assert_eq!(
+            header.flags,
+            NLM_F_REQUEST | NLM_F_ACK | NLM_F_CREATE | NLM_F_EXCL
+        );
+        assert_eq!(
+            (ndm.family, ndm.index, ndm.state),
+            (libc::AF_INET as u8, 4, NUD_PERMANENT)
+        );
+        assert_eq!(
+            attrs,
+            [
+                (NDA_DST, vec![192, 0, 2, 9]),
+                (NDA_LLADDR, mac.octets().to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn neighbor_deletes_name_only_the_address() {
+        let ip: IpAddr = "2001:db8::9".parse().unwrap();
+        let (header, ndm, attrs) = decode::<NdMsg>(&neigh_message(RTM_DELNEIGH, 4, &ip, None));
+        assert_eq!(header.ty, RTM_DELNEIGH);
+        assert_eq!(header.flags, NLM_F_REQUEST | NLM_F_ACK);
+        assert_eq!(ndm.family, libc::AF_INET6 as u8);
+        let types: Vec<u16> = attrs.iter().map(|(ty, _)| *ty).collect();
+        assert_eq!(types, [NDA_DST]);
+    }
+
+    #[test]
+    fn aliases_are_set_on_the_link() {
+        let (header, ifi, attrs) = decode::<IfInfoMsg>(&alias_message(4, "uplink to rack 4"));
+        assert_eq!(header.ty, RTM_SETLINK);
+        assert_eq!(header.flags, NLM_F_REQUEST | NLM_F_ACK);
+        assert_eq!(ifi.index, 4);
+        assert_eq!(attrs, [(IFLA_IFALIAS, b"uplink to rack 4".to_vec())]);
+        // Clearing sends an empty one.
+        let (_, _, attrs) = decode::<IfInfoMsg>(&alias_message(4, ""));
+        assert_eq!(attrs, [(IFLA_IFALIAS, Vec::new())]);
+    }
+
+    #[test]
+    fn aliases_are_read_from_their_attribute() {
+        let mut message = Message::new(RTM_NEWLINK, 0);
+        message.push(&IfInfoMsg {
+            index: 9,
+            ..IfInfoMsg::default()
+        });
+        message.attr(IFLA_IFNAME, b"dummy0\0");
+        message.attr(IFLA_IFALIAS, b"uplink to rack 4\0");
+        let link = parse_link(&message.buf[mem::size_of::<NlMsgHdr>()..]).unwrap();
+        assert_eq!(link.alias.as_deref(), Some("uplink to rack 4"));
+        assert_eq!(parse_link(LO).unwrap().alias, None);
+    }
+
+    #[test]
+    fn captured_links_parse() {
+        let lo = parse_link(LO).unwrap();
+        assert_eq!(lo.index, 1);
+        assert_eq!(lo.name.as_deref(), Some("lo"));
+        assert_eq!(lo.mtu, Some(65536));
+        assert_eq!(lo.kind, None);
+        assert_eq!(lo.group, Some(0));
+        assert_eq!(lo.promiscuity, Some(0));
+        assert!(lo.stats.is_some());
+
+        let eth0 = parse_link(ETH0).unwrap();
+        assert_eq!(eth0.index, 4);
+        assert_eq!(eth0.name.as_deref(), Some("eth0"));
+        assert_eq!(eth0.mtu, Some(1400));
+        assert_eq!(eth0.tx_queue_len, Some(1000));
+        assert_eq!(eth0.num_tx_queues, Some(1));
+        assert_eq!(eth0.promiscuity, Some(0));
+    }
+
+    #[test]
+    fn queue_sizes_come_from_their_attributes() {
+        let queues = |payload| {
+            let link = parse_link(payload).unwrap();
+            (link.tx_queue_len, link.num_tx_queues, link.num_rx_queues)
+        };
+        assert_eq!(queues(LO), (Some(1000), Some(1), Some(1)));
+        assert_eq!(queues(ETH0), (Some(1000), Some(1), Some(1)));
+        assert_eq!(queues(IFB0_PROMISC), (Some(32), Some(1), Some(1)));
+    }
+
+    #[test]
+    fn missing_queue_attributes_are_none() {
+        let mut message = Message::new(RTM_NEWLINK, 0);
+        message.push(&IfInfoMsg {
+            index: 9,
+            ..IfInfoMsg::default()
+        });
+        message.attr(IFLA_IFNAME, b"dummy0\0");
+        message.attr(IFLA_TXQLEN, &0u32.to_ne_bytes());
+        let link = parse_link(&message.buf[mem::size_of::<NlMsgHdr>()..]).unwrap();
+        assert_eq!(link.tx_queue_len, Some(0));
+        assert_eq!(link.num_tx_queues, None);
+        assert_eq!(link.num_rx_queues, None);
+    }
+
+    #[test]
+    fn stats_are_the_64_bit_counters() {
+        let stats = parse_link(LO).unwrap().stats.unwrap();
+        assert_eq!(stats.width, crate::CounterWidth::Bits64);
+        // Whatever loopback sends, it receives.
+        assert_eq!(stats.rx_bytes, stats.tx_bytes);
+        assert_eq!(stats.rx_packets, stats.tx_packets);
+        assert!(stats.rx_bytes >= stats.rx_packets);
+    }
+
+    #[test]
+    fn link_kinds_come_from_the_link_info() {
+        assert_eq!(parse_link(LO).unwrap().kind, None);
+        // A virtio NIC, not a software device.
+        assert_eq!(parse_link(ETH0).unwrap().kind, None);
+        assert_eq!(
+            parse_link(IFB0_PROMISC).unwrap().kind.as_deref(),
+            Some("ifb")
+        );
+    }
+
+    #[test]
+    fn promiscuity_counts_the_flag() {
+        let ifb0 = parse_link(IFB0_PROMISC).unwrap();
+        assert_ne!(ifi_flags(IFB0_PROMISC) & libc::IFF_PROMISC as u32, 0);
+        assert_eq!(ifb0.promiscuity, Some(1));
+    }
+
+    #[test]
+    fn promiscuity_counts_packet_sockets_without_the_flag() {
+        let ifb0 = parse_link(IFB0_PACKET_SOCKET).unwrap();
+        assert_eq!(ifi_flags(IFB0_PACKET_SOCKET) & libc::IFF_PROMISC as u32, 0);
+        assert_eq!(ifb0.promiscuity, Some(1));
+    }
+
+    #[test]
+    fn missing_promiscuity_is_none() {
+        let mut message = Message::new(RTM_NEWLINK, 0);
+        message.push(&IfInfoMsg {
+            index: 9,
+            ..IfInfoMsg::default()
+        });
+        message.attr(IFLA_IFNAME, b"dummy0\0");
+        let link = parse_link(&message.buf[mem::size_of::<NlMsgHdr>()..]).unwrap();
+        assert_eq!(link.name.as_deref(), Some("dummy0"));
+        assert_eq!(link.promiscuity, None);
+    }
+
+    #[cfg(feature = "wireguard")]
+    #[test]
+    fn families_are_asked_for_by_name() {
+        let (header, genl, attrs) = decode::<GenlMsgHdr>(&family_message("wireguard"));
+        // GENL_ID_CTRL.
+        assert_eq!(header.ty, 0x10);
+        assert_eq!(header.flags, NLM_F_REQUEST);
+        // CTRL_CMD_GETFAMILY.
+        assert_eq!((genl.cmd, genl.version), (3, 1));
+        assert_eq!(attrs, [(2, b"wireguard\0".to_vec())]);
+    }
+
+    #[cfg(feature = "wireguard")]
+    #[test]
+    fn family_ids_are_read_from_the_reply() {
+        // The start of a CTRL_CMD_NEWFAMILY reply.
+        let reply = [
+            1, 2, 0, 0, // cmd, version
+            14, 0, 2, 0, b'w', b'i', b'r', b'e', b'g', b'u', b'a', b'r', b'd', 0, 0,
+            0, // name
+            6, 0, 1, 0, 0x15, 0, 0, 0, // id
+            8, 0, 3, 0, 1, 0, 0, 0, // version
+        ]
+        .to_vec();
+        assert_eq!(family_id_in(std::slice::from_ref(&reply)), Some(0x15));
+        assert_eq!(family_id_in(&[reply[..24].to_vec()]), None);
+        assert_eq!(family_id_in(&[]), None);
+    }
+
+    #[cfg(feature = "wireguard")]
+    #[test]
+    fn families_are_looked_up() {
+        let mut socket = Socket::generic().unwrap();
+        assert_eq!(family_id(&mut socket, "nlctrl").unwrap(), 0x10);
+        let err = family_id(&mut socket, "ifaces-none").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(err.to_string(), "no generic netlink family \"ifaces-none\"");
+    }
+
+    #[test]
+    fn captured_veths_have_their_peer() {
+        // ifveth0, made with `ip link add ifveth0 type veth peer name ifveth1`.
+        let veth = parse_link(VETH).unwrap();
+        assert_eq!(veth.index, 178);
+        assert_eq!(veth.kind.as_deref(), Some("veth"));
+        assert_eq!(veth.link, Some(177));
+        assert_eq!(veth.link_netnsid, None);
+
+        // ifveth2, with its peer made in another namespace.
+        let veth = parse_link(VETH_NETNS).unwrap();
+        assert_eq!(veth.index, 179);
+        assert_eq!(veth.kind.as_deref(), Some("veth"));
+        assert_eq!(veth.link, Some(2));
+        assert_eq!(veth.link_netnsid, Some(0));
+    }
+
+    #[test]
+    fn captured_lower_links_are_read() {
+        // ifmv0, a macvlan on ifveth0.
+        let macvlan = parse_link(MACVLAN).unwrap();
+        assert_eq!(macvlan.kind.as_deref(), Some("macvlan"));
+        assert_eq!(macvlan.link, Some(178));
+        assert_eq!(macvlan.link_netnsid, None);
+        // Links without one.
+        assert_eq!(parse_link(LO).unwrap().link, None);
+        assert_eq!(parse_link(ETH0).unwrap().link, None);
+    }
+
+    #[test]
+    fn vlans_are_made_on_their_parent() {
+        let name = crate::validate_name("eth0.7").unwrap();
+        let (header, ifi, attrs) = decode::<IfInfoMsg>(&vlan_message(4, 7, &name));
+        assert_eq!(header.ty, RTM_NEWLINK);
+        assert_eq!(
+            header.flags,
+            NLM_F_REQUEST | NLM_F_ACK | NLM_F_CREATE | NLM_F_EXCL
+        );
+        assert_eq!(ifi.index, 0);
+        assert_eq!(attrs.len(), 3);
+        assert_eq!(attrs[0], (IFLA_IFNAME, b"eth0.7\0".to_vec()));
+        assert_eq!(attrs[1], (IFLA_LINK, 4u32.to_ne_bytes().to_vec()));
+        assert_eq!(attrs[2].0, IFLA_LINKINFO);
+        let info: Vec<(u16, &[u8])> = Attrs::new(&attrs[2].1).collect();
+        assert_eq!(info.len(), 2);
+        assert_eq!(info[0], (IFLA_INFO_KIND, &b"vlan"[..]));
+        assert_eq!(info[1].0, IFLA_INFO_DATA);
+        let data: Vec<(u16, &[u8])> = Attrs::new(info[1].1).collect();
+        assert_eq!(data, [(IFLA_VLAN_ID, &7u16.to_ne_bytes()[..])]);
+    }
+
+    #[test]
+    fn nested_attributes_are_flagged() {
+        let name = crate::validate_name("eth0.7").unwrap();
+        let message = vlan_message(4, 7, &name);
+        let payload = &message.buf[mem::size_of::<NlMsgHdr>() + mem::size_of::<IfInfoMsg>()..];
+        // IFLA_IFNAME and IFLA_LINK, 12 and 8 bytes, then IFLA_LINKINFO.
+        let linkinfo = &payload[20..];
+        let ty = read::<u16>(&linkinfo[2..]).unwrap();
+        assert_eq!(ty, IFLA_LINKINFO | NLA_F_NESTED);
+        let len = read::<u16>(linkinfo).unwrap() as usize;
+        // Itself, "vlan" and IFLA_INFO_DATA holding the 2-byte id.
+        assert_eq!(len, 4 + 8 + 4 + 8);
+        assert_eq!(linkinfo.len(), len);
+    }
+
+    /// The payload of a message `fill` builds, as a dump hands it out.
+    fn payload(fill: impl FnOnce(&mut Message)) -> Vec<u8> {
+        let mut message = Message::new(0, 0);
+        fill(&mut message);
+        message.buf[mem::size_of::<NlMsgHdr>()..].to_vec()
+    }
+
+    /// A main-table route to `dst_len` bits of its destination.
+    fn route_entry(ty: u8, dst_len: u8, attrs: &[(u16, &[u8])]) -> Vec<u8> {
+        payload(|message| {
+            message.push(&RtMsg {
+                family: libc::AF_INET as u8,
+                dst_len,
+                table: RT_TABLE_MAIN,
+                ty,
+                ..RtMsg::default()
+            });
+            for (ty, value) in attrs {
+                message.attr(*ty, value);
+            }
+        })
+    }
+
+    #[test]
+    fn the_default_route_with_the_lowest_metric_wins() {
+        let dump = [
+            // The on-link route of the subnet, `192.0.2.0/24 dev eth0`.
+            route_entry(RTN_UNICAST, 24, &[(RTA_OIF, &2u32.to_ne_bytes())]),
+            route_entry(
+                RTN_UNICAST,
+                0,
+                &[
+                    (RTA_GATEWAY, &[192, 0, 2, 1]),
+                    (RTA_OIF, &2u32.to_ne_bytes()),
+                    (RTA_PRIORITY, &600u32.to_ne_bytes()),
+                ],
+            ),
+            route_entry(
+                RTN_UNICAST,
+                0,
+                &[
+                    (RTA_GATEWAY, &[198, 51, 100, 1]),
+                    (RTA_OIF, &3u32.to_ne_bytes()),
+                    (RTA_PRIORITY, &100u32.to_ne_bytes()),
+                ],
+            ),
+        ];
+        assert_eq!(
+            default_gateway_in(&dump),
+            Some(("198.51.100.1".parse().unwrap(), 3))
+        );
+        // Without a metric it is 0, the lowest.
+        let unmetered = route_entry(
+            RTN_UNICAST,
+            0,
+            &[
+                (RTA_GATEWAY, &[203, 0, 113, 1]),
+                (RTA_OIF, &4u32.to_ne_bytes()),
+            ],
+        );
+        let dump = [dump[1].clone(), unmetered];
+        assert_eq!(
+            default_gateway_in(&dump),
+            Some(("203.0.113.1".parse().unwrap(), 4))
+        );
+    }
+
+    #[test]
+    fn routes_that_are_no_default_gateway_are_skipped() {
+        let oif = 2u32.to_ne_bytes();
+        let gateway: &[u8] = &[192, 0, 2, 1];
+        let dump = [
+            // `blackhole default`.
+            route_entry(6, 0, &[]),
+            // `default dev ppp0`, without a gateway.
+            route_entry(RTN_UNICAST, 0, &[(RTA_OIF, &oif)]),
+            // A default route of another table.
+            route_entry(
+                RTN_UNICAST,
+                0,
+                &[
+                    (RTA_GATEWAY, gateway),
+                    (RTA_OIF, &oif),
+                    (RTA_TABLE, &100u32.to_ne_bytes()),
+                ],
+            ),
+            // A gateway without a link.
+            route_entry(RTN_UNICAST, 0, &[(RTA_GATEWAY, gateway)]),
+            // Too short for a header.
+            vec![0; 4],
+        ];
+        assert_eq!(default_gateway_in(&dump), None);
+        assert_eq!(default_gateway_in(&[]), None);
+    }
+
+    #[test]
+    fn ipv6_gateways_are_read() {
+        let gateway: Ipv6Addr = "fe80::1".parse().unwrap();
+        let dump = [route_entry(
+            RTN_UNICAST,
+            0,
+            &[
+                (RTA_GATEWAY, &gateway.octets()),
+                (RTA_OIF, &7u32.to_ne_bytes()),
+                (RTA_PRIORITY, &1024u32.to_ne_bytes()),
+            ],
+        )];
+        assert_eq!(default_gateway_in(&dump), Some((gateway.into(), 7)));
+    }
+
+    fn neighbor_entry(index: i32, state: u16, dst: &[u8], lladdr: Option<&[u8]>) -> Vec<u8> {
+        payload(|message| {
+            message.push(&NdMsg {
+                family: libc::AF_INET as u8,
+                index,
+                state,
+                ..NdMsg::default()
+            });
+            message.attr(NDA_DST, dst);
+            if let Some(lladdr) = lladdr {
+                message.attr(NDA_LLADDR, lladdr);
+            }
+        })
+    }
+
+    #[test]
+    fn resolved_neighbors_give_their_mac() {
+        const NUD_REACHABLE: u16 = 0x02;
+        const NUD_STALE: u16 = 0x04;
+        let gateway: IpAddr = "192.0.2.1".parse().unwrap();
+        let mac = [0x02, 0, 0x5e, 0x10, 0, 1];
+        let dump = [
+            // The same address on another link.
+            neighbor_entry(3, NUD_REACHABLE, &[192, 0, 2, 1], Some(&[2; 6])),
+            // Another neighbor of the link.
+            neighbor_entry(2, NUD_REACHABLE, &[192, 0, 2, 7], Some(&[7; 6])),
+            neighbor_entry(2, NUD_STALE, &[192, 0, 2, 1], Some(&mac)),
+        ];
+        assert_eq!(neighbor_mac_in(&dump, 2, &gateway), Some(MacAddr::new(mac)));
+        assert_eq!(neighbor_mac_in(&dump, 4, &gateway), None);
+    }
+
+    #[test]
+    fn unresolved_neighbors_have_no_mac() {
+        let gateway: IpAddr = "192.0.2.1".parse().unwrap();
+        let dst = [192, 0, 2, 1];
+        let dump = [
+            neighbor_entry(2, NUD_INCOMPLETE, &dst, None),
+            // The kernel keeps the last address of a failed entry.
+            neighbor_entry(2, NUD_FAILED, &dst, Some(&[1; 6])),
+            // NUD_NOARP, without a link-layer address.
+            neighbor_entry(2, 0x40, &dst, None),
+        ];
+        assert_eq!(neighbor_mac_in(&dump, 2, &gateway), None);
+    }
+
+    #[test]
+    fn the_default_gateway_is_on_a_link_of_this_machine() {
+        let interfaces = crate::ifaces().unwrap();
+        for family in [libc::AF_INET as u8, libc::AF_INET6 as u8] {
+            if let Some((_, index)) = default_gateway(family).unwrap() {
+                assert!(interfaces.iter().any(|i| i.index == Some(index)));
+            }
+        }
+    }
+
+    #[test]
+    fn groups_are_read_from_their_attribute() {
+        let link = |attrs: &[(u16, &[u8])]| {
+            let payload = payload(|message| {
+                message.push(&IfInfoMsg {
+                    index: 9,
+                    ..IfInfoMsg::default()
+                });
+                message.attr(IFLA_IFNAME, b"dummy0\0");
+                for (ty, value) in attrs {
+                    message.attr(*ty, value);
+                }
+            });
+            parse_link(&payload).unwrap()
+        };
+        assert_eq!(link(&[(IFLA_GROUP, &7u32.to_ne_bytes())]).group, Some(7));
+        // The default group is a group, and no attribute is none.
+        assert_eq!(link(&[(IFLA_GROUP, &0u32.to_ne_bytes())]).group, Some(0));
+        assert_eq!(link(&[]).group, None);
+        assert_eq!(link(&[(IFLA_GROUP, &[7, 0])]).group, None);
+        for captured in [ETH0, MACVLAN, VETH] {
+            assert_eq!(parse_link(captured).unwrap().group, Some(0));
+        }
+    }
+
+    /// A rule of `table`, looked up for marks `fwmark` under `fwmask`.
+    fn rule_entry(table: u32, mark: Option<(u32, u32)>) -> Vec<u8> {
+        payload(|message| {
+            message.push(&RtMsg {
+                family: libc::AF_INET as u8,
+                // Tables past 255 only fit the attribute, the header has
+                // RT_TABLE_COMPAT.
+                table: u8::try_from(table).unwrap_or(252),
+                ..RtMsg::default()
+            });
+            message.attr(FRA_TABLE, &table.to_ne_bytes());
+            if let Some((fwmark, fwmask)) = mark {
+                message.attr(FRA_FWMARK, &fwmark.to_ne_bytes());
+                message.attr(FRA_FWMASK, &fwmask.to_ne_bytes());
+            }
+        })
+    }
+
+    #[test]
+    fn rules_are_read_from_their_attributes() {
+        let dump = [
+            rule_entry(RT_TABLE_MAIN as u32, None),
+            rule_entry(1030, Some((0x10064, 0x1ffff))),
+            // The table in the header alone, as older kernels send it.
+            payload(|message| {
+                message.push(&RtMsg {
+                    table: 97,
+                    ..RtMsg::default()
+                });
+            }),
+            vec![0; 4],
+        ];
+        assert_eq!(
+            rules_in(&dump),
+            [
+                Rule {
+                    table: 254,
+                    fwmark: None,
+                    fwmask: None,
+                },
+                Rule {
+                    table: 1030,
+                    fwmark: Some(0x10064),
+                    fwmask: Some(0x1ffff),
+                },
+                Rule {
+                    table: 97,
+                    fwmark: None,
+                    fwmask: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn the_rules_of_this_machine_look_up_main() {
+        let rules = rules(libc::AF_INET as u8).unwrap();
+        assert!(rules
+            .iter()
+            .any(|rule| rule.table == RT_TABLE_MAIN as u32 && rule.fwmark.is_none()));
+    }
+
+    #[test]
+    fn ipv6_tokens_are_set_in_the_inet6_spec() {
+        let token: Ipv6Addr = "::1:2".parse().unwrap();
+        let message = ipv6_token_message(7, &token);
+        let (header, ifi, attrs) = decode::<IfInfoMsg>(&message);
+        assert_eq!(header.ty, RTM_SETLINK);
+        assert_eq!(header.flags, NLM_F_REQUEST | NLM_F_ACK);
+        assert_eq!(ifi.index, 7);
+        assert_eq!(attrs.len(), 1);
+        assert_eq!(attrs[0].0, IFLA_AF_SPEC);
+        let families: Vec<(u16, &[u8])> = Attrs::new(&attrs[0].1).collect();
+        assert_eq!(families.len(), 1);
+        assert_eq!(families[0].0, libc::AF_INET6 as u16);
+        let inet6: Vec<(u16, &[u8])> = Attrs::new(families[0].1).collect();
+        assert_eq!(inet6, [(IFLA_INET6_TOKEN, &token.octets()[..])]);
+        // The spec is nested, as the kernel wants it.
+        let spec = mem::size_of::<NlMsgHdr>() + mem::size_of::<IfInfoMsg>();
+        let spec_type = read::<u16>(&message.buf[spec + 2..]).unwrap();
+        assert_eq!(spec_type, IFLA_AF_SPEC | NLA_F_NESTED);
+    }
+
+    #[test]
+    fn ipv6_tokens_are_read_from_the_inet6_spec() {
+        let token: Ipv6Addr = "::1:2".parse().unwrap();
+        let message = ipv6_token_message(7, &token);
+        let link = parse_link(&message.buf[mem::size_of::<NlMsgHdr>()..]).unwrap();
+        assert_eq!(link.ipv6_token, Some(token));
+        // Links with IPv6 have the unspecified token when none is set.
+        assert_eq!(
+            parse_link(ETH0).unwrap().ipv6_token,
+            Some(Ipv6Addr::UNSPECIFIED)
+        );
+        // A spec of AF_INET only is no token.
+        let payload = payload(|message| {
+            message.push(&IfInfoMsg::default());
+            message.nested(IFLA_AF_SPEC, |spec| {
+                spec.nested(libc::AF_INET as u16, |inet| {
+                    inet.attr(IFLA_INET6_TOKEN, &token.octets());
+                });
+            });
+        });
+        assert_eq!(parse_link(&payload).unwrap().ipv6_token, None);
+    }
+
+    /// A `kind` link as the kernel dumps it, with `data` in its
+    /// `IFLA_INFO_DATA`.
+    fn tunnel_link(kind: &str, data: &[(u16, &[u8])]) -> Link {
+        let payload = payload(|message| {
+            message.push(&IfInfoMsg {
+                index: 9,
+                ..IfInfoMsg::default()
+            });
+            message.attr(IFLA_IFNAME, b"tun0\0");
+            message.nested(IFLA_LINKINFO, |info| {
+                info.attr(IFLA_INFO_KIND, kind.as_bytes());
+                info.nested(IFLA_INFO_DATA, |nested| {
+                    for (ty, value) in data {
+                        nested.attr(*ty, value);
+                    }
+                });
+            });
+        });
+        parse_link(&payload).unwrap()
+    }
+
+    fn ip(ip: &str) -> Option<IpAddr> {
+        Some(ip.parse().unwrap())
+    }
+
+    // Attributes the kernel sends besides the endpoints, which are skipped.
+    const IFLA_GRE_LINK: u16 = 1;
+    const IFLA_GRE_IFLAGS: u16 = 2;
+    const IFLA_GRE_TTL: u16 = 8;
+    const IFLA_IPTUN_TTL: u16 = 4;
+    const IFLA_VXLAN_PORT: u16 = 15;
+
+    #[test]
+    fn gre_endpoints_are_read() {
+        let link = tunnel_link(
+            "gre",
+            &[
+                (IFLA_GRE_LINK, &0u32.to_ne_bytes()),
+                (IFLA_GRE_IFLAGS, &0u16.to_ne_bytes()),
+                (IFLA_GRE_LOCAL, &[192, 0, 2, 1]),
+                (IFLA_GRE_REMOTE, &[198, 51, 100, 7]),
+                (IFLA_GRE_TTL, &[64]),
+            ],
+        );
+        assert_eq!(link.kind.as_deref(), Some("gre"));
+        assert_eq!(
+            link.tunnel,
+            Some(TunnelEndpoints {
+                local: ip("192.0.2.1"),
+                remote: ip("198.51.100.7"),
+                vni: None,
+            })
+        );
+    }
+
+    #[test]
+    fn nbma_gre_tunnels_have_no_remote() {
+        let link = tunnel_link(
+            "gre",
+            &[
+                (IFLA_GRE_LOCAL, &[192, 0, 2, 1]),
+                (IFLA_GRE_REMOTE, &[0, 0, 0, 0]),
+            ],
+        );
+        let tunnel = link.tunnel.unwrap();
+        assert_eq!((tunnel.local, tunnel.remote), (ip("192.0.2.1"), None));
+        // Nor a local address when bound to none.
+        let link = tunnel_link(
+            "gre",
+            &[
+                (IFLA_GRE_LOCAL, &[0, 0, 0, 0]),
+                (IFLA_GRE_REMOTE, &[0, 0, 0, 0]),
+            ],
+        );
+        assert_eq!(link.tunnel, Some(TunnelEndpoints::default()));
+    }
+
+    #[test]
+    fn gretap_endpoints_are_read() {
+        let link = tunnel_link(
+            "gretap",
+            &[
+                (IFLA_GRE_LOCAL, &[10, 0, 0, 1]),
+                (IFLA_GRE_REMOTE, &[10, 0, 0, 2]),
+            ],
+        );
+        let tunnel = link.tunnel.unwrap();
+        assert_eq!(
+            (tunnel.local, tunnel.remote),
+            (ip("10.0.0.1"), ip("10.0.0.2"))
+        );
+    }
+
+    #[test]
+    fn ip6gre_endpoints_are_16_bytes() {
+        let local = "2001:db8::1".parse::<Ipv6Addr>().unwrap().octets();
+        let remote = "2001:db8:ffff::2".parse::<Ipv6Addr>().unwrap().octets();
+        let link = tunnel_link(
+            "ip6gre",
+            &[(IFLA_GRE_LOCAL, &local), (IFLA_GRE_REMOTE, &remote)],
+        );
+        let tunnel = link.tunnel.unwrap();
+        assert_eq!(
+            (tunnel.local, tunnel.remote),
+            (ip("2001:db8::1"), ip("2001:db8:ffff::2"))
+        );
+        // The IPv6 wildcard is no endpoint either.
+        let link = tunnel_link(
+            "ip6gre",
+            &[(IFLA_GRE_LOCAL, &local), (IFLA_GRE_REMOTE, &[0; 16])],
+        );
+        assert_eq!(link.tunnel.unwrap().remote, None);
+    }
+
+    #[test]
+    fn ipip_and_sit_endpoints_are_read() {
+        for kind in ["ipip", "sit"] {
+            let link = tunnel_link(
+                kind,
+                &[
+                    (IFLA_IPTUN_LOCAL, &[192, 0, 2, 1]),
+                    (IFLA_IPTUN_REMOTE, &[203, 0, 113, 9]),
+                    (IFLA_IPTUN_TTL, &[0]),
+                ],
+            );
+            assert_eq!(
+                link.tunnel,
+                Some(TunnelEndpoints {
+                    local: ip("192.0.2.1"),
+                    remote: ip("203.0.113.9"),
+                    vni: None,
+                }),
+                "{}",
+                kind
+            );
+        }
+        // The GRE numbering means something else to them.
+        let link = tunnel_link("sit", &[(IFLA_GRE_REMOTE, &[203, 0, 113, 9])]);
+        assert_eq!(link.tunnel, Some(TunnelEndpoints::default()));
+    }
+
+    #[test]
+    fn vxlan_devices_have_their_vni_and_group() {
+        let link = tunnel_link(
+            "vxlan",
+            &[
+                (IFLA_VXLAN_ID, &42u32.to_ne_bytes()),
+                (IFLA_VXLAN_GROUP, &[239, 1, 1, 1]),
+                (IFLA_VXLAN_LOCAL, &[192, 0, 2, 1]),
+                (IFLA_VXLAN_PORT, &4789u16.to_be_bytes()),
+            ],
+        );
+        assert_eq!(
+            link.tunnel,
+            Some(TunnelEndpoints {
+                local: ip("192.0.2.1"),
+                remote: ip("239.1.1.1"),
+                vni: Some(42),
+            })
+        );
+    }
+
+    #[test]
+    fn ipv6_vxlan_groups_come_from_group6() {
+        let group6 = "ff05::100".parse::<Ipv6Addr>().unwrap().octets();
+        let local6 = "2001:db8::1".parse::<Ipv6Addr>().unwrap().octets();
+        let link = tunnel_link(
+            "vxlan",
+            &[
+                (IFLA_VXLAN_ID, &7u32.to_ne_bytes()),
+                (IFLA_VXLAN_GROUP, &[0, 0, 0, 0]),
+                (IFLA_VXLAN_GROUP6, &group6),
+                (IFLA_VXLAN_LOCAL6, &local6),
+            ],
+        );
+        assert_eq!(
+            link.tunnel,
+            Some(TunnelEndpoints {
+                local: ip("2001:db8::1"),
+                remote: ip("ff05::100"),
+                vni: Some(7),
+            })
+        );
+        // A device that learns its peers has no group at all.
+        let link = tunnel_link("vxlan", &[(IFLA_VXLAN_ID, &7u32.to_ne_bytes())]);
+        assert_eq!(link.tunnel.unwrap().remote, None);
+    }
+
+    #[test]
+    fn other_kinds_are_no_tunnels() {
+        let data = payload(|message| {
+            message.attr(IFLA_GRE_LOCAL, &[192, 0, 2, 1]);
+        });
+        assert_eq!(parse_tunnel("macvlan", &data), None);
+        assert_eq!(parse_tunnel("bridge", &[]), None);
+        assert_eq!(parse_tunnel("gre", &data).unwrap().local, ip("192.0.2.1"));
+        let link = tunnel_link("macvlan", &[(IFLA_GRE_LOCAL, &[192, 0, 2, 1])]);
+        assert_eq!(link.tunnel, None);
+        // Links without a kind or data are none either.
+        assert_eq!(parse_link(LO).unwrap().tunnel, None);
+    }
+}
@@ -0,0 +1,368 @@
+//! What NetworkManager thinks of the interfaces, over D-Bus.
+//!
+//! Everything here gives `None` when NetworkManager, or the system bus, is
+//! not there; most machines without a desktop have neither.
+
+use log::debug;
+use zbus::blocking::Connection;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+const SERVICE: &str = "org.freedesktop.NetworkManager";
+const PATH: &str = "/org/freedesktop/NetworkManager";
+const DEVICE: &str = "org.freedesktop.NetworkManager.Device";
+const ACTIVE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+
+/// The `NMDeviceState` of a device.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum NmDeviceState {
+    Unknown,
+    /// NetworkManager leaves the device alone.
+    Unmanaged,
+    /// Managed, but not ready for a connection, e.g. without carrier.
+    Unavailable,
+    Disconnected,
+    Prepare,
+    Config,
+    NeedAuth,
+    IpConfig,
+    IpCheck,
+    Secondaries,
+    Activated,
+    Deactivating,
+    Failed,
+}
+
+impl NmDeviceState {
+    fn from_raw(state: u32) -> NmDeviceState {
+        match state {
+            10 => NmDeviceState::Unmanaged,
+            20 => NmDeviceState::Unavailable,
+            30 => NmDeviceState::Disconnected,
+            40 => NmDeviceState::Prepare,
+            50 => NmDeviceState::Config,
+            60 => NmDeviceState::NeedAuth,
+            70 => NmDeviceState::IpConfig,
+            80 => NmDeviceState::IpCheck,
+            90 => NmDeviceState::Secondaries,
+            100 => NmDeviceState::Activated,
+            110 => NmDeviceState::Deactivating,
+            120 => NmDeviceState::Failed,
+            _ => NmDeviceState::Unknown,
+        }
+    }
+}
+
+/// A NetworkManager device, see [`nm_device`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct NmDevice {
+    pub state: NmDeviceState,
+    /// The id of the connection active on the device, as `nmcli` shows it.
+    pub connection_id: Option<String>,
+}
+
+/// The `NMConnectivityState` of the machine, see [`nm_connectivity`].
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Connectivity {
+    /// Not checked, or checking is switched off.
+    Unknown,
+    None,
+    /// Behind a captive portal.
+    Portal,
+    /// Connected, but the internet is out of reach.
+    Limited,
+    Full,
+}
+
+impl Connectivity {
+    fn from_raw(state: u32) -> Connectivity {
+        match state {
+            1 => Connectivity::None,
+            2 => Connectivity::Portal,
+            3 => Connectivity::Limited,
+            4 => Connectivity::Full,
+            _ => Connectivity::Unknown,
+        }
+    }
+}
+
+/// A property as the mapping below needs it. Object paths come as `Str`.
+#[derive(PartialEq, Eq, Debug, Clone)]
+enum Property {
+    U32(u32),
+    Str(String),
+}
+
+/// The calls the mapping makes, so that it can run against something other
+/// than the system bus.
+trait Bus {
+    /// The object path of the device behind interface `name`.
+    fn device_path(&self, name: &str) -> Option<String>;
+    /// Property `name` of `interface` on the object at `path`.
+    fn property(&self, path: &str, interface: &str, name: &str) -> Option<Property>;
+}
+
+struct SystemBus(Connection);
+
+impl SystemBus {
+    fn connect() -> Option<SystemBus> {
+        match Connection::system() {
+            Ok(connection) => Some(SystemBus(connection)),
+            Err(err) => {
+                debug!("no system bus: {}", err);
+                None
+            }
+        }
+    }
+}
+
+impl Bus for SystemBus {
+    fn device_path(&self, name: &str) -> Option<String> {
+        let reply = self
+            .0
+            .call_method(
+                Some(SERVICE),
+                PATH,
+                Some(SERVICE),
+                "GetDeviceByIpIface",
+                &name,
+            )
+            .map_err(|err| debug!("GetDeviceByIpIface({}) failed: {}", name, err))
+            .ok()?;
+        let path: OwnedObjectPath = reply.body().deserialize().ok()?;
+        Some(path.as_str().to_owned())
+    }
+
+    fn property(&self, path: &str, interface: &str, name: &str) -> Option<Property> {
+        let reply = self
+            .0
+            .call_method(
+                Some(SERVICE),
+                path,
+                Some("org.freedesktop.DBus.Properties"),
+                "Get",
+                &(interface, name),
+            )
+            .map_err(|err| debug!("reading {}.{} failed: {}", interface, name, err))
+            .ok()?;
+        let value: OwnedValue = reply.body().deserialize().ok()?;
+        match &*value {
+            Value::U32(value) => Some(Property::U32(*value)),
+            Value::Str(value) => Some(Property::Str(value.as_str().to_owned())),
+            Value::ObjectPath(value) => Some(Property::Str(value.as_str().to_owned())),
+            _ => None,
+        }
+    }
+}
+
+/// NetworkManager's view of interface `name`, or `None` if it does not know
+/// the interface or is not running.
+pub fn nm_device(name: &str) -> Option<NmDevice> {
+    let name = crate::validate_name(name).ok()?;
+    device_of(&SystemBus::connect()?, name.as_str())
+}
+
+/// Whether NetworkManager counts traffic over interface `name` as metered,
+/// or `None` if it does not know, does not know the interface or is not
+/// running.
+pub(crate) fn nm_metered(name: &str) -> Option<bool> {
+    let name = crate::validate_name(name).ok()?;
+    metered_of(&SystemBus::connect()?, name.as_str())
+}
+
+/// Whether NetworkManager found this machine to be online, or `None` if it
+/// is not running.
+pub fn nm_connectivity() -> Option<Connectivity> {
+    connectivity_of(&SystemBus::connect()?)
+}
+
+fn device_of(bus: &impl Bus, name: &str) -> Option<NmDevice> {
+    let path = bus.device_path(name)?;
+    let state = match bus.property(&path, DEVICE, "State")? {
+        Property::U32(state) => NmDeviceState::from_raw(state),
+        Property::Str(_) => return None,
+    };
+    // "/" when nothing is active.
+    let connection_id = match bus.property(&path, DEVICE, "ActiveConnection") {
+        Some(Property::Str(active)) if active != "/" => match bus.property(&active, ACTIVE, "Id") {
+            Some(Property::Str(id)) => Some(id),
+            _ => None,
+        },
+        _ => None,
+    };
+    Some(NmDevice {
+        state,
+        connection_id,
+    })
+}
+
+fn metered_of(bus: &impl Bus, name: &str) -> Option<bool> {
+    let path = bus.device_path(name)?;
+    match bus.property(&path, DEVICE, "Metered")? {
+        Property::U32(metered) => metered_from_raw(metered),
+        Property::Str(_) => None,
+    }
+}
+
+/// Map an `NMMetered`: unknown, yes, no, guess-yes or guess-no. Guesses
+/// count, NetworkManager makes them from the device type and DHCP hints.
+fn metered_from_raw(metered: u32) -> Option<bool> {
+    match metered {
+        1 | 3 => Some(true),
+        2 | 4 => Some(false),
+        _ => None,
+    }
+}
+
+fn connectivity_of(bus: &impl Bus) -> Option<Connectivity> {
+    match bus.property(PATH, SERVICE, "Connectivity")? {
+        Property::U32(state) => Some(Connectivity::from_raw(state)),
+        Property::Str(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    const ETH0: &str = "/org/freedesktop/NetworkManager/Devices/2";
+    const WIRED: &str = "/org/freedesktop/NetworkManager/ActiveConnection/1";
+
+    /// A bus answering from tables, later properties replacing earlier
+    /// ones.
+    #[derive(Default)]
+    struct FakeBus {
+        devices: HashMap<&'static str, &'static str>,
+        properties: Vec<((&'static str, &'static str, &'static str), Property)>,
+    }
+
+    impl FakeBus {
+        fn with(
+            mut self,
+            path: &'static str,
+            interface: &'static str,
+            name: &'static str,
+            value: Property,
+        ) -> Self {
+            self.properties.push(((path, interface, name), value));
+            self
+        }
+
+        /// eth0, in `state`, with the connection at `active`.
+        fn eth0(state: u32, active: &'static str) -> FakeBus {
+            let mut bus = FakeBus::default()
+                .with(ETH0, DEVICE, "State", Property::U32(state))
+                .with(
+                    ETH0,
+                    DEVICE,
+                    "ActiveConnection",
+                    Property::Str(active.to_owned()),
+                )
+                .with(
+                    WIRED,
+                    ACTIVE,
+                    "Id",
+                    Property::Str("Wired connection 1".to_owned()),
+                );
+            bus.devices.insert("eth0", ETH0);
+            bus
+        }
+    }
+
+    impl Bus for FakeBus {
+        fn device_path(&self, name: &str) -> Option<String> {
+            self.devices.get(name).map(|path| path.to_string())
+        }
+
+        fn property(&self, path: &str, interface: &str, name: &str) -> Option<Property> {
+            self.properties
+                .iter()
+                .rev()
+                .find(|((p, i, n), _)| (*p, *i, *n) == (path, interface, name))
+                .map(|(_, value)| value.clone())
+        }
+    }
+
+    #[test]
+    fn device_states_map_to_their_values() {
+        let states = [
+            (0, NmDeviceState::Unknown),
+            (10, NmDeviceState::Unmanaged),
+            (20, NmDeviceState::Unavailable),
+            (30, NmDeviceState::Disconnected),
+            (70, NmDeviceState::IpConfig),
+            (100, NmDeviceState::Activated),
+            (120, NmDeviceState::Failed),
+            (15, NmDeviceState::Unknown),
+        ];
+        for (raw, state) in states {
+            assert_eq!(NmDeviceState::from_raw(raw), state, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn active_devices_have_their_connection() {
+        let device = device_of(&FakeBus::eth0(100, WIRED), "eth0").unwrap();
+        assert_eq!(device.state, NmDeviceState::Activated);
+        assert_eq!(device.connection_id.as_deref(), Some("Wired connection 1"));
+    }
+
+    #[test]
+    fn unmanaged_devices_have_no_connection() {
+        let device = device_of(&FakeBus::eth0(10, "/"), "eth0").unwrap();
+        assert_eq!(
+            device,
+            NmDevice {
+                state: NmDeviceState::Unmanaged,
+                connection_id: None,
+            }
+        );
+        // An active connection that went away meanwhile.
+        let bus = FakeBus::eth0(100, "/org/freedesktop/NetworkManager/ActiveConnection/9");
+        assert_eq!(device_of(&bus, "eth0").unwrap().connection_id, None);
+    }
+
+    #[test]
+    fn unknown_devices_and_odd_replies_are_none() {
+        let bus = FakeBus::eth0(100, WIRED);
+        assert_eq!(device_of(&bus, "wlan0"), None);
+        let bus = bus.with(ETH0, DEVICE, "State", Property::Str("activated".to_owned()));
+        assert_eq!(device_of(&bus, "eth0"), None);
+    }
+
+    #[test]
+    fn connectivity_maps_to_its_values() {
+        let connectivity = |raw| {
+            let bus = FakeBus::default().with(PATH, SERVICE, "Connectivity", Property::U32(raw));
+            connectivity_of(&bus)
+        };
+        assert_eq!(connectivity(0), Some(Connectivity::Unknown));
+        assert_eq!(connectivity(1), Some(Connectivity::None));
+        assert_eq!(connectivity(2), Some(Connectivity::Portal));
+        assert_eq!(connectivity(3), Some(Connectivity::Limited));
+        assert_eq!(connectivity(4), Some(Connectivity::Full));
+        assert_eq!(connectivity_of(&FakeBus::default()), None);
+    }
+
+    #[test]
+    fn guesses_count_as_metered() {
+        let metered = |raw| {
+            let bus = FakeBus::eth0(100, WIRED).with(ETH0, DEVICE, "Metered", Property::U32(raw));
+            metered_of(&bus, "eth0")
+        };
+        assert_eq!(metered(0), None);
+        assert_eq!(metered(1), Some(true));
+        assert_eq!(metered(2), Some(false));
+        assert_eq!(metered(3), Some(true));
+        assert_eq!(metered(4), Some(false));
+        assert_eq!(metered_of(&FakeBus::eth0(100, WIRED), "eth0"), None);
+    }
+
+    #[test]
+    fn bad_names_are_none() {
+        assert_eq!(nm_device(""), None);
+        assert_eq!(nm_device("nosuchif0"), None);
+        assert_eq!(nm_metered(""), None);
+    }
+}
@@ -0,0 +1,204 @@
+//! `IP_PKTINFO` and `IPV6_PKTINFO` control messages, read with `recvmsg`.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket};
+use std::os::unix::io::AsRawFd;
+use std::{io, mem, ptr};
+
+use super::socket::socket_addr;
+use crate::pktinfo::Received;
+
+/// Room for one `in6_pktinfo` message, or one `in_pktinfo` message, and
+/// aligned for `cmsghdr`.
+type ControlBuffer = [u64; 8];
+
+pub fn enable_pktinfo(socket: &UdpSocket, v6: bool) -> io::Result<()> {
+    let (level, option) = match v6 {
+        true => (libc::IPPROTO_IPV6, libc::IPV6_RECVPKTINFO),
+        false => (libc::IPPROTO_IP, libc::IP_PKTINFO),
+    };
+    let on: libc::c_int = 1;
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            option,
+            &on as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    match ret {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Receive a datagram with its packet info, `None` if it came without.
+pub fn recv_pktinfo(socket: &UdpSocket, buf: &mut [u8]) -> io::Result<Received> {
+    let mut from: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let mut control: ControlBuffer = [0; 8];
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_name = &mut from as *mut _ as *mut libc::c_void;
+    msg.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = mem::size_of::<ControlBuffer>() as _;
+
+    let len = match unsafe { libc::recvmsg(socket.as_raw_fd(), &mut msg, 0) } {
+        -1 => return Err(io::Error::last_os_error()),
+        len => len as usize,
+    };
+    let from = socket_addr(&from).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the sender address is not IPv4 or IPv6",
+        )
+    })?;
+    Ok((len, from, unsafe { parse_control(&msg) }))
+}
+
+/// The interface index and destination address in the control messages
+/// of `msg`, from the first packet info message.
+///
+/// # Safety
+///
+/// `msg_control` and `msg_controllen` have to describe a buffer the
+/// kernel filled in, or one laid out the same way.
+unsafe fn parse_control(msg: &libc::msghdr) -> Option<(u32, IpAddr)> {
+    let mut cmsg = libc::CMSG_FIRSTHDR(msg);
+    while !cmsg.is_null() {
+        let data = libc::CMSG_DATA(cmsg);
+        match ((*cmsg).cmsg_level, (*cmsg).cmsg_type) {
+            (libc::IPPROTO_IP, libc::IP_PKTINFO) => {
+                let info = ptr::read_unaligned(data as *const libc::in_pktinfo);
+                let destination = Ipv4Addr::from(u32::from_be(info.ipi_addr.s_addr));
+                return Some((info.ipi_ifindex as _, destination.into()));
+            }
+            (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO) => {
+                let info = ptr::read_unaligned(data as *const libc::in6_pktinfo);
+                let destination = Ipv6Addr::from(info.ipi6_addr.s6_addr);
+                return Some((info.ipi6_ifindex as _, destination.into()));
+            }
+            _ => {}
+        }
+        cmsg = libc::CMSG_NXTHDR(msg, cmsg);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A control buffer with `messages` laid out as the kernel does, and
+    /// a `msghdr` over it.
+    struct Control {
+        _buffer: Box<ControlBuffer>,
+        msg: libc::msghdr,
+    }
+
+    impl Control {
+        fn new(messages: &[(libc::c_int, libc::c_int, &[u8])]) -> Control {
+            let mut buffer = Box::new([0; 8]);
+            let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+            msg.msg_control = buffer.as_mut_ptr() as *mut libc::c_void;
+            msg.msg_controllen = messages
+                .iter()
+                .map(|(_, _, data)| unsafe { libc::CMSG_SPACE(data.len() as _) } as usize)
+                .sum::<usize>() as _;
+            assert!(msg.msg_controllen as usize <= mem::size_of::<ControlBuffer>());
+            unsafe {
+                let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+                for (level, ty, data) in messages {
+                    (*cmsg).cmsg_level = *level;
+                    (*cmsg).cmsg_type = *ty;
+                    (*cmsg).cmsg_len = libc::CMSG_LEN(data.len() as _) as _;
+                    ptr::copy_nonoverlapping(data.as_ptr(), libc::CMSG_DATA(cmsg), data.len());
+                    cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+                }
+            }
+            Control {
+                _buffer: buffer,
+                msg,
+            }
+        }
+
+        fn parse(&self) -> Option<(u32, IpAddr)> {
+            unsafe { parse_control(&self.msg) }
+        }
+    }
+
+    fn bytes_of<T>(value: &T) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) }
+    }
+
+    fn in_pktinfo(index: libc::c_int, destination: [u8; 4]) -> libc::in_pktinfo {
+        let mut info: libc::in_pktinfo = unsafe { mem::zeroed() };
+        info.ipi_ifindex = index as _;
+        info.ipi_addr.s_addr = u32::from_ne_bytes(destination);
+        info
+    }
+
+    fn in6_pktinfo(index: libc::c_uint, destination: Ipv6Addr) -> libc::in6_pktinfo {
+        let mut info: libc::in6_pktinfo = unsafe { mem::zeroed() };
+        info.ipi6_ifindex = index as _;
+        info.ipi6_addr.s6_addr = destination.octets();
+        info
+    }
+
+    #[test]
+    fn ipv4_packet_info_is_read() {
+        let info = in_pktinfo(3, [192, 0, 2, 1]);
+        let control = Control::new(&[(libc::IPPROTO_IP, libc::IP_PKTINFO, bytes_of(&info))]);
+        assert_eq!(control.parse(), Some((3, IpAddr::from([192, 0, 2, 1]))));
+    }
+
+    #[test]
+    fn ipv6_packet_info_is_read() {
+        let destination: Ipv6Addr = "ff02::fb".parse().unwrap();
+        let info = in6_pktinfo(7, destination);
+        let control = Control::new(&[(libc::IPPROTO_IPV6, libc::IPV6_PKTINFO, bytes_of(&info))]);
+        assert_eq!(control.parse(), Some((7, destination.into())));
+    }
+
+    #[test]
+    fn other_messages_are_skipped() {
+        let ttl: libc::c_int = 64;
+        let info = in_pktinfo(2, [255, 255, 255, 255]);
+        let control = Control::new(&[
+            (libc::IPPROTO_IP, libc::IP_TTL, bytes_of(&ttl)),
+            (libc::IPPROTO_IP, libc::IP_PKTINFO, bytes_of(&info)),
+        ]);
+        assert_eq!(
+            control.parse(),
+            Some((2, IpAddr::from([255, 255, 255, 255])))
+        );
+    }
+
+    #[test]
+    fn no_packet_info_is_none() {
+        assert_eq!(Control::new(&[]).parse(), None);
+        let ttl: libc::c_int = 64;
+        let control = Control::new(&[(libc::IPPROTO_IP, libc::IP_TTL, bytes_of(&ttl))]);
+        assert_eq!(control.parse(), None);
+        // Right type, wrong level.
+        let info = in_pktinfo(2, [192, 0, 2, 1]);
+        let control = Control::new(&[(libc::SOL_SOCKET, libc::IP_PKTINFO, bytes_of(&info))]);
+        assert_eq!(control.parse(), None);
+    }
+
+    #[test]
+    fn the_first_packet_info_wins() {
+        let first = in_pktinfo(2, [192, 0, 2, 1]);
+        let second = in_pktinfo(5, [198, 51, 100, 1]);
+        let control = Control::new(&[
+            (libc::IPPROTO_IP, libc::IP_PKTINFO, bytes_of(&first)),
+            (libc::IPPROTO_IP, libc::IP_PKTINFO, bytes_of(&second)),
+        ]);
+        assert_eq!(control.parse(), Some((2, IpAddr::from([192, 0, 2, 1]))));
+    }
+}
@@ -0,0 +1,55 @@
+//! PTR names with `getnameinfo`.
+
+use std::ffi::CStr;
+use std::mem;
+use std::net::IpAddr;
+
+/// `NI_MAXHOST`, which not every libc target defines.
+const MAX_HOST: usize = 1025;
+
+/// The PTR name of `addr`, `None` if it has none or the lookup fails.
+pub fn ptr_name(addr: IpAddr) -> Option<String> {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let len = match addr {
+        IpAddr::V4(addr) => {
+            let sin = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in) };
+            sin.sin_family = libc::AF_INET as libc::sa_family_t;
+            sin.sin_addr.s_addr = u32::from(addr).to_be();
+            mem::size_of::<libc::sockaddr_in>()
+        }
+        IpAddr::V6(addr) => {
+            let sin6 = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6) };
+            sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sin6.sin6_addr.s6_addr = addr.octets();
+            mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ))]
+    {
+        storage.ss_len = len as u8;
+    }
+
+    let mut host = [0 as libc::c_char; MAX_HOST];
+    let ret = unsafe {
+        libc::getnameinfo(
+            &storage as *const _ as *const libc::sockaddr,
+            len as libc::socklen_t,
+            host.as_mut_ptr(),
+            host.len() as _,
+            std::ptr::null_mut(),
+            0,
+            libc::NI_NAMEREQD,
+        )
+    };
+    if ret != 0 {
+        return None;
+    }
+    let host = unsafe { CStr::from_ptr(host.as_ptr()) };
+    host.to_str().ok().map(str::to_owned)
+}
@@ -0,0 +1,413 @@
+//! Changing routes through a `PF_ROUTE` socket.
+
+use std::io;
+use std::mem;
+use std::net::IpAddr;
+use std::os::raw::{c_int, c_ushort};
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+
+use super::sockaddr_dl;
+use crate::{MacAddr, Route};
+
+pub const RTM_ADD: u8 = 0x1;
+pub const RTM_DELETE: u8 = 0x2;
+
+const RTF_UP: c_int = 0x1;
+const RTF_GATEWAY: c_int = 0x2;
+const RTF_HOST: c_int = 0x4;
+/// `RTF_LLINFO` on macOS and OpenBSD, `RTF_LLDATA` on FreeBSD and NetBSD:
+/// the route is a link-layer (ARP or NDP) entry.
+const RTF_LLINFO: c_int = 0x400;
+const RTF_STATIC: c_int = 0x800;
+
+const RTA_DST: c_int = 0x1;
+const RTA_GATEWAY: c_int = 0x2;
+const RTA_NETMASK: c_int = 0x4;
+
+const IFT_ETHER: u8 = 0x6;
+
+#[cfg(target_os = "netbsd")]
+const RTM_VERSION: u8 = 4;
+#[cfg(not(target_os = "netbsd"))]
+const RTM_VERSION: u8 = 5;
+
+/// Addresses after the header are padded to this.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const SA_ALIGN: usize = 4;
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+const SA_ALIGN: usize = 8;
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct rt_msghdr {
+    rtm_msglen: c_ushort,
+    rtm_version: u8,
+    rtm_type: u8,
+    rtm_index: c_ushort,
+    rtm_flags: c_int,
+    rtm_addrs: c_int,
+    rtm_pid: i32,
+    rtm_seq: c_int,
+    rtm_errno: c_int,
+    rtm_use: c_int,
+    rtm_inits: u32,
+    rtm_rmx: [u32; 14],
+}
+
+#[cfg(target_os = "freebsd")]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct rt_msghdr {
+    rtm_msglen: c_ushort,
+    rtm_version: u8,
+    rtm_type: u8,
+    rtm_index: c_ushort,
+    _rtm_spare1: c_ushort,
+    rtm_flags: c_int,
+    rtm_addrs: c_int,
+    rtm_pid: i32,
+    rtm_seq: c_int,
+    rtm_errno: c_int,
+    rtm_fmask: c_int,
+    rtm_inits: std::os::raw::c_ulong,
+    rtm_rmx: [std::os::raw::c_ulong; 14],
+}
+
+#[cfg(target_os = "openbsd")]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct rt_msghdr {
+    rtm_msglen: c_ushort,
+    rtm_version: u8,
+    rtm_type: u8,
+    rtm_hdrlen: c_ushort,
+    rtm_index: c_ushort,
+    rtm_tableid: c_ushort,
+    rtm_priority: u8,
+    rtm_mpls: u8,
+    rtm_addrs: c_int,
+    rtm_flags: c_int,
+    rtm_fmask: c_int,
+    rtm_pid: i32,
+    rtm_seq: c_int,
+    rtm_errno: c_int,
+    rtm_inits: u32,
+    rtm_rmx: [u64; 7],
+}
+
+#[cfg(target_os = "netbsd")]
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct rt_msghdr {
+    rtm_msglen: c_ushort,
+    rtm_version: u8,
+    rtm_type: u8,
+    rtm_index: c_ushort,
+    rtm_flags: c_int,
+    rtm_addrs: c_int,
+    rtm_pid: i32,
+    rtm_seq: c_int,
+    rtm_errno: c_int,
+    rtm_use: c_int,
+    rtm_inits: c_int,
+    rtm_rmx: [u64; 10],
+}
+
+fn push_padded(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(bytes);
+    buf.resize((buf.len() + SA_ALIGN - 1) & !(SA_ALIGN - 1), 0);
+}
+
+fn sockaddr_of(addr: &IpAddr) -> Vec<u8> {
+    match addr {
+        IpAddr::V4(addr) => {
+            let mut sa: libc::sockaddr_in = unsafe { mem::zeroed() };
+            sa.sin_len = mem::size_of::<libc::sockaddr_in>() as u8;
+            sa.sin_family = libc::AF_INET as libc::sa_family_t;
+            sa.sin_addr.s_addr = u32::from_ne_bytes(addr.octets());
+            bytes_of(&sa).to_vec()
+        }
+        IpAddr::V6(addr) => {
+            let mut sa: libc::sockaddr_in6 = unsafe { mem::zeroed() };
+            sa.sin6_len = mem::size_of::<libc::sockaddr_in6>() as u8;
+            sa.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sa.sin6_addr.s6_addr = addr.octets();
+            bytes_of(&sa).to_vec()
+        }
+    }
+}
+
+fn bytes_of<T>(value: &T) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) }
+}
+
+/// The netmask of a `prefix_len` network in the family of `addr`.
+fn netmask(addr: &IpAddr, prefix_len: u8) -> IpAddr {
+    match addr {
+        IpAddr::V4(_) => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+            IpAddr::from(mask.to_be_bytes())
+        }
+        IpAddr::V6(_) => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+            IpAddr::from(mask.to_be_bytes())
+        }
+    }
+}
+
+/// The `RTM_ADD` or `RTM_DELETE` message for `route`.
+///
+/// On-link routes name their interface with a link-level gateway address,
+/// as `route add -interface` does.
+pub fn route_message(ty: u8, route: &Route, seq: c_int) -> Vec<u8> {
+    let mut header = rt_msghdr {
+        rtm_version: RTM_VERSION,
+        rtm_type: ty,
+        rtm_index: route.ifindex.unwrap_or(0) as c_ushort,
+        rtm_flags: RTF_UP | RTF_STATIC,
+        rtm_addrs: RTA_DST | RTA_GATEWAY,
+        rtm_seq: seq,
+        ..rt_msghdr::default()
+    };
+    #[cfg(target_os = "openbsd")]
+    {
+        header.rtm_hdrlen = mem::size_of::<rt_msghdr>() as c_ushort;
+        header.rtm_tableid = route.table.unwrap_or(0) as c_ushort;
+    }
+    if route.gateway.is_some() {
+        header.rtm_flags |= RTF_GATEWAY;
+    }
+    if route.is_host() {
+        header.rtm_flags |= RTF_HOST;
+    } else {
+        header.rtm_addrs |= RTA_NETMASK;
+    }
+
+    let mut body = Vec::new();
+    push_padded(&mut body, &sockaddr_of(&route.destination));
+    match route.gateway {
+        Some(gateway) => push_padded(&mut body, &sockaddr_of(&gateway)),
+        None => {
+            let mut sdl: sockaddr_dl = unsafe { mem::zeroed() };
+            sdl.sdl_len = mem::size_of::<sockaddr_dl>() as u8;
+            sdl.sdl_family = libc::AF_LINK as u8;
+            sdl.sdl_index = route.ifindex.unwrap_or(0) as u16;
+            push_padded(&mut body, bytes_of(&sdl));
+        }
+    }
+    if !route.is_host() {
+        let mask = netmask(&route.destination, route.prefix_len);
+        push_padded(&mut body, &sockaddr_of(&mask));
+    }
+
+    header.rtm_msglen = (mem::size_of::<rt_msghdr>() + body.len()) as c_ushort;
+    let mut message = bytes_of(&header).to_vec();
+    message.extend_from_slice(&body);
+    message
+}
+
+/// The `RTM_ADD` message for a static neighbor entry, as `arp -s` and
+/// `ndp -s` send it, or the `RTM_DELETE` one without `mac`.
+pub fn neighbor_message(
+    ty: u8,
+    index: u32,
+    ip: &IpAddr,
+    mac: Option<MacAddr>,
+    seq: c_int,
+) -> Vec<u8> {
+    let mut header = rt_msghdr {
+        rtm_version: RTM_VERSION,
+        rtm_type: ty,
+        rtm_index: index as c_ushort,
+        rtm_flags: RTF_UP | RTF_HOST | RTF_STATIC | RTF_LLINFO,
+        rtm_addrs: RTA_DST,
+        rtm_seq: seq,
+        ..rt_msghdr::default()
+    };
+    #[cfg(target_os = "openbsd")]
+    {
+        header.rtm_hdrlen = mem::size_of::<rt_msghdr>() as c_ushort;
+    }
+
+    let mut body = Vec::new();
+    push_padded(&mut body, &sockaddr_of(ip));
+    if let Some(mac) = mac {
+        header.rtm_addrs |= RTA_GATEWAY;
+        let mut sdl: sockaddr_dl = unsafe { mem::zeroed() };
+        sdl.sdl_len = mem::size_of::<sockaddr_dl>() as u8;
+        sdl.sdl_family = libc::AF_LINK as u8;
+        sdl.sdl_index = index as u16;
+        sdl.sdl_type = IFT_ETHER;
+        sdl.sdl_alen = 6;
+        sdl.sdl_data[..6].copy_from_slice(&mac.octets());
+        push_padded(&mut body, bytes_of(&sdl));
+    }
+
+    header.rtm_msglen = (mem::size_of::<rt_msghdr>() + body.len()) as c_ushort;
+    let mut message = bytes_of(&header).to_vec();
+    message.extend_from_slice(&body);
+    message
+}
+
+/// Add or delete a route, as `ty` says.
+///
+/// The kernel checks the message as it is written, so a failed change
+/// comes back as the error of the `write`. Deleting a route that is not
+/// there fails with `ErrorKind::NotFound`.
+pub fn change_route(ty: u8, route: &Route) -> io::Result<()> {
+    send(&route_message(ty, route, 1))
+}
+
+/// Add or delete a neighbor entry, as `ty` says. Deleting one that is not
+/// there fails with `ErrorKind::NotFound`.
+pub fn change_neighbor(ty: u8, index: u32, ip: &IpAddr, mac: Option<MacAddr>) -> io::Result<()> {
+    send(&neighbor_message(ty, index, ip, mac, 1))
+}
+
+fn send(message: &[u8]) -> io::Result<()> {
+    let fd = unsafe { libc::socket(libc::PF_ROUTE, libc::SOCK_RAW, libc::AF_UNSPEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    let ret = unsafe {
+        libc::write(
+            socket.as_raw_fd(),
+            message.as_ptr() as *const libc::c_void,
+            message.len(),
+        )
+    };
+    if ret < 0 {
+        let err = io::Error::last_os_error();
+        return match err.raw_os_error() {
+            Some(libc::ESRCH) => Err(io::Error::new(io::ErrorKind::NotFound, err)),
+            _ => Err(err),
+        };
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The header of `message` and the addresses after it, as their family
+    /// and bytes.
+    fn decode(message: &[u8]) -> (rt_msghdr, Vec<(u8, Vec<u8>)>) {
+        let header: rt_msghdr =
+            unsafe { std::ptr::read_unaligned(message.as_ptr() as *const rt_msghdr) };
+        assert_eq!(header.rtm_msglen as usize, message.len());
+        let mut addrs = Vec::new();
+        let mut body = &message[mem::size_of::<rt_msghdr>()..];
+        while !body.is_empty() {
+            let len = body[0] as usize;
+            addrs.push((body[1], body[..len].to_vec()));
+            body = &body[(len + SA_ALIGN - 1) & !(SA_ALIGN - 1)..];
+        }
+        (header, addrs)
+    }
+
+    fn ip_at(sa: &[u8]) -> IpAddr {
+        match sa[1] as c_int {
+            libc::AF_INET => IpAddr::from([sa[4], sa[5], sa[6], sa[7]]),
+            _ => {
+                let mut octets = [0; 16];
+                octets.copy_from_slice(&sa[8..24]);
+                IpAddr::from(octets)
+            }
+        }
+    }
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    #[test]
+    fn gateway_routes_carry_a_netmask() {
+        let route = Route::new(ip("10.0.0.0"), 8).via(ip("192.0.2.1"));
+        let (header, addrs) = decode(&route_message(RTM_ADD, &route, 7));
+        assert_eq!(header.rtm_version, RTM_VERSION);
+        assert_eq!(header.rtm_type, RTM_ADD);
+        assert_eq!(header.rtm_seq, 7);
+        assert_eq!(header.rtm_flags, RTF_UP | RTF_STATIC | RTF_GATEWAY);
+        assert_eq!(header.rtm_addrs, RTA_DST | RTA_GATEWAY | RTA_NETMASK);
+        let ips: Vec<IpAddr> = addrs.iter().map(|(_, sa)| ip_at(sa)).collect();
+        assert_eq!(ips, [ip("10.0.0.0"), ip("192.0.2.1"), ip("255.0.0.0")]);
+    }
+
+    #[test]
+    fn on_link_host_routes_name_the_interface() {
+        let route = Route::host(ip("2001:db8::7")).on(4);
+        let (header, addrs) = decode(&route_message(RTM_DELETE, &route, 1));
+        assert_eq!(header.rtm_type, RTM_DELETE);
+        assert_eq!(header.rtm_index, 4);
+        assert_eq!(header.rtm_flags, RTF_UP | RTF_STATIC | RTF_HOST);
+        assert_eq!(header.rtm_addrs, RTA_DST | RTA_GATEWAY);
+        assert_eq!(addrs.len(), 2);
+        assert_eq!(addrs[0].0 as c_int, libc::AF_INET6);
+        assert_eq!(ip_at(&addrs[0].1), ip("2001:db8::7"));
+        assert_eq!(addrs[1].0 as c_int, libc::AF_LINK);
+        let sdl: sockaddr_dl =
+            unsafe { std::ptr::read_unaligned(addrs[1].1.as_ptr() as *const sockaddr_dl) };
+        assert_eq!(sdl.sdl_index, 4);
+        assert_eq!(sdl.sdl_alen, 0);
+    }
+
+    #[test]
+    fn ipv6_netmasks_cover_the_prefix() {
+        let route = Route::new(ip("2001:db8:1::"), 48).on(4);
+        let (_, addrs) = decode(&route_message(RTM_ADD, &route, 1));
+        assert_eq!(ip_at(&addrs[2].1), ip("ffff:ffff:ffff::"));
+        assert_eq!(netmask(&ip("::"), 0), ip("::"));
+        assert_eq!(netmask(&ip("0.0.0.0"), 32), ip("255.255.255.255"));
+    }
+
+    #[test]
+    fn neighbor_entries_carry_the_mac() {
+        let mac = MacAddr::new([0x02, 0, 0, 0, 0, 0x01]);
+        let (header, addrs) = decode(&neighbor_message(
+            RTM_ADD,
+            4,
+            &ip("192.0.2.9"),
+            Some(mac),
+            1,
+        ));
+        assert_eq!(
+            header.rtm_flags,
+            RTF_UP | RTF_HOST | RTF_STATIC | RTF_LLINFO
+        );
+        assert_eq!(header.rtm_addrs, RTA_DST | RTA_GATEWAY);
+        let sdl: sockaddr_dl =
+            unsafe { std::ptr::read_unaligned(addrs[1].1.as_ptr() as *const sockaddr_dl) };
+        assert_eq!(
+            (sdl.sdl_index, sdl.sdl_type, sdl.sdl_alen),
+            (4, IFT_ETHER, 6)
+        );
+        assert_eq!(&sdl.sdl_data[..6], &mac.octets()[..]);
+
+        let (header, addrs) = decode(&neighbor_message(RTM_DELETE, 4, &ip("192.0.2.9"), None, 1));
+        assert_eq!(header.rtm_addrs, RTA_DST);
+        assert_eq!(addrs.len(), 1);
+    }
+
+    #[test]
+    fn ipv6_neighbor_entries_name_the_address() {
+        let mac = MacAddr::new([0x02, 0, 0, 0, 0, 0x01]);
+        let (header, addrs) = decode(&neighbor_message(
+            RTM_ADD,
+            4,
+            &ip("2001:db8::9"),
+            Some(mac),
+            1,
+        ));
+        assert_eq!(header.rtm_index, 4);
+        assert_eq!(addrs[0].0, libc::AF_INET6 as u8);
+        assert_eq!(ip_at(&addrs[0].1), ip("2001:db8::9"));
+        assert_eq!(addrs[1].0, libc::AF_LINK as u8);
+    }
+}
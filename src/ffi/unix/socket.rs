@@ -0,0 +1,279 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::{io, mem};
+
+use crate::Interface;
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios"
+))]
+fn setsockopt<T>(
+    fd: RawFd,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: &T,
+    len: usize,
+) -> io::Result<()> {
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            name,
+            value as *const T as *const libc::c_void,
+            len as libc::socklen_t,
+        )
+    };
+    match ret {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn bind(fd: RawFd, iface: &Interface) -> io::Result<()> {
+    let name = crate::validate_name(&iface.name)?;
+    let name = name.as_str().as_bytes();
+    setsockopt(
+        fd,
+        libc::SOL_SOCKET,
+        libc::SO_BINDTODEVICE,
+        &name[0],
+        name.len(),
+    )
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn bind(fd: RawFd, iface: &Interface) -> io::Result<()> {
+    let index = match iface.index {
+        Some(index) => index,
+        None => super::if_index(&crate::validate_name(&iface.name)?)?,
+    };
+
+    // The option differs per address family, so ask the socket what it is.
+    let mut addr: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    if unsafe { libc::getsockname(fd, &mut addr as *mut _ as *mut libc::sockaddr, &mut len) } == -1
+    {
+        return Err(io::Error::last_os_error());
+    }
+    let (level, option) = match addr.ss_family as libc::c_int {
+        libc::AF_INET6 => (libc::IPPROTO_IPV6, libc::IPV6_BOUND_IF),
+        _ => (libc::IPPROTO_IP, libc::IP_BOUND_IF),
+    };
+    setsockopt(fd, level, option, &index, mem::size_of::<u32>())
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios"
+)))]
+fn bind(_fd: RawFd, _iface: &Interface) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "binding a socket to an interface is not supported on this platform",
+    ))
+}
+
+/// Restrict a socket to sending and receiving through one interface.
+///
+/// Call this before `bind`/`connect`. Linux and Android use `SO_BINDTODEVICE`
+/// with the interface name, macOS and iOS use `IP_BOUND_IF`/`IPV6_BOUND_IF`
+/// with the interface index, picked by the socket's own address family.
+/// Binding to a device on Linux needs `CAP_NET_RAW` on kernels before 5.7.
+pub fn bind_socket_to_interface(socket: &impl AsRawFd, iface: &Interface) -> io::Result<()> {
+    bind(socket.as_raw_fd(), iface)
+}
+
+/// The address and port of a sockaddr_in or sockaddr_in6, `None` for other
+/// families.
+pub(super) fn socket_addr(addr: &libc::sockaddr_storage) -> Option<SocketAddr> {
+    match addr.ss_family as libc::c_int {
+        libc::AF_INET => {
+            let addr = unsafe { &*(addr as *const _ as *const libc::sockaddr_in) };
+            Some(SocketAddr::V4(SocketAddrV4::new(
+                Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr)),
+                u16::from_be(addr.sin_port),
+            )))
+        }
+        libc::AF_INET6 => {
+            let addr = unsafe { &*(addr as *const _ as *const libc::sockaddr_in6) };
+            Some(SocketAddr::V6(SocketAddrV6::new(
+                Ipv6Addr::from(addr.sin6_addr.s6_addr),
+                u16::from_be(addr.sin6_port),
+                addr.sin6_flowinfo,
+                addr.sin6_scope_id,
+            )))
+        }
+        _ => None,
+    }
+}
+
+/// The local address of `fd`, from `getsockname`.
+fn local_addr(fd: RawFd) -> io::Result<SocketAddr> {
+    let mut addr: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    if unsafe { libc::getsockname(fd, &mut addr as *mut _ as *mut libc::sockaddr, &mut len) } == -1
+    {
+        return Err(io::Error::last_os_error());
+    }
+    socket_addr(&addr)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not an IPv4 or IPv6 socket"))
+}
+
+/// The index of the interface `fd` is bound to with `SO_BINDTODEVICE`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn bound_index(fd: RawFd, _v6: bool) -> io::Result<Option<u32>> {
+    let mut name = [0u8; libc::IFNAMSIZ];
+    let mut len = name.len() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_BINDTODEVICE,
+            name.as_mut_ptr() as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let name = &name[..len as usize];
+    let name = name.split(|&b| b == 0).next().unwrap_or_default();
+    if name.is_empty() {
+        return Ok(None);
+    }
+    let name = String::from_utf8_lossy(name);
+    super::if_index(&crate::validate_name(&name)?).map(Some)
+}
+
+/// The index of the interface `fd` is bound to with `IP_BOUND_IF` or
+/// `IPV6_BOUND_IF`.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn bound_index(fd: RawFd, v6: bool) -> io::Result<Option<u32>> {
+    let (level, option) = match v6 {
+        true => (libc::IPPROTO_IPV6, libc::IPV6_BOUND_IF),
+        false => (libc::IPPROTO_IP, libc::IP_BOUND_IF),
+    };
+    let mut index: u32 = 0;
+    let mut len = mem::size_of::<u32>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            fd,
+            level,
+            option,
+            &mut index as *mut u32 as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    match (ret, index) {
+        (-1, _) => Err(io::Error::last_os_error()),
+        (_, 0) => Ok(None),
+        (_, index) => Ok(Some(index)),
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios"
+)))]
+fn bound_index(_fd: RawFd, _v6: bool) -> io::Result<Option<u32>> {
+    Ok(None)
+}
+
+/// The interface entry `socket` uses, matched by its local address, scope
+/// id included, among the entries [`ifaces`](crate::ifaces) lists.
+///
+/// A socket bound to a device, with `SO_BINDTODEVICE` on Linux and Android
+/// or `IP_BOUND_IF` on macOS and iOS, gets the entry of that interface if
+/// several have the address. A socket bound to the unspecified address, or
+/// not bound yet, receives on every interface, so this is `None` for it
+/// unless it is bound to a device. A socket that is not IPv4 or IPv6 fails
+/// with `ErrorKind::InvalidInput`.
+pub fn interface_of_socket(socket: &impl AsRawFd) -> io::Result<Option<Interface>> {
+    let fd = socket.as_raw_fd();
+    let local = local_addr(fd)?;
+    crate::socket::interface_of(local, bound_index(fd, local.is_ipv6())?)
+}
+
+// Other platforms cannot bind sockets to interfaces.
+#[cfg(all(
+    test,
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios"
+    )
+))]
+mod tests {
+    use super::*;
+
+    use std::net::{TcpListener, TcpStream, UdpSocket};
+
+    use crate::{InterfaceFlags, Kind};
+
+    fn loopback() -> Interface {
+        crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| {
+                iface.kind == Kind::Ipv4 && iface.flags.contains(InterfaceFlags::LOOPBACK)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn udp_traffic_flows_on_the_bound_interface() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        bind_socket_to_interface(&socket, &loopback()).unwrap();
+        socket
+            .send_to(b"ping", socket.local_addr().unwrap())
+            .unwrap();
+        let mut buf = [0; 4];
+        socket
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+        let (len, from) = socket.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"ping");
+        assert_eq!(from, socket.local_addr().unwrap());
+    }
+
+    #[test]
+    fn tcp_connections_reach_a_bound_listener() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        bind_socket_to_interface(&listener, &loopback()).unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        listener.accept().unwrap();
+    }
+
+    #[test]
+    fn bogus_interfaces_fail_cleanly() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let err = bind_socket_to_interface(&socket, &Interface::new("", Kind::Ipv4)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = bind_socket_to_interface(&socket, &Interface::new("nosuchif0", Kind::Ipv4));
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(err.unwrap_err().raw_os_error(), Some(libc::ENODEV));
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn wildcard_sockets_bound_to_a_device_are_on_it() {
+        let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
+        assert!(interface_of_socket(&socket).unwrap().is_none());
+        let lo = loopback();
+        bind_socket_to_interface(&socket, &lo).unwrap();
+        assert_eq!(bound_index(socket.as_raw_fd(), false).unwrap(), lo.index);
+        let iface = interface_of_socket(&socket).unwrap().unwrap();
+        assert_eq!(iface.index, lo.index);
+        assert_eq!(iface.kind, Kind::Ipv4);
+    }
+}
@@ -0,0 +1,177 @@
+//! An owned `getifaddrs` list, which [`ifaces`](super::ifaces) reads and
+//! `ifaces::sys` hands out.
+
+#![cfg_attr(not(feature = "sys"), allow(dead_code))]
+
+use std::ffi::CStr;
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::{io, ptr};
+
+use super::{freeifaddrs, getifaddrs, ifaddrs, nix_socketaddr_to_sockaddr};
+
+/// The list `getifaddrs` returns, freed with `freeifaddrs` on drop.
+pub struct IfAddrs {
+    head: *mut ifaddrs,
+}
+
+// The list is not tied to the thread that asked for it.
+unsafe impl Send for IfAddrs {}
+unsafe impl Sync for IfAddrs {}
+
+impl IfAddrs {
+    pub fn new() -> io::Result<IfAddrs> {
+        let mut head: *mut ifaddrs = ptr::null_mut();
+        match unsafe { getifaddrs(&mut head) } {
+            0 => Ok(IfAddrs { head }),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    /// The first entry, null for an empty list. Valid while `self` is.
+    pub fn as_ptr(&self) -> *const ifaddrs {
+        self.head
+    }
+
+    pub fn iter(&self) -> IfAddrsIter<'_> {
+        IfAddrsIter {
+            next: self.head,
+            _list: PhantomData,
+        }
+    }
+}
+
+impl Drop for IfAddrs {
+    fn drop(&mut self) {
+        if !self.head.is_null() {
+            unsafe { freeifaddrs(self.head) };
+            #[cfg(test)]
+            tests::FREED.with(|freed| freed.set(freed.get() + 1));
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a IfAddrs {
+    type Item = IfAddr<'a>;
+    type IntoIter = IfAddrsIter<'a>;
+
+    fn into_iter(self) -> IfAddrsIter<'a> {
+        self.iter()
+    }
+}
+
+/// The entries of an [`IfAddrs`], in the order `getifaddrs` lists them.
+pub struct IfAddrsIter<'a> {
+    next: *const ifaddrs,
+    _list: PhantomData<&'a IfAddrs>,
+}
+
+impl<'a> Iterator for IfAddrsIter<'a> {
+    type Item = IfAddr<'a>;
+
+    fn next(&mut self) -> Option<IfAddr<'a>> {
+        let raw = unsafe { self.next.as_ref()? };
+        self.next = raw.ifa_next;
+        Some(IfAddr { raw })
+    }
+}
+
+/// One entry of an [`IfAddrs`].
+#[derive(Clone, Copy)]
+pub struct IfAddr<'a> {
+    raw: &'a ifaddrs,
+}
+
+impl<'a> IfAddr<'a> {
+    /// The entry as the C library filled it in, for the fields there is no
+    /// accessor for.
+    pub fn raw(&self) -> &'a ifaddrs {
+        self.raw
+    }
+
+    pub fn name(&self) -> &'a CStr {
+        unsafe { CStr::from_ptr(self.raw.ifa_name) }
+    }
+
+    /// The `IFF_*` flags.
+    pub fn flags(&self) -> u32 {
+        self.raw.flags()
+    }
+
+    /// The address `ifa_addr` points at, `None` if there is none or it is
+    /// not IPv4 or IPv6.
+    pub fn address(&self) -> Option<SocketAddr> {
+        nix_socketaddr_to_sockaddr(self.raw.ifa_addr)
+    }
+
+    pub fn netmask(&self) -> Option<SocketAddr> {
+        nix_socketaddr_to_sockaddr(self.raw.ifa_netmask)
+    }
+
+    /// The `sa_family` of `ifa_addr`, `None` without an address.
+    pub fn family(&self) -> Option<i32> {
+        unsafe { self.raw.ifa_addr.as_ref() }.map(|sa| sa.sa_family as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    thread_local! {
+        /// The lists this thread has freed.
+        pub static FREED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    fn freed() -> usize {
+        FREED.with(Cell::get)
+    }
+
+    #[test]
+    fn lists_are_freed_once_on_drop() {
+        let before = freed();
+        let list = IfAddrs::new().unwrap();
+        assert!(list.iter().count() > 0);
+        assert_eq!(freed(), before);
+        drop(list);
+        assert_eq!(freed(), before + 1);
+    }
+
+    #[test]
+    fn empty_lists_are_not_freed() {
+        let before = freed();
+        let list = IfAddrs {
+            head: ptr::null_mut(),
+        };
+        assert!(list.as_ptr().is_null());
+        assert_eq!(list.iter().count(), 0);
+        drop(list);
+        assert_eq!(freed(), before);
+    }
+
+    #[test]
+    fn entries_are_iterated_from_the_head() {
+        let list = IfAddrs::new().unwrap();
+        let first = list.iter().next().unwrap();
+        assert!(ptr::eq(first.raw(), list.as_ptr()));
+        // Iterating again, either way, starts over.
+        let names: Vec<&CStr> = list.iter().map(|entry| entry.name()).collect();
+        let again: Vec<&CStr> = (&list).into_iter().map(|entry| entry.name()).collect();
+        assert_eq!(names, again);
+    }
+
+    #[test]
+    fn loopback_is_read_through_the_accessors() {
+        let list = IfAddrs::new().unwrap();
+        let lo = list
+            .iter()
+            .find(|entry| entry.address() == Some(SocketAddr::from(([127, 0, 0, 1], 0))))
+            .unwrap();
+        assert!(lo.name().to_str().unwrap().starts_with("lo"));
+        assert_ne!(lo.flags() & libc::IFF_LOOPBACK as u32, 0);
+        assert_eq!(lo.family(), Some(libc::AF_INET));
+        assert_eq!(lo.netmask(), Some(SocketAddr::from(([255, 0, 0, 0], 0))));
+    }
+}
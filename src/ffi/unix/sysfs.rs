@@ -0,0 +1,455 @@
+//! Reading `/sys` and `/proc`, relative to a root that can be moved.
+
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{CarrierChanges, Sriov, ValidName};
+
+pub struct FsRoot {
+    root: PathBuf,
+}
+
+impl FsRoot {
+    /// The real `/sys` and `/proc`.
+    pub fn system() -> FsRoot {
+        FsRoot::at("/")
+    }
+
+    /// A tree laid out like `/`, e.g. a copy taken for a bug report.
+    pub fn at(root: impl Into<PathBuf>) -> FsRoot {
+        FsRoot { root: root.into() }
+    }
+
+    pub fn path(&self, relative: impl AsRef<Path>) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    pub fn exists(&self, relative: impl AsRef<Path>) -> bool {
+        self.path(relative).exists()
+    }
+
+    /// The contents of a file without the trailing newline.
+    pub fn read(&self, relative: impl AsRef<Path>) -> io::Result<String> {
+        let mut contents = fs::read_to_string(self.path(relative))?;
+        let len = contents.trim_end().len();
+        contents.truncate(len);
+        Ok(contents)
+    }
+
+    /// Replace the contents of a file, as writing a sysctl does.
+    pub fn write(&self, relative: impl AsRef<Path>, contents: &str) -> io::Result<()> {
+        fs::write(self.path(relative), contents)
+    }
+
+    /// A file holding a single integer, as sysctls and sysfs attributes do.
+    pub fn read_int(&self, relative: impl AsRef<Path>) -> io::Result<i64> {
+        self.read(relative)?
+            .parse()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+/// Whether `/sys/class/net/<name>` has the `wireless` directory of the
+/// wireless extensions or the `phy80211` link of cfg80211 drivers.
+pub fn is_wireless(root: &FsRoot, name: &ValidName) -> bool {
+    let dir = format!("sys/class/net/{}", name);
+    root.exists(format!("{}/wireless", dir)) || root.exists(format!("{}/phy80211", dir))
+}
+
+/// The `DEVTYPE` line of the `uevent` of `name`.
+pub fn devtype(root: &FsRoot, name: &ValidName) -> Option<String> {
+    let uevent = root.read(format!("sys/class/net/{}/uevent", name)).ok()?;
+    uevent
+        .lines()
+        .find_map(|line| line.strip_prefix("DEVTYPE="))
+        .map(str::to_owned)
+}
+
+/// The carrier counters of `name`. `None` on kernels older than 3.15,
+/// which have no `carrier_changes`.
+pub fn carrier_changes(root: &FsRoot, name: &ValidName) -> Option<CarrierChanges> {
+    let count = |file: &str| {
+        let count = root.read_int(format!("sys/class/net/{}/{}", name, file));
+        count.ok().and_then(|count| u64::try_from(count).ok())
+    };
+    Some(CarrierChanges {
+        changes: count("carrier_changes")?,
+        up: count("carrier_up_count"),
+        down: count("carrier_down_count"),
+    })
+}
+
+/// The SR-IOV role of the device behind `name`, from the `physfn` link a
+/// VF's PCI device has to its PF and the `virtfn<N>` links and
+/// `sriov_numvfs` of a PF.
+pub fn sriov(root: &FsRoot, name: &ValidName) -> Option<Sriov> {
+    let device = root.path(format!("sys/class/net/{}/device", name));
+    let physfn = device.join("physfn");
+    if fs::symlink_metadata(&physfn).is_ok() {
+        let own = fs::canonicalize(&device).ok();
+        let vf_index = virtfns(&physfn)
+            .into_iter()
+            .find(|(_, path)| own.is_some() && fs::canonicalize(path).ok() == own)
+            .map(|(index, _)| index);
+        let pf_name = net_name(&physfn);
+        let pf_index = pf_name.as_ref().and_then(|pf| {
+            let index = root.read_int(format!("sys/class/net/{}/ifindex", pf));
+            index.ok().map(|index| index as u32)
+        });
+        return Some(Sriov::Virtual {
+            vf_index,
+            pf_name,
+            pf_index,
+        });
+    }
+    let num_vfs = root
+        .read_int(format!("sys/class/net/{}/device/sriov_numvfs", name))
+        .ok()?;
+    Some(Sriov::Physical {
+        num_vfs: num_vfs as u32,
+        vfs: virtfns(&device)
+            .into_iter()
+            .map(|(index, path)| (index, net_name(&path)))
+            .collect(),
+    })
+}
+
+/// The `virtfn<N>` links in the directory of a PF's PCI device, by `N`.
+fn virtfns(device: &Path) -> Vec<(u32, PathBuf)> {
+    let entries = match fs::read_dir(device) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut vfs: Vec<(u32, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let index = name.to_str()?.strip_prefix("virtfn")?.parse().ok()?;
+            Some((index, entry.path()))
+        })
+        .collect();
+    vfs.sort();
+    vfs
+}
+
+/// The interface of the PCI device at `device`. `None` if it has none, as
+/// for a VF bound to `vfio-pci` for a guest, or if the link dangles.
+fn net_name(device: &Path) -> Option<String> {
+    let entry = fs::read_dir(device.join("net")).ok()?.flatten().next()?;
+    entry.file_name().into_string().ok()
+}
+
+/// Throwaway trees laid out like `/`, for tests.
+#[cfg(test)]
+pub mod fake {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::FsRoot;
+
+    /// A directory under the temporary directory, removed when dropped.
+    pub struct FakeTree {
+        dir: PathBuf,
+    }
+
+    impl FakeTree {
+        pub fn new() -> FakeTree {
+            static NEXT: AtomicUsize = AtomicUsize::new(0);
+            let dir = std::env::temp_dir().join(format!(
+                "ifaces-fake-{}-{}",
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::Relaxed)
+            ));
+            fs::create_dir_all(&dir).unwrap();
+            FakeTree { dir }
+        }
+
+        /// Write `contents` to `relative`, making its directories.
+        pub fn file(&self, relative: &str, contents: &str) -> &FakeTree {
+            let path = self.dir.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+            self
+        }
+
+        pub fn dir(&self, relative: &str) -> &FakeTree {
+            fs::create_dir_all(self.dir.join(relative)).unwrap();
+            self
+        }
+
+        /// Make `relative` a symlink to `target`, as sysfs links devices.
+        pub fn symlink(&self, relative: &str, target: &str) -> &FakeTree {
+            let path = self.dir.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::os::unix::fs::symlink(target, path).unwrap();
+            self
+        }
+
+        pub fn root(&self) -> FsRoot {
+            FsRoot::at(&self.dir)
+        }
+    }
+
+    impl Drop for FakeTree {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fake::FakeTree;
+    use super::*;
+
+    #[test]
+    fn trailing_newlines_are_dropped() {
+        let tree = FakeTree::new();
+        tree.file("proc/sys/net/ipv6/conf/eth0/use_tempaddr", "2\n");
+        let root = tree.root();
+        assert_eq!(
+            root.read("proc/sys/net/ipv6/conf/eth0/use_tempaddr")
+                .unwrap(),
+            "2"
+        );
+        assert_eq!(
+            root.read_int("proc/sys/net/ipv6/conf/eth0/use_tempaddr")
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn bad_integers_are_invalid_data() {
+        let tree = FakeTree::new();
+        tree.file("sys/class/net/eth0/mtu", "fast\n");
+        let err = tree.root().read_int("sys/class/net/eth0/mtu").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        let err = tree
+            .root()
+            .read_int("sys/class/net/eth0/missing")
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn wireless_extensions_and_cfg80211_are_wireless() {
+        let tree = FakeTree::new();
+        tree.dir("sys/class/net/wlan0/wireless")
+            .dir("sys/class/ieee80211/phy0")
+            .symlink("sys/class/net/wlan1/phy80211", "../../ieee80211/phy0")
+            .file("sys/class/net/eth0/type", "1\n");
+        let root = tree.root();
+        let wireless = |name| is_wireless(&root, &crate::validate_name(name).unwrap());
+        assert!(wireless("wlan0"));
+        assert!(wireless("wlan1"));
+        assert!(!wireless("eth0"));
+        assert!(!wireless("wlan2"));
+    }
+
+    /// A PF at 0000:41:00.0 with four VFs: two with interfaces, one bound
+    /// to `vfio-pci` for a guest, and one whose device is gone; and an
+    /// Ethernet NIC without SR-IOV.
+    fn sriov_tree() -> FakeTree {
+        let tree = FakeTree::new();
+        let pci = "sys/devices/pci0000:40";
+        tree.file(&format!("{}/0000:41:00.0/sriov_numvfs", pci), "4\n")
+            .dir(&format!("{}/0000:41:00.0/net/enp65s0f0", pci))
+            .symlink(&format!("{}/0000:41:00.0/virtfn0", pci), "../0000:41:02.0")
+            .symlink(&format!("{}/0000:41:00.0/virtfn1", pci), "../0000:41:02.1")
+            .symlink(&format!("{}/0000:41:00.0/virtfn2", pci), "../0000:41:02.2")
+            .symlink(&format!("{}/0000:41:00.0/virtfn3", pci), "../0000:41:02.3")
+            // Not a VF.
+            .file(&format!("{}/0000:41:00.0/virtfn_stats", pci), "")
+            .symlink(
+                "sys/class/net/enp65s0f0/device",
+                "../../../devices/pci0000:40/0000:41:00.0",
+            )
+            .file("sys/class/net/enp65s0f0/ifindex", "4\n");
+        for (function, name) in [
+            ("0", Some("enp65s0f0v0")),
+            ("1", Some("enp65s0f0v1")),
+            ("2", None),
+        ] {
+            let device = format!("{}/0000:41:02.{}", pci, function);
+            tree.symlink(&format!("{}/physfn", device), "../0000:41:00.0");
+            match name {
+                Some(name) => {
+                    tree.dir(&format!("{}/net/{}", device, name)).symlink(
+                        &format!("sys/class/net/{}/device", name),
+                        &format!("../../../devices/pci0000:40/0000:41:02.{}", function),
+                    );
+                }
+                None => {
+                    tree.file(&format!("{}/driver_override", device), "vfio-pci\n");
+                }
+            }
+        }
+        tree.dir("sys/devices/pci0000:00/0000:00:1f.6/net/eno1")
+            .symlink(
+                "sys/class/net/eno1/device",
+                "../../../devices/pci0000:00/0000:00:1f.6",
+            )
+            .dir("sys/class/net/lo");
+        tree
+    }
+
+    fn sriov_of(root: &FsRoot, name: &str) -> Option<Sriov> {
+        sriov(root, &crate::validate_name(name).unwrap())
+    }
+
+    #[test]
+    fn pfs_list_their_vfs() {
+        let tree = sriov_tree();
+        assert_eq!(
+            sriov_of(&tree.root(), "enp65s0f0"),
+            Some(Sriov::Physical {
+                num_vfs: 4,
+                vfs: vec![
+                    (0, Some("enp65s0f0v0".to_owned())),
+                    (1, Some("enp65s0f0v1".to_owned())),
+                    // Passed through to a guest.
+                    (2, None),
+                    // Dangling.
+                    (3, None),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn vfs_find_their_pf() {
+        let tree = sriov_tree();
+        let root = tree.root();
+        for (name, vf_index) in [("enp65s0f0v0", 0), ("enp65s0f0v1", 1)] {
+            assert_eq!(
+                sriov_of(&root, name),
+                Some(Sriov::Virtual {
+                    vf_index: Some(vf_index),
+                    pf_name: Some("enp65s0f0".to_owned()),
+                    pf_index: Some(4),
+                }),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn vfs_with_a_dangling_pf_are_still_vfs() {
+        let tree = FakeTree::new();
+        tree.dir("sys/devices/pci0000:40/0000:41:02.0/net/enp65s0f0v0")
+            .symlink(
+                "sys/devices/pci0000:40/0000:41:02.0/physfn",
+                "../0000:41:00.0",
+            )
+            .symlink(
+                "sys/class/net/enp65s0f0v0/device",
+                "../../../devices/pci0000:40/0000:41:02.0",
+            );
+        assert_eq!(
+            sriov_of(&tree.root(), "enp65s0f0v0"),
+            Some(Sriov::Virtual {
+                vf_index: None,
+                pf_name: None,
+                pf_index: None,
+            })
+        );
+    }
+
+    #[test]
+    fn pfs_without_vfs_have_none_enabled() {
+        let tree = sriov_tree();
+        tree.file("sys/devices/pci0000:40/0000:41:00.0/sriov_numvfs", "0\n");
+        for vf in 0..4 {
+            fs::remove_file(
+                tree.root()
+                    .path(format!("sys/devices/pci0000:40/0000:41:00.0/virtfn{}", vf)),
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            sriov_of(&tree.root(), "enp65s0f0"),
+            Some(Sriov::Physical {
+                num_vfs: 0,
+                vfs: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn other_interfaces_have_no_sriov() {
+        let tree = sriov_tree();
+        let root = tree.root();
+        // A PCI NIC without the capability, one without a device, and one
+        // that is not there.
+        assert_eq!(sriov_of(&root, "eno1"), None);
+        assert_eq!(sriov_of(&root, "lo"), None);
+        assert_eq!(sriov_of(&root, "eth9"), None);
+    }
+
+    #[test]
+    fn carrier_counters_are_read() {
+        let tree = FakeTree::new();
+        tree.file("sys/class/net/eth0/carrier_changes", "7\n")
+            .file("sys/class/net/eth0/carrier_up_count", "4\n")
+            .file("sys/class/net/eth0/carrier_down_count", "3\n")
+            // Before 4.16, only the total.
+            .file("sys/class/net/eth1/carrier_changes", "2\n")
+            // Before 3.15, nothing.
+            .dir("sys/class/net/eth2")
+            .file("sys/class/net/eth3/carrier_changes", "-1\n");
+        let changes =
+            |name: &str| carrier_changes(&tree.root(), &crate::validate_name(name).unwrap());
+        assert_eq!(
+            changes("eth0"),
+            Some(CarrierChanges {
+                changes: 7,
+                up: Some(4),
+                down: Some(3),
+            })
+        );
+        assert_eq!(
+            changes("eth1"),
+            Some(CarrierChanges {
+                changes: 2,
+                up: None,
+                down: None,
+            })
+        );
+        assert_eq!(changes("eth2"), None);
+        assert_eq!(changes("eth3"), None);
+        assert_eq!(changes("eth4"), None);
+    }
+
+    #[test]
+    fn loopback_has_carrier_counters() {
+        let lo = carrier_changes(&FsRoot::system(), &crate::validate_name("lo").unwrap());
+        assert!(lo.is_some());
+    }
+
+    #[test]
+    fn device_types_come_from_the_uevent() {
+        let tree = FakeTree::new();
+        tree.file(
+            "sys/class/net/wwan0/uevent",
+            "DEVTYPE=wwan\nINTERFACE=wwan0\nIFINDEX=5\n",
+        )
+        // cdc_mbim modems, which name their link after the control device.
+        .file(
+            "sys/class/net/wwp0s20f0u6i12/uevent",
+            "INTERFACE=wwp0s20f0u6i12\nIFINDEX=6\nDEVTYPE=wwan\n",
+        )
+        .file("sys/class/net/eth0/uevent", "INTERFACE=eth0\nIFINDEX=2\n")
+        .dir("sys/class/net/eth1");
+        let devtype = |name: &str| devtype(&tree.root(), &crate::validate_name(name).unwrap());
+        assert_eq!(devtype("wwan0").as_deref(), Some("wwan"));
+        assert_eq!(devtype("wwp0s20f0u6i12").as_deref(), Some("wwan"));
+        assert_eq!(devtype("eth0"), None);
+        assert_eq!(devtype("eth1"), None);
+        assert_eq!(devtype("eth2"), None);
+    }
+}
@@ -0,0 +1,228 @@
+//! Where a NIC sits in the machine: its NUMA node and the CPUs its
+//! interrupts go to.
+
+use std::fs;
+use std::io;
+
+use super::sysfs::FsRoot;
+use crate::{validate_name, ValidName};
+
+/// One MSI or MSI-X interrupt of a NIC.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct IrqInfo {
+    pub irq: u32,
+    /// The CPUs in `/proc/irq/<irq>/smp_affinity_list`, in order. Empty if
+    /// it cannot be read.
+    pub cpus: Vec<u32>,
+}
+
+/// The NUMA node and interrupts of the device behind an interface, see
+/// [`nic_topology`].
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct NicTopology {
+    /// `None` on machines with a single node, where the kernel reports -1.
+    pub numa_node: Option<i32>,
+    /// By IRQ number. Empty for devices without MSI, e.g. legacy INTx.
+    pub irqs: Vec<IrqInfo>,
+}
+
+/// The NUMA node and interrupt affinity of the interface called `name`.
+///
+/// Virtual interfaces (loopback, bridges, veths, tunnels) have no device
+/// and get an empty topology. An interface that does not exist gives
+/// `NotFound`.
+pub fn nic_topology(name: &str) -> io::Result<NicTopology> {
+    topology_from(&FsRoot::system(), &validate_name(name)?)
+}
+
+fn topology_from(root: &FsRoot, name: &ValidName) -> io::Result<NicTopology> {
+    if !root.exists(format!("sys/class/net/{}", name)) {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no such interface"));
+    }
+    let device = format!("sys/class/net/{}/device", name);
+    if !root.exists(&device) {
+        return Ok(NicTopology::default());
+    }
+    let numa_node = match root.read_int(format!("{}/numa_node", device)) {
+        Ok(node) if node >= 0 => Some(node as i32),
+        _ => None,
+    };
+    let mut irqs: Vec<u32> = match fs::read_dir(root.path(format!("{}/msi_irqs", device))) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    irqs.sort_unstable();
+    Ok(NicTopology {
+        numa_node,
+        irqs: irqs
+            .into_iter()
+            .map(|irq| IrqInfo {
+                irq,
+                cpus: root
+                    .read(format!("proc/irq/{}/smp_affinity_list", irq))
+                    .ok()
+                    .and_then(|list| parse_cpu_list(&list).ok())
+                    .unwrap_or_default(),
+            })
+            .collect(),
+    })
+}
+
+/// A CPU list as the kernel prints it, e.g. `0-3,8,10-11`, in order and
+/// without repeats. An empty list is no CPUs.
+pub fn parse_cpu_list(list: &str) -> io::Result<Vec<u32>> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("bad CPU list {:?}", list),
+        )
+    };
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|part| !part.is_empty()) {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (first, last),
+            None => (part, part),
+        };
+        let first: u32 = first.trim().parse().map_err(|_| invalid())?;
+        let last: u32 = last.trim().parse().map_err(|_| invalid())?;
+        if first > last {
+            return Err(invalid());
+        }
+        cpus.extend(first..=last);
+    }
+    cpus.sort_unstable();
+    cpus.dedup();
+    Ok(cpus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::sysfs::fake::FakeTree;
+    use super::*;
+
+    #[test]
+    fn cpu_lists_are_parsed() {
+        let table: [(&str, &[u32]); 9] = [
+            ("0", &[0]),
+            ("0-3", &[0, 1, 2, 3]),
+            ("0-3,8,10-11\n", &[0, 1, 2, 3, 8, 10, 11]),
+            ("5,1,3", &[1, 3, 5]),
+            ("0-2,1-3", &[0, 1, 2, 3]),
+            ("7-7", &[7]),
+            ("", &[]),
+            ("\n", &[]),
+            ("0,,2,", &[0, 2]),
+        ];
+        for (list, cpus) in table {
+            assert_eq!(parse_cpu_list(list).unwrap(), cpus, "{:?}", list);
+        }
+    }
+
+    #[test]
+    fn malformed_cpu_lists_are_invalid_data() {
+        for list in ["3-1", "a", "0-", "-3", "1-2-3", "0;1", "-1", "0x3", "ff"] {
+            let err = parse_cpu_list(list).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", list);
+            assert_eq!(err.to_string(), format!("bad CPU list {:?}", list));
+        }
+    }
+
+    /// eth0 on node 1 with three MSI-X vectors, one of which has no
+    /// affinity file; eno1 on a single-node machine without MSI; and lo.
+    fn tree() -> FakeTree {
+        let tree = FakeTree::new();
+        let eth0 = "sys/devices/pci0000:40/0000:41:00.0";
+        tree.file(&format!("{}/numa_node", eth0), "1\n")
+            .file(&format!("{}/msi_irqs/120", eth0), "msix\n")
+            .file(&format!("{}/msi_irqs/96", eth0), "msix\n")
+            .file(&format!("{}/msi_irqs/121", eth0), "msix\n")
+            .symlink(
+                "sys/class/net/eth0/device",
+                "../../../devices/pci0000:40/0000:41:00.0",
+            )
+            .file("proc/irq/96/smp_affinity_list", "0-3\n")
+            .file("proc/irq/120/smp_affinity_list", "8,10-11\n")
+            .file("sys/devices/pci0000:00/0000:00:1f.6/numa_node", "-1\n")
+            .symlink(
+                "sys/class/net/eno1/device",
+                "../../../devices/pci0000:00/0000:00:1f.6",
+            )
+            .dir("sys/class/net/lo");
+        tree
+    }
+
+    fn topology(root: &FsRoot, name: &str) -> io::Result<NicTopology> {
+        topology_from(root, &validate_name(name).unwrap())
+    }
+
+    #[test]
+    fn nics_have_their_node_and_interrupts() {
+        let tree = tree();
+        assert_eq!(
+            topology(&tree.root(), "eth0").unwrap(),
+            NicTopology {
+                numa_node: Some(1),
+                irqs: vec![
+                    IrqInfo {
+                        irq: 96,
+                        cpus: vec![0, 1, 2, 3],
+                    },
+                    IrqInfo {
+                        irq: 120,
+                        cpus: vec![8, 10, 11],
+                    },
+                    // No affinity file.
+                    IrqInfo {
+                        irq: 121,
+                        cpus: Vec::new(),
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn single_node_machines_have_no_node() {
+        let tree = tree();
+        assert_eq!(
+            topology(&tree.root(), "eno1").unwrap(),
+            NicTopology::default()
+        );
+    }
+
+    #[test]
+    fn bad_affinity_lists_are_no_cpus() {
+        let tree = tree();
+        tree.file("proc/irq/96/smp_affinity_list", "garbage\n");
+        let topology = topology(&tree.root(), "eth0").unwrap();
+        assert_eq!(topology.irqs[0].irq, 96);
+        assert_eq!(topology.irqs[0].cpus, Vec::<u32>::new());
+    }
+
+    #[test]
+    fn virtual_interfaces_have_an_empty_topology() {
+        let tree = tree();
+        assert_eq!(
+            topology(&tree.root(), "lo").unwrap(),
+            NicTopology::default()
+        );
+        let err = topology(&tree.root(), "eth9").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn loopback_has_no_device() {
+        assert_eq!(nic_topology("lo").unwrap(), NicTopology::default());
+        assert_eq!(
+            nic_topology("ifaces-none0").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            nic_topology("a/b").unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
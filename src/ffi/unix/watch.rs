@@ -0,0 +1,322 @@
+//! Change notifications from the kernel: an rtnetlink subscription on Linux,
+//! a routing socket elsewhere.
+
+use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::time::Duration;
+
+use crate::ChangeHint;
+
+pub struct Watcher {
+    fd: OwnedFd,
+}
+
+impl Watcher {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn new() -> io::Result<Watcher> {
+        use super::netlink::{RTMGRP_IPV4_IFADDR, RTMGRP_IPV6_IFADDR, RTMGRP_LINK};
+
+        let groups = RTMGRP_LINK | RTMGRP_IPV4_IFADDR | RTMGRP_IPV6_IFADDR;
+        let socket = super::netlink::Socket::subscribe(groups)?;
+        Ok(Watcher {
+            fd: socket.into_fd(),
+        })
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub fn new() -> io::Result<Watcher> {
+        use std::os::unix::io::FromRawFd;
+
+        let fd = unsafe { libc::socket(libc::PF_ROUTE, libc::SOCK_RAW, libc::AF_UNSPEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Watcher {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        })
+    }
+
+    /// Block until the kernel reports a change or `timeout` passes, `None`
+    /// waiting forever. Returns what changed, if anything, after reading
+    /// every queued message.
+    ///
+    /// An interrupted wait counts as no change, callers re-check anyway.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<Option<ChangeHint>> {
+        let timeout = match timeout {
+            Some(timeout) => timeout.as_millis().min(i32::MAX as u128) as i32,
+            None => -1,
+        };
+        let mut pollfd = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        match unsafe { libc::poll(&mut pollfd, 1, timeout) } {
+            -1 => {
+                let err = io::Error::last_os_error();
+                match err.kind() {
+                    io::ErrorKind::Interrupted => Ok(None),
+                    _ => Err(err),
+                }
+            }
+            0 => Ok(None),
+            _ => {
+                let mut hint = None;
+                for this in self.drain_events().unwrap_or_default() {
+                    hint = match hint {
+                        Some(hint) if hint != this => Some(ChangeHint::Unknown),
+                        _ => Some(this),
+                    };
+                }
+                Ok(Some(hint.unwrap_or(ChangeHint::Unknown)))
+            }
+        }
+    }
+
+    /// Read every queued message without blocking, one hint each.
+    ///
+    /// Only the message types are needed, callers re-enumerate. An
+    /// overflowed socket reports ENOBUFS, which is just as much a change,
+    /// of unknown kind.
+    pub fn drain_events(&mut self) -> io::Result<Vec<ChangeHint>> {
+        let mut events = Vec::new();
+        let mut buf = [0u8; 8192];
+        loop {
+            let len = unsafe {
+                libc::recv(
+                    self.fd.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    libc::MSG_DONTWAIT,
+                )
+            };
+            if len == 0 {
+                return Ok(events);
+            }
+            if len < 0 {
+                let err = io::Error::last_os_error();
+                match err.raw_os_error() {
+                    Some(libc::EINTR) => continue,
+                    Some(libc::ENOBUFS) => {
+                        events.push(ChangeHint::Unknown);
+                        continue;
+                    }
+                    _ if err.kind() == io::ErrorKind::WouldBlock => return Ok(events),
+                    _ => return Err(err),
+                }
+            }
+            events.extend(message_types(&buf[..len as usize]).into_iter().map(hint_of));
+        }
+    }
+}
+
+impl AsRawFd for Watcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl AsFd for Watcher {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const RTM_NEWLINK: u16 = 16;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const RTM_DELLINK: u16 = 17;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const RTM_NEWADDR: u16 = 20;
+
+/// The `nlmsg_type` of each message in the datagram `bytes`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn message_types(bytes: &[u8]) -> Vec<u16> {
+    use super::netlink::{align, read, NlMsgHdr};
+
+    let mut types = Vec::new();
+    let mut rest = bytes;
+    while let Some(header) = read::<NlMsgHdr>(rest) {
+        let msg_len = header.len as usize;
+        if msg_len < std::mem::size_of::<NlMsgHdr>() || msg_len > rest.len() {
+            break;
+        }
+        types.push(header.ty);
+        rest = rest.get(align(msg_len)..).unwrap_or(&[]);
+    }
+    types
+}
+
+/// Routing sockets hand out one message per read, with its type after the
+/// length and version.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn message_types(bytes: &[u8]) -> Vec<u16> {
+    bytes.get(3).map(|&ty| ty as u16).into_iter().collect()
+}
+
+/// What a message of type `ty` says changed.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn hint_of(ty: u16) -> ChangeHint {
+    match ty {
+        RTM_NEWLINK | RTM_DELLINK => ChangeHint::Links,
+        RTM_NEWADDR | super::netlink::RTM_DELADDR => ChangeHint::Addresses,
+        _ => ChangeHint::Unknown,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const RTM_NEWADDR: u16 = 0xc;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const RTM_DELADDR: u16 = 0xd;
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "netbsd")))]
+const RTM_IFINFO: u16 = 0xe;
+#[cfg(target_os = "netbsd")]
+const RTM_IFINFO: u16 = 0x14;
+
+/// What a message of type `ty` says changed. Route changes, which the
+/// socket reports as well, are `Unknown`.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn hint_of(ty: u16) -> ChangeHint {
+    match ty {
+        RTM_IFINFO => ChangeHint::Links,
+        RTM_NEWADDR | RTM_DELADDR => ChangeHint::Addresses,
+        _ => ChangeHint::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::io::FromRawFd;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const ROUTE_CHANGE: u16 = 24;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    const ROUTE_CHANGE: u16 = 0x1;
+
+    /// A datagram of the kernel's with a message of each type in `types`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn datagram(types: &[u16]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for &ty in types {
+            // An nlmsghdr and a 4-byte payload.
+            bytes.extend_from_slice(&20u32.to_ne_bytes());
+            bytes.extend_from_slice(&ty.to_ne_bytes());
+            bytes.extend_from_slice(&[0; 14]);
+        }
+        bytes
+    }
+
+    /// Routing sockets hand out one message per read, so only one type.
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn datagram(types: &[u16]) -> Vec<u8> {
+        let mut bytes = vec![0; 16];
+        bytes[..2].copy_from_slice(&16u16.to_ne_bytes());
+        bytes[2] = 5;
+        bytes[3] = types[0] as u8;
+        bytes
+    }
+
+    /// A watcher reading from a socket the test writes `datagrams` into.
+    fn injected(datagrams: &[Vec<u8>]) -> Watcher {
+        let mut fds = [0; 2];
+        let ret = unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_DGRAM, 0, fds.as_mut_ptr()) };
+        assert_eq!(ret, 0, "{}", io::Error::last_os_error());
+        let (ours, theirs) =
+            unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+        for datagram in datagrams {
+            let sent = unsafe {
+                libc::send(
+                    theirs.as_raw_fd(),
+                    datagram.as_ptr() as *const libc::c_void,
+                    datagram.len(),
+                    0,
+                )
+            };
+            assert_eq!(sent, datagram.len() as isize);
+        }
+        Watcher { fd: ours }
+    }
+
+    #[test]
+    fn message_types_map_to_hints() {
+        assert_eq!(hint_of(RTM_NEWADDR), ChangeHint::Addresses);
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            assert_eq!(hint_of(RTM_NEWLINK), ChangeHint::Links);
+            assert_eq!(hint_of(RTM_DELLINK), ChangeHint::Links);
+            assert_eq!(
+                hint_of(super::super::netlink::RTM_DELADDR),
+                ChangeHint::Addresses
+            );
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            assert_eq!(hint_of(RTM_IFINFO), ChangeHint::Links);
+            assert_eq!(hint_of(RTM_DELADDR), ChangeHint::Addresses);
+        }
+        assert_eq!(hint_of(ROUTE_CHANGE), ChangeHint::Unknown);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn every_message_of_a_datagram_is_read() {
+        let bytes = datagram(&[RTM_NEWLINK, RTM_NEWADDR, ROUTE_CHANGE]);
+        assert_eq!(
+            message_types(&bytes),
+            [RTM_NEWLINK, RTM_NEWADDR, ROUTE_CHANGE]
+        );
+        // A truncated message ends the datagram.
+        assert_eq!(
+            message_types(&bytes[..bytes.len() - 1]),
+            [RTM_NEWLINK, RTM_NEWADDR]
+        );
+        assert!(message_types(&[]).is_empty());
+    }
+
+    #[test]
+    fn injected_messages_are_drained() {
+        let mut watcher = injected(&[datagram(&[RTM_NEWADDR]), datagram(&[ROUTE_CHANGE])]);
+        assert_eq!(
+            watcher.drain_events().unwrap(),
+            [ChangeHint::Addresses, ChangeHint::Unknown]
+        );
+        assert_eq!(watcher.drain_events().unwrap(), []);
+    }
+
+    #[test]
+    fn waits_combine_what_is_queued() {
+        let mut watcher = injected(&[datagram(&[RTM_NEWADDR]), datagram(&[RTM_NEWADDR])]);
+        let hint = watcher.wait(Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(hint, Some(ChangeHint::Addresses));
+        // Differing hints are `Unknown`.
+        let mut watcher = injected(&[datagram(&[RTM_NEWADDR]), datagram(&[ROUTE_CHANGE])]);
+        let hint = watcher.wait(Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(hint, Some(ChangeHint::Unknown));
+    }
+
+    #[test]
+    fn waits_without_messages_time_out() {
+        let mut watcher = injected(&[]);
+        assert_eq!(watcher.wait(Some(Duration::from_millis(20))).unwrap(), None);
+        assert_eq!(watcher.wait(Some(Duration::ZERO)).unwrap(), None);
+    }
+
+    #[test]
+    fn injected_messages_reach_an_event_loop() {
+        use polling::{Event, Events, Poller};
+
+        let mut watcher = injected(&[datagram(&[RTM_NEWADDR])]);
+        let poller = Poller::new().unwrap();
+        unsafe { poller.add(&watcher, Event::readable(7)) }.unwrap();
+        let mut events = Events::new();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(5)))
+            .unwrap();
+        let keys: Vec<usize> = events.iter().map(|event| event.key).collect();
+        assert_eq!(keys, [7]);
+        assert_eq!(watcher.drain_events().unwrap(), [ChangeHint::Addresses]);
+        poller.delete(&watcher).unwrap();
+    }
+}
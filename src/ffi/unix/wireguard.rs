@@ -0,0 +1,200 @@
+//! WireGuard devices of the kernel module, over the `wireguard` generic
+//! netlink family.
+
+use std::collections::HashSet;
+use std::io;
+use std::mem;
+
+use super::netlink::{self, align, read, Attrs, GenlMsgHdr, Message, Socket, NLM_F_DUMP};
+use crate::{ValidName, WgInfo};
+
+const WG_GENL_NAME: &str = "wireguard";
+const WG_GENL_VERSION: u8 = 1;
+const WG_CMD_GET_DEVICE: u8 = 0;
+
+const WGDEVICE_A_IFNAME: u16 = 2;
+const WGDEVICE_A_PUBLIC_KEY: u16 = 4;
+const WGDEVICE_A_LISTEN_PORT: u16 = 6;
+const WGDEVICE_A_PEERS: u16 = 8;
+const WGPEER_A_PUBLIC_KEY: u16 = 1;
+
+/// The `WG_CMD_GET_DEVICE` request for `name`, to family `family`.
+fn get_device_message(family: u16, name: &ValidName) -> Message {
+    // The kernel only answers this as a dump.
+    let mut message = Message::new(family, NLM_F_DUMP);
+    message.push(&GenlMsgHdr {
+        cmd: WG_CMD_GET_DEVICE,
+        version: WG_GENL_VERSION,
+        ..GenlMsgHdr::default()
+    });
+    let mut ifname = name.as_str().as_bytes().to_vec();
+    ifname.push(0);
+    message.attr(WGDEVICE_A_IFNAME, &ifname);
+    message
+}
+
+/// Decode the replies to `WG_CMD_GET_DEVICE`.
+///
+/// A device with many peers comes in several messages, and a peer with
+/// many allowed IPs is repeated at the start of the next one, so peers are
+/// counted by their keys.
+fn parse_device(replies: &[Vec<u8>]) -> WgInfo {
+    let mut info = WgInfo::default();
+    let mut peers = HashSet::new();
+    let attrs = replies
+        .iter()
+        .filter_map(|payload| payload.get(align(mem::size_of::<GenlMsgHdr>())..))
+        .flat_map(Attrs::new);
+    for (ty, value) in attrs {
+        match ty {
+            WGDEVICE_A_PUBLIC_KEY => info.public_key = read(value).unwrap_or_default(),
+            WGDEVICE_A_LISTEN_PORT => info.listen_port = read(value).unwrap_or_default(),
+            WGDEVICE_A_PEERS => {
+                for (_, peer) in Attrs::new(value) {
+                    peers.extend(
+                        Attrs::new(peer)
+                            .filter(|&(ty, _)| ty == WGPEER_A_PUBLIC_KEY)
+                            .filter_map(|(_, key)| read::<[u8; 32]>(key)),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    info.peers = peers.len();
+    info
+}
+
+/// What the kernel says about WireGuard device `name`.
+///
+/// No such link, or no `wireguard` module, is `ErrorKind::NotFound`, and a
+/// link of another kind `ErrorKind::InvalidInput`.
+pub fn device(name: &ValidName) -> io::Result<WgInfo> {
+    let mut socket = Socket::generic()?;
+    let family = netlink::family_id(&mut socket, WG_GENL_NAME)?;
+    match socket.request(&mut get_device_message(family, name)) {
+        Ok(replies) => Ok(parse_device(&replies)),
+        Err(err) => Err(match err.raw_os_error() {
+            Some(libc::ENODEV) => io::Error::new(io::ErrorKind::NotFound, err),
+            Some(libc::EOPNOTSUPP) => io::Error::new(io::ErrorKind::InvalidInput, err),
+            _ => err,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::unix::netlink::{NlMsgHdr, NLM_F_REQUEST};
+
+    /// `WGPEER_A_PUBLIC_KEY` of `key`, and `WGPEER_A_ALLOWEDIPS` with
+    /// 10.0.0.`host`/32.
+    fn peer(index: u8, key: u8, host: u8) -> Vec<u8> {
+        [
+            &[72, 0, index, 0x80][..],
+            &[36, 0, 1, 0],
+            &[key; 32],
+            &[32, 0, 9, 0x80],
+            &[28, 0, 0, 0x80],
+            &[6, 0, 1, 0, 2, 0, 0, 0], // AF_INET
+            &[8, 0, 2, 0, 10, 0, 0, host],
+            &[5, 0, 3, 0, 32, 0, 0, 0],
+        ]
+        .concat()
+    }
+
+    /// The two replies of a device with two peers, the first of which has
+    /// its second allowed IP in the second reply.
+    fn replies() -> Vec<Vec<u8>> {
+        let first = [
+            &[0, 1, 0, 0][..],
+            &[8, 0, 1, 0, 7, 0, 0, 0],
+            &[8, 0, 2, 0, b'w', b'g', b'0', 0],
+            &[36, 0, 4, 0],
+            &[0x11; 32],
+            &[6, 0, 6, 0, 0x6c, 0xca, 0, 0], // 51820
+            &[76, 0, 8, 0x80],
+            &peer(0, 0xaa, 2),
+        ]
+        .concat();
+        let second = [
+            &[0, 1, 0, 0][..],
+            &[8, 0, 1, 0, 7, 0, 0, 0],
+            &[8, 0, 2, 0, b'w', b'g', b'0', 0],
+            &[148, 0, 8, 0x80],
+            &peer(0, 0xaa, 3),
+            &peer(1, 0xbb, 4),
+        ]
+        .concat();
+        vec![first, second]
+    }
+
+    #[test]
+    fn devices_are_asked_for_by_name() {
+        let name = crate::validate_name("wg0").unwrap();
+        let message = get_device_message(0x15, &name);
+        let bytes = message.bytes();
+        let header = read::<NlMsgHdr>(bytes).unwrap();
+        assert_eq!(header.ty, 0x15);
+        assert_eq!(header.flags, NLM_F_REQUEST | NLM_F_DUMP);
+        assert_eq!(
+            bytes[mem::size_of::<NlMsgHdr>()..],
+            [0, 1, 0, 0, 8, 0, 2, 0, b'w', b'g', b'0', 0]
+        );
+    }
+
+    #[test]
+    fn devices_are_read_from_the_replies() {
+        assert_eq!(
+            parse_device(&replies()),
+            WgInfo {
+                listen_port: 51820,
+                public_key: [0x11; 32],
+                peers: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn peers_split_across_replies_count_once() {
+        let replies = replies();
+        assert_eq!(parse_device(&replies[..1]).peers, 1);
+        assert_eq!(
+            parse_device(&[replies[1].clone(), replies[1].clone()]).peers,
+            2
+        );
+    }
+
+    #[test]
+    fn short_attributes_are_left_out() {
+        let reply = [
+            &[0, 1, 0, 0][..],
+            &[8, 0, 4, 0, 0x11, 0x11, 0x11, 0x11],
+            &[5, 0, 6, 0, 0x6c, 0, 0, 0],
+            // A peer with a 4-byte key.
+            &[16, 0, 8, 0x80],
+            &[12, 0, 0, 0x80],
+            &[8, 0, 1, 0, 0xaa, 0xaa, 0xaa, 0xaa],
+        ]
+        .concat();
+        assert_eq!(parse_device(&[reply]), WgInfo::default());
+        assert_eq!(parse_device(&[]), WgInfo::default());
+        // Not even a whole genlmsghdr.
+        assert_eq!(parse_device(&[vec![0, 1]]), WgInfo::default());
+    }
+
+    #[test]
+    fn other_links_are_not_devices() {
+        let kind = |name: &str| {
+            device(&crate::validate_name(name).unwrap())
+                .unwrap_err()
+                .kind()
+        };
+        // Whether the module is loaded or not.
+        assert!(matches!(
+            kind("lo"),
+            io::ErrorKind::InvalidInput | io::ErrorKind::NotFound
+        ));
+        assert_eq!(kind("ifaces-no-wg0"), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,109 @@
+//! Configuration on platforms where it is not wired up: Redox, which takes
+//! changes as writes to `netcfg` files, and ESP-IDF, where the application
+//! owns the netifs. Everything in here fails with `ErrorKind::Unsupported`.
+
+use std::io;
+use std::net::{IpAddr, Ipv6Addr};
+
+use super::unsupported;
+use crate::{Ipv6Disabled, Kind, MacAddr, Route, TempAddrs, ValidName, WolInfo, WolModes};
+
+pub fn promiscuous(_name: &str) -> io::Result<bool> {
+    Err(unsupported("promiscuous mode"))
+}
+
+pub fn set_promiscuous(_name: &str, _enabled: bool) -> io::Result<bool> {
+    Err(unsupported("promiscuous mode"))
+}
+
+pub fn add_route(_route: &Route) -> io::Result<()> {
+    Err(unsupported("changing routes"))
+}
+
+pub fn remove_route(_route: &Route) -> io::Result<()> {
+    Err(unsupported("changing routes"))
+}
+
+pub fn remove_address(_name: &str, _addr: IpAddr, _prefix_len: u8) -> io::Result<()> {
+    Err(unsupported("removing addresses"))
+}
+
+pub fn wol(_name: &str) -> io::Result<WolInfo> {
+    Err(unsupported("Wake-on-LAN"))
+}
+
+pub fn set_wol(_name: &str, _modes: WolModes) -> io::Result<()> {
+    Err(unsupported("Wake-on-LAN"))
+}
+
+pub fn alias(_name: &str) -> io::Result<Option<String>> {
+    Err(unsupported("interface aliases"))
+}
+
+pub fn set_alias(_name: &str, _text: &str) -> io::Result<()> {
+    Err(unsupported("interface aliases"))
+}
+
+pub(crate) fn install_neighbor(_index: u32, _ip: IpAddr, _mac: MacAddr) -> io::Result<()> {
+    Err(unsupported("changing neighbor entries"))
+}
+
+pub(crate) fn delete_neighbor(_index: u32, _ip: IpAddr) -> io::Result<()> {
+    Err(unsupported("changing neighbor entries"))
+}
+
+pub(crate) fn default_gateway(_family: Kind) -> io::Result<Option<(IpAddr, u32)>> {
+    Err(unsupported("reading the default route"))
+}
+
+pub(crate) fn neighbor_mac(_index: u32, _ip: IpAddr) -> io::Result<Option<MacAddr>> {
+    Err(unsupported("reading neighbor entries"))
+}
+
+pub(crate) fn add_vlan(_parent: u32, _vlan_id: u16, _name: &ValidName) -> io::Result<u32> {
+    Err(unsupported("creating VLANs"))
+}
+
+pub(crate) fn delete_vlan(_index: u32) -> io::Result<()> {
+    Err(unsupported("creating VLANs"))
+}
+
+pub(crate) fn add_bridge(_name: &ValidName) -> io::Result<u32> {
+    Err(unsupported("creating bridges"))
+}
+
+pub(crate) fn delete_bridge(_index: u32) -> io::Result<()> {
+    Err(unsupported("creating bridges"))
+}
+
+pub(crate) fn add_bridge_port(_bridge: u32, _port: u32) -> io::Result<()> {
+    Err(unsupported("creating bridges"))
+}
+
+pub(crate) fn remove_bridge_port(_bridge: u32, _port: u32) -> io::Result<()> {
+    Err(unsupported("creating bridges"))
+}
+
+pub fn set_metric(_name: &str, _family: Kind, _metric: u32) -> io::Result<()> {
+    Err(unsupported("interface metrics"))
+}
+
+pub fn ipv6_token(_name: &str) -> io::Result<Option<Ipv6Addr>> {
+    Err(unsupported("IPv6 tokens"))
+}
+
+pub fn set_ipv6_token(_name: &str, _token: Ipv6Addr) -> io::Result<()> {
+    Err(unsupported("IPv6 tokens"))
+}
+
+pub fn temp_addrs(_name: &str) -> io::Result<TempAddrs> {
+    Err(unsupported("temporary addresses"))
+}
+
+pub fn set_temp_addrs(_name: &str, _mode: TempAddrs) -> io::Result<()> {
+    Err(unsupported("temporary addresses"))
+}
+
+pub(crate) fn ipv6_disabled(_name: &str) -> io::Result<Option<Ipv6Disabled>> {
+    Err(unsupported("telling whether IPv6 is disabled"))
+}
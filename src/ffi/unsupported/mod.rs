@@ -0,0 +1,55 @@
+//! What the backends without these facilities share: Redox and ESP-IDF
+//! only list interfaces, everything else fails or comes back empty.
+
+use std::io;
+
+use crate::{HardwareKind, Interface, OsSpecific};
+
+pub mod config;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+fn unsupported(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{} is not supported on this platform", what),
+    )
+}
+
+/// Not supported: these sockets cannot be bound to an interface.
+pub fn bind_socket_to_interface<T>(_socket: &T, _iface: &Interface) -> io::Result<()> {
+    Err(unsupported("binding sockets to an interface"))
+}
+
+/// Not supported: there is no telling which interface a socket uses.
+pub fn interface_of_socket<T>(_socket: &T) -> io::Result<Option<Interface>> {
+    Err(unsupported("finding the interface of a socket"))
+}
+
+pub(crate) fn hardware_kind(_os: &OsSpecific) -> HardwareKind {
+    HardwareKind::Other
+}
+
+/// No address flags here, so this is always empty.
+pub(crate) fn deprecated_or_temporary_v6() -> Vec<std::net::Ipv6Addr> {
+    Vec::new()
+}
+
+pub(crate) fn dad_state(
+    _name: &str,
+    _addr: std::net::Ipv6Addr,
+) -> io::Result<Option<crate::dad::DadState>> {
+    Err(unsupported("address DAD state"))
+}
+
+pub(crate) fn is_wireless(_name: &str, _os: &OsSpecific) -> bool {
+    false
+}
+
+pub(crate) fn if_index(_name: &crate::ValidName) -> io::Result<u32> {
+    Err(unsupported("looking up interface indices"))
+}
+
+pub(crate) fn index_name(_index: u32) -> Option<String> {
+    None
+}
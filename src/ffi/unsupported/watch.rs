@@ -0,0 +1,26 @@
+//! No change notifications on these platforms, so callers fall back to
+//! polling.
+
+use std::io;
+use std::time::Duration;
+
+use crate::ChangeHint;
+
+pub struct Watcher(());
+
+impl Watcher {
+    pub fn new() -> io::Result<Watcher> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "change notifications are not supported on this platform",
+        ))
+    }
+
+    pub fn wait(&mut self, _timeout: Option<Duration>) -> io::Result<Option<ChangeHint>> {
+        Ok(None)
+    }
+
+    pub fn drain_events(&mut self) -> io::Result<Vec<ChangeHint>> {
+        Ok(Vec::new())
+    }
+}
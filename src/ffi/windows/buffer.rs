@@ -0,0 +1,203 @@
+//! Bounds-checked reads of the `GetAdaptersAddresses` buffer.
+//!
+//! Windows writes the whole adapter list into the buffer it is given, the
+//! structures pointing at each other and at their strings and addresses
+//! inside it. Every pointer is checked against the buffer before it is
+//! followed, so a bad pointer or length field from a driver loses what it
+//! points at instead of reading past the buffer.
+
+use std::convert::TryFrom;
+use std::ffi::CStr;
+use std::marker::PhantomData;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::{mem, ptr};
+
+use winapi::shared::ws2def::{AF_INET, AF_INET6, SOCKADDR_IN, SOCKET_ADDRESS};
+use winapi::shared::ws2ipdef::SOCKADDR_IN6;
+
+use super::WideString;
+
+/// Types every bit pattern is a value of, which can be read out of
+/// arbitrary bytes.
+///
+/// # Safety
+///
+/// Only for `repr(C)` structures of integers, raw pointers and other
+/// `Plain` types, without enums, references or `bool`s.
+pub(super) unsafe trait Plain {}
+
+unsafe impl Plain for SOCKADDR_IN {}
+unsafe impl Plain for SOCKADDR_IN6 {}
+
+#[derive(Clone, Copy)]
+pub(super) struct AdapterBuffer<'a> {
+    bytes: &'a [u8],
+    /// The address the pointers in `bytes` are relative to.
+    base: usize,
+}
+
+impl<'a> AdapterBuffer<'a> {
+    /// `bytes`, with its pointers taken as addresses `base` is the start
+    /// of. For a buffer Windows filled in, `base` is where it is.
+    pub fn new(bytes: &'a [u8], base: usize) -> AdapterBuffer<'a> {
+        AdapterBuffer { bytes, base }
+    }
+
+    /// Where `addr` is in the buffer, if it is and is aligned to `align`.
+    fn offset(&self, addr: usize, align: usize) -> Option<usize> {
+        let offset = addr.checked_sub(self.base)?;
+        match offset <= self.bytes.len() && (self.bytes.as_ptr() as usize + offset) % align == 0 {
+            true => Some(offset),
+            false => None,
+        }
+    }
+
+    /// The `len` bytes at `addr`, if they are all in the buffer.
+    fn slice(&self, addr: usize, len: usize) -> Option<&'a [u8]> {
+        let offset = self.offset(addr, 1)?;
+        self.bytes.get(offset..offset.checked_add(len)?)
+    }
+
+    /// The `T` at `addr`, if all of it is in the buffer and it is aligned.
+    fn at<T: Plain>(&self, addr: usize) -> Option<&'a T> {
+        let offset = self.offset(addr, mem::align_of::<T>())?;
+        self.bytes
+            .get(offset..offset.checked_add(mem::size_of::<T>())?)?;
+        Some(unsafe { &*(self.bytes.as_ptr().add(offset) as *const T) })
+    }
+
+    /// The list that starts at the start of the buffer, each entry
+    /// pointing at the next one with `link`.
+    pub fn list<T: Plain>(&self, link: fn(&T) -> *const T) -> Chain<'a, T> {
+        Chain::new(*self, Some(self.base), link)
+    }
+
+    /// The list that starts at `first`.
+    pub fn chain<T: Plain>(&self, first: *const T, link: fn(&T) -> *const T) -> Chain<'a, T> {
+        let first = match first.is_null() {
+            true => None,
+            false => Some(first as usize),
+        };
+        Chain::new(*self, first, link)
+    }
+
+    /// The NUL-terminated UTF-16 string at `ptr`, empty if `ptr` is null
+    /// and `None` if it does not end inside the buffer.
+    pub fn wide_string(&self, ptr: *const u16) -> Option<WideString> {
+        if ptr.is_null() {
+            return Some(WideString::default());
+        }
+        let offset = self.offset(ptr as usize, mem::align_of::<u16>())?;
+        let units: Vec<u16> = self.bytes[offset..]
+            .chunks_exact(2)
+            .map(|unit| u16::from_ne_bytes([unit[0], unit[1]]))
+            .take_while(|&unit| unit != 0)
+            .collect();
+        match offset + units.len() * 2 + 2 <= self.bytes.len() {
+            true => Some(WideString::from_wide(&units)),
+            false => None,
+        }
+    }
+
+    /// The NUL-terminated string at `ptr`, like
+    /// [`wide_string`](AdapterBuffer::wide_string).
+    pub fn c_string(&self, ptr: *const i8) -> Option<String> {
+        if ptr.is_null() {
+            return Some(String::new());
+        }
+        let offset = self.offset(ptr as usize, 1)?;
+        let string = CStr::from_bytes_until_nul(&self.bytes[offset..]).ok()?;
+        Some(string.to_string_lossy().into_owned())
+    }
+
+    /// The IPv4 or IPv6 address `addr` points at, with its length checked
+    /// against the family. The reason it could not be read otherwise.
+    pub fn socket_address(&self, addr: &SOCKET_ADDRESS) -> Result<SocketAddr, String> {
+        if addr.lpSockaddr.is_null() {
+            return Err("no address".to_owned());
+        }
+        let len = usize::try_from(addr.iSockaddrLength).unwrap_or(0);
+        let bytes = self
+            .slice(addr.lpSockaddr as usize, len)
+            .ok_or_else(|| "address outside the adapter buffer".to_owned())?;
+        let too_short = || format!("{} byte address is too short", len);
+        let family = match bytes {
+            [a, b, ..] => u16::from_ne_bytes([*a, *b]) as i32,
+            _ => return Err(too_short()),
+        };
+        match family {
+            AF_INET => {
+                let sin: SOCKADDR_IN = read(bytes).ok_or_else(too_short)?;
+                let ip = Ipv4Addr::from(u32::from_be(unsafe { *sin.sin_addr.S_un.S_addr() }));
+                Ok(SocketAddr::V4(SocketAddrV4::new(ip, 0)))
+            }
+            AF_INET6 => {
+                let sin6: SOCKADDR_IN6 = read(bytes).ok_or_else(too_short)?;
+                let ip = Ipv6Addr::from(unsafe { *sin6.sin6_addr.u.Byte() });
+                let scope_id = unsafe { *sin6.u.sin6_scope_id() };
+                Ok(SocketAddr::V6(SocketAddrV6::new(
+                    ip,
+                    0,
+                    sin6.sin6_flowinfo,
+                    scope_id,
+                )))
+            }
+            family => Err(format!("unsupported address family {}", family)),
+        }
+    }
+}
+
+/// A `T` from the start of `bytes`, which need not be aligned.
+fn read<T: Plain>(bytes: &[u8]) -> Option<T> {
+    match bytes.len() >= mem::size_of::<T>() {
+        true => Some(unsafe { ptr::read_unaligned(bytes.as_ptr() as *const T) }),
+        false => None,
+    }
+}
+
+/// The entries of a linked list in the buffer, see
+/// [`AdapterBuffer::chain`].
+///
+/// An entry that is not all in the buffer comes out as `None` and ends the
+/// list. So does going around more times than the buffer has room for
+/// entries, which only a list that loops back on itself does.
+pub(super) struct Chain<'a, T> {
+    buffer: AdapterBuffer<'a>,
+    next: Option<usize>,
+    left: usize,
+    link: fn(&T) -> *const T,
+    _entries: PhantomData<&'a T>,
+}
+
+impl<'a, T: Plain> Chain<'a, T> {
+    fn new(buffer: AdapterBuffer<'a>, first: Option<usize>, link: fn(&T) -> *const T) -> Self {
+        Chain {
+            buffer,
+            next: first,
+            left: buffer.bytes.len() / mem::size_of::<T>().max(1),
+            link,
+            _entries: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Plain> Iterator for Chain<'a, T> {
+    type Item = Option<&'a T>;
+
+    fn next(&mut self) -> Option<Option<&'a T>> {
+        let addr = self.next.take()?;
+        if self.left == 0 {
+            return Some(None);
+        }
+        self.left -= 1;
+        let entry = match self.buffer.at::<T>(addr) {
+            Some(entry) => entry,
+            None => return Some(None),
+        };
+        let next = (self.link)(entry);
+        if !next.is_null() {
+            self.next = Some(next as usize);
+        }
+        Some(Some(entry))
+    }
+}
@@ -0,0 +1,722 @@
+use std::net::{IpAddr, Ipv6Addr};
+use std::{io, mem, ptr};
+
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::netioapi::{
+    MIB_IPFORWARD_ROW2, MIB_IPINTERFACE_ROW, MIB_IPNET_ROW2, MIB_UNICASTIPADDRESS_ROW,
+};
+use winapi::shared::nldef::{NlnsIncomplete, NlnsPermanent, NlnsUnreachable};
+use winapi::shared::winerror::{
+    ERROR_FILE_NOT_FOUND, ERROR_NOT_FOUND, ERROR_OBJECT_ALREADY_EXISTS, ERROR_SUCCESS, NO_ERROR,
+};
+use winapi::shared::ws2def::{AF_INET, AF_INET6, AF_UNSPEC};
+use winapi::shared::ws2ipdef::SOCKADDR_INET;
+use winapi::um::winreg::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_DWORD};
+
+use super::iphlpapi::{
+    self, CreateIpForwardEntry2, CreateIpNetEntry2, DeleteIpForwardEntry2, DeleteIpNetEntry2,
+    DeleteUnicastIpAddressEntry, FreeMibTable, GetIpForwardTable2, GetIpInterfaceEntry,
+    GetIpNetEntry2, InitializeIpForwardEntry, InitializeIpInterfaceEntry,
+    InitializeUnicastIpAddressEntry, SetIpInterfaceEntry,
+};
+use super::AdapterFlags;
+use crate::{
+    validate_name, Ipv6Disabled, Ipv6DisabledEvidence, Kind, MacAddr, Route, TempAddrs, ValidName,
+    WolInfo, WolModes,
+};
+
+fn unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "promiscuous mode is not supported on Windows yet",
+    )
+}
+
+/// Returns whether promiscuous mode is switched on for the interface.
+///
+/// Windows keeps this behind the NDIS packet filter OID, which is not wired
+/// up yet, so this always fails with `ErrorKind::Unsupported`.
+pub fn promiscuous(_name: &str) -> io::Result<bool> {
+    Err(unsupported())
+}
+
+/// Switches promiscuous mode on or off and returns the previous state.
+///
+/// Not supported on Windows yet, see [`promiscuous`].
+pub fn set_promiscuous(_name: &str, _enabled: bool) -> io::Result<bool> {
+    Err(unsupported())
+}
+
+fn sockaddr_inet(addr: &IpAddr) -> SOCKADDR_INET {
+    let mut sa: SOCKADDR_INET = unsafe { mem::zeroed() };
+    unsafe {
+        match addr {
+            IpAddr::V4(addr) => {
+                let sin = sa.Ipv4_mut();
+                sin.sin_family = AF_INET as u16;
+                *sin.sin_addr.S_un.S_addr_mut() = u32::from_ne_bytes(addr.octets());
+            }
+            IpAddr::V6(addr) => {
+                let sin6 = sa.Ipv6_mut();
+                sin6.sin6_family = AF_INET6 as u16;
+                *sin6.sin6_addr.u.Byte_mut() = addr.octets();
+            }
+        }
+    }
+    sa
+}
+
+fn ip_of(sa: &SOCKADDR_INET) -> Option<IpAddr> {
+    unsafe {
+        match *sa.si_family() as i32 {
+            AF_INET => Some(IpAddr::from(sa.Ipv4().sin_addr.S_un.S_addr().to_ne_bytes())),
+            AF_INET6 => Some(IpAddr::from(*sa.Ipv6().sin6_addr.u.Byte())),
+            _ => None,
+        }
+    }
+}
+
+fn forward_row(route: &Route) -> io::Result<MIB_IPFORWARD_ROW2> {
+    let ifindex = route.ifindex.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Windows needs the interface of every route",
+        )
+    })?;
+    let mut row: MIB_IPFORWARD_ROW2 = unsafe { mem::zeroed() };
+    unsafe { InitializeIpForwardEntry(&mut row) };
+    row.InterfaceIndex = ifindex;
+    row.DestinationPrefix.Prefix = sockaddr_inet(&route.destination);
+    row.DestinationPrefix.PrefixLength = route.prefix_len;
+    // The unspecified address of the family makes an on-link route.
+    row.NextHop = match route.gateway {
+        Some(gateway) => sockaddr_inet(&gateway),
+        None => sockaddr_inet(&match route.destination {
+            IpAddr::V4(_) => IpAddr::from([0u8; 4]),
+            IpAddr::V6(_) => IpAddr::from([0u8; 16]),
+        }),
+    };
+    if let Some(metric) = route.metric {
+        row.Metric = metric;
+    }
+    Ok(row)
+}
+
+pub(super) fn netio_result(ret_code: u32) -> io::Result<()> {
+    let err = iphlpapi::os_error(ret_code);
+    match ret_code {
+        NO_ERROR => Ok(()),
+        ERROR_OBJECT_ALREADY_EXISTS => Err(io::Error::new(io::ErrorKind::AlreadyExists, err)),
+        ERROR_NOT_FOUND => Err(io::Error::new(io::ErrorKind::NotFound, err)),
+        _ => Err(err),
+    }
+}
+
+/// Installs `route` with `CreateIpForwardEntry2`.
+///
+/// Every route needs `ifindex` on Windows; ones without a gateway are
+/// on-link. Fails with `ErrorKind::AlreadyExists` if the same route is
+/// already there. `table` is ignored, Windows has a single table.
+pub fn add_route(route: &Route) -> io::Result<()> {
+    route.validate()?;
+    let row = forward_row(route)?;
+    netio_result(unsafe { CreateIpForwardEntry2(&row) })
+}
+
+/// Removes `route` with `DeleteIpForwardEntry2`.
+pub fn remove_route(route: &Route) -> io::Result<()> {
+    route.validate()?;
+    let row = forward_row(route)?;
+    netio_result(unsafe { DeleteIpForwardEntry2(&row) })
+}
+
+/// Returns the label of interface `name`. On Windows that is the friendly
+/// name, which the crate uses as the name, so this is `Some(name)` for
+/// every adapter that exists.
+pub fn alias(name: &str) -> io::Result<Option<String>> {
+    luid_of(&validate_name(name)?)?;
+    Ok(Some(name.to_owned()))
+}
+
+/// Renaming adapters needs the shell's connection objects; there is no
+/// IP Helper call for it, so this fails with `ErrorKind::Unsupported`.
+pub fn set_alias(_name: &str, _text: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "renaming adapters is not supported on Windows",
+    ))
+}
+
+fn wol_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Wake-on-LAN settings are not supported on Windows yet",
+    )
+}
+
+/// Returns the Wake-on-LAN triggers of interface `name`.
+///
+/// Windows keeps these behind the NDIS power management OIDs, which are not
+/// wired up yet, so this always fails with `ErrorKind::Unsupported`.
+pub fn wol(_name: &str) -> io::Result<WolInfo> {
+    Err(wol_unsupported())
+}
+
+/// Switches Wake-on-LAN triggers. Not supported on Windows yet, see
+/// [`wol`].
+pub fn set_wol(_name: &str, _modes: WolModes) -> io::Result<()> {
+    Err(wol_unsupported())
+}
+
+fn net_row(index: u32, ip: &IpAddr) -> MIB_IPNET_ROW2 {
+    let mut row: MIB_IPNET_ROW2 = unsafe { mem::zeroed() };
+    row.Address = sockaddr_inet(ip);
+    row.InterfaceIndex = index;
+    row
+}
+
+/// Installs a permanent neighbor entry mapping `ip` to `mac` on interface
+/// `index` with `CreateIpNetEntry2`.
+pub(crate) fn install_neighbor(index: u32, ip: IpAddr, mac: MacAddr) -> io::Result<()> {
+    netio_result(unsafe { CreateIpNetEntry2(&permanent_row(index, &ip, mac)) })
+}
+
+fn permanent_row(index: u32, ip: &IpAddr, mac: MacAddr) -> MIB_IPNET_ROW2 {
+    let mut row = net_row(index, ip);
+    row.PhysicalAddress[..6].copy_from_slice(&mac.octets());
+    row.PhysicalAddressLength = 6;
+    row.State = NlnsPermanent;
+    row
+}
+
+/// Deletes the neighbor entry of `ip` on interface `index` with
+/// `DeleteIpNetEntry2`.
+pub(crate) fn delete_neighbor(index: u32, ip: IpAddr) -> io::Result<()> {
+    netio_result(unsafe { DeleteIpNetEntry2(&net_row(index, &ip)) })
+}
+
+/// The next hop and interface of the default route of `family` with
+/// `GetIpForwardTable2`, the one with the lowest route metric if there
+/// are several.
+pub(crate) fn default_gateway(family: Kind) -> io::Result<Option<(IpAddr, u32)>> {
+    let family = match family {
+        Kind::Ipv4 => AF_INET,
+        Kind::Ipv6 => AF_INET6,
+        _ => AF_UNSPEC,
+    };
+    let mut table = std::ptr::null_mut();
+    netio_result(unsafe { GetIpForwardTable2(family as u16, &mut table) })?;
+    let rows = unsafe {
+        std::slice::from_raw_parts((*table).Table.as_ptr(), (*table).NumEntries as usize)
+    };
+    let best = rows
+        .iter()
+        .filter(|row| row.DestinationPrefix.PrefixLength == 0)
+        .filter_map(|row| {
+            let gateway = ip_of(&row.NextHop).filter(|ip| !ip.is_unspecified())?;
+            Some((row.Metric, gateway, row.InterfaceIndex))
+        })
+        .min_by_key(|&(metric, _, _)| metric)
+        .map(|(_, gateway, index)| (gateway, index));
+    unsafe { FreeMibTable(table as _) };
+    Ok(best)
+}
+
+/// The MAC address of the neighbor entry of `ip` on interface `index`
+/// with `GetIpNetEntry2`, `None` while it is unresolved.
+pub(crate) fn neighbor_mac(index: u32, ip: IpAddr) -> io::Result<Option<MacAddr>> {
+    let mut row = net_row(index, &ip);
+    match netio_result(unsafe { GetIpNetEntry2(&mut row) }) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    if row.State == NlnsIncomplete || row.State == NlnsUnreachable {
+        return Ok(None);
+    }
+    Ok(row
+        .PhysicalAddress
+        .get(..row.PhysicalAddressLength as usize)
+        .and_then(MacAddr::from_hardware_addr))
+}
+
+fn vlans_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "creating VLANs is not supported on Windows yet",
+    )
+}
+
+/// VLANs on Windows are a property of the NIC driver, set through its
+/// advanced settings, so this fails with `ErrorKind::Unsupported`.
+pub(crate) fn add_vlan(_parent: u32, _vlan_id: u16, _name: &ValidName) -> io::Result<u32> {
+    Err(vlans_unsupported())
+}
+
+pub(crate) fn delete_vlan(_index: u32) -> io::Result<()> {
+    Err(vlans_unsupported())
+}
+
+fn bridges_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "creating bridges is not supported on Windows yet",
+    )
+}
+
+/// Network bridges on Windows are made through the shell's connection
+/// objects, not the IP Helper, so these fail with `ErrorKind::Unsupported`.
+pub(crate) fn add_bridge(_name: &ValidName) -> io::Result<u32> {
+    Err(bridges_unsupported())
+}
+
+pub(crate) fn delete_bridge(_index: u32) -> io::Result<()> {
+    Err(bridges_unsupported())
+}
+
+pub(crate) fn add_bridge_port(_bridge: u32, _port: u32) -> io::Result<()> {
+    Err(bridges_unsupported())
+}
+
+pub(crate) fn remove_bridge_port(_bridge: u32, _port: u32) -> io::Result<()> {
+    Err(bridges_unsupported())
+}
+
+/// The `NET_LUID` of the adapter called `name`.
+fn luid_of(name: &ValidName) -> io::Result<u64> {
+    crate::ifaces()?
+        .into_iter()
+        .find(|iface| name.matches(&iface.name))
+        .map(|iface| iface.os_specific().luid)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such interface"))
+}
+
+/// Removes the address `addr/prefix_len` from interface `name` with
+/// `DeleteUnicastIpAddressEntry`.
+///
+/// Fails with `ErrorKind::NotFound` if the interface does not have it.
+pub fn remove_address(name: &str, addr: IpAddr, prefix_len: u8) -> io::Result<()> {
+    let mut row: MIB_UNICASTIPADDRESS_ROW = unsafe { mem::zeroed() };
+    unsafe { InitializeUnicastIpAddressEntry(&mut row) };
+    row.Address = sockaddr_inet(&addr);
+    row.InterfaceLuid.Value = luid_of(&validate_name(name)?)?;
+    row.OnLinkPrefixLength = prefix_len;
+    netio_result(unsafe { DeleteUnicastIpAddressEntry(&row) })
+}
+
+/// Sets the metric of interface `name` for `family`, `Ipv4` or `Ipv6`, and
+/// switches its automatic metric off.
+///
+/// The interface row is read with `GetIpInterfaceEntry` and written back
+/// with only the metric changed, so every other setting stays as it was.
+pub fn set_metric(name: &str, family: Kind, metric: u32) -> io::Result<()> {
+    let family = match family {
+        Kind::Ipv4 => AF_INET,
+        Kind::Ipv6 => AF_INET6,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the family must be Ipv4 or Ipv6",
+            ))
+        }
+    };
+    update_metric(
+        luid_of(&validate_name(name)?)?,
+        family as u16,
+        metric,
+        |row| unsafe { GetIpInterfaceEntry(row) },
+        |row| unsafe { SetIpInterfaceEntry(row) },
+    )
+}
+
+/// The read-modify-write of [`set_metric`], with the two calls passed in.
+fn update_metric(
+    luid: u64,
+    family: u16,
+    metric: u32,
+    get: impl FnOnce(&mut MIB_IPINTERFACE_ROW) -> u32,
+    set: impl FnOnce(&mut MIB_IPINTERFACE_ROW) -> u32,
+) -> io::Result<()> {
+    let mut row: MIB_IPINTERFACE_ROW = unsafe { mem::zeroed() };
+    unsafe { InitializeIpInterfaceEntry(&mut row) };
+    row.Family = family;
+    row.InterfaceLuid.Value = luid;
+    netio_result(get(&mut row))?;
+
+    row.Metric = metric;
+    row.UseAutomaticMetric = 0;
+    // The row reads back with the site prefix length set, but writing it
+    // is rejected for IPv4.
+    if family == AF_INET as u16 {
+        row.SitePrefixLength = 0;
+    }
+    netio_result(set(&mut row))
+}
+
+fn tokens_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Windows has no IPv6 interface tokens",
+    )
+}
+
+/// Windows makes interface identifiers of its own, randomized or from
+/// the MAC address, and has no setting for a fixed one, so this fails
+/// with `ErrorKind::Unsupported`.
+pub fn ipv6_token(_name: &str) -> io::Result<Option<Ipv6Addr>> {
+    Err(tokens_unsupported())
+}
+
+/// Not supported on Windows, see [`ipv6_token`].
+pub fn set_ipv6_token(_name: &str, _token: Ipv6Addr) -> io::Result<()> {
+    Err(tokens_unsupported())
+}
+
+fn temp_addrs_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Windows has no interface setting for temporary addresses",
+    )
+}
+
+/// Returns whether interface `name` makes temporary IPv6 addresses.
+///
+/// The IP interface row has no such setting, and the global one behind
+/// `netsh interface ipv6 set privacy` has no public API, so this fails with
+/// `ErrorKind::Unsupported`.
+pub fn temp_addrs(_name: &str) -> io::Result<TempAddrs> {
+    Err(temp_addrs_unsupported())
+}
+
+/// Sets whether interface `name` makes temporary IPv6 addresses. Not
+/// supported on Windows, see [`temp_addrs`].
+pub fn set_temp_addrs(_name: &str, _mode: TempAddrs) -> io::Result<()> {
+    Err(temp_addrs_unsupported())
+}
+
+const TCPIP6_PARAMETERS: &str = "SYSTEM\\CurrentControlSet\\Services\\Tcpip6\\Parameters";
+const IF_TYPE_SOFTWARE_LOOPBACK: u32 = 24;
+const IF_TYPE_TUNNEL: u32 = 131;
+
+/// Whether IPv6 is off on adapter `name`, see
+/// [`ipv6_disabled_evidence`](crate::ipv6_disabled_evidence).
+pub(crate) fn ipv6_disabled(name: &str) -> io::Result<Option<Ipv6Disabled>> {
+    let name = validate_name(name)?;
+    let ifaces = crate::ifaces()?;
+    let os = match ifaces.iter().find(|iface| name.matches(&iface.name)) {
+        Some(iface) => iface.os_specific(),
+        None => return Err(io::Error::new(io::ErrorKind::NotFound, "no such adapter")),
+    };
+    let components = disabled_components()?;
+    let enabled = os.adapter_flags.contains(AdapterFlags::IPV6_ENABLED);
+    Ok(Some(Ipv6Disabled {
+        disabled: !enabled || disabled_by_components(components, os.if_type),
+        evidence: vec![
+            Ipv6DisabledEvidence::DisabledComponents(components),
+            Ipv6DisabledEvidence::AdapterIpv6Enabled(enabled),
+        ],
+    }))
+}
+
+/// The `DisabledComponents` registry value, 0 when it is not set.
+fn disabled_components() -> io::Result<u32> {
+    let key = wide(TCPIP6_PARAMETERS);
+    let value = wide("DisabledComponents");
+    let mut data: DWORD = 0;
+    let mut size = mem::size_of::<DWORD>() as DWORD;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key.as_ptr(),
+            value.as_ptr(),
+            RRF_RT_REG_DWORD,
+            ptr::null_mut(),
+            &mut data as *mut DWORD as *mut _,
+            &mut size,
+        )
+    };
+    match status as u32 {
+        ERROR_SUCCESS => Ok(data),
+        ERROR_FILE_NOT_FOUND => Ok(0),
+        code => Err(iphlpapi::os_error(code)),
+    }
+}
+
+/// Whether `DisabledComponents` turns IPv6 off on adapters of `if_type`:
+/// bit `0x01` covers tunnels, bit `0x10` everything else but loopback,
+/// which keeps IPv6 whatever the value. The other bits only change
+/// preferences.
+fn disabled_by_components(components: u32, if_type: u32) -> bool {
+    match if_type {
+        IF_TYPE_SOFTWARE_LOOPBACK => false,
+        IF_TYPE_TUNNEL => components & 0x01 != 0,
+        _ => components & 0x10 != 0,
+    }
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(Some(0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::shared::winerror::ERROR_ACCESS_DENIED;
+
+    use super::*;
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    #[test]
+    fn socket_addresses_round_trip() {
+        for addr in ["192.0.2.1", "0.0.0.0", "2001:db8::1", "::"] {
+            assert_eq!(ip_of(&sockaddr_inet(&ip(addr))), Some(ip(addr)));
+        }
+        let unspecified: SOCKADDR_INET = unsafe { mem::zeroed() };
+        assert_eq!(ip_of(&unspecified), None);
+    }
+
+    #[test]
+    fn forward_rows_hold_the_route() {
+        let route = Route::new(ip("10.0.0.0"), 8)
+            .via(ip("192.0.2.1"))
+            .on(4)
+            .with_metric(25);
+        let row = forward_row(&route).unwrap();
+        assert_eq!(row.InterfaceIndex, 4);
+        assert_eq!(ip_of(&row.DestinationPrefix.Prefix), Some(ip("10.0.0.0")));
+        assert_eq!(row.DestinationPrefix.PrefixLength, 8);
+        assert_eq!(ip_of(&row.NextHop), Some(ip("192.0.2.1")));
+        assert_eq!(row.Metric, 25);
+    }
+
+    #[test]
+    fn on_link_rows_have_an_unspecified_next_hop() {
+        let row = forward_row(&Route::host(ip("2001:db8::7")).on(4)).unwrap();
+        assert_eq!(row.DestinationPrefix.PrefixLength, 128);
+        assert_eq!(ip_of(&row.NextHop), Some(ip("::")));
+        let row = forward_row(&Route::new(ip("198.51.100.0"), 24).on(4)).unwrap();
+        assert_eq!(ip_of(&row.NextHop), Some(ip("0.0.0.0")));
+    }
+
+    #[test]
+    fn routes_need_an_interface() {
+        let route = Route::new(ip("10.0.0.0"), 8).via(ip("192.0.2.1"));
+        let err = forward_row(&route).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn netio_errors_keep_their_meaning() {
+        assert!(netio_result(NO_ERROR).is_ok());
+        let kind = |code| netio_result(code).unwrap_err().kind();
+        assert_eq!(
+            kind(ERROR_OBJECT_ALREADY_EXISTS),
+            io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(kind(ERROR_NOT_FOUND), io::ErrorKind::NotFound);
+        let err = netio_result(ERROR_FILE_NOT_FOUND).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(ERROR_FILE_NOT_FOUND as i32));
+    }
+
+    /// What the fake `GetIpInterfaceEntry` fills in.
+    fn read_back(row: &mut MIB_IPINTERFACE_ROW) -> u32 {
+        row.InterfaceIndex = 4;
+        row.Metric = 35;
+        row.UseAutomaticMetric = 1;
+        row.NlMtu = 1400;
+        row.SitePrefixLength = 64;
+        row.ForwardingEnabled = 1;
+        row.RouterDiscoveryBehavior = 2;
+        NO_ERROR
+    }
+
+    #[test]
+    fn metrics_are_written_over_the_row_as_read() {
+        let mut written = None;
+        update_metric(0x1234, AF_INET6 as u16, 5, read_back, |row| {
+            written = Some(*row);
+            NO_ERROR
+        })
+        .unwrap();
+        let row = written.unwrap();
+        assert_eq!(row.InterfaceLuid.Value, 0x1234);
+        assert_eq!(row.Family, AF_INET6 as u16);
+        assert_eq!((row.Metric, row.UseAutomaticMetric), (5, 0));
+        // Everything else is as it was read.
+        assert_eq!(row.InterfaceIndex, 4);
+        assert_eq!(row.NlMtu, 1400);
+        assert_eq!(row.SitePrefixLength, 64);
+        assert_eq!(row.ForwardingEnabled, 1);
+        assert_eq!(row.RouterDiscoveryBehavior, 2);
+    }
+
+    #[test]
+    fn ipv4_rows_are_written_without_the_site_prefix_length() {
+        let mut written = None;
+        update_metric(0x1234, AF_INET as u16, 5, read_back, |row| {
+            written = Some(*row);
+            NO_ERROR
+        })
+        .unwrap();
+        let row = written.unwrap();
+        assert_eq!(row.SitePrefixLength, 0);
+        assert_eq!(row.NlMtu, 1400);
+    }
+
+    #[test]
+    fn failed_reads_write_nothing() {
+        let mut wrote = false;
+        let err = update_metric(
+            0x1234,
+            AF_INET as u16,
+            5,
+            |_| ERROR_NOT_FOUND,
+            |_| {
+                wrote = true;
+                NO_ERROR
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(!wrote);
+
+        let err = update_metric(0x1234, AF_INET as u16, 5, read_back, |_| {
+            ERROR_ACCESS_DENIED
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn metrics_are_set_on_this_machine() {
+        let iface = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| {
+                iface.kind == Kind::Ipv4 && iface.flags.contains(crate::InterfaceFlags::LOOPBACK)
+            })
+            .unwrap();
+        let luid = iface.os_specific().luid;
+        let mut before: MIB_IPINTERFACE_ROW = unsafe { mem::zeroed() };
+        unsafe { InitializeIpInterfaceEntry(&mut before) };
+        before.Family = AF_INET as u16;
+        before.InterfaceLuid.Value = luid;
+        netio_result(unsafe { GetIpInterfaceEntry(&mut before) }).unwrap();
+        match set_metric(&iface.name, Kind::Ipv4, before.Metric) {
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                eprintln!("not elevated, skipping: {}", err);
+                return;
+            }
+            result => result.unwrap(),
+        }
+        // Put the automatic metric back if it was on.
+        if before.UseAutomaticMetric != 0 {
+            before.SitePrefixLength = 0;
+            netio_result(unsafe { SetIpInterfaceEntry(&mut before) }).unwrap();
+        }
+    }
+
+    #[test]
+    fn aliases_are_the_friendly_names() {
+        let lo = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(crate::InterfaceFlags::LOOPBACK))
+            .unwrap();
+        assert_eq!(alias(&lo.name).unwrap(), Some(lo.name.clone()));
+        let err = alias("No Such Adapter").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = set_alias(&lo.name, "uplink to rack 4").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn wol_is_unsupported() {
+        let err = wol("Ethernet").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        let err = set_wol("Ethernet", WolModes::MAGIC).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn temp_addrs_are_unsupported() {
+        let err = temp_addrs("Ethernet").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        let err = set_temp_addrs("Ethernet", TempAddrs::Enabled).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn neighbor_rows_are_permanent_and_carry_the_mac() {
+        let mac = MacAddr::new([0x02, 0, 0, 0, 0, 0x01]);
+        let row = permanent_row(4, &ip("192.0.2.9"), mac);
+        assert_eq!(row.InterfaceIndex, 4);
+        assert_eq!(ip_of(&row.Address), Some(ip("192.0.2.9")));
+        assert_eq!(row.State, NlnsPermanent);
+        assert_eq!(row.PhysicalAddressLength, 6);
+        assert_eq!(&row.PhysicalAddress[..6], &mac.octets()[..]);
+
+        let row = net_row(4, &ip("2001:db8::9"));
+        assert_eq!(ip_of(&row.Address), Some(ip("2001:db8::9")));
+        assert_eq!(row.PhysicalAddressLength, 0);
+    }
+
+    #[test]
+    fn disabled_components_decode_by_adapter_type() {
+        const ETHERNET: u32 = 6;
+        for (components, ethernet, tunnel) in [
+            (0x00, false, false),
+            (0x01, false, true),
+            (0x10, true, false),
+            (0x11, true, true),
+            // Prefer IPv4 over IPv6, nothing turned off.
+            (0x20, false, false),
+            (0xff, true, true),
+            (0xffff_ffff, true, true),
+        ] {
+            assert_eq!(
+                disabled_by_components(components, ETHERNET),
+                ethernet,
+                "{:#x}",
+                components
+            );
+            assert_eq!(
+                disabled_by_components(components, IF_TYPE_TUNNEL),
+                tunnel,
+                "{:#x}",
+                components
+            );
+            assert!(!disabled_by_components(
+                components,
+                IF_TYPE_SOFTWARE_LOOPBACK
+            ));
+        }
+    }
+
+    #[test]
+    fn registry_names_are_nul_terminated() {
+        assert_eq!(wide("Tcpip6"), [84, 99, 112, 105, 112, 54, 0]);
+        assert_eq!(wide(""), [0]);
+    }
+
+    #[test]
+    fn ipv6_disabled_is_read_on_this_machine() {
+        let lo = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(crate::InterfaceFlags::LOOPBACK))
+            .unwrap();
+        let status = ipv6_disabled(&lo.name).unwrap().unwrap();
+        match status.evidence[..] {
+            [Ipv6DisabledEvidence::DisabledComponents(_), Ipv6DisabledEvidence::AdapterIpv6Enabled(enabled)] =>
+            {
+                assert_eq!(status.disabled, !enabled)
+            }
+            _ => panic!("{:?}", status.evidence),
+        }
+        let err = ipv6_disabled("No Such Adapter").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
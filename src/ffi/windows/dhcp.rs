@@ -0,0 +1,186 @@
+use std::ffi::CStr;
+use std::net::Ipv4Addr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{io, mem};
+
+use winapi::shared::minwindef::ULONG;
+use winapi::shared::winerror::{ERROR_BUFFER_OVERFLOW, ERROR_NO_DATA, ERROR_SUCCESS};
+use winapi::ucrt::corecrt::time_t;
+use winapi::um::iptypes::IP_ADAPTER_INFO;
+
+use super::iphlpapi::{self, GetAdaptersInfo};
+use crate::Interface;
+
+/// The DHCPv4 lease an adapter holds, see [`dhcp_lease`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct DhcpLease {
+    pub obtained: SystemTime,
+    pub expires: SystemTime,
+    pub server: Ipv4Addr,
+}
+
+impl DhcpLease {
+    /// Whether the lease ran out, which happens while the DHCP server is
+    /// unreachable.
+    pub fn is_expired(&self) -> bool {
+        self.expires <= SystemTime::now()
+    }
+}
+
+/// The DHCPv4 lease of the adapter behind `iface`.
+///
+/// `GetAdaptersAddresses` does not report leases, so this looks the adapter
+/// up by its GUID in `GetAdaptersInfo`. `None` if the adapter does not use
+/// DHCP, is not in that list, or reports no lease times (0 or -1).
+pub fn dhcp_lease(iface: &Interface) -> io::Result<Option<DhcpLease>> {
+    let buffer = adapters_info()?;
+    let mut info = match buffer.is_empty() {
+        true => std::ptr::null(),
+        false => buffer.as_ptr() as *const IP_ADAPTER_INFO,
+    };
+
+    while !info.is_null() {
+        let adapter = unsafe { &*info };
+        let name = unsafe { CStr::from_ptr(adapter.AdapterName.as_ptr()) };
+        if name.to_string_lossy() == iface.os_specific().adapter_name {
+            return Ok(lease_of(adapter));
+        }
+        info = adapter.Next;
+    }
+    Ok(None)
+}
+
+fn lease_of(adapter: &IP_ADAPTER_INFO) -> Option<DhcpLease> {
+    if adapter.DhcpEnabled == 0 {
+        return None;
+    }
+    let server = unsafe { CStr::from_ptr(adapter.DhcpServer.IpAddress.String.as_ptr()) };
+    Some(DhcpLease {
+        obtained: system_time(adapter.LeaseObtained)?,
+        expires: system_time(adapter.LeaseExpires)?,
+        server: server
+            .to_str()
+            .ok()
+            .and_then(|server| server.parse().ok())
+            .unwrap_or(Ipv4Addr::UNSPECIFIED),
+    })
+}
+
+/// Convert a `time_t`. Windows uses 0 and -1 for "no time", and nothing
+/// before 1970 is a lease time either.
+fn system_time(time: time_t) -> Option<SystemTime> {
+    match time {
+        time if time <= 0 => None,
+        time => Some(UNIX_EPOCH + Duration::from_secs(time as u64)),
+    }
+}
+
+fn adapters_info() -> io::Result<Vec<u64>> {
+    let mut buffer: Vec<u64> = Vec::new();
+    let mut length: ULONG = 0;
+    loop {
+        let ret = unsafe {
+            GetAdaptersInfo(
+                match buffer.capacity() {
+                    0 => std::ptr::null_mut(),
+                    _ => buffer.as_mut_ptr() as *mut IP_ADAPTER_INFO,
+                },
+                &mut length,
+            )
+        };
+        match ret {
+            ERROR_SUCCESS => {
+                unsafe { buffer.set_len(buffer.capacity()) };
+                return Ok(buffer);
+            }
+            ERROR_NO_DATA => return Ok(Vec::new()),
+            ERROR_BUFFER_OVERFLOW => {
+                buffer = Vec::with_capacity((length as usize).div_ceil(mem::size_of::<u64>()));
+            }
+            _ => return Err(iphlpapi::os_error(ret)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(enabled: bool, obtained: time_t, expires: time_t, server: &str) -> IP_ADAPTER_INFO {
+        let mut adapter: IP_ADAPTER_INFO = unsafe { mem::zeroed() };
+        adapter.DhcpEnabled = enabled as _;
+        adapter.LeaseObtained = obtained;
+        adapter.LeaseExpires = expires;
+        for (dst, src) in adapter
+            .DhcpServer
+            .IpAddress
+            .String
+            .iter_mut()
+            .zip(server.bytes())
+        {
+            *dst = src as _;
+        }
+        adapter
+    }
+
+    #[test]
+    fn lease_times_are_seconds_since_the_epoch() {
+        assert_eq!(system_time(1), Some(UNIX_EPOCH + Duration::from_secs(1)));
+        assert_eq!(
+            system_time(1_700_000_000),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert_eq!(system_time(0), None);
+        assert_eq!(system_time(-1), None);
+        assert_eq!(system_time(-86_400), None);
+    }
+
+    #[test]
+    fn leases_are_read_from_the_adapter() {
+        let lease = lease_of(&adapter(true, 1_700_000_000, 1_700_086_400, "192.0.2.1")).unwrap();
+        assert_eq!(
+            lease.obtained,
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        );
+        assert_eq!(
+            lease.expires,
+            UNIX_EPOCH + Duration::from_secs(1_700_086_400)
+        );
+        assert_eq!(lease.server, Ipv4Addr::new(192, 0, 2, 1));
+        // Long gone by now.
+        assert!(lease.is_expired());
+    }
+
+    #[test]
+    fn current_leases_are_not_expired() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as time_t;
+        let lease = lease_of(&adapter(true, now - 60, now + 3600, "192.0.2.1")).unwrap();
+        assert!(!lease.is_expired());
+    }
+
+    #[test]
+    fn adapters_without_a_lease_have_none() {
+        assert_eq!(lease_of(&adapter(false, 1, 2, "192.0.2.1")), None);
+        assert_eq!(lease_of(&adapter(true, 0, 2, "192.0.2.1")), None);
+        assert_eq!(lease_of(&adapter(true, 1, -1, "192.0.2.1")), None);
+    }
+
+    #[test]
+    fn unparsable_servers_are_unspecified() {
+        let lease = lease_of(&adapter(true, 1, 2, "")).unwrap();
+        assert_eq!(lease.server, Ipv4Addr::UNSPECIFIED);
+    }
+
+    #[test]
+    fn loopback_has_no_lease() {
+        let lo = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(crate::InterfaceFlags::LOOPBACK))
+            .unwrap();
+        assert_eq!(dhcp_lease(&lo).unwrap(), None);
+    }
+}
@@ -0,0 +1,234 @@
+use std::{io, mem};
+
+use winapi::shared::netioapi::MIB_IPINTERFACE_ROW;
+use winapi::shared::ws2def::{AF_INET, AF_INET6};
+
+use super::config::netio_result;
+use super::iphlpapi::{GetIpInterfaceEntry, InitializeIpInterfaceEntry};
+use crate::Kind;
+
+/// `RouterDiscoveryBehavior`: whether the interface takes router
+/// advertisements, or DHCP.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RouterDiscovery {
+    Disabled,
+    Enabled,
+    /// Set by DHCP, the default for IPv4.
+    Dhcp,
+    Unknown(u32),
+}
+
+impl From<u32> for RouterDiscovery {
+    fn from(value: u32) -> RouterDiscovery {
+        match value {
+            0 => RouterDiscovery::Disabled,
+            1 => RouterDiscovery::Enabled,
+            2 => RouterDiscovery::Dhcp,
+            other => RouterDiscovery::Unknown(other),
+        }
+    }
+}
+
+/// `LinkLocalAddressBehavior`: when the interface gives itself a
+/// link-local address.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum LinkLocalBehavior {
+    AlwaysOff,
+    /// Only once no other address came within the link-local timeout, the
+    /// APIPA behavior of IPv4.
+    Delayed,
+    AlwaysOn,
+    Unknown(u32),
+}
+
+impl From<u32> for LinkLocalBehavior {
+    fn from(value: u32) -> LinkLocalBehavior {
+        match value {
+            0 => LinkLocalBehavior::AlwaysOff,
+            1 => LinkLocalBehavior::Delayed,
+            2 => LinkLocalBehavior::AlwaysOn,
+            other => LinkLocalBehavior::Unknown(other),
+        }
+    }
+}
+
+/// The IPv4 or IPv6 settings of an interface, see
+/// [`ip_interface_settings`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct IpInterfaceSettings {
+    pub family: Kind,
+    pub index: u32,
+    pub luid: u64,
+    /// The MTU of the family, which can differ from the adapter's.
+    pub mtu: u32,
+    pub metric: u32,
+    pub automatic_metric: bool,
+    pub router_discovery: RouterDiscovery,
+    /// How many neighbor solicitations duplicate address detection sends,
+    /// 0 for none.
+    pub dad_transmits: u32,
+    pub link_local: LinkLocalBehavior,
+    pub forwarding: bool,
+    /// Whether packets may go out with a source address of another
+    /// interface.
+    pub weak_host_send: bool,
+    /// Whether packets for addresses of other interfaces are taken.
+    pub weak_host_receive: bool,
+    pub connected: bool,
+}
+
+impl From<&MIB_IPINTERFACE_ROW> for IpInterfaceSettings {
+    fn from(row: &MIB_IPINTERFACE_ROW) -> IpInterfaceSettings {
+        IpInterfaceSettings {
+            family: match row.Family as i32 {
+                AF_INET => Kind::Ipv4,
+                AF_INET6 => Kind::Ipv6,
+                _ => Kind::Unknown,
+            },
+            index: row.InterfaceIndex,
+            luid: row.InterfaceLuid.Value,
+            mtu: row.NlMtu,
+            metric: row.Metric,
+            automatic_metric: row.UseAutomaticMetric != 0,
+            router_discovery: row.RouterDiscoveryBehavior.into(),
+            dad_transmits: row.DadTransmits,
+            link_local: row.LinkLocalAddressBehavior.into(),
+            forwarding: row.ForwardingEnabled != 0,
+            weak_host_send: row.WeakHostSend != 0,
+            weak_host_receive: row.WeakHostReceive != 0,
+            connected: row.Connected != 0,
+        }
+    }
+}
+
+/// The `family` settings, `Ipv4` or `Ipv6`, of interface `index`, read
+/// with `GetIpInterfaceEntry`.
+///
+/// This is the per-family configuration `GetAdaptersAddresses` does not
+/// report. Fails with `ErrorKind::NotFound` if the interface does not
+/// exist or the family is not bound to it. The hop limit is not part of
+/// the row, and so not read.
+pub fn ip_interface_settings(index: u32, family: Kind) -> io::Result<IpInterfaceSettings> {
+    let family = match family {
+        Kind::Ipv4 => AF_INET,
+        Kind::Ipv6 => AF_INET6,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the family must be Ipv4 or Ipv6",
+            ))
+        }
+    };
+    let mut row: MIB_IPINTERFACE_ROW = unsafe { mem::zeroed() };
+    unsafe { InitializeIpInterfaceEntry(&mut row) };
+    row.Family = family as u16;
+    row.InterfaceIndex = index;
+    netio_result(unsafe { GetIpInterfaceEntry(&mut row) })?;
+    Ok(IpInterfaceSettings::from(&row))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::InterfaceFlags;
+
+    /// The IPv6 row of a typical Ethernet adapter.
+    fn ethernet_row() -> MIB_IPINTERFACE_ROW {
+        let mut row: MIB_IPINTERFACE_ROW = unsafe { mem::zeroed() };
+        row.Family = AF_INET6 as u16;
+        row.InterfaceIndex = 12;
+        row.InterfaceLuid.Value = 0x0006_0000_0100_0000;
+        row.NlMtu = 1500;
+        row.Metric = 25;
+        row.UseAutomaticMetric = 1;
+        row.RouterDiscoveryBehavior = 1;
+        row.DadTransmits = 1;
+        row.LinkLocalAddressBehavior = 2;
+        row.Connected = 1;
+        row
+    }
+
+    #[test]
+    fn rows_are_converted() {
+        let settings = IpInterfaceSettings::from(&ethernet_row());
+        assert_eq!(
+            settings,
+            IpInterfaceSettings {
+                family: Kind::Ipv6,
+                index: 12,
+                luid: 0x0006_0000_0100_0000,
+                mtu: 1500,
+                metric: 25,
+                automatic_metric: true,
+                router_discovery: RouterDiscovery::Enabled,
+                dad_transmits: 1,
+                link_local: LinkLocalBehavior::AlwaysOn,
+                forwarding: false,
+                weak_host_send: false,
+                weak_host_receive: false,
+                connected: true,
+            }
+        );
+    }
+
+    #[test]
+    fn ipv4_rows_keep_their_behaviors() {
+        // A forwarding IPv4 row with APIPA and router discovery from DHCP.
+        let mut row = ethernet_row();
+        row.Family = AF_INET as u16;
+        row.RouterDiscoveryBehavior = 2;
+        row.LinkLocalAddressBehavior = 1;
+        row.DadTransmits = 0;
+        row.ForwardingEnabled = 1;
+        row.WeakHostSend = 1;
+        row.WeakHostReceive = 1;
+        row.Connected = 0;
+        let settings = IpInterfaceSettings::from(&row);
+        assert_eq!(settings.family, Kind::Ipv4);
+        assert_eq!(settings.router_discovery, RouterDiscovery::Dhcp);
+        assert_eq!(settings.link_local, LinkLocalBehavior::Delayed);
+        assert_eq!(settings.dad_transmits, 0);
+        assert!(settings.forwarding && settings.weak_host_send && settings.weak_host_receive);
+        assert!(!settings.connected);
+    }
+
+    #[test]
+    fn unknown_values_are_kept() {
+        assert_eq!(RouterDiscovery::from(0), RouterDiscovery::Disabled);
+        // RouterDiscoveryUnchanged, which only setting uses.
+        assert_eq!(
+            RouterDiscovery::from(u32::MAX),
+            RouterDiscovery::Unknown(u32::MAX)
+        );
+        assert_eq!(LinkLocalBehavior::from(0), LinkLocalBehavior::AlwaysOff);
+        assert_eq!(LinkLocalBehavior::from(3), LinkLocalBehavior::Unknown(3));
+        let mut row = ethernet_row();
+        row.Family = 0;
+        assert_eq!(IpInterfaceSettings::from(&row).family, Kind::Unknown);
+    }
+
+    #[test]
+    fn loopback_settings_are_read() {
+        let lo = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(InterfaceFlags::LOOPBACK))
+            .unwrap();
+        let index = lo.index.unwrap();
+        let settings = ip_interface_settings(index, Kind::Ipv4).unwrap();
+        assert_eq!(settings.family, Kind::Ipv4);
+        assert_eq!(settings.index, index);
+        assert_eq!(settings.luid, lo.os_specific().luid);
+        assert!(settings.mtu > 0);
+        assert!(settings.connected);
+    }
+
+    #[test]
+    fn bad_requests_fail() {
+        let err = ip_interface_settings(1, Kind::Link).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = ip_interface_settings(u32::MAX, Kind::Ipv4).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,236 @@
+//! The IP Helper (`Iphlpapi.dll`) functions the crate calls.
+//!
+//! Normally they are plain imports. With the `lazy-iphlpapi` feature the
+//! DLL is loaded from System32 on the first call instead, and each function
+//! is looked up once and kept in a `OnceLock`, so the import table only
+//! names kernel32. If the DLL or a function is missing the call fails with
+//! `ERROR_PROC_NOT_FOUND`, which [`os_error`] makes
+//! `ErrorKind::Unsupported`; functions without a result do nothing.
+//!
+//! Functions newer than the oldest Windows the crate runs on are always
+//! looked up that way, so that it still loads where they are missing.
+
+// Not every feature calls every function.
+#![allow(non_snake_case, dead_code)]
+
+use std::io;
+
+use winapi::shared::ifdef::NET_LUID;
+use winapi::shared::minwindef::{BOOL, DWORD, PULONG, ULONG};
+use winapi::shared::netioapi::{
+    MIB_IPFORWARD_ROW2, MIB_IPINTERFACE_ROW, MIB_IPNET_ROW2, MIB_UNICASTIPADDRESS_ROW,
+    PMIB_IPFORWARD_TABLE2,
+};
+use winapi::shared::winerror::{ERROR_MOD_NOT_FOUND, ERROR_PROC_NOT_FOUND};
+use winapi::um::iptypes::IP_ADAPTER_INFO;
+use winapi::um::minwinbase::OVERLAPPED;
+use winapi::um::winnt::{HANDLE, PVOID, WCHAR};
+
+/// What a function that could not be loaded returns.
+trait Missing {
+    const MISSING: Self;
+}
+
+impl Missing for u32 {
+    const MISSING: u32 = ERROR_PROC_NOT_FOUND;
+}
+
+impl Missing for i32 {
+    const MISSING: i32 = 0;
+}
+
+impl Missing for () {
+    const MISSING: () = ();
+}
+
+/// The address of `name`, a NUL-terminated function name, in
+/// `Iphlpapi.dll`, or 0.
+fn lookup(name: &str) -> usize {
+    use std::sync::OnceLock;
+
+    use winapi::um::libloaderapi::{GetProcAddress, LoadLibraryExW};
+
+    const LOAD_LIBRARY_SEARCH_SYSTEM32: DWORD = 0x800;
+    static DLL: OnceLock<usize> = OnceLock::new();
+
+    let dll = *DLL.get_or_init(|| {
+        let path: Vec<u16> = "Iphlpapi.dll\0".encode_utf16().collect();
+        let dll = unsafe {
+            LoadLibraryExW(
+                path.as_ptr(),
+                std::ptr::null_mut(),
+                LOAD_LIBRARY_SEARCH_SYSTEM32,
+            )
+        };
+        dll as usize
+    });
+    match dll {
+        0 => 0,
+        dll => unsafe { GetProcAddress(dll as _, name.as_ptr() as *const i8) as usize },
+    }
+}
+
+macro_rules! lazy {
+    ($(fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> $ret:ty;)*) => {
+        $(
+            pub unsafe fn $name($($arg: $ty),*) -> $ret {
+                static PROC: std::sync::OnceLock<usize> = std::sync::OnceLock::new();
+                let proc = *PROC.get_or_init(|| lookup(concat!(stringify!($name), "\0")));
+                if proc == 0 {
+                    return <$ret as Missing>::MISSING;
+                }
+                let f: unsafe extern "system" fn($($ty),*) -> $ret = std::mem::transmute(proc);
+                f($($arg),*)
+            }
+        )*
+    };
+}
+
+macro_rules! iphlpapi {
+    ($(fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> $ret:ty;)*) => {
+        #[cfg(not(feature = "lazy-iphlpapi"))]
+        #[link(name = "Iphlpapi")]
+        extern "system" {
+            $(pub fn $name($($arg: $ty),*) -> $ret;)*
+        }
+
+        #[cfg(feature = "lazy-iphlpapi")]
+        lazy! {
+            $(fn $name($($arg: $ty),*) -> $ret;)*
+        }
+    };
+}
+
+iphlpapi! {
+    fn GetAdaptersAddresses(
+        family: ULONG,
+        flags: ULONG,
+        reserved: PVOID,
+        addresses: *mut u8,
+        size: PULONG,
+    ) -> ULONG;
+    fn GetAdaptersInfo(info: *mut IP_ADAPTER_INFO, size: PULONG) -> ULONG;
+    fn ConvertInterfaceIndexToLuid(index: u32, luid: *mut NET_LUID) -> DWORD;
+    fn ConvertInterfaceLuidToIndex(luid: *const NET_LUID, index: *mut u32) -> DWORD;
+    fn ConvertInterfaceAliasToLuid(alias: *const WCHAR, luid: *mut NET_LUID) -> DWORD;
+    fn ConvertInterfaceLuidToAlias(luid: *const NET_LUID, alias: *mut WCHAR, len: usize) -> DWORD;
+    fn InitializeIpForwardEntry(row: *mut MIB_IPFORWARD_ROW2) -> ();
+    fn GetIpForwardTable2(family: u16, table: *mut PMIB_IPFORWARD_TABLE2) -> DWORD;
+    fn FreeMibTable(memory: PVOID) -> ();
+    fn CreateIpForwardEntry2(row: *const MIB_IPFORWARD_ROW2) -> DWORD;
+    fn DeleteIpForwardEntry2(row: *const MIB_IPFORWARD_ROW2) -> DWORD;
+    fn GetIpNetEntry2(row: *mut MIB_IPNET_ROW2) -> DWORD;
+    fn CreateIpNetEntry2(row: *const MIB_IPNET_ROW2) -> DWORD;
+    fn DeleteIpNetEntry2(row: *const MIB_IPNET_ROW2) -> DWORD;
+    fn InitializeUnicastIpAddressEntry(row: *mut MIB_UNICASTIPADDRESS_ROW) -> ();
+    fn DeleteUnicastIpAddressEntry(row: *const MIB_UNICASTIPADDRESS_ROW) -> DWORD;
+    fn InitializeIpInterfaceEntry(row: *mut MIB_IPINTERFACE_ROW) -> ();
+    fn GetIpInterfaceEntry(row: *mut MIB_IPINTERFACE_ROW) -> DWORD;
+    fn SetIpInterfaceEntry(row: *mut MIB_IPINTERFACE_ROW) -> DWORD;
+    fn NotifyAddrChange(handle: *mut HANDLE, overlapped: *mut OVERLAPPED) -> DWORD;
+    fn CancelIPChangeNotify(overlapped: *mut OVERLAPPED) -> BOOL;
+}
+
+/// `NL_NETWORK_CONNECTIVITY_HINT`, which winapi does not have.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct NL_NETWORK_CONNECTIVITY_HINT {
+    pub ConnectivityLevel: i32,
+    pub ConnectivityCost: i32,
+    pub ApproachingDataLimit: u8,
+    pub OverDataLimit: u8,
+    pub Roaming: u8,
+}
+
+// Windows 10 2004 and later.
+lazy! {
+    fn GetNetworkConnectivityHintForInterface(
+        index: ULONG,
+        hint: *mut NL_NETWORK_CONNECTIVITY_HINT,
+    ) -> DWORD;
+}
+
+/// The error for status `code` of one of these functions.
+pub fn os_error(code: u32) -> io::Error {
+    let err = io::Error::from_raw_os_error(code as i32);
+    match code {
+        ERROR_PROC_NOT_FOUND | ERROR_MOD_NOT_FOUND => {
+            io::Error::new(io::ErrorKind::Unsupported, err)
+        }
+        _ => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::ptr;
+
+    use winapi::shared::ws2def::AF_UNSPEC;
+
+    use super::*;
+
+    mod lazy_loaded {
+        use super::*;
+
+        lazy! {
+            fn GetAdaptersAddresses(
+                family: ULONG,
+                flags: ULONG,
+                reserved: PVOID,
+                addresses: *mut u8,
+                size: PULONG,
+            ) -> ULONG;
+            fn NoSuchIphlpapiFunction() -> u32;
+        }
+    }
+
+    mod linked {
+        use super::*;
+
+        #[link(name = "Iphlpapi")]
+        extern "system" {
+            pub fn GetAdaptersAddresses(
+                family: ULONG,
+                flags: ULONG,
+                reserved: PVOID,
+                addresses: *mut u8,
+                size: PULONG,
+            ) -> ULONG;
+        }
+    }
+
+    type Entry = (String, Option<u32>, Option<SocketAddr>);
+
+    /// The entries `ifaces` makes of the adapter list `get` fills in.
+    fn entries(get: impl FnMut(*mut u8, &mut ULONG) -> ULONG) -> Vec<Entry> {
+        let mut buffer: Vec<u64> = Vec::new();
+        super::super::fill_adapter_buffer(&mut buffer, get).unwrap();
+        let bytes = super::super::adapter_bytes(&buffer);
+        let (ifaces, _) = super::super::map_adapter_buffer(bytes, bytes.as_ptr() as usize);
+        ifaces
+            .into_iter()
+            .map(|iface| (iface.name, iface.index, iface.addr))
+            .collect()
+    }
+
+    #[test]
+    fn lazy_and_linked_give_the_same_adapters() {
+        let family = AF_UNSPEC as ULONG;
+        let lazy = entries(|addresses, size| unsafe {
+            lazy_loaded::GetAdaptersAddresses(family, 0, ptr::null_mut(), addresses, size)
+        });
+        let linked = entries(|addresses, size| unsafe {
+            linked::GetAdaptersAddresses(family, 0, ptr::null_mut(), addresses, size)
+        });
+        assert!(!lazy.is_empty());
+        assert_eq!(lazy, linked);
+    }
+
+    #[test]
+    fn missing_functions_are_unsupported() {
+        let code = unsafe { lazy_loaded::NoSuchIphlpapiFunction() };
+        assert_eq!(code, ERROR_PROC_NOT_FOUND);
+        assert_eq!(os_error(code).kind(), io::ErrorKind::Unsupported);
+    }
+}
@@ -1,44 +1,72 @@
-#![allow(non_upper_case_globals)]
+#![allow(non_upper_case_globals, clippy::enum_variant_names)]
 
-use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::cell::RefCell;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
 use std::{io, mem, ptr};
 
 use bitflags::bitflags;
+use log::trace;
 use winapi::shared::basetsd::{UINT32, UINT8, ULONG64};
 use winapi::shared::guiddef::GUID;
-use winapi::shared::minwindef::{BYTE, DWORD, PULONG, ULONG};
+use winapi::shared::minwindef::{BYTE, DWORD, ULONG};
 use winapi::shared::winerror::{
     ERROR_ADDRESS_NOT_ASSOCIATED, ERROR_BUFFER_OVERFLOW, ERROR_INVALID_PARAMETER,
-    ERROR_NOT_ENOUGH_MEMORY, ERROR_NO_DATA, ERROR_SUCCESS,
+    ERROR_MOD_NOT_FOUND, ERROR_NOT_ENOUGH_MEMORY, ERROR_NO_DATA, ERROR_PROC_NOT_FOUND,
+    ERROR_SUCCESS,
 };
-use winapi::shared::ws2def::{AF_INET, AF_INET6, AF_UNSPEC, SOCKADDR_IN, SOCKET_ADDRESS};
-use winapi::shared::ws2ipdef::SOCKADDR_IN6;
-use winapi::um::winnt::{PCHAR, PVOID, PWCHAR, WCHAR};
+use winapi::shared::ws2def::{AF_UNSPEC, SOCKET_ADDRESS};
+use winapi::um::winnt::{PCHAR, PWCHAR, WCHAR};
 
-use crate::{Interface, Kind};
+use self::buffer::{AdapterBuffer, Plain};
+use self::iphlpapi::{
+    GetAdaptersAddresses, GetNetworkConnectivityHintForInterface, NL_NETWORK_CONNECTIVITY_HINT,
+};
+use crate::dad::DadState;
+use crate::{
+    AddressOrigin, HardwareKind, Interface, InterfaceFlags, Kind, MacAddr, OsSpecific, Warning,
+    WarningAction,
+};
+
+mod buffer;
+pub mod config;
+mod dhcp;
+mod ip_interface;
+mod iphlpapi;
+mod network;
+#[cfg(feature = "pktinfo")]
+pub mod pktinfo;
+#[cfg(feature = "resolve")]
+pub mod resolve;
+mod socket;
+pub mod sys;
+#[cfg(feature = "watch")]
+pub mod watch;
+mod wide;
+
+pub use dhcp::{dhcp_lease, DhcpLease};
+pub use ip_interface::{
+    ip_interface_settings, IpInterfaceSettings, LinkLocalBehavior, RouterDiscovery,
+};
+pub use network::{group_by_network, Guid};
+pub use socket::{bind_socket_to_interface, interface_of_socket};
+pub use wide::{AdapterStrings, WideString};
 
 const MAX_ADAPTER_ADDRESS_LENGTH: usize = 8;
 const ZONE_INDICES_LENGTH: usize = 16;
 const MAX_DHCPV6_DUID_LENGTH: usize = 130;
 const MAX_DNS_SUFFIX_STRING_LENGTH: usize = 256;
 
-#[allow(unused)]
+pub const IP_ADAPTER_DDNS_ENABLED: DWORD = 0x0001;
+pub const IP_ADAPTER_REGISTER_ADAPTER_SUFFIX: DWORD = 0x0002;
+pub const IP_ADAPTER_DHCP_ENABLED: DWORD = 0x0004;
+pub const IP_ADAPTER_RECEIVE_ONLY: DWORD = 0x0008;
+pub const IP_ADAPTER_NO_MULTICAST: DWORD = 0x0010;
+pub const IP_ADAPTER_IPV6_OTHER_STATEFUL_CONFIG: DWORD = 0x0020;
+pub const IP_ADAPTER_NETBIOS_OVER_TCPIP_ENABLED: DWORD = 0x0040;
 pub const IP_ADAPTER_IPV4_ENABLED: DWORD = 0x0080;
-#[allow(unused)]
 pub const IP_ADAPTER_IPV6_ENABLED: DWORD = 0x0100;
-
-const PREALLOC_ADAPTERS_LEN: usize = 15 * 1024;
-
-#[link(name = "Iphlpapi")]
-extern "system" {
-    pub fn GetAdaptersAddresses(
-        family: ULONG,
-        flags: ULONG,
-        reserved: PVOID,
-        addresses: *mut u8,
-        size: PULONG,
-    ) -> ULONG;
-}
+pub const IP_ADAPTER_IPV6_MANAGE_ADDRESS_CONFIG: DWORD = 0x0200;
 
 #[repr(C)]
 pub struct IpAdapterAddresses {
@@ -51,7 +79,7 @@ pub struct IpAdapterAddresses {
 #[repr(C)]
 pub struct IpAdapterAddressesHead {
     pub length: ULONG,
-    if_index: DWORD,
+    pub if_index: DWORD,
 }
 
 /// All Windows & Later
@@ -69,9 +97,9 @@ pub struct IpAdaptersAddressesAll {
     pub physical_address: [BYTE; MAX_ADAPTER_ADDRESS_LENGTH],
     pub physical_address_length: DWORD,
     pub flags: DWORD,
-    mtu: DWORD,
+    pub mtu: DWORD,
     pub if_type: DWORD,
-    oper_status: IfOperStatus,
+    pub oper_status: i32,
 }
 
 /// Windows XP & Later
@@ -79,7 +107,7 @@ pub struct IpAdaptersAddressesAll {
 pub struct IpAdaptersAddressesXp {
     pub ipv6_if_index: DWORD,
     pub zone_indices: [DWORD; ZONE_INDICES_LENGTH],
-    first_prefix: *const IpAdapterPrefix,
+    pub first_prefix: *const IpAdapterPrefix,
 }
 
 /// Windows Vista & Later
@@ -91,12 +119,12 @@ pub struct IpAdaptersAddressesVista {
     first_gateway_address: *const IpAdapterGatewayAddress,
     ipv4_metric: ULONG,
     ipv6_metric: ULONG,
-    luid: IfLuid,
+    pub luid: IfLuid,
     dhcpv4_server: SOCKET_ADDRESS,
     compartment_id: UINT32,
     network_guid: GUID,
-    connection_type: NetIfConnectionType,
-    tunnel_type: TunnelType,
+    connection_type: i32,
+    pub tunnel_type: i32,
     dhcpv6_server: SOCKET_ADDRESS,
     dhcpv6_client_duid: [BYTE; MAX_DHCPV6_DUID_LENGTH],
     dhcpv6_client_duid_length: ULONG,
@@ -104,21 +132,87 @@ pub struct IpAdaptersAddressesVista {
     first_dns_suffix: *const IpAdapterDnsSuffix,
 }
 
+/// The enums Windows fills in are kept as the raw `i32`s, since a value
+/// this crate does not know of would not be a valid Rust enum.
 #[repr(C)]
 pub struct IpAdapterUnicastAddress {
     pub length: ULONG,
     flags: DWORD,
     pub next: *const IpAdapterUnicastAddress,
     pub address: SOCKET_ADDRESS,
-    prefix_origin: IpPrefixOrigin,
-    suffix_origin: IpSuffixOrigin,
-    pub dad_state: IpDadState,
+    prefix_origin: i32,
+    suffix_origin: i32,
+    pub dad_state: i32,
     valid_lifetime: ULONG,
     preferred_lifetime: ULONG,
     lease_lifetime: ULONG,
     on_link_prefix_length: UINT8,
 }
 
+impl IpAdapterAddresses {
+    /// Whether the structure Windows filled in is at least `end` bytes
+    /// long. Older releases return shorter structures, without the fields
+    /// added since, and report how long in `head.length`.
+    fn covers(&self, end: usize) -> bool {
+        self.head.length as usize >= end
+    }
+
+    /// The XP section, if this structure has it.
+    pub fn xp(&self) -> Option<&IpAdaptersAddressesXp> {
+        let end = mem::offset_of!(IpAdapterAddresses, xp) + mem::size_of::<IpAdaptersAddressesXp>();
+        match self.covers(end) {
+            true => Some(&self.xp),
+            false => None,
+        }
+    }
+
+    /// The adapter's `NET_LUID`, from the Vista section.
+    pub fn luid(&self) -> Option<u64> {
+        let end = mem::offset_of!(IpAdapterAddresses, vista)
+            + mem::offset_of!(IpAdaptersAddressesVista, luid)
+            + mem::size_of::<IfLuid>();
+        match self.covers(end) {
+            true => Some(self.vista.luid.bits()),
+            false => None,
+        }
+    }
+
+    /// The GUID of the network the adapter is connected to, from the Vista
+    /// section. `None` if it is not connected to one.
+    pub fn network_guid(&self) -> Option<Guid> {
+        let end = mem::offset_of!(IpAdapterAddresses, vista)
+            + mem::offset_of!(IpAdaptersAddressesVista, network_guid)
+            + mem::size_of::<GUID>();
+        match self.covers(end) {
+            true => Some(Guid::from_raw(&self.vista.network_guid)).filter(|guid| !guid.is_nil()),
+            false => None,
+        }
+    }
+
+    /// The adapter's tunnel type, from the Vista section.
+    pub fn tunnel_type(&self) -> Option<u32> {
+        let end = mem::offset_of!(IpAdapterAddresses, vista)
+            + mem::offset_of!(IpAdaptersAddressesVista, tunnel_type)
+            + mem::size_of::<i32>();
+        match self.covers(end) {
+            true => Some(self.vista.tunnel_type as u32),
+            false => None,
+        }
+    }
+}
+
+impl IpAdapterUnicastAddress {
+    /// The prefix length of the address's network, which XP does not give.
+    pub fn on_link_prefix_length(&self) -> Option<u8> {
+        let end = mem::offset_of!(IpAdapterUnicastAddress, on_link_prefix_length)
+            + mem::size_of::<UINT8>();
+        match self.length as usize >= end {
+            true => Some(self.on_link_prefix_length),
+            false => None,
+        }
+    }
+}
+
 #[repr(C)]
 pub struct IpAdapterAnycastAddress {
     length: ULONG,
@@ -152,6 +246,15 @@ pub struct IpAdapterPrefix {
     prefix_length: ULONG,
 }
 
+unsafe impl Plain for IpAdapterAddresses {}
+unsafe impl Plain for IpAdapterUnicastAddress {}
+unsafe impl Plain for IpAdapterPrefix {}
+
+/// Adapter entries shorter than the fields every release fills in.
+const MIN_ADAPTER_LENGTH: usize = mem::offset_of!(IpAdapterAddresses, xp);
+/// Unicast entries shorter than XP's.
+const MIN_UNICAST_LENGTH: usize = mem::offset_of!(IpAdapterUnicastAddress, on_link_prefix_length);
+
 #[repr(C)]
 pub struct IpAdapterWinsServerAddress {
     length: ULONG,
@@ -175,7 +278,26 @@ pub struct IpAdapterDnsSuffix {
 }
 
 bitflags! {
-    struct IfLuid: ULONG64 {
+    /// The `Flags` of an adapter, with the same bits as the
+    /// `IP_ADAPTER_*` constants.
+    #[derive(Default)]
+    pub struct AdapterFlags: DWORD {
+        const DDNS_ENABLED = IP_ADAPTER_DDNS_ENABLED;
+        const REGISTER_ADAPTER_SUFFIX = IP_ADAPTER_REGISTER_ADAPTER_SUFFIX;
+        const DHCP_ENABLED = IP_ADAPTER_DHCP_ENABLED;
+        /// The adapter only receives, e.g. a satellite downlink.
+        const RECEIVE_ONLY = IP_ADAPTER_RECEIVE_ONLY;
+        const NO_MULTICAST = IP_ADAPTER_NO_MULTICAST;
+        const IPV6_OTHER_STATEFUL_CONFIG = IP_ADAPTER_IPV6_OTHER_STATEFUL_CONFIG;
+        const NETBIOS_OVER_TCPIP_ENABLED = IP_ADAPTER_NETBIOS_OVER_TCPIP_ENABLED;
+        const IPV4_ENABLED = IP_ADAPTER_IPV4_ENABLED;
+        const IPV6_ENABLED = IP_ADAPTER_IPV6_ENABLED;
+        const IPV6_MANAGE_ADDRESS_CONFIG = IP_ADAPTER_IPV6_MANAGE_ADDRESS_CONFIG;
+    }
+}
+
+bitflags! {
+    pub struct IfLuid: ULONG64 {
         const Reserved = 0x0000000000FFFFFF;
         const NetLuidIndex = 0x0000FFFFFF000000;
         const IfType = 0xFFFF000000000000;
@@ -193,6 +315,21 @@ pub enum IpPrefixOrigin {
     IpPrefixOriginUnchanged = 16,
 }
 
+impl IpPrefixOrigin {
+    fn from_raw(raw: i32) -> Option<IpPrefixOrigin> {
+        use IpPrefixOrigin::*;
+        Some(match raw {
+            0 => IpPrefixOriginOther,
+            1 => IpPrefixOriginManual,
+            2 => IpPrefixOriginWellKnown,
+            3 => IpPrefixOriginDhcp,
+            4 => IpPrefixOriginRouterAdvertisement,
+            16 => IpPrefixOriginUnchanged,
+            _ => return None,
+        })
+    }
+}
+
 #[allow(unused)]
 #[repr(C)]
 pub enum IpSuffixOrigin {
@@ -205,6 +342,22 @@ pub enum IpSuffixOrigin {
     IpSuffixOriginUnchanged = 16,
 }
 
+impl IpSuffixOrigin {
+    fn from_raw(raw: i32) -> Option<IpSuffixOrigin> {
+        use IpSuffixOrigin::*;
+        Some(match raw {
+            0 => IpSuffixOriginOther,
+            1 => IpSuffixOriginManual,
+            2 => IpSuffixOriginWellKnown,
+            3 => IpSuffixOriginDhcp,
+            4 => IpSuffixOriginLinkLayerAddress,
+            5 => IpSuffixOriginRandom,
+            16 => IpSuffixOriginUnchanged,
+            _ => return None,
+        })
+    }
+}
+
 #[allow(unused)]
 #[derive(PartialEq, Eq)]
 #[repr(C)]
@@ -216,6 +369,20 @@ pub enum IpDadState {
     IpDadStatePreferred,
 }
 
+impl IpDadState {
+    fn from_raw(raw: i32) -> Option<IpDadState> {
+        use IpDadState::*;
+        Some(match raw {
+            0 => IpDadStateInvalid,
+            1 => IpDadStateTentative,
+            2 => IpDadStateDuplicate,
+            3 => IpDadStateDeprecated,
+            4 => IpDadStatePreferred,
+            _ => return None,
+        })
+    }
+}
+
 #[allow(unused)]
 #[repr(C)]
 pub enum IfOperStatus {
@@ -238,6 +405,7 @@ pub enum NetIfConnectionType {
 }
 
 #[allow(unused)]
+#[derive(Clone, Copy)]
 #[repr(C)]
 pub enum TunnelType {
     TunnelTypeNone = 0,
@@ -249,155 +417,1137 @@ pub enum TunnelType {
     TunnelTypeIpHttps = 15,
 }
 
-unsafe fn v4_socket_from_adapter(unicast_addr: &IpAdapterUnicastAddress) -> SocketAddrV4 {
-    let socket_addr = &unicast_addr.address;
+/// Walk the on-link prefixes of an adapter, dropping duplicates.
+fn prefixes_of(buffer: &AdapterBuffer, first: *const IpAdapterPrefix) -> Vec<(IpAddr, u8)> {
+    let mut prefixes = Vec::new();
+    for entry in buffer
+        .chain(first, |prefix| prefix.next)
+        .map_while(|entry| entry)
+    {
+        if let Ok(addr) = buffer.socket_address(&entry.address) {
+            let entry = (addr.ip(), entry.prefix_length as u8);
+            if !prefixes.contains(&entry) {
+                prefixes.push(entry);
+            }
+        }
+    }
+    prefixes
+}
 
-    let in_addr: SOCKADDR_IN = mem::transmute(*socket_addr.lpSockaddr);
-    let sin_addr = in_addr.sin_addr.S_un.S_addr();
+/// The mask of the longest on-link network `addr` is in. Host routes and
+/// multicast prefixes are not networks and are left out.
+fn mask_from_prefixes(addr: IpAddr, prefixes: &[(IpAddr, u8)]) -> Option<SocketAddr> {
+    // Addresses and masks as the top bits of a u128.
+    let bits = |addr: IpAddr| match addr {
+        IpAddr::V4(addr) => ((u32::from(addr) as u128) << 96, 32),
+        IpAddr::V6(addr) => (u128::from(addr), 128),
+    };
+    let mask = |len: u8| u128::MAX.checked_shl(128 - len as u32).unwrap_or(0);
 
-    #[allow(clippy::identity_op)]
-    SocketAddrV4::new(
-        Ipv4Addr::new(
-            (sin_addr >> 0) as u8,
-            (sin_addr >> 8) as u8,
-            (sin_addr >> 16) as u8,
-            (sin_addr >> 24) as u8,
-        ),
-        0,
-    )
+    let (addr_bits, width) = bits(addr);
+    let len = prefixes
+        .iter()
+        .filter(|(prefix, len)| {
+            let (prefix_bits, prefix_width) = bits(*prefix);
+            prefix_width == width
+                && (*len as u32) < width
+                && !prefix.is_multicast()
+                && prefix_bits & mask(*len) == addr_bits & mask(*len)
+        })
+        .map(|(_, len)| *len)
+        .max()?;
+    let mask = match addr {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from((mask(len) >> 96) as u32)),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(mask(len))),
+    };
+    Some(SocketAddr::new(mask, 0))
 }
 
-unsafe fn v6_socket_from_adapter(unicast_addr: &IpAdapterUnicastAddress) -> SocketAddrV6 {
-    let socket_addr = &unicast_addr.address;
+/// Where `addr` came from, by its prefix and suffix origins.
+fn origin_of(addr: &IpAddr, unicast_addr: &IpAdapterUnicastAddress) -> AddressOrigin {
+    if let Some(origin) = AddressOrigin::of_address(addr) {
+        return origin;
+    }
+    match (
+        IpPrefixOrigin::from_raw(unicast_addr.prefix_origin),
+        IpSuffixOrigin::from_raw(unicast_addr.suffix_origin),
+    ) {
+        (
+            Some(IpPrefixOrigin::IpPrefixOriginRouterAdvertisement),
+            Some(IpSuffixOrigin::IpSuffixOriginRandom),
+        ) => AddressOrigin::SlaacTemporary,
+        (Some(IpPrefixOrigin::IpPrefixOriginRouterAdvertisement), _) => AddressOrigin::Slaac,
+        (Some(IpPrefixOrigin::IpPrefixOriginDhcp), _)
+        | (_, Some(IpSuffixOrigin::IpSuffixOriginDhcp)) => AddressOrigin::Dhcp,
+        (Some(IpPrefixOrigin::IpPrefixOriginManual), _) => AddressOrigin::Static,
+        (Some(IpPrefixOrigin::IpPrefixOriginWellKnown), _) => AddressOrigin::WellKnown,
+        _ => AddressOrigin::Unknown,
+    }
+}
 
-    let sock_addr6: *const SOCKADDR_IN6 = mem::transmute(socket_addr.lpSockaddr);
-    let in6_addr: SOCKADDR_IN6 = *sock_addr6;
+/// The mask of `addr`, from the adapter's prefixes or else the address's
+/// own prefix length.
+fn mask_of(
+    addr: IpAddr,
+    unicast_addr: &IpAdapterUnicastAddress,
+    prefixes: &[(IpAddr, u8)],
+) -> Option<SocketAddr> {
+    mask_from_prefixes(addr, prefixes).or_else(|| {
+        let len = unicast_addr.on_link_prefix_length()? as u32;
+        let mask = match addr {
+            IpAddr::V4(_) if len <= 32 => {
+                IpAddr::V4(Ipv4Addr::from(u32::MAX.checked_shl(32 - len).unwrap_or(0)))
+            }
+            IpAddr::V6(_) if len <= 128 => IpAddr::V6(Ipv6Addr::from(
+                u128::MAX.checked_shl(128 - len).unwrap_or(0),
+            )),
+            _ => return None,
+        };
+        Some(SocketAddr::new(mask, 0))
+    })
+}
 
-    let sin6_addr = in6_addr.sin6_addr.u.Byte();
-    let v6_addr: Ipv6Addr = (*sin6_addr).into();
+/// Fill `buffer` with the adapter list.
+fn local_ifaces_with_buffer(buffer: &mut Vec<u64>) -> io::Result<()> {
+    fill_adapter_buffer(buffer, |addresses, length| unsafe {
+        GetAdaptersAddresses(AF_UNSPEC as u32, 0, ptr::null_mut(), addresses, length)
+    })
+}
+
+/// Fill `buffer` with what `get_adapters` writes, which takes the buffer
+/// and length arguments of `GetAdaptersAddresses` and returns its error
+/// code. Public for the benchmarks.
+///
+/// Whatever `buffer` already holds is offered first. An empty buffer makes
+/// Windows report the size it needs, so it is allocated exactly once. The
+/// list can grow between two calls, in which case we go around again with
+/// the new size.
+pub fn fill_adapter_buffer(
+    buffer: &mut Vec<u64>,
+    mut get_adapters: impl FnMut(*mut u8, &mut ULONG) -> ULONG,
+) -> io::Result<()> {
+    let mut length = (buffer.len() * mem::size_of::<u64>()) as ULONG;
 
-    SocketAddrV6::new(
-        v6_addr,
-        0,
-        in6_addr.sin6_flowinfo,
-        *in6_addr.u.sin6_scope_id(),
-    )
+    loop {
+        let addresses = match buffer.len() {
+            0 => ptr::null_mut(),
+            _ => buffer.as_mut_ptr() as *mut u8,
+        };
+        let ret_code = get_adapters(addresses, &mut length);
+        match ret_code {
+            ERROR_SUCCESS => return Ok(()),
+            ERROR_BUFFER_OVERFLOW => {
+                trace!("GetAdaptersAddresses needs a {} byte buffer", length);
+                // u64 elements keep the buffer aligned for IpAdapterAddresses.
+                // Zeroed, so all of it can be read as bytes.
+                *buffer = vec![0; (length as usize).div_ceil(8)];
+            }
+            _ => return Err(adapters_error(ret_code)),
+        }
+    }
 }
 
-unsafe fn local_ifaces_with_buffer(buffer: &mut Vec<u8>) -> io::Result<()> {
-    let mut length = buffer.capacity() as u32;
+fn adapter_bytes(buffer: &[u64]) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u8, mem::size_of_val(buffer)) }
+}
 
-    let ret_code = GetAdaptersAddresses(
-        AF_UNSPEC as u32,
-        0,
-        ptr::null_mut(),
-        buffer.as_mut_ptr(),
-        &mut length,
-    );
+fn adapters_error(ret_code: ULONG) -> io::Error {
     match ret_code {
-        ERROR_SUCCESS => Ok(()),
-        ERROR_ADDRESS_NOT_ASSOCIATED => Err(io::Error::new(
+        ERROR_ADDRESS_NOT_ASSOCIATED => io::Error::new(
             io::ErrorKind::AddrNotAvailable,
             "An address has not yet been associated with the network endpoint.",
-        )),
-        ERROR_BUFFER_OVERFLOW => {
-            buffer.reserve_exact(length as usize);
-
-            local_ifaces_with_buffer(buffer)
-        }
-        ERROR_INVALID_PARAMETER => Err(io::Error::new(
+        ),
+        ERROR_INVALID_PARAMETER => io::Error::new(
             io::ErrorKind::InvalidInput,
             "One of the parameters is invalid.",
-        )),
-        ERROR_NOT_ENOUGH_MEMORY => Err(io::Error::new(
-            io::ErrorKind::Other,
+        ),
+        ERROR_NOT_ENOUGH_MEMORY => io::Error::other(
             "Insufficient memory resources are available to complete the operation.",
-        )),
-        ERROR_NO_DATA => Err(io::Error::new(
+        ),
+        ERROR_NO_DATA => io::Error::new(
             io::ErrorKind::AddrNotAvailable,
             "No addresses were found for the requested parameters.",
-        )),
-        _ => Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Some Other Error Occured.",
-        )),
+        ),
+        ERROR_PROC_NOT_FOUND | ERROR_MOD_NOT_FOUND => iphlpapi::os_error(ret_code),
+        _ => io::Error::other("Some Other Error Occured."),
     }
 }
 
-unsafe fn map_adapter_addresses(mut adapter_addr: *const IpAdapterAddresses) -> Vec<Interface> {
-    let mut adapter_addresses = Vec::new();
+fn flags_of(adapter: &IpAdapterAddresses) -> InterfaceFlags {
+    let mut flags = InterfaceFlags::empty();
+    if adapter.all.oper_status == IfOperStatus::IfOperStatusUp as i32 {
+        flags |= InterfaceFlags::UP | InterfaceFlags::RUNNING;
+    }
+    match HardwareKind::from_iana(adapter.all.if_type) {
+        HardwareKind::Loopback => flags |= InterfaceFlags::LOOPBACK,
+        HardwareKind::Ppp | HardwareKind::Tunnel => flags |= InterfaceFlags::POINT_TO_POINT,
+        _ => flags |= InterfaceFlags::BROADCAST,
+    }
+    if adapter.all.flags & IP_ADAPTER_NO_MULTICAST == 0 {
+        flags |= InterfaceFlags::MULTICAST;
+    }
+    flags
+}
 
-    loop {
-        if adapter_addr.is_null() {
-            break;
+pub(crate) fn hardware_kind(os: &OsSpecific) -> HardwareKind {
+    HardwareKind::from_iana(os.if_type)
+}
+
+/// Where duplicate address detection for `addr` on the adapter called
+/// `name` stands, `None` if the adapter does not have the address.
+pub(crate) fn dad_state(name: &str, addr: Ipv6Addr) -> io::Result<Option<DadState>> {
+    let name = crate::validate_name(name)?;
+    ADAPTERS_BUFFER.with(|buffer| {
+        let mut adapters_list = buffer.borrow_mut();
+        adapters_list.refresh()?;
+        let bytes = adapters_list.bytes();
+        dad_state_in(
+            &AdapterBuffer::new(bytes, bytes.as_ptr() as usize),
+            &name,
+            addr,
+        )
+    })
+}
+
+fn dad_state_in(
+    buffer: &AdapterBuffer,
+    name: &crate::ValidName,
+    addr: Ipv6Addr,
+) -> io::Result<Option<DadState>> {
+    let mut found_adapter = false;
+    let adapters = buffer.list(|adapter: &IpAdapterAddresses| adapter.all.next);
+    for adapter in adapters.map_while(|adapter| adapter) {
+        if !name.matches(&name_of(buffer, adapter)) {
+            continue;
+        }
+        found_adapter = true;
+        let unicasts = buffer.chain(adapter.all.first_unicast_address, |entry| entry.next);
+        for entry in unicasts.map_while(|entry| entry) {
+            let ip = buffer.socket_address(&entry.address).map(|addr| addr.ip());
+            if ip != Ok(IpAddr::V6(addr)) {
+                continue;
+            }
+            return Ok(Some(match IpDadState::from_raw(entry.dad_state) {
+                Some(IpDadState::IpDadStateDuplicate) => DadState::Duplicate,
+                Some(IpDadState::IpDadStatePreferred | IpDadState::IpDadStateDeprecated) => {
+                    DadState::Usable
+                }
+                _ => DadState::Tentative,
+            }));
         }
+    }
+    match found_adapter {
+        true => Ok(None),
+        false => Err(io::Error::new(io::ErrorKind::NotFound, "no such interface")),
+    }
+}
+
+/// Deprecated addresses are already left out of the enumeration, and
+/// temporary ones are not told apart.
+pub(crate) fn deprecated_or_temporary_v6() -> Vec<std::net::Ipv6Addr> {
+    Vec::new()
+}
+
+/// The friendly name of the adapter with index `index`, which is what
+/// `name` holds, with `ConvertInterfaceIndexToLuid` and
+/// `ConvertInterfaceLuidToAlias`.
+pub(crate) fn index_name(index: u32) -> Option<String> {
+    use self::iphlpapi::{ConvertInterfaceIndexToLuid, ConvertInterfaceLuidToAlias};
+    use winapi::shared::ifdef::NET_LUID;
+    use winapi::shared::winerror::NO_ERROR;
+
+    let mut luid: NET_LUID = unsafe { mem::zeroed() };
+    if unsafe { ConvertInterfaceIndexToLuid(index, &mut luid) } != NO_ERROR {
+        return None;
+    }
+    // IF_MAX_STRING_SIZE characters and the terminator.
+    let mut alias = [0u16; 257];
+    if unsafe { ConvertInterfaceLuidToAlias(&luid, alias.as_mut_ptr(), alias.len()) } != NO_ERROR {
+        return None;
+    }
+    let len = alias.iter().position(|&c| c == 0).unwrap_or(alias.len());
+    Some(String::from_utf16_lossy(&alias[..len]))
+}
+
+/// The index of the adapter with the friendly name `name`, with
+/// `ConvertInterfaceAliasToLuid` and `ConvertInterfaceLuidToIndex`.
+pub(crate) fn if_index(name: &crate::ValidName) -> io::Result<u32> {
+    use self::iphlpapi::{ConvertInterfaceAliasToLuid, ConvertInterfaceLuidToIndex};
+    use winapi::shared::ifdef::NET_LUID;
+    use winapi::shared::winerror::NO_ERROR;
+
+    let alias: Vec<u16> = name.as_str().encode_utf16().chain(Some(0)).collect();
+    let mut luid: NET_LUID = unsafe { mem::zeroed() };
+    let mut index = 0;
+    if unsafe { ConvertInterfaceAliasToLuid(alias.as_ptr(), &mut luid) } != NO_ERROR
+        || unsafe { ConvertInterfaceLuidToIndex(&luid, &mut index) } != NO_ERROR
+    {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no such interface"));
+    }
+    Ok(index)
+}
+
+/// Whether Windows counts traffic over adapter `index` as metered, from
+/// its connectivity cost hint. `None` when the cost is not known, and
+/// before Windows 10 2004, which added the hint.
+pub(crate) fn is_metered(index: u32) -> Option<bool> {
+    let mut hint = NL_NETWORK_CONNECTIVITY_HINT::default();
+    match unsafe { GetNetworkConnectivityHintForInterface(index, &mut hint) } {
+        ERROR_SUCCESS => metered_from_cost(hint.ConnectivityCost),
+        _ => None,
+    }
+}
+
+/// Map an `NL_NETWORK_CONNECTIVITY_COST_HINT`: unknown, unrestricted,
+/// fixed or variable.
+fn metered_from_cost(cost: i32) -> Option<bool> {
+    match cost {
+        1 => Some(false),
+        2 | 3 => Some(true),
+        _ => None,
+    }
+}
+
+/// Whether the adapter is `IF_TYPE_IEEE80211`.
+pub(crate) fn is_wireless(_name: &str, os: &OsSpecific) -> bool {
+    hardware_kind(os) == HardwareKind::Wireless
+}
+
+fn count_unicast_addresses(buffer: &AdapterBuffer) -> usize {
+    buffer
+        .list(|adapter: &IpAdapterAddresses| adapter.all.next)
+        .map_while(|adapter| adapter)
+        .map(|adapter| {
+            buffer
+                .chain(adapter.all.first_unicast_address, |entry| entry.next)
+                .map_while(|entry| entry)
+                .count()
+        })
+        .sum()
+}
+
+/// Map a `GetAdaptersAddresses` buffer to interfaces, with a [`Warning`]
+/// for each entry that was left out or could not be read whole.
+///
+/// The pointers in `bytes` are taken as addresses in a buffer that starts
+/// at `base`: `bytes.as_ptr() as usize` for the buffer Windows filled in,
+/// or 0 to read them as offsets into `bytes`. Nothing outside `bytes` is
+/// read whatever it holds, which makes this safe to call on any bytes.
+/// Structures have to be aligned in memory, which is why `bytes` should
+/// start 8-byte aligned.
+pub fn map_adapter_buffer(bytes: &[u8], base: usize) -> (Vec<Interface>, Vec<Warning>) {
+    let buffer = AdapterBuffer::new(bytes, base);
+    let mut warnings = Vec::new();
+    let interfaces = map_adapter_addresses(&buffer, &mut warnings);
+    (interfaces, warnings)
+}
+
+/// The name of the entries of `adapter`: its friendly name, e.g.
+/// `Ethernet 2`, or its GUID adapter name if that is empty or cannot be
+/// read.
+fn name_of(buffer: &AdapterBuffer, adapter: &IpAdapterAddresses) -> String {
+    match buffer.wide_string(adapter.all.friendly_name) {
+        Some(name) if !name.is_empty() => name.as_str().to_owned(),
+        _ => buffer
+            .c_string(adapter.all.adapter_name)
+            .unwrap_or_default(),
+    }
+}
 
-        let curr_adapter_addr = &*adapter_addr;
-        let mut unicast_addr = curr_adapter_addr.all.first_unicast_address;
+fn map_adapter_addresses(buffer: &AdapterBuffer, warnings: &mut Vec<Warning>) -> Vec<Interface> {
+    let mut adapter_addresses = Vec::with_capacity(count_unicast_addresses(buffer));
 
-        loop {
-            if unicast_addr.is_null() {
+    let adapters = buffer.list(|adapter: &IpAdapterAddresses| adapter.all.next);
+    for curr_adapter_addr in adapters {
+        let curr_adapter_addr = match curr_adapter_addr {
+            Some(adapter) => adapter,
+            None => {
+                warnings.push(Warning::new(
+                    "",
+                    0,
+                    "next",
+                    WarningAction::Skipped,
+                    "adapter outside the adapter buffer",
+                ));
                 break;
             }
-            let curr_unicast_addr = &*unicast_addr;
+        };
+        if (curr_adapter_addr.head.length as usize) < MIN_ADAPTER_LENGTH {
+            warnings.push(Warning::new(
+                "",
+                0,
+                "length",
+                WarningAction::Skipped,
+                format!(
+                    "{} byte adapter entry is too short",
+                    curr_adapter_addr.head.length
+                ),
+            ));
+            continue;
+        }
+
+        let name = name_of(buffer, curr_adapter_addr);
+        let mut string = |field, ptr| {
+            buffer.wide_string(ptr).unwrap_or_else(|| {
+                warnings.push(Warning::new(
+                    name.as_str(),
+                    0,
+                    field,
+                    WarningAction::Defaulted,
+                    "string runs past the adapter buffer",
+                ));
+                WideString::default()
+            })
+        };
+        let strings = AdapterStrings {
+            friendly_name: string("friendly_name", curr_adapter_addr.all.friendly_name),
+            description: string("description", curr_adapter_addr.all.description),
+            dns_suffix: string("dns_suffix", curr_adapter_addr.all.dns_suffix),
+        };
+        let os = Arc::new(OsSpecific {
+            luid: curr_adapter_addr.luid().unwrap_or(0),
+            adapter_name: buffer
+                .c_string(curr_adapter_addr.all.adapter_name)
+                .unwrap_or_default(),
+            if_type: curr_adapter_addr.all.if_type,
+            tunnel_type: curr_adapter_addr.tunnel_type().unwrap_or(0),
+            network_guid: curr_adapter_addr.network_guid(),
+            adapter_flags: AdapterFlags::from_bits_truncate(curr_adapter_addr.all.flags),
+            prefixes: curr_adapter_addr
+                .xp()
+                .map(|xp| prefixes_of(buffer, xp.first_prefix))
+                .unwrap_or_default(),
+            strings,
+        });
+        let ipv6_if_index = curr_adapter_addr.xp().map(|xp| xp.ipv6_if_index);
+        let flags = flags_of(curr_adapter_addr);
+        let mac = MacAddr::from_hardware_addr(
+            curr_adapter_addr
+                .all
+                .physical_address
+                .get(..curr_adapter_addr.all.physical_address_length as usize)
+                .unwrap_or(&[]),
+        );
+
+        let unicasts = buffer.chain(curr_adapter_addr.all.first_unicast_address, |entry| {
+            entry.next
+        });
+        for (entry, curr_unicast_addr) in unicasts.enumerate() {
+            let mut skip = |field, reason: String| {
+                warnings.push(Warning::new(
+                    name.as_str(),
+                    entry,
+                    field,
+                    WarningAction::Skipped,
+                    reason,
+                ))
+            };
+            let curr_unicast_addr = match curr_unicast_addr {
+                Some(unicast_addr) => unicast_addr,
+                None => {
+                    skip("next", "entry outside the adapter buffer".to_owned());
+                    break;
+                }
+            };
 
-            // println!("{:?}",*curr_unicast_addr);
             // For some reason, some IpDadState::IpDadStateDeprecated addresses are return
             // These contain BOGUS interface indices and will cause problesm if used
-            match curr_unicast_addr.dad_state {
-                IpDadState::IpDadStateDeprecated => {}
-                _ => match curr_unicast_addr.length {
-                    0 => {}
-                    _ => {
-                        let socket_addr = &curr_unicast_addr.address;
-                        let sa_family = (*socket_addr.lpSockaddr).sa_family as i32;
-                        match sa_family {
-                            AF_INET => {
-                                adapter_addresses.push(Interface {
-                                    name: "".to_string(),
-                                    kind: Kind::Ipv4,
-                                    addr: Some(SocketAddr::V4(v4_socket_from_adapter(
-                                        &curr_unicast_addr,
-                                    ))),
-                                    mask: None,
-                                    hop: None,
-                                });
-                            }
-                            AF_INET6 => {
-                                let mut v6_sock = v6_socket_from_adapter(&curr_unicast_addr);
-                                // Make sure the scope id is set for ALL interfaces, not just link-local
-                                v6_sock.set_scope_id(curr_adapter_addr.xp.ipv6_if_index);
-                                adapter_addresses.push(Interface {
-                                    name: "".to_string(),
-                                    kind: Kind::Ipv6,
-                                    addr: Some(SocketAddr::V6(v6_sock)),
-                                    mask: None,
-                                    hop: None,
-                                });
-                            }
-                            _ => {}
-                        }
-                    }
-                },
+            if IpDadState::from_raw(curr_unicast_addr.dad_state)
+                == Some(IpDadState::IpDadStateDeprecated)
+            {
+                skip(
+                    "dad_state",
+                    "deprecated, with an unreliable index".to_owned(),
+                );
+                continue;
+            }
+            if (curr_unicast_addr.length as usize) < MIN_UNICAST_LENGTH {
+                skip(
+                    "length",
+                    format!("{} byte entry is too short", curr_unicast_addr.length),
+                );
+                continue;
+            }
+            let (kind, index, addr) = match buffer.socket_address(&curr_unicast_addr.address) {
+                Ok(addr @ SocketAddr::V4(_)) => {
+                    (Kind::Ipv4, Some(curr_adapter_addr.head.if_index), addr)
+                }
+                Ok(SocketAddr::V6(mut v6_sock)) => {
+                    // Make sure the scope id is set for ALL interfaces, not just link-local
+                    v6_sock.set_scope_id(ipv6_if_index.unwrap_or(0));
+                    (Kind::Ipv6, ipv6_if_index, SocketAddr::V6(v6_sock))
+                }
+                Err(reason) => {
+                    skip("addr", reason);
+                    continue;
+                }
             };
-            unicast_addr = curr_unicast_addr.next;
+            adapter_addresses.push(Interface {
+                name: name.clone(),
+                index,
+                kind,
+                addr: Some(addr),
+                mask: mask_of(addr.ip(), curr_unicast_addr, &os.prefixes),
+                hop: None,
+                origin: origin_of(&addr.ip(), curr_unicast_addr),
+                flags,
+                mac,
+                stats: None,
+                mtu: Some(curr_adapter_addr.all.mtu),
+                os: os.clone(),
+            });
         }
-
-        adapter_addr = curr_adapter_addr.all.next;
     }
 
     adapter_addresses
 }
 
+thread_local! {
+    // Reused across calls on the same thread so polling does not pay for a
+    // fresh allocation every time. Freed when the thread exits.
+    static ADAPTERS_BUFFER: RefCell<sys::AdapterList> = const { RefCell::new(sys::AdapterList::empty()) };
+}
+
 /// Query the local system for all interface addresses.
 pub fn ifaces() -> io::Result<Vec<Interface>> {
-    let mut adapters_list = Vec::with_capacity(PREALLOC_ADAPTERS_LEN);
-    unsafe {
-        match local_ifaces_with_buffer(&mut adapters_list) {
-            Ok(_) => Ok(map_adapter_addresses(mem::transmute(
-                adapters_list.as_ptr(),
-            ))),
-            Err(_) => Err(io::Error::new(io::ErrorKind::Other, "Oh, no ...")),
+    Ok(ifaces_with_warnings()?.0)
+}
+
+/// [`ifaces`], with a [`Warning`] for each address that was skipped.
+pub fn ifaces_with_warnings() -> io::Result<(Vec<Interface>, Vec<Warning>)> {
+    ADAPTERS_BUFFER.with(|buffer| {
+        let mut adapters_list = buffer.borrow_mut();
+        adapters_list.refresh()?;
+        let bytes = adapters_list.bytes();
+        Ok(map_adapter_buffer(bytes, bytes.as_ptr() as usize))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consecutive_calls_agree() {
+        let addresses = || -> Vec<_> {
+            ifaces()
+                .unwrap()
+                .into_iter()
+                .map(|iface| (iface.name, iface.kind, iface.addr, iface.mask))
+                .collect()
+        };
+        assert_eq!(addresses(), addresses());
+    }
+
+    #[test]
+    fn the_extras_of_loopback_are_filled_in() {
+        let lo = ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(crate::InterfaceFlags::LOOPBACK))
+            .unwrap();
+        let os = lo.os_specific();
+        // IF_TYPE_SOFTWARE_LOOPBACK.
+        assert_eq!(os.if_type, 24);
+        assert_eq!(os.tunnel_type, 0);
+        assert_ne!(os.luid, 0);
+        assert!(os.adapter_name.starts_with('{'), "{}", os.adapter_name);
+        assert_eq!(os.strings.friendly_name.as_str(), lo.name);
+    }
+
+    #[test]
+    fn adapter_flags_have_the_documented_bits() {
+        let bits = [
+            (AdapterFlags::DDNS_ENABLED, 0x0001),
+            (AdapterFlags::REGISTER_ADAPTER_SUFFIX, 0x0002),
+            (AdapterFlags::DHCP_ENABLED, 0x0004),
+            (AdapterFlags::RECEIVE_ONLY, 0x0008),
+            (AdapterFlags::NO_MULTICAST, 0x0010),
+            (AdapterFlags::IPV6_OTHER_STATEFUL_CONFIG, 0x0020),
+            (AdapterFlags::NETBIOS_OVER_TCPIP_ENABLED, 0x0040),
+            (AdapterFlags::IPV4_ENABLED, 0x0080),
+            (AdapterFlags::IPV6_ENABLED, 0x0100),
+            (AdapterFlags::IPV6_MANAGE_ADDRESS_CONFIG, 0x0200),
+        ];
+        for (flag, bit) in bits {
+            assert_eq!(flag.bits(), bit, "{:?}", flag);
+        }
+        assert_eq!(AdapterFlags::all().bits(), 0x03ff);
+    }
+
+    #[test]
+    fn adapter_flags_words_are_decoded() {
+        // A typical DHCP Ethernet adapter.
+        assert_eq!(
+            AdapterFlags::from_bits_truncate(0x01c5),
+            AdapterFlags::DDNS_ENABLED
+                | AdapterFlags::DHCP_ENABLED
+                | AdapterFlags::NETBIOS_OVER_TCPIP_ENABLED
+                | AdapterFlags::IPV4_ENABLED
+                | AdapterFlags::IPV6_ENABLED
+        );
+        // Bits Windows may add later are dropped.
+        assert_eq!(
+            AdapterFlags::from_bits_truncate(0x8000_0018),
+            AdapterFlags::RECEIVE_ONLY | AdapterFlags::NO_MULTICAST
+        );
+    }
+
+    #[test]
+    fn no_multicast_clears_the_multicast_flag() {
+        let mut adapter: IpAdapterAddresses = unsafe { mem::zeroed() };
+        adapter.all.if_type = 6;
+        adapter.all.oper_status = IfOperStatus::IfOperStatusUp as i32;
+        adapter.all.flags = IP_ADAPTER_IPV4_ENABLED;
+        assert!(flags_of(&adapter).contains(InterfaceFlags::MULTICAST));
+        adapter.all.flags |= IP_ADAPTER_NO_MULTICAST;
+        assert!(!flags_of(&adapter).contains(InterfaceFlags::MULTICAST));
+        assert!(flags_of(&adapter).contains(InterfaceFlags::UP | InterfaceFlags::BROADCAST));
+    }
+
+    use winapi::shared::ws2def::{AF_INET, AF_INET6, SOCKADDR_IN};
+    use winapi::shared::ws2ipdef::SOCKADDR_IN6;
+
+    const PREFIXES: [(&str, u8); 8] = [
+        ("192.0.2.0", 24),
+        // Listed twice.
+        ("192.0.2.0", 24),
+        // The host route to the address itself, and the broadcast one.
+        ("192.0.2.2", 32),
+        ("192.0.2.255", 32),
+        ("224.0.0.0", 4),
+        ("fd00::", 64),
+        ("fd00::2", 128),
+        ("ff00::", 8),
+    ];
+
+    /// A prefix list laid out the way Windows writes one.
+    #[repr(C)]
+    struct PrefixList {
+        entries: [IpAdapterPrefix; 8],
+        v4: [SOCKADDR_IN; 8],
+        v6: [SOCKADDR_IN6; 8],
+    }
+
+    fn prefix_list() -> Box<PrefixList> {
+        let mut list: Box<PrefixList> = Box::new(unsafe { mem::zeroed() });
+        let PrefixList { entries, v4, v6 } = &mut *list;
+        let first = entries.as_ptr();
+        for (i, (addr, len)) in PREFIXES.iter().enumerate() {
+            let entry = &mut entries[i];
+            entry.length = mem::size_of::<IpAdapterPrefix>() as ULONG;
+            entry.prefix_length = *len as ULONG;
+            if i + 1 < PREFIXES.len() {
+                entry.next = first.wrapping_add(i + 1);
+            }
+            match addr.parse().unwrap() {
+                IpAddr::V4(ip) => {
+                    let sin = &mut v4[i];
+                    sin.sin_family = AF_INET as u16;
+                    unsafe { *sin.sin_addr.S_un.S_addr_mut() = u32::from(ip).to_be() };
+                    entry.address.lpSockaddr = sin as *mut SOCKADDR_IN as *mut _;
+                    entry.address.iSockaddrLength = mem::size_of::<SOCKADDR_IN>() as i32;
+                }
+                IpAddr::V6(ip) => {
+                    let sin6 = &mut v6[i];
+                    sin6.sin6_family = AF_INET6 as u16;
+                    unsafe { *sin6.sin6_addr.u.Byte_mut() = ip.octets() };
+                    entry.address.lpSockaddr = sin6 as *mut SOCKADDR_IN6 as *mut _;
+                    entry.address.iSockaddrLength = mem::size_of::<SOCKADDR_IN6>() as i32;
+                }
+            }
+        }
+        list
+    }
+
+    fn bytes_of<T>(value: &T) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) }
+    }
+
+    fn prefix(addr: &str, len: u8) -> (IpAddr, u8) {
+        (addr.parse().unwrap(), len)
+    }
+
+    #[test]
+    fn prefixes_are_walked_without_duplicates() {
+        let list = prefix_list();
+        let bytes = bytes_of(&list);
+        let buffer = AdapterBuffer::new(bytes, bytes.as_ptr() as usize);
+        let prefixes = prefixes_of(&buffer, &list.entries[0]);
+        let mut expected: Vec<(IpAddr, u8)> = PREFIXES
+            .iter()
+            .map(|(addr, len)| prefix(addr, *len))
+            .collect();
+        expected.remove(1);
+        assert_eq!(prefixes, expected);
+        assert_eq!(prefixes_of(&buffer, ptr::null()), []);
+    }
+
+    #[test]
+    fn prefixes_outside_the_buffer_end_the_walk() {
+        let mut list = prefix_list();
+        let outside = mem::size_of::<PrefixList>() + 64;
+        let first = list.entries.as_ptr() as *const u8;
+        list.entries[2].next = first.wrapping_add(outside) as *const IpAdapterPrefix;
+        let bytes = bytes_of(&list);
+        let buffer = AdapterBuffer::new(bytes, bytes.as_ptr() as usize);
+        assert_eq!(
+            prefixes_of(&buffer, &list.entries[0]),
+            [prefix("192.0.2.0", 24), prefix("192.0.2.2", 32)]
+        );
+    }
+
+    #[test]
+    fn masks_come_from_the_longest_network() {
+        let prefixes: Vec<(IpAddr, u8)> = PREFIXES
+            .iter()
+            .map(|(addr, len)| prefix(addr, *len))
+            .chain(Some(prefix("192.0.0.0", 16)))
+            .collect();
+        let mask = |addr: &str| {
+            mask_from_prefixes(addr.parse().unwrap(), &prefixes).map(|mask| mask.ip().to_string())
+        };
+        // Not the host route.
+        assert_eq!(mask("192.0.2.2").as_deref(), Some("255.255.255.0"));
+        assert_eq!(mask("192.0.3.1").as_deref(), Some("255.255.0.0"));
+        assert_eq!(mask("fd00::2").as_deref(), Some("ffff:ffff:ffff:ffff::"));
+        assert_eq!(mask("198.51.100.1"), None);
+        // Multicast prefixes are not networks.
+        assert_eq!(mask("ff02::1"), None);
+        assert_eq!(mask("2001:db8::1"), None);
+    }
+
+    #[test]
+    fn the_buffer_is_reused_and_aligned() {
+        ifaces().unwrap();
+        let first = ADAPTERS_BUFFER.with(|buffer| buffer.borrow().bytes().as_ptr() as usize);
+        ifaces().unwrap();
+        let second = ADAPTERS_BUFFER.with(|buffer| buffer.borrow().bytes().as_ptr() as usize);
+        assert_eq!(first, second);
+        assert_eq!(second % mem::align_of::<IpAdapterAddresses>(), 0);
+    }
+
+    /// An adapter with one good unicast address and three of the kinds
+    /// drivers get wrong.
+    #[repr(C)]
+    struct MalformedAdapter {
+        adapter: IpAdapterAddresses,
+        unicasts: [IpAdapterUnicastAddress; 4],
+        addrs: [SOCKADDR_IN; 4],
+        name: [u16; 9],
+        guid: [u8; 40],
+    }
+
+    fn malformed_adapter() -> Box<MalformedAdapter> {
+        let mut list: Box<MalformedAdapter> = Box::new(unsafe { mem::zeroed() });
+        let MalformedAdapter {
+            adapter,
+            unicasts,
+            addrs,
+            name,
+            ..
+        } = &mut *list;
+        for (unit, c) in name.iter_mut().zip("Ethernet".encode_utf16()) {
+            *unit = c;
+        }
+        adapter.head.length = mem::size_of::<IpAdapterAddresses>() as ULONG;
+        adapter.head.if_index = 4;
+        adapter.all.friendly_name = name.as_mut_ptr();
+        adapter.all.first_unicast_address = unicasts.as_ptr();
+        let first = unicasts.as_ptr();
+        for (i, (unicast, sin)) in unicasts.iter_mut().zip(addrs.iter_mut()).enumerate() {
+            unicast.length = mem::size_of::<IpAdapterUnicastAddress>() as ULONG;
+            unicast.dad_state = IpDadState::IpDadStatePreferred as i32;
+            if i + 1 < 4 {
+                unicast.next = first.wrapping_add(i + 1);
+            }
+            sin.sin_family = AF_INET as u16;
+            unsafe { *sin.sin_addr.S_un.S_addr_mut() = u32::from_be_bytes([192, 0, 2, 2]).to_be() };
+            unicast.address.lpSockaddr = sin as *mut SOCKADDR_IN as *mut _;
+            unicast.address.iSockaddrLength = mem::size_of::<SOCKADDR_IN>() as i32;
+        }
+        unicasts[1].dad_state = IpDadState::IpDadStateDeprecated as i32;
+        unicasts[2].length = 8;
+        addrs[3].sin_family = AF_UNSPEC as u16;
+        list
+    }
+
+    #[test]
+    fn malformed_unicast_entries_are_warned_about() {
+        let list = malformed_adapter();
+        let bytes = bytes_of(&*list);
+        let (ifaces, warnings) = map_adapter_buffer(bytes, bytes.as_ptr() as usize);
+        assert_eq!(ifaces.len(), 1);
+        assert_eq!(ifaces[0].name, "Ethernet");
+        assert_eq!(ifaces[0].index, Some(4));
+        let warned: Vec<(&str, usize, &str, WarningAction)> = warnings
+            .iter()
+            .map(|w| (w.interface.as_str(), w.entry, w.field, w.action))
+            .collect();
+        assert_eq!(
+            warned,
+            [
+                ("Ethernet", 1, "dad_state", WarningAction::Skipped),
+                ("Ethernet", 2, "length", WarningAction::Skipped),
+                ("Ethernet", 3, "addr", WarningAction::Skipped),
+            ]
+        );
+        assert_eq!(warnings[2].reason, "unsupported address family 0");
+    }
+
+    #[test]
+    fn well_formed_adapters_have_no_warnings() {
+        let mut list = malformed_adapter();
+        list.unicasts[0].next = ptr::null();
+        let bytes = bytes_of(&*list);
+        let (ifaces, warnings) = map_adapter_buffer(bytes, bytes.as_ptr() as usize);
+        assert_eq!(ifaces.len(), 1);
+        assert_eq!(warnings, []);
+    }
+
+    #[test]
+    fn short_adapter_entries_are_skipped_whole() {
+        let mut list = malformed_adapter();
+        list.adapter.head.length = 16;
+        let bytes = bytes_of(&*list);
+        let (ifaces, warnings) = map_adapter_buffer(bytes, bytes.as_ptr() as usize);
+        assert!(ifaces.is_empty());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].field, "length");
+    }
+
+    #[test]
+    fn only_802_11_adapters_are_wireless() {
+        let os = |if_type| OsSpecific {
+            if_type,
+            ..OsSpecific::default()
+        };
+        // IF_TYPE_IEEE80211.
+        assert!(is_wireless("Wi-Fi", &os(71)));
+        // Ethernet, loopback, a tunnel, and WWAN.
+        for if_type in [6, 24, 131, 243] {
+            assert!(!is_wireless("Ethernet", &os(if_type)), "{}", if_type);
         }
     }
+
+    /// [`malformed_adapter`] with only its good address, and the XP and
+    /// Vista fields filled in.
+    fn full_adapter() -> Box<MalformedAdapter> {
+        let mut list = malformed_adapter();
+        list.unicasts[0].next = ptr::null();
+        list.unicasts[0].on_link_prefix_length = 24;
+        list.adapter.xp.ipv6_if_index = 9;
+        list.adapter.vista.luid = IfLuid::from_bits_truncate(0x0006_0000_0100_0000);
+        list.adapter.vista.tunnel_type = TunnelType::TunnelTypeTeredo as i32;
+        list
+    }
+
+    fn map(list: &MalformedAdapter) -> (Vec<Interface>, Vec<Warning>) {
+        let bytes = bytes_of(list);
+        map_adapter_buffer(bytes, bytes.as_ptr() as usize)
+    }
+
+    fn make_v6(list: &mut MalformedAdapter) {
+        let sin6 = list.addrs.as_mut_ptr() as *mut SOCKADDR_IN6;
+        // Two SOCKADDR_INs are room enough for one SOCKADDR_IN6.
+        let sin6 = unsafe { &mut *sin6 };
+        *sin6 = unsafe { mem::zeroed() };
+        sin6.sin6_family = AF_INET6 as u16;
+        unsafe {
+            *sin6.sin6_addr.u.Byte_mut() = "2001:db8::2".parse::<Ipv6Addr>().unwrap().octets()
+        };
+        list.unicasts[0].address.iSockaddrLength = mem::size_of::<SOCKADDR_IN6>() as i32;
+    }
+
+    #[test]
+    fn vista_adapters_are_read_whole() {
+        let list = full_adapter();
+        let (ifaces, warnings) = map(&list);
+        assert_eq!(warnings, []);
+        let os = ifaces[0].os_specific();
+        assert_eq!(os.luid, 0x0006_0000_0100_0000);
+        assert_eq!(os.tunnel_type, TunnelType::TunnelTypeTeredo as u32);
+        // From the on-link prefix length, with no prefix list to go by.
+        assert_eq!(ifaces[0].prefix_len(), Some(24));
+    }
+
+    #[test]
+    fn network_guids_come_from_the_vista_section() {
+        let mut list = full_adapter();
+        let raw = GUID {
+            Data1: 0x4d36_e972,
+            Data2: 0xe325,
+            Data3: 0x11ce,
+            Data4: [0xbf, 0xc1, 0x08, 0x00, 0x2b, 0xe1, 0x03, 0x18],
+        };
+        list.adapter.vista.network_guid = raw;
+        let (ifaces, _) = map(&list);
+        assert_eq!(
+            ifaces[0].os_specific().network_guid,
+            Some(Guid::from_raw(&raw))
+        );
+        // Not connected.
+        list.adapter.vista.network_guid = unsafe { mem::zeroed() };
+        assert_eq!(map(&list).0[0].os_specific().network_guid, None);
+        // Without the Vista section.
+        list.adapter.vista.network_guid = raw;
+        list.adapter.head.length = mem::offset_of!(IpAdapterAddresses, vista) as ULONG;
+        assert_eq!(map(&list).0[0].os_specific().network_guid, None);
+    }
+
+    #[test]
+    fn entries_share_the_decoded_friendly_name() {
+        let mut list = malformed_adapter();
+        for (unit, c) in list.name.iter_mut().zip("Réseau\0".encode_utf16()) {
+            *unit = c;
+        }
+        // Two good addresses.
+        list.unicasts[1].dad_state = IpDadState::IpDadStatePreferred as i32;
+        list.unicasts[1].next = ptr::null();
+        let (ifaces, warnings) = map(&list);
+        assert_eq!(warnings, []);
+        assert_eq!(ifaces.len(), 2);
+        assert!(ifaces.iter().all(|iface| iface.name == "Réseau"));
+    }
+
+    #[test]
+    fn adapters_without_a_friendly_name_go_by_their_guid() {
+        let mut list = full_adapter();
+        let guid = b"{4D36E972-E325-11CE-BFC1-08002BE10318}\0";
+        list.guid[..guid.len()].copy_from_slice(guid);
+        list.adapter.all.adapter_name = list.guid.as_mut_ptr() as PCHAR;
+        list.name[0] = 0;
+        let (ifaces, _) = map(&list);
+        assert_eq!(ifaces[0].name, "{4D36E972-E325-11CE-BFC1-08002BE10318}");
+        list.adapter.all.friendly_name = ptr::null_mut();
+        let (ifaces, _) = map(&list);
+        assert_eq!(ifaces[0].name, "{4D36E972-E325-11CE-BFC1-08002BE10318}");
+        assert_eq!(ifaces[0].os_specific().adapter_name, ifaces[0].name);
+    }
+
+    #[test]
+    fn xp_adapters_are_read_without_the_vista_fields() {
+        let mut list = full_adapter();
+        list.adapter.head.length = mem::offset_of!(IpAdapterAddresses, vista) as ULONG;
+        let (ifaces, warnings) = map(&list);
+        assert_eq!(warnings, []);
+        let os = ifaces[0].os_specific();
+        assert_eq!((os.luid, os.tunnel_type), (0, 0));
+        assert_eq!(ifaces[0].index, Some(4));
+
+        make_v6(&mut list);
+        let (ifaces, _) = map(&list);
+        assert_eq!(ifaces[0].index, Some(9));
+        assert_eq!(ifaces[0].addr, Some("[2001:db8::2%9]:0".parse().unwrap()));
+    }
+
+    #[test]
+    fn pre_xp_adapters_have_no_ipv6_index() {
+        let mut list = full_adapter();
+        list.adapter.head.length = MIN_ADAPTER_LENGTH as ULONG;
+        make_v6(&mut list);
+        let (ifaces, warnings) = map(&list);
+        assert_eq!(warnings, []);
+        assert_eq!(ifaces[0].index, None);
+        assert_eq!(ifaces[0].addr, Some("[2001:db8::2]:0".parse().unwrap()));
+        assert!(ifaces[0].os_specific().prefixes.is_empty());
+        assert_eq!(ifaces[0].os_specific().luid, 0);
+    }
+
+    #[test]
+    fn xp_unicast_entries_have_no_on_link_prefix_length() {
+        let mut list = full_adapter();
+        list.unicasts[0].length = MIN_UNICAST_LENGTH as ULONG;
+        let (ifaces, warnings) = map(&list);
+        assert_eq!(warnings, []);
+        assert_eq!(ifaces[0].mask, None);
+    }
+
+    #[test]
+    fn adapters_cut_off_by_the_buffer_end_are_not_read() {
+        let list = full_adapter();
+        let bytes = &bytes_of(&*list)[..MIN_ADAPTER_LENGTH];
+        let (ifaces, warnings) = map_adapter_buffer(bytes, bytes.as_ptr() as usize);
+        assert!(ifaces.is_empty());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].reason, "adapter outside the adapter buffer");
+    }
+
+    #[test]
+    fn dad_states_follow_the_unicast_entry() {
+        let mut list = full_adapter();
+        make_v6(&mut list);
+        let addr: Ipv6Addr = "2001:db8::2".parse().unwrap();
+        let state = |list: &MalformedAdapter, name: &str, addr: Ipv6Addr| {
+            let bytes = bytes_of(list);
+            let buffer = AdapterBuffer::new(bytes, bytes.as_ptr() as usize);
+            dad_state_in(&buffer, &crate::validate_name(name).unwrap(), addr)
+                .map_err(|err| err.kind())
+        };
+        for (raw, expected) in [
+            (IpDadState::IpDadStateInvalid, DadState::Tentative),
+            (IpDadState::IpDadStateTentative, DadState::Tentative),
+            (IpDadState::IpDadStateDuplicate, DadState::Duplicate),
+            (IpDadState::IpDadStateDeprecated, DadState::Usable),
+            (IpDadState::IpDadStatePreferred, DadState::Usable),
+        ] {
+            let raw = raw as i32;
+            list.unicasts[0].dad_state = raw;
+            assert_eq!(
+                state(&list, "Ethernet", addr),
+                Ok(Some(expected)),
+                "{}",
+                raw
+            );
+        }
+        assert_eq!(
+            state(&list, "Ethernet", "2001:db8::3".parse().unwrap()),
+            Ok(None)
+        );
+        assert_eq!(state(&list, "Wi-Fi", addr), Err(io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn origins_follow_the_prefix_and_suffix_origins() {
+        use IpPrefixOrigin::*;
+        use IpSuffixOrigin::*;
+
+        let table = [
+            (
+                IpPrefixOriginRouterAdvertisement,
+                IpSuffixOriginRandom,
+                AddressOrigin::SlaacTemporary,
+            ),
+            (
+                IpPrefixOriginRouterAdvertisement,
+                IpSuffixOriginLinkLayerAddress,
+                AddressOrigin::Slaac,
+            ),
+            (
+                IpPrefixOriginRouterAdvertisement,
+                IpSuffixOriginOther,
+                AddressOrigin::Slaac,
+            ),
+            (IpPrefixOriginDhcp, IpSuffixOriginDhcp, AddressOrigin::Dhcp),
+            // A DHCP suffix is enough.
+            (IpPrefixOriginOther, IpSuffixOriginDhcp, AddressOrigin::Dhcp),
+            (
+                IpPrefixOriginManual,
+                IpSuffixOriginManual,
+                AddressOrigin::Static,
+            ),
+            (
+                IpPrefixOriginWellKnown,
+                IpSuffixOriginWellKnown,
+                AddressOrigin::WellKnown,
+            ),
+            (
+                IpPrefixOriginOther,
+                IpSuffixOriginOther,
+                AddressOrigin::Unknown,
+            ),
+            (
+                IpPrefixOriginUnchanged,
+                IpSuffixOriginUnchanged,
+                AddressOrigin::Unknown,
+            ),
+        ];
+        let addr = "2001:db8::2".parse().unwrap();
+        for (prefix, suffix, origin) in table {
+            let mut unicast: IpAdapterUnicastAddress = unsafe { mem::zeroed() };
+            unicast.prefix_origin = prefix as i32;
+            unicast.suffix_origin = suffix as i32;
+            assert_eq!(
+                origin_of(&addr, &unicast),
+                origin,
+                "{} {}",
+                unicast.prefix_origin,
+                unicast.suffix_origin
+            );
+        }
+    }
+
+    #[test]
+    fn origins_windows_does_not_define_are_unknown() {
+        let mut unicast: IpAdapterUnicastAddress = unsafe { mem::zeroed() };
+        unicast.prefix_origin = 7;
+        unicast.suffix_origin = 9;
+        let addr = "192.0.2.2".parse().unwrap();
+        assert_eq!(origin_of(&addr, &unicast), AddressOrigin::Unknown);
+        // Not even with a known suffix.
+        unicast.suffix_origin = IpSuffixOrigin::IpSuffixOriginRandom as i32;
+        assert_eq!(origin_of(&addr, &unicast), AddressOrigin::Unknown);
+    }
+
+    #[test]
+    fn the_address_wins_over_the_origins() {
+        let mut unicast: IpAdapterUnicastAddress = unsafe { mem::zeroed() };
+        unicast.prefix_origin = IpPrefixOrigin::IpPrefixOriginManual as i32;
+        unicast.suffix_origin = IpSuffixOrigin::IpSuffixOriginManual as i32;
+        let origin = |ip: &str| origin_of(&ip.parse().unwrap(), &unicast);
+        assert_eq!(origin("fe80::1"), AddressOrigin::LinkLocal);
+        assert_eq!(origin("169.254.1.1"), AddressOrigin::LinkLocal);
+        assert_eq!(origin("::1"), AddressOrigin::WellKnown);
+        assert_eq!(origin("192.0.2.2"), AddressOrigin::Static);
+    }
+
+    #[test]
+    fn entries_carry_their_origin() {
+        let mut list = full_adapter();
+        list.unicasts[0].prefix_origin = IpPrefixOrigin::IpPrefixOriginDhcp as i32;
+        list.unicasts[0].suffix_origin = IpSuffixOrigin::IpSuffixOriginDhcp as i32;
+        let (ifaces, warnings) = map(&list);
+        assert_eq!(warnings, []);
+        assert_eq!(ifaces[0].origin, AddressOrigin::Dhcp);
+    }
+
+    #[test]
+    fn connectivity_costs_map_to_metered() {
+        // NetworkConnectivityCostHint{Unknown,Unrestricted,Fixed,Variable}.
+        let table = [
+            (0, None),
+            (1, Some(false)),
+            (2, Some(true)),
+            (3, Some(true)),
+        ];
+        for (cost, metered) in table {
+            assert_eq!(metered_from_cost(cost), metered, "{}", cost);
+        }
+        // Values later releases may add are not known either.
+        assert_eq!(metered_from_cost(4), None);
+        assert_eq!(metered_from_cost(-1), None);
+    }
+
+    #[test]
+    fn connectivity_hints_have_the_c_layout() {
+        assert_eq!(
+            mem::offset_of!(NL_NETWORK_CONNECTIVITY_HINT, ConnectivityCost),
+            4
+        );
+        assert_eq!(mem::offset_of!(NL_NETWORK_CONNECTIVITY_HINT, Roaming), 10);
+        assert_eq!(mem::size_of::<NL_NETWORK_CONNECTIVITY_HINT>(), 12);
+    }
+
+    #[test]
+    fn unknown_adapters_are_not_metered_or_unmetered() {
+        assert_eq!(is_metered(u32::MAX), None);
+        let mut iface = Interface::new("Ethernet", crate::Kind::Ipv4);
+        iface.index = None;
+        assert_eq!(iface.is_metered(), None);
+    }
+
+    #[test]
+    fn mobile_broadband_adapters_are_cellular() {
+        let kind = |if_type| {
+            hardware_kind(&OsSpecific {
+                if_type,
+                ..OsSpecific::default()
+            })
+        };
+        // IF_TYPE_WWANPP and IF_TYPE_WWANPP2.
+        assert_eq!(kind(243), HardwareKind::Cellular);
+        assert_eq!(kind(244), HardwareKind::Cellular);
+        assert_eq!(kind(6), HardwareKind::Ethernet);
+        assert_eq!(kind(71), HardwareKind::Wireless);
+    }
 }
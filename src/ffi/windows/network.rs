@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use winapi::shared::guiddef::GUID;
+
+use crate::Interface;
+
+/// A GUID, which shows in registry form, e.g.
+/// `{4D36E972-E325-11CE-BFC1-08002BE10318}`.
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Clone, Copy, Default)]
+pub struct Guid {
+    pub data1: u32,
+    pub data2: u16,
+    pub data3: u16,
+    pub data4: [u8; 8],
+}
+
+impl Guid {
+    pub(crate) fn from_raw(guid: &GUID) -> Guid {
+        Guid {
+            data1: guid.Data1,
+            data2: guid.Data2,
+            data3: guid.Data3,
+            data4: guid.Data4,
+        }
+    }
+
+    /// Whether this is the all-zero GUID, which stands for none.
+    pub fn is_nil(&self) -> bool {
+        *self == Guid::default()
+    }
+}
+
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let d = &self.data4;
+        write!(
+            f,
+            "{{{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}}}",
+            self.data1, self.data2, self.data3, d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7]
+        )
+    }
+}
+
+/// The adapters in `ifaces` by the network they are connected to, its
+/// [`network_guid`](crate::OsSpecific::network_guid). Adapters on the same
+/// LAN share the network, e.g. both ends of a bridged setup; the ones not
+/// connected to any are under `None`.
+///
+/// `ifaces` has an entry per address, each adapter is in here once, with
+/// its first entry, in the order of `ifaces`.
+pub fn group_by_network(ifaces: &[Interface]) -> HashMap<Option<Guid>, Vec<&Interface>> {
+    let mut groups: HashMap<Option<Guid>, Vec<&Interface>> = HashMap::new();
+    for iface in ifaces {
+        let os = iface.os_specific();
+        let group = groups.entry(os.network_guid).or_default();
+        if !group
+            .iter()
+            .any(|seen| seen.os_specific().adapter_name == os.adapter_name)
+        {
+            group.push(iface);
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Kind, OsSpecific};
+
+    const LAN: Guid = Guid {
+        data1: 0x4d36_e972,
+        data2: 0xe325,
+        data3: 0x11ce,
+        data4: [0xbf, 0xc1, 0x08, 0x00, 0x2b, 0xe1, 0x03, 0x18],
+    };
+
+    #[test]
+    fn guids_show_in_registry_form() {
+        assert_eq!(LAN.to_string(), "{4D36E972-E325-11CE-BFC1-08002BE10318}");
+        assert_eq!(
+            Guid::default().to_string(),
+            "{00000000-0000-0000-0000-000000000000}"
+        );
+        let small = Guid {
+            data1: 1,
+            data2: 2,
+            data3: 3,
+            data4: [0, 4, 0, 0, 0, 0, 0, 5],
+        };
+        assert_eq!(small.to_string(), "{00000001-0002-0003-0004-000000000005}");
+    }
+
+    #[test]
+    fn raw_guids_convert_field_by_field() {
+        let raw = GUID {
+            Data1: LAN.data1,
+            Data2: LAN.data2,
+            Data3: LAN.data3,
+            Data4: LAN.data4,
+        };
+        assert_eq!(Guid::from_raw(&raw), LAN);
+        assert!(!LAN.is_nil());
+        assert!(Guid::default().is_nil());
+    }
+
+    /// An entry of adapter `adapter` on network `network`.
+    fn entry(name: &str, adapter: &str, network: Option<Guid>, kind: Kind) -> Interface {
+        Interface::new(name, kind).with_os_specific(OsSpecific {
+            adapter_name: adapter.to_owned(),
+            network_guid: network,
+            ..OsSpecific::default()
+        })
+    }
+
+    #[test]
+    fn adapters_on_the_same_network_are_grouped() {
+        let ifaces = [
+            entry("Ethernet", "{A}", Some(LAN), Kind::Ipv4),
+            entry("Ethernet", "{A}", Some(LAN), Kind::Ipv6),
+            entry("Ethernet 2", "{B}", Some(LAN), Kind::Ipv4),
+            entry("Wi-Fi", "{C}", None, Kind::Ipv4),
+            entry("Wi-Fi", "{C}", None, Kind::Ipv6),
+        ];
+        let groups = group_by_network(&ifaces);
+        assert_eq!(groups.len(), 2);
+        let names = |network| {
+            groups[&network]
+                .iter()
+                .map(|iface| (iface.name.as_str(), iface.kind.clone()))
+                .collect::<Vec<_>>()
+        };
+        // Once each, with their first entry.
+        assert_eq!(
+            names(Some(LAN)),
+            [("Ethernet", Kind::Ipv4), ("Ethernet 2", Kind::Ipv4)]
+        );
+        assert_eq!(names(None), [("Wi-Fi", Kind::Ipv4)]);
+        assert!(group_by_network(&[]).is_empty());
+    }
+
+    #[test]
+    fn this_machine_is_grouped_by_adapter() {
+        let ifaces = crate::ifaces().unwrap();
+        let groups = group_by_network(&ifaces);
+        let mut adapters: Vec<&str> = groups
+            .values()
+            .flatten()
+            .map(|iface| iface.os_specific().adapter_name.as_str())
+            .collect();
+        let grouped = adapters.len();
+        adapters.sort_unstable();
+        adapters.dedup();
+        assert_eq!(adapters.len(), grouped);
+        for (network, members) in &groups {
+            assert!(members
+                .iter()
+                .all(|iface| iface.os_specific().network_guid == *network));
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! `IP_PKTINFO` and `IPV6_PKTINFO` control messages, read with
+//! `WSARecvMsg`.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket};
+use std::os::raw::{c_char, c_int};
+use std::os::windows::io::AsRawSocket;
+use std::sync::OnceLock;
+use std::{io, mem, ptr};
+
+use super::socket::socket_addr;
+use crate::pktinfo::Received;
+
+use winapi::shared::guiddef::GUID;
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::ws2def::{
+    IPPROTO_IP, IPPROTO_IPV6, SIO_GET_EXTENSION_FUNCTION_POINTER, SOCKADDR, SOCKADDR_STORAGE,
+    WSABUF, WSACMSGHDR, WSAMSG,
+};
+use winapi::shared::ws2ipdef::{IN6_PKTINFO, IN_PKTINFO, IPV6_PKTINFO, IP_PKTINFO};
+use winapi::um::mswsock::{LPFN_WSARECVMSG, WSAID_WSARECVMSG};
+use winapi::um::winsock2::{setsockopt, WSAIoctl, SOCKET, SOCKET_ERROR};
+
+/// Room for one `IN6_PKTINFO` message, or one `IN_PKTINFO` message, and
+/// aligned for `WSACMSGHDR`.
+type ControlBuffer = [u64; 8];
+
+/// What `WSA_CMSGHDR_ALIGN` and `WSA_CMSGDATA_ALIGN` round to.
+const CMSG_ALIGN: usize = mem::size_of::<usize>();
+
+pub fn enable_pktinfo(socket: &UdpSocket, v6: bool) -> io::Result<()> {
+    let (level, option) = match v6 {
+        true => (IPPROTO_IPV6 as c_int, IPV6_PKTINFO),
+        false => (IPPROTO_IP, IP_PKTINFO),
+    };
+    let on: DWORD = 1;
+    let ret = unsafe {
+        setsockopt(
+            socket.as_raw_socket() as SOCKET,
+            level,
+            option,
+            &on as *const DWORD as *const c_char,
+            mem::size_of::<DWORD>() as c_int,
+        )
+    };
+    match ret {
+        SOCKET_ERROR => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// `WSARecvMsg`, which has no import and is looked up through the first
+/// socket it is needed for.
+fn wsa_recv_msg(socket: SOCKET) -> io::Result<LPFN_WSARECVMSG> {
+    static FUNCTION: OnceLock<LPFN_WSARECVMSG> = OnceLock::new();
+    if let Some(function) = FUNCTION.get() {
+        return Ok(*function);
+    }
+    let mut guid: GUID = WSAID_WSARECVMSG;
+    let mut function: LPFN_WSARECVMSG = None;
+    let mut returned: DWORD = 0;
+    let ret = unsafe {
+        WSAIoctl(
+            socket,
+            SIO_GET_EXTENSION_FUNCTION_POINTER,
+            &mut guid as *mut GUID as *mut _,
+            mem::size_of::<GUID>() as DWORD,
+            &mut function as *mut LPFN_WSARECVMSG as *mut _,
+            mem::size_of::<LPFN_WSARECVMSG>() as DWORD,
+            &mut returned,
+            ptr::null_mut(),
+            None,
+        )
+    };
+    if ret == SOCKET_ERROR {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(*FUNCTION.get_or_init(|| function))
+}
+
+/// Receive a datagram with its packet info, `None` if it came without.
+pub fn recv_pktinfo(socket: &UdpSocket, buf: &mut [u8]) -> io::Result<Received> {
+    let socket = socket.as_raw_socket() as SOCKET;
+    let function = wsa_recv_msg(socket)?.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::Unsupported, "no WSARecvMsg for the socket")
+    })?;
+    let mut from: SOCKADDR_STORAGE = unsafe { mem::zeroed() };
+    let mut control: ControlBuffer = [0; 8];
+    let mut data = WSABUF {
+        len: buf.len().min(u32::MAX as usize) as u32,
+        buf: buf.as_mut_ptr() as *mut c_char,
+    };
+    let mut msg = WSAMSG {
+        name: &mut from as *mut SOCKADDR_STORAGE as *mut SOCKADDR,
+        namelen: mem::size_of::<SOCKADDR_STORAGE>() as c_int,
+        lpBuffers: &mut data,
+        dwBufferCount: 1,
+        Control: WSABUF {
+            len: mem::size_of::<ControlBuffer>() as u32,
+            buf: control.as_mut_ptr() as *mut c_char,
+        },
+        dwFlags: 0,
+    };
+    let mut len: DWORD = 0;
+    let ret = unsafe { function(socket, &mut msg, &mut len, ptr::null_mut(), None) };
+    if ret == SOCKET_ERROR {
+        return Err(io::Error::last_os_error());
+    }
+    let from = socket_addr(&from).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the sender address is not IPv4 or IPv6",
+        )
+    })?;
+    let control = &control_bytes(&control)[..msg.Control.len as usize];
+    Ok((len as usize, from, parse_control(control)))
+}
+
+fn control_bytes(control: &ControlBuffer) -> &[u8] {
+    unsafe {
+        std::slice::from_raw_parts(
+            control.as_ptr() as *const u8,
+            mem::size_of::<ControlBuffer>(),
+        )
+    }
+}
+
+/// The interface index and destination address in `control`, the
+/// control messages `WSARecvMsg` returned, from the first packet info
+/// message. Walks them as the `WSA_CMSG_*` macros do.
+fn parse_control(control: &[u8]) -> Option<(u32, IpAddr)> {
+    let header_len = mem::size_of::<WSACMSGHDR>();
+    let data_offset = header_len.next_multiple_of(CMSG_ALIGN);
+    let mut offset = 0;
+    while offset + header_len <= control.len() {
+        let header =
+            unsafe { ptr::read_unaligned(control[offset..].as_ptr() as *const WSACMSGHDR) };
+        if header.cmsg_len < header_len {
+            return None;
+        }
+        let data = control.get(offset + data_offset..offset + header.cmsg_len)?;
+        match (header.cmsg_level, header.cmsg_type) {
+            (IPPROTO_IP, IP_PKTINFO) if data.len() >= mem::size_of::<IN_PKTINFO>() => {
+                let info = unsafe { ptr::read_unaligned(data.as_ptr() as *const IN_PKTINFO) };
+                let destination =
+                    Ipv4Addr::from(unsafe { *info.ipi_addr.S_un.S_addr() }.to_ne_bytes());
+                return Some((info.ipi_ifindex, destination.into()));
+            }
+            (level, IPV6_PKTINFO)
+                if level == IPPROTO_IPV6 as c_int
+                    && data.len() >= mem::size_of::<IN6_PKTINFO>() =>
+            {
+                let info = unsafe { ptr::read_unaligned(data.as_ptr() as *const IN6_PKTINFO) };
+                let destination = Ipv6Addr::from(*unsafe { info.ipi6_addr.u.Byte() });
+                return Some((info.ipi6_ifindex, destination.into()));
+            }
+            _ => {}
+        }
+        offset += header.cmsg_len.next_multiple_of(CMSG_ALIGN);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::shared::ws2ipdef::IP_TTL;
+
+    use super::*;
+
+    /// `messages` laid out as `WSARecvMsg` does, with each header and its
+    /// data aligned to `CMSG_ALIGN`.
+    fn control(messages: &[(c_int, c_int, &[u8])]) -> Vec<u8> {
+        let header_len = mem::size_of::<WSACMSGHDR>();
+        let data_offset = header_len.next_multiple_of(CMSG_ALIGN);
+        let mut control = Vec::new();
+        for (level, ty, data) in messages {
+            let header = WSACMSGHDR {
+                cmsg_len: data_offset + data.len(),
+                cmsg_level: *level,
+                cmsg_type: *ty,
+            };
+            let start = control.len();
+            control.resize(start + data_offset, 0);
+            unsafe {
+                ptr::write_unaligned(control[start..].as_mut_ptr() as *mut WSACMSGHDR, header)
+            };
+            control.extend_from_slice(data);
+            control.resize(control.len().next_multiple_of(CMSG_ALIGN), 0);
+        }
+        control
+    }
+
+    fn bytes_of<T>(value: &T) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) }
+    }
+
+    fn in_pktinfo(index: u32, destination: [u8; 4]) -> IN_PKTINFO {
+        let mut info: IN_PKTINFO = unsafe { mem::zeroed() };
+        info.ipi_ifindex = index;
+        unsafe { *info.ipi_addr.S_un.S_addr_mut() = u32::from_ne_bytes(destination) };
+        info
+    }
+
+    #[test]
+    fn ipv4_packet_info_is_read() {
+        let info = in_pktinfo(3, [192, 0, 2, 1]);
+        let control = control(&[(IPPROTO_IP, IP_PKTINFO, bytes_of(&info))]);
+        assert_eq!(
+            parse_control(&control),
+            Some((3, IpAddr::from([192, 0, 2, 1])))
+        );
+    }
+
+    #[test]
+    fn ipv6_packet_info_is_read() {
+        let destination: Ipv6Addr = "ff02::fb".parse().unwrap();
+        let mut info: IN6_PKTINFO = unsafe { mem::zeroed() };
+        info.ipi6_ifindex = 7;
+        unsafe { *info.ipi6_addr.u.Byte_mut() = destination.octets() };
+        let control = control(&[(IPPROTO_IPV6 as c_int, IPV6_PKTINFO, bytes_of(&info))]);
+        assert_eq!(parse_control(&control), Some((7, destination.into())));
+    }
+
+    #[test]
+    fn other_messages_are_skipped() {
+        let ttl: c_int = 64;
+        let info = in_pktinfo(2, [255, 255, 255, 255]);
+        let control = control(&[
+            (IPPROTO_IP, IP_TTL, bytes_of(&ttl)),
+            (IPPROTO_IP, IP_PKTINFO, bytes_of(&info)),
+        ]);
+        assert_eq!(
+            parse_control(&control),
+            Some((2, IpAddr::from([255, 255, 255, 255])))
+        );
+    }
+
+    #[test]
+    fn short_and_malformed_buffers_are_none() {
+        assert_eq!(parse_control(&[]), None);
+        let info = in_pktinfo(3, [192, 0, 2, 1]);
+        let full = control(&[(IPPROTO_IP, IP_PKTINFO, bytes_of(&info))]);
+        // Cut into the data, and into the header.
+        assert_eq!(parse_control(&full[..full.len() - CMSG_ALIGN]), None);
+        assert_eq!(parse_control(&full[..4]), None);
+        // A data length shorter than IN_PKTINFO.
+        let short = control(&[(IPPROTO_IP, IP_PKTINFO, &bytes_of(&info)[..4])]);
+        assert_eq!(parse_control(&short), None);
+        // A length shorter than the header, which would never advance.
+        let mut looping = full.clone();
+        unsafe { ptr::write_unaligned(looping.as_mut_ptr() as *mut usize, 0) };
+        assert_eq!(parse_control(&looping), None);
+    }
+}
@@ -0,0 +1,60 @@
+//! PTR names with `GetNameInfoW`.
+
+use std::mem;
+use std::net::IpAddr;
+use std::os::raw::c_int;
+
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::ws2def::{AF_INET, AF_INET6, SOCKADDR, SOCKADDR_IN, SOCKADDR_STORAGE};
+use winapi::shared::ws2ipdef::SOCKADDR_IN6;
+use winapi::um::winsock2::{WSACleanup, WSAStartup, WSADATA};
+use winapi::um::ws2tcpip::GetNameInfoW;
+
+/// `NI_MAXHOST`.
+const MAX_HOST: usize = 1025;
+/// Fail rather than give the address back when it has no name.
+const NI_NAMEREQD: c_int = 0x04;
+
+/// The PTR name of `addr`, `None` if it has none or the lookup fails.
+pub fn ptr_name(addr: IpAddr) -> Option<String> {
+    let mut storage: SOCKADDR_STORAGE = unsafe { mem::zeroed() };
+    let len = match addr {
+        IpAddr::V4(addr) => {
+            let sin = unsafe { &mut *(&mut storage as *mut _ as *mut SOCKADDR_IN) };
+            sin.sin_family = AF_INET as u16;
+            unsafe { *sin.sin_addr.S_un.S_addr_mut() = u32::from(addr).to_be() };
+            mem::size_of::<SOCKADDR_IN>()
+        }
+        IpAddr::V6(addr) => {
+            let sin6 = unsafe { &mut *(&mut storage as *mut _ as *mut SOCKADDR_IN6) };
+            sin6.sin6_family = AF_INET6 as u16;
+            unsafe { *sin6.sin6_addr.u.Byte_mut() = addr.octets() };
+            mem::size_of::<SOCKADDR_IN6>()
+        }
+    };
+
+    // Winsock has to be started by whoever calls it; the count is per
+    // process, so this does not get in the way of std's own.
+    let mut data: WSADATA = unsafe { mem::zeroed() };
+    if unsafe { WSAStartup(0x0202, &mut data) } != 0 {
+        return None;
+    }
+    let mut host = [0u16; MAX_HOST];
+    let ret = unsafe {
+        GetNameInfoW(
+            &storage as *const _ as *const SOCKADDR,
+            len as c_int,
+            host.as_mut_ptr(),
+            host.len() as DWORD,
+            std::ptr::null_mut(),
+            0,
+            NI_NAMEREQD,
+        )
+    };
+    unsafe { WSACleanup() };
+    if ret != 0 {
+        return None;
+    }
+    let end = host.iter().position(|&c| c == 0).unwrap_or(host.len());
+    String::from_utf16(&host[..end]).ok()
+}
@@ -0,0 +1,194 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::os::raw::{c_char, c_int};
+use std::os::windows::io::AsRawSocket;
+use std::{io, mem};
+
+use winapi::shared::winerror::WSAEINVAL;
+use winapi::shared::ws2def::{
+    AF_INET, AF_INET6, IPPROTO_IP, IPPROTO_IPV6, SOCKADDR, SOCKADDR_IN, SOCKADDR_STORAGE,
+};
+use winapi::shared::ws2ipdef::{IPV6_UNICAST_IF, SOCKADDR_IN6};
+use winapi::um::winsock2::{
+    getsockname, getsockopt, setsockopt, SOCKET, SOCKET_ERROR, SOL_SOCKET, SO_PROTOCOL_INFOW,
+    WSAPROTOCOL_INFOW,
+};
+
+use crate::Interface;
+
+const IP_UNICAST_IF: c_int = 31;
+
+/// Restrict a socket to sending through one interface.
+///
+/// Call this before `bind`/`connect`. This sets `IP_UNICAST_IF` or
+/// `IPV6_UNICAST_IF` according to the socket's own address family, using the
+/// interface index.
+pub fn bind_socket_to_interface(socket: &impl AsRawSocket, iface: &Interface) -> io::Result<()> {
+    let socket = socket.as_raw_socket() as SOCKET;
+    let index = iface
+        .index
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "interface has no index"))?;
+
+    // getsockname() fails on unbound sockets, the protocol info does not.
+    let mut info: WSAPROTOCOL_INFOW = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<WSAPROTOCOL_INFOW>() as c_int;
+    let ret = unsafe {
+        getsockopt(
+            socket,
+            SOL_SOCKET,
+            SO_PROTOCOL_INFOW,
+            &mut info as *mut _ as *mut c_char,
+            &mut len,
+        )
+    };
+    if ret == SOCKET_ERROR {
+        return Err(io::Error::last_os_error());
+    }
+
+    // The IPv4 option wants the index in network byte order, IPv6 does not.
+    let (level, option, value) = match info.iAddressFamily {
+        AF_INET6 => (IPPROTO_IPV6 as c_int, IPV6_UNICAST_IF, index),
+        _ => (IPPROTO_IP, IP_UNICAST_IF, index.to_be()),
+    };
+    let ret = unsafe {
+        setsockopt(
+            socket,
+            level,
+            option,
+            &value as *const u32 as *const c_char,
+            mem::size_of::<u32>() as c_int,
+        )
+    };
+    match ret {
+        SOCKET_ERROR => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// The address and port of a SOCKADDR_IN or SOCKADDR_IN6, `None` for other
+/// families.
+pub(super) fn socket_addr(addr: &SOCKADDR_STORAGE) -> Option<SocketAddr> {
+    match addr.ss_family as c_int {
+        AF_INET => {
+            let addr = unsafe { &*(addr as *const _ as *const SOCKADDR_IN) };
+            let ip = Ipv4Addr::from(unsafe { *addr.sin_addr.S_un.S_addr() }.to_ne_bytes());
+            Some(SocketAddr::V4(SocketAddrV4::new(
+                ip,
+                u16::from_be(addr.sin_port),
+            )))
+        }
+        AF_INET6 => {
+            let addr = unsafe { &*(addr as *const _ as *const SOCKADDR_IN6) };
+            Some(SocketAddr::V6(SocketAddrV6::new(
+                Ipv6Addr::from(*unsafe { addr.sin6_addr.u.Byte() }),
+                u16::from_be(addr.sin6_port),
+                addr.sin6_flowinfo,
+                unsafe { *addr.u.sin6_scope_id() },
+            )))
+        }
+        _ => None,
+    }
+}
+
+/// The local address of `socket`, from `getsockname`. `None` if it is not
+/// bound yet, which Windows fails on.
+fn local_addr(socket: SOCKET) -> io::Result<Option<SocketAddr>> {
+    let mut addr: SOCKADDR_STORAGE = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<SOCKADDR_STORAGE>() as c_int;
+    if unsafe { getsockname(socket, &mut addr as *mut _ as *mut SOCKADDR, &mut len) }
+        == SOCKET_ERROR
+    {
+        let err = io::Error::last_os_error();
+        return match err.raw_os_error() {
+            Some(code) if code == WSAEINVAL as i32 => Ok(None),
+            _ => Err(err),
+        };
+    }
+    socket_addr(&addr)
+        .map(Some)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not an IPv4 or IPv6 socket"))
+}
+
+/// The index of the interface `socket` sends through, as
+/// [`bind_socket_to_interface`] sets it.
+fn bound_index(socket: SOCKET, v6: bool) -> io::Result<Option<u32>> {
+    let (level, option) = match v6 {
+        true => (IPPROTO_IPV6 as c_int, IPV6_UNICAST_IF),
+        false => (IPPROTO_IP, IP_UNICAST_IF),
+    };
+    let mut index: u32 = 0;
+    let mut len = mem::size_of::<u32>() as c_int;
+    let ret = unsafe {
+        getsockopt(
+            socket,
+            level,
+            option,
+            &mut index as *mut u32 as *mut c_char,
+            &mut len,
+        )
+    };
+    if ret == SOCKET_ERROR {
+        return Err(io::Error::last_os_error());
+    }
+    // Network byte order for IPv4, as when setting it.
+    let index = match v6 {
+        true => index,
+        false => u32::from_be(index),
+    };
+    Ok(Some(index).filter(|&index| index != 0))
+}
+
+/// The interface entry `socket` uses, matched by its local address, scope
+/// id included, among the entries [`ifaces`](crate::ifaces) lists.
+///
+/// A socket with `IP_UNICAST_IF` or `IPV6_UNICAST_IF` set gets the entry of
+/// that interface if several have the address. A socket bound to the
+/// unspecified address, or not bound yet, receives on every interface, so
+/// this is `None` for it unless one of those options is set. A socket that
+/// is not IPv4 or IPv6 fails with `ErrorKind::InvalidInput`.
+pub fn interface_of_socket(socket: &impl AsRawSocket) -> io::Result<Option<Interface>> {
+    let socket = socket.as_raw_socket() as SOCKET;
+    let local = match local_addr(socket)? {
+        Some(local) => local,
+        None => return Ok(None),
+    };
+    crate::socket::interface_of(local, bound_index(socket, local.is_ipv6())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::UdpSocket;
+
+    use crate::{InterfaceFlags, Kind};
+
+    #[test]
+    fn udp_traffic_flows_on_the_bound_interface() {
+        let loopback = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| {
+                iface.kind == Kind::Ipv4 && iface.flags.contains(InterfaceFlags::LOOPBACK)
+            })
+            .unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        bind_socket_to_interface(&socket, &loopback).unwrap();
+        socket
+            .send_to(b"ping", socket.local_addr().unwrap())
+            .unwrap();
+        let mut buf = [0; 4];
+        socket
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+        let (len, _) = socket.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"ping");
+    }
+
+    #[test]
+    fn interfaces_without_an_index_fail_cleanly() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let err = bind_socket_to_interface(&socket, &Interface::new("nosuchif0", Kind::Ipv4))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,191 @@
+//! An owned `GetAdaptersAddresses` buffer, which
+//! [`ifaces`](super::ifaces) reads and `ifaces::sys` hands out.
+
+#![cfg_attr(not(feature = "sys"), allow(dead_code))]
+
+use std::io;
+use std::net::SocketAddr;
+
+use super::buffer::{AdapterBuffer, Chain};
+use super::{adapter_bytes, local_ifaces_with_buffer, IpAdapterAddresses, WideString};
+
+/// The adapter list `GetAdaptersAddresses` fills in, in a buffer aligned
+/// for it. The pointers in it point into the buffer, which moves with the
+/// list.
+pub struct AdapterList {
+    // u64 elements keep the buffer aligned for IpAdapterAddresses.
+    buffer: Vec<u64>,
+}
+
+impl AdapterList {
+    /// An empty list, which [`refresh`](AdapterList::refresh) fills in.
+    pub(super) const fn empty() -> AdapterList {
+        AdapterList { buffer: Vec::new() }
+    }
+
+    pub fn new() -> io::Result<AdapterList> {
+        let mut list = AdapterList::empty();
+        list.refresh()?;
+        Ok(list)
+    }
+
+    /// Fetch the list again, reusing the buffer where it is big enough.
+    pub fn refresh(&mut self) -> io::Result<()> {
+        local_ifaces_with_buffer(&mut self.buffer)
+    }
+
+    /// The buffer, its pointers relative to `bytes().as_ptr()`.
+    pub fn bytes(&self) -> &[u8] {
+        adapter_bytes(&self.buffer)
+    }
+
+    pub fn iter(&self) -> Adapters<'_> {
+        let bytes = self.bytes();
+        let buffer = AdapterBuffer::new(bytes, bytes.as_ptr() as usize);
+        Adapters {
+            buffer,
+            adapters: buffer.list(|adapter: &IpAdapterAddresses| adapter.all.next),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a AdapterList {
+    type Item = Adapter<'a>;
+    type IntoIter = Adapters<'a>;
+
+    fn into_iter(self) -> Adapters<'a> {
+        self.iter()
+    }
+}
+
+/// The adapters of an [`AdapterList`], up to the first one that is not
+/// all in the buffer.
+pub struct Adapters<'a> {
+    buffer: AdapterBuffer<'a>,
+    adapters: Chain<'a, IpAdapterAddresses>,
+}
+
+impl<'a> Iterator for Adapters<'a> {
+    type Item = Adapter<'a>;
+
+    fn next(&mut self) -> Option<Adapter<'a>> {
+        let raw = self.adapters.next()??;
+        Some(Adapter {
+            raw,
+            buffer: self.buffer,
+        })
+    }
+}
+
+/// One adapter of an [`AdapterList`].
+#[derive(Clone, Copy)]
+pub struct Adapter<'a> {
+    raw: &'a IpAdapterAddresses,
+    buffer: AdapterBuffer<'a>,
+}
+
+impl<'a> Adapter<'a> {
+    /// The adapter as Windows filled it in, for the fields there is no
+    /// accessor for. Its pointers point into the list's buffer; fields past
+    /// `head.length` were not filled in by older releases of Windows.
+    pub fn raw(&self) -> &'a IpAdapterAddresses {
+        self.raw
+    }
+
+    pub fn index(&self) -> u32 {
+        self.raw.head.if_index
+    }
+
+    /// The GUID name, e.g. `{4D36E972-...}`.
+    pub fn adapter_name(&self) -> Option<String> {
+        self.buffer.c_string(self.raw.all.adapter_name)
+    }
+
+    pub fn friendly_name(&self) -> Option<WideString> {
+        self.buffer.wide_string(self.raw.all.friendly_name)
+    }
+
+    pub fn description(&self) -> Option<WideString> {
+        self.buffer.wide_string(self.raw.all.description)
+    }
+
+    /// The unicast addresses, leaving out the ones that cannot be read.
+    pub fn unicast_addresses(&self) -> impl Iterator<Item = SocketAddr> + 'a {
+        let buffer = self.buffer;
+        buffer
+            .chain(self.raw.all.first_unicast_address, |entry| entry.next)
+            .map_while(|entry| entry)
+            .filter_map(move |entry| buffer.socket_address(&entry.address).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::mem;
+
+    /// A list of `count` adapters chained in order, indexed from 1.
+    fn fabricated(count: usize) -> AdapterList {
+        let size = mem::size_of::<IpAdapterAddresses>();
+        let mut list = AdapterList {
+            buffer: vec![0; (count * size).div_ceil(mem::size_of::<u64>())],
+        };
+        let base = list.buffer.as_mut_ptr() as *mut u8;
+        for i in 0..count {
+            let adapter = unsafe { &mut *(base.add(i * size) as *mut IpAdapterAddresses) };
+            adapter.head.length = size as u32;
+            adapter.head.if_index = i as u32 + 1;
+            if i + 1 < count {
+                adapter.all.next = unsafe { base.add((i + 1) * size) } as *const _;
+            }
+        }
+        list
+    }
+
+    fn indices(list: &AdapterList) -> Vec<u32> {
+        list.iter().map(|adapter| adapter.index()).collect()
+    }
+
+    #[test]
+    fn adapters_are_listed_in_order() {
+        let list = fabricated(3);
+        assert_eq!(indices(&list), [1, 2, 3]);
+        let again: Vec<u32> = (&list).into_iter().map(|adapter| adapter.index()).collect();
+        assert_eq!(again, [1, 2, 3]);
+        assert!(list
+            .iter()
+            .all(|adapter| adapter.unicast_addresses().next().is_none()));
+    }
+
+    #[test]
+    fn lists_end_where_their_buffer_does() {
+        let mut list = fabricated(2);
+        let size = mem::size_of::<IpAdapterAddresses>();
+        let base = list.buffer.as_mut_ptr() as *mut u8;
+        // The second adapter points past the end of the buffer.
+        let second = unsafe { &mut *(base.add(size) as *mut IpAdapterAddresses) };
+        second.all.next = unsafe { base.add(2 * size) } as *const _;
+        assert_eq!(indices(&list), [1, 2]);
+    }
+
+    #[test]
+    fn empty_lists_have_no_adapters() {
+        let list = AdapterList::empty();
+        assert!(list.bytes().is_empty());
+        assert_eq!(list.iter().count(), 0);
+    }
+
+    #[test]
+    fn the_live_list_is_aligned_and_refreshed_in_place() {
+        let mut list = AdapterList::new().unwrap();
+        let align = mem::align_of::<IpAdapterAddresses>();
+        assert_eq!(list.bytes().as_ptr() as usize % align, 0);
+        // IF_TYPE_SOFTWARE_LOOPBACK.
+        assert!(list.iter().any(|adapter| adapter.raw().all.if_type == 24));
+        let before = indices(&list);
+        list.refresh().unwrap();
+        assert_eq!(list.bytes().as_ptr() as usize % align, 0);
+        assert_eq!(indices(&list), before);
+    }
+}
@@ -0,0 +1,121 @@
+//! Change notifications from `NotifyAddrChange`.
+
+use std::os::windows::io::{AsRawHandle, RawHandle};
+use std::time::Duration;
+use std::{io, mem, ptr};
+
+use winapi::shared::minwindef::{DWORD, FALSE};
+use winapi::shared::winerror::{ERROR_IO_PENDING, WAIT_TIMEOUT};
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::minwinbase::OVERLAPPED;
+use winapi::um::synchapi::{CreateEventW, WaitForSingleObject};
+use winapi::um::winbase::{INFINITE, WAIT_FAILED, WAIT_OBJECT_0};
+use winapi::um::winnt::HANDLE;
+
+use super::iphlpapi::{self, CancelIPChangeNotify, NotifyAddrChange};
+use crate::ChangeHint;
+
+/// What `OVERLAPPED::Internal` holds while the request is outstanding.
+const STATUS_PENDING: usize = 0x103;
+
+pub struct Watcher {
+    event: HANDLE,
+    // Boxed, Windows writes to it until the request completes or is
+    // cancelled.
+    overlapped: Box<OVERLAPPED>,
+}
+
+unsafe impl Send for Watcher {}
+
+impl Watcher {
+    pub fn new() -> io::Result<Watcher> {
+        let event = unsafe { CreateEventW(ptr::null_mut(), FALSE, FALSE, ptr::null()) };
+        if event.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let mut watcher = Watcher {
+            event,
+            overlapped: Box::new(unsafe { mem::zeroed() }),
+        };
+        watcher.arm()?;
+        Ok(watcher)
+    }
+
+    fn arm(&mut self) -> io::Result<()> {
+        *self.overlapped = unsafe { mem::zeroed() };
+        self.overlapped.hEvent = self.event;
+        let mut handle: HANDLE = ptr::null_mut();
+        match unsafe { NotifyAddrChange(&mut handle, &mut *self.overlapped) } {
+            ERROR_IO_PENDING => Ok(()),
+            code => Err(iphlpapi::os_error(code)),
+        }
+    }
+
+    /// Block until an address changes or `timeout` passes, `None` waiting
+    /// forever. Returns what changed, if anything; `NotifyAddrChange` only
+    /// reports addresses.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<Option<ChangeHint>> {
+        let timeout = match timeout {
+            Some(timeout) => timeout.as_millis().min(INFINITE as u128 - 1) as DWORD,
+            None => INFINITE,
+        };
+        match unsafe { WaitForSingleObject(self.event, timeout) } {
+            WAIT_OBJECT_0 => {
+                self.arm()?;
+                Ok(Some(ChangeHint::Addresses))
+            }
+            WAIT_TIMEOUT => Ok(None),
+            WAIT_FAILED => Err(io::Error::last_os_error()),
+            _ => Ok(None),
+        }
+    }
+
+    /// Re-arm and report the change if the request completed, without
+    /// blocking.
+    ///
+    /// Completion is read off the `OVERLAPPED`, as `HasOverlappedIoCompleted`
+    /// does, rather than the event: a caller's wait on the auto-reset event
+    /// has already reset it.
+    pub fn drain_events(&mut self) -> io::Result<Vec<ChangeHint>> {
+        let internal = unsafe { ptr::read_volatile(&self.overlapped.Internal) };
+        if internal == STATUS_PENDING {
+            return Ok(Vec::new());
+        }
+        self.arm()?;
+        Ok(vec![ChangeHint::Addresses])
+    }
+}
+
+impl AsRawHandle for Watcher {
+    fn as_raw_handle(&self) -> RawHandle {
+        self.event as RawHandle
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        unsafe {
+            CancelIPChangeNotify(&mut *self.overlapped);
+            CloseHandle(self.event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Instant;
+
+    #[test]
+    fn waits_time_out_and_drains_do_not_block() {
+        let mut watcher = Watcher::new().unwrap();
+        let start = Instant::now();
+        let hint = watcher.wait(Some(Duration::from_millis(50))).unwrap();
+        // Something else on the machine may change meanwhile.
+        assert!(hint.is_none() || start.elapsed() < Duration::from_millis(50));
+        let start = Instant::now();
+        let _ = watcher.drain_events().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
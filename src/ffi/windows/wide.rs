@@ -0,0 +1,118 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::os::windows::ffi::OsStringExt;
+
+/// A UTF-16 string as Windows handed it to us.
+///
+/// Adapter strings are not guaranteed to be valid UTF-16, some drivers leave
+/// unpaired surrogates in them. [`as_str`](WideString::as_str) gives a lossy
+/// version for display, with U+FFFD in place of anything invalid, while the
+/// original code units stay available through
+/// [`as_wide`](WideString::as_wide) and
+/// [`to_os_string`](WideString::to_os_string).
+#[derive(PartialEq, Eq, Hash, Clone, Default)]
+pub struct WideString {
+    lossy: String,
+    wide: Vec<u16>,
+}
+
+impl WideString {
+    /// Build from UTF-16 code units, stopping at the first NUL if there is one.
+    pub fn from_wide(wide: &[u16]) -> WideString {
+        let wide = match wide.iter().position(|&c| c == 0) {
+            Some(end) => &wide[..end],
+            None => wide,
+        };
+        WideString {
+            lossy: String::from_utf16_lossy(wide),
+            wide: wide.to_vec(),
+        }
+    }
+
+    /// The string for display, with invalid code units replaced.
+    pub fn as_str(&self) -> &str {
+        &self.lossy
+    }
+
+    /// The original code units, without the terminating NUL.
+    pub fn as_wide(&self) -> &[u16] {
+        &self.wide
+    }
+
+    pub fn to_os_string(&self) -> OsString {
+        OsString::from_wide(&self.wide)
+    }
+
+    /// Whether [`as_str`](WideString::as_str) is an exact conversion.
+    pub fn is_lossless(&self) -> bool {
+        String::from_utf16(&self.wide).is_ok()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wide.is_empty()
+    }
+}
+
+impl fmt::Display for WideString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.lossy)
+    }
+}
+
+impl fmt::Debug for WideString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.lossy, f)
+    }
+}
+
+/// The strings Windows keeps per adapter, shared by all of its addresses.
+#[non_exhaustive]
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct AdapterStrings {
+    pub friendly_name: WideString,
+    pub description: WideString,
+    pub dns_suffix: WideString,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(s: &str) -> Vec<u16> {
+        s.encode_utf16().collect()
+    }
+
+    #[test]
+    fn valid_strings_convert_exactly() {
+        let wide = WideString::from_wide(&utf16("Ethernet 2 \u{1f310}"));
+        assert_eq!(wide.as_str(), "Ethernet 2 \u{1f310}");
+        assert!(wide.is_lossless());
+        assert_eq!(wide.as_wide(), &utf16("Ethernet 2 \u{1f310}")[..]);
+    }
+
+    #[test]
+    fn unpaired_surrogates_are_kept() {
+        let units = [0x0057, 0xd800, 0x0069, 0xdfff];
+        let wide = WideString::from_wide(&units);
+        assert_eq!(wide.as_str(), "W\u{fffd}i\u{fffd}");
+        assert!(!wide.is_lossless());
+        assert_eq!(wide.as_wide(), &units[..]);
+        assert_eq!(wide.to_os_string(), OsString::from_wide(&units));
+        assert_eq!(wide.to_string(), "W\u{fffd}i\u{fffd}");
+    }
+
+    #[test]
+    fn the_first_nul_ends_the_string() {
+        let mut units = utf16("Wi-Fi");
+        units.push(0);
+        units.extend(utf16("garbage"));
+        let wide = WideString::from_wide(&units);
+        assert_eq!(wide.as_str(), "Wi-Fi");
+        assert_eq!(wide.as_wide(), &utf16("Wi-Fi")[..]);
+        assert!(wide.is_lossless());
+
+        let wide = WideString::from_wide(&[0, 0x0041]);
+        assert!(wide.is_empty());
+        assert_eq!(wide, WideString::default());
+    }
+}
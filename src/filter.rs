@@ -0,0 +1,385 @@
+//! Picking interfaces by name pattern and link group.
+
+use crate::Interface;
+
+/// A set of name patterns an interface has to match.
+///
+/// An interface passes if no include pattern was given or one of them
+/// matches, and none of the exclude patterns does. On Linux every name of
+/// the interface is tried, its alternative names included.
+///
+/// Patterns are globs: `*` matches any run of characters, `?` any one
+/// character, `[abc]` and `[a-z]` one of a set and `[!abc]` one outside
+/// it. Anything else, an unclosed `[` included, matches itself. Names are
+/// compared case-sensitively, except on Windows, where adapter names are
+/// not case-sensitive.
+///
+/// Filters can also take link groups, see [`Interface::group`]. Once one
+/// is given, interfaces outside all the given groups do not pass; there
+/// are no groups on platforms other than Linux, so nothing passes there.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct InterfaceFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    groups: Vec<u32>,
+}
+
+impl InterfaceFilter {
+    /// A filter every interface passes.
+    pub fn new() -> InterfaceFilter {
+        InterfaceFilter::default()
+    }
+
+    /// Only interfaces named like `pattern`.
+    pub fn name_glob(pattern: &str) -> InterfaceFilter {
+        InterfaceFilter::new().include(pattern)
+    }
+
+    /// Every interface not named like `pattern`.
+    pub fn exclude_name_glob(pattern: &str) -> InterfaceFilter {
+        InterfaceFilter::new().exclude(pattern)
+    }
+
+    /// A filter from patterns as a user would write them, where a leading
+    /// `!` makes an exclusion, e.g. `["eth*", "!docker*"]`.
+    pub fn from_patterns<I, S>(patterns: I) -> InterfaceFilter
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        patterns
+            .into_iter()
+            .fold(InterfaceFilter::new(), |filter, pattern| {
+                match pattern.as_ref().strip_prefix('!') {
+                    Some(pattern) => filter.exclude(pattern),
+                    None => filter.include(pattern.as_ref()),
+                }
+            })
+    }
+
+    /// Also let interfaces named like `pattern` through.
+    pub fn include(mut self, pattern: &str) -> InterfaceFilter {
+        self.include.push(pattern.to_owned());
+        self
+    }
+
+    /// Also keep out interfaces named like `pattern`.
+    pub fn exclude(mut self, pattern: &str) -> InterfaceFilter {
+        self.exclude.push(pattern.to_owned());
+        self
+    }
+
+    /// Only interfaces in link group `group`, or in one of the groups given
+    /// before.
+    pub fn group(mut self, group: u32) -> InterfaceFilter {
+        self.groups.push(group);
+        self
+    }
+
+    pub fn matches(&self, iface: &Interface) -> bool {
+        let names = names_of(iface);
+        let any = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| names.iter().any(|name| glob_match(pattern, name)))
+        };
+        let in_group = || {
+            iface
+                .group()
+                .is_some_and(|group| self.groups.contains(&group))
+        };
+        (self.include.is_empty() || any(&self.include))
+            && !any(&self.exclude)
+            && (self.groups.is_empty() || in_group())
+    }
+}
+
+fn names_of(iface: &Interface) -> Vec<&str> {
+    #[allow(unused_mut)]
+    let mut names = vec![iface.name.as_str()];
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    names.extend(iface.os.alt_names.iter().map(String::as_str));
+    names
+}
+
+/// Whether `name` matches the glob `pattern`, see [`InterfaceFilter`].
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was, and how much of the name it has taken.
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+                continue;
+            }
+            Some(_) => {
+                if let Some((len, true)) = match_one(&pattern[p..], name[n]) {
+                    p += len;
+                    n += 1;
+                    continue;
+                }
+            }
+            None => {}
+        }
+        // Let the last `*` take one more character and retry after it.
+        match star {
+            Some((star_p, star_n)) => {
+                star = Some((star_p, star_n + 1));
+                p = star_p + 1;
+                n = star_n + 1;
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Match the element at the start of `pattern` against `c`, giving how
+/// many pattern characters it took.
+fn match_one(pattern: &[char], c: char) -> Option<(usize, bool)> {
+    match pattern.first()? {
+        '?' => Some((1, true)),
+        '[' => match class(pattern) {
+            Some((len, negated, set)) => Some((len, in_class(set, c) != negated)),
+            None => Some((1, same('[', c))),
+        },
+        &literal => Some((1, same(literal, c))),
+    }
+}
+
+/// Split a `[...]` class into its length, whether it is negated, and its
+/// members, or `None` if it is not closed.
+fn class(pattern: &[char]) -> Option<(usize, bool, &[char])> {
+    let negated = pattern.get(1) == Some(&'!');
+    let start = if negated { 2 } else { 1 };
+    // A `]` right at the start is a member, not the end.
+    let end = pattern
+        .iter()
+        .skip(start + 1)
+        .position(|&c| c == ']')
+        .map(|i| i + start + 1)?;
+    Some((end + 1, negated, &pattern[start..end]))
+}
+
+fn in_class(set: &[char], c: char) -> bool {
+    let mut i = 0;
+    while i < set.len() {
+        if i + 2 < set.len() && set[i + 1] == '-' {
+            let (low, high) = (set[i], set[i + 2]);
+            if fold(c).any(|c| low <= c && c <= high) {
+                return true;
+            }
+            i += 3;
+        } else {
+            if same(set[i], c) {
+                return true;
+            }
+            i += 1;
+        }
+    }
+    false
+}
+
+/// `c` in every case the platform treats as the same name.
+fn fold(c: char) -> impl Iterator<Item = char> {
+    #[cfg(target_family = "windows")]
+    let other = match c.is_lowercase() {
+        true => c.to_uppercase().next(),
+        false => c.to_lowercase().next(),
+    };
+    #[cfg(not(target_family = "windows"))]
+    let other = None;
+    std::iter::once(c).chain(other)
+}
+
+fn same(pattern: char, c: char) -> bool {
+    fold(c).any(|c| c == pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Kind;
+
+    #[test]
+    fn globs_match_like_the_shell() {
+        let cases = [
+            ("eth0", "eth0", true),
+            ("eth0", "eth1", false),
+            ("eth*", "eth0", true),
+            ("eth*", "eth", true),
+            ("eth*", "veth0", false),
+            ("*eth*", "veth0", true),
+            ("*", "", true),
+            ("", "", true),
+            ("", "eth0", false),
+            ("en?", "en0", true),
+            ("en?", "en", false),
+            ("en?", "en10", false),
+            ("en?s*", "enp0s31f6", false),
+            ("enp?s*", "enp0s31f6", true),
+            ("*.100", "eth0.100", true),
+            ("*a*b", "aaab", true),
+            ("*a*b", "aaba", false),
+            ("wl[a-z]*", "wlp2s0", true),
+            ("wl[a-z]*", "wl0", false),
+            ("eth[0-3]", "eth2", true),
+            ("eth[0-3]", "eth4", false),
+            ("eth[!0-3]", "eth4", true),
+            ("eth[!0-3]", "eth2", false),
+            ("tun[13]", "tun3", true),
+            ("tun[13]", "tun2", false),
+            // A `]` first in a class is a member, and `-` at the end.
+            ("x[]]", "x]", true),
+            ("x[a-]", "x-", true),
+            // An unclosed class matches itself.
+            ("br[0", "br[0", true),
+            ("br[0", "br0", false),
+            // Non-ASCII names take one `?` per character.
+            ("wi?i", "wiﬁi", true),
+            ("Ethernet ?", "Ethernet 2", true),
+        ];
+        for (pattern, name, matches) in cases {
+            assert_eq!(
+                glob_match(pattern, name),
+                matches,
+                "{:?} on {:?}",
+                pattern,
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn case_follows_the_platform() {
+        let insensitive = cfg!(target_family = "windows");
+        assert_eq!(glob_match("ethernet*", "Ethernet 2"), insensitive);
+        assert_eq!(glob_match("WI-FI", "Wi-Fi"), insensitive);
+        assert_eq!(glob_match("[a-z]*", "Ethernet"), insensitive);
+        assert_eq!(glob_match("[!e]*", "Ethernet"), !insensitive);
+        assert!(glob_match("Wi-Fi", "Wi-Fi"));
+    }
+
+    fn iface(name: &str) -> Interface {
+        Interface::new(name, Kind::Ipv4)
+    }
+
+    fn passing<'a>(filter: &InterfaceFilter, names: &[&'a str]) -> Vec<&'a str> {
+        names
+            .iter()
+            .copied()
+            .filter(|name| filter.matches(&iface(name)))
+            .collect()
+    }
+
+    const NAMES: [&str; 6] = ["lo", "eth0", "eth1", "docker0", "veth1a2b", "wlan0"];
+
+    #[test]
+    fn includes_and_excludes_combine() {
+        assert_eq!(passing(&InterfaceFilter::new(), &NAMES), NAMES);
+        assert_eq!(
+            passing(&InterfaceFilter::name_glob("eth*"), &NAMES),
+            ["eth0", "eth1"]
+        );
+        assert_eq!(
+            passing(&InterfaceFilter::exclude_name_glob("*eth*"), &NAMES),
+            ["lo", "docker0", "wlan0"]
+        );
+        let filter = InterfaceFilter::name_glob("eth*")
+            .include("wlan?")
+            .exclude("eth1");
+        assert_eq!(passing(&filter, &NAMES), ["eth0", "wlan0"]);
+    }
+
+    #[test]
+    fn patterns_read_like_a_config() {
+        let filter = InterfaceFilter::from_patterns(["*", "!docker*", "!veth*", "!lo"]);
+        assert_eq!(passing(&filter, &NAMES), ["eth0", "eth1", "wlan0"]);
+        assert_eq!(
+            filter,
+            InterfaceFilter::new()
+                .include("*")
+                .exclude("docker*")
+                .exclude("veth*")
+                .exclude("lo")
+        );
+        assert_eq!(
+            InterfaceFilter::from_patterns(Vec::<String>::new()),
+            InterfaceFilter::new()
+        );
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn alternative_names_match_too() {
+        let mut eth0 = iface("eth0");
+        eth0.os = std::sync::Arc::new(crate::OsSpecific {
+            alt_names: vec!["enp0s31f6".to_owned()],
+            ..Default::default()
+        });
+        assert!(InterfaceFilter::name_glob("enp*").matches(&eth0));
+        assert!(!InterfaceFilter::exclude_name_glob("enp*").matches(&eth0));
+        assert!(InterfaceFilter::name_glob("eth0").matches(&eth0));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn in_group(name: &str, group: Option<u32>) -> Interface {
+        iface(name).with_os_specific(crate::OsSpecific {
+            group,
+            ..Default::default()
+        })
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn groups_narrow_the_names() {
+        let ifaces = [
+            in_group("eth0", Some(0)),
+            in_group("eth1", Some(10)),
+            in_group("eth2", Some(20)),
+            in_group("wlan0", Some(10)),
+            // Not read, e.g. from a snapshot.
+            in_group("eth3", None),
+        ];
+        let passing = |filter: &InterfaceFilter| -> Vec<&str> {
+            ifaces
+                .iter()
+                .filter(|iface| filter.matches(iface))
+                .map(|iface| iface.name.as_str())
+                .collect()
+        };
+        assert_eq!(
+            passing(&InterfaceFilter::new()),
+            ["eth0", "eth1", "eth2", "wlan0", "eth3"]
+        );
+        assert_eq!(
+            passing(&InterfaceFilter::new().group(10)),
+            ["eth1", "wlan0"]
+        );
+        assert_eq!(
+            passing(&InterfaceFilter::new().group(10).group(0)),
+            ["eth0", "eth1", "wlan0"]
+        );
+        assert_eq!(
+            passing(&InterfaceFilter::name_glob("eth*").group(10)),
+            ["eth1"]
+        );
+        assert_eq!(
+            passing(&InterfaceFilter::new().group(10).exclude("wlan*")),
+            ["eth1"]
+        );
+        assert!(passing(&InterfaceFilter::new().group(30)).is_empty());
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    #[test]
+    fn groups_pass_nothing_without_link_groups() {
+        assert!(!InterfaceFilter::new().group(0).matches(&iface("en0")));
+        assert!(InterfaceFilter::new().matches(&iface("en0")));
+    }
+}
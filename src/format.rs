@@ -0,0 +1,922 @@
+//! Rendering interface lists as text for people to read, or as JSON in
+//! the schema of `ip -j addr`, and reading scoped addresses back.
+
+use std::fmt::Write;
+use std::io;
+use std::net::{IpAddr, Ipv6Addr, SocketAddrV6};
+
+use crate::{Interface, InterfaceFlags, Kind, NextHop};
+
+/// Flags in the order and with the names `ip addr` uses. `RUNNING` is what
+/// `ip` calls `LOWER_UP`.
+const FLAG_NAMES: &[(InterfaceFlags, &str)] = &[
+    (InterfaceFlags::LOOPBACK, "LOOPBACK"),
+    (InterfaceFlags::BROADCAST, "BROADCAST"),
+    (InterfaceFlags::POINT_TO_POINT, "POINTOPOINT"),
+    (InterfaceFlags::MULTICAST, "MULTICAST"),
+    (InterfaceFlags::PROMISC, "PROMISC"),
+    (InterfaceFlags::UP, "UP"),
+    (InterfaceFlags::RUNNING, "LOWER_UP"),
+];
+
+/// The interfaces in the style of `ip addr`.
+///
+/// Entries are grouped by name, in the order the names first appear. Each
+/// interface gets a line with its index, name, flags and MTU, then its MAC
+/// address and one indented line per address. Anything unknown is left
+/// out, or shown as `?` where the line would not read without it.
+pub fn render_text(ifaces: &[Interface]) -> String {
+    let mut out = String::new();
+    for group in groups(ifaces) {
+        let first = group[0];
+        let flags: Vec<&str> = FLAG_NAMES
+            .iter()
+            .filter(|(flag, _)| first.flags.contains(*flag))
+            .map(|(_, name)| *name)
+            .collect();
+        let _ = write!(
+            out,
+            "{}: {}: <{}>",
+            or_unknown(first.index),
+            name_of(first),
+            flags.join(",")
+        );
+        if let Some(mtu) = group.iter().find_map(|iface| iface.mtu) {
+            let _ = write!(out, " mtu {}", mtu);
+        }
+        out.push('\n');
+        if let Some(mac) = group.iter().find_map(|iface| iface.mac) {
+            let _ = writeln!(out, "    link/ether {}", mac);
+        }
+        for iface in group {
+            let addr = match iface.addr {
+                Some(addr) => addr,
+                None => continue,
+            };
+            let family = match addr.ip() {
+                IpAddr::V4(_) => "inet",
+                IpAddr::V6(_) => "inet6",
+            };
+            let _ = write!(out, "    {} {}", family, address_of(iface));
+            match &iface.hop {
+                Some(NextHop::Broadcast(brd)) => {
+                    let _ = write!(out, " brd {}", brd.ip());
+                }
+                Some(NextHop::Destination(peer)) if peer.ip() != addr.ip() => {
+                    let _ = write!(out, " peer {}", peer.ip());
+                }
+                _ => {}
+            }
+            let _ = writeln!(out, " scope {}", scope_of(&addr.ip()));
+        }
+    }
+    out
+}
+
+/// The interfaces as a table with one row per address, and one for each
+/// interface without any.
+pub fn render_table(ifaces: &[Interface]) -> String {
+    let mut rows = vec![[
+        "NAME".to_owned(),
+        "INDEX".to_owned(),
+        "FAMILY".to_owned(),
+        "ADDRESS".to_owned(),
+    ]];
+    for group in groups(ifaces) {
+        let addressed: Vec<&Interface> = group
+            .iter()
+            .copied()
+            .filter(|iface| iface.addr.is_some())
+            .collect();
+        let rows_of = match addressed.is_empty() {
+            true => vec![group[0]],
+            false => addressed,
+        };
+        for iface in rows_of {
+            let family = match (&iface.kind, iface.addr.map(|addr| addr.ip())) {
+                (_, Some(IpAddr::V4(_))) => "inet",
+                (_, Some(IpAddr::V6(_))) => "inet6",
+                (Kind::Packet, None) | (Kind::Link, None) => "link",
+                _ => "-",
+            };
+            rows.push([
+                name_of(iface),
+                or_unknown(iface.index),
+                family.to_owned(),
+                match iface.addr {
+                    Some(_) => address_of(iface),
+                    None => iface
+                        .mac
+                        .map(|mac| mac.to_string())
+                        .unwrap_or_else(|| "-".to_owned()),
+                },
+            ]);
+        }
+    }
+
+    let mut widths = [0; 4];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut out = String::new();
+    for row in &rows {
+        let mut line = String::new();
+        for (i, cell) in row.iter().enumerate() {
+            let _ = write!(line, "{:width$}  ", cell, width = widths[i]);
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// The interfaces as JSON in the schema of `ip -j addr`, for tools that
+/// parse that command's output.
+///
+/// Each interface is an object with `ifindex`, `ifname`, `flags`, `mtu`
+/// and its MAC address as `address`, plus `group`, `txqlen` and
+/// `link_type` on Linux, and an `addr_info` array with the `family`,
+/// `local`, `prefixlen`, `broadcast` or peer `address`, `scope` and, for
+/// IPv4, `label` of each address. Whatever is not known is left out
+/// rather than given as `null`, and so is what the crate does not read,
+/// such as `operstate`, `qdisc` and address lifetimes. `NO-CARRIER` leads
+/// the flags of an interface that is up without carrier, and addresses
+/// listed under a label are given under their interface, as with `ip`.
+pub fn to_iproute2_json(ifaces: &[Interface]) -> String {
+    let mut out = String::from("[");
+    for (i, group) in links(ifaces).iter().enumerate() {
+        let first = group[0];
+        if i > 0 {
+            out.push(',');
+        }
+        out.push('{');
+        let mut fields = Vec::new();
+        if let Some(index) = first.index {
+            fields.push(format!("\"ifindex\":{}", index));
+        }
+        fields.push(format!("\"ifname\":{}", json_string(&first.name)));
+        let mut flags: Vec<&str> = FLAG_NAMES
+            .iter()
+            .filter(|(flag, _)| first.flags.contains(*flag))
+            .map(|(_, name)| *name)
+            .collect();
+        if first.flags.contains(InterfaceFlags::UP)
+            && !first.flags.contains(InterfaceFlags::RUNNING)
+        {
+            flags.insert(0, "NO-CARRIER");
+        }
+        let flags: Vec<String> = flags.iter().map(|flag| json_string(flag)).collect();
+        fields.push(format!("\"flags\":[{}]", flags.join(",")));
+        if let Some(mtu) = group.iter().find_map(|iface| iface.mtu) {
+            fields.push(format!("\"mtu\":{}", mtu));
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            let os = first.os_specific();
+            if let Some(group) = os.group {
+                fields.push(match group {
+                    0 => "\"group\":\"default\"".to_owned(),
+                    group => format!("\"group\":\"{}\"", group),
+                });
+            }
+            if let Some(len) = os.tx_queue_len {
+                fields.push(format!("\"txqlen\":{}", len));
+            }
+            let link_type = match os.hardware_type {
+                Some(1) => Some("ether"),
+                Some(772) => Some("loopback"),
+                Some(65534) => Some("none"),
+                _ => None,
+            };
+            if let Some(link_type) = link_type {
+                fields.push(format!("\"link_type\":\"{}\"", link_type));
+            }
+        }
+        if let Some(mac) = group.iter().find_map(|iface| iface.mac) {
+            fields.push(format!("\"address\":\"{}\"", mac));
+        }
+        let addrs: Vec<String> = group.iter().filter_map(|iface| addr_info(iface)).collect();
+        fields.push(format!("\"addr_info\":[{}]", addrs.join(",")));
+        out.push_str(&fields.join(","));
+        out.push('}');
+    }
+    out.push(']');
+    out
+}
+
+/// One `addr_info` object, `None` for entries without an address.
+fn addr_info(iface: &Interface) -> Option<String> {
+    let addr = iface.addr?.ip();
+    let mut fields = vec![
+        match addr {
+            IpAddr::V4(_) => "\"family\":\"inet\"".to_owned(),
+            IpAddr::V6(_) => "\"family\":\"inet6\"".to_owned(),
+        },
+        format!("\"local\":\"{}\"", addr),
+    ];
+    if let Some(NextHop::Destination(peer)) = &iface.hop {
+        if peer.ip() != addr {
+            fields.push(format!("\"address\":\"{}\"", peer.ip()));
+        }
+    }
+    if let Some(len) = iface.prefix_len() {
+        fields.push(format!("\"prefixlen\":{}", len));
+    }
+    if let Some(NextHop::Broadcast(brd)) = &iface.hop {
+        fields.push(format!("\"broadcast\":\"{}\"", brd.ip()));
+    }
+    fields.push(format!("\"scope\":\"{}\"", scope_of(&addr)));
+    if addr.is_ipv4() {
+        fields.push(format!("\"label\":{}", json_string(&iface.name)));
+    }
+    Some(format!("{{{}}}", fields.join(",")))
+}
+
+/// `s` as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `addr` with its scope as an interface name, e.g. `fe80::1%eth0`, or
+/// `[fe80::1%eth0]:80` if it has a port.
+///
+/// Only link-local addresses and interface- or link-local multicast get a
+/// scope, since the scope id means nothing for the others. The scope stays
+/// a number if no interface has it as its index, and is left out when it
+/// is 0.
+pub fn format_scoped(addr: &SocketAddrV6) -> String {
+    format_scoped_with(addr, crate::ffi::index_name)
+}
+
+/// [`format_scoped`], with `name_of` giving the name of an interface index
+/// instead of the OS, e.g. from an interface list the caller already has.
+pub fn format_scoped_with<F>(addr: &SocketAddrV6, name_of: F) -> String
+where
+    F: FnOnce(u32) -> Option<String>,
+{
+    let ip = addr.ip();
+    let scoped = match (addr.scope_id(), is_scoped(ip)) {
+        (0, _) | (_, false) => ip.to_string(),
+        (scope_id, true) => match name_of(scope_id) {
+            Some(name) => format!("{}%{}", ip, name),
+            None => format!("{}%{}", ip, scope_id),
+        },
+    };
+    match addr.port() {
+        0 => scoped,
+        port => format!("[{}]:{}", scoped, port),
+    }
+}
+
+/// Parse an IPv6 address with an optional zone, e.g. `fe80::1%eth0` or
+/// `fe80::1%3`, into the address and its scope id.
+///
+/// Numeric zones are taken as they are, anything else is looked up as an
+/// interface name and fails with `ErrorKind::NotFound` if there is none.
+/// Without a zone the scope id is 0. A zone on an address that has no use
+/// for one, see [`format_scoped`], is `ErrorKind::InvalidInput`, as are a
+/// malformed address and an empty zone; the message says which part was
+/// wrong.
+pub fn parse_scoped(s: &str) -> io::Result<(Ipv6Addr, u32)> {
+    parse_scoped_with(s, false, |zone| {
+        crate::ffi::if_index(&crate::validate_name(zone)?)
+    })
+}
+
+/// [`parse_scoped`] for a socket address with a port, e.g.
+/// `[fe80::1%eth0]:80`.
+pub fn parse_scoped_socket(s: &str) -> io::Result<SocketAddrV6> {
+    let invalid = || invalid_input(format!("`{}` is not a bracketed address and port", s));
+    let rest = s.strip_prefix('[').ok_or_else(invalid)?;
+    let (addr, port) = rest.rsplit_once("]:").ok_or_else(invalid)?;
+    let port = port
+        .parse()
+        .map_err(|_| invalid_input(format!("invalid port `{}`", port)))?;
+    let (ip, scope_id) = parse_scoped(addr)?;
+    Ok(SocketAddrV6::new(ip, port, 0, scope_id))
+}
+
+/// [`parse_scoped`], with `index_of` giving the index of an interface name
+/// instead of the OS. With `any_scope` zones are allowed on every address.
+pub fn parse_scoped_with<F>(s: &str, any_scope: bool, index_of: F) -> io::Result<(Ipv6Addr, u32)>
+where
+    F: FnOnce(&str) -> io::Result<u32>,
+{
+    let (addr, zone) = match s.split_once('%') {
+        Some((addr, zone)) => (addr, Some(zone)),
+        None => (s, None),
+    };
+    let ip: Ipv6Addr = addr
+        .parse()
+        .map_err(|_| invalid_input(format!("invalid IPv6 address `{}`", addr)))?;
+    let zone = match zone {
+        Some(zone) => zone,
+        None => return Ok((ip, 0)),
+    };
+    if zone.is_empty() {
+        return Err(invalid_input(format!("empty zone after `{}`", addr)));
+    }
+    if !any_scope && !is_scoped(&ip) {
+        return Err(invalid_input(format!(
+            "zone `{}` on {}, which is not link-local",
+            zone, ip
+        )));
+    }
+    if zone.bytes().all(|b| b.is_ascii_digit()) {
+        let scope_id = zone
+            .parse()
+            .map_err(|_| invalid_input(format!("zone `{}` is out of range", zone)))?;
+        return Ok((ip, scope_id));
+    }
+    match index_of(zone) {
+        Ok(index) => Ok((ip, index)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("zone `{}` is not an interface", zone),
+        )),
+        Err(err) => Err(err),
+    }
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// Whether scope ids of `ip` name an interface: link-local unicast, and
+/// multicast of interface-local or link-local scope.
+fn is_scoped(ip: &Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    first & 0xffc0 == 0xfe80 || first & 0xff0f == 0xff01 || first & 0xff0f == 0xff02
+}
+
+/// The entries of `ifaces`, grouped by link in order of first appearance:
+/// by index where it is known, so that IPv4 addresses listed under a label
+/// such as `eth0:1` join their interface, and by name otherwise.
+fn links(ifaces: &[Interface]) -> Vec<Vec<&Interface>> {
+    let mut links: Vec<Vec<&Interface>> = Vec::new();
+    for iface in ifaces {
+        let same = |link: &&mut Vec<&Interface>| match (link[0].index, iface.index) {
+            (Some(a), Some(b)) => a == b,
+            _ => link[0].name == iface.name,
+        };
+        match links.iter_mut().find(same) {
+            Some(link) => link.push(iface),
+            None => links.push(vec![iface]),
+        }
+    }
+    links
+}
+
+/// The entries of `ifaces`, grouped by name in order of first appearance.
+fn groups(ifaces: &[Interface]) -> Vec<Vec<&Interface>> {
+    let mut groups: Vec<Vec<&Interface>> = Vec::new();
+    for iface in ifaces {
+        match groups.iter_mut().find(|group| group[0].name == iface.name) {
+            Some(group) => group.push(iface),
+            None => groups.push(vec![iface]),
+        }
+    }
+    groups
+}
+
+fn name_of(iface: &Interface) -> String {
+    match iface.name.is_empty() {
+        true => "?".to_owned(),
+        false => iface.name.clone(),
+    }
+}
+
+fn or_unknown(index: Option<u32>) -> String {
+    index
+        .map(|index| index.to_string())
+        .unwrap_or_else(|| "?".to_owned())
+}
+
+/// The address with its prefix length, or with its mask if that is not
+/// contiguous.
+fn address_of(iface: &Interface) -> String {
+    let addr = match iface.addr {
+        Some(addr) => addr.ip(),
+        None => return "-".to_owned(),
+    };
+    match (iface.prefix_len(), iface.mask) {
+        (Some(len), _) => format!("{}/{}", addr, len),
+        (None, Some(mask)) => format!("{} mask {}", addr, mask.ip()),
+        (None, None) => addr.to_string(),
+    }
+}
+
+fn scope_of(addr: &IpAddr) -> &'static str {
+    match addr {
+        IpAddr::V4(v4) if v4.is_loopback() => "host",
+        IpAddr::V4(v4) if v4.is_link_local() => "link",
+        IpAddr::V6(v6) if v6.is_loopback() => "host",
+        IpAddr::V6(v6) if v6.segments()[0] & 0xffc0 == 0xfe80 => "link",
+        _ => "global",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::SocketAddr;
+    use std::path::Path;
+
+    use crate::MacAddr;
+
+    fn entry(name: &str, index: Option<u32>, kind: Kind, flags: InterfaceFlags) -> Interface {
+        let mut iface = Interface::new(name, kind);
+        iface.index = index;
+        iface.flags = flags;
+        iface
+    }
+
+    fn addressed(
+        mut iface: Interface,
+        addr: &str,
+        mask: Option<&str>,
+        hop: Option<NextHop>,
+    ) -> Interface {
+        iface.kind = match addr.parse::<SocketAddr>().unwrap() {
+            SocketAddr::V4(_) => Kind::Ipv4,
+            SocketAddr::V6(_) => Kind::Ipv6,
+        };
+        iface.addr = Some(addr.parse().unwrap());
+        iface.mask = mask.map(|mask| SocketAddr::new(mask.parse().unwrap(), 0));
+        iface.hop = hop;
+        iface
+    }
+
+    /// A machine with loopback, an Ethernet NIC, a WireGuard tunnel and
+    /// some entries missing the usual fields.
+    fn fixture() -> Vec<Interface> {
+        let up = InterfaceFlags::UP | InterfaceFlags::RUNNING;
+        let mut lo = entry("lo", Some(1), Kind::Packet, up | InterfaceFlags::LOOPBACK);
+        lo.mtu = Some(65536);
+        let mut eth0 = entry(
+            "eth0",
+            Some(2),
+            Kind::Packet,
+            up | InterfaceFlags::BROADCAST | InterfaceFlags::MULTICAST,
+        );
+        eth0.mtu = Some(1500);
+        eth0.mac = Some(MacAddr::new([0x02, 0x00, 0x00, 0x00, 0x00, 0x01]));
+        let wg0 = entry(
+            "wg0",
+            Some(3),
+            Kind::Ipv4,
+            up | InterfaceFlags::POINT_TO_POINT,
+        );
+        let odd = entry("odd0", None, Kind::Ipv4, InterfaceFlags::empty());
+        let brd = |addr: &str| {
+            Some(NextHop::Broadcast(SocketAddr::new(
+                addr.parse().unwrap(),
+                0,
+            )))
+        };
+        let peer = |addr: &str| {
+            Some(NextHop::Destination(SocketAddr::new(
+                addr.parse().unwrap(),
+                0,
+            )))
+        };
+        vec![
+            lo.clone(),
+            addressed(lo.clone(), "127.0.0.1:0", Some("255.0.0.0"), None),
+            addressed(
+                lo,
+                "[::1]:0",
+                Some("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+                None,
+            ),
+            eth0.clone(),
+            addressed(
+                eth0.clone(),
+                "192.0.2.10:0",
+                Some("255.255.255.0"),
+                brd("192.0.2.255"),
+            ),
+            addressed(eth0.clone(), "169.254.7.1:0", Some("255.255.0.0"), None),
+            addressed(eth0, "[fe80::1%2]:0", Some("ffff:ffff:ffff:ffff::"), None),
+            addressed(wg0.clone(), "10.0.0.1:0", None, peer("10.0.0.2")),
+            addressed(
+                wg0,
+                "[2001:db8::1]:0",
+                Some("ffff:ffff:ffff:ffff::"),
+                peer("2001:db8::1"),
+            ),
+            // A mask that is not a prefix, and an entry without anything.
+            addressed(odd.clone(), "198.51.100.7:0", Some("255.0.255.0"), None),
+            entry("", None, Kind::Unknown, InterfaceFlags::empty()),
+        ]
+    }
+
+    /// Compare `actual` with the snapshot in `tests/fixtures/format`, or
+    /// write it there with `IFACES_BLESS=1`.
+    fn snapshot(name: &str, actual: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/format")
+            .join(name);
+        if std::env::var_os("IFACES_BLESS").is_some() {
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            actual == expected,
+            "{} changed, rerun with IFACES_BLESS=1 if that is intended:\n{}",
+            name,
+            actual
+        );
+    }
+
+    #[test]
+    fn text_matches_its_snapshot() {
+        snapshot("ip-addr.txt", &render_text(&fixture()));
+    }
+
+    #[test]
+    fn table_matches_its_snapshot() {
+        snapshot("table.txt", &render_table(&fixture()));
+    }
+
+    #[test]
+    fn nothing_renders_as_nothing() {
+        assert_eq!(render_text(&[]), "");
+        assert_eq!(render_table(&[]), "NAME  INDEX  FAMILY  ADDRESS\n");
+    }
+
+    fn v6(s: &str) -> SocketAddrV6 {
+        s.parse().unwrap()
+    }
+
+    /// Names as a machine with `eth0` at index 4 has them.
+    fn eth0(index: u32) -> Option<String> {
+        (index == 4).then(|| "eth0".to_owned())
+    }
+
+    #[test]
+    fn link_local_scopes_are_named() {
+        assert_eq!(
+            format_scoped_with(&v6("[fe80::1%4]:0"), eth0),
+            "fe80::1%eth0"
+        );
+        assert_eq!(
+            format_scoped_with(&v6("[ff02::1%4]:0"), eth0),
+            "ff02::1%eth0"
+        );
+        assert_eq!(
+            format_scoped_with(&v6("[ff01::1%4]:0"), eth0),
+            "ff01::1%eth0"
+        );
+        assert_eq!(
+            format_scoped_with(&v6("[fe80::1%4]:443"), eth0),
+            "[fe80::1%eth0]:443"
+        );
+    }
+
+    #[test]
+    fn unknown_scopes_stay_numbers() {
+        assert_eq!(format_scoped_with(&v6("[fe80::1%9]:0"), eth0), "fe80::1%9");
+        assert_eq!(
+            format_scoped_with(&v6("[fe80::1%9]:53"), eth0),
+            "[fe80::1%9]:53"
+        );
+    }
+
+    #[test]
+    fn unscoped_addresses_have_no_zone() {
+        let never = |_| -> Option<String> { panic!("looked up") };
+        assert_eq!(format_scoped_with(&v6("[fe80::1]:0"), never), "fe80::1");
+        // Scope ids on global, site and organisation scopes mean nothing.
+        assert_eq!(
+            format_scoped_with(&v6("[2001:db8::1%4]:0"), never),
+            "2001:db8::1"
+        );
+        assert_eq!(format_scoped_with(&v6("[ff05::1%4]:0"), never), "ff05::1");
+        assert_eq!(
+            format_scoped_with(&v6("[ff0e::1%4]:80"), never),
+            "[ff0e::1]:80"
+        );
+        assert_eq!(format_scoped_with(&v6("[::1%4]:0"), never), "::1");
+    }
+
+    #[test]
+    fn the_os_names_the_loopback_scope() {
+        let lo = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(InterfaceFlags::LOOPBACK))
+            .unwrap();
+        let addr = SocketAddrV6::new("fe80::1".parse().unwrap(), 0, 0, lo.index.unwrap());
+        assert_eq!(format_scoped(&addr), format!("fe80::1%{}", lo.name));
+    }
+
+    fn index_of(zone: &str) -> io::Result<u32> {
+        match zone {
+            "eth0" => Ok(4),
+            _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+        }
+    }
+
+    /// The outcome of parsing `s`, the error as its kind and message.
+    fn parsed(s: &str, any_scope: bool) -> Result<(String, u32), (io::ErrorKind, String)> {
+        parse_scoped_with(s, any_scope, index_of)
+            .map(|(ip, scope_id)| (ip.to_string(), scope_id))
+            .map_err(|err| (err.kind(), err.to_string()))
+    }
+
+    fn ok(ip: &str, scope_id: u32) -> Result<(String, u32), (io::ErrorKind, String)> {
+        Ok((ip.to_owned(), scope_id))
+    }
+
+    fn invalid(msg: &str) -> Result<(String, u32), (io::ErrorKind, String)> {
+        Err((io::ErrorKind::InvalidInput, msg.to_owned()))
+    }
+
+    #[test]
+    fn zones_are_names_or_numbers() {
+        assert_eq!(parsed("fe80::1%eth0", false), ok("fe80::1", 4));
+        assert_eq!(parsed("fe80::1%3", false), ok("fe80::1", 3));
+        assert_eq!(parsed("ff02::1%eth0", false), ok("ff02::1", 4));
+        assert_eq!(parsed("fe80::1", false), ok("fe80::1", 0));
+        // Zones are not needed on the others.
+        assert_eq!(parsed("2001:db8::1", false), ok("2001:db8::1", 0));
+        // A name that starts with a digit is still a name.
+        assert_eq!(
+            parsed("fe80::1%4eth", false).unwrap_err().0,
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn the_message_says_which_part_is_wrong() {
+        assert_eq!(
+            parsed("fe80::g%eth0", false),
+            invalid("invalid IPv6 address `fe80::g`")
+        );
+        assert_eq!(
+            parsed("192.0.2.1%eth0", false),
+            invalid("invalid IPv6 address `192.0.2.1`")
+        );
+        assert_eq!(
+            parsed("fe80::1%", false),
+            invalid("empty zone after `fe80::1`")
+        );
+        assert_eq!(
+            parsed("fe80::1%99999999999", false),
+            invalid("zone `99999999999` is out of range")
+        );
+        assert_eq!(
+            parsed("fe80::1%eth9", false),
+            Err((
+                io::ErrorKind::NotFound,
+                "zone `eth9` is not an interface".to_owned()
+            ))
+        );
+        assert_eq!(parsed("", false), invalid("invalid IPv6 address ``"));
+    }
+
+    #[test]
+    fn zones_on_global_addresses_need_opting_in() {
+        assert_eq!(
+            parsed("2001:db8::1%eth0", false),
+            invalid("zone `eth0` on 2001:db8::1, which is not link-local")
+        );
+        assert_eq!(
+            parsed("ff05::1%3", false),
+            invalid("zone `3` on ff05::1, which is not link-local")
+        );
+        assert_eq!(parsed("2001:db8::1%eth0", true), ok("2001:db8::1", 4));
+        assert_eq!(parsed("ff05::1%3", true), ok("ff05::1", 3));
+        // An empty zone is wrong either way.
+        assert_eq!(
+            parsed("2001:db8::1%", true),
+            invalid("empty zone after `2001:db8::1`")
+        );
+    }
+
+    #[test]
+    fn lookup_errors_other_than_not_found_pass_through() {
+        let err = parse_scoped_with("fe80::1%eth0", false, |_| {
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn scoped_addresses_round_trip() {
+        for s in ["fe80::1%eth0", "ff02::fb%eth0", "fe80::1%9"] {
+            let (ip, scope_id) = parse_scoped_with(s, false, index_of).unwrap();
+            let addr = SocketAddrV6::new(ip, 0, 0, scope_id);
+            assert_eq!(format_scoped_with(&addr, eth0), s);
+        }
+    }
+
+    #[test]
+    fn the_os_resolves_zones() {
+        let lo = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(InterfaceFlags::LOOPBACK))
+            .unwrap();
+        let (ip, scope_id) = parse_scoped(&format!("fe80::1%{}", lo.name)).unwrap();
+        assert_eq!(
+            (ip, scope_id),
+            ("fe80::1".parse().unwrap(), lo.index.unwrap())
+        );
+        let err = parse_scoped("fe80::1%nosuchif0").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        // Names the platform would never give an interface.
+        let err = parse_scoped("fe80::1%a\0b").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn socket_addresses_take_a_port() {
+        let lo = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(InterfaceFlags::LOOPBACK))
+            .unwrap();
+        let addr = parse_scoped_socket(&format!("[fe80::1%{}]:443", lo.name)).unwrap();
+        assert_eq!(addr.port(), 443);
+        assert_eq!(addr.scope_id(), lo.index.unwrap());
+        assert_eq!(parse_scoped_socket("[fe80::1%7]:53").unwrap().scope_id(), 7);
+        for s in [
+            "fe80::1%7",
+            "[fe80::1%7]",
+            "[fe80::1%7]:",
+            "[fe80::1%7]:99999",
+        ] {
+            let err = parse_scoped_socket(s).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{}", s);
+        }
+    }
+
+    fn captured(name: &str) -> serde_json::Value {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/ip-addr")
+            .join(name);
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    /// The entries the crate reports on the machine `links` was captured
+    /// on: one per link, then one per address.
+    fn ifaces_of(links: &serde_json::Value) -> Vec<Interface> {
+        let mut ifaces = Vec::new();
+        for link in links.as_array().unwrap() {
+            let name = link["ifname"].as_str().unwrap();
+            let index = link["ifindex"].as_u64().map(|index| index as u32);
+            let mut flags = InterfaceFlags::empty();
+            for flag in link["flags"].as_array().unwrap() {
+                if let Some((bit, _)) = FLAG_NAMES.iter().find(|(_, name)| flag == *name) {
+                    flags |= *bit;
+                }
+            }
+            let mut iface = entry(name, index, Kind::Packet, flags);
+            iface.mtu = link["mtu"].as_u64().map(|mtu| mtu as u32);
+            iface.mac = link["address"].as_str().map(|mac| mac.parse().unwrap());
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            {
+                iface = iface.with_os_specific(crate::OsSpecific {
+                    group: match link["group"].as_str() {
+                        Some("default") => Some(0),
+                        group => group.map(|group| group.parse().unwrap()),
+                    },
+                    tx_queue_len: link["txqlen"].as_u64().map(|len| len as u32),
+                    hardware_type: match link["link_type"].as_str() {
+                        Some("ether") => Some(1),
+                        Some("loopback") => Some(772),
+                        Some("none") => Some(65534),
+                        _ => None,
+                    },
+                    ..crate::OsSpecific::default()
+                });
+            }
+            let link_entry = iface.clone();
+            ifaces.push(iface);
+            for info in link["addr_info"].as_array().unwrap() {
+                let ip: IpAddr = info["local"].as_str().unwrap().parse().unwrap();
+                let len = info["prefixlen"].as_u64().unwrap() as u32;
+                let (addr, mask) = match ip {
+                    IpAddr::V4(v4) => (
+                        SocketAddr::new(v4.into(), 0),
+                        std::net::Ipv4Addr::from(u32::MAX.checked_shl(32 - len).unwrap_or(0))
+                            .to_string(),
+                    ),
+                    IpAddr::V6(v6) => {
+                        let scope_id = match info["scope"].as_str() {
+                            Some("link") => index.unwrap(),
+                            _ => 0,
+                        };
+                        (
+                            SocketAddr::V6(SocketAddrV6::new(v6, 0, 0, scope_id)),
+                            Ipv6Addr::from(u128::MAX.checked_shl(128 - len).unwrap_or(0))
+                                .to_string(),
+                        )
+                    }
+                };
+                let hop = match (info["broadcast"].as_str(), info["address"].as_str()) {
+                    (Some(brd), _) => {
+                        Some(NextHop::Broadcast(SocketAddr::new(brd.parse().unwrap(), 0)))
+                    }
+                    (None, Some(peer)) => Some(NextHop::Destination(SocketAddr::new(
+                        peer.parse().unwrap(),
+                        0,
+                    ))),
+                    (None, None) => None,
+                };
+                let mut iface = link_entry.clone();
+                // IPv4 addresses are listed under their label.
+                if let Some(label) = info["label"].as_str() {
+                    iface.name = label.to_owned();
+                }
+                ifaces.push(addressed(iface, &addr.to_string(), Some(&mask), hop));
+            }
+        }
+        ifaces
+    }
+
+    /// `links` without what the crate does not read.
+    fn known_fields(mut links: serde_json::Value) -> serde_json::Value {
+        let mut known = vec!["ifindex", "ifname", "flags", "mtu", "address", "addr_info"];
+        if cfg!(any(target_os = "linux", target_os = "android")) {
+            known.extend(["group", "txqlen", "link_type"]);
+        }
+        let info_known = [
+            "family",
+            "local",
+            "address",
+            "prefixlen",
+            "broadcast",
+            "scope",
+            "label",
+        ];
+        for link in links.as_array_mut().unwrap() {
+            let link = link.as_object_mut().unwrap();
+            link.retain(|key, _| known.contains(&key.as_str()));
+            link["flags"].as_array_mut().unwrap().retain(|flag| {
+                flag == "NO-CARRIER" || FLAG_NAMES.iter().any(|(_, name)| flag == *name)
+            });
+            for info in link["addr_info"].as_array_mut().unwrap() {
+                info.as_object_mut()
+                    .unwrap()
+                    .retain(|key, _| info_known.contains(&key.as_str()));
+            }
+        }
+        links
+    }
+
+    #[test]
+    fn iproute2_json_matches_captured_output() {
+        for name in [
+            "debian-12-container.json",
+            "laptop-wifi-docker.json",
+            "server-wireguard.json",
+        ] {
+            let links = captured(name);
+            let json = to_iproute2_json(&ifaces_of(&links));
+            let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(json, known_fields(links), "{}", name);
+        }
+    }
+
+    #[test]
+    fn unknown_iproute2_fields_are_left_out() {
+        let odd = entry("odd0", None, Kind::Ipv4, InterfaceFlags::UP);
+        let ifaces = [odd.clone(), addressed(odd, "192.0.2.7:0", None, None)];
+        assert_eq!(
+            to_iproute2_json(&ifaces),
+            "[{\"ifname\":\"odd0\",\"flags\":[\"NO-CARRIER\",\"UP\"],\"addr_info\":[\
+             {\"family\":\"inet\",\"local\":\"192.0.2.7\",\"scope\":\"global\",\"label\":\"odd0\"}]}]"
+        );
+        assert!(!to_iproute2_json(&fixture()).contains("null"));
+        assert_eq!(to_iproute2_json(&[]), "[]");
+    }
+
+    #[test]
+    fn iproute2_names_are_escaped() {
+        let iface = entry("a\"b\\c\n", Some(9), Kind::Packet, InterfaceFlags::empty());
+        let json: serde_json::Value = serde_json::from_str(&to_iproute2_json(&[iface])).unwrap();
+        assert_eq!(json[0]["ifname"], "a\"b\\c\n");
+    }
+}
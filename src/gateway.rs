@@ -0,0 +1,296 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr, SocketAddrV6, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+use crate::{Interface, Kind, MacAddr};
+
+/// How often the neighbor table is read again after a probe, and how long
+/// apart. ARP and neighbor discovery answer well within the second.
+const PROBE_POLLS: u32 = 10;
+const PROBE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The MAC address of the default gateway of `family`, `Ipv4` or `Ipv6`,
+/// e.g. to tell which network the machine is on.
+///
+/// The gateway comes from the default route with the lowest metric, and
+/// its MAC address from the neighbor table. If the table has no entry yet
+/// and `probe` is set, an empty UDP datagram to the discard port makes the
+/// OS resolve the gateway, and the table is read again for a second at
+/// most; without `probe` nothing is sent. `None` without a default route,
+/// if the gateway is not on the link of its interface, or if it cannot be
+/// resolved.
+///
+/// Linux reads routes and neighbors over netlink and Windows from the IP
+/// Helper tables; other platforms fail with `ErrorKind::Unsupported`.
+pub fn gateway_mac(family: Kind, probe: bool) -> io::Result<Option<MacAddr>> {
+    match family {
+        Kind::Ipv4 | Kind::Ipv6 => {}
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the family must be Ipv4 or Ipv6",
+            ))
+        }
+    }
+    let (gateway, index) = match crate::ffi::config::default_gateway(family)? {
+        Some(route) => route,
+        None => return Ok(None),
+    };
+    if !on_link(gateway, index, &crate::ifaces()?) {
+        return Ok(None);
+    }
+    resolve(
+        probe,
+        || crate::ffi::config::neighbor_mac(index, gateway),
+        || send_probe(gateway, index),
+    )
+}
+
+/// The interface of the default route, e.g. to show which one the machine
+/// goes online over: the interface of the IPv4 default route, or of the
+/// IPv6 one when there is no IPv4 default route. `None` without either.
+///
+/// Of the entries of the interface, the first of the route's family is
+/// returned, or its first entry when it has no address of that family.
+///
+/// The routes are read as for [`gateway_mac`], so other platforms fail
+/// with `ErrorKind::Unsupported`.
+pub fn default_interface() -> io::Result<Option<Interface>> {
+    default_interface_with(crate::ffi::config::default_gateway, crate::ifaces)
+}
+
+fn default_interface_with(
+    mut default_gateway: impl FnMut(Kind) -> io::Result<Option<(IpAddr, u32)>>,
+    enumerate: impl FnOnce() -> io::Result<Vec<Interface>>,
+) -> io::Result<Option<Interface>> {
+    for family in [Kind::Ipv4, Kind::Ipv6] {
+        if let Some((_, index)) = default_gateway(family.clone())? {
+            return Ok(enumerate()?
+                .into_iter()
+                .filter(|iface| iface.index == Some(index))
+                .min_by_key(|iface| iface.kind != family));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether `gateway` can be reached directly over interface `index`:
+/// IPv6 link-local, or on the network of one of its addresses.
+fn on_link(gateway: IpAddr, index: u32, ifaces: &[Interface]) -> bool {
+    if let IpAddr::V6(ip) = gateway {
+        if ip.segments()[0] & 0xffc0 == 0xfe80 {
+            return true;
+        }
+    }
+    ifaces
+        .iter()
+        .any(|iface| iface.index == Some(index) && iface.contains(gateway))
+}
+
+/// Look the gateway up with `lookup`, and if it is missing and `probe` is
+/// set, `send` a probe and look again until it shows up or the polls run
+/// out.
+fn resolve(
+    probe: bool,
+    mut lookup: impl FnMut() -> io::Result<Option<MacAddr>>,
+    send: impl FnOnce() -> io::Result<()>,
+) -> io::Result<Option<MacAddr>> {
+    if let Some(mac) = lookup()? {
+        return Ok(Some(mac));
+    }
+    if !probe {
+        return Ok(None);
+    }
+    send()?;
+    for _ in 0..PROBE_POLLS {
+        thread::sleep(PROBE_INTERVAL);
+        if let Some(mac) = lookup()? {
+            return Ok(Some(mac));
+        }
+    }
+    Ok(None)
+}
+
+/// An empty datagram to the discard port of `gateway`, which needs its
+/// link-layer address before it can go out.
+fn send_probe(gateway: IpAddr, index: u32) -> io::Result<()> {
+    let (local, target): (SocketAddr, SocketAddr) = match gateway {
+        IpAddr::V4(_) => (([0u8; 4], 0).into(), (gateway, 9).into()),
+        IpAddr::V6(ip) => (
+            ([0u8; 16], 0).into(),
+            SocketAddrV6::new(ip, 9, 0, index).into(),
+        ),
+    };
+    UdpSocket::bind(local)?.send_to(&[], target)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    fn eth0() -> Vec<Interface> {
+        let mut eth0 = Interface::new("eth0", Kind::Ipv4);
+        eth0.index = Some(2);
+        eth0.addr = Some(([192, 0, 2, 2], 0).into());
+        eth0.mask = Some(([255, 255, 255, 0], 0).into());
+        vec![eth0]
+    }
+
+    #[test]
+    fn gateways_on_the_network_of_their_link_are_on_link() {
+        let ifaces = eth0();
+        assert!(on_link("192.0.2.1".parse().unwrap(), 2, &ifaces));
+        // The right network, the wrong link.
+        assert!(!on_link("192.0.2.1".parse().unwrap(), 3, &ifaces));
+        assert!(!on_link("198.51.100.1".parse().unwrap(), 2, &ifaces));
+        // Link-local gateways need no address of theirs.
+        assert!(on_link("fe80::1".parse().unwrap(), 3, &ifaces));
+        assert!(!on_link("2001:db8::1".parse().unwrap(), 2, &ifaces));
+    }
+
+    const MAC: MacAddr = MacAddr::new([0x02, 0, 0x5e, 0x10, 0, 1]);
+
+    #[test]
+    fn known_gateways_are_not_probed() {
+        let sent = Cell::new(false);
+        let send = || {
+            sent.set(true);
+            Ok(())
+        };
+        assert_eq!(resolve(true, || Ok(Some(MAC)), send).unwrap(), Some(MAC));
+        assert!(!sent.get());
+    }
+
+    #[test]
+    fn nothing_is_sent_without_probe() {
+        let lookups = Cell::new(0);
+        let lookup = || {
+            lookups.set(lookups.get() + 1);
+            Ok(None)
+        };
+        let send = || -> io::Result<()> { panic!("probed") };
+        assert_eq!(resolve(false, lookup, send).unwrap(), None);
+        assert_eq!(lookups.get(), 1);
+    }
+
+    #[test]
+    fn probed_gateways_are_looked_up_until_they_resolve() {
+        let (lookups, sent) = (Cell::new(0), Cell::new(0));
+        let lookup = || {
+            lookups.set(lookups.get() + 1);
+            // Resolved by the second poll after the probe.
+            Ok(match sent.get() > 0 && lookups.get() == 3 {
+                true => Some(MAC),
+                false => None,
+            })
+        };
+        let send = || {
+            sent.set(sent.get() + 1);
+            Ok(())
+        };
+        assert_eq!(resolve(true, lookup, send).unwrap(), Some(MAC));
+        assert_eq!((lookups.get(), sent.get()), (3, 1));
+    }
+
+    #[test]
+    fn unresolvable_gateways_give_up() {
+        let lookups = Cell::new(0);
+        let lookup = || {
+            lookups.set(lookups.get() + 1);
+            Ok(None)
+        };
+        assert_eq!(resolve(true, lookup, || Ok(())).unwrap(), None);
+        assert_eq!(lookups.get(), PROBE_POLLS + 1);
+    }
+
+    #[test]
+    fn lookup_and_probe_errors_are_returned() {
+        let denied = || io::Error::from(io::ErrorKind::PermissionDenied);
+        let err = resolve(true, || Err(denied()), || Ok(())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        let err = resolve(true, || Ok(None), || Err(denied())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn only_ip_families_have_gateways() {
+        let err = gateway_mac(Kind::Link, false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    /// `eth0` with an IPv6 entry and its link entry besides the IPv4 one,
+    /// and `lo`.
+    fn two_links() -> Vec<Interface> {
+        let mut ifaces = eth0();
+        let mut link = Interface::new("eth0", Kind::Link);
+        link.index = Some(2);
+        let mut v6 = Interface::new("eth0", Kind::Ipv6);
+        v6.index = Some(2);
+        v6.addr = Some("[2001:db8::2]:0".parse().unwrap());
+        let mut lo = Interface::new("lo", Kind::Ipv4);
+        lo.index = Some(1);
+        ifaces.insert(0, link);
+        ifaces.push(v6);
+        ifaces.insert(0, lo);
+        ifaces
+    }
+
+    fn route(
+        v4: Option<u32>,
+        v6: Option<u32>,
+    ) -> impl FnMut(Kind) -> io::Result<Option<(IpAddr, u32)>> {
+        move |family| {
+            Ok(match family {
+                Kind::Ipv4 => v4.map(|index| ("192.0.2.1".parse().unwrap(), index)),
+                _ => v6.map(|index| ("fe80::1".parse().unwrap(), index)),
+            })
+        }
+    }
+
+    #[test]
+    fn the_default_interface_is_the_entry_of_the_route_family() {
+        let found = default_interface_with(route(Some(2), None), || Ok(two_links()))
+            .unwrap()
+            .unwrap();
+        assert_eq!((found.name.as_str(), found.kind), ("eth0", Kind::Ipv4));
+        let found = default_interface_with(route(None, Some(2)), || Ok(two_links()))
+            .unwrap()
+            .unwrap();
+        assert_eq!((found.name.as_str(), found.kind), ("eth0", Kind::Ipv6));
+    }
+
+    #[test]
+    fn interfaces_without_an_address_of_the_family_give_their_first_entry() {
+        let mut ifaces = two_links();
+        ifaces.retain(|iface| iface.kind != Kind::Ipv6);
+        let found = default_interface_with(route(None, Some(2)), || Ok(ifaces))
+            .unwrap()
+            .unwrap();
+        assert_eq!((found.name.as_str(), found.kind), ("eth0", Kind::Link));
+    }
+
+    #[test]
+    fn no_default_route_is_no_default_interface() {
+        let enumerate = || -> io::Result<Vec<Interface>> { panic!("enumerated") };
+        assert!(default_interface_with(route(None, None), enumerate)
+            .unwrap()
+            .is_none());
+        // A route over a link that is gone by the time of the enumeration.
+        assert!(
+            default_interface_with(route(Some(7), None), || Ok(two_links()))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn route_errors_are_returned() {
+        let unsupported = |_| Err(io::Error::from(io::ErrorKind::Unsupported));
+        let err = default_interface_with(unsupported, || Ok(two_links())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+}
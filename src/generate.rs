@@ -0,0 +1,255 @@
+//! Generating plausible interfaces from fuzzer input, with `arbitrary`.
+//!
+//! [`Interface`] implements [`Arbitrary`] with [`Constraints::default`]; the
+//! functions in here take other constraints.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{AddressOrigin, Interface, InterfaceFlags, Kind, MacAddr, NextHop};
+
+/// What a generated [`Interface`] may look like.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Constraints {
+    /// Give any IPv6 address a scope id, not only link-local ones.
+    pub scope_id_anywhere: bool,
+}
+
+impl<'a> Arbitrary<'a> for InterfaceFlags {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<InterfaceFlags> {
+        Ok(InterfaceFlags::from_bits_truncate(u.arbitrary()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Interface {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Interface> {
+        interface(u, &Constraints::default())
+    }
+}
+
+/// An interface entry that could have come from [`ifaces`](crate::ifaces).
+///
+/// The address, mask and next hop are all of the family `kind` says, or
+/// all `None` for link entries. Masks are contiguous, and a broadcast
+/// address belongs to the network it is on.
+pub fn interface(u: &mut Unstructured<'_>, constraints: &Constraints) -> Result<Interface> {
+    let index = match u.arbitrary()? {
+        true => Some(u.int_in_range(1..=u32::MAX)?),
+        false => None,
+    };
+    let kind = Kind::arbitrary(u)?;
+    let (addr, mask, hop) = match kind {
+        Kind::Ipv4 => v4(u)?,
+        Kind::Ipv6 => v6(u, index.unwrap_or(0), constraints)?,
+        _ => (None, None, None),
+    };
+    Ok(Interface {
+        name: name(u)?,
+        index,
+        kind,
+        addr,
+        mask,
+        hop,
+        origin: match addr {
+            Some(addr) => AddressOrigin::of_address(&addr.ip()).unwrap_or(u.arbitrary()?),
+            None => AddressOrigin::Unknown,
+        },
+        flags: u.arbitrary()?,
+        mac: match u.arbitrary()? {
+            true => MacAddr::from_hardware_addr(&u.arbitrary::<[u8; 6]>()?),
+            false => None,
+        },
+        stats: u.arbitrary()?,
+        mtu: match u.arbitrary()? {
+            true => Some(u.int_in_range(68..=65535)?),
+            false => None,
+        },
+        os: Default::default(),
+    })
+}
+
+/// An interface name of up to 15 characters, as both Linux and the BSDs
+/// allow.
+fn name(u: &mut Unstructured<'_>) -> Result<String> {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let len = u.int_in_range(1..=15)?;
+    (0..len)
+        .map(|_| u.choose(CHARS).map(|&c| c as char))
+        .collect()
+}
+
+type Entry = (Option<SocketAddr>, Option<SocketAddr>, Option<NextHop>);
+
+fn v4(u: &mut Unstructured<'_>) -> Result<Entry> {
+    let addr = Ipv4Addr::from(u.arbitrary::<u32>()?);
+    let len: u32 = u.int_in_range(0..=32)?;
+    let mask = u32::MAX.checked_shl(32 - len).unwrap_or(0);
+    let hop = match u.int_in_range(0..=2)? {
+        0 if len < 31 => Some(NextHop::Broadcast(SocketAddr::new(
+            IpAddr::V4(Ipv4Addr::from(u32::from(addr) | !mask)),
+            0,
+        ))),
+        1 => Some(NextHop::Destination(SocketAddr::new(
+            IpAddr::V4(Ipv4Addr::from(u.arbitrary::<u32>()?)),
+            0,
+        ))),
+        _ => None,
+    };
+    Ok((
+        Some(SocketAddr::new(IpAddr::V4(addr), 0)),
+        Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::from(mask)), 0)),
+        hop,
+    ))
+}
+
+fn v6(u: &mut Unstructured<'_>, index: u32, constraints: &Constraints) -> Result<Entry> {
+    let addr = Ipv6Addr::from(u.arbitrary::<u128>()?);
+    let len: u32 = u.int_in_range(0..=128)?;
+    let mask = u128::MAX.checked_shl(128 - len).unwrap_or(0);
+    let link_local = addr.segments()[0] & 0xffc0 == 0xfe80;
+    let scope_id = match link_local || constraints.scope_id_anywhere {
+        true => index,
+        false => 0,
+    };
+    let hop = match u.arbitrary()? {
+        true => Some(NextHop::Destination(SocketAddr::new(
+            IpAddr::V6(Ipv6Addr::from(u.arbitrary::<u128>()?)),
+            0,
+        ))),
+        false => None,
+    };
+    Ok((
+        Some(SocketAddr::V6(SocketAddrV6::new(addr, 0, 0, scope_id))),
+        Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(mask)), 0)),
+        hop,
+    ))
+}
+
+#[cfg(all(test, feature = "arbitrary"))]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn mask_of(ip: IpAddr, len: u8) -> IpAddr {
+        match ip {
+            IpAddr::V4(_) => {
+                let bits = u32::MAX.checked_shl(32 - len as u32).unwrap_or(0);
+                Ipv4Addr::from(bits).into()
+            }
+            IpAddr::V6(_) => {
+                let bits = u128::MAX.checked_shl(128 - len as u32).unwrap_or(0);
+                Ipv6Addr::from(bits).into()
+            }
+        }
+    }
+
+    fn with_mask(mask: IpAddr) -> Interface {
+        let mut iface = Interface::new("eth0", Kind::Ipv4);
+        iface.mask = Some(SocketAddr::new(mask, 0));
+        iface
+    }
+
+    fn generated(bytes: &[u8], constraints: &Constraints) -> Option<Interface> {
+        interface(&mut Unstructured::new(bytes), constraints).ok()
+    }
+
+    fn bytes() -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec(any::<u8>(), 0..256)
+    }
+
+    proptest! {
+        #[test]
+        fn prefix_len_round_trips_through_the_mask(len in 0u8..=128, v4 in any::<bool>()) {
+            let (ip, len) = match v4 {
+                true => (IpAddr::from([0; 4]), len % 33),
+                false => (IpAddr::from([0u16; 8]), len),
+            };
+            let iface = with_mask(mask_of(ip, len));
+            prop_assert_eq!(iface.prefix_len(), Some(len));
+        }
+
+        #[test]
+        fn holes_in_masks_have_no_prefix_len(bits in any::<u32>()) {
+            let contiguous = bits.leading_ones() == bits.count_ones();
+            let iface = with_mask(Ipv4Addr::from(bits).into());
+            prop_assert_eq!(iface.prefix_len().is_some(), contiguous);
+        }
+
+        #[test]
+        fn generated_masks_give_their_prefix_len_back(bytes in bytes()) {
+            let iface = match generated(&bytes, &Constraints::default()) {
+                Some(iface) => iface,
+                None => return Ok(()),
+            };
+            match (iface.kind.clone(), iface.addr, iface.mask) {
+                (Kind::Ipv4, Some(addr), Some(mask)) => {
+                    prop_assert!(addr.is_ipv4() && mask.is_ipv4());
+                    let len = iface.prefix_len().unwrap();
+                    prop_assert!(len <= 32);
+                    prop_assert_eq!(mask.ip(), mask_of(addr.ip(), len));
+                }
+                (Kind::Ipv6, Some(addr), Some(mask)) => {
+                    prop_assert!(addr.is_ipv6() && mask.is_ipv6());
+                    let len = iface.prefix_len().unwrap();
+                    prop_assert_eq!(mask.ip(), mask_of(addr.ip(), len));
+                }
+                (Kind::Ipv4, ..) | (Kind::Ipv6, ..) => prop_assert!(false, "{:?}", iface),
+                (_, addr, mask) => {
+                    prop_assert!(addr.is_none() && mask.is_none() && iface.hop.is_none())
+                }
+            }
+        }
+
+        #[test]
+        fn broadcast_addresses_are_on_the_network(bytes in bytes()) {
+            let iface = match generated(&bytes, &Constraints::default()) {
+                Some(iface) => iface,
+                None => return Ok(()),
+            };
+            let len = iface.prefix_len();
+            if let (Some(NextHop::Broadcast(hop)), Some(addr), Some(len)) =
+                (iface.hop, iface.addr, len)
+            {
+                let mask = mask_of(addr.ip(), len);
+                let network = |ip: IpAddr| match (ip, mask) {
+                    (IpAddr::V4(ip), IpAddr::V4(mask)) => u32::from(ip) & u32::from(mask),
+                    _ => unreachable!(),
+                };
+                prop_assert!(len < 31);
+                prop_assert_eq!(network(hop.ip()), network(addr.ip()));
+            }
+        }
+
+        #[test]
+        fn scope_ids_only_on_link_local_addresses(
+            bytes in bytes(),
+            anywhere in any::<bool>(),
+        ) {
+            let constraints = Constraints { scope_id_anywhere: anywhere };
+            let iface = generated(&bytes, &constraints);
+            if let Some(Interface { addr: Some(SocketAddr::V6(addr)), index, .. }) = iface {
+                let link_local = addr.ip().segments()[0] & 0xffc0 == 0xfe80;
+                match link_local || anywhere {
+                    true => prop_assert_eq!(addr.scope_id(), index.unwrap_or(0)),
+                    false => prop_assert_eq!(addr.scope_id(), 0),
+                }
+            }
+        }
+
+        #[test]
+        fn mac_addresses_round_trip_through_display(octets in any::<[u8; 6]>()) {
+            let mac = MacAddr::new(octets);
+            prop_assert_eq!(mac.to_string().parse::<MacAddr>(), Ok(mac));
+            prop_assert_eq!(mac.to_string().replace(':', "-").parse::<MacAddr>(), Ok(mac));
+        }
+
+        #[test]
+        fn generated_names_are_valid(bytes in prop::collection::vec(any::<u8>(), 0..64)) {
+            if let Ok(name) = name(&mut Unstructured::new(&bytes)) {
+                let valid = crate::validate_name(&name).unwrap();
+                prop_assert_eq!(valid.to_string(), name);
+            }
+        }
+    }
+}
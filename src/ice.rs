@@ -0,0 +1,228 @@
+//! Gathering ICE host candidates (RFC 8445, section 5.1.1.1).
+
+use std::io;
+use std::net::{IpAddr, Ipv6Addr};
+
+use crate::{HardwareKind, Interface, InterfaceFlags};
+
+/// A local address to offer as an ICE host candidate.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct HostCandidate {
+    pub addr: IpAddr,
+    pub ifindex: u32,
+    /// The candidate priority for component 1, see RFC 8445 section 5.1.2.
+    /// Higher is better, and no two candidates share one.
+    pub priority_hint: u32,
+}
+
+/// Which addresses [`ice_host_candidates_with`] leaves in.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct IceOptions {
+    /// Include unique local IPv6 addresses (`fc00::/7`).
+    pub include_ula: bool,
+}
+
+/// The host candidates of this machine, with the default [`IceOptions`].
+pub fn ice_host_candidates() -> io::Result<Vec<HostCandidate>> {
+    ice_host_candidates_with(&IceOptions::default())
+}
+
+/// The host candidates of this machine.
+///
+/// Left out are interfaces that are not up and running, loopback addresses,
+/// IPv4 and IPv6 link-local addresses, deprecated site-local IPv6
+/// addresses, IPv4-mapped IPv6 addresses, and unique local addresses unless
+/// asked for.
+///
+/// Candidates come highest priority first. Interfaces rank by their
+/// [`HardwareKind`]: wired, then wireless, then anything unknown, then
+/// tunnels and virtual devices such as container bridges. On each
+/// interface IPv6 addresses come before IPv4 ones. Ties are broken by
+/// interface index and address, so the same machine always gives the same
+/// order.
+pub fn ice_host_candidates_with(options: &IceOptions) -> io::Result<Vec<HostCandidate>> {
+    Ok(host_candidates(&crate::ifaces()?, options))
+}
+
+fn host_candidates(ifaces: &[Interface], options: &IceOptions) -> Vec<HostCandidate> {
+    let mut candidates: Vec<(u8, u8, u32, IpAddr)> = Vec::new();
+    for iface in ifaces {
+        let up = InterfaceFlags::UP | InterfaceFlags::RUNNING;
+        if !iface.flags.contains(up) || iface.flags.contains(InterfaceFlags::LOOPBACK) {
+            continue;
+        }
+        let (addr, ifindex) = match (iface.addr, iface.index) {
+            (Some(addr), Some(ifindex)) => (addr.ip(), ifindex),
+            _ => continue,
+        };
+        let family = match addr {
+            IpAddr::V4(v4) if v4.is_loopback() || v4.is_link_local() || v4.is_unspecified() => {
+                continue
+            }
+            IpAddr::V6(v6) if !v6_usable(&v6, options) => continue,
+            IpAddr::V4(_) => 0,
+            IpAddr::V6(_) => 1,
+        };
+        let rank = match iface.hardware_kind() {
+            HardwareKind::Ethernet => 3,
+            HardwareKind::Wireless => 2,
+            HardwareKind::Other => 1,
+            _ => 0,
+        };
+        let candidate = (rank, family, ifindex, addr);
+        if !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+
+    candidates.sort_by(|a, b| {
+        (b.0, b.1)
+            .cmp(&(a.0, a.1))
+            .then((a.2, a.3).cmp(&(b.2, b.3)))
+    });
+    candidates
+        .into_iter()
+        .enumerate()
+        .map(|(position, (_, _, ifindex, addr))| HostCandidate {
+            addr,
+            ifindex,
+            priority_hint: priority(65535u32.saturating_sub(position as u32)),
+        })
+        .collect()
+}
+
+fn v6_usable(addr: &Ipv6Addr, options: &IceOptions) -> bool {
+    let segments = addr.segments();
+    let link_local = segments[0] & 0xffc0 == 0xfe80;
+    let site_local = segments[0] & 0xffc0 == 0xfec0;
+    let ula = segments[0] & 0xfe00 == 0xfc00;
+    let mapped = segments[..5] == [0; 5] && segments[5] == 0xffff;
+    !(addr.is_loopback()
+        || addr.is_unspecified()
+        || link_local
+        || site_local
+        || mapped
+        || (ula && !options.include_ula))
+}
+
+/// The RFC 8445 priority of a host candidate for component 1.
+fn priority(local_preference: u32) -> u32 {
+    const HOST_TYPE_PREFERENCE: u32 = 126;
+    (HOST_TYPE_PREFERENCE << 24) | (local_preference << 8) | (256 - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Kind, OsSpecific};
+    use std::net::SocketAddr;
+
+    fn entry(
+        name: &str,
+        index: u32,
+        addr: &str,
+        flags: InterfaceFlags,
+        os: OsSpecific,
+    ) -> Interface {
+        let ip: IpAddr = addr.parse().unwrap();
+        let mut iface = Interface::new(name, if ip.is_ipv4() { Kind::Ipv4 } else { Kind::Ipv6 })
+            .with_os_specific(os);
+        iface.index = Some(index);
+        iface.addr = Some(SocketAddr::new(ip, 0));
+        iface.flags = flags;
+        iface
+    }
+
+    fn up() -> InterfaceFlags {
+        InterfaceFlags::UP | InterfaceFlags::RUNNING
+    }
+
+    fn addrs(candidates: &[HostCandidate]) -> Vec<String> {
+        candidates.iter().map(|c| c.addr.to_string()).collect()
+    }
+
+    #[test]
+    fn unusable_addresses_are_left_out() {
+        let os = OsSpecific::default;
+        let ifaces = vec![
+            entry("lo", 1, "127.0.0.1", up() | InterfaceFlags::LOOPBACK, os()),
+            entry("lo", 1, "::1", up() | InterfaceFlags::LOOPBACK, os()),
+            entry("eth0", 2, "169.254.10.1", up(), os()),
+            entry("eth0", 2, "fe80::1", up(), os()),
+            entry("eth0", 2, "fec0::1", up(), os()),
+            entry("eth0", 2, "::ffff:192.0.2.9", up(), os()),
+            entry("eth0", 2, "fd00::2", up(), os()),
+            entry("eth0", 2, "192.0.2.2", up(), os()),
+            entry("eth1", 3, "198.51.100.2", InterfaceFlags::UP, os()),
+        ];
+        let candidates = host_candidates(&ifaces, &IceOptions::default());
+        assert_eq!(addrs(&candidates), ["192.0.2.2"]);
+
+        let options = IceOptions { include_ula: true };
+        let candidates = host_candidates(&ifaces, &options);
+        assert_eq!(addrs(&candidates), ["fd00::2", "192.0.2.2"]);
+    }
+
+    #[test]
+    fn priorities_descend_and_duplicates_collapse() {
+        let ifaces = vec![
+            entry("eth0", 2, "192.0.2.2", up(), OsSpecific::default()),
+            entry("eth0", 2, "192.0.2.2", up(), OsSpecific::default()),
+            entry("eth0", 2, "192.0.2.3", up(), OsSpecific::default()),
+        ];
+        let candidates = host_candidates(&ifaces, &IceOptions::default());
+        assert_eq!(addrs(&candidates), ["192.0.2.2", "192.0.2.3"]);
+        assert_eq!(candidates[0].priority_hint, priority(65535));
+        assert_eq!(candidates[0].priority_hint, 0x7effffff);
+        assert!(candidates[0].priority_hint > candidates[1].priority_hint);
+    }
+
+    /// A laptop on Wi-Fi and Ethernet, running Docker.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn wired_then_wireless_then_bridges() {
+        let ethernet = || OsSpecific {
+            hardware_type: Some(libc::ARPHRD_ETHER),
+            ..OsSpecific::default()
+        };
+        let wifi = || OsSpecific {
+            devtype: Some("wlan".to_owned()),
+            ..ethernet()
+        };
+        let bridge = || OsSpecific {
+            link_kind: Some("bridge".to_owned()),
+            devtype: Some("bridge".to_owned()),
+            ..ethernet()
+        };
+        let ifaces = vec![
+            entry(
+                "lo",
+                1,
+                "127.0.0.1",
+                up() | InterfaceFlags::LOOPBACK,
+                OsSpecific::default(),
+            ),
+            entry("wlp2s0", 2, "192.168.1.20", up(), wifi()),
+            entry("wlp2s0", 2, "2001:db8:1::20", up(), wifi()),
+            entry("wlp2s0", 2, "fe80::20", up(), wifi()),
+            entry("docker0", 3, "172.17.0.1", up(), bridge()),
+            entry("enp0s31f6", 4, "192.168.1.30", up(), ethernet()),
+            entry("enp0s31f6", 4, "2001:db8:1::30", up(), ethernet()),
+            // A bridge without containers has no carrier.
+            entry("br-5c1d", 5, "172.18.0.1", InterfaceFlags::UP, bridge()),
+        ];
+        let candidates = host_candidates(&ifaces, &IceOptions::default());
+        assert_eq!(
+            addrs(&candidates),
+            [
+                "2001:db8:1::30",
+                "192.168.1.30",
+                "2001:db8:1::20",
+                "192.168.1.20",
+                "172.17.0.1",
+            ]
+        );
+        let indexes: Vec<u32> = candidates.iter().map(|c| c.ifindex).collect();
+        assert_eq!(indexes, [4, 4, 2, 2, 3]);
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt;
+
+#[cfg(not(target_family = "windows"))]
+use crate::MacAddr;
+
+/// Identifies an interface across snapshots, see [`Interface::id`].
+///
+/// Names are the least stable part of an interface, they change with renames
+/// and predictable-name migrations, so the id is built from what the platform
+/// keeps fixed for the lifetime of a device:
+///
+/// * Windows: the `NET_LUID` and the adapter GUID. Both survive renames,
+///   reboots and, for the same device in the same slot, re-plugging.
+/// * Linux: the ifindex and the MAC address. A rename keeps both. Altnames
+///   are left out, as they can be added and removed at any time.
+/// * Other Unix: the name and the MAC address, since the BSDs hand out the
+///   same name to a device that comes back.
+///
+/// When a USB NIC is unplugged and plugged back in, Linux gives it a new
+/// ifindex, so it comes back with a different id even though the MAC is the
+/// same. Whether that is the same NIC again is a policy decision; compare
+/// [`Interface::mac`] if that is what you want. On the BSDs the name and MAC
+/// usually both come back, and so does the id.
+///
+/// Interfaces without a MAC, such as loopback and tunnels, are identified by
+/// the remaining part alone.
+///
+/// [`Interface::id`]: crate::Interface::id
+/// [`Interface::mac`]: crate::Interface::mac
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Debug)]
+pub struct InterfaceId {
+    #[cfg(target_family = "windows")]
+    luid: u64,
+    #[cfg(target_family = "windows")]
+    guid: String,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    index: Option<u32>,
+    #[cfg(all(
+        not(target_family = "windows"),
+        not(any(target_os = "linux", target_os = "android"))
+    ))]
+    name: String,
+    #[cfg(not(target_family = "windows"))]
+    mac: Option<MacAddr>,
+}
+
+impl InterfaceId {
+    pub(crate) fn of(iface: &crate::Interface) -> InterfaceId {
+        InterfaceId {
+            #[cfg(target_family = "windows")]
+            luid: iface.os_specific().luid,
+            #[cfg(target_family = "windows")]
+            guid: iface.os_specific().adapter_name.clone(),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            index: iface.index,
+            #[cfg(all(
+                not(target_family = "windows"),
+                not(any(target_os = "linux", target_os = "android"))
+            ))]
+            name: iface.name.clone(),
+            #[cfg(not(target_family = "windows"))]
+            mac: iface.mac,
+        }
+    }
+}
+
+/// Windows shows the adapter GUID, Linux `index/mac` and the other Unixes
+/// `name/mac`, leaving out the MAC when there is none.
+impl fmt::Display for InterfaceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(target_family = "windows")]
+        {
+            f.write_str(&self.guid)
+        }
+
+        #[cfg(not(target_family = "windows"))]
+        {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            match self.index {
+                Some(index) => write!(f, "{}", index)?,
+                None => f.write_str("?")?,
+            }
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            f.write_str(&self.name)?;
+
+            match self.mac {
+                Some(mac) => write!(f, "/{}", mac),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use crate::{Interface, Kind, MacAddr};
+
+    /// An entry of a NIC as a snapshot would have it, the Windows ids
+    /// derived from `index` the way a fresh LUID would be.
+    fn nic(name: &str, index: u32, mac: [u8; 6], kind: Kind) -> Interface {
+        let mut iface = Interface::new(name, kind);
+        iface.index = Some(index);
+        iface.mac = Some(MacAddr::new(mac));
+        #[cfg(target_family = "windows")]
+        {
+            iface = iface.with_os_specific(crate::OsSpecific {
+                luid: 0x0006_0000_0000_0000 | u64::from(index),
+                adapter_name: format!("{{00000000-0000-0000-0000-{:012x}}}", index),
+                ..crate::OsSpecific::default()
+            });
+        }
+        iface
+    }
+
+    const MAC: [u8; 6] = [0x02, 0, 0, 0, 0, 0x01];
+
+    #[test]
+    fn entries_of_one_interface_share_an_id() {
+        let snapshot = [
+            nic("eth0", 2, MAC, Kind::Packet),
+            nic("eth0", 2, MAC, Kind::Ipv4),
+            nic("eth0", 2, MAC, Kind::Ipv6),
+            nic("eth1", 3, [0x02, 0, 0, 0, 0, 0x02], Kind::Ipv4),
+        ];
+        let ids: BTreeSet<_> = snapshot.iter().map(Interface::id).collect();
+        assert_eq!(ids.len(), 2);
+    }
+
+    #[test]
+    fn ids_survive_re_enumeration() {
+        let before = [
+            nic("eth0", 2, MAC, Kind::Ipv4),
+            nic("lo", 1, [0; 6], Kind::Ipv4),
+        ];
+        let after = [
+            nic("lo", 1, [0; 6], Kind::Ipv4),
+            nic("eth0", 2, MAC, Kind::Ipv4),
+        ];
+        let by_id: HashMap<_, _> = before.iter().map(|iface| (iface.id(), iface)).collect();
+        for iface in &after {
+            assert_eq!(by_id[&iface.id()].name, iface.name);
+        }
+    }
+
+    #[test]
+    fn renames() {
+        let before = nic("eth0", 2, MAC, Kind::Ipv4);
+        let after = nic("enp0s3", 2, MAC, Kind::Ipv4);
+        // Only the other Unixes go by name.
+        let same = cfg!(any(
+            target_family = "windows",
+            target_os = "linux",
+            target_os = "android"
+        ));
+        assert_eq!(before.id() == after.id(), same);
+    }
+
+    // Windows gives a replugged device its LUID and index back.
+    #[cfg(not(target_family = "windows"))]
+    #[test]
+    fn replugged_nics_get_a_new_index() {
+        let before = nic("eth1", 5, MAC, Kind::Ipv4);
+        let after = nic("eth1", 6, MAC, Kind::Ipv4);
+        // The other Unixes give it the same name again.
+        let same = !cfg!(any(target_os = "linux", target_os = "android"));
+        assert_eq!(before.id() == after.id(), same);
+    }
+
+    #[cfg(not(target_family = "windows"))]
+    #[test]
+    fn reused_indexes_with_another_mac_are_another_nic() {
+        let before = nic("eth1", 5, MAC, Kind::Ipv4);
+        let after = nic("eth1", 5, [0x02, 0, 0, 0, 0, 0x09], Kind::Ipv4);
+        assert_ne!(before.id(), after.id());
+    }
+
+    #[test]
+    fn display() {
+        let id = nic("eth0", 2, MAC, Kind::Ipv4).id().to_string();
+        #[cfg(target_family = "windows")]
+        assert_eq!(id, "{00000000-0000-0000-0000-000000000002}");
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(id, "2/02:00:00:00:00:01");
+        #[cfg(all(
+            not(target_family = "windows"),
+            not(any(target_os = "linux", target_os = "android"))
+        ))]
+        assert_eq!(id, "eth0/02:00:00:00:00:01");
+
+        // Without a MAC there is no separator.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            let mut lo = Interface::new("lo", Kind::Ipv4);
+            lo.index = Some(1);
+            assert_eq!(lo.id().to_string(), "1");
+        }
+    }
+}
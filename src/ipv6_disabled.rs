@@ -0,0 +1,55 @@
+use std::io;
+
+/// What [`ipv6_disabled_evidence`] based its answer on.
+#[non_exhaustive]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Ipv6DisabledEvidence {
+    /// Linux: `disable_ipv6` under `/proc/sys/net/ipv6/conf/<name>/`.
+    DisableIpv6(bool),
+    /// Linux: `disable_ipv6` of the `all` entry, the host-level setting.
+    DisableIpv6All(bool),
+    /// Linux: there is no `/proc/sys/net/ipv6`, the kernel runs without
+    /// IPv6, e.g. after booting with `ipv6.disable=1`.
+    NoIpv6Stack,
+    /// Windows: the `DisabledComponents` value of the `Tcpip6` service
+    /// parameters, 0 when it is not set.
+    DisabledComponents(u32),
+    /// Windows: whether the adapter has `IP_ADAPTER_IPV6_ENABLED`.
+    AdapterIpv6Enabled(bool),
+    /// macOS and FreeBSD: whether the interface has an IPv6 address.
+    HasInet6Address(bool),
+    /// macOS and FreeBSD: the `ND6_IFF_*` flags of the interface, from
+    /// `SIOCGIFINFO_IN6`.
+    Nd6Flags(u32),
+}
+
+/// The answer of [`ipv6_disabled_evidence`], with what it is based on.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Ipv6Disabled {
+    pub disabled: bool,
+    pub evidence: Vec<Ipv6DisabledEvidence>,
+}
+
+/// Whether IPv6 is turned off on interface `name`, as opposed to not being
+/// configured yet. `None` when the platform gives no answer.
+///
+/// See [`ipv6_disabled_evidence`] for what is read.
+pub fn ipv6_disabled(name: &str) -> io::Result<Option<bool>> {
+    Ok(ipv6_disabled_evidence(name)?.map(|status| status.disabled))
+}
+
+/// Like [`ipv6_disabled`], with the settings the answer comes from.
+///
+/// - Linux: disabled if `disable_ipv6` is set for the interface or for
+///   `all`, or the kernel has no IPv6 at all.
+/// - Windows: disabled if the adapter lacks `IP_ADAPTER_IPV6_ENABLED`, or
+///   `DisabledComponents` turns IPv6 off for its kind of adapter: bit
+///   `0x01` for tunnels, bit `0x10` for the others but loopback.
+/// - macOS and FreeBSD, a best effort: disabled if the interface has
+///   `ND6_IFF_IFDISABLED` or no IPv6 address, not even a link-local one.
+///
+/// An interface that does not exist gives `NotFound`. Other platforms fail
+/// with `ErrorKind::Unsupported`.
+pub fn ipv6_disabled_evidence(name: &str) -> io::Result<Option<Ipv6Disabled>> {
+    crate::ffi::config::ipv6_disabled(name)
+}
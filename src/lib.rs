@@ -1,26 +1,261 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod core;
+pub use self::core::classify;
+#[cfg(feature = "smoltcp")]
+pub use self::core::from_smoltcp;
+pub use self::core::{
+    detect_flaps, is_eui64_derived, AddressOrigin, CarrierChanges, CounterWidth, HardwareKind,
+    Interface, InterfaceFlags, InterfaceStats, Kind, MacAddr, MacAddrParseError, NextHop,
+    OsSpecific, StatsDelta, TunnelEndpoints,
+};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::core::{Sriov, VethPeer};
+
+#[cfg(feature = "std")]
+mod broadcast;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "std")]
+mod dad;
+#[cfg(feature = "std")]
 pub mod ffi;
-pub use ffi::ifaces;
+#[cfg(feature = "std")]
+mod filter;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+mod gateway;
+#[cfg(all(feature = "std", feature = "arbitrary"))]
+pub mod generate;
+#[cfg(feature = "std")]
+pub mod ice;
+#[cfg(feature = "std")]
+mod id;
+#[cfg(feature = "std")]
+mod ipv6_disabled;
+#[cfg(all(feature = "std", feature = "metrics"))]
+mod metrics;
+#[cfg(feature = "std")]
+pub mod monitor;
+#[cfg(feature = "std")]
+mod multicast;
+#[cfg(feature = "std")]
+mod name;
+#[cfg(feature = "std")]
+mod nat64;
+#[cfg(feature = "std")]
+mod options;
+#[cfg(all(feature = "std", feature = "oui"))]
+mod oui;
+#[cfg(all(feature = "std", feature = "pktinfo"))]
+mod pktinfo;
+#[cfg(all(feature = "std", feature = "resolve"))]
+mod resolve;
+#[cfg(feature = "std")]
+mod route;
+#[cfg(feature = "std")]
+mod signature;
+#[cfg(feature = "std")]
+mod socket;
+#[cfg(feature = "std")]
+mod stack;
+#[cfg(all(feature = "std", feature = "sys"))]
+pub mod sys;
+#[cfg(feature = "std")]
+mod temp_addrs;
+#[cfg(feature = "std")]
+mod timeout;
+#[cfg(feature = "std")]
+mod vpn;
+#[cfg(feature = "std")]
+mod wait;
+#[cfg(feature = "std")]
+mod warning;
+#[cfg(all(feature = "std", feature = "wireguard"))]
+mod wireguard;
+#[cfg(feature = "std")]
+mod wol;
+#[cfg(feature = "std")]
+mod zone;
+#[cfg(feature = "std")]
+pub use broadcast::broadcast_targets;
+#[cfg(feature = "std")]
+pub use cache::{WatchedCache, DEFAULT_TTL};
+#[cfg(feature = "std")]
+pub use dad::{wait_for_dad, DadOutcome};
+#[cfg(all(feature = "std", target_os = "openbsd"))]
+pub use ffi::ifaces_in_rdomain;
+#[cfg(feature = "std")]
+pub use ffi::{bind_socket_to_interface, ifaces, ifaces_with_warnings, interface_of_socket};
+#[cfg(all(feature = "std", target_os = "android"))]
+pub use ffi::{bind_socket_to_network, network_ifaces};
+#[cfg(all(feature = "std", target_family = "windows"))]
+pub use ffi::{
+    dhcp_lease, group_by_network, ip_interface_settings, AdapterFlags, AdapterStrings, DhcpLease,
+    Guid, IpInterfaceSettings, LinkLocalBehavior, RouterDiscovery, WideString,
+};
+#[cfg(all(feature = "std", any(target_os = "linux", target_os = "android")))]
+pub use ffi::{ipv4_settings, ipv6_settings, AcceptRa, Ipv4Settings, Ipv6Settings, RpFilter};
+#[cfg(all(feature = "std", any(target_os = "linux", target_os = "android")))]
+pub use ffi::{ipv6_addr_gen, AddrGenMode, Ipv6AddrGen};
+#[cfg(all(feature = "std", any(target_os = "linux", target_os = "android")))]
+pub use ffi::{nic_topology, IrqInfo, NicTopology};
+#[cfg(all(feature = "std", all(target_os = "linux", feature = "networkmanager")))]
+pub use ffi::{nm_connectivity, nm_device, Connectivity, NmDevice, NmDeviceState};
+#[cfg(all(feature = "std", any(target_os = "linux", target_os = "android")))]
+pub use ffi::{
+    offloads, timestamping_info, Offload, OffloadInfo, RxFilters, TimestampingFlags,
+    TimestampingInfo, TxTypes,
+};
+#[cfg(all(feature = "std", target_os = "macos"))]
+pub use ffi::{primary_interface, service_order};
+#[cfg(feature = "std")]
+pub use filter::{glob_match, InterfaceFilter};
+#[cfg(feature = "std")]
+pub use gateway::{default_interface, gateway_mac};
+#[cfg(feature = "std")]
+pub use ice::ice_host_candidates;
+#[cfg(feature = "std")]
+pub use id::InterfaceId;
+#[cfg(feature = "std")]
+pub use ipv6_disabled::{
+    ipv6_disabled, ipv6_disabled_evidence, Ipv6Disabled, Ipv6DisabledEvidence,
+};
+#[cfg(all(feature = "std", feature = "metrics"))]
+pub use metrics::render_prometheus;
+#[cfg(feature = "std")]
+pub use monitor::StatsMonitor;
+#[cfg(feature = "std")]
+pub use multicast::{join_multicast_all, leave_multicast_all, JoinReport};
+#[cfg(feature = "std")]
+pub use name::{validate_name, NameError, ValidName};
+#[cfg(feature = "std")]
+pub use nat64::{nat64_info, Nat64Info};
+#[cfg(feature = "std")]
+pub use options::IfacesOptions;
+#[cfg(all(feature = "std", feature = "oui"))]
+pub use oui::lookup_oui;
+#[cfg(all(feature = "std", feature = "pktinfo"))]
+pub use pktinfo::{enable_pktinfo, recv_from_with_interface, InterfaceRef};
+#[cfg(all(feature = "std", feature = "resolve"))]
+pub use resolve::{annotate_ptr, AnnotatedInterface, PtrOptions};
+#[cfg(feature = "std")]
+pub use route::Route;
+#[cfg(feature = "std")]
+pub use signature::network_signature;
+#[cfg(feature = "std")]
+pub use stack::{has_global_ipv6, has_ipv4, stack_support, StackSupport};
+#[cfg(feature = "std")]
+pub use temp_addrs::TempAddrs;
+#[cfg(feature = "std")]
+pub use timeout::ifaces_with_timeout;
+#[cfg(feature = "std")]
+pub use vpn::VpnEvidence;
+#[cfg(feature = "std")]
+pub use wait::{await_stable, wait_for, wait_for_interface, StableSnapshot, POLL_INTERVAL};
+#[cfg(all(feature = "std", feature = "watch"))]
+pub use wait::{wait_for_change, ChangeHint, ChangeWatcher};
+#[cfg(feature = "std")]
+pub use warning::{Warning, WarningAction};
+#[cfg(all(feature = "std", feature = "wireguard"))]
+pub use wireguard::{wireguard_info, WgInfo};
+#[cfg(feature = "std")]
+pub use wol::{WolInfo, WolModes};
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub enum NextHop {
-    Broadcast(std::net::SocketAddr),
-    Destination(std::net::SocketAddr),
-}
+#[cfg(feature = "std")]
+impl Interface {
+    /// An identifier that stays the same across renames and re-enumerations.
+    pub fn id(&self) -> InterfaceId {
+        InterfaceId::of(self)
+    }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub enum Kind {
-    Packet,
-    Link,
-    Ipv4,
-    Ipv6,
-    Unknown,
-}
+    pub fn hardware_kind(&self) -> HardwareKind {
+        ffi::hardware_kind(&self.os)
+    }
+
+    /// Whether this is a Wi-Fi interface.
+    ///
+    /// This is a cheap check of what the OS says about the link: sysfs on
+    /// Linux, the interface type on Windows and the media type on macOS and
+    /// FreeBSD. Drivers that do not register with the wireless stack, such
+    /// as some out-of-tree Linux ones and USB dongles that pose as
+    /// Ethernet, are not recognized, and neither is anything on the other
+    /// platforms.
+    pub fn is_wireless(&self) -> bool {
+        ffi::is_wireless(&self.name, &self.os)
+    }
+
+    /// [`is_metered`], taking cellular interfaces for metered when the
+    /// platform cannot say.
+    ///
+    /// Most mobile plans are metered, but not all, which is why
+    /// [`is_metered`] does not guess this.
+    ///
+    /// [`is_metered`]: Interface::is_metered
+    pub fn is_metered_or_cellular(&self) -> Option<bool> {
+        self.is_metered().or_else(|| match self.hardware_kind() {
+            HardwareKind::Cellular => Some(true),
+            _ => None,
+        })
+    }
+
+    /// Whether traffic over the interface is metered, so large transfers
+    /// should wait. `None` when the platform cannot say.
+    ///
+    /// Windows answers from the connectivity cost hint, from Windows 10
+    /// 2004 on. Linux asks NetworkManager with the `networkmanager`
+    /// feature, counting its guesses. Everywhere else, Android included,
+    /// this is `None`.
+    pub fn is_metered(&self) -> Option<bool> {
+        #[cfg(target_family = "windows")]
+        return ffi::is_metered(self.index?);
+        #[cfg(all(target_os = "linux", feature = "networkmanager"))]
+        return ffi::nm_metered(&self.name);
+        #[cfg(not(any(
+            target_family = "windows",
+            all(target_os = "linux", feature = "networkmanager")
+        )))]
+        return None;
+    }
+
+    /// Whether this looks like a VPN interface, see [`vpn_evidence`].
+    ///
+    /// [`vpn_evidence`]: Interface::vpn_evidence
+    pub fn is_vpn(&self) -> bool {
+        self.vpn_evidence().is_some()
+    }
+
+    /// The rule that makes this look like a VPN interface, `None` if no
+    /// rule does.
+    ///
+    /// The rules are tried in order, and the first match wins:
+    ///
+    /// - Linux: a `wireguard`, `tun` (tap too) or `ppp` link kind; for
+    ///   links without a kind, a name starting with `tun`, `tap`, `wg` or
+    ///   `ppp`.
+    /// - Windows: never for the 6to4, ISATAP and Teredo tunnels; then the
+    ///   other, direct and IP-HTTPS tunnel types, the PPP and tunnel
+    ///   interface types, and descriptions mentioning TAP-Windows, Wintun,
+    ///   WireGuard, WAN Miniport or VPN.
+    /// - macOS: a `utun`, `ipsec` or `ppp` interface that SystemConfiguration
+    ///   has as the interface of a connected PPP, IPSec or VPN service.
+    ///   This asks the dynamic store on each call.
+    ///
+    /// Everywhere else this is `None`. These are heuristics: a VPN that
+    /// runs over an ordinary-looking interface is missed, and a tap device
+    /// a VM uses is taken for one.
+    pub fn vpn_evidence(&self) -> Option<VpnEvidence> {
+        vpn::evidence(self)
+    }
 
-#[derive(Debug, Clone)]
-pub struct Interface {
-    pub name: String,
-    pub kind: Kind,
-    pub addr: Option<std::net::SocketAddr>,
-    pub mask: Option<std::net::SocketAddr>,
-    pub hop: Option<NextHop>,
+    /// NetworkManager's view of the interface, see [`nm_device`].
+    #[cfg(all(target_os = "linux", feature = "networkmanager"))]
+    pub fn nm_device(&self) -> Option<NmDevice> {
+        nm_device(&self.name)
+    }
 }
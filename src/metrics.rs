@@ -0,0 +1,262 @@
+//! Interface statistics in the Prometheus text exposition format.
+
+use std::fmt::Write;
+
+use crate::{Interface, InterfaceFlags, InterfaceStats};
+
+/// A counter: its name after `node_network_`, help text and value.
+type Counter = (&'static str, &'static str, fn(&InterfaceStats) -> u64);
+
+/// The counters, with the names and help texts node_exporter uses.
+const COUNTERS: &[Counter] = &[
+    ("receive_bytes_total", "Bytes received.", |s| s.rx_bytes),
+    ("transmit_bytes_total", "Bytes sent.", |s| s.tx_bytes),
+    ("receive_packets_total", "Packets received.", |s| {
+        s.rx_packets
+    }),
+    ("transmit_packets_total", "Packets sent.", |s| s.tx_packets),
+    ("receive_errs_total", "Receive errors.", |s| s.rx_errors),
+    ("transmit_errs_total", "Transmit errors.", |s| s.tx_errors),
+    ("receive_drop_total", "Received packets dropped.", |s| {
+        s.rx_dropped
+    }),
+    (
+        "transmit_drop_total",
+        "Packets dropped before sending.",
+        |s| s.tx_dropped,
+    ),
+    (
+        "receive_multicast_total",
+        "Multicast packets received.",
+        |s| s.multicast,
+    ),
+    ("transmit_colls_total", "Collisions while sending.", |s| {
+        s.collisions
+    }),
+];
+
+/// Render the statistics and state of `ifaces` as Prometheus metrics.
+///
+/// Every interface is one `device`, however many entries it has in
+/// `ifaces`; the first entry with counters gives them. Series follow
+/// node_exporter's `node_network_*` names, so dashboards made for it
+/// work: the counters above, plus the `node_network_up` gauge (1 when the
+/// interface is up and running) and `node_network_mtu_bytes`. Interfaces
+/// without counters only have the gauges.
+pub fn render_prometheus(ifaces: &[Interface]) -> String {
+    let mut devices: Vec<&Interface> = Vec::new();
+    for iface in ifaces {
+        match devices.iter_mut().find(|device| device.name == iface.name) {
+            Some(device) if device.stats.is_none() && iface.stats.is_some() => *device = iface,
+            Some(_) => {}
+            None => devices.push(iface),
+        }
+    }
+
+    let mut out = String::new();
+    for (name, help, value) in COUNTERS {
+        family(&mut out, name, help, "counter");
+        for device in &devices {
+            if let Some(stats) = &device.stats {
+                sample(&mut out, name, &device.name, value(stats));
+            }
+        }
+    }
+    family(
+        &mut out,
+        "up",
+        "Whether the interface is up and running.",
+        "gauge",
+    );
+    for device in &devices {
+        let up = device
+            .flags
+            .contains(InterfaceFlags::UP | InterfaceFlags::RUNNING);
+        sample(&mut out, "up", &device.name, up as u64);
+    }
+    family(&mut out, "mtu_bytes", "The MTU of the interface.", "gauge");
+    for device in &devices {
+        if let Some(mtu) = device.mtu {
+            sample(&mut out, "mtu_bytes", &device.name, mtu as u64);
+        }
+    }
+    out
+}
+
+fn family(out: &mut String, name: &str, help: &str, ty: &str) {
+    let _ = writeln!(out, "# HELP node_network_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE node_network_{} {}", name, ty);
+}
+
+fn sample(out: &mut String, name: &str, device: &str, value: u64) {
+    let _ = writeln!(
+        out,
+        "node_network_{}{{device=\"{}\"}} {}",
+        name,
+        escape_label(device),
+        value
+    );
+}
+
+/// Escape a label value: backslash, double quote and line feed.
+fn escape_label(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Kind;
+
+    fn stats(base: u64) -> InterfaceStats {
+        InterfaceStats {
+            rx_bytes: base + 1,
+            tx_bytes: base + 2,
+            rx_packets: base + 3,
+            tx_packets: base + 4,
+            rx_errors: base + 5,
+            tx_errors: base + 6,
+            rx_dropped: base + 7,
+            tx_dropped: base + 8,
+            multicast: base + 9,
+            collisions: base + 10,
+            ..InterfaceStats::default()
+        }
+    }
+
+    /// `lo` and `eth0` up with counters, `eth0` twice and with its
+    /// counters on the second entry only, and `tun0` down without any.
+    fn fixture() -> Vec<Interface> {
+        let mut lo = Interface::new("lo", Kind::Ipv4);
+        lo.flags = InterfaceFlags::UP | InterfaceFlags::RUNNING | InterfaceFlags::LOOPBACK;
+        lo.mtu = Some(65536);
+        lo.stats = Some(stats(100));
+        let mut eth0_v6 = Interface::new("eth0", Kind::Ipv6);
+        eth0_v6.flags = InterfaceFlags::UP | InterfaceFlags::RUNNING;
+        eth0_v6.mtu = Some(1500);
+        let mut eth0 = eth0_v6.clone();
+        eth0.kind = Kind::Ipv4;
+        eth0.stats = Some(stats(200));
+        let mut tun0 = Interface::new("tun0", Kind::Ipv4);
+        tun0.flags = InterfaceFlags::UP;
+        vec![lo, eth0_v6, eth0, tun0]
+    }
+
+    const SNAPSHOT: &str = r#"# HELP node_network_receive_bytes_total Bytes received.
+# TYPE node_network_receive_bytes_total counter
+node_network_receive_bytes_total{device="lo"} 101
+node_network_receive_bytes_total{device="eth0"} 201
+# HELP node_network_transmit_bytes_total Bytes sent.
+# TYPE node_network_transmit_bytes_total counter
+node_network_transmit_bytes_total{device="lo"} 102
+node_network_transmit_bytes_total{device="eth0"} 202
+# HELP node_network_receive_packets_total Packets received.
+# TYPE node_network_receive_packets_total counter
+node_network_receive_packets_total{device="lo"} 103
+node_network_receive_packets_total{device="eth0"} 203
+# HELP node_network_transmit_packets_total Packets sent.
+# TYPE node_network_transmit_packets_total counter
+node_network_transmit_packets_total{device="lo"} 104
+node_network_transmit_packets_total{device="eth0"} 204
+# HELP node_network_receive_errs_total Receive errors.
+# TYPE node_network_receive_errs_total counter
+node_network_receive_errs_total{device="lo"} 105
+node_network_receive_errs_total{device="eth0"} 205
+# HELP node_network_transmit_errs_total Transmit errors.
+# TYPE node_network_transmit_errs_total counter
+node_network_transmit_errs_total{device="lo"} 106
+node_network_transmit_errs_total{device="eth0"} 206
+# HELP node_network_receive_drop_total Received packets dropped.
+# TYPE node_network_receive_drop_total counter
+node_network_receive_drop_total{device="lo"} 107
+node_network_receive_drop_total{device="eth0"} 207
+# HELP node_network_transmit_drop_total Packets dropped before sending.
+# TYPE node_network_transmit_drop_total counter
+node_network_transmit_drop_total{device="lo"} 108
+node_network_transmit_drop_total{device="eth0"} 208
+# HELP node_network_receive_multicast_total Multicast packets received.
+# TYPE node_network_receive_multicast_total counter
+node_network_receive_multicast_total{device="lo"} 109
+node_network_receive_multicast_total{device="eth0"} 209
+# HELP node_network_transmit_colls_total Collisions while sending.
+# TYPE node_network_transmit_colls_total counter
+node_network_transmit_colls_total{device="lo"} 110
+node_network_transmit_colls_total{device="eth0"} 210
+# HELP node_network_up Whether the interface is up and running.
+# TYPE node_network_up gauge
+node_network_up{device="lo"} 1
+node_network_up{device="eth0"} 1
+node_network_up{device="tun0"} 0
+# HELP node_network_mtu_bytes The MTU of the interface.
+# TYPE node_network_mtu_bytes gauge
+node_network_mtu_bytes{device="lo"} 65536
+node_network_mtu_bytes{device="eth0"} 1500
+"#;
+
+    #[test]
+    fn fixture_stats_render_as_the_snapshot() {
+        assert_eq!(render_prometheus(&fixture()), SNAPSHOT);
+    }
+
+    #[test]
+    fn no_interfaces_still_have_the_headers() {
+        let out = render_prometheus(&[]);
+        assert_eq!(out.lines().count(), 2 * (COUNTERS.len() + 2));
+        assert!(out.lines().all(|line| line.starts_with("# ")));
+    }
+
+    #[test]
+    fn every_family_is_typed_once() {
+        let out = render_prometheus(&fixture());
+        let types: Vec<&str> = out
+            .lines()
+            .filter_map(|line| line.strip_prefix("# TYPE "))
+            .collect();
+        assert_eq!(types.len(), COUNTERS.len() + 2);
+        for ty in &types {
+            let (name, ty) = ty.split_once(' ').unwrap();
+            let expected = if name.ends_with("_total") {
+                "counter"
+            } else {
+                "gauge"
+            };
+            assert_eq!(ty, expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        let table = [
+            ("eth0", "eth0"),
+            (r"a\b", r"a\\b"),
+            (r#"a"b"#, r#"a\"b"#),
+            ("a\nb", r"a\nb"),
+            (r#"\"\n"#, r#"\\\"\\n"#),
+            // Nothing else is.
+            ("a\tb'c\r", "a\tb'c\r"),
+            ("Ethernet 2 (Wi-Fi)", "Ethernet 2 (Wi-Fi)"),
+            ("Verbindung für Überträger", "Verbindung für Überträger"),
+        ];
+        for (value, escaped) in table {
+            assert_eq!(escape_label(value), escaped, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn device_labels_are_escaped_in_samples() {
+        let mut iface = Interface::new("Wi-Fi \"home\"\\2", Kind::Ipv4);
+        iface.mtu = Some(1500);
+        let out = render_prometheus(&[iface]);
+        assert!(out.contains("node_network_up{device=\"Wi-Fi \\\"home\\\"\\\\2\"} 0\n"));
+        assert!(out.contains("node_network_mtu_bytes{device=\"Wi-Fi \\\"home\\\"\\\\2\"} 1500\n"));
+    }
+}
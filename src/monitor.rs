@@ -0,0 +1,354 @@
+//! Sampling interface counters in the background.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use log::debug;
+
+use crate::{Interface, InterfaceFilter, InterfaceId, InterfaceStats, StatsDelta};
+
+/// Which interfaces a [`StatsMonitor`] samples.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Selection {
+    All,
+    Names(Vec<String>),
+    Filter(InterfaceFilter),
+}
+
+impl Selection {
+    pub fn matches(&self, iface: &Interface) -> bool {
+        match self {
+            Selection::All => true,
+            Selection::Names(names) => names.contains(&iface.name),
+            Selection::Filter(filter) => filter.matches(iface),
+        }
+    }
+}
+
+/// The latest rates of one interface.
+#[derive(PartialEq, Debug, Clone)]
+pub struct InterfaceRates {
+    pub id: InterfaceId,
+    pub name: String,
+    pub stats: InterfaceStats,
+    pub rates: StatsDelta,
+}
+
+/// One of the rates in [`StatsDelta`].
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Metric {
+    RxBytes,
+    TxBytes,
+    RxPackets,
+    TxPackets,
+    RxErrors,
+    TxErrors,
+    RxDropped,
+    TxDropped,
+}
+
+impl Metric {
+    pub fn of(&self, delta: &StatsDelta) -> f64 {
+        match self {
+            Metric::RxBytes => delta.rx_bytes,
+            Metric::TxBytes => delta.tx_bytes,
+            Metric::RxPackets => delta.rx_packets,
+            Metric::TxPackets => delta.tx_packets,
+            Metric::RxErrors => delta.rx_errors,
+            Metric::TxErrors => delta.tx_errors,
+            Metric::RxDropped => delta.rx_dropped,
+            Metric::TxDropped => delta.tx_dropped,
+        }
+    }
+}
+
+/// A per-second limit on a [`Metric`], checked on every interface sampled.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Threshold {
+    pub metric: Metric,
+    pub limit: f64,
+}
+
+/// Sent when a rate goes above a [`Threshold`], and again when it falls back.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Crossing {
+    pub id: InterfaceId,
+    pub name: String,
+    pub threshold: Threshold,
+    pub rate: f64,
+    pub above: bool,
+}
+
+/// Samples interface counters on a background thread.
+///
+/// Each sample is one enumeration, however many interfaces are selected.
+/// [`snapshot`](StatsMonitor::snapshot) only clones an `Arc`, so it can be
+/// called as often as needed. Interfaces are tracked by [`InterfaceId`]: one
+/// that disappears is dropped from the snapshot, and it shows up again with
+/// a fresh series one interval after it returns. Dropping the monitor stops
+/// the thread and waits for it.
+pub struct StatsMonitor {
+    latest: Arc<Mutex<Arc<Vec<InterfaceRates>>>>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StatsMonitor {
+    pub fn spawn(interval: Duration, interfaces: Selection) -> StatsMonitor {
+        StatsMonitor::start(interval, interfaces, Vec::new(), None)
+    }
+
+    /// Like [`spawn`](StatsMonitor::spawn), also reporting every crossing
+    /// of `thresholds` on the returned channel.
+    pub fn spawn_with_thresholds(
+        interval: Duration,
+        interfaces: Selection,
+        thresholds: Vec<Threshold>,
+    ) -> (StatsMonitor, Receiver<Crossing>) {
+        let (sender, receiver) = mpsc::channel();
+        let monitor = StatsMonitor::start(interval, interfaces, thresholds, Some(sender));
+        (monitor, receiver)
+    }
+
+    fn start(
+        interval: Duration,
+        interfaces: Selection,
+        thresholds: Vec<Threshold>,
+        crossings: Option<Sender<Crossing>>,
+    ) -> StatsMonitor {
+        let latest = Arc::new(Mutex::new(Arc::new(Vec::new())));
+        let (stop, stopped) = mpsc::channel();
+        let mut sampler = Sampler {
+            interfaces,
+            thresholds,
+            crossings,
+            previous: HashMap::new(),
+            above: HashMap::new(),
+        };
+
+        let published = latest.clone();
+        let thread = thread::spawn(move || loop {
+            match crate::ifaces() {
+                Ok(ifaces) => {
+                    let rates = sampler.sample(&ifaces, Instant::now());
+                    *published.lock().unwrap() = Arc::new(rates);
+                }
+                Err(err) => debug!("stats sample failed: {}", err),
+            }
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => return,
+            }
+        });
+
+        StatsMonitor {
+            latest,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// The rates from the last two samples.
+    pub fn snapshot(&self) -> Arc<Vec<InterfaceRates>> {
+        self.latest.lock().unwrap().clone()
+    }
+}
+
+impl Drop for StatsMonitor {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct Sampler {
+    interfaces: Selection,
+    thresholds: Vec<Threshold>,
+    crossings: Option<Sender<Crossing>>,
+    previous: HashMap<InterfaceId, (InterfaceStats, Instant)>,
+    above: HashMap<(InterfaceId, usize), bool>,
+}
+
+impl Sampler {
+    fn sample(&mut self, ifaces: &[Interface], now: Instant) -> Vec<InterfaceRates> {
+        let mut current = HashMap::new();
+        let mut rates = Vec::new();
+        for iface in ifaces {
+            let stats = match iface.stats {
+                Some(stats) if self.interfaces.matches(iface) => stats,
+                _ => continue,
+            };
+            let id = iface.id();
+            if current.contains_key(&id) {
+                continue;
+            }
+            if let Some((prev, then)) = self.previous.get(&id) {
+                let delta = StatsDelta::between(prev, &stats, now.duration_since(*then));
+                self.check(&id, &iface.name, &delta);
+                rates.push(InterfaceRates {
+                    id: id.clone(),
+                    name: iface.name.clone(),
+                    stats,
+                    rates: delta,
+                });
+            }
+            current.insert(id, (stats, now));
+        }
+
+        self.above.retain(|(id, _), _| current.contains_key(id));
+        self.previous = current;
+        rates
+    }
+
+    fn check(&mut self, id: &InterfaceId, name: &str, delta: &StatsDelta) {
+        let crossings = match &self.crossings {
+            Some(crossings) => crossings,
+            None => return,
+        };
+        for (i, threshold) in self.thresholds.iter().enumerate() {
+            let rate = threshold.metric.of(delta);
+            let above = rate > threshold.limit;
+            let was_above = self.above.insert((id.clone(), i), above).unwrap_or(false);
+            if above != was_above {
+                let _ = crossings.send(Crossing {
+                    id: id.clone(),
+                    name: name.to_owned(),
+                    threshold: *threshold,
+                    rate,
+                    above,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Kind;
+
+    fn eth(name: &str, index: u32, rx_bytes: u64) -> Interface {
+        let mut iface = Interface::new(name, Kind::Ipv4);
+        iface.index = Some(index);
+        iface.stats = Some(InterfaceStats {
+            rx_bytes,
+            ..InterfaceStats::default()
+        });
+        iface
+    }
+
+    fn sampler(interfaces: Selection, thresholds: Vec<Threshold>) -> (Sampler, Receiver<Crossing>) {
+        let (sender, receiver) = mpsc::channel();
+        let sampler = Sampler {
+            interfaces,
+            thresholds,
+            crossings: Some(sender),
+            previous: HashMap::new(),
+            above: HashMap::new(),
+        };
+        (sampler, receiver)
+    }
+
+    fn names(rates: &[InterfaceRates]) -> Vec<&str> {
+        rates.iter().map(|rates| rates.name.as_str()).collect()
+    }
+
+    #[test]
+    fn rates_need_two_samples() {
+        let (mut sampler, _) = sampler(Selection::All, Vec::new());
+        let start = Instant::now();
+        assert!(sampler.sample(&[eth("eth0", 2, 0)], start).is_empty());
+        let rates = sampler.sample(&[eth("eth0", 2, 500)], start + Duration::from_millis(500));
+        assert_eq!(names(&rates), ["eth0"]);
+        assert_eq!(rates[0].rates.rx_bytes, 1000.0);
+        assert_eq!(rates[0].stats.rx_bytes, 500);
+    }
+
+    #[test]
+    fn entries_of_one_interface_are_sampled_once() {
+        let (mut sampler, _) = sampler(Selection::All, Vec::new());
+        let start = Instant::now();
+        let mut v6 = eth("eth0", 2, 0);
+        v6.kind = Kind::Ipv6;
+        sampler.sample(&[eth("eth0", 2, 0), v6.clone()], start);
+        v6.stats.as_mut().unwrap().rx_bytes = 100;
+        let rates = sampler.sample(&[eth("eth0", 2, 100), v6], start + Duration::from_secs(1));
+        assert_eq!(names(&rates), ["eth0"]);
+    }
+
+    #[test]
+    fn vanished_interfaces_start_over() {
+        let (mut sampler, _) = sampler(Selection::All, Vec::new());
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        sampler.sample(&[eth("eth0", 2, 0), eth("usb0", 5, 0)], at(0));
+        let rates = sampler.sample(&[eth("eth0", 2, 10), eth("usb0", 5, 10)], at(1));
+        assert_eq!(names(&rates), ["eth0", "usb0"]);
+        let rates = sampler.sample(&[eth("eth0", 2, 20)], at(2));
+        assert_eq!(names(&rates), ["eth0"]);
+        // Back, with counters from zero: a fresh series, not a reset.
+        let rates = sampler.sample(&[eth("eth0", 2, 30), eth("usb0", 5, 0)], at(3));
+        assert_eq!(names(&rates), ["eth0"]);
+        let rates = sampler.sample(&[eth("eth0", 2, 40), eth("usb0", 5, 7)], at(4));
+        assert_eq!(names(&rates), ["eth0", "usb0"]);
+        assert_eq!(rates[1].rates.rx_bytes, 7.0);
+        assert!(!rates[1].rates.reset);
+    }
+
+    #[test]
+    fn selections() {
+        let start = Instant::now();
+        let snapshot = |rx| [eth("eth0", 2, rx), eth("eth1", 3, rx)];
+        let filter = InterfaceFilter::new().exclude("eth1");
+        for (selection, selected) in [
+            (Selection::Names(vec!["eth1".to_owned()]), "eth1"),
+            (Selection::Filter(filter), "eth0"),
+        ] {
+            let (mut sampler, _) = sampler(selection, Vec::new());
+            sampler.sample(&snapshot(0), start);
+            let rates = sampler.sample(&snapshot(1), start + Duration::from_secs(1));
+            assert_eq!(names(&rates), [selected]);
+        }
+    }
+
+    #[test]
+    fn crossings_are_reported_once_each_way() {
+        let threshold = Threshold {
+            metric: Metric::RxBytes,
+            limit: 100.0,
+        };
+        let (mut sampler, crossings) = sampler(Selection::All, vec![threshold]);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        for (secs, rx) in [(0, 0), (1, 50), (2, 250), (3, 500), (4, 550), (5, 600)] {
+            sampler.sample(&[eth("eth0", 2, rx)], at(secs));
+        }
+        let crossings: Vec<(bool, f64)> = crossings
+            .try_iter()
+            .map(|crossing| (crossing.above, crossing.rate))
+            .collect();
+        assert_eq!(crossings, [(true, 200.0), (false, 50.0)]);
+    }
+
+    #[test]
+    fn the_monitor_publishes_and_stops() {
+        let monitor = StatsMonitor::spawn(Duration::from_millis(10), Selection::All);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while monitor.snapshot().is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        let snapshot = monitor.snapshot();
+        assert!(!snapshot.is_empty());
+        let start = Instant::now();
+        drop(monitor);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        // The snapshot outlives the monitor.
+        assert!(!snapshot.is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+
+use crate::{Interface, InterfaceFlags};
+
+/// What [`join_multicast_all`] or [`leave_multicast_all`] did on each
+/// interface.
+#[derive(Debug)]
+pub struct JoinReport {
+    /// One entry per interface tried, in enumeration order.
+    pub results: Vec<(Interface, io::Result<()>)>,
+}
+
+impl JoinReport {
+    pub fn succeeded(&self) -> impl Iterator<Item = &Interface> {
+        self.results
+            .iter()
+            .filter(|(_, result)| result.is_ok())
+            .map(|(iface, _)| iface)
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = (&Interface, &io::Error)> {
+        self.results
+            .iter()
+            .filter_map(|(iface, result)| result.as_ref().err().map(|err| (iface, err)))
+    }
+
+    pub fn any_succeeded(&self) -> bool {
+        self.succeeded().next().is_some()
+    }
+}
+
+/// Join `group` on every interface that is up and multicast-capable.
+///
+/// IPv4 groups are joined once per interface, by the first IPv4 address on
+/// it, except on Windows, where the interface index is passed in the
+/// RFC 3678 `0.0.0.0/8` form so that interfaces sharing an address still
+/// each get a membership. IPv6 groups are joined by interface index.
+///
+/// Failures, such as `EADDRINUSE` for a group already joined, are collected
+/// in the report instead of stopping the loop. Only failing to enumerate
+/// the interfaces is an error.
+pub fn join_multicast_all(socket: &UdpSocket, group: IpAddr) -> io::Result<JoinReport> {
+    Ok(apply(crate::ifaces()?, group, |iface, group| match group {
+        IpAddr::V4(group) => socket.join_multicast_v4(&group, &v4_interface(iface)?),
+        IpAddr::V6(group) => socket.join_multicast_v6(&group, v6_interface(iface)?),
+    }))
+}
+
+/// Leave `group` on every interface [`join_multicast_all`] would join it on.
+pub fn leave_multicast_all(socket: &UdpSocket, group: IpAddr) -> io::Result<JoinReport> {
+    Ok(apply(crate::ifaces()?, group, |iface, group| match group {
+        IpAddr::V4(group) => socket.leave_multicast_v4(&group, &v4_interface(iface)?),
+        IpAddr::V6(group) => socket.leave_multicast_v6(&group, v6_interface(iface)?),
+    }))
+}
+
+fn apply<F>(ifaces: Vec<Interface>, group: IpAddr, mut op: F) -> JoinReport
+where
+    F: FnMut(&Interface, IpAddr) -> io::Result<()>,
+{
+    let mut results: Vec<(Interface, io::Result<()>)> = Vec::new();
+    for iface in ifaces {
+        if !iface
+            .flags
+            .contains(InterfaceFlags::UP | InterfaceFlags::MULTICAST)
+        {
+            continue;
+        }
+        match (group, iface.addr.map(|addr| addr.ip())) {
+            (IpAddr::V4(_), Some(IpAddr::V4(_))) | (IpAddr::V6(_), Some(IpAddr::V6(_))) => {}
+            _ => continue,
+        }
+        if results.iter().any(|(done, _)| done.id() == iface.id()) {
+            continue;
+        }
+        let result = op(&iface, group);
+        results.push((iface, result));
+    }
+    JoinReport { results }
+}
+
+#[cfg(target_family = "windows")]
+fn v4_interface(iface: &Interface) -> io::Result<Ipv4Addr> {
+    Ok(Ipv4Addr::from(v6_interface(iface)?))
+}
+
+#[cfg(not(target_family = "windows"))]
+fn v4_interface(iface: &Interface) -> io::Result<Ipv4Addr> {
+    match iface.addr.map(|addr| addr.ip()) {
+        Some(IpAddr::V4(addr)) => Ok(addr),
+        _ => Err(io::Error::new(
+            io::ErrorKind::AddrNotAvailable,
+            "interface has no IPv4 address",
+        )),
+    }
+}
+
+fn v6_interface(iface: &Interface) -> io::Result<u32> {
+    iface
+        .index
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "interface has no index"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Kind;
+
+    fn entry(name: &str, index: u32, addr: &str, flags: InterfaceFlags) -> Interface {
+        let addr: IpAddr = addr.parse().unwrap();
+        let kind = match addr {
+            IpAddr::V4(_) => Kind::Ipv4,
+            IpAddr::V6(_) => Kind::Ipv6,
+        };
+        let mut iface = Interface::new(name, kind);
+        iface.index = Some(index);
+        iface.addr = Some((addr, 0).into());
+        iface.flags = flags;
+        iface
+    }
+
+    fn up() -> InterfaceFlags {
+        InterfaceFlags::UP | InterfaceFlags::MULTICAST
+    }
+
+    fn snapshot() -> Vec<Interface> {
+        vec![
+            entry(
+                "lo",
+                1,
+                "127.0.0.1",
+                InterfaceFlags::UP | InterfaceFlags::LOOPBACK,
+            ),
+            entry("eth0", 2, "192.0.2.1", up()),
+            // A second address on the same interface.
+            entry("eth0", 2, "192.0.2.2", up()),
+            entry("eth0", 2, "fe80::1", up()),
+            entry("eth1", 3, "198.51.100.1", InterfaceFlags::MULTICAST),
+            entry("wlan0", 4, "203.0.113.1", up()),
+            entry("wlan0", 4, "fe80::2", up()),
+        ]
+    }
+
+    #[test]
+    fn failures_are_collected_per_interface() {
+        let group: IpAddr = "239.255.0.1".parse().unwrap();
+        let report = apply(snapshot(), group, |iface, _| match iface.name.as_str() {
+            "eth0" => Err(io::Error::from(io::ErrorKind::AddrInUse)),
+            _ => Ok(()),
+        });
+        let tried: Vec<&str> = report
+            .results
+            .iter()
+            .map(|(iface, _)| iface.name.as_str())
+            .collect();
+        assert_eq!(tried, ["eth0", "wlan0"]);
+        let failed: Vec<(&str, io::ErrorKind)> = report
+            .failed()
+            .map(|(iface, err)| (iface.name.as_str(), err.kind()))
+            .collect();
+        assert_eq!(failed, [("eth0", io::ErrorKind::AddrInUse)]);
+        let succeeded: Vec<&str> = report
+            .succeeded()
+            .map(|iface| iface.name.as_str())
+            .collect();
+        assert_eq!(succeeded, ["wlan0"]);
+        assert!(report.any_succeeded());
+    }
+
+    #[test]
+    fn the_first_address_of_the_family_is_used() {
+        let mut seen = Vec::new();
+        apply(snapshot(), "239.255.0.1".parse().unwrap(), |iface, _| {
+            seen.push(iface.addr.unwrap().ip().to_string());
+            Ok(())
+        });
+        assert_eq!(seen, ["192.0.2.1", "203.0.113.1"]);
+
+        let mut seen = Vec::new();
+        apply(snapshot(), "ff02::fb".parse().unwrap(), |iface, _| {
+            seen.push(v6_interface(iface).unwrap());
+            Ok(())
+        });
+        assert_eq!(seen, [2, 4]);
+    }
+
+    #[test]
+    fn nothing_to_join_is_an_empty_report() {
+        let report = apply(Vec::new(), "239.255.0.1".parse().unwrap(), |_, _| Ok(()));
+        assert!(report.results.is_empty());
+        assert!(!report.any_succeeded());
+    }
+
+    #[test]
+    fn interfaces_are_named_by_address_or_index() {
+        let eth0 = entry("eth0", 7, "192.0.2.1", up());
+        #[cfg(not(target_family = "windows"))]
+        assert_eq!(v4_interface(&eth0).unwrap(), Ipv4Addr::new(192, 0, 2, 1));
+        #[cfg(target_family = "windows")]
+        assert_eq!(v4_interface(&eth0).unwrap(), Ipv4Addr::new(0, 0, 0, 7));
+        let err = v6_interface(&Interface::new("eth0", Kind::Ipv6)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
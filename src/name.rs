@@ -0,0 +1,270 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
+
+/// Longest name on Linux and the BSDs: `IFNAMSIZ` less the terminating
+/// NUL, in bytes.
+#[cfg(target_family = "unix")]
+const MAX_LEN: usize = 15;
+/// Longest adapter friendly name: `IF_MAX_STRING_SIZE`, in UTF-16 units.
+#[cfg(target_family = "windows")]
+const MAX_LEN: usize = 256;
+
+/// An interface name that passed [`validate_name`].
+///
+/// Functions that hand names to the OS take one of these, so a name cannot
+/// get to an ioctl, netlink or sysfs without being checked. Names compare
+/// the way the platform does: exactly on Unix, ignoring case on Windows.
+#[derive(Debug, Clone)]
+pub struct ValidName(String);
+
+impl ValidName {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// Whether `name` names the same interface as this.
+    pub fn matches(&self, name: &str) -> bool {
+        #[cfg(target_family = "windows")]
+        return self.0.to_lowercase() == name.to_lowercase();
+        #[cfg(not(target_family = "windows"))]
+        return self.0 == name;
+    }
+
+    fn key(&self) -> std::borrow::Cow<'_, str> {
+        #[cfg(target_family = "windows")]
+        return self.0.to_lowercase().into();
+        #[cfg(not(target_family = "windows"))]
+        return self.0.as_str().into();
+    }
+}
+
+impl PartialEq for ValidName {
+    fn eq(&self, other: &ValidName) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for ValidName {}
+
+impl Hash for ValidName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
+impl AsRef<str> for ValidName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ValidName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Why [`validate_name`] turned a name down.
+#[non_exhaustive]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum NameError {
+    Empty,
+    /// Longer than `max`: bytes on Unix, UTF-16 units on Windows.
+    TooLong {
+        max: usize,
+    },
+    /// A character the platform does not allow in names.
+    InvalidChar(char),
+    /// `.` or `..`, which Linux keeps for directory entries.
+    Reserved,
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NameError::Empty => write!(f, "empty interface name"),
+            NameError::TooLong { max } => {
+                write!(f, "interface name longer than {} characters", max)
+            }
+            NameError::InvalidChar(c) => write!(f, "{:?} is not allowed in interface names", c),
+            NameError::Reserved => write!(f, "reserved interface name"),
+        }
+    }
+}
+
+impl std::error::Error for NameError {}
+
+impl From<NameError> for io::Error {
+    fn from(err: NameError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, err)
+    }
+}
+
+/// Check `name` against the platform's rules for interface names.
+///
+/// Linux takes 1 to 15 bytes without `/`, `:`, whitespace or NUL, and not
+/// `.` or `..`. The BSDs and macOS take 1 to 15 bytes without NUL. Windows
+/// friendly names are 1 to 256 UTF-16 units without NUL. The name is not
+/// looked up, so a valid name may still not exist.
+pub fn validate_name(name: &str) -> Result<ValidName, NameError> {
+    if name.is_empty() {
+        return Err(NameError::Empty);
+    }
+    #[cfg(target_family = "unix")]
+    let len = name.len();
+    #[cfg(target_family = "windows")]
+    let len = name.encode_utf16().count();
+    if len > MAX_LEN {
+        return Err(NameError::TooLong { max: MAX_LEN });
+    }
+    if let Some(c) = name.chars().find(|&c| !allowed(c)) {
+        return Err(NameError::InvalidChar(c));
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if name == "." || name == ".." {
+        return Err(NameError::Reserved);
+    }
+    Ok(ValidName(name.to_owned()))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn allowed(c: char) -> bool {
+    // What the kernel's dev_valid_name() turns down; isspace() is ASCII.
+    !matches!(c, '\0' | '/' | ':') && !c.is_ascii_whitespace() && c != '\x0b'
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn allowed(c: char) -> bool {
+    c != '\0'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn names_within_the_rules_pass() {
+        for name in ["lo", "eth0", "wlp2s0", "br-1a2b3c4d5e6f", "veth.100"] {
+            assert_eq!(validate_name(name).unwrap().as_str(), name);
+        }
+        let longest = "x".repeat(MAX_LEN);
+        assert_eq!(validate_name(&longest).unwrap().into_string(), longest);
+    }
+
+    #[test]
+    fn empty_and_long_names_are_turned_down() {
+        assert_eq!(validate_name("").unwrap_err(), NameError::Empty);
+        assert_eq!(
+            validate_name(&"x".repeat(MAX_LEN + 1)).unwrap_err(),
+            NameError::TooLong { max: MAX_LEN }
+        );
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn unix_lengths_are_bytes() {
+        // Five three-byte characters fill the 15 bytes.
+        assert!(validate_name("€€€€€").is_ok());
+        assert_eq!(
+            validate_name("€€€€€x").unwrap_err(),
+            NameError::TooLong { max: 15 }
+        );
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn linux_characters_follow_dev_valid_name() {
+        for c in ['\0', '/', ':', ' ', '\t', '\n', '\r', '\x0b', '\x0c'] {
+            let name = format!("eth{}0", c);
+            assert_eq!(validate_name(&name).unwrap_err(), NameError::InvalidChar(c));
+        }
+        // Not ASCII whitespace, so the kernel takes it.
+        assert!(validate_name("eth\u{a0}0").is_ok());
+        assert_eq!(validate_name(".").unwrap_err(), NameError::Reserved);
+        assert_eq!(validate_name("..").unwrap_err(), NameError::Reserved);
+        assert!(validate_name("...").is_ok());
+    }
+
+    #[cfg(all(
+        target_family = "unix",
+        not(any(target_os = "linux", target_os = "android"))
+    ))]
+    #[test]
+    fn bsd_names_only_exclude_nul() {
+        assert!(validate_name("en 0/1:2").is_ok());
+        assert!(validate_name(".").is_ok());
+        assert_eq!(
+            validate_name("en\0x").unwrap_err(),
+            NameError::InvalidChar('\0')
+        );
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn windows_names_are_friendly_names() {
+        assert!(validate_name("Ethernet 2").is_ok());
+        assert!(validate_name("vEthernet (Default Switch)").is_ok());
+        assert!(validate_name("Wi-Fi: 5 GHz / office").is_ok());
+        assert_eq!(
+            validate_name("Wi\0Fi").unwrap_err(),
+            NameError::InvalidChar('\0')
+        );
+        // UTF-16 units: 128 characters outside the BMP are 256 of them.
+        assert!(validate_name(&"🛜".repeat(128)).is_ok());
+        assert_eq!(
+            validate_name(&"🛜".repeat(129)).unwrap_err(),
+            NameError::TooLong { max: 256 }
+        );
+    }
+
+    #[test]
+    fn names_compare_the_way_the_platform_does() {
+        let name = validate_name("Ethernet").unwrap();
+        let insensitive = cfg!(target_family = "windows");
+        assert!(name.matches("Ethernet"));
+        assert_eq!(name.matches("ETHERNET"), insensitive);
+        assert_eq!(name == validate_name("ethernet").unwrap(), insensitive);
+        let set: HashSet<ValidName> = ["Ethernet", "ETHERNET", "ethernet"]
+            .iter()
+            .map(|name| validate_name(name).unwrap())
+            .collect();
+        assert_eq!(set.len(), if insensitive { 1 } else { 3 });
+        // Whatever it compares as, it keeps its spelling.
+        assert_eq!(validate_name("ETHERNET").unwrap().to_string(), "ETHERNET");
+    }
+
+    #[test]
+    fn errors_say_what_is_wrong() {
+        assert_eq!(NameError::Empty.to_string(), "empty interface name");
+        assert_eq!(
+            NameError::TooLong { max: 15 }.to_string(),
+            "interface name longer than 15 characters"
+        );
+        assert_eq!(
+            NameError::InvalidChar('/').to_string(),
+            "'/' is not allowed in interface names"
+        );
+        assert_eq!(NameError::Reserved.to_string(), "reserved interface name");
+        let err = io::Error::from(NameError::Empty);
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "empty interface name");
+    }
+
+    #[test]
+    fn invalid_names_stop_before_the_os() {
+        let bad = "x".repeat(MAX_LEN + 1);
+        let err = crate::config::alias(&bad).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = crate::config::remove_neighbor(&bad, "192.0.2.9".parse().unwrap());
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        let err = crate::wait_for_interface(&bad, std::time::Duration::from_secs(5));
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+}
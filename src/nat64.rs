@@ -0,0 +1,166 @@
+//! Telling whether IPv4 is only reachable through NAT64 (464XLAT, RFC 6877).
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, ToSocketAddrs};
+
+use log::debug;
+
+use crate::{Interface, InterfaceFlags};
+
+/// How CLAT interfaces are named: `clat` by clatd on Linux, `v4-<uplink>`
+/// on Android.
+const CLAT_NAME_PREFIXES: &[&str] = &["clat", "v4-"];
+
+/// The name DNS64 synthesizes AAAA records for (RFC 7050).
+const IPV4ONLY_ARPA: &str = "ipv4only.arpa";
+/// The A records of `ipv4only.arpa`.
+const WELL_KNOWN_V4: [Ipv4Addr; 2] = [Ipv4Addr::new(192, 0, 0, 170), Ipv4Addr::new(192, 0, 0, 171)];
+
+/// Where the IPv4 address sits in an IPv6 address for each NAT64 prefix
+/// length, from RFC 6052 section 2.2. Byte 8 is always skipped.
+const EMBEDDINGS: &[(u8, [usize; 4])] = &[
+    (96, [12, 13, 14, 15]),
+    (64, [9, 10, 11, 12]),
+    (56, [7, 9, 10, 11]),
+    (48, [6, 7, 9, 10]),
+    (40, [5, 6, 7, 9]),
+    (32, [4, 5, 6, 7]),
+];
+
+/// The NAT64 setup of the network, see [`nat64_info`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Nat64Info {
+    /// The prefix IPv4 addresses are mapped into, e.g. `64:ff9b::/96`.
+    pub prefix: (Ipv6Addr, u8),
+    /// The local interface translating IPv4 into IPv6, if there is one.
+    pub clat_interface: Option<String>,
+}
+
+/// The NAT64 prefix of the network, or `None` when IPv4 is not translated.
+///
+/// The prefix is discovered by resolving `ipv4only.arpa` (RFC 7050), so
+/// this blocks on a DNS query and only finds networks with DNS64. A failing
+/// lookup counts as no NAT64. The CLAT interface is found by its name.
+pub fn nat64_info() -> io::Result<Option<Nat64Info>> {
+    let ifaces = crate::ifaces()?;
+    let lookup = match (IPV4ONLY_ARPA, 0).to_socket_addrs() {
+        Ok(addrs) => addrs.map(|addr| addr.ip()).collect(),
+        Err(err) => {
+            debug!("resolving {} failed: {}", IPV4ONLY_ARPA, err);
+            Vec::new()
+        }
+    };
+    Ok(info(&ifaces, &lookup))
+}
+
+fn info(ifaces: &[Interface], lookup: &[IpAddr]) -> Option<Nat64Info> {
+    let prefix = lookup.iter().find_map(|addr| match addr {
+        IpAddr::V6(addr) => nat64_prefix(addr),
+        IpAddr::V4(_) => None,
+    })?;
+    let clat_interface = ifaces
+        .iter()
+        .find(|iface| iface.flags.contains(InterfaceFlags::UP) && is_clat(&iface.name))
+        .map(|iface| iface.name.clone());
+    Some(Nat64Info {
+        prefix,
+        clat_interface,
+    })
+}
+
+fn is_clat(name: &str) -> bool {
+    CLAT_NAME_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+/// The prefix `addr` was synthesized from, if it embeds one of the
+/// addresses of `ipv4only.arpa`.
+fn nat64_prefix(addr: &Ipv6Addr) -> Option<(Ipv6Addr, u8)> {
+    let octets = addr.octets();
+    EMBEDDINGS.iter().find_map(|&(len, at)| {
+        let embedded = Ipv4Addr::new(octets[at[0]], octets[at[1]], octets[at[2]], octets[at[3]]);
+        if !WELL_KNOWN_V4.contains(&embedded) {
+            return None;
+        }
+        let mask = u128::MAX << (128 - len);
+        Some((Ipv6Addr::from(u128::from(*addr) & mask), len))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Kind;
+
+    /// The address DNS64 synthesizes for `ipv4only.arpa` with each prefix
+    /// length, placed as in the examples of RFC 6052 section 2.4.
+    const SYNTHESIZED: [(&str, &str, u8); 7] = [
+        ("64:ff9b::c000:aa", "64:ff9b::", 96),
+        ("64:ff9b::192.0.0.171", "64:ff9b::", 96),
+        ("2001:db8:122:344:c0:0:aa00:0", "2001:db8:122:344::", 64),
+        ("2001:db8:122:3c0:0:aa::", "2001:db8:122:300::", 56),
+        ("2001:db8:122:c000:0:aa00::", "2001:db8:122::", 48),
+        ("2001:db8:1c0:0:aa::", "2001:db8:100::", 40),
+        ("2001:db8:c000:aa::", "2001:db8::", 32),
+    ];
+
+    fn iface(name: &str, flags: InterfaceFlags) -> Interface {
+        let mut iface = Interface::new(name, Kind::Ipv4);
+        iface.flags = flags;
+        iface
+    }
+
+    #[test]
+    fn prefixes_are_found_at_every_length() {
+        for (addr, prefix, len) in SYNTHESIZED {
+            let addr: Ipv6Addr = addr.parse().unwrap();
+            assert_eq!(
+                nat64_prefix(&addr),
+                Some((prefix.parse().unwrap(), len)),
+                "{}",
+                addr
+            );
+        }
+    }
+
+    #[test]
+    fn other_addresses_have_no_prefix() {
+        for addr in ["2001:db8::1", "64:ff9b::c000:201", "::1"] {
+            assert_eq!(nat64_prefix(&addr.parse().unwrap()), None, "{}", addr);
+        }
+    }
+
+    #[test]
+    fn clat_interfaces_are_found_by_name() {
+        let lookup = [
+            "192.0.0.170".parse().unwrap(),
+            "64:ff9b::c000:aa".parse().unwrap(),
+        ];
+        let up = InterfaceFlags::UP;
+        let cases = [
+            (vec![iface("eth0", up), iface("clat", up)], Some("clat")),
+            (
+                vec![iface("rmnet_data0", up), iface("v4-rmnet_data0", up)],
+                Some("v4-rmnet_data0"),
+            ),
+            (vec![iface("clat", InterfaceFlags::empty())], None),
+            (vec![iface("v4tunnel", up)], None),
+        ];
+        for (ifaces, clat) in cases {
+            let info = info(&ifaces, &lookup).unwrap();
+            assert_eq!(info.prefix, ("64:ff9b::".parse().unwrap(), 96));
+            assert_eq!(info.clat_interface.as_deref(), clat);
+        }
+    }
+
+    #[test]
+    fn no_synthesized_address_is_no_nat64() {
+        let ifaces = [iface("clat", InterfaceFlags::UP)];
+        // What a resolver without DNS64 returns.
+        let lookup: Vec<IpAddr> = WELL_KNOWN_V4.iter().map(|&addr| addr.into()).collect();
+        assert_eq!(info(&ifaces, &lookup), None);
+        assert_eq!(info(&ifaces, &[]), None);
+    }
+}
@@ -0,0 +1,204 @@
+//! Enumerating with settings other than the defaults of [`ifaces`].
+//!
+//! [`ifaces`]: crate::ifaces
+
+use std::collections::HashSet;
+use std::io;
+use std::net::SocketAddr;
+
+use crate::{Interface, Kind, Warning, WarningAction};
+
+/// How to enumerate; [`IfacesOptions::new`] gives what [`ifaces`] does.
+///
+/// [`ifaces`]: crate::ifaces
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct IfacesOptions {
+    dedupe: bool,
+}
+
+impl IfacesOptions {
+    pub fn new() -> IfacesOptions {
+        IfacesOptions::default()
+    }
+
+    /// Collapse entries that are the same in name, kind, address and mask
+    /// into the first of them. Some drivers, bonds among them, report an
+    /// address twice. Ports and IPv6 flow info mean nothing here and are
+    /// ignored; IPv6 scope ids are compared. Each dropped entry gets a
+    /// [`Warning`], with `entry` its position in the list before the drops.
+    pub fn dedupe(mut self, dedupe: bool) -> IfacesOptions {
+        self.dedupe = dedupe;
+        self
+    }
+
+    pub fn ifaces(&self) -> io::Result<Vec<Interface>> {
+        Ok(self.ifaces_with_warnings()?.0)
+    }
+
+    pub fn ifaces_with_warnings(&self) -> io::Result<(Vec<Interface>, Vec<Warning>)> {
+        let (ifaces, mut warnings) = crate::ffi::ifaces_with_warnings()?;
+        Ok((self.apply(ifaces, &mut warnings), warnings))
+    }
+
+    fn apply(&self, ifaces: Vec<Interface>, warnings: &mut Vec<Warning>) -> Vec<Interface> {
+        match self.dedupe {
+            true => dedupe(ifaces, warnings),
+            false => ifaces,
+        }
+    }
+}
+
+fn dedupe(ifaces: Vec<Interface>, warnings: &mut Vec<Warning>) -> Vec<Interface> {
+    type Key = (String, Kind, Option<SocketAddr>, Option<SocketAddr>);
+    let mut seen: HashSet<Key> = HashSet::new();
+    let mut kept = Vec::with_capacity(ifaces.len());
+    for (entry, iface) in ifaces.into_iter().enumerate() {
+        let key = (
+            iface.name.clone(),
+            iface.kind.clone(),
+            iface.addr.map(normalized),
+            iface.mask.map(normalized),
+        );
+        match seen.insert(key) {
+            true => kept.push(iface),
+            false => warnings.push(Warning::new(
+                iface.name,
+                entry,
+                "addr",
+                WarningAction::Skipped,
+                "the same address as an earlier entry",
+            )),
+        }
+    }
+    kept
+}
+
+/// `addr` without its port and flow info.
+fn normalized(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V4(mut v4) => {
+            v4.set_port(0);
+            SocketAddr::V4(v4)
+        }
+        SocketAddr::V6(mut v6) => {
+            v6.set_port(0);
+            v6.set_flowinfo(0);
+            SocketAddr::V6(v6)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, addr: &str, mask: &str) -> Interface {
+        let addr: SocketAddr = addr.parse().unwrap();
+        let kind = match addr {
+            SocketAddr::V4(_) => Kind::Ipv4,
+            SocketAddr::V6(_) => Kind::Ipv6,
+        };
+        let mut iface = Interface::new(name, kind);
+        iface.addr = Some(addr);
+        iface.mask = Some(mask.parse().unwrap());
+        iface
+    }
+
+    /// A bond that reports its address twice, the second time with a
+    /// port, and an IPv6 address that is there twice with flow info.
+    fn fixture() -> Vec<Interface> {
+        vec![
+            entry("lo", "127.0.0.1:0", "255.0.0.0:0"),
+            entry("bond0", "192.0.2.2:0", "255.255.255.0:0"),
+            entry("bond0", "192.0.2.2:9", "255.255.255.0:0"),
+            entry("bond0", "[fe80::2%5]:0", "[ffff:ffff:ffff:ffff::]:0"),
+            entry("bond0", "[fe80::2%5]:0", "[ffff:ffff:ffff:ffff::]:0"),
+            entry("eth0", "192.0.2.2:0", "255.255.255.0:0"),
+        ]
+    }
+
+    fn addrs(ifaces: &[Interface]) -> Vec<(&str, String)> {
+        ifaces
+            .iter()
+            .map(|iface| (iface.name.as_str(), iface.addr.unwrap().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn duplicates_are_kept_by_default() {
+        let mut warnings = Vec::new();
+        let ifaces = IfacesOptions::new().apply(fixture(), &mut warnings);
+        assert_eq!(ifaces.len(), 6);
+        assert_eq!(warnings, []);
+    }
+
+    #[test]
+    fn duplicates_collapse_into_the_first() {
+        let mut fixture = fixture();
+        if let Some(SocketAddr::V6(addr)) = &mut fixture[4].addr {
+            addr.set_flowinfo(7);
+        }
+        let mut warnings = Vec::new();
+        let ifaces = IfacesOptions::new()
+            .dedupe(true)
+            .apply(fixture, &mut warnings);
+        assert_eq!(
+            addrs(&ifaces),
+            [
+                ("lo", "127.0.0.1:0".to_owned()),
+                ("bond0", "192.0.2.2:0".to_owned()),
+                ("bond0", "[fe80::2%5]:0".to_owned()),
+                // The same address on another interface is no duplicate.
+                ("eth0", "192.0.2.2:0".to_owned()),
+            ]
+        );
+        let warned: Vec<(&str, usize, &str, WarningAction)> = warnings
+            .iter()
+            .map(|w| (w.interface.as_str(), w.entry, w.field, w.action))
+            .collect();
+        assert_eq!(
+            warned,
+            [
+                ("bond0", 2, "addr", WarningAction::Skipped),
+                ("bond0", 4, "addr", WarningAction::Skipped),
+            ]
+        );
+    }
+
+    #[test]
+    fn scope_ids_masks_and_kinds_tell_entries_apart() {
+        let ifaces = vec![
+            entry("bond0", "[fe80::2%5]:0", "[ffff:ffff:ffff:ffff::]:0"),
+            entry("bond0", "[fe80::2%6]:0", "[ffff:ffff:ffff:ffff::]:0"),
+            entry("bond0", "192.0.2.2:0", "255.255.255.0:0"),
+            entry("bond0", "192.0.2.2:0", "255.255.0.0:0"),
+            // An entry without an address, e.g. the link layer one.
+            Interface::new("bond0", Kind::Packet),
+            Interface::new("bond0", Kind::Ipv4),
+        ];
+        let mut warnings = Vec::new();
+        let kept = IfacesOptions::new()
+            .dedupe(true)
+            .apply(ifaces.clone(), &mut warnings);
+        assert_eq!(kept.len(), ifaces.len());
+        assert_eq!(warnings, []);
+    }
+
+    #[test]
+    fn this_machine_has_no_duplicates_left() {
+        let (ifaces, _) = IfacesOptions::new()
+            .dedupe(true)
+            .ifaces_with_warnings()
+            .unwrap();
+        let mut seen = HashSet::new();
+        for iface in &ifaces {
+            let key = (
+                iface.name.clone(),
+                iface.kind.clone(),
+                iface.addr.map(normalized),
+                iface.mask.map(normalized),
+            );
+            assert!(seen.insert(key), "{:?}", iface);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! The IEEE registry of MAC address prefixes, with the `oui` feature.
+//!
+//! `table.rs` is generated with `tools/gen-oui.py` from the registry's
+//! `oui.csv`; regenerate it to pick up new assignments. The checked-in
+//! copy only has a seed of common prefixes so far.
+
+use crate::MacAddr;
+
+#[rustfmt::skip]
+mod table;
+
+/// The organization the IEEE assigned the 24-bit prefix `oui` to.
+///
+/// Only MA-L assignments, whole 24-bit prefixes, are known. Prefixes the
+/// IEEE splits into 28-bit (MA-M) and 36-bit (MA-S) blocks give their MA-L
+/// holder, the "IEEE Registration Authority", not the owner of the block.
+pub fn lookup_oui(oui: &[u8; 3]) -> Option<&'static str> {
+    let i = table::PREFIXES.binary_search(oui).ok()?;
+    Some(table::VENDORS[table::VENDOR_OF[i] as usize])
+}
+
+impl MacAddr {
+    /// The organization the address prefix is registered to, see
+    /// [`lookup_oui`].
+    ///
+    /// `None` for locally administered addresses, such as the random ones
+    /// of virtual interfaces and Wi-Fi privacy, which have no vendor, and
+    /// for prefixes not in the table.
+    pub fn vendor(&self) -> Option<&'static str> {
+        let [a, b, c, ..] = self.octets();
+        if a & 0x02 != 0 {
+            return None;
+        }
+        lookup_oui(&[a, b, c])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mac(s: &str) -> MacAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn the_table_can_be_binary_searched() {
+        assert!(table::PREFIXES.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(table::VENDOR_OF
+            .iter()
+            .all(|&i| (i as usize) < table::VENDORS.len()));
+    }
+
+    #[test]
+    fn known_prefixes_have_their_vendor() {
+        assert_eq!(lookup_oui(&[0x3c, 0xfd, 0xfe]), Some("Intel Corporate"));
+        assert_eq!(lookup_oui(&[0x00, 0x50, 0x56]), Some("VMware, Inc."));
+        // The first and last of the table.
+        assert_eq!(lookup_oui(&[0x00, 0x00, 0x0c]), Some("Cisco Systems, Inc"));
+        assert_eq!(
+            lookup_oui(&[0xdc, 0xa6, 0x32]),
+            Some("Raspberry Pi Trading Ltd")
+        );
+        assert_eq!(mac("00:1b:21:0a:0b:0c").vendor(), Some("Intel Corporate"));
+        assert_eq!(
+            mac("08:00:27:00:00:01").vendor(),
+            Some("PCS Systemtechnik GmbH")
+        );
+    }
+
+    #[test]
+    fn unknown_prefixes_have_none() {
+        assert_eq!(lookup_oui(&[0x00, 0x00, 0x00]), None);
+        assert_eq!(lookup_oui(&[0xff, 0xff, 0xff]), None);
+        assert_eq!(lookup_oui(&[0x3c, 0xfd, 0xff]), None);
+    }
+
+    #[test]
+    fn locally_administered_addresses_have_no_vendor() {
+        // Random Wi-Fi and container addresses.
+        assert_eq!(mac("02:42:ac:11:00:02").vendor(), None);
+        assert_eq!(mac("da:a1:19:00:00:01").vendor(), None);
+        // Even with the bit set on a registered prefix.
+        assert_eq!(mac("3e:fd:fe:00:00:01").vendor(), None);
+        assert_eq!(lookup_oui(&[0x3c, 0xfd, 0xfe]), Some("Intel Corporate"));
+    }
+
+    #[test]
+    fn ma_m_and_ma_s_blocks_give_the_registration_authority() {
+        // 70:b3:d5 is split into 36-bit MA-S blocks, each with its owner.
+        assert_eq!(
+            mac("70:b3:d5:00:10:01").vendor(),
+            Some("IEEE Registration Authority")
+        );
+        assert_eq!(
+            mac("70:b3:d5:ff:f0:01").vendor(),
+            Some("IEEE Registration Authority")
+        );
+    }
+}
@@ -0,0 +1,62 @@
+// Generated by tools/gen-oui.py from the IEEE MA-L registry. Do not edit.
+
+/// Registered prefixes, sorted.
+pub static PREFIXES: [[u8; 3]; 18] = [
+    [0x00, 0x00, 0x0c],
+    [0x00, 0x03, 0x93],
+    [0x00, 0x05, 0x69],
+    [0x00, 0x0c, 0x29],
+    [0x00, 0x0d, 0x3a],
+    [0x00, 0x15, 0x5d],
+    [0x00, 0x16, 0x3e],
+    [0x00, 0x1a, 0x11],
+    [0x00, 0x1b, 0x21],
+    [0x00, 0x1c, 0x42],
+    [0x00, 0x25, 0x90],
+    [0x00, 0x50, 0x56],
+    [0x00, 0xe0, 0x4c],
+    [0x08, 0x00, 0x27],
+    [0x3c, 0xfd, 0xfe],
+    [0x70, 0xb3, 0xd5],
+    [0xb8, 0x27, 0xeb],
+    [0xdc, 0xa6, 0x32],
+];
+
+/// The index in `VENDORS` of the owner of each of `PREFIXES`.
+pub static VENDOR_OF: [u16; 18] = [
+    1,
+    0,
+    12,
+    12,
+    5,
+    5,
+    13,
+    2,
+    4,
+    7,
+    11,
+    12,
+    8,
+    6,
+    4,
+    3,
+    9,
+    10,
+];
+
+pub static VENDORS: [&str; 14] = [
+    "Apple, Inc.",
+    "Cisco Systems, Inc",
+    "Google Inc.",
+    "IEEE Registration Authority",
+    "Intel Corporate",
+    "Microsoft Corporation",
+    "PCS Systemtechnik GmbH",
+    "Parallels, Inc.",
+    "REALTEK SEMICONDUCTOR CORP.",
+    "Raspberry Pi Foundation",
+    "Raspberry Pi Trading Ltd",
+    "Super Micro Computer, Inc.",
+    "VMware, Inc.",
+    "Xensource, Inc.",
+];
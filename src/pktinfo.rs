@@ -0,0 +1,125 @@
+//! Which interface a datagram arrived on, for UDP servers bound to the
+//! unspecified address.
+
+use std::io;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::sync::OnceLock;
+
+use crate::Interface;
+
+/// A datagram's length and sender, with the arrival interface index and
+/// destination address if it came with packet info.
+pub(crate) type Received = (usize, SocketAddr, Option<(u32, IpAddr)>);
+
+/// The interface a datagram arrived on and the address it was sent to,
+/// see [`recv_from_with_interface`].
+#[derive(Debug, Clone)]
+pub struct InterfaceRef {
+    index: u32,
+    destination: IpAddr,
+    name: OnceLock<Option<String>>,
+}
+
+impl InterfaceRef {
+    pub(crate) fn new(index: u32, destination: IpAddr) -> InterfaceRef {
+        InterfaceRef {
+            index,
+            destination,
+            name: OnceLock::new(),
+        }
+    }
+
+    /// The index of the interface.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The destination address of the datagram, one of the interface's own
+    /// addresses or a broadcast or multicast address.
+    pub fn destination(&self) -> IpAddr {
+        self.destination
+    }
+
+    /// The name of the interface, looked up by index on the first call and
+    /// kept for the later ones. `None` if the interface is gone.
+    pub fn name(&self) -> Option<&str> {
+        self.name
+            .get_or_init(|| crate::ffi::index_name(self.index))
+            .as_deref()
+    }
+
+    /// The entries of the interface as [`ifaces`](crate::ifaces) lists them,
+    /// empty if it is gone.
+    pub fn interfaces(&self) -> io::Result<Vec<Interface>> {
+        Ok(crate::ifaces()?
+            .into_iter()
+            .filter(|iface| iface.index == Some(self.index))
+            .collect())
+    }
+}
+
+/// Ask for the arrival interface and destination address of each datagram
+/// `socket` receives, with `IP_PKTINFO` or, on IPv6 sockets,
+/// `IPV6_RECVPKTINFO`. Call it once, before [`recv_from_with_interface`].
+///
+/// Linux, Android, macOS, iOS and Windows; other platforms fail with
+/// `ErrorKind::Unsupported`.
+pub fn enable_pktinfo(socket: &UdpSocket) -> io::Result<()> {
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_family = "windows"
+    ))]
+    return crate::ffi::enable_pktinfo(socket, socket.local_addr()?.is_ipv6());
+    #[allow(unreachable_code)]
+    {
+        let _ = socket;
+        Err(pktinfo_unsupported())
+    }
+}
+
+/// [`UdpSocket::recv_from`], also telling which interface the datagram
+/// arrived on and where it was sent to. Windows reads it with `WSARecvMsg`.
+///
+/// A datagram too big for `buf` is cut short, as with `recv_from`. If it
+/// comes without packet info, because [`enable_pktinfo`] was not called,
+/// the datagram is consumed and this fails with `ErrorKind::InvalidInput`.
+pub fn recv_from_with_interface(
+    socket: &UdpSocket,
+    buf: &mut [u8],
+) -> io::Result<(usize, SocketAddr, InterfaceRef)> {
+    let (len, from, info) = recv(socket, buf)?;
+    match info {
+        Some((index, destination)) => Ok((len, from, InterfaceRef::new(index, destination))),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the datagram came without packet info, see enable_pktinfo",
+        )),
+    }
+}
+
+fn recv(socket: &UdpSocket, buf: &mut [u8]) -> io::Result<Received> {
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_family = "windows"
+    ))]
+    return crate::ffi::recv_pktinfo(socket, buf);
+    #[allow(unreachable_code)]
+    {
+        let _ = (socket, buf);
+        Err(pktinfo_unsupported())
+    }
+}
+
+#[allow(dead_code)]
+fn pktinfo_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "packet info is not supported on this platform",
+    )
+}
@@ -0,0 +1,396 @@
+//! Reverse DNS names for interface addresses, for reports meant for people.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::debug;
+
+use crate::Interface;
+
+/// An entry of the list with the PTR name of its address, see
+/// [`annotate_ptr`].
+#[derive(Debug, Clone)]
+pub struct AnnotatedInterface {
+    pub interface: Interface,
+    /// `None` if the address was skipped, has no PTR record, or the lookup
+    /// failed or did not finish in time.
+    pub ptr: Option<String>,
+}
+
+/// How to look the names up; [`PtrOptions::new`] gives what
+/// [`annotate_ptr`] does, with a per-lookup timeout of 2 seconds.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct PtrOptions {
+    timeout: Duration,
+    total: Duration,
+    concurrency: usize,
+    skip_local: bool,
+}
+
+impl Default for PtrOptions {
+    fn default() -> PtrOptions {
+        PtrOptions {
+            timeout: Duration::from_secs(2),
+            total: Duration::from_secs(10),
+            concurrency: 8,
+            skip_local: true,
+        }
+    }
+}
+
+impl PtrOptions {
+    pub fn new() -> PtrOptions {
+        PtrOptions::default()
+    }
+
+    /// How long a single lookup may take.
+    pub fn timeout(mut self, timeout: Duration) -> PtrOptions {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How long all of them may take, 10 seconds by default. Lookups not
+    /// started or not finished by then give `None`.
+    pub fn total(mut self, total: Duration) -> PtrOptions {
+        self.total = total;
+        self
+    }
+
+    /// How many lookups run at once, 8 by default.
+    pub fn concurrency(mut self, concurrency: usize) -> PtrOptions {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Whether to leave out loopback and link-local addresses, which have
+    /// no names worth asking the DNS for. On by default.
+    pub fn skip_local(mut self, skip_local: bool) -> PtrOptions {
+        self.skip_local = skip_local;
+        self
+    }
+
+    pub fn annotate(&self, ifaces: &[Interface]) -> Vec<AnnotatedInterface> {
+        self.annotate_with(ifaces, ptr_name)
+    }
+
+    /// [`annotate`](PtrOptions::annotate) with `resolve` in place of the
+    /// system resolver.
+    fn annotate_with<R>(&self, ifaces: &[Interface], resolve: R) -> Vec<AnnotatedInterface>
+    where
+        R: Fn(IpAddr) -> Option<String> + Send + Sync + 'static,
+    {
+        let mut addrs: Vec<IpAddr> = Vec::new();
+        for iface in ifaces {
+            let addr = match iface.addr {
+                Some(addr) => addr.ip(),
+                None => continue,
+            };
+            let skipped = self.skip_local && is_local(addr);
+            if !skipped && !addrs.contains(&addr) {
+                addrs.push(addr);
+            }
+        }
+        let names = self.lookup(&addrs, Arc::new(resolve));
+        ifaces
+            .iter()
+            .map(|iface| AnnotatedInterface {
+                interface: iface.clone(),
+                ptr: iface
+                    .addr
+                    .and_then(|addr| names.get(&addr.ip()).cloned().flatten()),
+            })
+            .collect()
+    }
+
+    /// Look `addrs` up, each on a thread of its own, at most `concurrency`
+    /// at a time. A lookup that times out cannot be cancelled; it runs to
+    /// completion and its result is thrown away.
+    fn lookup<R>(&self, addrs: &[IpAddr], resolve: Arc<R>) -> HashMap<IpAddr, Option<String>>
+    where
+        R: Fn(IpAddr) -> Option<String> + Send + Sync + 'static,
+    {
+        let deadline = Instant::now() + self.total;
+        let (done, results) = mpsc::channel();
+        let mut waiting: VecDeque<IpAddr> = addrs.iter().copied().collect();
+        let mut running: HashMap<IpAddr, Instant> = HashMap::new();
+        let mut names = HashMap::new();
+
+        loop {
+            while running.len() < self.concurrency && Instant::now() < deadline {
+                let addr = match waiting.pop_front() {
+                    Some(addr) => addr,
+                    None => break,
+                };
+                let (done, resolve) = (done.clone(), resolve.clone());
+                let spawned =
+                    thread::Builder::new()
+                        .name("ifaces-ptr".to_owned())
+                        .spawn(move || {
+                            let _ = done.send((addr, resolve(addr)));
+                        });
+                match spawned {
+                    Ok(_) => {
+                        running.insert(addr, Instant::now());
+                    }
+                    Err(err) => debug!("no thread to look {} up: {}", addr, err),
+                }
+            }
+            let next = match running.values().min() {
+                Some(&started) => (started + self.timeout).min(deadline),
+                None => break,
+            };
+            match results.recv_timeout(next.saturating_duration_since(Instant::now())) {
+                Ok((addr, name)) => {
+                    if running.remove(&addr).is_some() {
+                        names.insert(addr, name);
+                    }
+                }
+                Err(_) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        debug!("{} PTR lookups did not finish in time", running.len());
+                        break;
+                    }
+                    running.retain(|addr, started| {
+                        let keep = *started + self.timeout > now;
+                        if !keep {
+                            debug!("PTR lookup of {} timed out", addr);
+                        }
+                        keep
+                    });
+                }
+            }
+        }
+        names
+    }
+}
+
+/// Each entry of `ifaces` with the PTR name of its address, from the
+/// system resolver (`getnameinfo`, or `GetNameInfoW` on Windows).
+///
+/// The lookups run concurrently, each given `timeout`; see [`PtrOptions`]
+/// for the overall cap and the rest. Every distinct address is looked up
+/// once. Loopback and link-local addresses are skipped, and every failure
+/// leaves the entry's `ptr` at `None`. Other platforms give `None`
+/// throughout.
+pub fn annotate_ptr(ifaces: &[Interface], timeout: Duration) -> Vec<AnnotatedInterface> {
+    PtrOptions::new().timeout(timeout).annotate(ifaces)
+}
+
+fn is_local(addr: IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(addr) => addr.is_loopback() || addr.is_link_local() || addr.is_unspecified(),
+        IpAddr::V6(addr) => {
+            addr.is_loopback() || addr.is_unspecified() || addr.segments()[0] & 0xffc0 == 0xfe80
+        }
+    }
+}
+
+fn ptr_name(addr: IpAddr) -> Option<String> {
+    #[cfg(any(
+        all(
+            target_family = "unix",
+            not(any(target_os = "redox", target_os = "espidf"))
+        ),
+        target_family = "windows"
+    ))]
+    return crate::ffi::ptr_name(addr);
+    #[allow(unreachable_code)]
+    {
+        let _ = addr;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Condvar, Mutex};
+
+    use crate::Kind;
+
+    fn entry(name: &str, addr: &str) -> Interface {
+        let mut iface = Interface::new(name, Kind::Ipv4);
+        iface.addr = Some(addr.parse().unwrap());
+        iface
+    }
+
+    fn ifaces() -> Vec<Interface> {
+        vec![
+            Interface::new("eth0", Kind::Packet),
+            entry("lo", "127.0.0.1:0"),
+            entry("lo", "[::1]:0"),
+            entry("eth0", "192.0.2.1:0"),
+            entry("eth0", "[fe80::1%2]:0"),
+            entry("eth0", "[2001:db8::1]:0"),
+            entry("eth0", "169.254.7.7:0"),
+            // The same address on a second interface.
+            entry("eth1", "192.0.2.1:0"),
+            entry("eth1", "198.51.100.9:0"),
+        ]
+    }
+
+    /// The names `annotated` got, entry by entry.
+    fn ptrs(annotated: &[AnnotatedInterface]) -> Vec<Option<&str>> {
+        annotated.iter().map(|a| a.ptr.as_deref()).collect()
+    }
+
+    /// Something lookups block on until the test lets them go.
+    #[derive(Default)]
+    struct Gate {
+        open: Mutex<bool>,
+        opened: Condvar,
+    }
+
+    impl Gate {
+        fn wait(&self) {
+            let mut open = self.open.lock().unwrap();
+            while !*open {
+                open = self.opened.wait(open).unwrap();
+            }
+        }
+
+        fn open(&self) {
+            *self.open.lock().unwrap() = true;
+            self.opened.notify_all();
+        }
+    }
+
+    #[test]
+    fn entries_get_the_names_of_their_addresses() {
+        let looked_up = Arc::new(AtomicUsize::new(0));
+        let counted = looked_up.clone();
+        let annotated = PtrOptions::new().annotate_with(&ifaces(), move |addr| {
+            counted.fetch_add(1, Ordering::SeqCst);
+            match addr.to_string().as_str() {
+                "192.0.2.1" => Some("host42.corp.example".to_owned()),
+                "2001:db8::1" => Some("host42-v6.corp.example".to_owned()),
+                // No PTR record, or a failed lookup.
+                _ => None,
+            }
+        });
+        assert_eq!(
+            ptrs(&annotated),
+            [
+                None,
+                None,
+                None,
+                Some("host42.corp.example"),
+                None,
+                Some("host42-v6.corp.example"),
+                None,
+                Some("host42.corp.example"),
+                None,
+            ]
+        );
+        // Every entry, in order.
+        assert_eq!(annotated[7].interface.name, "eth1");
+        // 192.0.2.1 once, 2001:db8::1 and 198.51.100.9, and nothing local.
+        assert_eq!(looked_up.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn local_addresses_are_looked_up_when_asked_for() {
+        let annotated = PtrOptions::new()
+            .skip_local(false)
+            .annotate_with(&ifaces(), |addr| Some(format!("name-of-{}", addr)));
+        assert_eq!(annotated[1].ptr.as_deref(), Some("name-of-127.0.0.1"));
+        assert_eq!(annotated[4].ptr.as_deref(), Some("name-of-fe80::1"));
+        assert_eq!(annotated[6].ptr.as_deref(), Some("name-of-169.254.7.7"));
+        // Still nothing for entries without an address.
+        assert_eq!(annotated[0].ptr, None);
+    }
+
+    #[test]
+    fn lookups_run_at_most_concurrency_at_a_time() {
+        let ifaces: Vec<Interface> = (1..=12)
+            .map(|i| entry("eth0", &format!("192.0.2.{}:0", i)))
+            .collect();
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let (counted, seen) = (running.clone(), most.clone());
+        let annotated = PtrOptions::new()
+            .concurrency(3)
+            .annotate_with(&ifaces, move |addr| {
+                let now = counted.fetch_add(1, Ordering::SeqCst) + 1;
+                seen.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(10));
+                counted.fetch_sub(1, Ordering::SeqCst);
+                Some(addr.to_string())
+            });
+        assert!(annotated.iter().all(|a| a.ptr.is_some()));
+        assert!(most.load(Ordering::SeqCst) <= 3);
+        assert!(most.load(Ordering::SeqCst) >= 1);
+    }
+
+    #[test]
+    fn slow_lookups_time_out_alone() {
+        let gate = Arc::new(Gate::default());
+        let blocked = gate.clone();
+        let start = Instant::now();
+        let annotated = PtrOptions::new()
+            .timeout(Duration::from_millis(50))
+            .annotate_with(&ifaces(), move |addr| {
+                if addr.is_ipv6() {
+                    blocked.wait();
+                }
+                Some(addr.to_string())
+            });
+        gate.open();
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(annotated[3].ptr.as_deref(), Some("192.0.2.1"));
+        assert_eq!(annotated[5].ptr, None);
+        assert_eq!(annotated[8].ptr.as_deref(), Some("198.51.100.9"));
+    }
+
+    #[test]
+    fn the_overall_cap_ends_every_lookup() {
+        let gate = Arc::new(Gate::default());
+        let (blocked, started) = (gate.clone(), Arc::new(AtomicUsize::new(0)));
+        let counted = started.clone();
+        let start = Instant::now();
+        let annotated = PtrOptions::new()
+            .timeout(Duration::from_secs(60))
+            .total(Duration::from_millis(50))
+            .concurrency(1)
+            .annotate_with(&ifaces(), move |addr| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                blocked.wait();
+                Some(addr.to_string())
+            });
+        gate.open();
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(annotated.iter().all(|a| a.ptr.is_none()));
+        // The others were never started.
+        assert_eq!(started.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn options_have_their_defaults() {
+        let options = PtrOptions::new();
+        assert_eq!(options, PtrOptions::default());
+        assert_eq!(options.timeout, Duration::from_secs(2));
+        assert_eq!(options.total, Duration::from_secs(10));
+        assert_eq!(options.concurrency, 8);
+        assert!(options.skip_local);
+        assert_eq!(PtrOptions::new().concurrency(0).concurrency, 1);
+    }
+
+    #[test]
+    fn loopback_is_not_looked_up_on_this_machine() {
+        let ifaces = crate::ifaces().unwrap();
+        let loopback: Vec<Interface> = ifaces
+            .into_iter()
+            .filter(|iface| iface.flags.contains(crate::InterfaceFlags::LOOPBACK))
+            .collect();
+        let annotated = annotate_ptr(&loopback, Duration::from_secs(1));
+        assert_eq!(annotated.len(), loopback.len());
+        assert!(annotated.iter().all(|a| a.ptr.is_none()));
+    }
+}
@@ -0,0 +1,170 @@
+use std::io;
+use std::net::IpAddr;
+
+/// A route: where traffic for `destination/prefix_len` is sent.
+///
+/// Build one with [`Route::new`] or [`Route::host`] and the `via`/`on`
+/// setters, e.g. `Route::new(net, 24).via(gateway)` for a routed network
+/// or `Route::host(addr).on(ifindex)` for an on-link host.
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Route {
+    pub destination: IpAddr,
+    pub prefix_len: u8,
+    /// The next hop, `None` for a route to a network that is on-link.
+    pub gateway: Option<IpAddr>,
+    /// The index of the interface the route goes out of. On-link routes
+    /// need one.
+    pub ifindex: Option<u32>,
+    /// The route metric; lower wins. `None` leaves it to the OS.
+    pub metric: Option<u32>,
+    /// The routing table: the Linux table id, or the OpenBSD `rtable`.
+    /// `None` is the main table. Other platforms only have the one.
+    pub table: Option<u32>,
+}
+
+impl Route {
+    pub fn new(destination: IpAddr, prefix_len: u8) -> Route {
+        Route {
+            destination,
+            prefix_len,
+            gateway: None,
+            ifindex: None,
+            metric: None,
+            table: None,
+        }
+    }
+
+    /// A route to the single address `addr` (/32 or /128).
+    pub fn host(addr: IpAddr) -> Route {
+        Route::new(addr, max_prefix_len(&addr))
+    }
+
+    pub fn via(mut self, gateway: IpAddr) -> Route {
+        self.gateway = Some(gateway);
+        self
+    }
+
+    pub fn on(mut self, ifindex: u32) -> Route {
+        self.ifindex = Some(ifindex);
+        self
+    }
+
+    pub fn with_metric(mut self, metric: u32) -> Route {
+        self.metric = Some(metric);
+        self
+    }
+
+    pub fn in_table(mut self, table: u32) -> Route {
+        self.table = Some(table);
+        self
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.prefix_len == max_prefix_len(&self.destination)
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.prefix_len == 0
+    }
+
+    /// Check what every platform would reject anyway, with a clearer
+    /// error: a prefix longer than the address, host bits set in the
+    /// destination, a gateway of the other family, or neither a gateway
+    /// nor an interface.
+    pub(crate) fn validate(&self) -> io::Result<()> {
+        let invalid = |msg| Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+        let width = max_prefix_len(&self.destination);
+        if self.prefix_len > width {
+            return invalid("prefix length longer than the address");
+        }
+        let bits = match self.destination {
+            IpAddr::V4(addr) => u32::from(addr) as u128,
+            IpAddr::V6(addr) => u128::from(addr),
+        };
+        let host_mask = match self.prefix_len {
+            0 => u128::MAX >> (128 - width),
+            len => (1u128 << (width - len)) - 1,
+        };
+        if bits & host_mask != 0 {
+            return invalid("destination has host bits set");
+        }
+        match self.gateway {
+            Some(gateway) if gateway.is_ipv4() != self.destination.is_ipv4() => {
+                invalid("gateway and destination are of different families")
+            }
+            None if self.ifindex.is_none() => invalid("on-link route without an interface"),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn max_prefix_len(addr: &IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(destination: &str, prefix_len: u8) -> Route {
+        Route::new(destination.parse().unwrap(), prefix_len)
+    }
+
+    #[test]
+    fn builders_fill_in_the_fields() {
+        let route = route("10.0.0.0", 8)
+            .via("192.0.2.1".parse().unwrap())
+            .on(4)
+            .with_metric(50)
+            .in_table(100);
+        assert_eq!(route.gateway, Some("192.0.2.1".parse().unwrap()));
+        assert_eq!(route.ifindex, Some(4));
+        assert_eq!(route.metric, Some(50));
+        assert_eq!(route.table, Some(100));
+
+        let host = Route::host("2001:db8::1".parse().unwrap());
+        assert_eq!(host.prefix_len, 128);
+        assert!(host.is_host() && !host.is_default());
+        assert!(self::route("0.0.0.0", 0).is_default());
+        assert!(Route::host("192.0.2.1".parse().unwrap()).is_host());
+    }
+
+    #[test]
+    fn plausible_routes_are_valid() {
+        let routes = [
+            route("10.0.0.0", 8).via("192.0.2.1".parse().unwrap()),
+            route("198.51.100.0", 24).on(4),
+            route("0.0.0.0", 0).via("192.0.2.1".parse().unwrap()),
+            route("::", 0).via("fe80::1".parse().unwrap()).on(4),
+            Route::host("192.0.2.7".parse().unwrap()).on(4),
+            Route::host("2001:db8::7".parse().unwrap()).on(4),
+        ];
+        for route in &routes {
+            route.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn implausible_routes_are_invalid_input() {
+        let gateway = |route: Route| route.via("192.0.2.1".parse().unwrap());
+        let routes = [
+            (gateway(route("10.0.0.0", 33)), "longer"),
+            (route("2001:db8::", 129).on(4), "longer"),
+            (gateway(route("10.0.0.1", 8)), "host bits"),
+            (gateway(route("0.0.0.1", 0)), "host bits"),
+            (route("2001:db8::1", 64).on(4), "host bits"),
+            (gateway(route("2001:db8::", 64)), "families"),
+            (route("198.51.100.0", 24), "without an interface"),
+        ];
+        for (route, reason) in &routes {
+            let err = route.validate().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{:?}", route);
+            assert!(err.to_string().contains(reason), "{:?}: {}", route, err);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io;
+
+use crate::{Interface, InterfaceFlags};
+
+/// A fingerprint of the network configuration, to tell cheaply whether it
+/// changed since the last call.
+///
+/// It covers each entry's [`InterfaceId`](crate::InterfaceId), name,
+/// address (with the IPv6 scope id), mask and whether it is up and
+/// running. Anything else, counters and MTU included, is left out, and the
+/// order the platform lists entries in does not matter. Two calls give the
+/// same value as long as none of that changed, and almost certainly
+/// different ones if some of it did. Values are only comparable within the
+/// same build of a program.
+pub fn network_signature() -> io::Result<u64> {
+    Ok(signature(&crate::ifaces()?))
+}
+
+pub(crate) fn signature(ifaces: &[Interface]) -> u64 {
+    let mut entries: Vec<_> = ifaces
+        .iter()
+        .map(|iface| {
+            (
+                iface.id(),
+                iface.name.as_str(),
+                iface.addr,
+                iface.mask,
+                (iface.flags & (InterfaceFlags::UP | InterfaceFlags::RUNNING)).bits(),
+            )
+        })
+        .collect();
+    entries.sort();
+    entries.dedup();
+    let mut hasher = DefaultHasher::new();
+    entries.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InterfaceStats, Kind};
+
+    fn entry(name: &str, index: u32, addr: &str, mask: &str) -> Interface {
+        let mut iface = Interface::new(
+            name,
+            if addr.starts_with('[') {
+                Kind::Ipv6
+            } else {
+                Kind::Ipv4
+            },
+        );
+        iface.index = Some(index);
+        iface.addr = Some(addr.parse().unwrap());
+        iface.mask = Some(mask.parse().unwrap());
+        iface.flags = InterfaceFlags::UP | InterfaceFlags::RUNNING | InterfaceFlags::MULTICAST;
+        iface.mtu = Some(1500);
+        iface
+    }
+
+    fn fixture() -> Vec<Interface> {
+        vec![
+            entry("lo", 1, "127.0.0.1:0", "255.0.0.0:0"),
+            entry(
+                "lo",
+                1,
+                "[::1]:0",
+                "[ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff]:0",
+            ),
+            entry("eth0", 2, "192.0.2.2:0", "255.255.255.0:0"),
+            entry("eth0", 2, "[fe80::1%2]:0", "[ffff:ffff:ffff:ffff::]:0"),
+            entry("eth0", 2, "[2001:db8::2]:0", "[ffff:ffff:ffff:ffff::]:0"),
+            entry("wlan0", 3, "198.51.100.7:0", "255.255.255.0:0"),
+        ]
+    }
+
+    fn changed(change: impl FnOnce(&mut Vec<Interface>)) -> bool {
+        let mut ifaces = fixture();
+        change(&mut ifaces);
+        signature(&ifaces) != signature(&fixture())
+    }
+
+    #[test]
+    fn the_same_configuration_has_the_same_signature() {
+        assert_eq!(signature(&fixture()), signature(&fixture()));
+        assert_eq!(signature(&[]), signature(&[]));
+    }
+
+    #[test]
+    fn the_order_does_not_matter() {
+        let ifaces = fixture();
+        let mut reversed = ifaces.clone();
+        reversed.reverse();
+        let mut rotated = ifaces.clone();
+        rotated.rotate_left(2);
+        let mut swapped = ifaces.clone();
+        swapped.swap(1, 4);
+        for permutation in [reversed, rotated, swapped] {
+            assert_eq!(signature(&permutation), signature(&ifaces));
+        }
+    }
+
+    #[test]
+    fn repeated_entries_do_not_matter() {
+        assert!(!changed(|ifaces| {
+            let lo = ifaces[0].clone();
+            ifaces.push(lo);
+        }));
+    }
+
+    #[test]
+    fn material_changes_change_it() {
+        // One address.
+        assert!(changed(
+            |ifaces| ifaces[2].addr = Some("192.0.2.3:0".parse().unwrap())
+        ));
+        // One prefix.
+        assert!(changed(
+            |ifaces| ifaces[2].mask = Some("255.255.0.0:0".parse().unwrap())
+        ));
+        // The scope of a link-local address.
+        assert!(changed(
+            |ifaces| ifaces[3].addr = Some("[fe80::1%9]:0".parse().unwrap())
+        ));
+        // A link going down, or losing carrier.
+        assert!(changed(|ifaces| ifaces[5].flags.remove(InterfaceFlags::UP)));
+        assert!(changed(|ifaces| ifaces[5]
+            .flags
+            .remove(InterfaceFlags::RUNNING)));
+        // A new index, as when the interface was made again.
+        assert!(changed(|ifaces| ifaces[5].index = Some(7)));
+        assert!(changed(|ifaces| ifaces[5].name = "wlan1".to_owned()));
+        // An address more or less.
+        assert!(changed(|ifaces| {
+            ifaces.pop();
+        }));
+        assert!(changed(|ifaces| ifaces.push(entry(
+            "eth0",
+            2,
+            "192.0.2.9:0",
+            "255.255.255.0:0"
+        ))));
+    }
+
+    #[test]
+    fn volatile_fields_are_left_out() {
+        assert!(!changed(|ifaces| {
+            for iface in ifaces.iter_mut() {
+                iface.stats = Some(InterfaceStats {
+                    rx_bytes: 12345,
+                    ..InterfaceStats::default()
+                });
+                iface.mtu = Some(9000);
+                iface.flags.insert(InterfaceFlags::PROMISC);
+                iface.flags.remove(InterfaceFlags::MULTICAST);
+            }
+        }));
+    }
+
+    #[test]
+    fn consecutive_calls_agree() {
+        assert_eq!(network_signature().unwrap(), network_signature().unwrap());
+    }
+}
@@ -0,0 +1,164 @@
+//! Which interface a socket uses, from its local address and the device it
+//! is bound to.
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::Interface;
+
+/// The entry of the interface a socket with local address `local` uses,
+/// `bound` being the index of the interface it is bound to, if any.
+///
+/// An unspecified `local` matches no address, so only `bound` can tell.
+/// Otherwise the entry with `local` as its address is picked, on the
+/// interface of the scope id if `local` has one, and preferably on the
+/// interface the socket is bound to.
+pub(crate) fn interface_of(local: SocketAddr, bound: Option<u32>) -> io::Result<Option<Interface>> {
+    Ok(interface_in(crate::ifaces()?, local, bound))
+}
+
+/// [`interface_of`] among `ifaces`.
+fn interface_in(
+    ifaces: Vec<Interface>,
+    local: SocketAddr,
+    bound: Option<u32>,
+) -> Option<Interface> {
+    let scope = match local {
+        SocketAddr::V6(addr) if addr.scope_id() != 0 => Some(addr.scope_id()),
+        _ => None,
+    };
+    // A dual-stack socket talking IPv4 has the address mapped into IPv6.
+    let ip = match local.ip() {
+        IpAddr::V6(ip) => ip.to_ipv4_mapped().map_or(IpAddr::V6(ip), IpAddr::V4),
+        ip => ip,
+    };
+    if ip.is_unspecified() {
+        let bound = bound?;
+        let entries = ifaces
+            .into_iter()
+            .filter(|iface| iface.index == Some(bound))
+            .collect();
+        return pick(entries, |iface| {
+            iface.addr.map(|addr| addr.is_ipv4()) == Some(local.is_ipv4())
+        });
+    }
+
+    let entries = ifaces
+        .into_iter()
+        .filter(|iface| {
+            iface.addr.map(|addr| addr.ip()) == Some(ip)
+                && (scope.is_none() || iface.index == scope)
+        })
+        .collect();
+    pick(entries, |iface| bound.is_none() || iface.index == bound)
+}
+
+/// The first of `entries` that is `preferred`, or else the first one.
+fn pick(entries: Vec<Interface>, preferred: impl Fn(&Interface) -> bool) -> Option<Interface> {
+    let at = entries.iter().position(preferred).unwrap_or(0);
+    entries.into_iter().nth(at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Kind;
+
+    fn entry(name: &str, index: u32, addr: &str) -> Interface {
+        let addr: SocketAddr = addr.parse().unwrap();
+        let mut iface = Interface::new(
+            name,
+            match addr {
+                SocketAddr::V4(_) => Kind::Ipv4,
+                SocketAddr::V6(_) => Kind::Ipv6,
+            },
+        );
+        iface.index = Some(index);
+        iface.addr = Some(addr);
+        iface
+    }
+
+    /// Loopback, two NICs with the same link-local address, and an
+    /// address configured on both of them.
+    fn ifaces() -> Vec<Interface> {
+        let mut lo = Interface::new("lo", Kind::Packet);
+        lo.index = Some(1);
+        vec![
+            lo,
+            entry("lo", 1, "127.0.0.1:0"),
+            entry("lo", 1, "[::1]:0"),
+            entry("eth0", 2, "192.0.2.1:0"),
+            entry("eth0", 2, "[fe80::1%2]:0"),
+            entry("eth0", 2, "198.51.100.1:0"),
+            entry("eth1", 3, "[fe80::1%3]:0"),
+            entry("eth1", 3, "198.51.100.1:0"),
+        ]
+    }
+
+    /// The name and address of what `local` and `bound` match.
+    fn matched(local: &str, bound: Option<u32>) -> Option<(String, SocketAddr)> {
+        interface_in(ifaces(), local.parse().unwrap(), bound)
+            .map(|iface| (iface.name, iface.addr.unwrap()))
+    }
+
+    fn found(name: &str, addr: &str) -> Option<(String, SocketAddr)> {
+        Some((name.to_owned(), addr.parse().unwrap()))
+    }
+
+    #[test]
+    fn local_addresses_pick_their_entry() {
+        assert_eq!(matched("127.0.0.1:5353", None), found("lo", "127.0.0.1:0"));
+        assert_eq!(matched("[::1]:5353", None), found("lo", "[::1]:0"));
+        assert_eq!(matched("192.0.2.1:80", None), found("eth0", "192.0.2.1:0"));
+        assert_eq!(matched("203.0.113.1:80", None), None);
+    }
+
+    #[test]
+    fn scope_ids_pick_the_interface() {
+        assert_eq!(
+            matched("[fe80::1%3]:546", None),
+            found("eth1", "[fe80::1%3]:0")
+        );
+        assert_eq!(
+            matched("[fe80::1%2]:546", None),
+            found("eth0", "[fe80::1%2]:0")
+        );
+        assert_eq!(matched("[fe80::1%9]:546", None), None);
+    }
+
+    #[test]
+    fn mapped_addresses_match_ipv4_entries() {
+        assert_eq!(
+            matched("[::ffff:192.0.2.1]:80", None),
+            found("eth0", "192.0.2.1:0")
+        );
+    }
+
+    #[test]
+    fn the_bound_device_breaks_ties() {
+        assert_eq!(
+            matched("198.51.100.1:80", None),
+            found("eth0", "198.51.100.1:0")
+        );
+        assert_eq!(
+            matched("198.51.100.1:80", Some(3)),
+            found("eth1", "198.51.100.1:0")
+        );
+        // The address wins over a device that does not have it.
+        assert_eq!(
+            matched("192.0.2.1:80", Some(3)),
+            found("eth0", "192.0.2.1:0")
+        );
+    }
+
+    #[test]
+    fn wildcard_sockets_need_a_bound_device() {
+        assert_eq!(matched("0.0.0.0:80", None), None);
+        assert_eq!(matched("[::]:80", None), None);
+        // An entry of the family of the socket.
+        assert_eq!(matched("0.0.0.0:80", Some(2)), found("eth0", "192.0.2.1:0"));
+        assert_eq!(matched("[::]:80", Some(2)), found("eth0", "[fe80::1%2]:0"));
+        assert_eq!(matched("0.0.0.0:80", Some(9)), None);
+    }
+}
@@ -0,0 +1,192 @@
+use std::io;
+use std::net::{IpAddr, Ipv6Addr};
+
+use crate::classify::is_cgnat;
+use crate::{Interface, InterfaceFlags};
+
+/// Which IP versions this machine can reach the internet over, as far as
+/// its addresses tell. Whether the networks behind them route anywhere is
+/// not checked.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct StackSupport {
+    /// An IPv4 address other than a loopback or link-local one is on an
+    /// interface that is up.
+    pub ipv4: bool,
+    /// Every such IPv4 address is in the shared `100.64.0.0/10` range, so
+    /// the machine is behind carrier-grade NAT.
+    pub ipv4_cgnat: bool,
+    /// A global unicast IPv6 address (`2000::/3`) that is neither
+    /// deprecated nor temporary is on an interface that is up.
+    pub global_ipv6: bool,
+}
+
+/// What [`StackSupport`] says about the current interfaces.
+///
+/// Deprecated and temporary IPv6 addresses are only told apart on Linux.
+/// Windows leaves deprecated ones out of the enumeration; a temporary
+/// address is counted there and on the other platforms, which makes no
+/// difference as long as it comes with a public one.
+pub fn stack_support() -> io::Result<StackSupport> {
+    let excluded = crate::ffi::deprecated_or_temporary_v6();
+    Ok(classify(&crate::ifaces()?, &excluded))
+}
+
+/// Whether an IPv4 address other than a loopback or link-local one is on an
+/// interface that is up, see [`stack_support`].
+pub fn has_ipv4() -> io::Result<bool> {
+    Ok(stack_support()?.ipv4)
+}
+
+/// Whether a global IPv6 address is on an interface that is up, see
+/// [`stack_support`]. Link-local addresses alone do not count.
+pub fn has_global_ipv6() -> io::Result<bool> {
+    Ok(stack_support()?.global_ipv6)
+}
+
+fn classify(ifaces: &[Interface], excluded: &[Ipv6Addr]) -> StackSupport {
+    let mut support = StackSupport::default();
+    let mut all_cgnat = true;
+    for iface in ifaces {
+        if !iface.flags.contains(InterfaceFlags::UP) {
+            continue;
+        }
+        match iface.addr.map(|addr| addr.ip()) {
+            Some(IpAddr::V4(addr)) if !addr.is_loopback() && !addr.is_link_local() => {
+                support.ipv4 = true;
+                all_cgnat &= is_cgnat(addr);
+            }
+            Some(IpAddr::V6(addr)) if is_global_unicast(addr) && !excluded.contains(&addr) => {
+                support.global_ipv6 = true;
+            }
+            _ => {}
+        }
+    }
+    support.ipv4_cgnat = support.ipv4 && all_cgnat;
+    support
+}
+
+fn is_global_unicast(addr: Ipv6Addr) -> bool {
+    addr.segments()[0] & 0xe000 == 0x2000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Kind;
+
+    /// An entry for `addr` on interface `name`, up unless `name` ends in
+    /// `-down`.
+    fn entry(name: &str, addr: &str) -> Interface {
+        let ip: IpAddr = addr.parse().unwrap();
+        let kind = if ip.is_ipv4() { Kind::Ipv4 } else { Kind::Ipv6 };
+        let mut iface = Interface::new(name, kind);
+        iface.addr = Some((ip, 0).into());
+        if !name.ends_with("-down") {
+            iface.flags = InterfaceFlags::UP;
+        }
+        iface
+    }
+
+    fn lo() -> Vec<Interface> {
+        vec![entry("lo", "127.0.0.1"), entry("lo", "::1")]
+    }
+
+    fn machine(entries: &[(&str, &str)]) -> Vec<Interface> {
+        let mut ifaces = lo();
+        ifaces.extend(entries.iter().map(|(name, addr)| entry(name, addr)));
+        ifaces
+    }
+
+    fn support(ipv4: bool, ipv4_cgnat: bool, global_ipv6: bool) -> StackSupport {
+        StackSupport {
+            ipv4,
+            ipv4_cgnat,
+            global_ipv6,
+        }
+    }
+
+    #[test]
+    fn machines_are_classified() {
+        let cases = [
+            ("loopback only", machine(&[]), support(false, false, false)),
+            (
+                "dual stack",
+                machine(&[
+                    ("eth0", "192.0.2.2"),
+                    ("eth0", "2001:db8::2"),
+                    ("eth0", "fe80::2"),
+                ]),
+                support(true, false, true),
+            ),
+            (
+                "IPv6 link-local only",
+                machine(&[("eth0", "192.0.2.2"), ("eth0", "fe80::2")]),
+                support(true, false, false),
+            ),
+            (
+                "IPv6 only, with a ULA",
+                machine(&[("eth0", "fd00::2"), ("eth0", "2001:db8::2")]),
+                support(false, false, true),
+            ),
+            (
+                "a ULA is not global",
+                machine(&[("eth0", "fd00::2"), ("eth0", "fe80::2")]),
+                support(false, false, false),
+            ),
+            (
+                "behind CGNAT",
+                machine(&[("wwan0", "100.64.12.7"), ("wwan0", "2001:db8:1::7")]),
+                support(true, true, true),
+            ),
+            (
+                "CGNAT and a public address",
+                machine(&[("wwan0", "100.127.255.254"), ("eth0", "198.51.100.2")]),
+                support(true, false, false),
+            ),
+            (
+                "just outside the shared range",
+                machine(&[("eth0", "100.128.0.1")]),
+                support(true, false, false),
+            ),
+            (
+                "only an IPv4 link-local address",
+                machine(&[("eth0", "169.254.7.7")]),
+                support(false, false, false),
+            ),
+            (
+                "addresses on down interfaces",
+                machine(&[("eth0-down", "192.0.2.2"), ("eth0-down", "2001:db8::2")]),
+                support(false, false, false),
+            ),
+        ];
+        for (name, ifaces, expected) in cases {
+            assert_eq!(classify(&ifaces, &[]), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn deprecated_and_temporary_addresses_do_not_count() {
+        let ifaces = machine(&[("eth0", "2001:db8::2"), ("eth0", "2001:db8::7e57")]);
+        let deprecated: Ipv6Addr = "2001:db8::2".parse().unwrap();
+        let temporary: Ipv6Addr = "2001:db8::7e57".parse().unwrap();
+        assert!(classify(&ifaces, &[deprecated]).global_ipv6);
+        assert!(!classify(&ifaces, &[deprecated, temporary]).global_ipv6);
+    }
+
+    #[test]
+    fn entries_without_addresses_are_ignored() {
+        let mut ifaces = lo();
+        let mut link = Interface::new("eth0", Kind::Packet);
+        link.flags = InterfaceFlags::UP;
+        ifaces.push(link);
+        assert_eq!(classify(&ifaces, &[]), StackSupport::default());
+    }
+
+    #[test]
+    fn this_machine_is_classified() {
+        let support = stack_support().unwrap();
+        assert_eq!(has_ipv4().unwrap(), support.ipv4);
+        assert_eq!(has_global_ipv6().unwrap(), support.global_ipv6);
+        assert!(support.ipv4 || !support.ipv4_cgnat);
+    }
+}
@@ -0,0 +1,23 @@
+//! The OS's own interface lists, for the fields the rest of the crate does
+//! not expose yet.
+//!
+//! Thin, safe wrappers: an owned list that is freed on drop, and views of
+//! its entries with accessors for the common fields and `raw()` for the
+//! rest. [`ifaces`](crate::ifaces) is built on the same lists.
+//!
+//! **Not covered by semver.** The raw structures follow the C headers and
+//! change with what the crate needs from them; pin an exact version when
+//! depending on this module.
+//!
+//! - unix: [`IfAddrs`], the `getifaddrs` list, and [`sockaddr_from_bytes`].
+//! - Windows: [`AdapterList`], the `GetAdaptersAddresses` buffer.
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use crate::ffi::union_ifa_ifu;
+#[cfg(all(
+    target_family = "unix",
+    not(any(target_os = "redox", target_os = "espidf"))
+))]
+pub use crate::ffi::{ifaddrs, sockaddr_from_bytes, IfAddr, IfAddrs, IfAddrsIter};
+#[cfg(target_family = "windows")]
+pub use crate::ffi::{Adapter, AdapterList, Adapters, IpAdapterAddresses};
@@ -0,0 +1,66 @@
+/// Whether RFC 8981 temporary ("privacy") IPv6 addresses are made, as
+/// Linux's `use_tempaddr` and the BSD `use_tempaddr`/`prefer_tempaddr`
+/// sysctls have it.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum TempAddrs {
+    /// Not generated. Also what loopback and point-to-point links report
+    /// with -1.
+    Disabled,
+    /// Generated, but the public address is preferred for outgoing
+    /// connections.
+    Enabled,
+    /// Generated and preferred.
+    Preferred,
+}
+
+impl From<i64> for TempAddrs {
+    /// The Linux `use_tempaddr` value: 0 or less, 1, or 2 and up.
+    fn from(value: i64) -> TempAddrs {
+        match value {
+            i64::MIN..=0 => TempAddrs::Disabled,
+            1 => TempAddrs::Enabled,
+            _ => TempAddrs::Preferred,
+        }
+    }
+}
+
+impl From<TempAddrs> for i64 {
+    fn from(value: TempAddrs) -> i64 {
+        match value {
+            TempAddrs::Disabled => 0,
+            TempAddrs::Enabled => 1,
+            TempAddrs::Preferred => 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn use_tempaddr_values_map_to_modes() {
+        let values = [
+            (i64::MIN, TempAddrs::Disabled),
+            (-1, TempAddrs::Disabled),
+            (0, TempAddrs::Disabled),
+            (1, TempAddrs::Enabled),
+            (2, TempAddrs::Preferred),
+            (3, TempAddrs::Preferred),
+        ];
+        for (value, mode) in values {
+            assert_eq!(TempAddrs::from(value), mode, "{}", value);
+        }
+    }
+
+    #[test]
+    fn modes_round_trip() {
+        for mode in [
+            TempAddrs::Disabled,
+            TempAddrs::Enabled,
+            TempAddrs::Preferred,
+        ] {
+            assert_eq!(TempAddrs::from(i64::from(mode)), mode);
+        }
+    }
+}
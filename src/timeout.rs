@@ -0,0 +1,175 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::Interface;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The helper thread's queue, `None` until the first call.
+static WORKER: OnceLock<Mutex<Option<mpsc::Sender<Job>>>> = OnceLock::new();
+
+/// [`ifaces`](crate::ifaces), giving up with `ErrorKind::TimedOut` if it
+/// takes longer than `timeout`.
+///
+/// The enumeration runs on a helper thread that is started on the first
+/// call and kept for the later ones. An enumeration that times out is not
+/// cancelled, the OS call cannot be interrupted; it runs to completion on
+/// the helper thread and its result is thrown away. Calls made while it is
+/// still stuck queue up behind it, and time out too unless it finishes in
+/// their time; calls that have already timed out are skipped when their
+/// turn comes.
+pub fn ifaces_with_timeout(timeout: Duration) -> io::Result<Vec<Interface>> {
+    run_with_timeout(timeout, crate::ifaces)
+}
+
+fn run_with_timeout<T, F>(timeout: Duration, f: F) -> io::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+{
+    let (reply, result) = mpsc::channel();
+    let abandoned = Arc::new(AtomicBool::new(false));
+    let skip = abandoned.clone();
+    submit(Box::new(move || {
+        if !skip.load(Ordering::Relaxed) {
+            let _ = reply.send(f());
+        }
+    }))?;
+    match result.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => {
+            abandoned.store(true, Ordering::Relaxed);
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "enumerating the interfaces timed out",
+            ))
+        }
+        Err(RecvTimeoutError::Disconnected) => Err(io::Error::other("enumeration panicked")),
+    }
+}
+
+/// Hand `job` to the helper thread, starting it if there is none or the
+/// last one died to a panic.
+fn submit(mut job: Job) -> io::Result<()> {
+    let mut worker = WORKER
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(sender) = worker.as_ref() {
+        match sender.send(job) {
+            Ok(()) => return Ok(()),
+            Err(mpsc::SendError(returned)) => job = returned,
+        }
+    }
+    let (sender, jobs) = mpsc::channel::<Job>();
+    thread::Builder::new()
+        .name("ifaces-enumerate".to_owned())
+        .spawn(move || {
+            for job in jobs {
+                job();
+            }
+        })?;
+    // The receiver lives until the thread exits, so this cannot fail.
+    let _ = sender.send(job);
+    *worker = Some(sender);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread::ThreadId;
+    use std::time::Instant;
+
+    /// The helper thread is shared, so the tests take turns with it.
+    static HELPER: Mutex<()> = Mutex::new(());
+
+    fn turn() -> std::sync::MutexGuard<'static, ()> {
+        HELPER
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn helper_id() -> io::Result<ThreadId> {
+        run_with_timeout(Duration::from_secs(5), || Ok(thread::current().id()))
+    }
+
+    #[test]
+    fn slow_enumerations_time_out() {
+        let _turn = turn();
+        let start = Instant::now();
+        let err = run_with_timeout(Duration::from_millis(50), || {
+            thread::sleep(Duration::from_millis(500));
+            Ok(())
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_millis(400));
+        // The helper is free again once the stuck enumeration is done.
+        helper_id().unwrap();
+    }
+
+    #[test]
+    fn the_helper_thread_is_kept_for_the_next_call() {
+        let _turn = turn();
+        let first = helper_id().unwrap();
+        assert_ne!(first, thread::current().id());
+        assert_eq!(helper_id().unwrap(), first);
+    }
+
+    #[test]
+    fn calls_that_timed_out_are_skipped() {
+        let _turn = turn();
+        let stuck = run_with_timeout(Duration::from_millis(20), || {
+            thread::sleep(Duration::from_millis(300));
+            Ok(())
+        });
+        assert_eq!(stuck.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        let ran = Arc::new(AtomicBool::new(false));
+        let flag = ran.clone();
+        let queued = run_with_timeout(Duration::from_millis(20), move || {
+            flag.store(true, Ordering::Relaxed);
+            Ok(())
+        });
+        assert_eq!(queued.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        // Runs after both of them had their turn.
+        helper_id().unwrap();
+        assert!(!ran.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn the_helper_thread_is_started_again_after_a_panic() {
+        let _turn = turn();
+        let before = helper_id().unwrap();
+        let err = run_with_timeout(Duration::from_secs(5), || -> io::Result<()> {
+            panic!("enumeration failed")
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "enumeration panicked");
+        // A call made while the old thread unwinds can still reach it and
+        // be dropped with its queue.
+        let after = (0..50)
+            .find_map(|_| {
+                helper_id().ok().or_else(|| {
+                    thread::sleep(Duration::from_millis(10));
+                    None
+                })
+            })
+            .unwrap();
+        assert_ne!(after, before);
+        assert_eq!(helper_id().unwrap(), after);
+    }
+
+    #[test]
+    fn this_machine() {
+        let _turn = turn();
+        assert!(!ifaces_with_timeout(Duration::from_secs(10))
+            .unwrap()
+            .is_empty());
+    }
+}
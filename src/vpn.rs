@@ -0,0 +1,308 @@
+//! Telling VPN interfaces apart, for [`Interface::is_vpn`].
+
+use crate::Interface;
+
+/// Which rule made [`Interface::vpn_evidence`] take an interface for a VPN.
+#[non_exhaustive]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum VpnEvidence {
+    /// Linux: the netlink link kind, e.g. `wireguard`.
+    LinkKind(&'static str),
+    /// Linux: the name starts with a prefix VPN software uses, e.g. `wg`,
+    /// for links whose kind netlink does not tell.
+    NamePrefix(&'static str),
+    /// Windows: the `TUNNEL_TYPE_*` of the adapter.
+    TunnelType(u32),
+    /// Windows: the `IF_TYPE_*` of the adapter, PPP or tunnel.
+    InterfaceType(u32),
+    /// Windows: the adapter description has this in it, e.g. `TAP-Windows`.
+    Description(&'static str),
+    /// macOS: SystemConfiguration has the interface as the one of the VPN
+    /// service with this id.
+    VpnService(String),
+}
+
+// The rules of every platform are built for the tests, so that they all
+// run wherever the tests do.
+#[cfg(any(target_os = "linux", target_os = "android", test))]
+mod linux {
+    use super::VpnEvidence;
+
+    // `tun` covers tap devices too, they share the link kind.
+    const LINK_KINDS: &[&str] = &["wireguard", "tun", "ppp"];
+    const NAME_PREFIXES: &[&str] = &["tun", "tap", "wg", "ppp"];
+
+    /// The first rule `name` and `link_kind` match. The name only counts
+    /// when the kind is unknown.
+    pub(crate) fn evidence(name: &str, link_kind: Option<&str>) -> Option<VpnEvidence> {
+        if let Some(kind) = link_kind {
+            return LINK_KINDS
+                .iter()
+                .find(|&&known| known == kind)
+                .map(|&kind| VpnEvidence::LinkKind(kind));
+        }
+        NAME_PREFIXES
+            .iter()
+            .find(|&&prefix| name.starts_with(prefix))
+            .map(|&prefix| VpnEvidence::NamePrefix(prefix))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn vpn_link_kinds_match() {
+            let cases = [
+                ("wg0", "wireguard"),
+                ("tun0", "tun"),
+                // A tap device, whatever its name.
+                ("vpn-office", "tun"),
+                ("ppp0", "ppp"),
+            ];
+            for (name, kind) in cases {
+                assert_eq!(
+                    evidence(name, Some(kind)),
+                    Some(VpnEvidence::LinkKind(kind)),
+                    "{}",
+                    name
+                );
+            }
+        }
+
+        #[test]
+        fn other_link_kinds_do_not_match_by_name() {
+            // A known kind decides, whatever the name.
+            assert_eq!(evidence("wg0", Some("bridge")), None);
+            assert_eq!(evidence("tun-bond", Some("bond")), None);
+            assert_eq!(evidence("eth0", Some("veth")), None);
+        }
+
+        #[test]
+        fn names_match_without_a_kind() {
+            assert_eq!(evidence("wg0", None), Some(VpnEvidence::NamePrefix("wg")));
+            assert_eq!(evidence("tap3", None), Some(VpnEvidence::NamePrefix("tap")));
+            assert_eq!(evidence("tun0", None), Some(VpnEvidence::NamePrefix("tun")));
+            assert_eq!(evidence("ppp1", None), Some(VpnEvidence::NamePrefix("ppp")));
+            assert_eq!(evidence("eth0", None), None);
+            assert_eq!(evidence("lo", None), None);
+        }
+    }
+}
+
+#[cfg(any(target_family = "windows", test))]
+mod windows {
+    use super::VpnEvidence;
+
+    // The IPv6 transition tunnels, 6to4, ISATAP and Teredo, carry traffic
+    // in the clear and are never taken for VPNs.
+    const TRANSITION_TUNNEL_TYPES: &[u32] = &[11, 13, 14];
+    // TUNNEL_TYPE_OTHER, TUNNEL_TYPE_DIRECT and TUNNEL_TYPE_IPHTTPS.
+    const VPN_TUNNEL_TYPES: &[u32] = &[1, 2, 15];
+    // IF_TYPE_PPP and IF_TYPE_TUNNEL.
+    const VPN_IF_TYPES: &[u32] = &[23, 131];
+    // Compared without regard to case.
+    const VPN_DESCRIPTIONS: &[&str] =
+        &["TAP-Windows", "Wintun", "WireGuard", "WAN Miniport", "VPN"];
+
+    /// The first rule an adapter with these types and description
+    /// matches.
+    pub(crate) fn evidence(
+        tunnel_type: u32,
+        if_type: u32,
+        description: &str,
+    ) -> Option<VpnEvidence> {
+        if TRANSITION_TUNNEL_TYPES.contains(&tunnel_type) {
+            return None;
+        }
+        if VPN_TUNNEL_TYPES.contains(&tunnel_type) {
+            return Some(VpnEvidence::TunnelType(tunnel_type));
+        }
+        if VPN_IF_TYPES.contains(&if_type) {
+            return Some(VpnEvidence::InterfaceType(if_type));
+        }
+        let description = description.to_lowercase();
+        VPN_DESCRIPTIONS
+            .iter()
+            .find(|known| description.contains(&known.to_lowercase()))
+            .map(|&known| VpnEvidence::Description(known))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        // IF_TYPE_ETHERNET_CSMACD and IF_TYPE_IEEE80211.
+        const ETHERNET: u32 = 6;
+        const WIFI: u32 = 71;
+
+        #[test]
+        fn transition_tunnels_are_never_vpns() {
+            for tunnel_type in [11, 13, 14] {
+                assert_eq!(
+                    evidence(tunnel_type, 131, "Teredo Tunneling Pseudo-Interface"),
+                    None
+                );
+            }
+            // Whatever the description.
+            assert_eq!(evidence(13, 131, "Microsoft ISATAP Adapter #2 VPN"), None);
+        }
+
+        #[test]
+        fn vpn_tunnel_types_match_first() {
+            assert_eq!(
+                evidence(15, 131, "IP-HTTPS Platform Interface"),
+                Some(VpnEvidence::TunnelType(15))
+            );
+            assert_eq!(evidence(1, 131, ""), Some(VpnEvidence::TunnelType(1)));
+            assert_eq!(evidence(2, ETHERNET, ""), Some(VpnEvidence::TunnelType(2)));
+        }
+
+        #[test]
+        fn ppp_and_tunnel_adapters_match() {
+            assert_eq!(
+                evidence(0, 23, "WAN Miniport (PPPOE)"),
+                Some(VpnEvidence::InterfaceType(23))
+            );
+            assert_eq!(evidence(0, 131, ""), Some(VpnEvidence::InterfaceType(131)));
+        }
+
+        #[test]
+        fn vpn_drivers_match_by_description() {
+            let cases = [
+                ("TAP-Windows Adapter V9", "TAP-Windows"),
+                ("Wintun Userspace Tunnel", "Wintun"),
+                ("WireGuard Tunnel", "WireGuard"),
+                ("WAN Miniport (IKEv2)", "WAN Miniport"),
+                ("Fortinet SSL VPN Virtual Ethernet Adapter", "VPN"),
+                // Without regard to case.
+                ("tap-windows adapter v9", "TAP-Windows"),
+            ];
+            for (description, known) in cases {
+                assert_eq!(
+                    evidence(0, ETHERNET, description),
+                    Some(VpnEvidence::Description(known)),
+                    "{}",
+                    description
+                );
+            }
+        }
+
+        #[test]
+        fn ordinary_adapters_do_not_match() {
+            assert_eq!(
+                evidence(0, ETHERNET, "Intel(R) Ethernet Connection I219-V"),
+                None
+            );
+            assert_eq!(evidence(0, WIFI, "Intel(R) Wi-Fi 6 AX201 160MHz"), None);
+            assert_eq!(evidence(0, 24, "Software Loopback Interface 1"), None);
+        }
+    }
+}
+
+#[cfg(any(target_os = "macos", test))]
+mod darwin {
+    use super::VpnEvidence;
+
+    // The interfaces VPN services bring up.
+    const NAME_PREFIXES: &[&str] = &["utun", "ipsec", "ppp"];
+
+    /// The rule: one of the interfaces VPN services use, and in `services`,
+    /// the interface names and ids of the VPN services that are up.
+    pub(crate) fn evidence(name: &str, services: &[(String, String)]) -> Option<VpnEvidence> {
+        if !NAME_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+            return None;
+        }
+        services
+            .iter()
+            .find(|(iface, _)| iface == name)
+            .map(|(_, service)| VpnEvidence::VpnService(service.clone()))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn services() -> Vec<(String, String)> {
+            vec![
+                (
+                    "utun3".to_owned(),
+                    "8F2C1E3A-0B4D-4A57-9E21-6C1D2B3A4F50".to_owned(),
+                ),
+                (
+                    "ppp0".to_owned(),
+                    "1A2B3C4D-0000-4000-8000-000000000001".to_owned(),
+                ),
+                // A service on an interface VPNs do not bring up.
+                (
+                    "en0".to_owned(),
+                    "1A2B3C4D-0000-4000-8000-000000000002".to_owned(),
+                ),
+            ]
+        }
+
+        #[test]
+        fn vpn_interfaces_of_vpn_services_match() {
+            assert_eq!(
+                evidence("utun3", &services()),
+                Some(VpnEvidence::VpnService(
+                    "8F2C1E3A-0B4D-4A57-9E21-6C1D2B3A4F50".to_owned()
+                ))
+            );
+            assert_eq!(
+                evidence("ppp0", &services()),
+                Some(VpnEvidence::VpnService(
+                    "1A2B3C4D-0000-4000-8000-000000000001".to_owned()
+                ))
+            );
+        }
+
+        #[test]
+        fn other_interfaces_do_not_match() {
+            // The utun interfaces iCloud Private Relay and the system keep.
+            assert_eq!(evidence("utun0", &services()), None);
+            assert_eq!(evidence("ipsec0", &[]), None);
+            // Not a VPN interface, whatever the service.
+            assert_eq!(evidence("en0", &services()), None);
+        }
+    }
+}
+
+pub(crate) fn evidence(iface: &Interface) -> Option<VpnEvidence> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    return linux::evidence(&iface.name, iface.os.link_kind.as_deref());
+    #[cfg(target_family = "windows")]
+    return windows::evidence(
+        iface.os.tunnel_type,
+        iface.os.if_type,
+        iface.os.strings.description.as_str(),
+    );
+    #[cfg(target_os = "macos")]
+    return darwin::evidence(&iface.name, &crate::ffi::vpn_services());
+    #[allow(unreachable_code)]
+    {
+        let _ = iface;
+        None
+    }
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use crate::{Interface, Kind, OsSpecific, VpnEvidence};
+
+    fn link(name: &str, link_kind: Option<&str>) -> Interface {
+        Interface::new(name, Kind::Link).with_os_specific(OsSpecific {
+            link_kind: link_kind.map(str::to_owned),
+            ..OsSpecific::default()
+        })
+    }
+
+    #[test]
+    fn interfaces_report_the_rule_they_match() {
+        let wg = link("wg0", Some("wireguard"));
+        assert!(wg.is_vpn());
+        assert_eq!(wg.vpn_evidence(), Some(VpnEvidence::LinkKind("wireguard")));
+        assert!(!link("eth0", None).is_vpn());
+        assert!(!link("tun0", Some("veth")).is_vpn());
+    }
+}
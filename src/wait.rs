@@ -0,0 +1,572 @@
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::signature::signature;
+use crate::Interface;
+
+/// How often [`wait_for`] enumerates again when it is polling.
+///
+/// With the `watch` feature it re-enumerates as soon as the kernel reports a
+/// change instead, and only polls if change notifications are unavailable.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Block until an entry matching `predicate` shows up, or `timeout` passes.
+///
+/// The current interfaces are checked first, so a condition that already
+/// holds returns straight away. `Ok(None)` means the timeout passed.
+pub fn wait_for<P>(predicate: P, timeout: Duration) -> io::Result<Option<Interface>>
+where
+    P: Fn(&Interface) -> bool,
+{
+    // Subscribed before the first enumeration, so no change slips through
+    // between the two.
+    let mut changes = Changes::new();
+    wait_with(
+        crate::ifaces,
+        |remaining| changes.wait(remaining),
+        predicate,
+        timeout,
+    )
+}
+
+/// [`wait_for`] over any source of snapshots and of change waits.
+fn wait_with<E, W, P>(
+    mut enumerate: E,
+    mut wait: W,
+    predicate: P,
+    timeout: Duration,
+) -> io::Result<Option<Interface>>
+where
+    E: FnMut() -> io::Result<Vec<Interface>>,
+    W: FnMut(Duration) -> io::Result<()>,
+    P: Fn(&Interface) -> bool,
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(found) = enumerate()?.into_iter().find(|iface| predicate(iface)) {
+            return Ok(Some(found));
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(None);
+        }
+        wait(remaining)?;
+    }
+}
+
+/// Block until an interface called `name` exists, or `timeout` passes.
+///
+/// A name the platform would never give an interface fails straight away
+/// with `ErrorKind::InvalidInput`, see [`validate_name`](crate::validate_name).
+pub fn wait_for_interface(name: &str, timeout: Duration) -> io::Result<Option<Interface>> {
+    let name = crate::validate_name(name)?;
+    wait_for(|iface| name.matches(&iface.name), timeout)
+}
+
+/// What [`await_stable`] returns.
+#[derive(Debug, Clone)]
+pub struct StableSnapshot {
+    /// The interfaces at the end of the wait.
+    pub ifaces: Vec<Interface>,
+    /// Whether `max_wait` passed before the configuration settled, so that
+    /// `ifaces` may still be changing.
+    pub timed_out: bool,
+}
+
+/// Block until the network configuration has stopped changing for
+/// `quiet_period`, or `max_wait` passes, e.g. before starting work that
+/// should not run while interfaces come up during boot.
+///
+/// A change is one [`network_signature`](crate::network_signature) sees,
+/// so counters and the like do not count. Change notifications wake the
+/// wait early with the `watch` feature; otherwise it polls every
+/// [`POLL_INTERVAL`]. Returns the latest interfaces either way.
+pub fn await_stable(quiet_period: Duration, max_wait: Duration) -> io::Result<StableSnapshot> {
+    let mut changes = Changes::new();
+    stable_with(
+        crate::ifaces,
+        |remaining| changes.wait(remaining),
+        quiet_period,
+        max_wait,
+    )
+}
+
+/// [`await_stable`] over any source of snapshots and of change waits.
+fn stable_with<E, W>(
+    mut enumerate: E,
+    mut wait: W,
+    quiet_period: Duration,
+    max_wait: Duration,
+) -> io::Result<StableSnapshot>
+where
+    E: FnMut() -> io::Result<Vec<Interface>>,
+    W: FnMut(Duration) -> io::Result<()>,
+{
+    let deadline = Instant::now() + max_wait;
+    let mut ifaces = enumerate()?;
+    let mut current = signature(&ifaces);
+    let mut quiet_since = Instant::now();
+    loop {
+        let now = Instant::now();
+        let settled = quiet_since + quiet_period;
+        if now >= settled || now >= deadline {
+            return Ok(StableSnapshot {
+                ifaces,
+                timed_out: now < settled,
+            });
+        }
+        wait(settled.min(deadline) - now)?;
+        ifaces = enumerate()?;
+        let next = signature(&ifaces);
+        if next != current {
+            current = next;
+            quiet_since = Instant::now();
+        }
+    }
+}
+
+/// What [`wait_for_change`] saw change. Only a hint, enumerate to find out
+/// the details.
+#[cfg(feature = "watch")]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ChangeHint {
+    /// Addresses were added or removed.
+    Addresses,
+    /// Interfaces came, went, or changed state.
+    Links,
+    /// Something else, or several of the above.
+    Unknown,
+}
+
+/// Block until the network configuration changes, or `timeout` passes,
+/// `None` waiting forever. `Ok(None)` means the timeout passed.
+///
+/// The subscription only lives for the call, so changes from before it
+/// are not seen; callers that must not miss any should enumerate after
+/// it returns and compare. On Windows every change is `Addresses`, since
+/// that is all `NotifyAddrChange` reports.
+#[cfg(feature = "watch")]
+pub fn wait_for_change(timeout: Option<Duration>) -> io::Result<Option<ChangeHint>> {
+    let mut watcher = crate::ffi::Watcher::new()?;
+    change_with(|remaining| watcher.wait(remaining), timeout)
+}
+
+/// [`wait_for_change`] over any source of waits.
+#[cfg(feature = "watch")]
+fn change_with<W>(mut wait: W, timeout: Option<Duration>) -> io::Result<Option<ChangeHint>>
+where
+    W: FnMut(Option<Duration>) -> io::Result<Option<ChangeHint>>,
+{
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    loop {
+        let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        if let Some(hint) = wait(remaining)? {
+            return Ok(Some(hint));
+        }
+        // Interrupted waits come back early, go on until the deadline.
+        if remaining.is_some_and(|remaining| remaining.is_zero()) {
+            return Ok(None);
+        }
+    }
+}
+
+/// A change subscription for an event loop the caller already runs, in
+/// place of a thread blocked in [`wait_for_change`].
+///
+/// Register the handle with epoll, kqueue or `poll` on unix, through
+/// `AsRawFd`/`AsFd`, or with `WaitForMultipleObjects` on Windows, through
+/// `AsRawHandle`, and call [`drain_events`](ChangeWatcher::drain_events)
+/// when it signals.
+///
+/// On unix the handle is a netlink or routing socket, readable while
+/// messages are queued, so level-triggered registration works as is.
+/// `drain_events` reads until the socket is empty, which is what
+/// edge-triggered registration (`EPOLLET`, `EV_CLEAR`) needs as well. On
+/// Windows it is an auto-reset event, signalled once per notification; no
+/// further notification comes until `drain_events` re-arms it.
+#[cfg(feature = "watch")]
+pub struct ChangeWatcher {
+    watcher: crate::ffi::Watcher,
+}
+
+#[cfg(feature = "watch")]
+impl ChangeWatcher {
+    /// Subscribe. Fails with `ErrorKind::Unsupported` on platforms without
+    /// change notifications.
+    pub fn new() -> io::Result<ChangeWatcher> {
+        Ok(ChangeWatcher {
+            watcher: crate::ffi::Watcher::new()?,
+        })
+    }
+
+    /// What changed since the last call, one hint per notification,
+    /// without blocking. Empty if nothing did, e.g. after a spurious
+    /// wakeup.
+    ///
+    /// An `Unknown` may also mean the kernel dropped notifications because
+    /// the queue was full, `ENOBUFS` on the socket. Changes were lost then,
+    /// so enumerate everything again rather than act on the other hints.
+    pub fn drain_events(&mut self) -> io::Result<Vec<ChangeHint>> {
+        self.watcher.drain_events()
+    }
+
+    /// Block until a notification arrives, or `timeout` passes, `None`
+    /// waiting forever, for callers without an event loop. Everything
+    /// queued is drained and comes back as a single hint, `Unknown` if
+    /// they differ. `Ok(None)` means the timeout passed or the wait was
+    /// interrupted by a signal.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<Option<ChangeHint>> {
+        self.watcher.wait(timeout)
+    }
+}
+
+#[cfg(all(
+    feature = "watch",
+    target_family = "unix",
+    not(any(target_os = "redox", target_os = "espidf"))
+))]
+impl std::os::unix::io::AsRawFd for ChangeWatcher {
+    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+        self.watcher.as_raw_fd()
+    }
+}
+
+#[cfg(all(
+    feature = "watch",
+    target_family = "unix",
+    not(any(target_os = "redox", target_os = "espidf"))
+))]
+impl std::os::unix::io::AsFd for ChangeWatcher {
+    fn as_fd(&self) -> std::os::unix::io::BorrowedFd<'_> {
+        self.watcher.as_fd()
+    }
+}
+
+#[cfg(all(feature = "watch", target_family = "windows"))]
+impl std::os::windows::io::AsRawHandle for ChangeWatcher {
+    fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
+        self.watcher.as_raw_handle()
+    }
+}
+
+/// Something to block on until the interfaces may have changed.
+pub(crate) struct Changes {
+    #[cfg(feature = "watch")]
+    watcher: Option<crate::ffi::Watcher>,
+}
+
+impl Changes {
+    pub(crate) fn new() -> Changes {
+        Changes {
+            #[cfg(feature = "watch")]
+            watcher: match crate::ffi::Watcher::new() {
+                Ok(watcher) => Some(watcher),
+                Err(err) => {
+                    log::debug!("change notifications unavailable, polling: {}", err);
+                    None
+                }
+            },
+        }
+    }
+
+    pub(crate) fn wait(&mut self, timeout: Duration) -> io::Result<()> {
+        #[cfg(feature = "watch")]
+        if let Some(watcher) = &mut self.watcher {
+            return watcher.wait(Some(timeout)).map(|_| ());
+        }
+        std::thread::sleep(timeout.min(POLL_INTERVAL));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    use crate::Kind;
+
+    /// Snapshots that gain `eth0` once `waits` changes have been waited
+    /// for, with `waited` counting them.
+    fn snapshot(waited: &Cell<u32>, waits: u32) -> io::Result<Vec<Interface>> {
+        let mut ifaces = vec![Interface::new("lo", Kind::Ipv4)];
+        if waited.get() >= waits {
+            ifaces.push(Interface::new("eth0", Kind::Ipv4));
+        }
+        Ok(ifaces)
+    }
+
+    fn change(waited: &Cell<u32>) -> io::Result<()> {
+        waited.set(waited.get() + 1);
+        Ok(())
+    }
+
+    #[test]
+    fn satisfied_conditions_do_not_wait() {
+        let waited = Cell::new(0);
+        let found = wait_with(
+            || snapshot(&waited, 0),
+            |_| change(&waited),
+            |iface| iface.name == "eth0",
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert_eq!(found.map(|iface| iface.name), Some("eth0".to_owned()));
+        assert_eq!(waited.get(), 0);
+    }
+
+    #[test]
+    fn changes_are_checked_as_they_come() {
+        let waited = Cell::new(0);
+        let found = wait_with(
+            || snapshot(&waited, 3),
+            |_| change(&waited),
+            |iface| iface.name == "eth0",
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert_eq!(found.map(|iface| iface.name), Some("eth0".to_owned()));
+        assert_eq!(waited.get(), 3);
+    }
+
+    #[test]
+    fn waits_end_at_the_timeout() {
+        let timeout = Duration::from_millis(50);
+        let start = Instant::now();
+        let found = wait_with(
+            || snapshot(&Cell::new(0), 1),
+            |remaining| {
+                assert!(remaining <= timeout);
+                std::thread::sleep(remaining);
+                Ok(())
+            },
+            |iface| iface.name == "eth0",
+            timeout,
+        )
+        .unwrap();
+        assert!(found.is_none());
+        assert!(start.elapsed() >= timeout);
+    }
+
+    #[test]
+    fn errors_end_the_wait() {
+        let err = wait_with(
+            || Err(io::Error::from(io::ErrorKind::PermissionDenied)),
+            |_| Ok(()),
+            |_| true,
+            Duration::from_secs(5),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        let err = wait_with(
+            || Ok(Vec::new()),
+            |_| Err(io::Error::from(io::ErrorKind::BrokenPipe)),
+            |_| true,
+            Duration::from_secs(5),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn existing_interfaces_are_found_straight_away() {
+        let first = crate::ifaces().unwrap().remove(0);
+        let start = Instant::now();
+        let found = wait_for(|iface| iface.name == first.name, Duration::from_secs(5)).unwrap();
+        assert_eq!(found.map(|iface| iface.name), Some(first.name));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn missing_interfaces_time_out() {
+        let found = wait_for_interface("nosuchif0", Duration::from_millis(50)).unwrap();
+        assert!(found.is_none());
+    }
+
+    #[test]
+    fn invalid_names_fail_straight_away() {
+        let err = wait_for_interface("", Duration::from_secs(5)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    /// Waits of at most `step`, after each of which the snapshot gains an
+    /// entry until `changes` have been seen, and the MTU changes anyway.
+    fn churn(
+        step: Duration,
+        changes: u32,
+    ) -> (
+        impl FnMut() -> io::Result<Vec<Interface>>,
+        impl FnMut(Duration) -> io::Result<()>,
+    ) {
+        let waited = std::rc::Rc::new(Cell::new(0));
+        let counted = waited.clone();
+        let enumerate = move || {
+            let waits = waited.get();
+            let mut ifaces: Vec<_> = (0..=waits.min(changes))
+                .map(|i| Interface::new(&format!("eth{}", i), Kind::Ipv4))
+                .collect();
+            // Not a change.
+            ifaces[0].mtu = Some(1500 + waits);
+            Ok(ifaces)
+        };
+        let wait = move |remaining: Duration| {
+            std::thread::sleep(remaining.min(step));
+            change(&counted)
+        };
+        (enumerate, wait)
+    }
+
+    #[test]
+    fn stable_configurations_return_after_the_quiet_period() {
+        let quiet = Duration::from_millis(30);
+        let (enumerate, wait) = churn(Duration::from_millis(10), 0);
+        let start = Instant::now();
+        let stable = stable_with(enumerate, wait, quiet, Duration::from_secs(5)).unwrap();
+        assert!(!stable.timed_out);
+        assert_eq!(stable.ifaces.len(), 1);
+        assert!(start.elapsed() >= quiet);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn no_quiet_period_returns_straight_away() {
+        let waits = Cell::new(0);
+        let stable = stable_with(
+            || snapshot(&waits, 0),
+            |_| change(&waits),
+            Duration::ZERO,
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert!(!stable.timed_out);
+        assert_eq!(waits.get(), 0);
+    }
+
+    #[test]
+    fn changes_restart_the_quiet_period() {
+        let step = Duration::from_millis(10);
+        let quiet = Duration::from_millis(50);
+        let (enumerate, wait) = churn(step, 4);
+        let start = Instant::now();
+        let stable = stable_with(enumerate, wait, quiet, Duration::from_secs(5)).unwrap();
+        assert!(!stable.timed_out);
+        // The snapshot after the last change.
+        assert_eq!(stable.ifaces.len(), 5);
+        assert!(start.elapsed() >= step * 4 + quiet);
+    }
+
+    #[test]
+    fn churn_times_out_with_the_latest_snapshot() {
+        let max_wait = Duration::from_millis(50);
+        let (enumerate, wait) = churn(Duration::from_millis(5), u32::MAX);
+        let start = Instant::now();
+        let stable = stable_with(enumerate, wait, Duration::from_secs(5), max_wait).unwrap();
+        assert!(stable.timed_out);
+        assert!(stable.ifaces.len() > 1);
+        assert!(start.elapsed() >= max_wait);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn errors_end_the_stable_wait() {
+        let err = stable_with(
+            || Ok(Vec::new()),
+            |_| Err(io::Error::from(io::ErrorKind::BrokenPipe)),
+            Duration::from_secs(1),
+            Duration::from_secs(5),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn this_machine_settles() {
+        let stable = await_stable(Duration::ZERO, Duration::from_secs(1)).unwrap();
+        assert!(!stable.timed_out);
+        assert!(!stable.ifaces.is_empty());
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn changes_end_the_wait() {
+        let waits = Cell::new(0);
+        let hint = change_with(
+            |remaining| {
+                waits.set(waits.get() + 1);
+                assert!(remaining.unwrap() <= Duration::from_secs(5));
+                Ok(Some(ChangeHint::Links))
+            },
+            Some(Duration::from_secs(5)),
+        )
+        .unwrap();
+        assert_eq!((hint, waits.get()), (Some(ChangeHint::Links), 1));
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn spurious_wakeups_wait_on() {
+        // Interrupted waits come back empty before the deadline.
+        let waits = Cell::new(0);
+        let hint = change_with(
+            |_| {
+                waits.set(waits.get() + 1);
+                Ok((waits.get() == 3).then_some(ChangeHint::Addresses))
+            },
+            Some(Duration::from_secs(5)),
+        )
+        .unwrap();
+        assert_eq!((hint, waits.get()), (Some(ChangeHint::Addresses), 3));
+        // And without one.
+        let waits = Cell::new(0);
+        let hint = change_with(
+            |remaining| {
+                assert_eq!(remaining, None);
+                waits.set(waits.get() + 1);
+                Ok((waits.get() == 3).then_some(ChangeHint::Unknown))
+            },
+            None,
+        )
+        .unwrap();
+        assert_eq!(hint, Some(ChangeHint::Unknown));
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn change_waits_end_at_the_timeout() {
+        let timeout = Duration::from_millis(50);
+        let start = Instant::now();
+        let hint = change_with(
+            |remaining| {
+                std::thread::sleep(remaining.unwrap().min(Duration::from_millis(10)));
+                Ok(None)
+            },
+            Some(timeout),
+        )
+        .unwrap();
+        assert_eq!(hint, None);
+        assert!(start.elapsed() >= timeout);
+        let err = change_with(|_| Err(io::Error::from(io::ErrorKind::BrokenPipe)), None);
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn one_shot_waits_time_out() {
+        let start = Instant::now();
+        let hint = wait_for_change(Some(Duration::from_millis(50))).unwrap();
+        // Something else on the machine may change meanwhile.
+        assert!(hint.is_none() || start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[cfg(all(feature = "watch", any(target_os = "linux", target_os = "android")))]
+    #[test]
+    fn watcher_waits_time_out() {
+        let mut watcher = ChangeWatcher::new().unwrap();
+        let start = Instant::now();
+        let hint = watcher.wait(Some(Duration::from_millis(50))).unwrap();
+        // Something else on the machine may change meanwhile.
+        assert!(hint.is_none() || start.elapsed() < Duration::from_millis(50));
+        let _ = watcher.drain_events().unwrap();
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt;
+
+/// What happened to an entry a [`Warning`] is about.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum WarningAction {
+    /// The entry was left out of the result.
+    Skipped,
+    /// The entry was kept, with the field left empty.
+    Defaulted,
+}
+
+/// Something about one entry of the platform's interface list that could
+/// not be used, see [`ifaces_with_warnings`](crate::ifaces_with_warnings).
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Warning {
+    /// The interface name, with anything that is not UTF-8 replaced.
+    pub interface: String,
+    /// The position of the entry in the platform's list: the `getifaddrs`
+    /// entry on unix, the unicast address of the adapter on Windows.
+    pub entry: usize,
+    /// The field that could not be read, e.g. `"addr"` or `"index"`.
+    pub field: &'static str,
+    pub action: WarningAction,
+    pub reason: String,
+}
+
+impl Warning {
+    pub(crate) fn new(
+        interface: impl Into<String>,
+        entry: usize,
+        field: &'static str,
+        action: WarningAction,
+        reason: impl Into<String>,
+    ) -> Warning {
+        Warning {
+            interface: interface.into(),
+            entry,
+            field,
+            action,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let action = match self.action {
+            WarningAction::Skipped => "skipped",
+            WarningAction::Defaulted => "left out",
+        };
+        write!(
+            f,
+            "{} entry {}: {} {}: {}",
+            self.interface, self.entry, action, self.field, self.reason
+        )
+    }
+}
@@ -0,0 +1,256 @@
+//! WireGuard device details, without `wg show`.
+
+use std::io::{self, BufRead, BufReader, Write};
+
+use crate::ValidName;
+
+mod x25519;
+
+/// What [`wireguard_info`] tells about a WireGuard interface.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct WgInfo {
+    /// The UDP port the device listens on, 0 before it picked one.
+    pub listen_port: u16,
+    /// All zeros while the device has no private key.
+    pub public_key: [u8; 32],
+    pub peers: usize,
+}
+
+/// The listen port, public key and peer count of WireGuard interface
+/// `name`.
+///
+/// On Linux the kernel module is asked over generic netlink. Interfaces it
+/// does not know, and every interface on macOS and Windows, are looked for
+/// on the userspace API socket (UAPI) that `wireguard-go` serves:
+/// `/var/run/wireguard/<name>.sock`, or the
+/// `\\.\pipe\ProtectedPrefix\Administrators\WireGuard\<name>` named pipe.
+/// On macOS `name` is the `utun` interface, the one [`ifaces`] lists, not
+/// the `wg-quick` name. The WireGuard for Windows app, which runs the
+/// kernel driver, does not serve the named pipe.
+///
+/// Both need administrator rights: `CAP_NET_ADMIN` for netlink, or access
+/// to the socket or pipe. Not having them is `ErrorKind::PermissionDenied`,
+/// and an interface that is not a WireGuard device, or does not exist,
+/// `ErrorKind::NotFound`.
+///
+/// [`ifaces`]: crate::ifaces
+pub fn wireguard_info(name: &str) -> io::Result<WgInfo> {
+    let name = crate::validate_name(name)?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    match crate::ffi::wg_device(&name) {
+        Err(err)
+            if matches!(
+                err.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::InvalidInput
+            ) =>
+        {
+            // A wireguard-go device is a tun link, and so of another kind.
+            return match uapi(&name) {
+                Err(uapi_err) if uapi_err.kind() == io::ErrorKind::NotFound => {
+                    Err(io::Error::new(io::ErrorKind::NotFound, err))
+                }
+                ret => ret,
+            };
+        }
+        ret => return ret,
+    }
+    #[allow(unreachable_code)]
+    uapi(&name)
+}
+
+/// Ask the `wireguard-go` of `name` over its UAPI socket.
+fn uapi(name: &ValidName) -> io::Result<WgInfo> {
+    #[cfg(target_family = "unix")]
+    let stream =
+        std::os::unix::net::UnixStream::connect(format!("/var/run/wireguard/{}.sock", name));
+    #[cfg(target_family = "windows")]
+    let stream = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(format!(
+            r"\\.\pipe\ProtectedPrefix\Administrators\WireGuard\{}",
+            name
+        ));
+    let mut stream = stream?;
+    stream.write_all(b"get=1\n\n")?;
+    parse_uapi(BufReader::new(stream))
+}
+
+/// Decode the reply to a UAPI `get=1`, up to the blank line that ends it.
+///
+/// The reply gives the private key only, so the public key is computed
+/// from it. Each `public_key` line starts a peer.
+fn parse_uapi<R: BufRead>(reader: R) -> io::Result<WgInfo> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let mut info = WgInfo::default();
+    for line in reader.lines() {
+        let line = line?;
+        if line.is_empty() {
+            return Ok(info);
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("malformed UAPI line {:?}", line)))?;
+        match key {
+            "private_key" => {
+                let private =
+                    hex_key(value).ok_or_else(|| invalid("malformed private key".into()))?;
+                if private != [0; 32] {
+                    info.public_key = x25519::public_key(&private);
+                }
+            }
+            "listen_port" => {
+                info.listen_port = value
+                    .parse()
+                    .map_err(|_| invalid(format!("malformed listen port {:?}", value)))?
+            }
+            "public_key" => info.peers += 1,
+            "errno" if value != "0" => {
+                return Err(io::Error::other(format!(
+                    "wireguard-go reported errno {}",
+                    value
+                )))
+            }
+            _ => {}
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "UAPI reply ended early",
+    ))
+}
+
+/// A key in the 64 hex digits UAPI writes them in.
+fn hex_key(hex: &str) -> Option<[u8; 32]> {
+    let hex = hex.as_bytes();
+    // `from_str_radix` also takes a sign.
+    if hex.len() != 64 || !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    let mut key = [0; 32];
+    for (byte, pair) in key.iter_mut().zip(hex.chunks(2)) {
+        let digits = std::str::from_utf8(pair).ok()?;
+        *byte = u8::from_str_radix(digits, 16).ok()?;
+    }
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `get=1` reply of the cross-platform UAPI documentation.
+    const REPLY: &str = "\
+private_key=e84b5a6d2717c1003a13b431570353dbaca9146cf150c5f8575680feba52027a
+listen_port=12912
+public_key=b85996fecc9c7f1fc6d2572a76eda11d59bcd20be8e543b15ce4bd85a8e75a33
+preshared_key=188515093e952f5f22e865cef3012e72f8b5f0b598ac0309d5dacce3b70fcf52
+allowed_ip=192.168.4.4/32
+endpoint=[abcd:23::33%2]:51820
+public_key=58402e695ba1772b1cc9309755f043251ea77fdcf10fbe63989ceb7e19321376
+tx_bytes=38333
+rx_bytes=2224
+allowed_ip=192.168.4.6/32
+persistent_keepalive_interval=111
+endpoint=182.122.22.19:3233
+public_key=662e14fd594556f522604703340351258903b64f35553763f19426ab2a515c58
+endpoint=5.152.198.39:51820
+allowed_ip=192.168.4.10/32
+allowed_ip=192.168.4.11/32
+tx_bytes=1212111
+rx_bytes=1929999999
+protocol_version=1
+errno=0
+
+";
+
+    fn parse(reply: &str) -> io::Result<WgInfo> {
+        parse_uapi(reply.as_bytes())
+    }
+
+    #[test]
+    fn replies_are_decoded() {
+        assert_eq!(
+            parse(REPLY).unwrap(),
+            WgInfo {
+                listen_port: 12912,
+                public_key: hex_key(
+                    "c1532e1b3d3508fc7ebc354fa679620f33f287149542e684c67b7b0d81362b29"
+                )
+                .unwrap(),
+                peers: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn devices_without_a_key_or_peers_are_zeros() {
+        let reply = "\
+private_key=0000000000000000000000000000000000000000000000000000000000000000
+listen_port=0
+errno=0
+
+";
+        assert_eq!(parse(reply).unwrap(), WgInfo::default());
+        // A device without a private key has no such line at all.
+        assert_eq!(parse("errno=0\n\n").unwrap(), WgInfo::default());
+    }
+
+    #[test]
+    fn the_blank_line_ends_the_reply() {
+        let info = parse(&format!("{}public_key=garbage\n", REPLY)).unwrap();
+        assert_eq!(info.peers, 3);
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let err = parse("errno=19\n\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert_eq!(err.to_string(), "wireguard-go reported errno 19");
+
+        let kind = |reply: &str| parse(reply).unwrap_err().kind();
+        assert_eq!(kind("listen_port=12912\n"), io::ErrorKind::UnexpectedEof);
+        assert_eq!(kind(""), io::ErrorKind::UnexpectedEof);
+        assert_eq!(kind("listen_port\n\n"), io::ErrorKind::InvalidData);
+        assert_eq!(kind("listen_port=65536\n\n"), io::ErrorKind::InvalidData);
+        assert_eq!(kind("private_key=e84b\n\n"), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn keys_are_64_hex_digits() {
+        let key = hex_key("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1F");
+        let mut expected = [0; 32];
+        for (i, byte) in expected.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        assert_eq!(key, Some(expected));
+        assert_eq!(hex_key(""), None);
+        assert_eq!(hex_key(&"0".repeat(63)), None);
+        assert_eq!(hex_key(&"0".repeat(66)), None);
+        assert_eq!(hex_key(&"g".repeat(64)), None);
+        // Signs are not digits.
+        assert_eq!(hex_key(&"+1".repeat(32)), None);
+        assert_eq!(hex_key(&"é".repeat(32)), None);
+    }
+
+    #[test]
+    fn other_interfaces_are_not_found() {
+        let lo = crate::ifaces()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.flags.contains(crate::InterfaceFlags::LOOPBACK))
+            .unwrap();
+        assert_eq!(
+            wireguard_info(&lo.name).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            wireguard_info("ifaces-no-wg0").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            wireguard_info("").unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Just enough X25519 (RFC 7748) to get a public key from a private one,
+//! since the userspace protocol only reports the latter.
+//!
+//! Field elements are sixteen 16-bit limbs in `i64`s, as in TweetNaCl.
+//! This is constant-time in the key, like the original, but not fast; it
+//! runs once per query.
+
+type Fe = [i64; 16];
+
+const A24: Fe = [0xdb41, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+fn carry(o: &mut Fe) {
+    for i in 0..16 {
+        o[i] += 1 << 16;
+        let c = o[i] >> 16;
+        match i {
+            15 => o[0] += 38 * (c - 1),
+            _ => o[i + 1] += c - 1,
+        }
+        o[i] -= c << 16;
+    }
+}
+
+/// Swap `p` and `q` if `b` is 1, without branching on it.
+fn swap(p: &mut Fe, q: &mut Fe, b: i64) {
+    let mask = !(b - 1);
+    for i in 0..16 {
+        let t = mask & (p[i] ^ q[i]);
+        p[i] ^= t;
+        q[i] ^= t;
+    }
+}
+
+fn pack(n: &Fe) -> [u8; 32] {
+    let mut t = *n;
+    carry(&mut t);
+    carry(&mut t);
+    carry(&mut t);
+    for _ in 0..2 {
+        let mut m: Fe = [0; 16];
+        m[0] = t[0] - 0xffed;
+        for i in 1..15 {
+            m[i] = t[i] - 0xffff - ((m[i - 1] >> 16) & 1);
+            m[i - 1] &= 0xffff;
+        }
+        m[15] = t[15] - 0x7fff - ((m[14] >> 16) & 1);
+        let b = (m[15] >> 16) & 1;
+        m[14] &= 0xffff;
+        swap(&mut t, &mut m, 1 - b);
+    }
+    let mut out = [0; 32];
+    for i in 0..16 {
+        out[2 * i] = t[i] as u8;
+        out[2 * i + 1] = (t[i] >> 8) as u8;
+    }
+    out
+}
+
+fn unpack(n: &[u8; 32]) -> Fe {
+    let mut o: Fe = [0; 16];
+    for i in 0..16 {
+        o[i] = n[2 * i] as i64 + ((n[2 * i + 1] as i64) << 8);
+    }
+    o[15] &= 0x7fff;
+    o
+}
+
+fn add(a: &Fe, b: &Fe) -> Fe {
+    let mut o = *a;
+    o.iter_mut().zip(b).for_each(|(o, b)| *o += b);
+    o
+}
+
+fn sub(a: &Fe, b: &Fe) -> Fe {
+    let mut o = *a;
+    o.iter_mut().zip(b).for_each(|(o, b)| *o -= b);
+    o
+}
+
+fn mul(a: &Fe, b: &Fe) -> Fe {
+    let mut t = [0i64; 31];
+    for i in 0..16 {
+        for j in 0..16 {
+            t[i + j] += a[i] * b[j];
+        }
+    }
+    for i in 0..15 {
+        t[i] += 38 * t[i + 16];
+    }
+    let mut o: Fe = [0; 16];
+    o.copy_from_slice(&t[..16]);
+    carry(&mut o);
+    carry(&mut o);
+    o
+}
+
+fn square(a: &Fe) -> Fe {
+    mul(a, a)
+}
+
+/// `a` to the power p - 2, its inverse.
+fn invert(a: &Fe) -> Fe {
+    let mut c = *a;
+    for i in (0..=253).rev() {
+        c = square(&c);
+        if i != 2 && i != 4 {
+            c = mul(&c, a);
+        }
+    }
+    c
+}
+
+/// `scalar` times the point with u-coordinate `point`.
+fn scalarmult(scalar: &[u8; 32], point: &[u8; 32]) -> [u8; 32] {
+    let mut z = *scalar;
+    z[31] = (z[31] & 127) | 64;
+    z[0] &= 248;
+    let x = unpack(point);
+    let (mut a, mut b, mut c, mut d): (Fe, Fe, Fe, Fe) = ([0; 16], x, [0; 16], [0; 16]);
+    a[0] = 1;
+    d[0] = 1;
+    for i in (0..=254).rev() {
+        let bit = ((z[i >> 3] >> (i & 7)) & 1) as i64;
+        swap(&mut a, &mut b, bit);
+        swap(&mut c, &mut d, bit);
+        let e = add(&a, &c);
+        a = sub(&a, &c);
+        c = add(&b, &d);
+        b = sub(&b, &d);
+        d = square(&e);
+        let f = square(&a);
+        a = mul(&c, &a);
+        c = mul(&b, &e);
+        let e = add(&a, &c);
+        a = sub(&a, &c);
+        b = square(&a);
+        c = sub(&d, &f);
+        a = mul(&c, &A24);
+        a = add(&a, &d);
+        c = mul(&c, &a);
+        a = mul(&d, &f);
+        d = mul(&b, &x);
+        b = square(&e);
+        swap(&mut a, &mut b, bit);
+        swap(&mut c, &mut d, bit);
+    }
+    pack(&mul(&a, &invert(&c)))
+}
+
+/// The public key of `private`.
+pub fn public_key(private: &[u8; 32]) -> [u8; 32] {
+    let mut base = [0; 32];
+    base[0] = 9;
+    scalarmult(private, &base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(hex: &str) -> [u8; 32] {
+        super::super::hex_key(hex).unwrap()
+    }
+
+    #[test]
+    fn scalarmult_matches_rfc_7748() {
+        // Section 5.2, the first test vector.
+        assert_eq!(
+            scalarmult(
+                &key("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4"),
+                &key("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c"),
+            ),
+            key("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552")
+        );
+    }
+
+    #[test]
+    fn public_keys_match_rfc_7748() {
+        // Section 6.1, Alice and Bob.
+        let pairs = [
+            (
+                "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+                "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a",
+            ),
+            (
+                "5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb",
+                "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f",
+            ),
+        ];
+        for (private, public) in pairs {
+            assert_eq!(public_key(&key(private)), key(public), "{}", private);
+        }
+    }
+
+    #[test]
+    fn private_keys_are_clamped() {
+        let private = key("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let mut unclamped = private;
+        unclamped[0] |= 7;
+        unclamped[31] |= 0x80;
+        unclamped[31] &= !0x40;
+        assert_ne!(unclamped, private);
+        assert_eq!(public_key(&unclamped), public_key(&private));
+    }
+}
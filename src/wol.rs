@@ -0,0 +1,28 @@
+use bitflags::bitflags;
+
+bitflags! {
+    /// Wake-on-LAN triggers, with the bits of the Linux `WAKE_*` constants.
+    #[derive(Default)]
+    pub struct WolModes: u32 {
+        /// Any activity on the PHY.
+        const PHY = 0x1;
+        const UNICAST = 0x2;
+        const MULTICAST = 0x4;
+        const BROADCAST = 0x8;
+        const ARP = 0x10;
+        /// The magic packet: the MAC address, 16 times over.
+        const MAGIC = 0x20;
+        /// The magic packet followed by the SecureOn password.
+        const MAGIC_SECURE = 0x40;
+        /// Packets matching a filter set up with `ethtool -N`.
+        const FILTER = 0x80;
+    }
+}
+
+/// Which Wake-on-LAN triggers an interface has, see
+/// [`config::wol`](crate::config::wol).
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct WolInfo {
+    pub supported: WolModes,
+    pub enabled: WolModes,
+}
@@ -0,0 +1,139 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::Interface;
+
+impl Interface {
+    /// The reverse DNS zone of the network this address is on.
+    ///
+    /// IPv4 prefixes on an octet boundary give the matching `in-addr.arpa`
+    /// zone, e.g. `2.0.192.in-addr.arpa` for `192.0.2.1/24`. Prefixes longer
+    /// than /24 give the RFC 2317 delegation name, `0/25.2.0.192.in-addr.arpa`
+    /// for `192.0.2.1/25`. Other IPv4 prefixes span several zones and give
+    /// `None`. IPv6 prefixes are rounded down to a nibble, so `/56` and `/58`
+    /// both give 14 labels under `ip6.arpa`.
+    ///
+    /// `None` as well when the address or a contiguous mask is missing.
+    pub fn reverse_zone(&self) -> Option<String> {
+        let len = self.prefix_len()?;
+        match self.addr?.ip() {
+            IpAddr::V4(addr) => v4_zone(addr, len),
+            IpAddr::V6(addr) => Some(v6_zone(addr, len)),
+        }
+    }
+}
+
+fn v4_zone(addr: Ipv4Addr, len: u8) -> Option<String> {
+    let octets = addr.octets();
+    let labels = |count: usize| {
+        let mut zone = String::new();
+        for octet in octets[..count].iter().rev() {
+            zone.push_str(&format!("{}.", octet));
+        }
+        zone + "in-addr.arpa"
+    };
+
+    match len {
+        25..=31 => {
+            let network = octets[3] & (0xff << (32 - len));
+            Some(format!("{}/{}.{}", network, len, labels(3)))
+        }
+        _ if len % 8 == 0 => Some(labels(len as usize / 8)),
+        _ => None,
+    }
+}
+
+fn v6_zone(addr: Ipv6Addr, len: u8) -> String {
+    let mut zone = String::new();
+    let nibbles = len as usize / 4;
+    for i in (0..nibbles).rev() {
+        let octet = addr.octets()[i / 2];
+        let nibble = match i % 2 {
+            0 => octet >> 4,
+            _ => octet & 0xf,
+        };
+        zone.push_str(&format!("{:x}.", nibble));
+    }
+    zone + "ip6.arpa"
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::*;
+    use crate::Kind;
+
+    fn entry(addr: &str, len: u32) -> Interface {
+        let ip: IpAddr = addr.parse().unwrap();
+        let (kind, mask) = match ip {
+            IpAddr::V4(_) => {
+                let mask = u32::MAX.checked_shl(32 - len).unwrap_or(0);
+                (Kind::Ipv4, IpAddr::V4(mask.into()))
+            }
+            IpAddr::V6(_) => {
+                let mask = u128::MAX.checked_shl(128 - len).unwrap_or(0);
+                (Kind::Ipv6, IpAddr::V6(mask.into()))
+            }
+        };
+        let mut iface = Interface::new("eth0", kind);
+        iface.addr = Some(SocketAddr::new(ip, 0));
+        iface.mask = Some(SocketAddr::new(mask, 0));
+        iface
+    }
+
+    #[test]
+    fn zones() {
+        let table: &[(&str, u32, Option<&str>)] = &[
+            ("10.1.2.3", 8, Some("10.in-addr.arpa")),
+            ("172.16.5.4", 12, None),
+            ("172.16.5.4", 16, Some("16.172.in-addr.arpa")),
+            ("192.0.2.1", 24, Some("2.0.192.in-addr.arpa")),
+            ("192.0.2.1", 25, Some("0/25.2.0.192.in-addr.arpa")),
+            ("192.0.2.200", 25, Some("128/25.2.0.192.in-addr.arpa")),
+            ("192.0.2.77", 30, Some("76/30.2.0.192.in-addr.arpa")),
+            ("192.0.2.1", 32, Some("1.2.0.192.in-addr.arpa")),
+            (
+                "2001:db8:abcd::1",
+                48,
+                Some("d.c.b.a.8.b.d.0.1.0.0.2.ip6.arpa"),
+            ),
+            (
+                "2001:db8:abcd:1200::1",
+                56,
+                Some("2.1.d.c.b.a.8.b.d.0.1.0.0.2.ip6.arpa"),
+            ),
+            // Rounded down to /56.
+            (
+                "2001:db8:abcd:12c0::1",
+                58,
+                Some("2.1.d.c.b.a.8.b.d.0.1.0.0.2.ip6.arpa"),
+            ),
+            (
+                "2001:db8:abcd:1234::1",
+                64,
+                Some("4.3.2.1.d.c.b.a.8.b.d.0.1.0.0.2.ip6.arpa"),
+            ),
+            ("::1", 0, Some("ip6.arpa")),
+        ];
+        for &(addr, len, zone) in table {
+            assert_eq!(
+                entry(addr, len).reverse_zone().as_deref(),
+                zone,
+                "{}/{}",
+                addr,
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn entries_without_a_network_have_no_zone() {
+        let mut iface = entry("192.0.2.1", 24);
+        iface.mask = None;
+        assert_eq!(iface.reverse_zone(), None);
+        // Not contiguous.
+        iface.mask = Some("255.0.255.0:0".parse().unwrap());
+        assert_eq!(iface.reverse_zone(), None);
+        assert_eq!(Interface::new("eth0", Kind::Link).reverse_zone(), None);
+    }
+}
@@ -0,0 +1,44 @@
+//! Setting and reading back the alias of a bridge made for the test.
+//! Needs root or `CAP_NET_ADMIN`, and passes without changing anything if
+//! the bridge cannot be made.
+
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use std::io;
+
+use ifaces::config::{alias, create_bridge, delete_bridge, set_alias};
+
+const BRIDGE: &str = "ifalias0";
+
+fn enumerated_alias() -> Option<String> {
+    ifaces::ifaces()
+        .unwrap()
+        .into_iter()
+        .find(|iface| iface.name == BRIDGE)
+        .and_then(|iface| iface.os_specific().alias.clone())
+}
+
+#[test]
+fn aliases_round_trip() {
+    match create_bridge(BRIDGE) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("not privileged, skipping: {}", err);
+            return;
+        }
+        result => result.unwrap(),
+    };
+    let before = alias(BRIDGE);
+    let set = set_alias(BRIDGE, "uplink to rack 4");
+    let after = alias(BRIDGE);
+    let enumerated = enumerated_alias();
+    let longest = set_alias(BRIDGE, &"x".repeat(255)).and_then(|_| alias(BRIDGE));
+    let cleared = set_alias(BRIDGE, "").and_then(|_| alias(BRIDGE));
+    delete_bridge(BRIDGE).unwrap();
+
+    assert_eq!(before.unwrap(), None);
+    set.unwrap();
+    assert_eq!(after.unwrap().as_deref(), Some("uplink to rack 4"));
+    assert_eq!(enumerated.as_deref(), Some("uplink to rack 4"));
+    assert_eq!(longest.unwrap(), Some("x".repeat(255)));
+    assert_eq!(cleared.unwrap(), None);
+}
@@ -0,0 +1,60 @@
+//! Binding to the addresses `to_bind_addr` gives for the interfaces of the
+//! machine running the tests.
+
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+
+use ifaces::InterfaceFlags;
+
+/// Bind to `addr`, `None` if the family is switched off on this machine.
+fn bind(addr: SocketAddr) -> Option<UdpSocket> {
+    match UdpSocket::bind(addr) {
+        Ok(socket) => Some(socket),
+        Err(err) if err.kind() == io::ErrorKind::AddrNotAvailable && addr.is_ipv6() => None,
+        Err(err) => panic!("binding {} failed: {}", addr, err),
+    }
+}
+
+#[test]
+fn loopback_addresses_can_be_bound() {
+    let ifaces = ifaces::ifaces().unwrap();
+    let loopback = |v6: bool| {
+        ifaces.iter().find(|iface| {
+            iface.flags.contains(InterfaceFlags::LOOPBACK)
+                && iface.addr.is_some_and(|addr| addr.is_ipv6() == v6)
+        })
+    };
+
+    let v4 = loopback(false).unwrap().to_bind_addr(0).unwrap();
+    let socket = bind(v4).unwrap();
+    assert_eq!(socket.local_addr().unwrap().ip(), v4.ip());
+
+    // Containers and hosts with IPv6 switched off have no ::1.
+    if let Some(lo) = loopback(true) {
+        let v6 = lo.to_bind_addr(0).unwrap();
+        if let Some(socket) = bind(v6) {
+            assert_eq!(socket.local_addr().unwrap().ip(), v6.ip());
+        }
+    }
+}
+
+#[test]
+fn link_local_addresses_can_be_bound() {
+    let ifaces = ifaces::ifaces().unwrap();
+    let link_local = ifaces.iter().filter(|iface| {
+        matches!(iface.addr, Some(SocketAddr::V6(addr)) if addr.ip().segments()[0] & 0xffc0 == 0xfe80)
+    });
+    for iface in link_local {
+        let addr = iface.to_bind_addr(0).unwrap();
+        let socket = match UdpSocket::bind(addr) {
+            Ok(socket) => socket,
+            // Still going through duplicate address detection.
+            Err(err) if err.kind() == io::ErrorKind::AddrNotAvailable => continue,
+            Err(err) => panic!("binding {} on {} failed: {}", addr, iface.name, err),
+        };
+        match socket.local_addr().unwrap() {
+            SocketAddr::V6(local) => assert_eq!(local.scope_id(), iface.index.unwrap()),
+            SocketAddr::V4(_) => unreachable!(),
+        }
+    }
+}
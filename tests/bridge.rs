@@ -0,0 +1,83 @@
+//! Adding a port made for the test to a bridge made for the test, and
+//! reading the master back from `ifaces`. Needs root or `CAP_NET_ADMIN`
+//! and iproute2 for the port, and passes without changing anything if
+//! either is missing.
+
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use std::io;
+use std::process::Command;
+
+use ifaces::config::{bridge_add_port, bridge_remove_port, create_bridge, delete_bridge};
+
+const BRIDGE: &str = "ifbridge0";
+const OTHER: &str = "ifbridge1";
+const PORT: &str = "ifbrport0";
+
+/// Whether `ip` with `args`, split at the spaces, succeeds.
+fn ip(args: &str) -> bool {
+    Command::new("ip")
+        .args(args.split(' '))
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// A dummy link called `PORT`, or one end of a veth pair where there is no
+/// dummy driver.
+fn add_port() -> bool {
+    ip("link add ifbrport0 type dummy") || ip("link add ifbrport0 type veth peer name ifbrpeer0")
+}
+
+fn master() -> Option<u32> {
+    ifaces::ifaces()
+        .unwrap()
+        .into_iter()
+        .find(|iface| iface.name == PORT)
+        .and_then(|iface| iface.os_specific().master)
+}
+
+#[test]
+fn ports_are_added_and_removed() {
+    let bridge = match create_bridge(BRIDGE) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("not privileged, skipping: {}", err);
+            return;
+        }
+        result => result.unwrap(),
+    };
+    if !add_port() {
+        delete_bridge(BRIDGE).unwrap();
+        eprintln!("cannot make a port with `ip link add`, skipping");
+        return;
+    }
+    let added = bridge_add_port(BRIDGE, PORT);
+    let master_after_add = master();
+    let added_again = bridge_add_port(BRIDGE, PORT);
+    let other = create_bridge(OTHER);
+    let busy = bridge_add_port(OTHER, PORT);
+    let master_after_busy = master();
+    let not_a_bridge = bridge_add_port(PORT, OTHER);
+    let removed = bridge_remove_port(BRIDGE, PORT);
+    let master_after_remove = master();
+    let removed_again = bridge_remove_port(BRIDGE, PORT);
+    assert!(ip("link del ifbrport0"));
+    if other.is_ok() {
+        delete_bridge(OTHER).unwrap();
+    }
+    delete_bridge(BRIDGE).unwrap();
+
+    added.unwrap();
+    assert_eq!(master_after_add, Some(bridge));
+    added_again.unwrap();
+    other.unwrap();
+    assert_eq!(busy.unwrap_err().kind(), io::ErrorKind::ResourceBusy);
+    assert_eq!(master_after_busy, Some(bridge));
+    assert_eq!(
+        not_a_bridge.unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+    removed.unwrap();
+    assert_eq!(master_after_remove, None);
+    assert_eq!(removed_again.unwrap_err().kind(), io::ErrorKind::NotFound);
+}
@@ -0,0 +1,55 @@
+//! Duplicate address detection on a bridge made for the test, with `ip`
+//! adding the addresses. Needs root or `CAP_NET_ADMIN` and iproute2, and
+//! passes without changing anything if the bridge cannot be made.
+
+#![cfg(target_os = "linux")]
+
+use std::io;
+use std::process::Command;
+use std::time::Duration;
+
+use ifaces::config::{create_bridge, delete_bridge};
+use ifaces::{wait_for_dad, DadOutcome};
+
+const BRIDGE: &str = "ifdad0";
+
+fn ip(args: &[&str]) {
+    let output = Command::new("ip").args(args).output().unwrap();
+    assert!(
+        output.status.success(),
+        "ip {:?}: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn detection_outcomes_are_reported() {
+    match create_bridge(BRIDGE) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("not privileged, skipping: {}", err);
+            return;
+        }
+        result => result.unwrap(),
+    };
+    if Command::new("ip").arg("-V").output().is_err() {
+        delete_bridge(BRIDGE).unwrap();
+        eprintln!("no iproute2, skipping");
+        return;
+    }
+    // The bridge stays down, so detection for the second address does not
+    // even start.
+    ip(&["addr", "add", "2001:db8:48::1/64", "dev", BRIDGE, "nodad"]);
+    ip(&["addr", "add", "2001:db8:48::2/64", "dev", BRIDGE]);
+    let short = Duration::from_millis(100);
+    let skipped = wait_for_dad(BRIDGE, "2001:db8:48::1".parse().unwrap(), short);
+    let tentative = wait_for_dad(BRIDGE, "2001:db8:48::2".parse().unwrap(), short);
+    let missing = wait_for_dad(BRIDGE, "2001:db8:48::3".parse().unwrap(), short);
+    delete_bridge(BRIDGE).unwrap();
+
+    assert_eq!(skipped.unwrap(), DadOutcome::Preferred);
+    assert_eq!(tentative.unwrap(), DadOutcome::TimedOut);
+    assert_eq!(missing.unwrap(), DadOutcome::TimedOut);
+    let err = wait_for_dad("ifdad-none", "2001:db8:48::1".parse().unwrap(), short).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
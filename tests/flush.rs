@@ -0,0 +1,93 @@
+//! Flushing the addresses of a bridge made for the test, with `ip` adding
+//! them. Needs root or `CAP_NET_ADMIN` and iproute2, and passes without
+//! changing anything if the bridge cannot be made.
+
+#![cfg(target_os = "linux")]
+
+use std::collections::BTreeSet;
+use std::io;
+use std::net::IpAddr;
+use std::process::Command;
+
+use ifaces::config::{create_bridge, delete_bridge, flush_addresses};
+use ifaces::Kind;
+
+const BRIDGE: &str = "ifflush0";
+
+fn ip(args: &[&str]) {
+    let output = Command::new("ip").args(args).output().unwrap();
+    assert!(
+        output.status.success(),
+        "ip {:?}: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+fn addrs() -> BTreeSet<IpAddr> {
+    ifaces::ifaces()
+        .unwrap()
+        .into_iter()
+        .filter(|iface| iface.name == BRIDGE)
+        .filter_map(|iface| Some(iface.addr?.ip()))
+        .collect()
+}
+
+fn set(addrs: &[&str]) -> BTreeSet<IpAddr> {
+    addrs.iter().map(|addr| addr.parse().unwrap()).collect()
+}
+
+#[test]
+fn every_address_is_flushed() {
+    match create_bridge(BRIDGE) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("not privileged, skipping: {}", err);
+            return;
+        }
+        result => result.unwrap(),
+    };
+    if Command::new("ip").arg("-V").output().is_err() {
+        delete_bridge(BRIDGE).unwrap();
+        eprintln!("no iproute2, skipping");
+        return;
+    }
+    // A primary IPv4 address with a secondary in its network, so the
+    // secondary has to go first.
+    for addr in [
+        "198.51.100.1/24",
+        "198.51.100.2/24",
+        "203.0.113.1/28",
+        "2001:db8:42::1/64",
+        "fe80::42/64",
+    ] {
+        ip(&["addr", "add", addr, "dev", BRIDGE]);
+    }
+    let before = addrs();
+    let v4 = flush_addresses(BRIDGE, Some(Kind::Ipv4), false);
+    let after_v4 = addrs();
+    let v6 = flush_addresses(BRIDGE, None, true);
+    let after_v6 = addrs();
+    let all = flush_addresses(BRIDGE, None, false);
+    let after_all = addrs();
+    delete_bridge(BRIDGE).unwrap();
+
+    assert_eq!(
+        before,
+        set(&[
+            "198.51.100.1",
+            "198.51.100.2",
+            "203.0.113.1",
+            "2001:db8:42::1",
+            "fe80::42"
+        ])
+    );
+    let v4 = v4.unwrap();
+    assert!(v4.all_removed(), "{:?}", v4);
+    assert_eq!(v4.results.len(), 3);
+    assert_eq!(after_v4, set(&["2001:db8:42::1", "fe80::42"]));
+    let v6 = v6.unwrap();
+    assert!(v6.all_removed(), "{:?}", v6);
+    assert_eq!(after_v6, set(&["fe80::42"]));
+    assert!(all.unwrap().all_removed());
+    assert!(after_all.is_empty());
+}
@@ -0,0 +1,68 @@
+//! Putting a bridge made for the test in a link group with `ip link set
+//! group` and reading the group back. Needs root or `CAP_NET_ADMIN` and
+//! iproute2, and passes without changing anything if the bridge cannot be
+//! made.
+
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use std::io;
+use std::process::Command;
+
+use ifaces::config::{create_bridge, delete_bridge};
+use ifaces::InterfaceFilter;
+
+const BRIDGE: &str = "ifgroup0";
+
+fn enumerated_group() -> Option<u32> {
+    ifaces::ifaces()
+        .unwrap()
+        .into_iter()
+        .find(|iface| iface.name == BRIDGE)
+        .and_then(|iface| iface.group())
+}
+
+fn set_group(group: u32) -> io::Result<()> {
+    let status = Command::new("ip")
+        .args(["link", "set", "dev", BRIDGE, "group", &group.to_string()])
+        .status()?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(io::Error::other(format!("ip link set group: {}", status))),
+    }
+}
+
+fn filtered(filter: &InterfaceFilter) -> bool {
+    ifaces::ifaces()
+        .unwrap()
+        .iter()
+        .any(|iface| iface.name == BRIDGE && filter.matches(iface))
+}
+
+#[test]
+fn groups_round_trip() {
+    match create_bridge(BRIDGE) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("not privileged, skipping: {}", err);
+            return;
+        }
+        result => result.unwrap(),
+    };
+    let before = enumerated_group();
+    let set = set_group(7);
+    let after = enumerated_group();
+    let in_7 = filtered(&InterfaceFilter::new().group(7));
+    let in_0 = filtered(&InterfaceFilter::new().group(0));
+    delete_bridge(BRIDGE).unwrap();
+
+    match set {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            eprintln!("no iproute2, skipping: {}", err);
+            return;
+        }
+        result => result.unwrap(),
+    }
+    assert_eq!(before, Some(0));
+    assert_eq!(after, Some(7));
+    assert!(in_7);
+    assert!(!in_0);
+}
@@ -0,0 +1,59 @@
+//! `interface_of_socket` on sockets bound to loopback.
+
+#![cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_family = "windows"
+))]
+
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+
+use ifaces::{interface_of_socket, InterfaceFlags};
+
+#[test]
+fn ipv4_loopback_sockets_are_on_loopback() {
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let iface = interface_of_socket(&socket).unwrap().unwrap();
+    assert!(iface.flags.contains(InterfaceFlags::LOOPBACK));
+    assert_eq!(
+        iface.addr.map(|addr| addr.ip()),
+        Some(Ipv4Addr::LOCALHOST.into())
+    );
+}
+
+#[test]
+fn ipv6_loopback_sockets_are_on_loopback() {
+    let socket = match UdpSocket::bind((Ipv6Addr::LOCALHOST, 0)) {
+        Ok(socket) => socket,
+        Err(err) if err.kind() == io::ErrorKind::AddrNotAvailable => {
+            eprintln!("no ::1, skipping: {}", err);
+            return;
+        }
+        Err(err) => panic!("{}", err),
+    };
+    let iface = interface_of_socket(&socket).unwrap().unwrap();
+    assert!(iface.flags.contains(InterfaceFlags::LOOPBACK));
+    assert_eq!(
+        iface.addr.map(|addr| addr.ip()),
+        Some(Ipv6Addr::LOCALHOST.into())
+    );
+}
+
+#[test]
+fn wildcard_sockets_have_no_interface() {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+    assert!(interface_of_socket(&socket).unwrap().is_none());
+}
+
+#[test]
+fn connected_sockets_are_on_the_interface_of_their_route() {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+    socket
+        .connect(SocketAddr::from((Ipv4Addr::LOCALHOST, 9)))
+        .unwrap();
+    let iface = interface_of_socket(&socket).unwrap().unwrap();
+    assert!(iface.flags.contains(InterfaceFlags::LOOPBACK));
+}
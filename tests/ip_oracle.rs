@@ -0,0 +1,357 @@
+//! Compare `ifaces()` against `ip -j addr` on Linux.
+//!
+//! The comparison with the machine running the tests needs iproute2 and
+//! only runs with `IFACES_IP_ORACLE=1`:
+//!
+//! `IFACES_IP_ORACLE=1 cargo test --test ip_oracle`
+//!
+//! The parsing and comparison run over the captured outputs in
+//! `tests/fixtures/ip-addr` either way.
+//!
+//! Names, addresses, prefix lengths, scope ids and the UP, BROADCAST,
+//! LOOPBACK, POINTOPOINT and MULTICAST flags are compared. Some differences
+//! are expected and not reported:
+//!
+//! - addresses `ip` marks `deprecated`, which some releases of the crate
+//!   leave out,
+//! - IPv4 addresses with a label (`eth0:1`), which `getifaddrs` reports
+//!   under the label, so they are looked for under it,
+//! - `RUNNING` and `LOWER_UP`, which are not the same flag,
+//! - links without addresses, which `ip` lists and `ifaces` has no address
+//!   entry for.
+
+#![cfg(target_os = "linux")]
+
+use std::collections::BTreeSet;
+use std::net::{IpAddr, SocketAddr, SocketAddrV6};
+use std::path::Path;
+use std::process::Command;
+
+use ifaces::{Interface, InterfaceFlags, Kind};
+use serde::Deserialize;
+
+/// One link of `ip -j addr`, with the fields compared.
+#[derive(Deserialize)]
+struct IpLink {
+    ifindex: u32,
+    ifname: String,
+    #[serde(default)]
+    flags: Vec<String>,
+    #[serde(default)]
+    addr_info: Vec<IpAddrInfo>,
+}
+
+#[derive(Deserialize)]
+struct IpAddrInfo {
+    family: String,
+    local: IpAddr,
+    prefixlen: u8,
+    scope: String,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    deprecated: bool,
+}
+
+/// An address as both sides are reduced to before comparing.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+struct Entry {
+    name: String,
+    ip: IpAddr,
+    prefix_len: Option<u8>,
+    scope_id: u32,
+}
+
+const FLAGS: [(&str, InterfaceFlags); 5] = [
+    ("UP", InterfaceFlags::UP),
+    ("BROADCAST", InterfaceFlags::BROADCAST),
+    ("LOOPBACK", InterfaceFlags::LOOPBACK),
+    ("POINTOPOINT", InterfaceFlags::POINT_TO_POINT),
+    ("MULTICAST", InterfaceFlags::MULTICAST),
+];
+
+fn parse(json: &str) -> serde_json::Result<Vec<IpLink>> {
+    serde_json::from_str(json)
+}
+
+/// The addresses `ip` lists, as `ifaces` should report them.
+fn expected(links: &[IpLink]) -> BTreeSet<Entry> {
+    let mut entries = BTreeSet::new();
+    for link in links {
+        for info in link.addr_info.iter().filter(|info| !info.deprecated) {
+            let name = match (&info.label, info.family.as_str()) {
+                (Some(label), "inet") => label.clone(),
+                _ => link.ifname.clone(),
+            };
+            let scope_id = match (info.family.as_str(), info.scope.as_str()) {
+                ("inet6", "link") => link.ifindex,
+                _ => 0,
+            };
+            entries.insert(Entry {
+                name,
+                ip: info.local,
+                prefix_len: Some(info.prefixlen),
+                scope_id,
+            });
+        }
+    }
+    entries
+}
+
+/// The addresses `ifaces` reports, leaving out the ones `ip` marks
+/// deprecated.
+fn actual(interfaces: &[Interface], links: &[IpLink]) -> BTreeSet<Entry> {
+    let deprecated: BTreeSet<IpAddr> = links
+        .iter()
+        .flat_map(|link| &link.addr_info)
+        .filter(|info| info.deprecated)
+        .map(|info| info.local)
+        .collect();
+    interfaces
+        .iter()
+        .filter_map(|interface| {
+            let addr = interface.addr?;
+            if deprecated.contains(&addr.ip()) {
+                return None;
+            }
+            Some(Entry {
+                name: interface.name.clone(),
+                ip: addr.ip(),
+                prefix_len: interface.prefix_len(),
+                scope_id: match addr {
+                    SocketAddr::V4(_) => 0,
+                    SocketAddr::V6(addr) => addr.scope_id(),
+                },
+            })
+        })
+        .collect()
+}
+
+/// The flags of each link that do not match, as text.
+fn flag_differences(interfaces: &[Interface], links: &[IpLink]) -> Vec<String> {
+    let mut differences = Vec::new();
+    for link in links {
+        let ours = match interfaces.iter().find(|i| i.name == link.ifname) {
+            Some(interface) => interface.flags,
+            None => continue,
+        };
+        for (name, flag) in FLAGS {
+            let theirs = link.flags.iter().any(|f| f == name);
+            if theirs != ours.contains(flag) {
+                differences.push(format!(
+                    "{}: {} is {} in ip, {} in ifaces",
+                    link.ifname,
+                    name,
+                    if theirs { "set" } else { "clear" },
+                    if ours.contains(flag) { "set" } else { "clear" },
+                ));
+            }
+        }
+    }
+    differences
+}
+
+/// Everything that does not match, as text.
+fn differences(interfaces: &[Interface], links: &[IpLink]) -> Vec<String> {
+    let expected = expected(links);
+    let actual = actual(interfaces, links);
+    let mut differences = Vec::new();
+    for entry in expected.difference(&actual) {
+        differences.push(format!("only in ip:     {:?}", entry));
+    }
+    for entry in actual.difference(&expected) {
+        differences.push(format!("only in ifaces: {:?}", entry));
+    }
+    differences.extend(flag_differences(interfaces, links));
+    differences
+}
+
+#[test]
+fn ifaces_matches_ip() {
+    if std::env::var_os("IFACES_IP_ORACLE").is_none() {
+        eprintln!("set IFACES_IP_ORACLE=1 to compare against `ip -j addr`");
+        return;
+    }
+    let output = Command::new("ip")
+        .args(["-j", "addr"])
+        .output()
+        .expect("running `ip -j addr`");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let links = parse(std::str::from_utf8(&output.stdout).unwrap()).unwrap();
+    let interfaces = ifaces::ifaces().unwrap();
+    let differences = differences(&interfaces, &links);
+    assert!(differences.is_empty(), "{}", differences.join("\n"));
+}
+
+const FIXTURES: [&str; 3] = [
+    "debian-12-container.json",
+    "laptop-wifi-docker.json",
+    "server-wireguard.json",
+];
+
+fn fixture(name: &str) -> Vec<IpLink> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/ip-addr")
+        .join(name);
+    parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// What `ifaces` reports on the machine `links` was captured on: an
+/// entry per address, all with the flags of their link.
+fn ifaces_of(links: &[IpLink]) -> Vec<Interface> {
+    let mut interfaces = Vec::new();
+    for link in links {
+        let mut flags = InterfaceFlags::empty();
+        for (name, flag) in FLAGS {
+            if link.flags.iter().any(|f| f == name) {
+                flags |= flag;
+            }
+        }
+        for info in &link.addr_info {
+            let (kind, name, width) = match (info.family.as_str(), &info.label) {
+                ("inet", Some(label)) => (Kind::Ipv4, label.as_str(), 32),
+                ("inet", None) => (Kind::Ipv4, link.ifname.as_str(), 32),
+                _ => (Kind::Ipv6, link.ifname.as_str(), 128),
+            };
+            let mask = u128::MAX
+                .checked_shl(width - u32::from(info.prefixlen))
+                .unwrap_or(0);
+            let (addr, mask) = match info.local {
+                IpAddr::V4(ip) => (
+                    SocketAddr::new(ip.into(), 0),
+                    SocketAddr::new(std::net::Ipv4Addr::from(mask as u32).into(), 0),
+                ),
+                IpAddr::V6(ip) => {
+                    let scope_id = match info.scope.as_str() {
+                        "link" => link.ifindex,
+                        _ => 0,
+                    };
+                    (
+                        SocketAddr::V6(SocketAddrV6::new(ip, 0, 0, scope_id)),
+                        SocketAddr::new(std::net::Ipv6Addr::from(mask).into(), 0),
+                    )
+                }
+            };
+            let mut interface = Interface::new(name, kind);
+            interface.index = Some(link.ifindex);
+            interface.addr = Some(addr);
+            interface.mask = Some(mask);
+            interface.flags = flags;
+            interfaces.push(interface);
+        }
+    }
+    interfaces
+}
+
+#[test]
+fn fixtures_parse() {
+    for name in FIXTURES {
+        let links = fixture(name);
+        let lo = links.iter().find(|link| link.ifname == "lo").unwrap();
+        assert_eq!(lo.ifindex, 1, "{}", name);
+        assert!(
+            lo.addr_info
+                .iter()
+                .any(|info| info.local == IpAddr::from([127, 0, 0, 1])),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn expected_entries_of_a_capture() {
+    let links = fixture("debian-12-container.json");
+    let entry = |name: &str, ip: &str, prefix_len, scope_id| Entry {
+        name: name.to_owned(),
+        ip: ip.parse().unwrap(),
+        prefix_len: Some(prefix_len),
+        scope_id,
+    };
+    let entries: BTreeSet<Entry> = vec![
+        entry("lo", "127.0.0.1", 8, 0),
+        entry("lo", "::1", 128, 0),
+        entry("eth0", "192.0.2.2", 24, 0),
+        entry("eth0", "fd00::2", 64, 0),
+        entry("eth0", "fe80::fc:ff:fe00:1", 64, 4),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(expected(&links), entries);
+}
+
+#[test]
+fn labels_name_ipv4_entries_only() {
+    let links = fixture("laptop-wifi-docker.json");
+    let names: BTreeSet<(String, IpAddr)> = expected(&links)
+        .into_iter()
+        .map(|entry| (entry.name, entry.ip))
+        .collect();
+    assert!(names.contains(&("docker0:1".to_owned(), "172.18.0.1".parse().unwrap())));
+    assert!(names.contains(&("docker0".to_owned(), "172.17.0.1".parse().unwrap())));
+    assert!(names
+        .iter()
+        .filter(|(_, ip)| ip.is_ipv6())
+        .all(|(name, _)| !name.contains(':')));
+}
+
+#[test]
+fn deprecated_addresses_are_tolerated() {
+    let links = fixture("laptop-wifi-docker.json");
+    let deprecated: IpAddr = "2001:db8:1f0a:3c00:9d2e:4a11:c07f:52b8".parse().unwrap();
+    assert!(expected(&links).iter().all(|entry| entry.ip != deprecated));
+    // Reported or not, it is no difference.
+    let interfaces = ifaces_of(&links);
+    assert!(interfaces
+        .iter()
+        .any(|interface| interface.addr.map(|addr| addr.ip()) == Some(deprecated)));
+    assert!(actual(&interfaces, &links)
+        .iter()
+        .all(|entry| entry.ip != deprecated));
+}
+
+#[test]
+fn matching_entries_have_no_differences() {
+    for name in FIXTURES {
+        let links = fixture(name);
+        let differences = differences(&ifaces_of(&links), &links);
+        assert!(differences.is_empty(), "{}: {:?}", name, differences);
+    }
+}
+
+#[test]
+fn unscoped_link_local_addresses_are_differences() {
+    let links = fixture("debian-12-container.json");
+    let mut interfaces = ifaces_of(&links);
+    for interface in &mut interfaces {
+        if let Some(SocketAddr::V6(addr)) = &mut interface.addr {
+            addr.set_scope_id(0);
+        }
+    }
+    assert_eq!(
+        differences(&interfaces, &links),
+        [
+            "only in ip:     Entry { name: \"eth0\", ip: fe80::fc:ff:fe00:1, prefix_len: Some(64), scope_id: 4 }",
+            "only in ifaces: Entry { name: \"eth0\", ip: fe80::fc:ff:fe00:1, prefix_len: Some(64), scope_id: 0 }",
+        ]
+    );
+}
+
+#[test]
+fn flag_mismatches_are_differences() {
+    let links = fixture("server-wireguard.json");
+    let mut interfaces = ifaces_of(&links);
+    for interface in interfaces.iter_mut().filter(|i| i.name == "wg0") {
+        interface.flags.remove(InterfaceFlags::POINT_TO_POINT);
+        // Not compared.
+        interface.flags.insert(InterfaceFlags::RUNNING);
+    }
+    assert_eq!(
+        differences(&interfaces, &links),
+        ["wg0: POINTOPOINT is set in ip, clear in ifaces"]
+    );
+}
@@ -0,0 +1,50 @@
+//! Setting and reading back the IPv6 token of a bridge made for the test.
+//! Needs root or `CAP_NET_ADMIN`, and passes without changing anything if
+//! the bridge cannot be made.
+
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use std::io;
+use std::net::Ipv6Addr;
+
+use ifaces::config::{create_bridge, delete_bridge, ipv6_token, set_ipv6_token};
+
+const BRIDGE: &str = "iftoken0";
+
+#[test]
+fn tokens_round_trip() {
+    match create_bridge(BRIDGE) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("not privileged, skipping: {}", err);
+            return;
+        }
+        result => result.unwrap(),
+    };
+    let token: Ipv6Addr = "::1:2".parse().unwrap();
+    let before = ipv6_token(BRIDGE);
+    let set = set_ipv6_token(BRIDGE, token);
+    let after = ipv6_token(BRIDGE);
+    let prefixed = set_ipv6_token(BRIDGE, "2001:db8::1".parse().unwrap());
+    let cleared = set_ipv6_token(BRIDGE, Ipv6Addr::UNSPECIFIED).and_then(|_| ipv6_token(BRIDGE));
+    delete_bridge(BRIDGE).unwrap();
+
+    assert_eq!(before.unwrap(), None);
+    set.unwrap();
+    assert_eq!(after.unwrap(), Some(token));
+    assert_eq!(prefixed.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(cleared.unwrap(), None);
+}
+
+#[test]
+fn loopback_refuses_tokens() {
+    match set_ipv6_token("lo", "::1:2".parse().unwrap()) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("not privileged, skipping: {}", err);
+        }
+        result => {
+            let err = result.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            assert!(err.to_string().contains("loopback"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! Changing the metric of a default route for real, on the loopback
+//! interface. The route is added with a metric far above any a machine
+//! routes by, and removed again. Needs root or `CAP_NET_ADMIN`, and passes
+//! without changing anything if adding it is refused.
+
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use std::io;
+
+use ifaces::config::{add_route, remove_route, set_metric};
+use ifaces::{InterfaceFlags, Kind, Route};
+
+fn lo() -> ifaces::Interface {
+    ifaces::ifaces()
+        .unwrap()
+        .into_iter()
+        .find(|iface| iface.flags.contains(InterfaceFlags::LOOPBACK))
+        .unwrap()
+}
+
+#[test]
+fn default_route_metrics_are_changed() {
+    let lo = lo();
+    let route = |metric| {
+        Route::new("0.0.0.0".parse().unwrap(), 0)
+            .on(lo.index.unwrap())
+            .with_metric(metric)
+    };
+    match add_route(&route(4_000_001)) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("not privileged, skipping: {}", err);
+            return;
+        }
+        result => result.unwrap(),
+    }
+    let changed = set_metric(&lo.name, Kind::Ipv4, 4_000_002);
+    // Take it out again before asserting anything further.
+    let old = remove_route(&route(4_000_001));
+    let new = remove_route(&route(4_000_002));
+    changed.unwrap();
+    assert_eq!(old.unwrap_err().kind(), io::ErrorKind::NotFound);
+    new.unwrap();
+
+    let err = set_metric(&lo.name, Kind::Ipv4, 4_000_002).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn the_family_must_be_an_address_family() {
+    let err = set_metric(&lo().name, Kind::Packet, 10).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,33 @@
+//! Joining a group for real on the interfaces of the machine running the
+//! tests.
+
+use std::net::{IpAddr, UdpSocket};
+
+use ifaces::{join_multicast_all, leave_multicast_all, InterfaceFlags};
+
+#[test]
+fn groups_are_joined_and_left() {
+    let eligible = ifaces::ifaces().unwrap().into_iter().any(|iface| {
+        iface
+            .flags
+            .contains(InterfaceFlags::UP | InterfaceFlags::MULTICAST)
+            && iface.addr.is_some_and(|addr| addr.is_ipv4())
+    });
+    let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
+    let group: IpAddr = "239.255.42.99".parse().unwrap();
+
+    let report = join_multicast_all(&socket, group).unwrap();
+    assert_eq!(report.any_succeeded(), eligible, "{:?}", report);
+    // Joining again fails on every interface, without stopping early.
+    let again = join_multicast_all(&socket, group).unwrap();
+    assert_eq!(again.results.len(), report.results.len());
+    assert_eq!(
+        again.failed().count(),
+        report.succeeded().count(),
+        "{:?}",
+        again
+    );
+
+    let left = leave_multicast_all(&socket, group).unwrap();
+    assert_eq!(left.succeeded().count(), report.succeeded().count());
+}
@@ -0,0 +1,63 @@
+//! Static neighbor entries on a bridge made for the test, with `ip` giving
+//! it an address. Needs root or `CAP_NET_ADMIN` and iproute2, and passes
+//! without changing anything if the bridge cannot be made.
+
+#![cfg(target_os = "linux")]
+
+use std::io;
+use std::process::Command;
+
+use ifaces::config::{add_neighbor, create_bridge, delete_bridge, remove_neighbor};
+use ifaces::MacAddr;
+
+const BRIDGE: &str = "ifneigh0";
+
+fn ip(args: &[&str]) -> String {
+    let output = Command::new("ip").args(args).output().unwrap();
+    assert!(
+        output.status.success(),
+        "ip {:?}: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn neighbors_are_pinned_and_removed() {
+    match create_bridge(BRIDGE) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("not privileged, skipping: {}", err);
+            return;
+        }
+        result => result.unwrap(),
+    };
+    if Command::new("ip").arg("-V").output().is_err() {
+        delete_bridge(BRIDGE).unwrap();
+        eprintln!("no iproute2, skipping");
+        return;
+    }
+    ip(&["addr", "add", "198.51.100.1/24", "dev", BRIDGE]);
+    let mac = MacAddr::new([0x02, 0, 0, 0, 0x42, 0x09]);
+    let neighbor = "198.51.100.9".parse().unwrap();
+
+    let added = add_neighbor(BRIDGE, neighbor, mac);
+    let shown = ip(&["neigh", "show", "dev", BRIDGE, "to", "198.51.100.9"]);
+    let again = add_neighbor(BRIDGE, neighbor, mac);
+    let off_link = add_neighbor(BRIDGE, "203.0.113.9".parse().unwrap(), mac);
+    let removed = remove_neighbor(BRIDGE, neighbor);
+    let left = ip(&["neigh", "show", "dev", BRIDGE, "to", "198.51.100.9"]);
+    let removed_again = remove_neighbor(BRIDGE, neighbor);
+    let no_such = add_neighbor("ifneigh-none", neighbor, mac);
+    delete_bridge(BRIDGE).unwrap();
+
+    added.unwrap();
+    assert!(shown.contains("02:00:00:00:42:09"), "{}", shown);
+    assert!(shown.contains("PERMANENT"), "{}", shown);
+    assert_eq!(again.unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+    assert_eq!(off_link.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    removed.unwrap();
+    assert!(left.trim().is_empty(), "{}", left);
+    assert_eq!(removed_again.unwrap_err().kind(), io::ErrorKind::NotFound);
+    assert_eq!(no_such.unwrap_err().kind(), io::ErrorKind::NotFound);
+}
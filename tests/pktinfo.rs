@@ -0,0 +1,86 @@
+//! The arrival interface of datagrams sent over loopback.
+
+#![cfg(all(
+    feature = "pktinfo",
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_family = "windows"
+    )
+))]
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+use ifaces::{enable_pktinfo, recv_from_with_interface, InterfaceFlags};
+
+/// Send a datagram to `socket` over loopback, and receive it.
+fn round_trip(
+    socket: &UdpSocket,
+    to: IpAddr,
+) -> io::Result<(usize, SocketAddr, ifaces::InterfaceRef)> {
+    socket.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let port = socket.local_addr()?.port();
+    let sender = UdpSocket::bind(SocketAddr::new(to, 0))?;
+    sender.send_to(b"ping", SocketAddr::new(to, port))?;
+    let mut buf = [0; 16];
+    let received = recv_from_with_interface(socket, &mut buf)?;
+    assert_eq!(&buf[..received.0], b"ping");
+    assert_eq!(received.1, sender.local_addr()?);
+    Ok(received)
+}
+
+fn assert_loopback(interface: &ifaces::InterfaceRef) {
+    let entries = interface.interfaces().unwrap();
+    assert!(
+        !entries.is_empty(),
+        "no interface with index {}",
+        interface.index()
+    );
+    assert!(entries
+        .iter()
+        .all(|iface| iface.flags.contains(InterfaceFlags::LOOPBACK)));
+    assert_eq!(interface.name(), Some(entries[0].name.as_str()));
+}
+
+#[test]
+fn ipv4_datagrams_arrive_on_loopback() {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+    enable_pktinfo(&socket).unwrap();
+    let (len, _, interface) = round_trip(&socket, Ipv4Addr::LOCALHOST.into()).unwrap();
+    assert_eq!(len, 4);
+    assert_eq!(interface.destination(), IpAddr::from(Ipv4Addr::LOCALHOST));
+    assert_loopback(&interface);
+}
+
+#[test]
+fn ipv6_datagrams_arrive_on_loopback() {
+    let socket = match UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0)) {
+        Ok(socket) => socket,
+        Err(err) => {
+            eprintln!("no IPv6, skipping: {}", err);
+            return;
+        }
+    };
+    enable_pktinfo(&socket).unwrap();
+    let interface = match round_trip(&socket, Ipv6Addr::LOCALHOST.into()) {
+        Ok((_, _, interface)) => interface,
+        Err(err) if err.kind() == io::ErrorKind::AddrNotAvailable => {
+            eprintln!("no ::1, skipping: {}", err);
+            return;
+        }
+        Err(err) => panic!("{}", err),
+    };
+    assert_eq!(interface.destination(), IpAddr::from(Ipv6Addr::LOCALHOST));
+    assert_loopback(&interface);
+}
+
+#[test]
+fn datagrams_without_packet_info_are_refused() {
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let err = round_trip(&socket, Ipv4Addr::LOCALHOST.into()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
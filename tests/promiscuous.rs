@@ -0,0 +1,43 @@
+//! Toggling promiscuous mode for real, on the loopback interface. Needs
+//! root or `CAP_NET_ADMIN`, and passes without changing anything if the
+//! first toggle is refused.
+
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use std::io;
+
+use ifaces::config::{promiscuous, set_promiscuous};
+use ifaces::InterfaceFlags;
+
+fn lo() -> ifaces::Interface {
+    ifaces::ifaces()
+        .unwrap()
+        .into_iter()
+        .find(|iface| iface.flags.contains(InterfaceFlags::LOOPBACK))
+        .unwrap()
+}
+
+#[test]
+fn promiscuous_mode_round_trips() {
+    let name = lo().name;
+    let before = promiscuous(&name).unwrap();
+    match set_promiscuous(&name, !before) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("not privileged, skipping: {}", err);
+            return;
+        }
+        result => assert_eq!(result.unwrap(), before),
+    }
+    let toggled = promiscuous(&name);
+    let iface = lo();
+    // Put it back before asserting anything further.
+    assert_eq!(set_promiscuous(&name, before).unwrap(), !before);
+    assert_eq!(toggled.unwrap(), !before);
+    assert_eq!(iface.flags.contains(InterfaceFlags::PROMISC), !before);
+    if !before {
+        assert!(iface.promiscuity() >= Some(1));
+    }
+    // Setting the current state changes nothing.
+    assert_eq!(set_promiscuous(&name, before).unwrap(), before);
+    assert_eq!(promiscuous(&name).unwrap(), before);
+}
@@ -0,0 +1,58 @@
+//! Installing and removing a route for real, on the loopback interface in
+//! a table of its own so nothing else is routed differently. Needs root or
+//! `CAP_NET_ADMIN`, and passes without changing anything if the first add
+//! is refused.
+
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use std::io;
+
+use ifaces::config::{add_route, remove_route};
+use ifaces::{InterfaceFlags, Route};
+
+const TABLE: u32 = 4242;
+
+fn lo_index() -> u32 {
+    ifaces::ifaces()
+        .unwrap()
+        .into_iter()
+        .find(|iface| iface.flags.contains(InterfaceFlags::LOOPBACK))
+        .and_then(|iface| iface.index)
+        .unwrap()
+}
+
+#[test]
+fn routes_are_added_and_removed() {
+    let routes = [
+        Route::new("198.51.100.0".parse().unwrap(), 24)
+            .on(lo_index())
+            .in_table(TABLE),
+        Route::host("2001:db8::42".parse().unwrap())
+            .on(lo_index())
+            .with_metric(7)
+            .in_table(TABLE),
+    ];
+    for route in &routes {
+        match add_route(route) {
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                eprintln!("not privileged, skipping: {}", err);
+                return;
+            }
+            result => result.unwrap(),
+        }
+        let again = add_route(route);
+        // Take it out again before asserting anything further.
+        let removed = remove_route(route);
+        assert_eq!(again.unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        removed.unwrap();
+        let err = remove_route(route).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound, "{:?}", route);
+    }
+}
+
+#[test]
+fn invalid_routes_are_refused_before_asking() {
+    let route = Route::new("198.51.100.0".parse().unwrap(), 33).in_table(TABLE);
+    let err = add_route(&route).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
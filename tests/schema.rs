@@ -0,0 +1,230 @@
+//! The `schemars` schema against what the `serde` impls write.
+//!
+//! The validator only knows the keywords schemars generates for the crate's
+//! types, and fails on any other so that a new one is not silently passed.
+
+#![cfg(all(feature = "serde", feature = "schemars"))]
+
+use std::net::SocketAddr;
+use std::path::Path;
+
+use ifaces::{Interface, InterfaceFlags, Kind, NextHop, Route};
+use serde_json::{Map, Value};
+
+/// Why `value` does not match `schema`, with the JSON path to it.
+fn validate(root: &Value, schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let schema = match schema {
+        Value::Bool(true) => return Ok(()),
+        Value::Object(schema) => schema,
+        _ => return Err(format!("{}: unexpected schema {}", path, schema)),
+    };
+    for (keyword, rule) in schema {
+        match keyword.as_str() {
+            "$schema" | "title" | "description" | "format" | "definitions" => {}
+            "$ref" => {
+                let name = rule.as_str().unwrap().trim_start_matches("#/definitions/");
+                validate(root, &root["definitions"][name], value, path)?;
+            }
+            "type" => {
+                let types: Vec<&str> = match rule {
+                    Value::String(ty) => vec![ty.as_str()],
+                    Value::Array(tys) => tys.iter().map(|ty| ty.as_str().unwrap()).collect(),
+                    _ => unreachable!(),
+                };
+                if !types.iter().any(|ty| has_type(value, ty)) {
+                    return Err(format!("{}: {} is not {:?}", path, value, types));
+                }
+            }
+            "enum" => {
+                if !rule.as_array().unwrap().contains(value) {
+                    return Err(format!("{}: {} is not one of {}", path, value, rule));
+                }
+            }
+            "minimum" => {
+                if let Some(number) = value.as_f64() {
+                    if number < rule.as_f64().unwrap() {
+                        return Err(format!("{}: {} is below {}", path, value, rule));
+                    }
+                }
+            }
+            "required" => {
+                if let Value::Object(object) = value {
+                    for field in rule.as_array().unwrap() {
+                        if !object.contains_key(field.as_str().unwrap()) {
+                            return Err(format!("{}: {} is missing", path, field));
+                        }
+                    }
+                }
+            }
+            "properties" => {
+                if let Value::Object(object) = value {
+                    properties(root, schema, rule.as_object().unwrap(), object, path)?;
+                }
+            }
+            "additionalProperties" => {}
+            "items" => {
+                if let Value::Array(items) = value {
+                    for (i, item) in items.iter().enumerate() {
+                        validate(root, rule, item, &format!("{}[{}]", path, i))?;
+                    }
+                }
+            }
+            "minItems" | "maxItems" => {
+                if let Value::Array(items) = value {
+                    let bound = rule.as_u64().unwrap() as usize;
+                    let ok = match keyword.as_str() {
+                        "minItems" => items.len() >= bound,
+                        _ => items.len() <= bound,
+                    };
+                    if !ok {
+                        return Err(format!(
+                            "{}: {} items, {} {}",
+                            path,
+                            items.len(),
+                            keyword,
+                            bound
+                        ));
+                    }
+                }
+            }
+            "allOf" => {
+                for schema in rule.as_array().unwrap() {
+                    validate(root, schema, value, path)?;
+                }
+            }
+            "anyOf" | "oneOf" => {
+                let matching = rule
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .filter(|schema| validate(root, schema, value, path).is_ok())
+                    .count();
+                let ok = match keyword.as_str() {
+                    "anyOf" => matching >= 1,
+                    _ => matching == 1,
+                };
+                if !ok {
+                    return Err(format!(
+                        "{}: {} matches {} of {}",
+                        path, value, matching, keyword
+                    ));
+                }
+            }
+            _ => return Err(format!("{}: unknown keyword {}", path, keyword)),
+        }
+    }
+    Ok(())
+}
+
+fn properties(
+    root: &Value,
+    schema: &Map<String, Value>,
+    properties: &Map<String, Value>,
+    object: &Map<String, Value>,
+    path: &str,
+) -> Result<(), String> {
+    for (field, value) in object {
+        match properties.get(field) {
+            Some(property) => validate(root, property, value, &format!("{}.{}", path, field))?,
+            None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                return Err(format!("{}: unexpected field {}", path, field));
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_u64() || value.is_i64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => false,
+    }
+}
+
+fn schema_of<T: schemars::JsonSchema>() -> Value {
+    serde_json::to_value(schemars::schema_for!(T)).unwrap()
+}
+
+fn check<T: schemars::JsonSchema>(value: &Value) -> Result<(), String> {
+    let schema = schema_of::<T>();
+    validate(&schema, &schema, value, "$")
+}
+
+fn snapshot() -> Value {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ifaces-snapshot.json");
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn snapshot_matches_the_schema() {
+    check::<Vec<Interface>>(&snapshot()).unwrap();
+}
+
+#[test]
+fn snapshot_round_trips() {
+    let snapshot = snapshot();
+    let interfaces: Vec<Interface> = serde_json::from_value(snapshot.clone()).unwrap();
+    assert_eq!(interfaces.len(), 7);
+    let eth0 = interfaces
+        .iter()
+        .find(|i| i.name == "eth0" && i.kind == Kind::Ipv4)
+        .unwrap();
+    assert_eq!(eth0.prefix_len(), Some(24));
+    assert_eq!(
+        eth0.hop,
+        Some(NextHop::Broadcast("192.0.2.255:0".parse().unwrap()))
+    );
+    assert!(eth0
+        .flags
+        .contains(InterfaceFlags::UP | InterfaceFlags::BROADCAST));
+    assert_eq!(serde_json::to_value(&interfaces).unwrap(), snapshot);
+}
+
+#[test]
+fn built_entries_match_the_schema() {
+    let mut interface = Interface::new("wg0", Kind::Ipv6);
+    interface.index = Some(7);
+    interface.addr = Some("[fe80::1%7]:0".parse().unwrap());
+    interface.mask = Some("[ffff:ffff:ffff:ffff::]:0".parse().unwrap());
+    interface.hop = Some(NextHop::Destination(SocketAddr::from(([10, 0, 0, 2], 0))));
+    interface.flags = InterfaceFlags::UP | InterfaceFlags::POINT_TO_POINT;
+    interface.mtu = Some(1420);
+    check::<Interface>(&serde_json::to_value(&interface).unwrap()).unwrap();
+
+    let route = Route::new("10.0.0.0".parse().unwrap(), 8)
+        .via("10.0.0.1".parse().unwrap())
+        .on(7);
+    check::<Route>(&serde_json::to_value(&route).unwrap()).unwrap();
+}
+
+#[test]
+fn mismatches_are_caught() {
+    let mut snapshot = snapshot();
+    snapshot[0]["kind"] = Value::from("Ethernet");
+    assert!(check::<Vec<Interface>>(&snapshot).is_err());
+
+    let mut snapshot = self::snapshot();
+    snapshot[1]["mac"] = Value::from(vec![1, 2, 3]);
+    assert!(check::<Vec<Interface>>(&snapshot).is_err());
+
+    let mut snapshot = self::snapshot();
+    snapshot[2]["hop"] = serde_json::json!({ "Gateway": "10.0.0.1:0" });
+    assert!(check::<Vec<Interface>>(&snapshot).is_err());
+
+    let mut snapshot = self::snapshot();
+    snapshot[3].as_object_mut().unwrap().remove("flags");
+    assert!(check::<Vec<Interface>>(&snapshot).is_err());
+}
+
+#[test]
+fn this_machine_matches_the_schema() {
+    let interfaces = ifaces::ifaces().unwrap();
+    check::<Vec<Interface>>(&serde_json::to_value(&interfaces).unwrap()).unwrap();
+}
@@ -0,0 +1,36 @@
+//! Setting the temporary address mode for real, on a bridge made for the
+//! test. Needs root or `CAP_NET_ADMIN`, and passes without changing
+//! anything if the bridge cannot be made.
+
+#![cfg(target_os = "linux")]
+
+use std::io;
+
+use ifaces::config::{create_bridge, delete_bridge, set_temp_addrs, temp_addrs};
+use ifaces::TempAddrs;
+
+const BRIDGE: &str = "iftemp0";
+
+#[test]
+fn modes_are_set_and_read_back() {
+    match create_bridge(BRIDGE) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("not privileged, skipping: {}", err);
+            return;
+        }
+        result => result.unwrap(),
+    };
+    let modes = [
+        TempAddrs::Preferred,
+        TempAddrs::Enabled,
+        TempAddrs::Disabled,
+    ];
+    let read: Vec<io::Result<TempAddrs>> = modes
+        .iter()
+        .map(|mode| set_temp_addrs(BRIDGE, *mode).and_then(|()| temp_addrs(BRIDGE)))
+        .collect();
+    delete_bridge(BRIDGE).unwrap();
+    for (mode, read) in modes.iter().zip(read) {
+        assert_eq!(read.unwrap(), *mode);
+    }
+}
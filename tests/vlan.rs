@@ -0,0 +1,57 @@
+//! Making a VLAN on a bridge made for the test. Needs root or
+//! `CAP_NET_ADMIN` and a kernel with 802.1Q support, and passes without
+//! changing anything if either is missing.
+
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use std::io;
+
+use ifaces::config::{create_bridge, create_vlan, delete_bridge, delete_vlan};
+
+const BRIDGE: &str = "ifvlanp0";
+
+fn listed(name: &str) -> Option<u32> {
+    ifaces::ifaces()
+        .unwrap()
+        .into_iter()
+        .find(|iface| iface.name == name)
+        .and_then(|iface| iface.index)
+}
+
+#[test]
+fn vlans_are_made_and_deleted() {
+    match create_bridge(BRIDGE) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("not privileged, skipping: {}", err);
+            return;
+        }
+        result => result.unwrap(),
+    };
+    let index = match create_vlan(BRIDGE, 7, None) {
+        Err(err) if err.kind() == io::ErrorKind::Unsupported => {
+            delete_bridge(BRIDGE).unwrap();
+            eprintln!("no 802.1Q support, skipping: {}", err);
+            return;
+        }
+        result => result,
+    };
+    let listed_index = listed("ifvlanp0.7");
+    let duplicate = create_vlan(BRIDGE, 7, None);
+    let same_id = create_vlan(BRIDGE, 7, Some("ifvlan7"));
+    let named = create_vlan(BRIDGE, 4094, Some("ifvlan4094"));
+    let not_a_vlan = delete_vlan(BRIDGE);
+    let deleted = delete_vlan("ifvlanp0.7");
+    let named_deleted = delete_vlan("ifvlan4094");
+    let gone = listed("ifvlanp0.7");
+    delete_bridge(BRIDGE).unwrap();
+
+    let index = index.unwrap();
+    assert_eq!(listed_index, Some(index));
+    assert_eq!(duplicate.unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+    assert_eq!(same_id.unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+    assert_ne!(named.unwrap(), index);
+    assert_eq!(not_a_vlan.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    deleted.unwrap();
+    named_deleted.unwrap();
+    assert_eq!(gone, None);
+}
@@ -0,0 +1,48 @@
+#!/usr/bin/env python3
+"""Generate src/oui/table.rs from the IEEE MA-L registry.
+
+    curl -O https://standards-oui.ieee.org/oui/oui.csv
+    python3 tools/gen-oui.py oui.csv > src/oui/table.rs
+"""
+
+import csv
+import sys
+
+
+def main(path):
+    entries = {}
+    with open(path, newline="", encoding="utf-8") as f:
+        for row in csv.DictReader(f):
+            if row["Registry"] != "MA-L":
+                continue
+            name = " ".join(row["Organization Name"].split())
+            entries[int(row["Assignment"], 16)] = name
+
+    vendors = sorted(set(entries.values()))
+    index = {name: i for i, name in enumerate(vendors)}
+    prefixes = sorted(entries)
+
+    out = sys.stdout
+    out.write("// Generated by tools/gen-oui.py from the IEEE MA-L registry. Do not edit.\n\n")
+    out.write("/// Registered prefixes, sorted.\n")
+    out.write("pub static PREFIXES: [[u8; 3]; %d] = [\n" % len(prefixes))
+    for prefix in prefixes:
+        out.write("    [0x%02x, 0x%02x, 0x%02x],\n" % tuple(prefix.to_bytes(3, "big")))
+    out.write("];\n\n")
+    out.write("/// The index in `VENDORS` of the owner of each of `PREFIXES`.\n")
+    out.write("pub static VENDOR_OF: [u16; %d] = [\n" % len(prefixes))
+    for prefix in prefixes:
+        out.write("    %d,\n" % index[entries[prefix]])
+    out.write("];\n\n")
+    out.write("pub static VENDORS: [&str; %d] = [\n" % len(vendors))
+    for name in vendors:
+        out.write("    %s,\n" % rust_string(name))
+    out.write("];\n")
+
+
+def rust_string(s):
+    return '"' + s.replace("\\", "\\\\").replace('"', '\\"') + '"'
+
+
+if __name__ == "__main__":
+    main(sys.argv[1])
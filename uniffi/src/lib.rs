@@ -0,0 +1,296 @@
+//! The `ifaces` module for Kotlin and Swift, through uniffi.
+//!
+//! Addresses are strings, IPv6 ones with a `%` and the scope id when they
+//! have one. `io::Error`s become `IfacesException` in Kotlin and
+//! `IfacesError` in Swift.
+
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use ifaces::{ChangeHint, ChangeWatcher, InterfaceFilter, InterfaceFlags, Kind, NextHop};
+
+uniffi::setup_scaffolding!();
+
+/// How often a [`Watch`] looks for new notifications.
+const DRAIN_INTERVAL: Duration = Duration::from_millis(250);
+
+const FLAG_NAMES: &[(InterfaceFlags, &str)] = &[
+    (InterfaceFlags::UP, "up"),
+    (InterfaceFlags::BROADCAST, "broadcast"),
+    (InterfaceFlags::LOOPBACK, "loopback"),
+    (InterfaceFlags::POINT_TO_POINT, "point_to_point"),
+    (InterfaceFlags::RUNNING, "running"),
+    (InterfaceFlags::MULTICAST, "multicast"),
+    (InterfaceFlags::PROMISC, "promisc"),
+];
+
+#[derive(Debug, uniffi::Error)]
+pub enum IfacesError {
+    /// The platform cannot do this.
+    Unsupported {
+        reason: String,
+    },
+    PermissionDenied {
+        reason: String,
+    },
+    InvalidInput {
+        reason: String,
+    },
+    /// Any other error, `code` being its errno, or the Windows error code.
+    Io {
+        code: Option<i32>,
+        reason: String,
+    },
+}
+
+impl From<io::Error> for IfacesError {
+    fn from(err: io::Error) -> IfacesError {
+        let reason = err.to_string();
+        match err.kind() {
+            io::ErrorKind::Unsupported => IfacesError::Unsupported { reason },
+            io::ErrorKind::PermissionDenied => IfacesError::PermissionDenied { reason },
+            io::ErrorKind::InvalidInput => IfacesError::InvalidInput { reason },
+            _ => IfacesError::Io {
+                code: err.raw_os_error(),
+                reason,
+            },
+        }
+    }
+}
+
+impl fmt::Display for IfacesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IfacesError::Unsupported { reason }
+            | IfacesError::PermissionDenied { reason }
+            | IfacesError::InvalidInput { reason }
+            | IfacesError::Io { reason, .. } => f.write_str(reason),
+        }
+    }
+}
+
+impl std::error::Error for IfacesError {}
+
+/// One entry of `ifaces()`.
+#[derive(PartialEq, Eq, Debug, Clone, uniffi::Record)]
+pub struct Interface {
+    pub name: String,
+    pub index: Option<u32>,
+    /// `"ipv4"`, `"ipv6"`, `"link"`, `"packet"`, `"can"`, `"bluetooth"` or
+    /// `"unknown"`.
+    pub kind: String,
+    pub addr: Option<String>,
+    pub netmask: Option<String>,
+    pub prefix_len: Option<u8>,
+    pub broadcast: Option<String>,
+    pub destination: Option<String>,
+    pub flags: Vec<String>,
+    pub mac: Option<String>,
+    pub mtu: Option<u32>,
+}
+
+impl From<ifaces::Interface> for Interface {
+    fn from(iface: ifaces::Interface) -> Interface {
+        let (broadcast, destination) = match iface.hop {
+            Some(NextHop::Broadcast(addr)) => (Some(address(addr)), None),
+            Some(NextHop::Destination(addr)) => (None, Some(address(addr))),
+            None => (None, None),
+        };
+        Interface {
+            prefix_len: iface.prefix_len(),
+            index: iface.index,
+            kind: match iface.kind {
+                Kind::Ipv4 => "ipv4",
+                Kind::Ipv6 => "ipv6",
+                Kind::Link => "link",
+                Kind::Packet => "packet",
+                Kind::Can => "can",
+                Kind::Bluetooth => "bluetooth",
+                Kind::Unknown => "unknown",
+            }
+            .to_owned(),
+            addr: iface.addr.map(address),
+            netmask: iface.mask.map(|mask| mask.ip().to_string()),
+            broadcast,
+            destination,
+            flags: FLAG_NAMES
+                .iter()
+                .filter(|(flag, _)| iface.flags.contains(*flag))
+                .map(|(_, name)| (*name).to_owned())
+                .collect(),
+            mac: iface.mac.map(|mac| mac.to_string()),
+            mtu: iface.mtu,
+            name: iface.name,
+        }
+    }
+}
+
+/// `addr` without the port, and with the scope id if it has one.
+fn address(addr: SocketAddr) -> String {
+    match addr {
+        SocketAddr::V6(addr) if addr.scope_id() != 0 => {
+            format!("{}%{}", addr.ip(), addr.scope_id())
+        }
+        addr => addr.ip().to_string(),
+    }
+}
+
+/// Which entries `ifacesWithOptions` gives. Every field defaults to what
+/// `ifaces()` does.
+#[derive(PartialEq, Eq, Debug, Clone, uniffi::Record)]
+pub struct IfacesOptions {
+    /// Collapse entries that are the same in name, kind, address and mask.
+    #[uniffi(default = false)]
+    pub dedupe: bool,
+    /// Name globs, e.g. `"wlan*"`; an interface matching none of them is
+    /// left out, if any are given.
+    #[uniffi(default = [])]
+    pub include: Vec<String>,
+    /// Name globs of interfaces to leave out.
+    #[uniffi(default = [])]
+    pub exclude: Vec<String>,
+    /// Link groups; an interface in none of them is left out, if any are
+    /// given. Only Linux has groups.
+    #[uniffi(default = [])]
+    pub groups: Vec<u32>,
+}
+
+#[uniffi::export]
+pub fn ifaces() -> Result<Vec<Interface>, IfacesError> {
+    let ifaces = ifaces::ifaces()?;
+    Ok(ifaces.into_iter().map(Interface::from).collect())
+}
+
+#[uniffi::export]
+pub fn ifaces_with_options(options: IfacesOptions) -> Result<Vec<Interface>, IfacesError> {
+    let ifaces = ifaces::IfacesOptions::new()
+        .dedupe(options.dedupe)
+        .ifaces()?;
+    let mut filter = InterfaceFilter::new();
+    for pattern in &options.include {
+        filter = filter.include(pattern);
+    }
+    for pattern in &options.exclude {
+        filter = filter.exclude(pattern);
+    }
+    for &group in &options.groups {
+        filter = filter.group(group);
+    }
+    Ok(ifaces
+        .into_iter()
+        .filter(|iface| filter.matches(iface))
+        .map(Interface::from)
+        .collect())
+}
+
+/// The interface the default route goes over, preferring IPv4, `None`
+/// without a default route. `Unsupported` where routes cannot be read.
+#[uniffi::export]
+pub fn default_interface() -> Result<Option<Interface>, IfacesError> {
+    Ok(ifaces::default_interface()?.map(Interface::from))
+}
+
+#[uniffi::remote(Enum)]
+pub enum ChangeHint {
+    Addresses,
+    Links,
+    Unknown,
+}
+
+/// What a [`Watch`] calls, on a thread of its own.
+#[uniffi::export(with_foreign)]
+pub trait ChangeListener: Send + Sync {
+    /// The network configuration changed; enumerate to find out how.
+    fn on_change(&self, hint: ChangeHint);
+    /// Reading the notifications failed. No more calls follow.
+    fn on_error(&self, error: IfacesError);
+}
+
+/// A change subscription calling a listener, until stopped or dropped.
+///
+/// Notifications are picked up every 250 milliseconds, none are missed in
+/// between. An `Unknown` hint may mean some were dropped by the OS.
+#[derive(uniffi::Object)]
+pub struct Watch {
+    stop: Arc<AtomicBool>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[uniffi::export]
+impl Watch {
+    /// Subscribe. Fails with `Unsupported` on platforms without change
+    /// notifications.
+    #[uniffi::constructor]
+    pub fn new(listener: Arc<dyn ChangeListener>) -> Result<Arc<Watch>, IfacesError> {
+        let mut watcher = ChangeWatcher::new()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let thread = thread::Builder::new()
+            .name("ifaces-watch".to_owned())
+            .spawn(move || {
+                while !stopped.load(Ordering::Relaxed) {
+                    thread::sleep(DRAIN_INTERVAL);
+                    match watcher.drain_events() {
+                        Ok(hints) => {
+                            for hint in hints {
+                                listener.on_change(hint);
+                            }
+                        }
+                        Err(err) => return listener.on_error(err.into()),
+                    }
+                }
+            })?;
+        Ok(Arc::new(Watch {
+            stop,
+            thread: Mutex::new(Some(thread)),
+        }))
+    }
+
+    /// Stop calling the listener. Waits for a call in progress, unless
+    /// made from the listener.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let thread = match self.thread.lock() {
+            Ok(mut thread) => thread.take(),
+            Err(_) => None,
+        };
+        if let Some(thread) = thread {
+            if thread.thread().id() != thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_errors_keep_their_kind_and_code() {
+        let err = IfacesError::from(io::Error::from_raw_os_error(24));
+        assert!(matches!(err, IfacesError::Io { code: Some(24), .. }));
+        let err = IfacesError::from(io::Error::new(io::ErrorKind::InvalidInput, "bad name"));
+        assert!(matches!(err, IfacesError::InvalidInput { ref reason } if reason == "bad name"));
+        let err = IfacesError::from(io::Error::from(io::ErrorKind::Unsupported));
+        assert!(matches!(err, IfacesError::Unsupported { .. }));
+    }
+
+    #[test]
+    fn scoped_addresses_carry_the_index() {
+        assert_eq!(address("[fe80::1%4]:0".parse().unwrap()), "fe80::1%4");
+        assert_eq!(address("[fd00::2]:0".parse().unwrap()), "fd00::2");
+        assert_eq!(address("192.0.2.2:0".parse().unwrap()), "192.0.2.2");
+    }
+}
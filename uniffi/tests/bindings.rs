@@ -0,0 +1,11 @@
+//! The generated bindings, exercised from each language.
+//!
+//! Needs `kotlinc` with the JNA jar on the `CLASSPATH`, `swiftc`, and
+//! `python3`. Leave a language out with e.g.
+//! `UNIFFI_TESTS_DISABLE_EXTENSIONS=kts,swift`.
+
+uniffi::build_foreign_language_testcases!(
+    "tests/bindings/test_ifaces.kts",
+    "tests/bindings/test_ifaces.swift",
+    "tests/bindings/test_ifaces.py",
+);
@@ -0,0 +1,55 @@
+import ifaces.*
+
+val entries = ifaces()
+assert(entries.isNotEmpty())
+
+for (entry in entries) {
+    assert(entry.name.isNotEmpty())
+    assert(entry.kind in setOf("ipv4", "ipv6", "link", "packet", "can", "bluetooth", "unknown"))
+    val addr = entry.addr
+    if (entry.kind == "ipv6" && addr != null && addr.startsWith("fe80:")) {
+        // Link-local addresses carry their scope.
+        assert(addr.endsWith("%${entry.index}")) { addr }
+    }
+}
+
+val lo = entries.first { "loopback" in it.flags && it.kind == "ipv4" }
+assert(lo.addr == "127.0.0.1")
+assert(lo.netmask == "255.0.0.0")
+assert(lo.prefixLen == 8.toUByte())
+
+// Defaults are what ifaces() does.
+assert(ifacesWithOptions(IfacesOptions()).size == entries.size)
+val onlyLo = ifacesWithOptions(IfacesOptions(include = listOf(lo.name)))
+assert(onlyLo.isNotEmpty() && onlyLo.all { it.name == lo.name })
+assert(ifacesWithOptions(IfacesOptions(exclude = listOf(lo.name))).none { it.name == lo.name })
+assert(ifacesWithOptions(IfacesOptions(include = listOf("nosuchif*"))).isEmpty())
+
+// The default route goes over one of the entries, if there is one.
+val online = try {
+    defaultInterface()
+} catch (e: IfacesException.Unsupported) {
+    null
+}
+assert(online == null || entries.any { it.index == online.index })
+
+// The errors are exceptions of their own.
+val error: IfacesException = IfacesException.Io(code = 24, reason = "Too many open files")
+assert(error is IfacesException.Io && error.code == 24)
+
+class Listener : ChangeListener {
+    val errors = mutableListOf<IfacesException>()
+    override fun onChange(hint: ChangeHint) {}
+    override fun onError(error: IfacesException) { errors.add(error) }
+}
+
+val listener = Listener()
+val watch = try {
+    Watch(listener)
+} catch (e: IfacesException.Unsupported) {
+    null
+}
+watch?.stop()
+// Stopping twice is harmless.
+watch?.stop()
+assert(listener.errors.isEmpty())
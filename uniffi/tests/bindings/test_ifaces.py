@@ -0,0 +1,88 @@
+import errno
+import os
+import resource
+import threading
+
+from ifaces_uniffi import (
+    ChangeHint,
+    ChangeListener,
+    IfacesError,
+    IfacesOptions,
+    Watch,
+    default_interface,
+    ifaces,
+    ifaces_with_options,
+)
+
+entries = ifaces()
+assert entries
+
+for entry in entries:
+    assert entry.name
+    assert entry.kind in {"ipv4", "ipv6", "link", "packet", "can", "bluetooth", "unknown"}
+    if entry.kind == "ipv6" and entry.addr.startswith("fe80:"):
+        # Link-local addresses carry their scope.
+        assert entry.addr.endswith("%{}".format(entry.index)), entry.addr
+
+lo = next(e for e in entries if "loopback" in e.flags and e.kind == "ipv4")
+assert lo.addr == "127.0.0.1"
+assert lo.netmask == "255.0.0.0"
+assert lo.prefix_len == 8
+
+# Defaults are what ifaces() does.
+assert len(ifaces_with_options(IfacesOptions())) == len(entries)
+only_lo = ifaces_with_options(IfacesOptions(include=[lo.name]))
+assert only_lo and all(e.name == lo.name for e in only_lo)
+assert all(e.name != lo.name for e in ifaces_with_options(IfacesOptions(exclude=[lo.name])))
+assert not ifaces_with_options(IfacesOptions(include=["nosuchif*"]))
+
+# The default route goes over one of the entries, if there is one.
+try:
+    online = default_interface()
+except IfacesError.Unsupported:
+    online = None
+assert online is None or online.index in {e.index for e in entries}
+
+# OS errors come out with their errno.
+soft, hard = resource.getrlimit(resource.RLIMIT_NOFILE)
+resource.setrlimit(resource.RLIMIT_NOFILE, (64, hard))
+fds = []
+try:
+    while True:
+        fds.append(os.dup(0))
+except OSError:
+    pass
+try:
+    ifaces()
+    raise AssertionError("ifaces() worked without file descriptors")
+except IfacesError.Io as err:
+    assert err.code == errno.EMFILE, err
+finally:
+    for fd in fds:
+        os.close(fd)
+    resource.setrlimit(resource.RLIMIT_NOFILE, (soft, hard))
+
+
+class Listener(ChangeListener):
+    def __init__(self):
+        self.errors = []
+        self.changes = threading.Event()
+
+    def on_change(self, hint):
+        assert hint in (ChangeHint.ADDRESSES, ChangeHint.LINKS, ChangeHint.UNKNOWN)
+        self.changes.set()
+
+    def on_error(self, error):
+        self.errors.append(error)
+
+
+listener = Listener()
+try:
+    watch = Watch(listener)
+except IfacesError.Unsupported:
+    watch = None
+if watch is not None:
+    watch.stop()
+    # Stopping twice is harmless.
+    watch.stop()
+    assert not listener.errors
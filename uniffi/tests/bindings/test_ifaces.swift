@@ -0,0 +1,65 @@
+import Ifaces
+
+let entries = try! ifaces()
+assert(!entries.isEmpty)
+
+for entry in entries {
+    assert(!entry.name.isEmpty)
+    assert(["ipv4", "ipv6", "link", "packet", "can", "bluetooth", "unknown"].contains(entry.kind))
+    if entry.kind == "ipv6", let addr = entry.addr, addr.hasPrefix("fe80:") {
+        // Link-local addresses carry their scope.
+        assert(addr.hasSuffix("%\(entry.index!)"), addr)
+    }
+}
+
+let lo = entries.first { $0.flags.contains("loopback") && $0.kind == "ipv4" }!
+assert(lo.addr == "127.0.0.1")
+assert(lo.netmask == "255.0.0.0")
+assert(lo.prefixLen == 8)
+
+// Defaults are what ifaces() does.
+assert(try! ifacesWithOptions(options: IfacesOptions()).count == entries.count)
+let onlyLo = try! ifacesWithOptions(options: IfacesOptions(include: [lo.name]))
+assert(!onlyLo.isEmpty && onlyLo.allSatisfy { $0.name == lo.name })
+assert(try! ifacesWithOptions(options: IfacesOptions(exclude: [lo.name])).allSatisfy { $0.name != lo.name })
+assert(try! ifacesWithOptions(options: IfacesOptions(include: ["nosuchif*"])).isEmpty)
+
+// The default route goes over one of the entries, if there is one.
+var online: Interface? = nil
+do {
+    online = try defaultInterface()
+} catch IfacesError.Unsupported(_) {
+} catch {
+    fatalError("\(error)")
+}
+if let online = online {
+    assert(entries.contains { $0.index == online.index })
+}
+
+// The errors are Swift errors.
+let error = IfacesError.Io(code: 24, reason: "Too many open files")
+let _: Error = error
+if case .Io(let code, _) = error {
+    assert(code == 24)
+} else {
+    assertionFailure("not an Io error")
+}
+
+final class Listener: ChangeListener, @unchecked Sendable {
+    var errors: [IfacesError] = []
+    func onChange(hint: ChangeHint) {}
+    func onError(error: IfacesError) { errors.append(error) }
+}
+
+let listener = Listener()
+var watch: Watch? = nil
+do {
+    watch = try Watch(listener: listener)
+} catch IfacesError.Unsupported(_) {
+} catch {
+    fatalError("\(error)")
+}
+watch?.stop()
+// Stopping twice is harmless.
+watch?.stop()
+assert(listener.errors.isEmpty)